@@ -1,13 +1,72 @@
-use clap::{Parser, Subcommand};
+use std::path::PathBuf;
+
+use clap::{Args, Parser, Subcommand};
+use jiff::{SignedDuration, Timestamp};
+
+use crate::commands::digest::DigestFormat;
+use crate::commands::prep::PrepVariant;
+use crate::commands::stats::GroupBy;
+use crate::commands::task::{OnFailure, OnSuccess};
+use crate::commands::toolspec::ToolFormat;
+use crate::helpers::{parse_duration, parse_elapsed, parse_minutes, parse_seconds, parse_when};
+use crate::models::{FailureCategory, Priority, ReasonCode, Reference};
+use crate::output::TextFormat;
+use crate::query::{self, Filter};
+use crate::sandbox::Sandbox;
 
 #[derive(Parser)]
 #[command(name = "radial")]
 #[command(about = "Task orchestration for LLM agents", long_about = None)]
+#[command(arg_required_else_help = true)]
+#[allow(clippy::struct_excessive_bools)]
 pub struct Cli {
+    // Required unless `--ephemeral` reads a session from stdin.
     #[command(subcommand)]
-    pub command: Commands,
+    pub command: Option<Commands>,
+
+    /// Use a store that only lives in memory. Without a command, run each
+    /// line of stdin as a command against the same store.
+    #[arg(long)]
+    pub ephemeral: bool,
+
+    /// Plan file (TOML) to create in the ephemeral store before running
+    #[arg(long, value_name = "PLAN", requires = "ephemeral")]
+    pub seed: Option<PathBuf>,
+
+    /// Show full text instead of truncating to the terminal width
+    #[arg(long, global = true, visible_alias = "wide")]
+    pub full: bool,
+
+    /// Show times in UTC instead of the local time zone
+    #[arg(long, global = true, conflicts_with = "local")]
+    pub utc: bool,
+
+    /// Show times in the local time zone (the default)
+    #[arg(long, global = true)]
+    pub local: bool,
+
+    /// Never draw progress bars for long operations
+    #[arg(long, global = true)]
+    pub no_progress: bool,
+
+    /// Only these fields in JSON output, comma-separated; nested fields as
+    /// paths such as `metrics.total_tokens`
+    #[arg(long, global = true, value_delimiter = ',', value_name = "FIELDS")]
+    pub fields: Vec<String>,
+
+    /// Print each result through a format string instead, such as
+    /// '{{id}}\t{{state}}\t{{description}}'
+    #[arg(long, global = true)]
+    pub template: Option<String>,
 }
 
+/// Top-level shortcuts and the commands they stand for.
+pub const SHORTCUTS: &[(&str, &str)] = &[
+    ("grab", "task claim-next"),
+    ("done", "task complete"),
+    ("drop", "task fail"),
+];
+
 #[derive(Subcommand)]
 pub enum Commands {
     /// Initialize radial in the current project
@@ -15,6 +74,18 @@ pub enum Commands {
         /// Initialize without committing to the repo (adds .radial to .gitignore or .git/info/exclude)
         #[arg(long)]
         stealth: bool,
+
+        /// Create a standalone store at this path instead of ./.radial, for
+        /// several repositories to share via `link`
+        #[arg(long, value_name = "PATH", conflicts_with = "stealth")]
+        bare: Option<PathBuf>,
+    },
+
+    /// Point this repository at a shared radial store
+    Link {
+        /// Path to the store (created with `init --bare`); ~ and $VARS are kept
+        /// unexpanded in the redirect file
+        path: String,
     },
 
     /// Manage goals
@@ -33,29 +104,193 @@ pub enum Commands {
     #[command(subcommand)]
     Task(TaskCommands),
 
+    /// Short for `task claim-next`
+    Grab(ClaimArgs),
+
+    /// Short for `task complete`
+    Done(CompleteArgs),
+
+    /// Fail a task, recording why as a comment; short for `task fail`
+    Drop {
+        /// The task ID to fail
+        task_id: String,
+
+        /// Why the task is being dropped
+        reason: Option<String>,
+
+        /// Why it was failed, for reports: `user_request`, `agent_auto`,
+        /// `lease_expired`, `verify_failed` or `budget_exceeded` (default:
+        /// `agent_auto` with `$RADIAL_AGENT` set, otherwise `user_request`)
+        #[arg(long)]
+        reason_code: Option<ReasonCode>,
+
+        #[command(flatten)]
+        postmortem: PostmortemArgs,
+
+        #[command(flatten)]
+        usage: UsageArgs,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Build a goal and its tasks in one go
+    #[command(subcommand)]
+    Plan(PlanCommands),
+
     /// Edit a goal or task
     #[command(subcommand)]
     Edit(EditCommands),
 
+    /// Show what a task receives from the tasks it was blocked by
+    #[command(subcommand)]
+    Context(ContextCommands),
+
     /// Show full details of a goal or task
     Show {
         /// The goal or task ID to show
         id: String,
 
+        /// How to show descriptions, results and comments: md or plain
+        #[arg(long, default_value = "md")]
+        render: TextFormat,
+
+        /// Link artifacts that are files in the repository to their page on
+        /// GitHub or GitLab, at the commit the task was completed on
+        #[arg(long)]
+        links: bool,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Open what a goal or task links to: references and URL artifacts in
+    /// the browser, file artifacts and a goal's markdown export in $EDITOR
+    Open {
+        /// The goal or task ID
+        id: String,
+
+        /// Which target to open when there are several: its number in
+        /// `--list`, or text from it
+        which: Option<String>,
+
+        /// List what can be opened instead of opening anything
+        #[arg(long)]
+        list: bool,
+
+        /// Output the list as JSON
+        #[arg(long, requires = "list")]
+        json: bool,
+    },
+
+    /// Check a plan file for errors and preview its tasks in dependency order
+    Validate {
+        /// Path to the plan file (TOML)
+        path: PathBuf,
+
+        /// Fail on warnings, such as tasks without a complete contract
+        #[arg(long)]
+        strict: bool,
+
         /// Output as JSON
         #[arg(long)]
         json: bool,
     },
 
     /// Remove completed goals and their tasks
-    Clean {
-        /// Remove all completed goals without prompting
+    Clean(CleanArgs),
+
+    /// Remove leftovers from interrupted writes and empty directories
+    Gc {
+        /// Report what would be removed without removing it
         #[arg(long)]
-        all: bool,
+        dry_run: bool,
 
-        /// Remove all goals regardless of status
+        /// Output as JSON
         #[arg(long)]
-        force: bool,
+        json: bool,
+    },
+
+    /// Check that blockers, parent goals and milestones all resolve and that
+    /// task files sit under their goal
+    Doctor {
+        /// Drop dangling blockers and milestones, and clear parents that
+        /// never existed
+        #[arg(long)]
+        fix: bool,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Write a goal, its sub-goals, their tasks and attachments to one
+    /// JSON bundle for someone else's store
+    Export {
+        /// The goal to export
+        #[arg(long)]
+        goal: String,
+
+        /// File to write (default: print the bundle)
+        #[arg(long, short)]
+        output: Option<PathBuf>,
+
+        /// Output as JSON when writing to a file
+        #[arg(long, requires = "output")]
+        json: bool,
+    },
+
+    /// Add the goals and tasks in a bundle from `export` under new IDs
+    Import {
+        /// The bundle file, or - for stdin
+        path: PathBuf,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Merge goals and tasks with another radial store
+    Sync {
+        /// Path to the other `.radial/` directory, or the project holding it
+        path: String,
+
+        /// Report what would change without writing either store
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Wait for tasks to become ready or goals to complete, and notify
+    Watch {
+        /// Only watch this goal
+        #[arg(long)]
+        goal: Option<String>,
+
+        /// Time between checks, such as 500ms or 10s; a bare number is seconds
+        #[arg(long, default_value = "2", value_parser = parse_seconds)]
+        interval: SignedDuration,
+
+        /// Print each event as a line of JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Serve a read-only dashboard of goals, task graphs and recent commands
+    #[cfg(feature = "web")]
+    Serve {
+        /// Serve the HTML dashboard
+        #[arg(long, required = true)]
+        web: bool,
+
+        /// Address to listen on
+        #[arg(long, default_value = "127.0.0.1:7878")]
+        addr: std::net::SocketAddr,
     },
 
     /// Show status of goals and tasks
@@ -68,6 +303,22 @@ pub enum Commands {
         #[arg(long)]
         task: Option<String>,
 
+        /// Only show tasks assigned to this agent
+        #[arg(long)]
+        agent: Option<String>,
+
+        /// Only show tasks in progress longer than the stale threshold
+        #[arg(long, conflicts_with = "task")]
+        stale: bool,
+
+        /// Show goals nested under their parents with rolled-up progress
+        #[arg(long, conflicts_with_all = ["task", "stale", "agent"])]
+        tree: bool,
+
+        /// Include goals scoped to other git branches
+        #[arg(long)]
+        all_branches: bool,
+
         /// Output as JSON
         #[arg(long)]
         json: bool,
@@ -78,165 +329,1190 @@ pub enum Commands {
         /// The goal ID to check for ready tasks
         goal_id: String,
 
+        /// Only show tasks not assigned to any agent
+        #[arg(long)]
+        unassigned: bool,
+
+        /// Rank by the highest priority of the work each task unblocks
+        #[arg(long)]
+        effective_priority: bool,
+
+        /// Also list tasks that are not ready and why
+        #[arg(long)]
+        explain: bool,
+
+        /// Allow goals scoped to another git branch
+        #[arg(long)]
+        all_branches: bool,
+
         /// Output as JSON
         #[arg(long)]
         json: bool,
     },
 
-    /// Output a preparation guide for LLM agents
-    Prep,
-}
+    /// Print a compact project digest
+    Summary {
+        /// Plain text sized for an agent's system prompt
+        #[arg(long, required = true)]
+        for_llm: bool,
 
-#[derive(Subcommand)]
-pub enum GoalCommands {
-    /// Create a new goal
-    Create {
-        /// The goal description
-        description: String,
+        /// Approximate token budget for the digest
+        #[arg(long, default_value_t = 1000)]
+        max_tokens: usize,
+    },
 
-        /// Output as JSON
+    /// Report what agents got done over a period, for a nightly job;
+    /// written or piped where `[digest]` in config.toml says
+    Digest {
+        /// How far back to look, such as 24h; defaults to `[digest] since`
+        #[arg(long, value_parser = parse_duration)]
+        since: Option<SignedDuration>,
+
+        /// md or html; defaults to `[digest] format`
+        #[arg(long)]
+        format: Option<DigestFormat>,
+
+        /// Print the digest instead of writing or piping it
+        #[arg(long)]
+        print: bool,
+
+        /// Output the digest's data as JSON instead
         #[arg(long)]
         json: bool,
     },
 
-    /// List all goals
-    List {
+    /// List recent task failures grouped by postmortem category
+    Postmortems {
+        /// Only failures in this goal
+        #[arg(long)]
+        goal: Option<String>,
+
+        /// How many of the most recent failures to show per category
+        #[arg(long, default_value = "5")]
+        limit: usize,
+
         /// Output as JSON
         #[arg(long)]
         json: bool,
     },
-}
 
-#[derive(Subcommand)]
-pub enum TaskCommands {
-    /// Create a new task
-    Create {
-        /// The goal ID this task belongs to
-        goal_id: String,
+    /// Break down recorded usage
+    #[command(subcommand)]
+    Stats(StatsCommands),
 
-        /// Task description
-        description: String,
+    /// Overview of every project in the global registry
+    #[command(subcommand)]
+    All(AllCommands),
 
-        /// What this task receives (contract)
+    /// Summarize goals and task metrics per project label
+    Projects {
+        /// Output as JSON
         #[arg(long)]
-        receives: Option<String>,
+        json: bool,
+    },
 
-        /// What this task produces (contract)
+    /// List what needs a human: failed, unverified and stale tasks, failing
+    /// gates and spent budgets
+    Inbox {
+        /// Group by kind with counts, showing the first few of each
         #[arg(long)]
-        produces: Option<String>,
+        digest: bool,
 
-        /// How to verify success (contract)
+        /// Output as JSON
         #[arg(long)]
-        verify: Option<String>,
+        json: bool,
+    },
 
-        /// IDs of tasks this task is blocked by
-        #[arg(long, value_delimiter = ',')]
-        blocked_by: Option<Vec<String>>,
+    /// Plan goals against named target dates
+    #[command(subcommand)]
+    Milestone(MilestoneCommands),
 
+    /// Show today's token usage per agent against configured budgets
+    Quota {
         /// Output as JSON
         #[arg(long)]
         json: bool,
     },
 
-    /// List tasks for a goal
-    List {
-        /// The goal ID to list tasks for
+    /// Propose blocked-by dependencies between a goal's tasks
+    SuggestDeps {
+        /// The goal ID to analyze
         goal_id: String,
 
+        /// Add the suggested dependencies
+        #[arg(long)]
+        apply: bool,
+
         /// Output as JSON
         #[arg(long)]
         json: bool,
-
-        /// Show comments on tasks
-        #[arg(short, long)]
-        verbose: bool,
     },
 
-    /// Mark a task as started
-    Start {
-        /// The task ID to start
-        task_id: String,
-    },
+    /// Propose radial operations from an agent transcript (markdown or JSONL)
+    IngestTranscript {
+        /// The transcript file, or `-` for stdin
+        path: PathBuf,
 
-    /// Mark a task as completed
-    Complete {
-        /// The task ID to complete
-        task_id: String,
+        /// Run the proposed operations
+        #[arg(long, conflicts_with = "json")]
+        apply: bool,
 
-        /// Summary of what was accomplished
+        /// Output as JSON
         #[arg(long)]
-        result: String,
+        json: bool,
+    },
 
-        /// Artifact paths created (comma-separated)
-        #[arg(long, value_delimiter = ',')]
-        artifacts: Option<Vec<String>>,
+    /// List the commands run against this store, or run one again
+    History {
+        /// Show only the last N commands
+        #[arg(
+            long,
+            value_name = "N",
+            default_value_t = 20,
+            conflicts_with = "replay"
+        )]
+        limit: usize,
 
-        /// Total tokens used for this task
-        #[arg(long)]
-        tokens: Option<i64>,
+        /// Run command N from the list again
+        #[arg(long, value_name = "N")]
+        replay: Option<usize>,
 
-        /// Elapsed time in milliseconds
+        /// Output as JSON
         #[arg(long)]
-        elapsed: Option<i64>,
+        json: bool,
     },
 
-    /// Mark a task as failed
-    Fail {
-        /// The task ID to fail
-        task_id: String,
+    /// Output a preparation guide for LLM agents
+    Prep {
+        /// Which guide: `full`, or `short` for small context windows
+        #[arg(long = "for", default_value = "full")]
+        variant: PrepVariant,
     },
 
-    /// Retry a failed task
-    Retry {
-        /// The task ID to retry
-        task_id: String,
+    /// Describe every command, flag, and state transition, for building tool
+    /// schemas
+    Capabilities {
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
     },
 
-    /// Add a comment to a task
-    Comment {
-        /// The task ID to comment on
-        task_id: String,
-
-        /// The comment text
-        text: String,
+    /// Print tool definitions for the core commands, to register radial with
+    /// an LLM API
+    Toolspec {
+        /// Definition format: openai or anthropic
+        #[arg(long, default_value = "openai")]
+        format: ToolFormat,
     },
 }
 
 #[derive(Subcommand)]
-pub enum EditCommands {
-    /// Edit a goal's description
-    Goal {
-        /// The goal ID to edit
-        goal_id: String,
+pub enum GoalCommands {
+    /// Create a new goal
+    Create {
+        /// The goal description
+        description: String,
 
-        /// New description
+        /// Short title shown in list views instead of the description
         #[arg(long)]
-        description: String,
+        title: Option<String>,
+
+        /// Nest the goal under an existing parent goal
+        #[arg(long)]
+        parent: Option<String>,
+
+        /// Scope the goal to a git branch; without a name, the current branch
+        #[arg(long)]
+        branch: Option<Option<String>>,
+
+        /// Project label grouping this goal with related ones
+        #[arg(long)]
+        project: Option<String>,
+
+        /// Milestone the goal is planned for (see `milestone add`)
+        #[arg(long)]
+        milestone: Option<String>,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
     },
 
-    /// Edit a task's description or contract
-    Task {
-        /// The task ID to edit
-        task_id: String,
+    /// List all goals
+    List {
+        /// Only goals labelled with this project
+        #[arg(long)]
+        project: Option<String>,
 
-        /// New description
+        /// Output as JSON
         #[arg(long)]
-        description: Option<String>,
+        json: bool,
+    },
+
+    /// Put a completed or failed goal back in progress
+    Reopen {
+        /// The goal ID to reopen
+        goal_id: String,
 
-        /// New receives (contract)
+        /// Also put its failed tasks back to pending, keeping their attempts
         #[arg(long)]
-        receives: Option<String>,
+        reset_failed: bool,
 
-        /// New produces (contract)
+        /// Output as JSON
         #[arg(long)]
-        produces: Option<String>,
+        json: bool,
+    },
 
-        /// New verify (contract)
+    /// Fold one goal into another: move its tasks and sub-goals, append its
+    /// description and criteria, then archive it
+    Merge {
+        /// The goal to keep
+        into: String,
+
+        /// The goal to merge into it and archive
+        from: String,
+
+        /// Output as JSON
         #[arg(long)]
-        verify: Option<String>,
+        json: bool,
+    },
 
-        /// Add a blocked-by dependency
-        #[arg(long, value_delimiter = ',')]
-        blocked_by: Option<Vec<String>>,
+    /// Move a goal and its tasks out of the store into .radial/archive/ (gzipped JSON)
+    Archive {
+        /// The goal ID to archive
+        goal_id: String,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Bring back a goal written by `goal archive`
+    Restore {
+        /// Path to the archive file
+        path: PathBuf,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
     },
+
+    /// Give one agent sole write access to a goal's tasks for a while
+    Claim {
+        /// The goal ID to claim
+        goal_id: String,
+
+        /// Agent taking the claim; defaults to `$RADIAL_AGENT`
+        #[arg(long)]
+        agent: Option<String>,
+
+        /// How long the claim lasts, e.g. 30m or 2h
+        #[arg(long, default_value = "1h", value_parser = parse_duration)]
+        ttl: SignedDuration,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// End a claim so other agents can change the goal's tasks again
+    Release {
+        /// The goal ID to release
+        goal_id: String,
+
+        /// Agent holding the claim; defaults to `$RADIAL_AGENT`
+        #[arg(long)]
+        agent: Option<String>,
+
+        /// Release another agent's claim
+        #[arg(long)]
+        force: bool,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Keep acceptance criteria: what must hold for the goal to be done
+    /// besides its tasks
+    #[command(subcommand)]
+    Criterion(CriterionCommands),
+
+    /// Check a goal's acceptance criteria, completing it once they are all
+    /// met and its tasks are done
+    Verify {
+        /// The goal ID to verify
+        goal_id: String,
+
+        /// Directory to run criterion commands in (default: current directory)
+        #[arg(long)]
+        dir: Option<PathBuf>,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum CriterionCommands {
+    /// Add an acceptance criterion to a goal
+    Add {
+        /// The goal ID
+        goal_id: String,
+
+        /// What must hold
+        text: String,
+
+        /// Shell command that checks it; without one, the criterion is
+        /// ticked by hand
+        #[arg(long)]
+        command: Option<String>,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Mark a hand-checked criterion met
+    Tick {
+        /// The goal ID
+        goal_id: String,
+
+        /// The criterion number, as shown by `goal criterion list`
+        number: usize,
+
+        /// Mark the criterion not met again
+        #[arg(long)]
+        undo: bool,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Show a goal's acceptance criteria
+    List {
+        /// The goal ID
+        goal_id: String,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum MilestoneCommands {
+    /// Add a milestone: a named target date goals can be attached to
+    Add {
+        /// Name of the milestone, e.g. v1.0
+        name: String,
+
+        /// Target date: a timestamp, or a duration from now such as 336h
+        #[arg(long, value_parser = parse_when)]
+        due: Timestamp,
+
+        /// What the milestone is for
+        #[arg(long)]
+        description: Option<String>,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Plan a goal for a milestone
+    Attach {
+        /// The milestone name
+        name: String,
+
+        /// The goal ID
+        goal_id: String,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Take a goal off its milestone
+    Detach {
+        /// The goal ID
+        goal_id: String,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Show progress towards milestones and the goals at risk of missing
+    /// them
+    Status {
+        /// Only this milestone; by default all of them, soonest first
+        name: Option<String>,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum PlanCommands {
+    /// Create a goal and its tasks from a guided prompt
+    New {
+        /// Prompt for the goal, each task's contract, and blockers by number
+        #[arg(long, required = true)]
+        interactive: bool,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Create a goal and its tasks from a plan file, after validating it
+    Apply {
+        /// Path to the plan file (TOML)
+        path: PathBuf,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum TaskCommands {
+    /// Create a new task
+    Create {
+        /// The goal ID this task belongs to
+        goal_id: String,
+
+        /// Task description
+        description: String,
+
+        /// Short title shown in list views instead of the description
+        #[arg(long)]
+        title: Option<String>,
+
+        /// What this task receives (contract)
+        #[arg(long)]
+        receives: Option<String>,
+
+        /// What this task produces (contract)
+        #[arg(long)]
+        produces: Option<String>,
+
+        /// How to verify success (contract)
+        #[arg(long)]
+        verify: Option<String>,
+
+        /// IDs of tasks this task is blocked by
+        #[arg(long, value_delimiter = ',')]
+        blocked_by: Option<Vec<String>>,
+
+        /// Artifact path a completed task in the goal must produce first
+        /// (repeatable)
+        #[arg(long = "blocked-by-artifact", value_name = "PATH")]
+        blocked_by_artifacts: Vec<String>,
+
+        /// Agent this task is assigned to
+        #[arg(long)]
+        assignee: Option<String>,
+
+        /// Task priority: low, normal, high, or urgent
+        #[arg(long, default_value = "normal")]
+        priority: Priority,
+
+        /// Keep the task out of `ready` until this time: a timestamp, or a
+        /// duration from now such as 2h
+        #[arg(long, value_parser = parse_when)]
+        not_before: Option<Timestamp>,
+
+        /// How long the task should take, such as 45m or 2h; `claim-next
+        /// --max-minutes` only hands out tasks that fit
+        #[arg(long, value_parser = parse_duration)]
+        estimate: Option<SignedDuration>,
+
+        /// Shell command that must succeed before the task is ready (repeatable)
+        #[arg(long = "gate")]
+        gates: Vec<String>,
+
+        /// Issue, pull request or ticket the task tracks, as kind:target such
+        /// as github:owner/repo#123, jira:PROJ-42 or url:https://… (repeatable)
+        #[arg(long = "ref", value_name = "KIND:TARGET")]
+        refs: Vec<Reference>,
+
+        /// Create the task even if an open task in the goal has nearly the
+        /// same description
+        #[arg(long)]
+        allow_duplicate: bool,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// List tasks for a goal, or across goals with --where
+    List {
+        /// The goal ID to list tasks for
+        #[arg(required_unless_present = "filter")]
+        goal_id: Option<String>,
+
+        /// Only tasks matching an expression such as
+        /// `state=in_progress AND tokens>10000`; fields are id, goal, state,
+        /// priority, assignee, title, description, ref, tokens, elapsed,
+        /// retries, attempts, created, updated and completed
+        #[arg(long = "where", value_name = "EXPR", value_parser = query::parse)]
+        filter: Option<Filter>,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+
+        /// Show comments on tasks
+        #[arg(short, long)]
+        verbose: bool,
+    },
+
+    /// Set the order of tasks within a goal
+    ///
+    /// Listed tasks are moved, in the given order, to the front of the goal
+    /// or next to the task named by --before/--after.
+    Reorder {
+        /// The goal ID whose tasks to reorder
+        goal_id: String,
+
+        /// Task IDs in the desired order
+        #[arg(required = true)]
+        task_ids: Vec<String>,
+
+        /// Place the tasks immediately before this task
+        #[arg(long, conflicts_with = "after")]
+        before: Option<String>,
+
+        /// Place the tasks immediately after this task
+        #[arg(long)]
+        after: Option<String>,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Show the task that would be picked up next, without starting it
+    Peek {
+        /// The goal ID to look in
+        goal_id: String,
+
+        /// Skip tasks assigned to other agents
+        #[arg(long)]
+        agent: Option<String>,
+
+        /// Only consider tasks estimated to take at most this long, such as
+        /// 90m or 1h 30m; a bare number is minutes
+        #[arg(long, value_name = "MINUTES", value_parser = parse_minutes)]
+        max_minutes: Option<SignedDuration>,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Start the next ready task in a goal, as `peek` would pick it
+    ClaimNext(ClaimArgs),
+
+    /// Mark a task as started
+    Start {
+        /// The task ID to start
+        task_id: String,
+
+        /// Agent claiming the task (recorded as its assignee)
+        #[arg(long)]
+        agent: Option<String>,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Mark a task as completed
+    Complete(CompleteArgs),
+
+    /// Move a task into a custom state from `[states]` in config, or back
+    /// to the core state it refines
+    Move {
+        /// The task ID to move
+        task_id: String,
+
+        /// The state to move to
+        state: String,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Mark a task as failed
+    Fail {
+        /// The task ID to fail
+        task_id: String,
+
+        /// Why the attempt failed, kept in the task's attempt history
+        #[arg(long)]
+        reason: Option<String>,
+
+        /// Why it was failed, for reports: `user_request`, `agent_auto`,
+        /// `lease_expired`, `verify_failed` or `budget_exceeded` (default:
+        /// `agent_auto` with `$RADIAL_AGENT` set, otherwise `user_request`)
+        #[arg(long)]
+        reason_code: Option<ReasonCode>,
+
+        #[command(flatten)]
+        postmortem: PostmortemArgs,
+
+        #[command(flatten)]
+        usage: UsageArgs,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Run a task's verify command, keeping its output as an attachment
+    Verify {
+        /// The task ID to verify
+        task_id: String,
+
+        /// Run inside `none` or `docker:<image>` instead of `[sandbox] wrapper`
+        #[arg(long)]
+        sandbox: Option<Sandbox>,
+
+        /// Directory to run in (default: current directory)
+        #[arg(long)]
+        dir: Option<PathBuf>,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Run a command for a task, optionally completing or failing it by the
+    /// exit status
+    Exec {
+        /// The task ID to run for
+        task_id: String,
+
+        /// `complete` to start (if pending) and complete the task when the
+        /// command succeeds, recording the log as an artifact
+        #[arg(long, default_value = "none")]
+        on_success: OnSuccess,
+
+        /// `fail` to start (if pending) and fail the task when the command
+        /// fails, recording the log in a postmortem
+        #[arg(long, default_value = "none")]
+        on_failure: OnFailure,
+
+        /// Run inside `none` or `docker:<image>` instead of `[sandbox] wrapper`
+        #[arg(long)]
+        sandbox: Option<Sandbox>,
+
+        /// Directory to run in (default: current directory)
+        #[arg(long)]
+        dir: Option<PathBuf>,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+
+        /// Command to run, after `--` (default: the task's verify command)
+        #[arg(last = true)]
+        command: Vec<String>,
+    },
+
+    /// Retry a failed task, optionally revising its description or contract
+    Retry {
+        /// The task ID to retry
+        task_id: String,
+
+        /// Open $EDITOR to revise the description and contract first
+        #[arg(long)]
+        edit: bool,
+
+        #[command(flatten)]
+        revision: RevisionArgs,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Add a comment to a task
+    Comment {
+        /// The task ID to comment on
+        task_id: String,
+
+        /// The comment text
+        text: String,
+
+        /// Reply to this comment on the same task, starting or continuing
+        /// its thread
+        #[arg(long, value_name = "COMMENT_ID")]
+        reply_to: Option<String>,
+    },
+
+    /// Report how far along an in-progress task is
+    Progress {
+        /// The task ID
+        task_id: String,
+
+        /// Percent complete, 0 to 100
+        #[arg(value_parser = clap::value_parser!(u8).range(0..=100))]
+        percent: u8,
+
+        /// What is done and what remains
+        #[arg(long)]
+        note: Option<String>,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Track small steps inside a task
+    #[command(subcommand)]
+    Check(CheckCommands),
+
+    /// Keep key-value notes with a task, such as a branch name or PR URL
+    #[command(subcommand)]
+    Ctx(CtxCommands),
+}
+
+/// Which goal to take the next task from, and for whom.
+#[derive(Args)]
+pub struct ClaimArgs {
+    /// The goal ID to take a task from
+    pub goal_id: String,
+
+    /// Skip tasks assigned to other agents, and record this agent as the
+    /// assignee
+    #[arg(long)]
+    pub agent: Option<String>,
+
+    /// Only take a task estimated to take at most this long, such as the
+    /// time left in a session (90m, 1h 30m; a bare number is minutes).
+    /// Tasks without an estimate are skipped
+    #[arg(long, value_name = "MINUTES", value_parser = parse_minutes)]
+    pub max_minutes: Option<SignedDuration>,
+
+    /// Output as JSON
+    #[arg(long)]
+    pub json: bool,
+}
+
+/// A task to complete and what came of it.
+#[derive(Args)]
+pub struct CompleteArgs {
+    /// The task ID to complete
+    pub task_id: String,
+
+    /// Summary of what was accomplished
+    #[arg(long, short)]
+    pub result: String,
+
+    /// Artifact paths created (comma-separated)
+    #[arg(long, value_delimiter = ',')]
+    pub artifacts: Option<Vec<String>>,
+
+    #[command(flatten)]
+    pub usage: UsageArgs,
+
+    /// Create a pending task in the same goal for work found along the
+    /// way (repeatable)
+    #[arg(long = "follow-up", value_name = "DESCRIPTION")]
+    pub follow_ups: Vec<String>,
+
+    /// Complete even though tasks it releases have other blockers that
+    /// were removed or finished ahead of their own blockers; only needed
+    /// in a terminal, elsewhere the warning is printed and the task completed
+    #[arg(long, short)]
+    pub yes: bool,
+
+    /// Output as JSON
+    #[arg(long)]
+    pub json: bool,
+}
+
+/// Options for `clean`.
+#[derive(Args)]
+#[allow(clippy::struct_excessive_bools)]
+pub struct CleanArgs {
+    /// Remove all completed goals without prompting
+    #[arg(long)]
+    pub all: bool,
+
+    /// Remove all goals regardless of status
+    #[arg(long)]
+    pub force: bool,
+
+    /// Show what would be removed, with task, comment and artifact
+    /// counts, without removing anything
+    #[arg(long)]
+    pub dry_run: bool,
+
+    /// Clean only this goal
+    #[arg(long, value_name = "ID")]
+    pub goal: Option<String>,
+
+    /// Drop dependencies other goals have on the removed tasks instead
+    /// of refusing
+    #[arg(long)]
+    pub unlink: bool,
+
+    /// Output as JSON
+    #[arg(long)]
+    pub json: bool,
+}
+
+/// What an attempt at a task cost, reported as it completes or fails.
+#[derive(Args, Clone)]
+pub struct UsageArgs {
+    /// Total tokens used by this attempt (default: prompt plus completion)
+    #[arg(long)]
+    pub tokens: Option<i64>,
+
+    /// Prompt (input) tokens used by this attempt
+    #[arg(long)]
+    pub prompt_tokens: Option<i64>,
+
+    /// Completion (output) tokens used by this attempt
+    #[arg(long)]
+    pub completion_tokens: Option<i64>,
+
+    /// Time the attempt took, such as 5m30s; a bare number is milliseconds
+    #[arg(long, value_parser = parse_elapsed)]
+    pub elapsed: Option<i64>,
+
+    /// Model that did the work, for pricing its tokens (see `[pricing]`);
+    /// defaults to `$RADIAL_MODEL`
+    #[arg(long)]
+    pub model: Option<String>,
+
+    /// Who served the model, such as openai; defaults to `$RADIAL_PROVIDER`
+    #[arg(long)]
+    pub provider: Option<String>,
+}
+
+/// An optional postmortem recorded when a task fails.
+#[derive(Args)]
+pub struct PostmortemArgs {
+    /// Why it failed: spec, dependency, environment, tooling, timeout, or other
+    #[arg(long)]
+    pub category: Option<FailureCategory>,
+
+    /// Path to logs from the failed attempt
+    #[arg(long)]
+    pub logs: Option<String>,
+
+    /// What should change before the next attempt
+    #[arg(long)]
+    pub fix: Option<String>,
+}
+
+/// Description and contract changes to apply when retrying a task.
+#[derive(Args)]
+pub struct RevisionArgs {
+    /// New description
+    #[arg(long)]
+    pub description: Option<String>,
+
+    /// What this task receives
+    #[arg(long)]
+    pub receives: Option<String>,
+
+    /// What this task produces
+    #[arg(long)]
+    pub produces: Option<String>,
+
+    /// How to verify the task is done
+    #[arg(long)]
+    pub verify: Option<String>,
+}
+
+#[derive(Subcommand)]
+pub enum CheckCommands {
+    /// Add an item to a task's checklist
+    Add {
+        /// The task ID
+        task_id: String,
+
+        /// What needs doing
+        text: String,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Mark a checklist item done
+    Tick {
+        /// The task ID
+        task_id: String,
+
+        /// The item number, as shown by `task check list`
+        item: usize,
+
+        /// Mark the item not done again
+        #[arg(long)]
+        undo: bool,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Show a task's checklist
+    List {
+        /// The task ID
+        task_id: String,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum CtxCommands {
+    /// Store a value under a key, replacing any earlier one
+    Set {
+        /// The task ID
+        task_id: String,
+
+        /// Name of the entry, such as `branch` or `pr`
+        key: String,
+
+        /// What to store
+        value: String,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Print one value, or every entry if no key is given
+    Get {
+        /// The task ID
+        task_id: String,
+
+        /// The entry to print
+        key: Option<String>,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Remove a key
+    Unset {
+        /// The task ID
+        task_id: String,
+
+        /// The entry to remove
+        key: String,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum ContextCommands {
+    /// Blockers' results and artifacts, matched against the task's receives contract
+    Task {
+        /// The task ID
+        task_id: String,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum StatsCommands {
+    /// Token spend per goal, agent, day, or model, biggest first, with its
+    /// cost once `[pricing]` is configured
+    Tokens {
+        /// Group by goal, agent, day (UTC), or model
+        #[arg(long, default_value = "goal")]
+        by: GroupBy,
+
+        /// Only show the N biggest groups
+        #[arg(long, value_name = "N")]
+        top: Option<usize>,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Compare models by success rate, retries, tokens and time per attempt
+    Models {
+        /// Only count attempts at this goal's tasks, to compare like with like
+        #[arg(long)]
+        goal: Option<String>,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Calendar heat map of task completions per day (UTC)
+    Activity {
+        /// How many weeks back to show, counting the current one
+        #[arg(long, default_value_t = 12, value_parser = clap::value_parser!(u16).range(1..))]
+        weeks: u16,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum AllCommands {
+    /// Register a project so `rd all` reports on it
+    Add {
+        /// The project directory, or one inside it (default: current directory)
+        path: Option<PathBuf>,
+
+        /// Name to show it under (default: the directory name)
+        #[arg(long)]
+        name: Option<String>,
+    },
+
+    /// Stop reporting on a project
+    Remove {
+        /// The registered name
+        name: String,
+    },
+
+    /// List registered projects
+    List {
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Goal and task counts for every registered project, across all branches
+    Status {
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Ready tasks in every registered project
+    Ready {
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum EditCommands {
+    /// Edit a goal's description or title
+    Goal {
+        /// The goal ID to edit
+        goal_id: String,
+
+        /// New description
+        #[arg(long, required_unless_present_any = ["title", "project"])]
+        description: Option<String>,
+
+        /// New title; an empty title removes it
+        #[arg(long)]
+        title: Option<String>,
+
+        /// New project label; an empty label removes it
+        #[arg(long)]
+        project: Option<String>,
+    },
+
+    /// Edit a task's description, title or contract
+    Task(Box<TaskEdit>),
+}
+
+/// The fields `edit task` can change.
+#[derive(Args)]
+pub struct TaskEdit {
+    /// The task ID to edit
+    pub task_id: String,
+
+    /// New description
+    #[arg(long)]
+    pub description: Option<String>,
+
+    /// New title; an empty title removes it
+    #[arg(long)]
+    pub title: Option<String>,
+
+    /// New receives (contract)
+    #[arg(long)]
+    pub receives: Option<String>,
+
+    /// New produces (contract)
+    #[arg(long)]
+    pub produces: Option<String>,
+
+    /// New verify (contract)
+    #[arg(long)]
+    pub verify: Option<String>,
+
+    /// Add a blocked-by dependency
+    #[arg(long, value_delimiter = ',')]
+    pub blocked_by: Option<Vec<String>>,
+
+    /// New assignee (agent name); an empty name unassigns the task
+    #[arg(long)]
+    pub assignee: Option<String>,
+
+    /// New priority: low, normal, high, or urgent
+    #[arg(long)]
+    pub priority: Option<Priority>,
+
+    /// Keep the task out of `ready` until this time (a timestamp, or a
+    /// duration from now such as 2h); an empty value lifts it
+    #[arg(long)]
+    pub not_before: Option<String>,
+
+    /// How long the task should take, such as 45m or 2h; an empty value
+    /// removes the estimate
+    #[arg(long)]
+    pub estimate: Option<String>,
+
+    /// Replace the task's gates, shell commands that must succeed before
+    /// it is ready (repeatable); `--gate ""` removes them all
+    #[arg(long = "gate")]
+    pub gates: Option<Vec<String>>,
+
+    /// Replace the task's references to issues, pull requests or tickets,
+    /// as kind:target (repeatable); `--ref ""` removes them all
+    #[arg(long = "ref", value_name = "KIND:TARGET")]
+    pub refs: Option<Vec<String>>,
+
+    /// Open every field in $EDITOR as TOML, starting from any flags given
+    #[arg(long, short, conflicts_with = "patch")]
+    pub interactive: bool,
+
+    /// Apply a JSON merge patch of fields from a file, or `-` for stdin;
+    /// prints the updated task as JSON
+    #[arg(long, value_name = "FILE")]
+    pub patch: Option<PathBuf>,
+
+    /// Output as JSON
+    #[arg(long)]
+    pub json: bool,
 }
@@ -0,0 +1,173 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result, anyhow, bail};
+use jiff::Timestamp;
+use serde::Serialize;
+
+use super::ready::{self, ReadyTask};
+use super::status::stale_age;
+use crate::db::Database;
+use crate::models::{GoalState, TaskState};
+use crate::registry::{Project, Registry};
+use crate::{RADIAL_DIR, follow_redirect};
+
+/// One registered project's goals and tasks, counted across every branch.
+#[derive(Debug, Default, Serialize)]
+pub struct ProjectStatus {
+    pub name: String,
+    pub path: PathBuf,
+    /// Why the project's store could not be read; the counts are then zero.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Goals not yet completed.
+    pub goals: usize,
+    pub ready: usize,
+    pub in_progress: usize,
+    pub stale: usize,
+    pub blocked: usize,
+    pub failed: usize,
+    pub completed: usize,
+}
+
+/// A task that can be started now, and the project it is in.
+#[derive(Debug, Serialize)]
+pub struct ProjectTask {
+    pub project: String,
+    #[serde(flatten)]
+    pub task: ReadyTask,
+}
+
+/// Register the project at `path`, or the one containing the current
+/// directory, under `name` or its directory name.
+pub fn add(path: Option<&Path>, name: Option<String>) -> Result<Project> {
+    let start = match path {
+        Some(path) => path.to_path_buf(),
+        None => std::env::current_dir().context("Failed to read current directory")?,
+    };
+    let start = start
+        .canonicalize()
+        .with_context(|| format!("No such directory: {}", start.display()))?;
+    let root = start
+        .ancestors()
+        .find(|dir| dir.join(RADIAL_DIR).is_dir())
+        .ok_or_else(|| anyhow!("No {RADIAL_DIR} in {} or above it", start.display()))?;
+
+    let name = match name {
+        Some(name) => name,
+        None => root
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .ok_or_else(|| anyhow!("Name the project at {} with --name", root.display()))?,
+    };
+    let project = Project {
+        name,
+        path: root.to_path_buf(),
+    };
+
+    let mut registry = Registry::load()?;
+    registry.add(project.clone())?;
+    registry.save()?;
+    Ok(project)
+}
+
+pub fn remove(name: &str) -> Result<Project> {
+    let mut registry = Registry::load()?;
+    let Some(project) = registry.remove(name) else {
+        let names: Vec<&str> = registry
+            .projects()
+            .iter()
+            .map(|p| p.name.as_str())
+            .collect();
+        bail!(
+            "No registered project '{name}'. Registered: {}",
+            names.join(", ")
+        );
+    };
+    registry.save()?;
+    Ok(project)
+}
+
+pub fn list() -> Result<Vec<Project>> {
+    Ok(Registry::load()?.projects().to_vec())
+}
+
+/// Counts for each registered project, in the order they were added. A
+/// project that can't be read is reported with its error rather than
+/// failing the whole overview.
+pub fn status() -> Result<Vec<ProjectStatus>> {
+    let now = Timestamp::now();
+    Ok(Registry::load()?
+        .projects()
+        .iter()
+        .map(|project| {
+            let mut status = ProjectStatus {
+                name: project.name.clone(),
+                path: project.path.clone(),
+                ..ProjectStatus::default()
+            };
+            match open(project).and_then(|db| Ok((ready_in(&db)?, db))) {
+                Ok((ready, db)) => {
+                    status.ready = ready.len();
+                    count(&mut status, &db, now);
+                }
+                Err(e) => status.error = Some(format!("{e:#}")),
+            }
+            status
+        })
+        .collect())
+}
+
+fn count(status: &mut ProjectStatus, db: &Database, now: Timestamp) {
+    let threshold = db.config().status.stale_after;
+    for goal in db.list_goals() {
+        if goal.state() != GoalState::Completed {
+            status.goals += 1;
+        }
+        for task in db.list_tasks(goal.id()) {
+            match task.state() {
+                TaskState::InProgress | TaskState::Verifying => status.in_progress += 1,
+                TaskState::Blocked => status.blocked += 1,
+                TaskState::Failed => status.failed += 1,
+                TaskState::Completed => status.completed += 1,
+                TaskState::Pending | TaskState::Unknown(_) => {}
+            }
+            if stale_age(task, threshold, now).is_some() {
+                status.stale += 1;
+            }
+        }
+    }
+}
+
+/// Ready tasks across every registered project, highest priority first
+/// within each project. Projects that can't be read are skipped.
+pub fn ready() -> Result<Vec<ProjectTask>> {
+    let mut tasks = Vec::new();
+    for project in Registry::load()?.projects() {
+        let Ok(ready) = open(project).and_then(|db| ready_in(&db)) else {
+            continue;
+        };
+        tasks.extend(ready.into_iter().map(|task| ProjectTask {
+            project: project.name.clone(),
+            task,
+        }));
+    }
+    Ok(tasks)
+}
+
+fn open(project: &Project) -> Result<Database> {
+    let radial_dir = project.path.join(RADIAL_DIR);
+    if !radial_dir.is_dir() {
+        bail!("No {RADIAL_DIR} in {}", project.path.display());
+    }
+    Database::open(follow_redirect(&radial_dir))
+}
+
+/// Every ready task in `db`, on any branch.
+fn ready_in(db: &Database) -> Result<Vec<ReadyTask>> {
+    let mut tasks = Vec::new();
+    for goal in db.list_goals() {
+        tasks.extend(ready::run(goal.id(), false, false, None, db)?);
+    }
+    tasks.sort_by_key(|t| std::cmp::Reverse(t.priority()));
+    Ok(tasks)
+}
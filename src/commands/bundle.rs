@@ -0,0 +1,394 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use jiff::Timestamp;
+use serde::{Deserialize, Serialize};
+
+use crate::commands::task;
+use crate::db::{Database, atomic_write};
+use crate::error::{Error, Result};
+use crate::id::generate_id;
+use crate::models::{Goal, Task, TaskState};
+use crate::sandbox::ATTACHMENTS_DIR;
+
+/// Format version written into bundles; newer bundles are refused.
+pub const BUNDLE_VERSION: u32 = 1;
+
+/// One goal, its sub-goals and all their tasks, written by `export --goal`
+/// so a plan can be handed to someone with their own store.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Bundle {
+    pub version: u32,
+    pub exported_at: Timestamp,
+    /// The exported goal first, then its sub-goals, each after its parent.
+    pub goals: Vec<Goal>,
+    /// Every goal's tasks, comments included, in goal order.
+    pub tasks: Vec<Task>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub attachments: Vec<Attachment>,
+}
+
+/// A file kept under `.radial/attachments/<task-id>/`, such as a verify log.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Attachment {
+    pub task_id: String,
+    pub name: String,
+    pub content: String,
+}
+
+/// Where `export --goal --output` wrote a bundle.
+#[derive(Debug, Serialize)]
+pub struct ExportReport {
+    pub path: PathBuf,
+    pub goal: Goal,
+    pub goal_count: usize,
+    pub task_count: usize,
+    pub attachment_count: usize,
+}
+
+/// What `import` added, with the ID each record was given.
+#[derive(Debug, Serialize)]
+pub struct ImportReport {
+    pub goal: Goal,
+    pub goal_count: usize,
+    pub task_count: usize,
+    pub attachment_count: usize,
+    /// Each exported ID and the ID it has in this store.
+    pub ids: Vec<(String, String)>,
+}
+
+/// Bundle goal `goal_id` with every goal below it, their tasks and the
+/// tasks' attachments. Attachments that aren't text are left out.
+pub fn export(goal_id: &str, db: &Database) -> Result<Bundle> {
+    let root = db.require_goal(goal_id)?;
+    let mut goals = vec![root.clone()];
+    let mut oldest_first = db.list_goals();
+    oldest_first.reverse();
+    // Bounded by the goal count in case a hand edit made the parents loop.
+    let mut next = 0;
+    while next < goals.len() && goals.len() <= oldest_first.len() {
+        let parent = goals[next].id().to_string();
+        goals.extend(
+            oldest_first
+                .iter()
+                .filter(|g| g.parent_id() == Some(parent.as_str()))
+                .map(|g| (*g).clone()),
+        );
+        next += 1;
+    }
+
+    let tasks: Vec<Task> = goals
+        .iter()
+        .flat_map(|g| db.list_tasks(g.id()))
+        .cloned()
+        .collect();
+    let mut attachments = Vec::new();
+    for task in &tasks {
+        let dir = db.base_path().join(ATTACHMENTS_DIR).join(task.id());
+        let mut files: Vec<_> = fs::read_dir(&dir)
+            .into_iter()
+            .flatten()
+            .flatten()
+            .map(|e| e.path())
+            .filter(|p| p.is_file())
+            .collect();
+        files.sort();
+        for path in files {
+            let (Some(name), Ok(content)) = (path.file_name(), fs::read_to_string(&path)) else {
+                continue;
+            };
+            attachments.push(Attachment {
+                task_id: task.id().to_string(),
+                name: name.to_string_lossy().into_owned(),
+                content,
+            });
+        }
+    }
+
+    Ok(Bundle {
+        version: BUNDLE_VERSION,
+        exported_at: Timestamp::now(),
+        goals,
+        tasks,
+        attachments,
+    })
+}
+
+/// Write `bundle` to `path` as JSON.
+pub fn write(bundle: &Bundle, path: &Path) -> Result<ExportReport> {
+    let content = serde_json::to_vec_pretty(bundle).context("Failed to serialize bundle")?;
+    atomic_write(path, &content)?;
+    Ok(ExportReport {
+        path: path.to_path_buf(),
+        goal: bundle.goals[0].clone(),
+        goal_count: bundle.goals.len(),
+        task_count: bundle.tasks.len(),
+        attachment_count: bundle.attachments.len(),
+    })
+}
+
+/// Read a bundle written by [`export`] from `path`, or stdin for `-`.
+pub fn read(path: &Path) -> Result<Bundle> {
+    let content = if path == Path::new("-") {
+        std::io::read_to_string(std::io::stdin()).context("Failed to read bundle from stdin")?
+    } else {
+        fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?
+    };
+    let bundle: Bundle = serde_json::from_str(&content)
+        .with_context(|| format!("Failed to parse {}", path.display()))?;
+    if bundle.version > BUNDLE_VERSION {
+        return Err(Error::conflict(format!(
+            "{} is a version {} bundle; this radial reads up to version {BUNDLE_VERSION}",
+            path.display(),
+            bundle.version
+        )));
+    }
+    Ok(bundle)
+}
+
+/// Add a bundle's goals and tasks to the store under new IDs, so it can be
+/// imported next to the plan it came from, or twice. Parents, goals and
+/// blockers are pointed at the new IDs; blockers outside the bundle are
+/// dropped. The top goal is added as a top-level goal, without the
+/// exporter's branch, claim, or a milestone this store doesn't have. A
+/// failure partway removes whatever was added.
+pub fn import(bundle: Bundle, db: &mut Database) -> Result<ImportReport> {
+    let Some(root_id) = bundle.goals.first().map(|g| g.id().to_string()) else {
+        return Err(Error::conflict("The bundle has no goals"));
+    };
+    let goal_ids: Vec<&str> = bundle.goals.iter().map(Goal::id).collect();
+    if let Some(task) = bundle
+        .tasks
+        .iter()
+        .find(|t| !goal_ids.contains(&t.goal_id()))
+    {
+        return Err(Error::conflict(format!(
+            "Task {} belongs to goal {}, which is not in the bundle",
+            task.id(),
+            task.goal_id()
+        )));
+    }
+    let alphabet = db.config().ids.alphabet;
+    let mut ids: Vec<(String, String)> = Vec::new();
+    let mut renamed: HashMap<String, String> = HashMap::new();
+    let old_ids = bundle
+        .goals
+        .iter()
+        .map(Goal::id)
+        .chain(bundle.tasks.iter().map(Task::id));
+    for old in old_ids {
+        let new = loop {
+            let id = generate_id(alphabet);
+            let taken = db.get_goal(&id).is_some()
+                || db.get_task(&id).is_some()
+                || renamed.values().any(|v| *v == id);
+            if !taken {
+                break id;
+            }
+        };
+        renamed.insert(old.to_string(), new.clone());
+        ids.push((old.to_string(), new));
+    }
+
+    let mut created = Vec::new();
+    if let Err(err) = add_records(&bundle, &renamed, &root_id, &mut created, db) {
+        for goal_id in created.iter().rev() {
+            db.discard_goal(goal_id)
+                .context("Failed to roll back partially imported bundle")?;
+        }
+        return Err(err);
+    }
+
+    let mut attachment_count = 0;
+    if !db.is_in_memory() {
+        for attachment in &bundle.attachments {
+            let (Some(task_id), Some(name)) = (
+                renamed.get(&attachment.task_id),
+                Path::new(&attachment.name).file_name(),
+            ) else {
+                continue;
+            };
+            let dir = db.base_path().join(ATTACHMENTS_DIR).join(task_id);
+            fs::create_dir_all(&dir)
+                .with_context(|| format!("Failed to create {}", dir.display()))?;
+            atomic_write(&dir.join(name), attachment.content.as_bytes())?;
+            attachment_count += 1;
+        }
+    }
+
+    for goal in &bundle.goals {
+        task::settle_blocked(&renamed[goal.id()], db)?;
+    }
+    Ok(ImportReport {
+        goal: db.get_goal(&renamed[&root_id]).unwrap().clone(),
+        goal_count: bundle.goals.len(),
+        task_count: bundle.tasks.len(),
+        attachment_count,
+        ids,
+    })
+}
+
+/// Create the bundle's goals and tasks under their new IDs, noting each goal
+/// in `created` so a failure partway can be rolled back. Work that was
+/// underway goes back to pending and unassigned, since the agents doing it
+/// are the exporter's.
+fn add_records(
+    bundle: &Bundle,
+    renamed: &HashMap<String, String>,
+    root_id: &str,
+    created: &mut Vec<String>,
+    db: &mut Database,
+) -> Result<()> {
+    for goal in &bundle.goals {
+        let parent = goal
+            .parent_id()
+            .filter(|_| goal.id() != root_id)
+            .and_then(|p| renamed.get(p))
+            .cloned();
+        let milestone = goal
+            .milestone()
+            .filter(|m| db.get_milestone(m).is_some())
+            .map(str::to_string);
+        let id = renamed[goal.id()].clone();
+        let mut goal = goal
+            .clone()
+            .with_id(id.clone(), parent)
+            .with_branch(None)
+            .with_milestone(milestone);
+        goal.set_claim(None);
+        db.create_goal(goal)?;
+        created.push(id);
+    }
+    for task in &bundle.tasks {
+        let blocked_by = task
+            .blocked_by()
+            .iter()
+            .filter_map(|id| renamed.get(id))
+            .cloned()
+            .collect();
+        let mut task = task
+            .clone()
+            .with_id(renamed[task.id()].clone(), renamed[task.goal_id()].clone());
+        task.set_blocked_by(blocked_by);
+        let underway = [TaskState::InProgress, TaskState::Verifying];
+        if task.transition_from_any(&underway, TaskState::Pending, db.reason()) {
+            task.set_assignee(None);
+        }
+        db.create_task(task)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{GoalBuilder, TaskBuilder, TempProject};
+    use rstest::rstest;
+
+    fn project() -> TempProject {
+        let mut project = TempProject::new().unwrap();
+        project.add_goal(GoalBuilder::new("Ship").id("g1")).unwrap();
+        project
+            .add_goal(GoalBuilder::new("Docs").id("g2").parent("g1"))
+            .unwrap();
+        project
+            .add_goal(GoalBuilder::new("Other").id("g3"))
+            .unwrap();
+        project
+            .add_task(
+                TaskBuilder::new("g1", "Schema")
+                    .id("t1")
+                    .state(TaskState::InProgress)
+                    .assignee("ann"),
+            )
+            .unwrap();
+        project
+            .add_task(
+                TaskBuilder::new("g1", "Migrate")
+                    .id("t2")
+                    .state(TaskState::Blocked)
+                    .blocked_by("t1"),
+            )
+            .unwrap();
+        project
+            .add_task(TaskBuilder::new("g2", "Guide").id("t3"))
+            .unwrap();
+        project
+            .add_task(TaskBuilder::new("g3", "Unrelated").id("t4"))
+            .unwrap();
+        let logs = project.radial_dir().join(ATTACHMENTS_DIR).join("t1");
+        fs::create_dir_all(&logs).unwrap();
+        fs::write(logs.join("verify-1.log"), "ok\n").unwrap();
+        project
+    }
+
+    // The goal, its sub-goal and their tasks and attachments are bundled;
+    // other goals are not.
+    #[rstest]
+    fn exports_the_subtree() {
+        let project = project();
+        let bundle = export("g1", project.db()).unwrap();
+        let goals: Vec<&str> = bundle.goals.iter().map(Goal::id).collect();
+        let tasks: Vec<&str> = bundle.tasks.iter().map(Task::id).collect();
+        assert_eq!(goals, ["g1", "g2"]);
+        assert_eq!(tasks, ["t1", "t2", "t3"]);
+        assert_eq!(bundle.attachments.len(), 1);
+        assert_eq!(bundle.attachments[0].content, "ok\n");
+    }
+
+    // Importing into the same store adds a copy under new IDs with its
+    // links pointed at the copies.
+    #[rstest]
+    fn imports_under_new_ids() {
+        let mut project = project();
+        let bundle = export("g1", project.db()).unwrap();
+        let report = import(bundle, project.db_mut()).unwrap();
+        assert_eq!((report.goal_count, report.task_count), (2, 3));
+        assert_eq!(report.attachment_count, 1);
+
+        let new: HashMap<&str, &str> = report
+            .ids
+            .iter()
+            .map(|(old, new)| (old.as_str(), new.as_str()))
+            .collect();
+        assert!(new.iter().all(|(old, new)| old != new));
+        project.reload().unwrap();
+        let db = project.db();
+        assert_eq!(db.get_goal(new["g2"]).unwrap().parent_id(), Some(new["g1"]));
+        let migrate = db.get_task(new["t2"]).unwrap();
+        assert_eq!(migrate.goal_id(), new["g1"]);
+        assert_eq!(migrate.blocked_by(), [new["t1"].to_string()]);
+        assert_eq!(migrate.state(), TaskState::Blocked);
+        let schema = db.get_task(new["t1"]).unwrap();
+        assert_eq!(schema.state(), TaskState::Pending);
+        assert_eq!(schema.assignee(), None);
+        assert_eq!(db.get_task("t1").unwrap().assignee(), Some("ann"));
+        assert!(
+            db.base_path()
+                .join(ATTACHMENTS_DIR)
+                .join(new["t1"])
+                .join("verify-1.log")
+                .is_file()
+        );
+        assert_eq!(db.list_goals().len(), 5);
+    }
+
+    // A bundle that fails partway leaves nothing behind, not even a
+    // tombstone.
+    #[rstest]
+    fn rolls_back_a_failed_import() {
+        let mut project = project();
+        let mut bundle = export("g1", project.db()).unwrap();
+        let duplicate = bundle.tasks[0].clone();
+        bundle.tasks.push(duplicate);
+        assert!(import(bundle, project.db_mut()).is_err());
+
+        project.reload().unwrap();
+        let db = project.db();
+        assert_eq!(db.list_goals().len(), 3);
+        assert_eq!(db.all_tasks().count(), 4);
+        assert!(db.tombstones().goals.is_empty());
+    }
+}
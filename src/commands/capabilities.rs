@@ -0,0 +1,228 @@
+use std::any::TypeId;
+
+use clap::{Arg, ArgAction, Command};
+use serde::Serialize;
+
+use crate::models::state_machine::State;
+use crate::models::{GoalState, TaskState};
+
+/// Everything an agent framework needs to call radial as a tool: its
+/// commands and flags, taken from the clap definitions, and the state
+/// tables from [`crate::models::state_machine`].
+#[derive(Debug, Serialize)]
+pub struct Capabilities {
+    pub name: String,
+    pub version: &'static str,
+    /// Flags accepted by every command.
+    pub global_flags: Vec<ArgSpec>,
+    /// Commands that can be run, with subcommands flattened into their full
+    /// name, e.g. `task start`.
+    pub commands: Vec<CommandSpec>,
+    pub task_states: Vec<StateSpec<TaskState>>,
+    pub goal_states: Vec<StateSpec<GoalState>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CommandSpec {
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub about: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub aliases: Vec<String>,
+    pub args: Vec<ArgSpec>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ArgSpec {
+    pub name: String,
+    /// `None` for positional arguments.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub long: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub short: Option<char>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub help: Option<String>,
+    pub required: bool,
+    /// `false` for switches such as `--json`.
+    pub takes_value: bool,
+    /// Whether the flag can be given more than once.
+    pub repeatable: bool,
+    pub kind: ValueKind,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default: Option<String>,
+}
+
+impl ArgSpec {
+    /// How the argument is written on the command line, e.g.
+    /// `[--agent <agent>]`.
+    pub fn usage(&self) -> String {
+        let value = format!("<{}>", self.name);
+        let usage = match &self.long {
+            Some(long) if self.takes_value => format!("--{long} {value}"),
+            Some(long) => format!("--{long}"),
+            None => value,
+        };
+        let usage = if self.repeatable {
+            format!("{usage}...")
+        } else {
+            usage
+        };
+        if self.required {
+            usage
+        } else {
+            format!("[{usage}]")
+        }
+    }
+}
+
+/// The shape of an argument's value, for typing it in a tool schema.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ValueKind {
+    /// A flag that takes no value, such as `--json`.
+    Switch,
+    Text,
+    Integer,
+    /// Several values, repeated or separated by a delimiter.
+    List,
+}
+
+/// A state and the states it can move to in one step.
+#[derive(Debug, Serialize)]
+pub struct StateSpec<S: 'static> {
+    pub state: S,
+    pub next: &'static [S],
+}
+
+/// Describe `cli`, the root clap command, together with the state machine.
+pub fn run(cli: &Command) -> Capabilities {
+    let mut commands = Vec::new();
+    for sub in cli.get_subcommands() {
+        collect_commands(sub, "", &mut commands);
+    }
+
+    Capabilities {
+        name: cli.get_name().to_string(),
+        version: env!("CARGO_PKG_VERSION"),
+        global_flags: args(cli),
+        commands,
+        task_states: states(),
+        goal_states: states(),
+    }
+}
+
+/// Add `cmd` to `out`, or its subcommands if it only groups others, as
+/// `rd task` does.
+fn collect_commands(cmd: &Command, prefix: &str, out: &mut Vec<CommandSpec>) {
+    if cmd.is_hide_set() {
+        return;
+    }
+    let name = format!("{prefix}{}", cmd.get_name());
+    if cmd.has_subcommands() {
+        for sub in cmd.get_subcommands() {
+            collect_commands(sub, &format!("{name} "), out);
+        }
+        return;
+    }
+
+    out.push(CommandSpec {
+        about: cmd.get_about().map(ToString::to_string),
+        aliases: cmd.get_visible_aliases().map(ToString::to_string).collect(),
+        args: args(cmd),
+        name,
+    });
+}
+
+/// Value types clap parses integer flags into.
+const INTEGER_TYPES: &[fn() -> TypeId] = &[
+    TypeId::of::<u8>,
+    TypeId::of::<u32>,
+    TypeId::of::<u64>,
+    TypeId::of::<usize>,
+    TypeId::of::<i32>,
+    TypeId::of::<i64>,
+];
+
+fn args(cmd: &Command) -> Vec<ArgSpec> {
+    cmd.get_arguments()
+        .filter(|arg| !arg.is_hide_set())
+        .map(arg_spec)
+        .collect()
+}
+
+fn arg_spec(arg: &Arg) -> ArgSpec {
+    let takes_value = arg.get_action().takes_values();
+    let repeatable = matches!(arg.get_action(), ArgAction::Append | ArgAction::Count);
+    let value_type = arg.get_value_parser().type_id();
+    let kind = if !takes_value {
+        ValueKind::Switch
+    } else if repeatable || arg.get_value_delimiter().is_some() {
+        ValueKind::List
+    } else if INTEGER_TYPES.iter().any(|id| value_type == id()) {
+        ValueKind::Integer
+    } else {
+        ValueKind::Text
+    };
+    let default = arg
+        .get_default_values()
+        .first()
+        .map(|v| v.to_string_lossy().into_owned());
+    ArgSpec {
+        name: arg.get_id().to_string(),
+        long: arg.get_long().map(ToString::to_string),
+        short: arg.get_short(),
+        help: arg.get_help().map(ToString::to_string),
+        required: arg.is_required_set(),
+        takes_value,
+        repeatable,
+        kind,
+        default,
+    }
+}
+
+fn states<S: State>() -> Vec<StateSpec<S>> {
+    S::ALL
+        .iter()
+        .map(|state| StateSpec {
+            state: state.clone(),
+            next: state.next_states(),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::Cli;
+    use clap::CommandFactory;
+    use rstest::rstest;
+
+    // Grouping commands are flattened into their leaves, and flags keep
+    // their shape: switches take no value, `--blocked-by-artifact` repeats, and
+    // positionals have no long name.
+    #[rstest]
+    fn describes_commands_and_flags() {
+        let caps = run(&Cli::command());
+        let names: Vec<&str> = caps.commands.iter().map(|c| c.name.as_str()).collect();
+        assert!(names.contains(&"task start"));
+        assert!(names.contains(&"capabilities"));
+        assert!(!names.contains(&"task"));
+
+        let create = caps
+            .commands
+            .iter()
+            .find(|c| c.name == "task create")
+            .unwrap();
+        let arg = |name: &str| create.args.iter().find(|a| a.name == name).unwrap();
+        assert!(arg("goal_id").required && arg("goal_id").long.is_none());
+        assert!(!arg("json").takes_value);
+        assert!(arg("blocked_by_artifacts").repeatable);
+        assert_eq!(arg("priority").default.as_deref(), Some("normal"));
+        assert_eq!(arg("blocked_by").kind, ValueKind::List);
+        assert_eq!(arg("json").kind, ValueKind::Switch);
+        assert_eq!(arg("priority").kind, ValueKind::Text);
+
+        assert!(caps.global_flags.iter().any(|a| a.name == "full"));
+        assert_eq!(caps.task_states.len(), TaskState::ALL.len());
+    }
+}
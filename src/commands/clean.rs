@@ -1,73 +1,371 @@
+use std::fs;
 use std::io::{self, Write};
+use std::path::Path;
 
-use anyhow::Result;
 use console::style;
+use serde::Serialize;
 
 use crate::db::Database;
-use crate::models::GoalState;
+use crate::error::{Error, Result};
+use crate::models::{Goal, GoalState, ReasonCode, Task, TaskState};
+use crate::output::fit;
+use crate::progress::Progress;
 
-pub fn run(all: bool, force: bool, db: &mut Database) -> Result<()> {
-    let goals: Vec<_> = db
-        .list_goals()
+/// A goal `clean` deleted, as it was just before.
+#[derive(Debug, Serialize)]
+pub struct RemovedGoal {
+    pub id: String,
+    /// The title, or the description if there is none.
+    pub headline: String,
+    pub state: GoalState,
+    pub task_count: usize,
+    pub comment_count: usize,
+    /// Artifacts listed by the goal's completed tasks. Only the record of
+    /// them goes; the files themselves are left alone.
+    pub artifact_count: usize,
+    /// Size of the goal's directory on disk.
+    pub bytes: u64,
+}
+
+impl RemovedGoal {
+    /// "3 task(s), 2 comment(s), 1 artifact(s), 4120 bytes"
+    pub fn contents(&self) -> String {
+        format!(
+            "{} task(s), {} comment(s), {} artifact(s), {} bytes",
+            self.task_count, self.comment_count, self.artifact_count, self.bytes
+        )
+    }
+
+    fn new(goal: &Goal, db: &Database) -> Self {
+        let tasks = db.list_tasks(goal.id());
+        Self {
+            id: goal.id().to_string(),
+            headline: goal.headline().to_string(),
+            state: goal.state(),
+            task_count: tasks.len(),
+            comment_count: tasks.iter().map(|t| t.comments().len()).sum(),
+            artifact_count: tasks
+                .iter()
+                .filter_map(|t| t.result())
+                .map(|r| r.artifacts().len())
+                .sum(),
+            bytes: dir_size(&db.base_path().join(goal.id())),
+        }
+    }
+}
+
+/// Result of `clean`.
+#[derive(Debug, Serialize)]
+pub struct CleanResult {
+    /// Whether goals in any state were candidates, not just completed ones.
+    pub force: bool,
+    /// Whether `removed` lists what would be removed, with nothing deleted.
+    pub dry_run: bool,
+    pub removed: Vec<RemovedGoal>,
+    /// IDs of candidates the user chose to keep when prompted.
+    pub kept: Vec<String>,
+    /// Dependencies from other goals that were dropped (or, on a dry run,
+    /// that stand in the way).
+    pub unlinked: Vec<Reference>,
+}
+
+/// What `clean` removes and how careful it is.
+#[derive(Debug, Default)]
+#[allow(clippy::struct_excessive_bools)]
+pub struct CleanOptions {
+    /// Skip the prompt for each completed goal.
+    pub all: bool,
+    /// Remove goals in any state, without prompting.
+    pub force: bool,
+    pub dry_run: bool,
+    /// Drop other goals' dependencies on removed tasks instead of refusing.
+    pub unlink: bool,
+    /// Only this goal.
+    pub goal: Option<String>,
+}
+
+/// A task outside the goals being removed that is blocked by one inside.
+#[derive(Debug, Clone, Serialize)]
+pub struct Reference {
+    pub task_id: String,
+    pub goal_id: String,
+    pub blocked_by: String,
+}
+
+/// Tasks in other goals that wait on tasks in `goal_ids`. Removing those
+/// goals would leave them blocked by tasks that no longer exist.
+pub fn outside_references(goal_ids: &[&str], db: &Database) -> Vec<Reference> {
+    let removed: Vec<&str> = goal_ids
+        .iter()
+        .flat_map(|goal_id| db.list_tasks(goal_id))
+        .map(Task::id)
+        .collect();
+    db.list_goals()
         .into_iter()
-        .filter(|g| force || g.state() == GoalState::Completed)
-        .cloned()
+        .filter(|g| !goal_ids.contains(&g.id()))
+        .flat_map(|g| db.list_tasks(g.id()))
+        .flat_map(|task| {
+            task.blocked_by()
+                .iter()
+                .filter(|id| removed.contains(&id.as_str()))
+                .map(|id| Reference {
+                    task_id: task.id().to_string(),
+                    goal_id: task.goal_id().to_string(),
+                    blocked_by: id.clone(),
+                })
+        })
+        .collect()
+}
+
+/// The error for goals that can't go because of `references`.
+pub fn referenced_error(references: &[Reference], remedy: &str) -> Error {
+    let lines: Vec<String> = references
+        .iter()
+        .map(|r| {
+            format!(
+                "  {} (goal {}) is blocked by {}",
+                r.task_id, r.goal_id, r.blocked_by
+            )
+        })
         .collect();
+    Error::conflict(format!(
+        "Tasks in other goals depend on tasks being removed:\n{}\n{remedy}",
+        lines.join("\n")
+    ))
+}
 
-    if goals.is_empty() {
-        let msg = if force {
-            "No goals found."
+/// Drop each reference's dependency, releasing a blocked task once it
+/// waits on nothing else.
+fn unlink(references: &[Reference], db: &mut Database) -> Result<()> {
+    let base = db.base_path().to_path_buf();
+    for reference in references {
+        let task = db.get_task_mut(&reference.task_id).unwrap();
+        let remaining = task
+            .blocked_by()
+            .iter()
+            .filter(|id| **id != reference.blocked_by)
+            .cloned()
+            .collect::<Vec<_>>();
+        let released = remaining.is_empty() && task.blocked_by_artifacts().is_empty();
+        task.set_blocked_by(remaining);
+        if released && task.state() == TaskState::Blocked {
+            task.unblock(ReasonCode::AgentAuto);
+        }
+        task.write_file(&base)?;
+    }
+    Ok(())
+}
+
+/// Remove completed goals, or goals in any state with `force`, asking about
+/// each unless `all` or `force` is set. `goal` limits this to one goal;
+/// `dry_run` only reports what would go. Goals whose tasks other goals
+/// still wait on are refused unless `unlink` drops those dependencies.
+pub fn run(options: &CleanOptions, db: &mut Database) -> Result<CleanResult> {
+    let CleanOptions {
+        all,
+        force,
+        dry_run,
+        unlink: unlink_references,
+        ref goal,
+    } = *options;
+    let goals: Vec<Goal> = match goal {
+        Some(goal_id) => {
+            let goal = db.require_goal_writable(goal_id)?;
+            if !force && goal.state() != GoalState::Completed {
+                return Err(Error::conflict(format!(
+                    "Goal {goal_id} is {}, not completed; use --force to remove it anyway",
+                    goal.state().as_ref()
+                )));
+            }
+            vec![goal.clone()]
+        }
+        None => db
+            .list_goals()
+            .into_iter()
+            .filter(|g| force || g.state() == GoalState::Completed)
+            // Goals another agent has claimed are theirs to clean up.
+            .filter(|g| db.check_claim(g.id(), db.agent()).is_ok())
+            .cloned()
+            .collect(),
+    };
+
+    let mut result = CleanResult {
+        force,
+        dry_run,
+        removed: Vec::new(),
+        kept: Vec::new(),
+        unlinked: Vec::new(),
+    };
+
+    for goal in goals {
+        let removed = RemovedGoal::new(&goal, db);
+        // --all, --force and --dry-run skip prompting
+        if dry_run || all || force || prompt_for_goal(&removed)? {
+            result.removed.push(removed);
         } else {
-            "No completed goals to clean."
-        };
-        println!("{msg}");
-        return Ok(());
+            result.kept.push(removed.id);
+        }
     }
 
-    let mut removed = 0;
-
-    for goal in &goals {
-        // --all or --force skip prompting
-        let should_remove = all || force || prompt_for_goal(goal)?;
-
-        if should_remove {
-            db.delete_goal(goal.id())?;
-            println!(
-                "  {} {} — {}",
-                style("Removed").red(),
-                style(goal.id()).cyan(),
-                truncate(goal.description(), 60),
-            );
-            removed += 1;
+    let goal_ids: Vec<&str> = result.removed.iter().map(|g| g.id.as_str()).collect();
+    result.unlinked = outside_references(&goal_ids, db);
+    if dry_run {
+        return Ok(result);
+    }
+    if !result.unlinked.is_empty() {
+        if !unlink_references {
+            return Err(referenced_error(
+                &result.unlinked,
+                "Nothing was removed. Pass --unlink to drop these dependencies and clean anyway.",
+            ));
         }
+        unlink(&result.unlinked, db)?;
+    }
+    let mut progress = Progress::new("Cleaning", result.removed.len());
+    for goal in &result.removed {
+        db.delete_goal(&goal.id)?;
+        progress.inc();
     }
 
-    println!("\nCleaned {} goal(s).", style(removed).bold());
-    Ok(())
+    Ok(result)
+}
+
+/// Total size of the files under `dir`; zero if it doesn't exist.
+fn dir_size(dir: &Path) -> u64 {
+    fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|entry| {
+            let path = entry.path();
+            if path.is_dir() {
+                dir_size(&path)
+            } else {
+                entry.metadata().map_or(0, |m| m.len())
+            }
+        })
+        .sum()
 }
 
-/// Prompt the user to confirm deletion of a single goal.
-fn prompt_for_goal(goal: &crate::models::Goal) -> Result<bool> {
-    let mut stdout = io::stdout().lock();
+/// Prompt the user to confirm deletion of a single goal. The prompt goes to
+/// stderr so `--json` output stays parseable.
+fn prompt_for_goal(goal: &RemovedGoal) -> Result<bool> {
+    let mut stderr = io::stderr().lock();
     write!(
-        stdout,
-        "Remove {} [{}] {}? [y/N] ",
-        style(goal.id()).cyan().bold(),
-        style(goal.state().as_ref()).dim(),
-        truncate(goal.description(), 50),
+        stderr,
+        "Remove {} [{}] {} ({})? [y/N] ",
+        style(&goal.id).cyan().bold(),
+        style(goal.state.as_ref()).dim(),
+        fit(&goal.headline, 80),
+        style(goal.contents()).dim(),
     )?;
-    stdout.flush()?;
+    stderr.flush()?;
 
     let mut input = String::new();
     io::stdin().read_line(&mut input)?;
     Ok(input.trim().eq_ignore_ascii_case("y"))
 }
 
-fn truncate(s: &str, max: usize) -> String {
-    let first_line = s.lines().next().unwrap_or(s);
-    if first_line.len() <= max {
-        first_line.to_string()
-    } else {
-        format!("{}…", &first_line[..max - 1])
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Outcome, TaskState};
+    use crate::testing::{GoalBuilder, TaskBuilder, TempProject};
+    use rstest::rstest;
+
+    fn project() -> TempProject {
+        let mut project = TempProject::new().unwrap();
+        project
+            .add_goal(
+                GoalBuilder::new("Done")
+                    .id("g1")
+                    .state(GoalState::Completed),
+            )
+            .unwrap();
+        project.add_goal(GoalBuilder::new("Open").id("g2")).unwrap();
+        let task = project
+            .add_task(TaskBuilder::new("g1", "work").state(TaskState::InProgress))
+            .unwrap();
+        let task = project.db_mut().get_task_mut(task.id()).unwrap();
+        task.complete(
+            Outcome::new("ok".into(), vec!["a.rs".into()]),
+            task.metrics().clone(),
+            ReasonCode::UserRequest,
+        );
+        project
+    }
+
+    // A dry run reports the same goals with their contents but deletes nothing.
+    #[rstest]
+    fn dry_run_removes_nothing() {
+        let mut project = project();
+        let options = CleanOptions {
+            all: true,
+            dry_run: true,
+            ..CleanOptions::default()
+        };
+        let result = run(&options, project.db_mut()).unwrap();
+        assert!(result.dry_run);
+        assert_eq!(result.removed.len(), 1);
+        let goal = &result.removed[0];
+        assert_eq!((goal.id.as_str(), goal.task_count), ("g1", 1));
+        assert_eq!(goal.artifact_count, 1);
+        assert!(goal.bytes > 0);
+        assert!(project.db().get_goal("g1").is_some());
+    }
+
+    // `--goal` picks one goal, and an unfinished one needs `--force`.
+    #[rstest]
+    #[case::completed("g1", false, true)]
+    #[case::open_refused("g2", false, false)]
+    #[case::open_forced("g2", true, true)]
+    fn cleans_one_goal(#[case] goal: &str, #[case] force: bool, #[case] removed: bool) {
+        let mut project = project();
+        let options = CleanOptions {
+            all: true,
+            force,
+            goal: Some(goal.to_string()),
+            ..CleanOptions::default()
+        };
+        let result = run(&options, project.db_mut());
+        assert_eq!(result.is_ok(), removed);
+        assert_eq!(project.db().get_goal(goal).is_none(), removed);
+        assert_eq!(project.db().list_goals().len(), if removed { 1 } else { 2 });
+    }
+
+    // A task in another goal blocked on a removed task stops the clean
+    // outright; with `unlink` the dependency goes and the task is released.
+    #[rstest]
+    #[case::refused(false)]
+    #[case::unlinked(true)]
+    fn guards_outside_dependents(#[case] unlink: bool) {
+        let mut project = project();
+        let blocker = project.db().list_tasks("g1")[0].id().to_string();
+        project
+            .add_task(
+                TaskBuilder::new("g2", "waits")
+                    .id("t9")
+                    .state(TaskState::Blocked)
+                    .blocked_by(blocker.as_str()),
+            )
+            .unwrap();
+
+        let options = CleanOptions {
+            all: true,
+            unlink,
+            ..CleanOptions::default()
+        };
+        let result = run(&options, project.db_mut());
+        assert_eq!(result.is_ok(), unlink);
+        assert_eq!(project.db().get_goal("g1").is_none(), unlink);
+        let waiting = project.db().get_task("t9").unwrap();
+        if unlink {
+            assert!(waiting.blocked_by().is_empty());
+            assert_eq!(waiting.state(), TaskState::Pending);
+            assert_eq!(result.unwrap().unlinked[0].blocked_by, blocker);
+        } else {
+            assert!(result.unwrap_err().to_string().contains("t9 (goal g2)"));
+            assert_eq!(waiting.blocked_by(), [blocker.as_str()]);
+        }
     }
 }
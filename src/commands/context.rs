@@ -0,0 +1,328 @@
+use std::collections::BTreeSet;
+
+use serde::Serialize;
+
+use crate::commands::suggest::{artifacts, keywords};
+use crate::db::Database;
+use crate::error::Result;
+use crate::models::{Contract, Outcome, Task, TaskState};
+
+/// A task together with what its blockers handed over.
+#[derive(Debug, Serialize)]
+pub struct TaskContext {
+    pub task: Task,
+    pub upstream: Vec<Upstream>,
+    /// `receives` with its placeholders filled in, when it has any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub inputs: Option<String>,
+    /// Files named in `receives` that no blocker produced, and placeholders
+    /// that can't be filled in yet.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub unresolved: Vec<String>,
+}
+
+/// Text with its placeholders filled in, and those that couldn't be.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Substituted {
+    pub text: String,
+    pub unresolved: Vec<String>,
+}
+
+/// One direct blocker and how its output lines up with the task's `receives`.
+#[derive(Debug, Serialize)]
+pub struct Upstream {
+    pub id: String,
+    pub description: String,
+    pub state: TaskState,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub result: Option<Outcome>,
+    /// Artifacts the blocker produced that `receives` asks for.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub matched_artifacts: Vec<String>,
+    /// Significant words shared between the blocker's output and `receives`.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub shared_terms: Vec<String>,
+}
+
+pub fn task(task_id: &str, db: &Database) -> Result<TaskContext> {
+    let task = db.require_task(task_id)?;
+    Ok(resolve(task, db))
+}
+
+/// Resolve `task`'s direct blockers against its `receives` contract.
+pub fn resolve(task: &Task, db: &Database) -> TaskContext {
+    let receives = task.contract().map(Contract::receives).unwrap_or_default();
+    let wanted = artifacts(receives);
+    let wanted_terms = keywords(receives);
+
+    let mut resolved = BTreeSet::new();
+    let upstream = task
+        .blocked_by()
+        .iter()
+        .filter_map(|id| db.get_task(id))
+        .map(|blocker| {
+            let produced = blocker
+                .result()
+                .into_iter()
+                .flat_map(|r| r.artifacts().iter().cloned())
+                .chain(
+                    blocker
+                        .contract()
+                        .map(|c| artifacts(c.produces()))
+                        .unwrap_or_default(),
+                )
+                .collect::<BTreeSet<String>>();
+
+            let matched_artifacts: Vec<String> = produced
+                .into_iter()
+                .filter(|artifact| {
+                    wanted
+                        .iter()
+                        .any(|w| w == artifact || file_name(w) == file_name(artifact))
+                })
+                .collect();
+            for artifact in &matched_artifacts {
+                resolved.extend(
+                    wanted
+                        .iter()
+                        .filter(|w| file_name(w) == file_name(artifact))
+                        .cloned(),
+                );
+            }
+
+            let output_text = format!(
+                "{} {}",
+                blocker
+                    .contract()
+                    .map(Contract::produces)
+                    .unwrap_or_default(),
+                blocker.result().map(Outcome::summary).unwrap_or_default()
+            );
+            let mut shared_terms: Vec<String> = keywords(&output_text)
+                .intersection(&wanted_terms)
+                .cloned()
+                .collect();
+            shared_terms.sort();
+
+            Upstream {
+                id: blocker.id().to_string(),
+                description: blocker.description().to_string(),
+                state: blocker.state(),
+                result: blocker.result().cloned(),
+                matched_artifacts,
+                shared_terms,
+            }
+        })
+        .collect();
+
+    let mut unresolved: Vec<String> = wanted
+        .into_iter()
+        .filter(|w| !resolved.contains(w))
+        .collect();
+    unresolved.sort();
+
+    let inputs = receives.contains("{{").then(|| {
+        let substituted = substitute(receives, db);
+        unresolved.extend(substituted.unresolved);
+        substituted.text
+    });
+
+    TaskContext {
+        task: task.clone(),
+        upstream,
+        inputs,
+        unresolved,
+    }
+}
+
+/// Fill in `{{task:<id>.<field>}}` placeholders from other tasks' results,
+/// where the field is `result` (the summary), `artifacts` (all of them,
+/// space-separated) or `artifacts[N]` (counting from 0). A placeholder
+/// naming an unknown task, or one with no such result yet, is left as
+/// written and reported.
+pub fn substitute(text: &str, db: &Database) -> Substituted {
+    let mut out = String::with_capacity(text.len());
+    let mut unresolved = Vec::new();
+    let mut rest = text;
+    while let Some(start) = rest.find("{{") {
+        let Some(len) = rest[start..].find("}}") else {
+            break;
+        };
+        out.push_str(&rest[..start]);
+        let placeholder = &rest[start..start + len + 2];
+        if let Some(value) = placeholder_value(placeholder[2..len].trim(), db) {
+            out.push_str(&value);
+        } else {
+            out.push_str(placeholder);
+            unresolved.push(placeholder.to_string());
+        }
+        rest = &rest[start + len + 2..];
+    }
+    out.push_str(rest);
+    Substituted {
+        text: out,
+        unresolved,
+    }
+}
+
+fn placeholder_value(reference: &str, db: &Database) -> Option<String> {
+    let (task_id, field) = reference.strip_prefix("task:")?.split_once('.')?;
+    let result = db.get_task(task_id)?.result()?;
+    match field {
+        "result" => Some(result.summary().to_string()),
+        "artifacts" => Some(result.artifacts().join(" ")),
+        _ => {
+            let index: usize = field
+                .strip_prefix("artifacts[")?
+                .strip_suffix(']')?
+                .parse()
+                .ok()?;
+            result.artifacts().get(index).cloned()
+        }
+    }
+}
+
+fn file_name(path: &str) -> &str {
+    path.rsplit(['/', '\\']).next().unwrap_or(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Goal, GoalState, Metrics, ReasonCode, TaskMetrics};
+    use jiff::Timestamp;
+    use rstest::rstest;
+    use tempfile::TempDir;
+
+    fn task(id: &str, receives: &str, produces: &str, blocked_by: &[&str]) -> Task {
+        let now = Timestamp::now();
+        Task::new(
+            id.to_string(),
+            "g1".to_string(),
+            format!("Task {id}"),
+            Some(Contract::new(
+                receives.to_string(),
+                produces.to_string(),
+                "check".to_string(),
+            )),
+            TaskState::Pending,
+            blocked_by.iter().map(ToString::to_string).collect(),
+            now,
+            now,
+        )
+    }
+
+    fn db_with(tasks: Vec<Task>) -> (TempDir, Database) {
+        let dir = TempDir::new().unwrap();
+        let mut db = Database::open(dir.path()).unwrap();
+        let now = Timestamp::now();
+        db.create_goal(Goal::new(
+            "g1".to_string(),
+            None,
+            "goal".to_string(),
+            GoalState::Pending,
+            now,
+            now,
+            None,
+            Metrics::default(),
+        ))
+        .unwrap();
+        for t in tasks {
+            db.create_task(t).unwrap();
+        }
+        (dir, db)
+    }
+
+    // A result artifact matching a receives path is reported on the blocker
+    // and no longer listed as unresolved; the other file stays unresolved.
+    #[rstest]
+    fn matches_result_artifacts_against_receives() {
+        let (_dir, mut db) = db_with(vec![
+            task("a", "spec", "the schema", &[]),
+            task("b", "out/schema.json and config.toml", "client", &["a"]),
+        ]);
+        let base = db.base_path().to_owned();
+        let a = db.get_task_mut("a").unwrap();
+        a.transition(
+            &TaskState::Pending,
+            TaskState::InProgress,
+            ReasonCode::UserRequest,
+        );
+        a.complete(
+            Outcome::new("Wrote schema".to_string(), vec!["schema.json".to_string()]),
+            TaskMetrics::default(),
+            ReasonCode::UserRequest,
+        );
+        a.write_file(&base).unwrap();
+
+        let ctx = resolve(db.get_task("b").unwrap(), &db);
+        assert_eq!(ctx.upstream.len(), 1);
+        assert_eq!(ctx.upstream[0].matched_artifacts, ["schema.json"]);
+        assert_eq!(ctx.upstream[0].shared_terms, ["schema"]);
+        assert_eq!(ctx.unresolved, ["config.toml"]);
+    }
+
+    // Unfinished blockers are still listed, without a result.
+    #[rstest]
+    fn lists_unfinished_blockers() {
+        let (_dir, db) = db_with(vec![
+            task("a", "spec", "api.rs", &[]),
+            task("b", "api.rs", "client", &["a"]),
+        ]);
+
+        let ctx = resolve(db.get_task("b").unwrap(), &db);
+        assert_eq!(ctx.upstream[0].state, TaskState::Pending);
+        assert!(ctx.upstream[0].result.is_none());
+        assert_eq!(ctx.upstream[0].matched_artifacts, ["api.rs"]);
+        assert!(ctx.unresolved.is_empty());
+    }
+
+    // Placeholders are filled from the named task's result; ones that point
+    // past its artifacts, at an unfinished task, or at no task are kept and
+    // reported.
+    #[rstest]
+    fn substitutes_result_placeholders() {
+        let (_dir, mut db) = db_with(vec![
+            task("a", "spec", "schema", &[]),
+            task("b", "spec", "client", &[]),
+        ]);
+        let base = db.base_path().to_owned();
+        let a = db.get_task_mut("a").unwrap();
+        a.transition(
+            &TaskState::Pending,
+            TaskState::InProgress,
+            ReasonCode::UserRequest,
+        );
+        a.complete(
+            Outcome::new(
+                "Wrote schema".to_string(),
+                vec!["schema.json".to_string(), "types.rs".to_string()],
+            ),
+            TaskMetrics::default(),
+            ReasonCode::UserRequest,
+        );
+        a.write_file(&base).unwrap();
+
+        let filled = substitute(
+            "{{task:a.artifacts[1]}} from {{ task:a.result }}; all: {{task:a.artifacts}}",
+            &db,
+        );
+        assert_eq!(
+            filled.text,
+            "types.rs from Wrote schema; all: schema.json types.rs"
+        );
+        assert!(filled.unresolved.is_empty());
+
+        let text = "{{task:a.artifacts[2]}} {{task:b.result}} {{task:zz.result}} {{oops";
+        let filled = substitute(text, &db);
+        assert_eq!(filled.text, text);
+        assert_eq!(
+            filled.unresolved,
+            [
+                "{{task:a.artifacts[2]}}",
+                "{{task:b.result}}",
+                "{{task:zz.result}}"
+            ]
+        );
+    }
+}
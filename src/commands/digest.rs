@@ -0,0 +1,480 @@
+//! `rd digest`: what agents got done over a period, as markdown or HTML,
+//! for a cron job to write to a file or pipe to a mail command. Where it
+//! goes is set under `[digest]` in `config.toml`.
+
+use std::fmt::Write as _;
+use std::io::Write as _;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+
+use anyhow::{Context, anyhow};
+use jiff::{SignedDuration, Timestamp};
+use serde::{Deserialize, Serialize};
+use strum::{AsRefStr, EnumString};
+
+use crate::config::DigestConfig;
+use crate::db::{Database, atomic_write};
+use crate::error::Result;
+use crate::models::{GoalState, TaskState};
+use crate::output::format_duration;
+
+/// How a digest is written.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, AsRefStr, EnumString,
+)]
+#[serde(rename_all = "lowercase")]
+#[strum(serialize_all = "lowercase")]
+pub enum DigestFormat {
+    #[default]
+    Md,
+    Html,
+}
+
+/// Everything that happened between `from` and `to`.
+#[derive(Debug, Serialize)]
+pub struct Digest {
+    pub from: Timestamp,
+    pub to: Timestamp,
+    pub tasks_completed: usize,
+    pub tasks_failed: usize,
+    pub tasks_created: usize,
+    /// Spent by the attempts that ended in the period.
+    pub tokens: i64,
+    pub elapsed_ms: i64,
+    /// Goals with anything to report, in `goal list` order.
+    pub goals: Vec<GoalDigest>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct GoalDigest {
+    pub id: String,
+    pub headline: String,
+    pub state: GoalState,
+    /// Whether the goal was completed in the period.
+    pub finished: bool,
+    pub tasks: usize,
+    pub tasks_completed: usize,
+    pub tasks_created: usize,
+    pub completed: Vec<DigestItem>,
+    pub failed: Vec<DigestItem>,
+}
+
+/// One attempt that ended in the period.
+#[derive(Debug, Serialize)]
+pub struct DigestItem {
+    pub task_id: String,
+    pub headline: String,
+    pub agent: Option<String>,
+    pub at: Timestamp,
+    pub tokens: i64,
+    pub elapsed_ms: i64,
+    /// The first line of the result, or why the attempt failed.
+    pub note: Option<String>,
+}
+
+/// What happened in the `since` before `now`: attempts that ended, tasks
+/// created and goals completed.
+pub fn build(since: SignedDuration, now: Timestamp, db: &Database) -> Digest {
+    let from = now.checked_sub(since).unwrap_or(Timestamp::MIN);
+    let within = |at: Timestamp| at > from && at <= now;
+    let mut digest = Digest {
+        from,
+        to: now,
+        tasks_completed: 0,
+        tasks_failed: 0,
+        tasks_created: 0,
+        tokens: 0,
+        elapsed_ms: 0,
+        goals: Vec::new(),
+    };
+
+    for goal in db.list_goals() {
+        let tasks = db.list_tasks(goal.id());
+        let mut entry = GoalDigest {
+            id: goal.id().to_string(),
+            headline: goal.headline().to_string(),
+            state: goal.state(),
+            finished: goal.completed_at().is_some_and(within),
+            tasks: tasks.len(),
+            tasks_completed: tasks
+                .iter()
+                .filter(|t| t.state() == TaskState::Completed)
+                .count(),
+            tasks_created: tasks.iter().filter(|t| within(t.created_at())).count(),
+            completed: Vec::new(),
+            failed: Vec::new(),
+        };
+        for task in &tasks {
+            for attempt in task.attempts().iter().filter(|a| within(a.ended_at())) {
+                let note = match attempt.outcome() {
+                    TaskState::Completed => attempt.result(),
+                    _ => attempt.reason(),
+                };
+                let item = DigestItem {
+                    task_id: task.id().to_string(),
+                    headline: task.headline().to_string(),
+                    agent: attempt.agent().map(str::to_string),
+                    at: attempt.ended_at(),
+                    tokens: attempt.tokens(),
+                    elapsed_ms: attempt.elapsed_ms(),
+                    note: note
+                        .and_then(|n| n.lines().next())
+                        .map(|n| n.trim().to_string())
+                        .filter(|n| !n.is_empty()),
+                };
+                digest.tokens += item.tokens;
+                digest.elapsed_ms += item.elapsed_ms;
+                if attempt.outcome() == TaskState::Completed {
+                    entry.completed.push(item);
+                } else {
+                    entry.failed.push(item);
+                }
+            }
+        }
+        entry.completed.sort_by_key(|i| i.at);
+        entry.failed.sort_by_key(|i| i.at);
+
+        digest.tasks_completed += entry.completed.len();
+        digest.tasks_failed += entry.failed.len();
+        digest.tasks_created += entry.tasks_created;
+        let active = entry.finished
+            || entry.tasks_created > 0
+            || !entry.completed.is_empty()
+            || !entry.failed.is_empty();
+        if active {
+            digest.goals.push(entry);
+        }
+    }
+    digest
+}
+
+/// `digest` as a markdown or HTML document.
+pub fn render(digest: &Digest, format: DigestFormat) -> String {
+    match format {
+        DigestFormat::Md => markdown(digest),
+        DigestFormat::Html => html(digest),
+    }
+}
+
+fn period(digest: &Digest) -> String {
+    format!(
+        "{} to {} UTC",
+        digest.from.strftime("%Y-%m-%d %H:%M"),
+        digest.to.strftime("%Y-%m-%d %H:%M")
+    )
+}
+
+fn totals(digest: &Digest) -> [String; 2] {
+    [
+        format!(
+            "Tasks: {} completed, {} failed, {} created",
+            digest.tasks_completed, digest.tasks_failed, digest.tasks_created
+        ),
+        format!(
+            "Spent: {} tokens over {} of agent time",
+            digest.tokens,
+            format_duration(SignedDuration::from_millis(digest.elapsed_ms))
+        ),
+    ]
+}
+
+fn goal_heading(goal: &GoalDigest) -> String {
+    let state = if goal.finished {
+        "completed"
+    } else {
+        goal.state.as_ref()
+    };
+    format!(
+        "{} ({}): {state}, {}/{} done",
+        goal.headline, goal.id, goal.tasks_completed, goal.tasks
+    )
+}
+
+fn item_line(item: &DigestItem) -> String {
+    let mut line = format!("{} {}", item.task_id, item.headline);
+    let mut details = Vec::new();
+    if let Some(agent) = &item.agent {
+        details.push(agent.clone());
+    }
+    if item.elapsed_ms > 0 {
+        details.push(format_duration(SignedDuration::from_millis(
+            item.elapsed_ms,
+        )));
+    }
+    if item.tokens > 0 {
+        details.push(format!("{} tokens", item.tokens));
+    }
+    if !details.is_empty() {
+        let _ = write!(line, " ({})", details.join(", "));
+    }
+    if let Some(note) = &item.note {
+        let _ = write!(line, ": {note}");
+    }
+    line
+}
+
+fn markdown(digest: &Digest) -> String {
+    let mut out = String::from("# Radial digest\n\n");
+    let _ = writeln!(out, "{}\n", period(digest));
+    for line in totals(digest) {
+        let _ = writeln!(out, "- {line}");
+    }
+    if digest.goals.is_empty() {
+        out.push_str("\nNothing happened in this period.\n");
+    }
+    for goal in &digest.goals {
+        let _ = writeln!(out, "\n## {}", goal_heading(goal));
+        for (label, items) in [("Completed", &goal.completed), ("Failed", &goal.failed)] {
+            if items.is_empty() {
+                continue;
+            }
+            let _ = writeln!(out, "\n{label}:\n");
+            for item in items {
+                let _ = writeln!(out, "- {}", item_line(item));
+            }
+        }
+    }
+    out
+}
+
+fn html(digest: &Digest) -> String {
+    let mut out = String::from(
+        "<!doctype html>\n<html>\n<head><meta charset=\"utf-8\"><title>Radial digest</title></head>\n<body>\n<h1>Radial digest</h1>\n",
+    );
+    let _ = writeln!(out, "<p>{}</p>", escape(&period(digest)));
+    out.push_str("<ul>\n");
+    for line in totals(digest) {
+        let _ = writeln!(out, "<li>{}</li>", escape(&line));
+    }
+    out.push_str("</ul>\n");
+    if digest.goals.is_empty() {
+        out.push_str("<p>Nothing happened in this period.</p>\n");
+    }
+    for goal in &digest.goals {
+        let _ = writeln!(out, "<h2>{}</h2>", escape(&goal_heading(goal)));
+        for (label, items) in [("Completed", &goal.completed), ("Failed", &goal.failed)] {
+            if items.is_empty() {
+                continue;
+            }
+            let _ = writeln!(out, "<h3>{label}</h3>\n<ul>");
+            for item in items {
+                let _ = writeln!(out, "<li>{}</li>", escape(&item_line(item)));
+            }
+            out.push_str("</ul>\n");
+        }
+    }
+    out.push_str("</body>\n</html>\n");
+    out
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Where a digest went.
+#[derive(Debug, Default, Serialize)]
+pub struct Delivery {
+    pub path: Option<PathBuf>,
+    pub command: Option<String>,
+}
+
+/// Write `text` to the configured file and pipe it to the configured
+/// command. A `{date}` in the path is replaced by `now`'s UTC date, so a
+/// nightly job keeps one file per day; a relative path is taken from the
+/// directory holding `.radial`.
+pub fn deliver(
+    text: &str,
+    config: &DigestConfig,
+    now: Timestamp,
+    db: &Database,
+) -> Result<Delivery> {
+    let mut delivery = Delivery::default();
+    if let Some(path) = &config.path {
+        let path = PathBuf::from(
+            path.to_string_lossy()
+                .replace("{date}", &now.strftime("%Y-%m-%d").to_string()),
+        );
+        let path = match db.project_dir() {
+            Some(dir) if path.is_relative() => dir.join(path),
+            _ => path,
+        };
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create {}", parent.display()))?;
+        }
+        atomic_write(&path, text.as_bytes())?;
+        delivery.path = Some(path);
+    }
+    if let Some(command) = &config.command {
+        pipe(text, command)?;
+        delivery.command = Some(command.clone());
+    }
+    Ok(delivery)
+}
+
+/// Run `command` through the shell with `text` on its stdin.
+fn pipe(text: &str, command: &str) -> Result<()> {
+    let (shell, flag) = if cfg!(windows) {
+        ("cmd", "/C")
+    } else {
+        ("sh", "-c")
+    };
+    let mut child = Command::new(shell)
+        .args([flag, command])
+        .stdin(Stdio::piped())
+        .spawn()
+        .with_context(|| format!("Failed to run digest command: {command}"))?;
+    child
+        .stdin
+        .take()
+        .expect("stdin is piped")
+        .write_all(text.as_bytes())
+        .with_context(|| format!("Failed to send the digest to: {command}"))?;
+    let status = child
+        .wait()
+        .with_context(|| format!("Failed to run digest command: {command}"))?;
+    if !status.success() {
+        return Err(anyhow!("Digest command exited with {status}: {command}").into());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Attempt, Outcome, ReasonCode, TaskMetrics};
+    use crate::testing::{GoalBuilder, TaskBuilder, TempProject};
+    use rstest::rstest;
+
+    /// A moment just ahead, so tasks the test creates fall in the period.
+    fn soon() -> Timestamp {
+        Timestamp::now()
+            .checked_add(SignedDuration::from_mins(1))
+            .unwrap()
+    }
+
+    fn project(now: Timestamp) -> TempProject {
+        let mut project = TempProject::new().unwrap();
+        project
+            .add_goal(GoalBuilder::new("Ship auth").id("g1"))
+            .unwrap();
+        project
+            .add_goal(GoalBuilder::new("Quiet").id("g2"))
+            .unwrap();
+        for id in ["t1", "t2"] {
+            project
+                .add_task(
+                    TaskBuilder::new("g1", format!("Task {id}"))
+                        .id(id)
+                        .state(TaskState::InProgress),
+                )
+                .unwrap();
+        }
+        let base = project.radial_dir();
+        let db = project.db_mut();
+        let hours_ago = |h| now.checked_sub(SignedDuration::from_hours(h)).unwrap();
+
+        let t1 = db.get_task_mut("t1").unwrap();
+        t1.add_attempt(
+            Attempt::new(
+                1,
+                Some("alice".into()),
+                TaskState::Completed,
+                None,
+                hours_ago(2),
+            )
+            .with_spend(5_000, 0, 0, 720_000)
+            .with_result("Wrote <schema>\nmore".into()),
+        );
+        t1.complete(
+            Outcome::new("done".into(), Vec::new()),
+            TaskMetrics::default(),
+            ReasonCode::UserRequest,
+        );
+        t1.write_file(&base).unwrap();
+
+        let t2 = db.get_task_mut("t2").unwrap();
+        t2.add_attempt(
+            Attempt::new(1, None, TaskState::Failed, None, hours_ago(30))
+                .with_failure(None, Some("old".into())),
+        );
+        t2.add_attempt(
+            Attempt::new(2, Some("bob".into()), TaskState::Failed, None, hours_ago(1))
+                .with_spend(1_000, 0, 0, 0)
+                .with_failure(None, Some("flaky tests".into())),
+        );
+        t2.write_file(&base).unwrap();
+        project
+    }
+
+    // Only attempts that ended in the period count, and goals with nothing
+    // to report are left out.
+    #[rstest]
+    fn collects_the_period() {
+        let now = soon();
+        let project = project(now);
+        let digest = build(SignedDuration::from_hours(24), now, project.db());
+
+        assert_eq!((digest.tasks_completed, digest.tasks_failed), (1, 1));
+        assert_eq!(digest.tokens, 6_000);
+        assert_eq!(digest.goals.len(), 1);
+        let g1 = digest.goals.iter().find(|g| g.id == "g1").unwrap();
+        assert_eq!(g1.completed[0].note.as_deref(), Some("Wrote <schema>"));
+        assert_eq!(g1.failed.len(), 1);
+        assert_eq!(g1.failed[0].note.as_deref(), Some("flaky tests"));
+
+        let later = now.checked_add(SignedDuration::from_hours(48)).unwrap();
+        let quiet = build(SignedDuration::from_hours(24), later, project.db());
+        assert!(quiet.goals.is_empty());
+        assert!(render(&quiet, DigestFormat::Md).contains("Nothing happened"));
+    }
+
+    // Both formats carry the same lines; HTML escapes them.
+    #[rstest]
+    fn renders_markdown_and_html() {
+        let now = soon();
+        let project = project(now);
+        let digest = build(SignedDuration::from_hours(24), now, project.db());
+
+        let md = render(&digest, DigestFormat::Md);
+        assert!(
+            md.contains("- Tasks: 1 completed, 1 failed, 2 created"),
+            "{md}"
+        );
+        assert!(md.contains("## Ship auth (g1): pending, 1/2 done"), "{md}");
+        assert!(
+            md.contains("- t1 Task t1 (alice, 12m, 5000 tokens): Wrote <schema>"),
+            "{md}"
+        );
+
+        let html = render(&digest, DigestFormat::Html);
+        assert!(html.contains("<h2>Ship auth (g1): pending, 1/2 done</h2>"));
+        assert!(html.contains("Wrote &lt;schema&gt;"));
+    }
+
+    // The file path's {date} is filled in, and the command gets the text on
+    // its stdin.
+    #[rstest]
+    fn delivers_to_file_and_command() {
+        let project = TempProject::new().unwrap();
+        let dir = project.radial_dir().parent().unwrap().to_path_buf();
+        let config = DigestConfig {
+            path: Some(PathBuf::from("reports/digest-{date}.md")),
+            command: Some(format!("cat > '{}'", dir.join("piped.md").display())),
+            ..DigestConfig::default()
+        };
+        let now: Timestamp = "2026-10-15T06:00:00Z".parse().unwrap();
+
+        let delivery = deliver("# Digest\n", &config, now, project.db()).unwrap();
+        let path = delivery.path.unwrap();
+        assert!(path.ends_with("reports/digest-2026-10-15.md"));
+        assert_eq!(std::fs::read_to_string(path).unwrap(), "# Digest\n");
+        assert_eq!(
+            std::fs::read_to_string(dir.join("piped.md")).unwrap(),
+            "# Digest\n"
+        );
+    }
+}
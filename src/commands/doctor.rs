@@ -0,0 +1,319 @@
+use std::fs;
+use std::path::PathBuf;
+
+use anyhow::Context;
+use serde::Serialize;
+
+use crate::db::Database;
+use crate::error::Result;
+
+/// A reference the store can't resolve.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IssueKind {
+    /// A task is blocked by a task that no longer exists.
+    MissingBlocker,
+    /// A sub-goal's parent is gone and was never recorded as deleted.
+    MissingParent,
+    /// A goal names a milestone that does not exist.
+    MissingMilestone,
+    /// A task file sits in a directory without a `goal.toml`, so it is
+    /// never loaded.
+    OrphanTaskFile,
+    /// A task file's `goal_id` names a different goal than its directory.
+    MisplacedTask,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Issue {
+    pub kind: IssueKind,
+    /// The goal or task the issue is about, or the orphaned file.
+    pub id: String,
+    pub message: String,
+    /// Whether `--fix` repaired it. Orphaned and misplaced files are left
+    /// for a person to move or delete.
+    pub fixed: bool,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct DoctorReport {
+    pub issues: Vec<Issue>,
+}
+
+/// Check that every reference between records resolves: blockers, parent
+/// goals, milestones, and that task files sit under their goal. With `fix`,
+/// dangling blockers and milestones are dropped, and parents are cleared
+/// unless the parent was deleted (and so may come back with `goal restore`).
+pub fn run(fix: bool, db: &mut Database) -> Result<DoctorReport> {
+    let mut report = DoctorReport::default();
+    check_blockers(fix, db, &mut report)?;
+    check_goals(fix, db, &mut report)?;
+    if !db.is_in_memory() {
+        check_files(db, &mut report)?;
+    }
+    Ok(report)
+}
+
+fn check_blockers(fix: bool, db: &mut Database, report: &mut DoctorReport) -> Result<()> {
+    let mut broken: Vec<(String, Vec<String>)> = Vec::new();
+    for task in db.all_tasks() {
+        let missing: Vec<String> = task
+            .blocked_by()
+            .iter()
+            .filter(|id| db.get_task(id).is_none())
+            .cloned()
+            .collect();
+        if !missing.is_empty() {
+            broken.push((task.id().to_string(), missing));
+        }
+    }
+    broken.sort();
+
+    let base = db.base_path().to_path_buf();
+    for (task_id, missing) in broken {
+        if fix {
+            let task = db.get_task_mut(&task_id).unwrap();
+            let kept = task
+                .blocked_by()
+                .iter()
+                .filter(|id| !missing.contains(id))
+                .cloned()
+                .collect();
+            task.set_blocked_by(kept);
+            task.write_file(&base)?;
+        }
+        report
+            .issues
+            .extend(missing.into_iter().map(|blocker| Issue {
+                kind: IssueKind::MissingBlocker,
+                id: task_id.clone(),
+                message: format!("Task {task_id} is blocked by {blocker}, which does not exist"),
+                fixed: fix,
+            }));
+    }
+    Ok(())
+}
+
+fn check_goals(fix: bool, db: &mut Database, report: &mut DoctorReport) -> Result<()> {
+    let mut goal_ids: Vec<String> = db.list_goals().iter().map(|g| g.id().to_string()).collect();
+    goal_ids.sort();
+
+    let base = db.base_path().to_path_buf();
+    for goal_id in goal_ids {
+        let goal = db.get_goal(&goal_id).unwrap();
+        let parent = goal
+            .parent_id()
+            .filter(|p| db.get_goal(p).is_none())
+            .map(|p| (p.to_string(), db.tombstones().goals.contains_key(p)));
+        let milestone = goal
+            .milestone()
+            .filter(|m| db.get_milestone(m).is_none())
+            .map(str::to_string);
+
+        if let Some((parent, deleted)) = &parent {
+            let message = if *deleted {
+                format!(
+                    "Goal {goal_id}'s parent {parent} was deleted; it comes back if the parent is restored"
+                )
+            } else {
+                format!("Goal {goal_id}'s parent {parent} does not exist")
+            };
+            report.issues.push(Issue {
+                kind: IssueKind::MissingParent,
+                id: goal_id.clone(),
+                message,
+                fixed: fix && !deleted,
+            });
+        }
+        if let Some(milestone) = &milestone {
+            report.issues.push(Issue {
+                kind: IssueKind::MissingMilestone,
+                id: goal_id.clone(),
+                message: format!(
+                    "Goal {goal_id} is planned for milestone {milestone}, which does not exist"
+                ),
+                fixed: fix,
+            });
+        }
+
+        let clear_parent = parent.is_some_and(|(_, deleted)| !deleted);
+        if fix && (clear_parent || milestone.is_some()) {
+            let goal = db.get_goal_mut(&goal_id).unwrap();
+            if clear_parent {
+                goal.set_parent_id(None);
+            }
+            if milestone.is_some() {
+                goal.set_milestone(None);
+            }
+            goal.write_file(&base)?;
+        }
+    }
+    Ok(())
+}
+
+/// Task files the loader skips or files under the wrong goal. Only goal
+/// directories are looked at; anything else under `.radial/` is left be.
+fn check_files(db: &Database, report: &mut DoctorReport) -> Result<()> {
+    let base = db.base_path();
+    let mut dirs: Vec<PathBuf> = fs::read_dir(base)
+        .context("Failed to read .radial directory")?
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|p| p.is_dir())
+        .collect();
+    dirs.sort();
+
+    for dir in dirs {
+        let dir_name = dir
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let has_goal = dir.join("goal.toml").exists();
+        let mut files: Vec<PathBuf> = fs::read_dir(&dir)
+            .with_context(|| format!("Failed to read {}", dir.display()))?
+            .filter_map(|e| e.ok().map(|e| e.path()))
+            .filter(|p| p.extension().is_some_and(|ext| ext == "toml"))
+            .filter(|p| p.file_name().is_some_and(|n| n != "goal.toml"))
+            .collect();
+        files.sort();
+
+        for file in files {
+            let Some(task_id) = file.file_stem().map(|s| s.to_string_lossy().into_owned()) else {
+                continue;
+            };
+            if !has_goal {
+                // Directories such as archive/ or history/ never hold goal
+                // tasks; only flag files that parse as tasks.
+                if is_task_file(&file) {
+                    report.issues.push(Issue {
+                        kind: IssueKind::OrphanTaskFile,
+                        id: file.display().to_string(),
+                        message: format!(
+                            "{} has no goal.toml beside it, so task {task_id} is never loaded",
+                            file.display()
+                        ),
+                        fixed: false,
+                    });
+                }
+                continue;
+            }
+            if let Some(task) = db.get_task(&task_id)
+                && task.goal_id() != dir_name
+            {
+                report.issues.push(Issue {
+                    kind: IssueKind::MisplacedTask,
+                    id: task_id.clone(),
+                    message: format!(
+                        "{} belongs to goal {} but sits under goal {dir_name}",
+                        file.display(),
+                        task.goal_id()
+                    ),
+                    fixed: false,
+                });
+            }
+        }
+    }
+    Ok(())
+}
+
+fn is_task_file(path: &std::path::Path) -> bool {
+    fs::read_to_string(path)
+        .ok()
+        .and_then(|text| toml::from_str::<crate::models::Task>(&text).ok())
+        .is_some()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::milestone;
+    use crate::testing::{GoalBuilder, TaskBuilder, TempProject};
+    use rstest::rstest;
+
+    fn kinds(report: &DoctorReport) -> Vec<(IssueKind, &str, bool)> {
+        report
+            .issues
+            .iter()
+            .map(|i| (i.kind, i.id.as_str(), i.fixed))
+            .collect()
+    }
+
+    // Dangling blockers, parents and milestones are reported, and with fix
+    // dropped, except a parent that was deleted and may be restored.
+    #[rstest]
+    fn finds_and_fixes_dangling_references() {
+        let mut project = TempProject::new().unwrap();
+        let due = jiff::Timestamp::now();
+        milestone::add("v1", due, None, project.db_mut()).unwrap();
+        project.add_goal(GoalBuilder::new("Gone").id("g0")).unwrap();
+        project
+            .add_goal(GoalBuilder::new("Ship").id("g1").milestone("v9"))
+            .unwrap();
+        project
+            .add_goal(GoalBuilder::new("Child").id("g2").parent("g0"))
+            .unwrap();
+        project
+            .add_goal(GoalBuilder::new("Stray").id("g3").parent("nope"))
+            .unwrap();
+        project
+            .add_task(TaskBuilder::new("g1", "Build").id("t1").blocked_by("t9"))
+            .unwrap();
+        project.db_mut().delete_goal("g0").unwrap();
+
+        let report = run(false, project.db_mut()).unwrap();
+        assert_eq!(
+            kinds(&report),
+            [
+                (IssueKind::MissingBlocker, "t1", false),
+                (IssueKind::MissingMilestone, "g1", false),
+                (IssueKind::MissingParent, "g2", false),
+                (IssueKind::MissingParent, "g3", false),
+            ]
+        );
+
+        let report = run(true, project.db_mut()).unwrap();
+        assert_eq!(
+            report.issues.iter().map(|i| i.fixed).collect::<Vec<_>>(),
+            [true, true, false, true]
+        );
+        project.reload().unwrap();
+        let db = project.db();
+        assert!(db.get_task("t1").unwrap().blocked_by().is_empty());
+        assert_eq!(db.get_goal("g1").unwrap().milestone(), None);
+        assert_eq!(db.get_goal("g2").unwrap().parent_id(), Some("g0"));
+        assert_eq!(db.get_goal("g3").unwrap().parent_id(), None);
+        assert_eq!(kinds(&run(false, project.db_mut()).unwrap()).len(), 1);
+    }
+
+    // Task files the loader would skip, or that sit under another goal,
+    // are reported but left alone.
+    #[rstest]
+    fn reports_stray_task_files() {
+        let mut project = TempProject::new().unwrap();
+        project.add_goal(GoalBuilder::new("Ship").id("g1")).unwrap();
+        project.add_goal(GoalBuilder::new("Docs").id("g2")).unwrap();
+        let task = project
+            .add_task(TaskBuilder::new("g1", "Build").id("t1"))
+            .unwrap();
+        let text = toml::to_string(&task).unwrap();
+        let radial = project.radial_dir();
+        fs::create_dir(radial.join("lost")).unwrap();
+        fs::write(radial.join("lost").join("t2.toml"), &text).unwrap();
+        fs::rename(
+            radial.join("g1").join("t1.toml"),
+            radial.join("g2").join("t1.toml"),
+        )
+        .unwrap();
+        project.reload().unwrap();
+
+        let report = run(true, project.db_mut()).unwrap();
+        let found: Vec<(IssueKind, bool)> =
+            report.issues.iter().map(|i| (i.kind, i.fixed)).collect();
+        assert_eq!(
+            found,
+            [
+                (IssueKind::MisplacedTask, false),
+                (IssueKind::OrphanTaskFile, false)
+            ]
+        );
+    }
+}
@@ -1,53 +1,249 @@
-use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
 
 use crate::db::Database;
-use crate::models::{Contract, Goal, Task};
+use crate::error::{Error, Result};
+use crate::helpers::{find_cycle, parse_duration, parse_when};
+use crate::models::{Contract, Goal, Priority, Reference, Task};
 
-pub fn goal(goal_id: &str, description: String, db: &mut Database) -> Result<Goal> {
+/// A task's editable fields, as written out for `edit task --interactive`.
+/// Fields left as `None` keep their current value.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TaskDraft {
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub receives: Option<String>,
+    pub produces: Option<String>,
+    pub verify: Option<String>,
+    pub blocked_by: Option<Vec<String>>,
+    pub assignee: Option<String>,
+    pub priority: Option<Priority>,
+    /// A timestamp or a duration from now; empty to lift it.
+    pub not_before: Option<String>,
+    /// A duration such as `45m`; empty to remove it.
+    pub estimate: Option<String>,
+    pub gates: Option<Vec<String>>,
+    /// References as `kind:target`, such as `github:owner/repo#123`.
+    pub refs: Option<Vec<String>>,
+}
+
+impl TaskDraft {
+    /// Every field of the task as it stands, as a starting point for editing.
+    pub fn from_task(task: &Task) -> Self {
+        let contract = task.contract();
+        Self {
+            title: Some(task.title().unwrap_or_default().to_string()),
+            description: Some(task.description().to_string()),
+            receives: contract.map(|c| c.receives().to_string()),
+            produces: contract.map(|c| c.produces().to_string()),
+            verify: contract.map(|c| c.verify().to_string()),
+            blocked_by: Some(task.blocked_by().to_vec()),
+            assignee: task.assignee().map(str::to_string),
+            priority: Some(task.priority()),
+            not_before: task.not_before().map(|at| at.to_string()),
+            estimate: task.estimate().map(|d| format!("{d:#}")),
+            gates: Some(task.gates().to_vec()),
+            refs: Some(task.refs().iter().map(ToString::to_string).collect()),
+        }
+    }
+
+    /// `self` with every field `other` sets replaced.
+    #[must_use]
+    pub fn overlay(self, other: Self) -> Self {
+        Self {
+            title: other.title.or(self.title),
+            description: other.description.or(self.description),
+            receives: other.receives.or(self.receives),
+            produces: other.produces.or(self.produces),
+            verify: other.verify.or(self.verify),
+            blocked_by: other.blocked_by.or(self.blocked_by),
+            assignee: other.assignee.or(self.assignee),
+            priority: other.priority.or(self.priority),
+            not_before: other.not_before.or(self.not_before),
+            estimate: other.estimate.or(self.estimate),
+            gates: other.gates.or(self.gates),
+            refs: other.refs.or(self.refs),
+        }
+    }
+}
+
+/// A new title, project, assignee or time; an empty one removes it.
+fn non_empty(text: String) -> Option<String> {
+    let text = text.trim();
+    (!text.is_empty()).then(|| text.to_string())
+}
+
+pub fn goal(
+    goal_id: &str,
+    description: Option<String>,
+    title: Option<String>,
+    project: Option<String>,
+    db: &mut Database,
+) -> Result<Goal> {
+    db.require_goal_writable(goal_id)?;
     let base = db.base_path().to_path_buf();
-    let goal = db
-        .get_goal_mut(goal_id)
-        .ok_or_else(|| anyhow!("Goal not found: {goal_id}"))?;
+    let goal = db.get_goal_mut(goal_id).unwrap();
 
-    goal.set_description(description);
+    if let Some(description) = description {
+        goal.set_description(description);
+    }
+    if let Some(title) = title {
+        goal.set_title(non_empty(title));
+    }
+    if let Some(project) = project {
+        goal.set_project(non_empty(project));
+    }
     goal.write_file(&base)?;
     Ok(goal.clone())
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn task(
     task_id: &str,
     description: Option<String>,
+    title: Option<String>,
     receives: Option<String>,
     produces: Option<String>,
     verify: Option<String>,
     blocked_by: Option<Vec<String>>,
+    assignee: Option<String>,
+    priority: Option<Priority>,
+    not_before: Option<String>,
+    estimate: Option<String>,
+    gates: Option<Vec<String>>,
+    refs: Option<Vec<String>>,
     db: &mut Database,
 ) -> Result<Task> {
+    let goal_id = db.require_task_writable(task_id)?.goal_id().to_string();
+    if let Some(deps) = &blocked_by {
+        check_blockers(&goal_id, deps, db)?;
+        check_cycle(task_id, deps, db)?;
+    }
+    let not_before = not_before
+        .map(|text| non_empty(text).map(|t| parse_when(&t)).transpose())
+        .transpose()
+        .map_err(Error::conflict)?;
+    let estimate = estimate
+        .map(|text| non_empty(text).map(|t| parse_duration(&t)).transpose())
+        .transpose()
+        .map_err(Error::conflict)?;
+    let refs = refs
+        .map(|refs| {
+            refs.into_iter()
+                .filter_map(non_empty)
+                .map(|r| r.parse::<Reference>())
+                .collect::<std::result::Result<Vec<_>, _>>()
+        })
+        .transpose()
+        .map_err(Error::conflict)?;
     let base = db.base_path().to_path_buf();
-    let task = db
-        .get_task_mut(task_id)
-        .ok_or_else(|| anyhow!("Task not found: {task_id}"))?;
+    let task = db.get_task_mut(task_id).unwrap();
 
     if let Some(desc) = description {
         task.set_description(desc);
     }
 
+    if let Some(title) = title {
+        task.set_title(non_empty(title));
+    }
+
     // Update contract fields, merging with existing values
     if receives.is_some() || produces.is_some() || verify.is_some() {
-        let existing = task.contract();
-        let new_receives = receives
-            .unwrap_or_else(|| existing.map_or(String::new(), |c| c.receives().to_string()));
-        let new_produces = produces
-            .unwrap_or_else(|| existing.map_or(String::new(), |c| c.produces().to_string()));
-        let new_verify =
-            verify.unwrap_or_else(|| existing.map_or(String::new(), |c| c.verify().to_string()));
-        task.set_contract(Contract::new(new_receives, new_produces, new_verify));
+        let contract = merge_contract(task.contract(), receives, produces, verify);
+        task.set_contract(contract);
     }
 
     if let Some(deps) = blocked_by {
         task.set_blocked_by(deps);
     }
 
+    if let Some(agent) = assignee {
+        task.set_assignee(non_empty(agent));
+    }
+
+    if let Some(priority) = priority {
+        task.set_priority(priority);
+    }
+
+    if let Some(not_before) = not_before {
+        task.set_not_before(not_before);
+    }
+
+    if let Some(estimate) = estimate {
+        task.set_estimate(estimate);
+    }
+
+    if let Some(gates) = gates {
+        task.set_gates(gates.into_iter().filter_map(non_empty).collect());
+    }
+
+    if let Some(refs) = refs {
+        task.set_refs(refs);
+    }
+
     task.write_file(&base)?;
     Ok(task.clone())
 }
+
+/// Every blocker must be a task in the same goal, as when creating a task.
+fn check_blockers(goal_id: &str, deps: &[String], db: &Database) -> Result<()> {
+    let existing: Vec<&str> = db.list_tasks(goal_id).iter().map(|t| t.id()).collect();
+    match deps.iter().find(|dep| !existing.contains(&dep.as_str())) {
+        Some(dep) => Err(Error::not_found("blocked-by task", dep, &existing)),
+        None => Ok(()),
+    }
+}
+
+/// Refuse to block `task_id` on `deps` if that closes a loop, showing the
+/// loop with each task's headline so it can be untangled.
+fn check_cycle(task_id: &str, deps: &[String], db: &Database) -> Result<()> {
+    let cycle = find_cycle(task_id, |id| {
+        if id == task_id {
+            deps.to_vec()
+        } else {
+            db.get_task(id)
+                .map(|t| t.blocked_by().to_vec())
+                .unwrap_or_default()
+        }
+    });
+    let Some(cycle) = cycle else {
+        return Ok(());
+    };
+
+    let path: Vec<String> = cycle
+        .iter()
+        .map(|id| match db.get_task(id) {
+            Some(task) => format!("{id} \"{}\"", task.headline()),
+            None => id.clone(),
+        })
+        .collect();
+    let first = &cycle[1];
+    // Edges carry no timestamps, so the newest one is the one this edit adds.
+    let advice = if db
+        .get_task(task_id)
+        .is_some_and(|t| t.blocked_by().contains(first))
+    {
+        "The loop was already in the stored files; remove one of its links".to_string()
+    } else {
+        format!("The newest link, {task_id} → {first}, is the one this edit adds; drop it")
+    };
+    Err(Error::conflict(format!(
+        "Blocking {task_id} on {first} would create a dependency cycle (each task waits on the next):\n  {}\n{advice}",
+        path.join(" → ")
+    )))
+}
+
+/// `existing` with any of the given fields replaced. Fields missing from both
+/// are left empty.
+pub fn merge_contract(
+    existing: Option<&Contract>,
+    receives: Option<String>,
+    produces: Option<String>,
+    verify: Option<String>,
+) -> Contract {
+    Contract::new(
+        receives.unwrap_or_else(|| existing.map_or(String::new(), |c| c.receives().to_string())),
+        produces.unwrap_or_else(|| existing.map_or(String::new(), |c| c.produces().to_string())),
+        verify.unwrap_or_else(|| existing.map_or(String::new(), |c| c.verify().to_string())),
+    )
+}
@@ -0,0 +1,170 @@
+use std::fs::{self, OpenOptions};
+use std::io::{Read, Seek, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use anyhow::Context;
+use fs2::FileExt;
+use jiff::Timestamp;
+use jiff::civil::Date;
+use jiff::tz::TimeZone;
+use serde::Serialize;
+
+use crate::commands::history::{Invocation, LOG_FILE};
+use crate::db::Database;
+use crate::error::Result;
+use crate::history::HISTORY_DIR;
+
+/// How long a `.tmp` file is left alone, since a writer running alongside
+/// `gc` may be about to rename it into place.
+const TEMP_FILE_GRACE: Duration = Duration::from_mins(5);
+
+/// What `gc` removed (or would remove with `--dry-run`).
+#[derive(Debug, Default, Serialize)]
+pub struct GcReport {
+    pub dry_run: bool,
+    pub removed: Vec<PathBuf>,
+    /// Entries dropped from the front of the command log.
+    pub pruned_commands: usize,
+    pub bytes_reclaimed: u64,
+}
+
+/// Remove files left behind by interrupted writes and directories that no
+/// longer hold anything, and history older than `[history] keep_for`.
+/// Live goal and task files are never touched.
+pub fn run(dry_run: bool, db: &Database) -> Result<GcReport> {
+    let mut report = GcReport {
+        dry_run,
+        ..GcReport::default()
+    };
+    if db.is_in_memory() {
+        return Ok(report);
+    }
+
+    collect_temp_files(db.base_path(), &mut report)?;
+    if let Some(keep_for) = db.config().history.keep_for {
+        let cutoff = Timestamp::now() - keep_for;
+        collect_old_changelogs(db.base_path(), cutoff, &mut report)?;
+        prune_command_log(db.base_path(), cutoff, dry_run, &mut report)?;
+    }
+    collect_empty_dirs(db.base_path(), &mut report)?;
+
+    if !dry_run {
+        for path in &report.removed {
+            if path.is_dir() {
+                fs::remove_dir(path)
+            } else {
+                fs::remove_file(path)
+            }
+            .with_context(|| format!("Failed to remove {}", path.display()))?;
+        }
+    }
+
+    Ok(report)
+}
+
+/// `atomic_write` renames its `.tmp` file into place; any that remain are
+/// from a process that died mid-write. Ones younger than
+/// [`TEMP_FILE_GRACE`] may still be in use and are kept.
+fn collect_temp_files(dir: &Path, report: &mut GcReport) -> Result<()> {
+    for entry in fs::read_dir(dir).with_context(|| format!("Failed to read {}", dir.display()))? {
+        let path = entry.context("Failed to read directory entry")?.path();
+        if path.is_dir() {
+            collect_temp_files(&path, report)?;
+        } else if path.extension().is_some_and(|ext| ext == "tmp") {
+            let age = fs::metadata(&path)
+                .and_then(|m| m.modified())
+                .ok()
+                .and_then(|modified| modified.elapsed().ok());
+            if age.is_none_or(|age| age < TEMP_FILE_GRACE) {
+                continue;
+            }
+            report.bytes_reclaimed += fs::metadata(&path).map_or(0, |m| m.len());
+            report.removed.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// Top-level directories that would be empty once temp files are gone.
+fn collect_empty_dirs(base: &Path, report: &mut GcReport) -> Result<()> {
+    for entry in fs::read_dir(base).context("Failed to read .radial directory")? {
+        let path = entry.context("Failed to read directory entry")?.path();
+        if !path.is_dir() {
+            continue;
+        }
+        let mut children = fs::read_dir(&path)
+            .with_context(|| format!("Failed to read {}", path.display()))?
+            .filter_map(|e| e.ok().map(|e| e.path()));
+        if children.all(|child| report.removed.contains(&child)) {
+            report.removed.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// Changelog days (`history/<date>.jsonl`, UTC) that ended before `cutoff`.
+fn collect_old_changelogs(base: &Path, cutoff: Timestamp, report: &mut GcReport) -> Result<()> {
+    let dir = base.join(HISTORY_DIR);
+    let Ok(entries) = fs::read_dir(&dir) else {
+        return Ok(());
+    };
+    let cutoff = cutoff.to_zoned(TimeZone::UTC).date();
+    for entry in entries {
+        let path = entry.context("Failed to read directory entry")?.path();
+        let date = path
+            .file_name()
+            .and_then(|name| name.to_str()?.strip_suffix(".jsonl"))
+            .and_then(|stem| stem.parse::<Date>().ok());
+        if date.is_some_and(|date| date < cutoff) {
+            report.bytes_reclaimed += fs::metadata(&path).map_or(0, |m| m.len());
+            report.removed.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// Drop commands logged before `cutoff` from the front of the command log.
+/// The file is rewritten in place under the same lock `rd` appends with,
+/// so a command finishing meanwhile isn't lost.
+fn prune_command_log(
+    base: &Path,
+    cutoff: Timestamp,
+    dry_run: bool,
+    report: &mut GcReport,
+) -> Result<()> {
+    let path = base.join(LOG_FILE);
+    let Ok(mut file) = OpenOptions::new().read(true).write(true).open(&path) else {
+        return Ok(());
+    };
+    file.lock_exclusive()
+        .context("Failed to acquire file lock")?;
+    let mut text = String::new();
+    file.read_to_string(&mut text)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+
+    // The log is in order, so everything before the first recent entry
+    // goes, including lines that don't parse.
+    let keep_from = text
+        .split_inclusive('\n')
+        .scan(0, |offset, line| {
+            let start = *offset;
+            *offset += line.len();
+            Some((start, line))
+        })
+        .find(|(_, line)| {
+            serde_json::from_str::<Invocation>(line).is_ok_and(|entry| entry.at >= cutoff)
+        })
+        .map_or(text.len(), |(start, _)| start);
+    report.pruned_commands = text[..keep_from].lines().count();
+    report.bytes_reclaimed += keep_from as u64;
+
+    if !dry_run && keep_from > 0 {
+        file.set_len(0)
+            .and_then(|()| file.rewind())
+            .and_then(|()| file.write_all(&text.as_bytes()[keep_from..]))
+            .with_context(|| format!("Failed to rewrite {}", path.display()))?;
+    }
+    file.unlock().context("Failed to unlock file")?;
+    Ok(())
+}
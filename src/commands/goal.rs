@@ -1,27 +1,845 @@
-use anyhow::Result;
-use jiff::Timestamp;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
 
-use crate::db::Database;
+use anyhow::Context;
+use jiff::{SignedDuration, Timestamp, Unit};
+use serde::{Deserialize, Serialize};
+
+use crate::commands::clean::{outside_references, referenced_error};
+use crate::commands::{ready, task};
+use crate::db::{AGENT_ENV, Database, atomic_write};
+use crate::error::{Error, Result};
+use crate::gzip;
 use crate::id::generate_id;
-use crate::models::{Goal, GoalState, Metrics};
+use crate::models::{Claim, Criterion, Goal, GoalState, Metrics, Task, TaskState};
+use crate::sandbox::{self, Run};
+
+/// Directory under `.radial/` that archived goals are written to.
+pub const ARCHIVE_DIR: &str = "archive";
+
+pub fn create(
+    description: String,
+    title: Option<String>,
+    parent_id: Option<String>,
+    branch: Option<String>,
+    project: Option<String>,
+    milestone: Option<String>,
+    db: &mut Database,
+) -> Result<Goal> {
+    if let Some(parent_id) = &parent_id {
+        db.require_goal(parent_id)?;
+    }
+    if let Some(milestone) = &milestone {
+        db.require_milestone(milestone)?;
+    }
 
-pub fn create(description: String, db: &mut Database) -> Result<Goal> {
     let now = Timestamp::now();
     let goal = Goal::new(
-        generate_id(),
-        None,
+        generate_id(db.config().ids.alphabet),
+        parent_id,
         description,
         GoalState::Pending,
         now,
         now,
         None,
         Metrics::default(),
-    );
+    )
+    .with_title(title.filter(|t| !t.trim().is_empty()))
+    .with_branch(branch)
+    .with_project(project.filter(|p| !p.trim().is_empty()))
+    .with_milestone(milestone);
 
     db.create_goal(goal.clone())?;
     Ok(goal)
 }
 
-pub fn list(db: &Database) -> Vec<Goal> {
-    db.list_goals().into_iter().cloned().collect()
+/// All goals, or only those labelled `project`.
+pub fn list(project: Option<&str>, db: &Database) -> Vec<Goal> {
+    db.list_goals()
+        .into_iter()
+        .filter(|g| project.is_none_or(|p| g.project() == Some(p)))
+        .cloned()
+        .collect()
+}
+
+/// Give `agent` (by default the one running the command) sole use of a
+/// goal's tasks for `ttl`. Other agents' changes to them are refused (see
+/// [`Database::check_claim`]) until the claim is released or runs out. The
+/// holder can claim again to extend it.
+pub fn claim(
+    goal_id: &str,
+    agent: Option<String>,
+    ttl: SignedDuration,
+    db: &mut Database,
+) -> Result<Goal> {
+    db.require_goal(goal_id)?;
+    let Some(agent) = agent.or_else(|| db.agent().map(str::to_string)) else {
+        return Err(Error::conflict(format!(
+            "Name the agent claiming the goal with --agent or {AGENT_ENV}"
+        )));
+    };
+    if ttl <= SignedDuration::ZERO {
+        return Err(Error::conflict(
+            "A claim needs a positive --ttl, such as 2h",
+        ));
+    }
+    db.check_claim(goal_id, Some(&agent))?;
+
+    let expires_at = (Timestamp::now() + ttl)
+        .round(Unit::Second)
+        .context("Claim expiry is out of range")?;
+    let base = db.base_path().to_path_buf();
+    let goal = db.get_goal_mut(goal_id).unwrap();
+    goal.set_claim(Some(Claim::new(agent, expires_at)));
+    goal.write_file(&base)?;
+    Ok(goal.clone())
+}
+
+/// End the claim on a goal. Only its holder may, unless `force`; releasing
+/// a goal nobody holds does nothing.
+pub fn release(goal_id: &str, agent: Option<&str>, force: bool, db: &mut Database) -> Result<Goal> {
+    db.require_goal(goal_id)?;
+    if !force {
+        db.check_claim(goal_id, agent.or(db.agent()))?;
+    }
+
+    let base = db.base_path().to_path_buf();
+    let goal = db.get_goal_mut(goal_id).unwrap();
+    if goal.claim(Timestamp::now()).is_some() {
+        goal.set_claim(None);
+        goal.write_file(&base)?;
+    }
+    Ok(goal.clone())
+}
+
+pub fn criterion_add(
+    goal_id: &str,
+    text: String,
+    command: Option<String>,
+    db: &mut Database,
+) -> Result<Goal> {
+    db.require_goal_writable(goal_id)?;
+
+    let base = db.base_path().to_path_buf();
+    let goal = db.get_goal_mut(goal_id).unwrap();
+    goal.add_criterion(Criterion::new(
+        text,
+        command.filter(|c| !c.trim().is_empty()),
+    ));
+    goal.write_file(&base)?;
+    Ok(goal.clone())
+}
+
+/// Mark a hand-checked criterion met, or not met with `undo`. Criteria with
+/// a command are only met by `verify`.
+pub fn criterion_tick(goal_id: &str, number: usize, undo: bool, db: &mut Database) -> Result<Goal> {
+    db.require_goal_writable(goal_id)?;
+
+    let base = db.base_path().to_path_buf();
+    let goal = db.get_goal_mut(goal_id).unwrap();
+    let count = goal.criteria().len();
+    let Some(criterion) = goal.criterion_mut(number) else {
+        return Err(Error::conflict(format!(
+            "Goal {goal_id} has no criterion {number} (it has {count})"
+        )));
+    };
+    if criterion.command().is_some() {
+        return Err(Error::conflict(format!(
+            "Criterion {number} is checked by its command. Run: rd goal verify {goal_id}"
+        )));
+    }
+    criterion.set_met(!undo);
+    goal.touch();
+    goal.write_file(&base)?;
+    Ok(goal.clone())
+}
+
+/// One criterion as `verify` found it.
+#[derive(Debug, Serialize)]
+pub struct CriterionCheck {
+    pub number: usize,
+    #[serde(flatten)]
+    pub criterion: Criterion,
+    /// The command's run, for criteria that have one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub run: Option<Run>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct GoalVerification {
+    pub goal_id: String,
+    pub criteria: Vec<CriterionCheck>,
+    /// Tasks in the goal not yet completed.
+    pub open_task_ids: Vec<String>,
+    /// Whether every criterion is met and every task completed.
+    pub passed: bool,
+    pub goal_state: GoalState,
+}
+
+/// Check a goal's acceptance criteria, running those with a command in `dir`
+/// as `rd task verify` runs a task's. Once every criterion is met and every
+/// task completed, the goal is marked completed.
+pub fn verify(goal_id: &str, dir: Option<&Path>, db: &mut Database) -> Result<GoalVerification> {
+    let goal = db.require_goal_writable(goal_id)?;
+    let commands: Vec<(usize, String)> = goal
+        .criteria()
+        .iter()
+        .enumerate()
+        .filter_map(|(i, c)| Some((i + 1, c.command()?.to_string())))
+        .collect();
+
+    let mut runs = HashMap::new();
+    if !commands.is_empty() {
+        let dir = task::run_dir(dir, db)?;
+        let config = &db.config().sandbox;
+        for (number, command) in commands {
+            let log = sandbox::log_path(goal_id, &format!("criterion-{number}"), db);
+            let run = sandbox::run(&command, &config.wrapper, &dir, &log, config.timeout)?;
+            runs.insert(number, run);
+        }
+    }
+
+    let open_task_ids: Vec<String> = db
+        .list_tasks(goal_id)
+        .into_iter()
+        .filter(|t| t.state() != TaskState::Completed)
+        .map(|t| t.id().to_string())
+        .collect();
+
+    let base = db.base_path().to_path_buf();
+    let goal = db.get_goal_mut(goal_id).unwrap();
+    for (number, run) in &runs {
+        goal.criterion_mut(*number).unwrap().set_met(run.passed);
+    }
+    let passed = goal.criteria_met() && open_task_ids.is_empty();
+    if passed && goal.state() != GoalState::Completed {
+        goal.mark_completed();
+    } else {
+        goal.touch();
+    }
+    goal.write_file(&base)?;
+
+    let criteria = goal
+        .criteria()
+        .iter()
+        .enumerate()
+        .map(|(i, criterion)| CriterionCheck {
+            number: i + 1,
+            criterion: criterion.clone(),
+            run: runs.remove(&(i + 1)),
+        })
+        .collect();
+    Ok(GoalVerification {
+        goal_id: goal_id.to_string(),
+        criteria,
+        open_task_ids,
+        passed,
+        goal_state: goal.state(),
+    })
+}
+
+/// A goal and its tasks, comments included, as written by `archive`.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ArchivedGoal {
+    pub archived_at: Timestamp,
+    pub goal: Goal,
+    pub tasks: Vec<Task>,
+}
+
+/// Where `archive` put a goal.
+#[derive(Debug, Serialize)]
+pub struct ArchiveReport {
+    pub path: PathBuf,
+    pub goal: Goal,
+    pub task_count: usize,
+}
+
+/// Move a goal and its tasks out of the live store into
+/// `.radial/archive/<goal_id>.json.gz`, replacing any earlier archive of it.
+/// Sub-goals must be archived first. The removal is recorded like a delete,
+/// so `sync` carries it to other stores; `restore` brings the goal back.
+pub fn archive(goal_id: &str, db: &mut Database) -> Result<ArchiveReport> {
+    let goal = db.require_goal_writable(goal_id)?.clone();
+    if db.is_in_memory() {
+        return Err(Error::conflict("Archiving needs a store on disk"));
+    }
+    let children: Vec<&str> = db
+        .list_goals()
+        .into_iter()
+        .filter(|g| g.parent_id() == Some(goal_id))
+        .map(Goal::id)
+        .collect();
+    if !children.is_empty() {
+        return Err(Error::conflict(format!(
+            "Goal {goal_id} has sub-goals; archive them first: {}",
+            children.join(", ")
+        )));
+    }
+
+    let references = outside_references(&[goal_id], db);
+    if !references.is_empty() {
+        return Err(referenced_error(
+            &references,
+            "Complete or re-plan those tasks before archiving.",
+        ));
+    }
+
+    let archived = ArchivedGoal {
+        archived_at: Timestamp::now(),
+        tasks: db.list_tasks(goal_id).into_iter().cloned().collect(),
+        goal,
+    };
+    let dir = db.base_path().join(ARCHIVE_DIR);
+    fs::create_dir_all(&dir).context("Failed to create archive directory")?;
+    let path = dir.join(format!("{goal_id}.json.gz"));
+    let content = serde_json::to_vec(&archived).context("Failed to serialize goal")?;
+    atomic_write(&path, &gzip::compress(&content))?;
+
+    db.delete_goal(goal_id)?;
+    Ok(ArchiveReport {
+        path,
+        task_count: archived.tasks.len(),
+        goal: archived.goal,
+    })
+}
+
+/// Put an archived goal and its tasks back into the live store. Fails if
+/// any of them already exist. The archive file is left in place. Plain JSON
+/// archives from before compression are still accepted.
+pub fn restore(path: &Path, db: &mut Database) -> Result<ArchivedGoal> {
+    let mut content =
+        fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    if gzip::is_gzip(&content) {
+        content = gzip::decompress(&content)
+            .with_context(|| format!("Failed to decompress {}", path.display()))?;
+    }
+    let archived: ArchivedGoal = serde_json::from_slice(&content)
+        .with_context(|| format!("Failed to parse {}", path.display()))?;
+
+    if db.get_goal(archived.goal.id()).is_some() {
+        return Err(Error::conflict(format!(
+            "Goal already exists: {}",
+            archived.goal.id()
+        )));
+    }
+    if let Some(task) = archived
+        .tasks
+        .iter()
+        .find(|t| db.get_task(t.id()).is_some())
+    {
+        return Err(Error::conflict(format!(
+            "Task already exists: {}",
+            task.id()
+        )));
+    }
+
+    db.unbury_goal(archived.goal.id())?;
+    db.put_goal(archived.goal.clone())?;
+    for task in &archived.tasks {
+        db.put_task(task.clone())?;
+    }
+    // The stored blocked states may be stale; go by the blockers instead.
+    task::settle_blocked(archived.goal.id(), db)?;
+    let tasks = db
+        .list_tasks(archived.goal.id())
+        .into_iter()
+        .cloned()
+        .collect();
+    Ok(ArchivedGoal { tasks, ..archived })
+}
+
+/// What `reopen` did.
+#[derive(Debug, Serialize)]
+pub struct ReopenReport {
+    pub goal: Goal,
+    /// Failed tasks put back to pending, or to blocked if what they wait on
+    /// is not done.
+    pub reset_task_ids: Vec<String>,
+    /// Of those, the ones still waiting on a blocker.
+    pub blocked_task_ids: Vec<String>,
+    /// Tasks ready to pick up now.
+    pub ready_task_ids: Vec<String>,
+}
+
+/// Put a completed or failed goal back in progress. With `reset_failed`,
+/// its failed tasks go back to pending too, keeping their attempts and
+/// metrics, and any whose blockers are unfinished become blocked; this also
+/// works on a goal still in progress with failed tasks.
+pub fn reopen(goal_id: &str, reset_failed: bool, db: &mut Database) -> Result<ReopenReport> {
+    let finished = matches!(
+        db.require_goal_writable(goal_id)?.state(),
+        GoalState::Completed | GoalState::Failed
+    );
+    let failed: Vec<String> = db
+        .list_tasks(goal_id)
+        .iter()
+        .filter(|t| reset_failed && t.state() == TaskState::Failed)
+        .map(|t| t.id().to_string())
+        .collect();
+    // A goal whose tasks failed may still be in progress, since failing a
+    // task doesn't end its goal; resetting them is all there is to do.
+    if !finished && failed.is_empty() {
+        return Err(Error::conflict(format!(
+            "Goal {goal_id} is {} and has no failed tasks to reset; only completed or failed goals can be reopened",
+            db.get_goal(goal_id).unwrap().state().as_ref()
+        )));
+    }
+
+    let base = db.base_path().to_path_buf();
+    let reason = db.reason();
+    for id in &failed {
+        let task = db.get_task_mut(id).unwrap();
+        task.reset(reason);
+        task.write_file(&base)?;
+    }
+    let settled = task::settle_blocked(goal_id, db)?;
+    let blocked_task_ids = failed
+        .iter()
+        .filter(|id| settled.contains(id))
+        .cloned()
+        .collect();
+
+    let goal = db.get_goal_mut(goal_id).unwrap();
+    goal.reopen();
+    goal.write_file(&base)?;
+    let goal = goal.clone();
+
+    let ready_task_ids = ready::run(goal_id, false, false, None, db)?
+        .into_iter()
+        .map(|r| r.task.id().to_string())
+        .collect();
+    Ok(ReopenReport {
+        goal,
+        reset_task_ids: failed,
+        blocked_task_ids,
+        ready_task_ids,
+    })
+}
+
+/// What `merge` did.
+#[derive(Debug, Serialize)]
+pub struct MergeReport {
+    /// The goal merged into, as it is now.
+    pub goal: Goal,
+    pub merged_goal_id: String,
+    /// Tasks moved over, in their new order.
+    pub moved_task_ids: Vec<String>,
+    /// Sub-goals of the merged goal, now under `goal`.
+    pub reparented_goal_ids: Vec<String>,
+    /// Where the emptied goal was archived.
+    pub archive_path: PathBuf,
+}
+
+/// Fold goal `from` into goal `into`: move its tasks after `into`'s own,
+/// keeping their IDs so every dependency still resolves and links between
+/// the two goals become links within one; append its description and
+/// acceptance criteria; move its sub-goals under `into`; then archive the
+/// emptied goal so it can be looked up or restored.
+pub fn merge(into: &str, from: &str, db: &mut Database) -> Result<MergeReport> {
+    if into == from {
+        return Err(Error::conflict("Cannot merge a goal into itself"));
+    }
+    db.require_goal_writable(into)?;
+    let source = db.require_goal_writable(from)?.clone();
+    if db.is_in_memory() {
+        return Err(Error::conflict("Merging needs a store on disk"));
+    }
+    // Bounded by the goal count in case a hand edit made the parents loop.
+    let mut ancestor = db.get_goal(into).unwrap().parent_id();
+    for _ in 0..db.list_goals().len() {
+        let Some(id) = ancestor else { break };
+        if id == from {
+            return Err(Error::conflict(format!(
+                "Goal {into} is a sub-goal of {from}; merge {from} into {into}'s parent, or the other way round"
+            )));
+        }
+        ancestor = db.get_goal(id).and_then(Goal::parent_id);
+    }
+
+    let base = db.base_path().to_path_buf();
+    let reparented_goal_ids: Vec<String> = db
+        .list_goals()
+        .into_iter()
+        .filter(|g| g.parent_id() == Some(from))
+        .map(|g| g.id().to_string())
+        .collect();
+    for id in &reparented_goal_ids {
+        let child = db.get_goal_mut(id).unwrap();
+        child.set_parent_id(Some(into.to_string()));
+        child.write_file(&base)?;
+    }
+
+    let first = db
+        .list_tasks(into)
+        .iter()
+        .map(|t| t.order() + 1)
+        .max()
+        .unwrap_or_default();
+    let moved_task_ids: Vec<String> = db
+        .list_tasks(from)
+        .iter()
+        .map(|t| t.id().to_string())
+        .collect();
+    for (order, id) in (first..).zip(&moved_task_ids) {
+        db.move_task(id, into, order)?;
+    }
+
+    let open = db
+        .list_tasks(into)
+        .iter()
+        .any(|t| t.state() != TaskState::Completed);
+    let goal = db.get_goal_mut(into).unwrap();
+    goal.set_description(format!(
+        "{}\n\nMerged from goal {from}:\n{}",
+        goal.description().trim_end(),
+        source.description()
+    ));
+    for criterion in source.criteria() {
+        goal.add_criterion(criterion.clone());
+    }
+    if goal.project().is_none() {
+        goal.set_project(source.project().map(str::to_string));
+    }
+    if goal.milestone().is_none() {
+        goal.set_milestone(source.milestone().map(str::to_string));
+    }
+    if open && goal.state() != GoalState::InProgress {
+        goal.mark_in_progress();
+    }
+    goal.write_file(&base)?;
+    task::settle_blocked(into, db)?;
+
+    let archived = archive(from, db)?;
+    Ok(MergeReport {
+        goal: db.get_goal(into).unwrap().clone(),
+        merged_goal_id: from.to_string(),
+        moved_task_ids,
+        reparented_goal_ids,
+        archive_path: archived.path,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{GoalBuilder, TaskBuilder, TempProject};
+    use rstest::rstest;
+
+    // Archiving takes the goal and its tasks out of the store and restoring
+    // brings back the same records, after which the goal is no longer
+    // tombstoned.
+    #[rstest]
+    fn archive_then_restore_round_trips() {
+        let mut project = TempProject::new().unwrap();
+        project.add_goal(GoalBuilder::new("Ship").id("g1")).unwrap();
+        project
+            .add_task(
+                TaskBuilder::new("g1", "Done work")
+                    .id("t1")
+                    .state(TaskState::Completed),
+            )
+            .unwrap();
+
+        let path = archive("g1", project.db_mut()).unwrap().path;
+        project.reload().unwrap();
+        assert!(project.db().get_goal("g1").is_none());
+        assert!(project.db().get_task("t1").is_none());
+        assert!(project.db().tombstones().goals.contains_key("g1"));
+
+        let restored = restore(&path, project.db_mut()).unwrap();
+        assert_eq!(restored.tasks.len(), 1);
+        project.reload().unwrap();
+        assert_eq!(project.db().list_tasks("g1")[0].id(), "t1");
+        assert!(!project.db().tombstones().goals.contains_key("g1"));
+        assert!(restore(&path, project.db_mut()).is_err());
+    }
+
+    // The archive on disk is gzipped, and archives written as plain JSON
+    // before compression still restore.
+    #[rstest]
+    fn restore_reads_plain_json_archives() {
+        let mut project = TempProject::new().unwrap();
+        project.add_goal(GoalBuilder::new("Ship").id("g1")).unwrap();
+        project
+            .add_task(TaskBuilder::new("g1", "Build").id("t1"))
+            .unwrap();
+
+        let path = archive("g1", project.db_mut()).unwrap().path;
+        let json = gzip::decompress(&fs::read(&path).unwrap()).unwrap();
+        let plain = path.with_file_name("g1.json");
+        fs::write(&plain, json).unwrap();
+
+        project.reload().unwrap();
+        restore(&plain, project.db_mut()).unwrap();
+        project.reload().unwrap();
+        assert_eq!(project.db().list_tasks("g1")[0].id(), "t1");
+    }
+
+    // Blocked states in the file are not trusted: a task whose blocker is
+    // already completed comes back ready, and one whose blocker is not
+    // comes back blocked.
+    #[rstest]
+    fn restore_recomputes_blocked_states() {
+        let mut project = TempProject::new().unwrap();
+        project.add_goal(GoalBuilder::new("Ship").id("g1")).unwrap();
+        project
+            .add_task(
+                TaskBuilder::new("g1", "done")
+                    .id("t1")
+                    .state(TaskState::Completed),
+            )
+            .unwrap();
+        project
+            .add_task(TaskBuilder::new("g1", "open").id("t2"))
+            .unwrap();
+        for (id, blocker) in [("t3", "t1"), ("t4", "t2")] {
+            let state = if id == "t3" {
+                TaskState::Blocked
+            } else {
+                TaskState::Pending
+            };
+            project
+                .add_task(
+                    TaskBuilder::new("g1", "waits")
+                        .id(id)
+                        .state(state)
+                        .blocked_by(blocker),
+                )
+                .unwrap();
+        }
+
+        let path = archive("g1", project.db_mut()).unwrap().path;
+        restore(&path, project.db_mut()).unwrap();
+        project.reload().unwrap();
+        let state = |id| project.db().get_task(id).unwrap().state();
+        assert_eq!(state("t3"), TaskState::Pending);
+        assert_eq!(state("t4"), TaskState::Blocked);
+    }
+
+    // While one agent holds a claim, other agents and unnamed callers are
+    // refused; the holder carries on, and a forced release lets others in.
+    #[rstest]
+    fn claim_locks_out_other_agents() {
+        let mut project = TempProject::new().unwrap();
+        project.add_goal(GoalBuilder::new("Ship").id("g1")).unwrap();
+        project
+            .add_task(TaskBuilder::new("g1", "work").id("t1"))
+            .unwrap();
+        let db = project.db_mut();
+        claim(
+            "g1",
+            Some("alice".into()),
+            SignedDuration::from_hours(2),
+            db,
+        )
+        .unwrap();
+
+        for agent in [Some("bob"), None] {
+            db.set_agent(agent.map(str::to_string));
+            let err = task::comment("t1", "hi".into(), None, db).unwrap_err();
+            assert!(err.to_string().contains("claimed by alice"));
+            assert!(claim("g1", None, SignedDuration::from_hours(1), db).is_err());
+            assert!(release("g1", None, false, db).is_err());
+        }
+
+        db.set_agent(Some("alice".into()));
+        task::comment("t1", "mine".into(), None, db).unwrap();
+
+        db.set_agent(Some("bob".into()));
+        assert!(
+            release("g1", None, true, db)
+                .unwrap()
+                .claim(Timestamp::now())
+                .is_none()
+        );
+        task::comment("t1", "now mine".into(), None, db).unwrap();
+    }
+
+    // A claim that has run out no longer stops anyone.
+    #[rstest]
+    fn expired_claim_is_ignored() {
+        let mut project = TempProject::new().unwrap();
+        project.add_goal(GoalBuilder::new("Ship").id("g1")).unwrap();
+        let past = Timestamp::now() - SignedDuration::from_mins(1);
+        project
+            .db_mut()
+            .get_goal_mut("g1")
+            .unwrap()
+            .set_claim(Some(Claim::new("alice".into(), past)));
+        assert!(project.db().check_claim("g1", Some("bob")).is_ok());
+    }
+
+    // A goal with sub-goals stays put until they are archived.
+    #[rstest]
+    fn archive_refuses_parents() {
+        let mut project = TempProject::new().unwrap();
+        project
+            .add_goal(GoalBuilder::new("Parent").id("g1"))
+            .unwrap();
+        project
+            .add_goal(GoalBuilder::new("Child").id("g2").parent("g1"))
+            .unwrap();
+        assert!(archive("g1", project.db_mut()).is_err());
+        assert!(archive("g2", project.db_mut()).is_ok());
+        assert!(archive("g1", project.db_mut()).is_ok());
+    }
+
+    // Finishing every task leaves a goal with criteria open; it completes
+    // only once verify finds each criterion met, by hand or by command.
+    #[cfg(unix)]
+    #[rstest]
+    fn verify_completes_a_goal_once_criteria_are_met() {
+        let mut project = TempProject::new().unwrap();
+        project.add_goal(GoalBuilder::new("Ship").id("g1")).unwrap();
+        project
+            .add_task(
+                TaskBuilder::new("g1", "work")
+                    .id("t1")
+                    .state(TaskState::InProgress),
+            )
+            .unwrap();
+        let dir = project.path().to_path_buf();
+        let db = project.db_mut();
+        criterion_add("g1", "Reviewed".into(), None, db).unwrap();
+        criterion_add("g1", "Builds".into(), Some("true".into()), db).unwrap();
+
+        let done = task::complete(
+            "t1",
+            "Done".into(),
+            None,
+            task::Usage::default(),
+            Vec::new(),
+            db,
+        )
+        .unwrap();
+        assert_ne!(done.goal_state, GoalState::Completed);
+
+        let first = verify("g1", Some(&dir), db).unwrap();
+        assert!(!first.passed);
+        assert!(first.criteria[1].criterion.met());
+        assert!(criterion_tick("g1", 2, false, db).is_err());
+
+        criterion_tick("g1", 1, false, db).unwrap();
+        let second = verify("g1", Some(&dir), db).unwrap();
+        assert!(second.passed);
+        assert_eq!(second.goal_state, GoalState::Completed);
+    }
+
+    // Merging moves tasks after the kept goal's own, so links between the
+    // two resolve within one goal and a blocked task is released; sub-goals
+    // follow, and the emptied goal is archived.
+    #[rstest]
+    fn merge_moves_tasks_and_archives_source() {
+        let mut project = TempProject::new().unwrap();
+        project.add_goal(GoalBuilder::new("Auth").id("g1")).unwrap();
+        project
+            .add_goal(GoalBuilder::new("Login page").id("g2").milestone("v1"))
+            .unwrap();
+        project
+            .add_goal(GoalBuilder::new("Styles").id("g3").parent("g2"))
+            .unwrap();
+        project
+            .add_task(
+                TaskBuilder::new("g1", "Schema")
+                    .id("t1")
+                    .state(TaskState::Completed),
+            )
+            .unwrap();
+        project
+            .add_task(
+                TaskBuilder::new("g2", "Form")
+                    .id("t2")
+                    .state(TaskState::Blocked)
+                    .blocked_by("t1"),
+            )
+            .unwrap();
+        project
+            .add_task(TaskBuilder::new("g2", "Copy").id("t3"))
+            .unwrap();
+        criterion_add("g2", "Reviewed".into(), None, project.db_mut()).unwrap();
+
+        let report = merge("g1", "g2", project.db_mut()).unwrap();
+        assert_eq!(report.moved_task_ids, ["t2", "t3"]);
+        assert_eq!(report.reparented_goal_ids, ["g3"]);
+        assert!(report.archive_path.exists());
+
+        project.reload().unwrap();
+        let db = project.db();
+        assert!(db.get_goal("g2").is_none());
+        let order: Vec<(&str, TaskState)> = db
+            .list_tasks("g1")
+            .iter()
+            .map(|t| (t.id(), t.state()))
+            .collect();
+        assert_eq!(
+            order,
+            [
+                ("t1", TaskState::Completed),
+                ("t2", TaskState::Pending),
+                ("t3", TaskState::Pending)
+            ]
+        );
+        let goal = db.get_goal("g1").unwrap();
+        assert!(
+            goal.description()
+                .contains("Merged from goal g2:\nLogin page")
+        );
+        assert_eq!(goal.criteria().len(), 1);
+        assert_eq!(goal.milestone(), Some("v1"));
+        assert_eq!(db.get_goal("g3").unwrap().parent_id(), Some("g1"));
+    }
+
+    // A goal can't be merged into itself or into one of its own sub-goals.
+    #[rstest]
+    fn merge_refuses_loops() {
+        let mut project = TempProject::new().unwrap();
+        project
+            .add_goal(GoalBuilder::new("Parent").id("g1"))
+            .unwrap();
+        project
+            .add_goal(GoalBuilder::new("Child").id("g2").parent("g1"))
+            .unwrap();
+        assert!(merge("g1", "g1", project.db_mut()).is_err());
+        let err = merge("g2", "g1", project.db_mut()).unwrap_err();
+        assert!(err.to_string().contains("sub-goal"), "{err}");
+        assert!(merge("g1", "g2", project.db_mut()).is_ok());
+    }
+
+    // Reopening with --reset-failed puts failed tasks back to pending, or
+    // blocked behind a failed blocker, and reports what is ready.
+    #[rstest]
+    fn reopen_resets_failed_tasks() {
+        let mut project = TempProject::new().unwrap();
+        project
+            .add_goal(GoalBuilder::new("Ship").id("g1").state(GoalState::Failed))
+            .unwrap();
+        let task = |id: &str, state| {
+            TaskBuilder::new("g1", id)
+                .id(id)
+                .state(state)
+                .contract("-", "-", "true")
+        };
+        project.add_task(task("a", TaskState::Failed)).unwrap();
+        project
+            .add_task(task("b", TaskState::Failed).blocked_by("a"))
+            .unwrap();
+        project.add_task(task("c", TaskState::Completed)).unwrap();
+
+        let report = reopen("g1", true, project.db_mut()).unwrap();
+        assert_eq!(report.goal.state(), GoalState::InProgress);
+        assert_eq!(report.reset_task_ids, ["a", "b"]);
+        assert_eq!(report.blocked_task_ids, ["b"]);
+        assert_eq!(report.ready_task_ids, ["a"]);
+
+        project.reload().unwrap();
+        let db = project.db();
+        assert_eq!(db.get_task("b").unwrap().state(), TaskState::Blocked);
+        assert_eq!(db.get_task("c").unwrap().state(), TaskState::Completed);
+        let err = reopen("g1", true, project.db_mut()).unwrap_err();
+        assert!(
+            err.to_string().contains("no failed tasks to reset"),
+            "{err}"
+        );
+    }
 }
@@ -0,0 +1,138 @@
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+
+use anyhow::Context;
+use fs2::FileExt;
+use jiff::Timestamp;
+use serde::{Deserialize, Serialize};
+
+use crate::db::Database;
+use crate::error::{Error, Result};
+
+/// Every `rd` command run against the store, one JSON object per line.
+pub const LOG_FILE: &str = "history.jsonl";
+
+/// One command as it was run.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Invocation {
+    pub at: Timestamp,
+    /// Arguments after `rd`.
+    pub argv: Vec<String>,
+    /// `RADIAL_AGENT` at the time, if set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub agent: Option<String>,
+    pub ok: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// An invocation with its place in the log, counting from 1 for the oldest.
+#[derive(Debug, Serialize)]
+pub struct Numbered {
+    pub number: usize,
+    #[serde(flatten)]
+    pub invocation: Invocation,
+}
+
+/// Append `argv` and how it went to the log.
+pub fn record(argv: &[String], error: Option<String>, db: &Database) -> Result<()> {
+    if db.is_in_memory() {
+        return Ok(());
+    }
+    let invocation = Invocation {
+        at: Timestamp::now(),
+        argv: argv.to_vec(),
+        agent: db.agent().map(str::to_string),
+        ok: error.is_none(),
+        error,
+    };
+    let mut line = serde_json::to_string(&invocation).context("Failed to serialize command")?;
+    line.push('\n');
+
+    let path = db.base_path().join(LOG_FILE);
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .with_context(|| format!("Failed to open {}", path.display()))?;
+    file.lock_exclusive()
+        .context("Failed to acquire file lock")?;
+    file.write_all(line.as_bytes())
+        .with_context(|| format!("Failed to append to {}", path.display()))?;
+    file.unlock().context("Failed to unlock file")?;
+    Ok(())
+}
+
+/// The logged commands, oldest first, keeping the last `limit` if given.
+/// Lines that don't parse, e.g. from a torn write, are skipped but still
+/// counted so numbers stay stable.
+pub fn list(limit: Option<usize>, db: &Database) -> Result<Vec<Numbered>> {
+    let text = match fs::read_to_string(db.base_path().join(LOG_FILE)) {
+        Ok(text) => text,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(e.into()),
+    };
+    let mut entries: Vec<Numbered> = text
+        .lines()
+        .enumerate()
+        .filter_map(|(i, line)| {
+            let invocation = serde_json::from_str(line).ok()?;
+            Some(Numbered {
+                number: i + 1,
+                invocation,
+            })
+        })
+        .collect();
+    if let Some(limit) = limit {
+        entries.drain(..entries.len().saturating_sub(limit));
+    }
+    Ok(entries)
+}
+
+/// Invocation `number` from the log.
+pub fn entry(number: usize, db: &Database) -> Result<Invocation> {
+    list(None, db)?
+        .into_iter()
+        .find(|e| e.number == number)
+        .map(|e| e.invocation)
+        .ok_or_else(|| Error::not_found("history entry", &number.to_string(), &[]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TempProject;
+    use rstest::rstest;
+
+    fn argv(words: &str) -> Vec<String> {
+        words.split(' ').map(str::to_string).collect()
+    }
+
+    // Entries keep their numbers when the list is cut to the last few, and
+    // can be fetched by number for replay.
+    #[rstest]
+    fn records_and_numbers_invocations() {
+        let mut project = TempProject::new().unwrap();
+        project.db_mut().set_agent(Some("bot".to_string()));
+        record(&argv("goal create Ship"), None, project.db()).unwrap();
+        record(
+            &argv("task start x"),
+            Some("Task not found: x".into()),
+            project.db(),
+        )
+        .unwrap();
+        record(&argv("status"), None, project.db()).unwrap();
+
+        let last = list(Some(2), project.db()).unwrap();
+        assert_eq!(last.len(), 2);
+        assert_eq!(last[0].number, 2);
+        assert!(!last[0].invocation.ok);
+        assert_eq!(last[0].invocation.agent.as_deref(), Some("bot"));
+
+        assert_eq!(
+            entry(1, project.db()).unwrap().argv,
+            argv("goal create Ship")
+        );
+        assert!(entry(4, project.db()).is_err());
+    }
+}
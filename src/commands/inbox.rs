@@ -0,0 +1,203 @@
+use std::collections::HashMap;
+
+use jiff::Timestamp;
+use serde::Serialize;
+
+use super::quota;
+use super::status::stale_age;
+use crate::db::Database;
+use crate::gate;
+use crate::models::{Task, TaskState};
+
+/// How many items each group of the digest shows.
+const DIGEST_ITEMS: usize = 3;
+
+/// What needs a human, most urgent first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ItemKind {
+    /// A task failed and hasn't been retried.
+    Failed,
+    /// An agent has spent its daily token budget.
+    OverBudget,
+    /// A task is in `verifying`, waiting to be signed off.
+    AwaitingVerification,
+    /// A task has been in progress longer than `[status] stale_after`.
+    Stale,
+    /// A pending task held back by a gate that is failing.
+    WaitingOnGate,
+}
+
+/// One thing needing attention. `id` is a task ID, or the agent's name for
+/// [`ItemKind::OverBudget`].
+#[derive(Debug, Clone, Serialize)]
+pub struct Item {
+    pub kind: ItemKind,
+    pub id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub goal_id: Option<String>,
+    pub message: String,
+    /// When the task last changed, so the longest-waiting come first.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub since: Option<Timestamp>,
+}
+
+/// The items of one kind, for the digest.
+#[derive(Debug, Serialize)]
+pub struct Group {
+    pub kind: ItemKind,
+    pub count: usize,
+    /// The longest-waiting few.
+    pub items: Vec<Item>,
+}
+
+/// Everything needing attention now, most urgent kind first and the
+/// longest-waiting first within a kind. Worked out from the current state
+/// of the store, so an item disappears once it has been dealt with.
+pub fn run(db: &Database) -> Vec<Item> {
+    let now = Timestamp::now();
+    let threshold = db.config().status.stale_after;
+    let tasks: Vec<&Task> = db.all_tasks().collect();
+
+    let mut items = Vec::new();
+    let mut gated = Vec::new();
+    for task in &tasks {
+        let item = |kind, message: String| Item {
+            kind,
+            id: task.id().to_string(),
+            goal_id: Some(task.goal_id().to_string()),
+            message,
+            since: Some(task.updated_at()),
+        };
+        match task.state() {
+            TaskState::Failed => {
+                let waiting = tasks
+                    .iter()
+                    .filter(|t| t.blocked_by().iter().any(|b| b == task.id()))
+                    .count();
+                let message = if waiting > 0 {
+                    format!("{} ({waiting} waiting on it)", task.headline())
+                } else {
+                    task.headline().to_string()
+                };
+                items.push(item(ItemKind::Failed, message));
+            }
+            TaskState::Verifying => {
+                items.push(item(
+                    ItemKind::AwaitingVerification,
+                    task.headline().to_string(),
+                ));
+            }
+            TaskState::InProgress if stale_age(task, threshold, now).is_some() => {
+                items.push(item(ItemKind::Stale, task.headline().to_string()));
+            }
+            TaskState::Pending
+                if !task.gates().is_empty() && task.contract().is_some() && task.available(now) =>
+            {
+                gated.push(*task);
+            }
+            _ => {}
+        }
+    }
+
+    let results = gate::evaluate(gated.iter().flat_map(|t| t.gates()).map(String::as_str), db);
+    for task in gated {
+        let failing: Vec<&str> = task
+            .gates()
+            .iter()
+            .filter(|g| !results[g.as_str()])
+            .map(String::as_str)
+            .collect();
+        if !failing.is_empty() {
+            items.push(Item {
+                kind: ItemKind::WaitingOnGate,
+                id: task.id().to_string(),
+                goal_id: Some(task.goal_id().to_string()),
+                message: format!("{} (gate: {})", task.headline(), failing.join(", ")),
+                since: Some(task.updated_at()),
+            });
+        }
+    }
+
+    items.extend(
+        quota::run(db)
+            .into_iter()
+            .filter(quota::AgentUsage::exceeded)
+            .map(|usage| Item {
+                kind: ItemKind::OverBudget,
+                message: format!(
+                    "{} tokens today of {}",
+                    usage.tokens_today,
+                    usage.daily_limit.unwrap_or_default()
+                ),
+                id: usage.agent,
+                goal_id: None,
+                since: None,
+            }),
+    );
+
+    items.sort_by_key(|i| (i.kind, i.since));
+    items
+}
+
+/// `items` grouped by kind, keeping the first few of each.
+pub fn digest(items: Vec<Item>) -> Vec<Group> {
+    let mut counts: HashMap<ItemKind, usize> = HashMap::new();
+    let mut groups: Vec<Group> = Vec::new();
+    for item in items {
+        *counts.entry(item.kind).or_default() += 1;
+        match groups.last_mut() {
+            Some(group) if group.kind == item.kind => {
+                if group.items.len() < DIGEST_ITEMS {
+                    group.items.push(item);
+                }
+            }
+            _ => groups.push(Group {
+                kind: item.kind,
+                count: 0,
+                items: vec![item],
+            }),
+        }
+    }
+    for group in &mut groups {
+        group.count = counts[&group.kind];
+    }
+    groups
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{GoalBuilder, TaskBuilder, TempProject};
+    use rstest::rstest;
+
+    // Failures come before verifications, a failure counts what waits on
+    // it, and untroubled tasks stay out of the inbox.
+    #[rstest]
+    fn collects_items_by_urgency() {
+        let mut project = TempProject::new().unwrap();
+        project.add_goal(GoalBuilder::new("Ship").id("g1")).unwrap();
+        let task = |id: &str, state| TaskBuilder::new("g1", id).id(id).state(state);
+        project.add_task(task("t1", TaskState::Verifying)).unwrap();
+        project.add_task(task("t2", TaskState::Failed)).unwrap();
+        project
+            .add_task(task("t3", TaskState::Blocked).blocked_by("t2"))
+            .unwrap();
+        project.add_task(task("t4", TaskState::Pending)).unwrap();
+
+        let items = run(project.db());
+        let kinds: Vec<(ItemKind, &str)> = items.iter().map(|i| (i.kind, i.id.as_str())).collect();
+        assert_eq!(
+            kinds,
+            [
+                (ItemKind::Failed, "t2"),
+                (ItemKind::AwaitingVerification, "t1")
+            ]
+        );
+        assert!(items[0].message.ends_with("(1 waiting on it)"));
+
+        let groups = digest(items);
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups[0].count, 1);
+    }
+}
@@ -0,0 +1,250 @@
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::db::Database;
+use crate::helpers::split_words;
+use crate::models::TaskState;
+
+/// Commands worth replaying from a transcript: the ones that record work.
+/// Queries and store maintenance are left out.
+const REPLAYABLE: &[&[&str]] = &[
+    &["goal", "create"],
+    &["task", "create"],
+    &["task", "start"],
+    &["task", "complete"],
+    &["task", "fail"],
+    &["task", "comment"],
+    &["task", "progress"],
+    &["done"],
+    &["drop"],
+];
+
+/// Words that mark a line mentioning a task ID as reporting it finished.
+const COMPLETION_WORDS: &[&str] = &["completed", "complete", "finished", "done"];
+
+/// Longest result summary taken from a transcript line.
+const MAX_RESULT_CHARS: usize = 200;
+
+/// Where a proposed operation came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Source {
+    /// The agent wrote out a radial command.
+    Command,
+    /// The agent said in prose that an in-progress task was finished.
+    Outcome,
+}
+
+/// A radial command found in a transcript, ready to run.
+#[derive(Debug, Clone, Serialize)]
+pub struct Proposal {
+    /// Line of the transcript it was found on, counting from 1.
+    pub line: usize,
+    pub source: Source,
+    /// Arguments after `rd`.
+    pub args: Vec<String>,
+}
+
+/// Find radial commands written out in `text`, and in-progress tasks it
+/// reports as finished. JSONL transcripts are read message by message;
+/// anything else is read as markdown. Each operation is proposed once, in
+/// the order found.
+pub fn scan(text: &str, db: &Database) -> Vec<Proposal> {
+    let mut proposals: Vec<Proposal> = Vec::new();
+    let (messages, jsonl) = messages(text);
+    for (start, message) in messages {
+        let mut in_fence = false;
+        for (offset, row) in message.lines().enumerate() {
+            let line = if jsonl { start } else { start + offset };
+            let trimmed = row.trim();
+            if trimmed.starts_with("```") {
+                in_fence = !in_fence;
+                continue;
+            }
+            for args in commands_in(trimmed, in_fence) {
+                push(&mut proposals, line, Source::Command, args);
+            }
+            if let Some(args) = outcome_in(trimmed, db) {
+                push(&mut proposals, line, Source::Outcome, args);
+            }
+        }
+    }
+    proposals
+}
+
+fn push(proposals: &mut Vec<Proposal>, line: usize, source: Source, args: Vec<String>) {
+    let same_task = |p: &Proposal| source == Source::Outcome && p.args.get(..3) == args.get(..3);
+    if !proposals.iter().any(|p| p.args == args || same_task(p)) {
+        proposals.push(Proposal { line, source, args });
+    }
+}
+
+/// The text of each message with the line it starts on, and whether the
+/// file is JSONL. A file whose non-empty lines are all JSON objects is
+/// JSONL, and each object's strings are its text; otherwise the whole file
+/// is one markdown message.
+fn messages(text: &str) -> (Vec<(usize, String)>, bool) {
+    let parsed: Option<Vec<(usize, Value)>> = text
+        .lines()
+        .enumerate()
+        .filter(|(_, l)| !l.trim().is_empty())
+        .map(|(i, l)| {
+            serde_json::from_str::<Value>(l)
+                .ok()
+                .filter(Value::is_object)
+                .map(|v| (i + 1, v))
+        })
+        .collect();
+    match parsed {
+        Some(values) if !values.is_empty() => {
+            let messages = values
+                .into_iter()
+                .map(|(line, value)| {
+                    let mut strings = Vec::new();
+                    collect_strings(&value, &mut strings);
+                    (line, strings.join("\n"))
+                })
+                .collect();
+            (messages, true)
+        }
+        _ => (vec![(1, text.to_string())], false),
+    }
+}
+
+fn collect_strings(value: &Value, out: &mut Vec<String>) {
+    match value {
+        Value::String(s) => out.push(s.clone()),
+        Value::Array(items) => items.iter().for_each(|v| collect_strings(v, out)),
+        Value::Object(map) => map.values().for_each(|v| collect_strings(v, out)),
+        _ => {}
+    }
+}
+
+/// Replayable commands on one line: inside code spans, or the whole line
+/// when it is in a code block or a `$` prompt.
+fn commands_in(line: &str, in_fence: bool) -> Vec<Vec<String>> {
+    let mut candidates: Vec<&str> = line.split('`').skip(1).step_by(2).collect();
+    if in_fence || line.starts_with("$ ") {
+        candidates.push(line.trim_start_matches("$ "));
+    }
+    candidates
+        .into_iter()
+        .filter_map(|candidate| {
+            let candidate = candidate.trim();
+            let rest = candidate
+                .strip_prefix("rd ")
+                .or_else(|| candidate.strip_prefix("radial "))?;
+            let mut words = split_words(rest).ok()?;
+            if let Some(comment) = words.iter().position(|w| w.starts_with('#')) {
+                words.truncate(comment);
+            }
+            let replayable = REPLAYABLE.iter().any(|prefix| {
+                words.len() > prefix.len() && words.iter().zip(prefix.iter()).all(|(w, p)| w == p)
+            });
+            let placeholder = words.iter().any(|w| w.starts_with('<') && w.ends_with('>'));
+            (replayable && !placeholder).then_some(words)
+        })
+        .collect()
+}
+
+/// `task complete` for an in-progress task that `line` says is finished,
+/// using the line as the result.
+fn outcome_in(line: &str, db: &Database) -> Option<Vec<String>> {
+    let lower = line.to_lowercase();
+    let words: Vec<&str> = lower
+        .split(|c: char| !(c.is_alphanumeric() || c == '_' || c == '-'))
+        .collect();
+    if !COMPLETION_WORDS.iter().any(|w| words.contains(w)) {
+        return None;
+    }
+    let original: Vec<&str> = line
+        .split(|c: char| !(c.is_alphanumeric() || c == '_' || c == '-'))
+        .collect();
+    let task = original.iter().find_map(|word| {
+        db.get_task(word)
+            .filter(|t| t.state() == TaskState::InProgress)
+    })?;
+    let result: String = line
+        .trim_start_matches(['-', '*', '>', ' '])
+        .chars()
+        .take(MAX_RESULT_CHARS)
+        .collect();
+    Some(vec![
+        "task".to_string(),
+        "complete".to_string(),
+        task.id().to_string(),
+        "--result".to_string(),
+        result,
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{GoalBuilder, TaskBuilder, TempProject};
+    use rstest::rstest;
+
+    fn project() -> TempProject {
+        let mut project = TempProject::new().unwrap();
+        project.add_goal(GoalBuilder::new("Ship").id("g1")).unwrap();
+        project
+            .add_task(
+                TaskBuilder::new("g1", "Auth")
+                    .id("t_auth")
+                    .state(TaskState::InProgress),
+            )
+            .unwrap();
+        project
+    }
+
+    fn args(proposals: &[Proposal]) -> Vec<String> {
+        proposals.iter().map(|p| p.args.join(" ")).collect()
+    }
+
+    // Commands are taken from code spans, fences and prompts; queries,
+    // templates with placeholders and comments are not.
+    #[rstest]
+    fn finds_commands_in_markdown() {
+        let project = project();
+        let text = "I ran `rd task start t_auth` and `rd status`.\n\
+                    ```bash\n\
+                    rd task comment t_auth \"looks good\"  # note\n\
+                    rd task complete <task_id> --result x\n\
+                    ```\n\
+                    $ radial goal create \"Docs\"\n";
+        let proposals = scan(text, project.db());
+        assert_eq!(
+            args(&proposals),
+            vec![
+                "task start t_auth",
+                "task comment t_auth looks good",
+                "goal create Docs"
+            ]
+        );
+        assert_eq!(proposals[2].line, 6);
+    }
+
+    // In a JSONL transcript every string in a message is read, and prose
+    // saying an in-progress task is done proposes completing it.
+    #[rstest]
+    fn finds_outcomes_in_jsonl() {
+        let project = project();
+        let text = r#"{"role": "user", "content": "Please finish the auth task"}
+{"role": "assistant", "content": [{"type": "text", "text": "Finished t_auth: login works."}]}
+{"role": "assistant", "content": "t_auth is done, as said."}"#;
+        let proposals = scan(text, project.db());
+        assert_eq!(proposals.len(), 1);
+        assert_eq!(proposals[0].source, Source::Outcome);
+        assert_eq!(proposals[0].line, 2);
+        assert_eq!(
+            proposals[0].args,
+            [
+                "task",
+                "complete",
+                "t_auth",
+                "--result",
+                "Finished t_auth: login works."
+            ]
+        );
+    }
+}
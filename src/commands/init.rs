@@ -27,6 +27,28 @@ pub fn run(stealth: bool) -> Result<()> {
     Ok(())
 }
 
+/// Create a standalone radial store at `path`, e.g. on a network share, for
+/// several repositories to share via `radial link`.
+pub fn bare(path: &Path) -> Result<()> {
+    let has_entries = fs::read_dir(path).is_ok_and(|mut entries| entries.next().is_some());
+    if has_entries {
+        println!("Radial store already exists at {}", path.display());
+        return Ok(());
+    }
+
+    fs::create_dir_all(path).with_context(|| format!("Failed to create {}", path.display()))?;
+
+    let db = Database::open(path)?;
+    db.init_schema()?;
+
+    println!("Initialized bare radial store in {}", path.display());
+    println!(
+        "Point a repository at it with: radial link {}",
+        path.display()
+    );
+    Ok(())
+}
+
 /// Adds `.radial` to git exclusions.
 /// Prefers `.git/info/exclude` if it exists (truly local), otherwise uses `.gitignore`.
 fn add_to_gitignore() -> Result<()> {
@@ -0,0 +1,41 @@
+use anyhow::{Context, Result, bail};
+use std::fs;
+use std::path::Path;
+
+use crate::db::Database;
+use crate::helpers::expand_redirect_path;
+use crate::{RADIAL_DIR, REDIRECT_FILE};
+
+/// Point this repository's `.radial/` at a shared store by writing a redirect
+/// file. `target` is stored as given so `~` and `$VAR` stay portable.
+pub fn run(target: &str) -> Result<()> {
+    let radial_dir = Path::new(RADIAL_DIR);
+    let cwd = std::env::current_dir().context("Failed to read current directory")?;
+
+    let resolved = expand_redirect_path(target, &cwd);
+    if !resolved.is_dir() {
+        bail!(
+            "No radial store at {}\nCreate one with: radial init --bare {target}",
+            resolved.display()
+        );
+    }
+
+    let redirect_path = radial_dir.join(REDIRECT_FILE);
+    if radial_dir.is_dir() && !redirect_path.is_file() {
+        let local = Database::open(radial_dir)?;
+        let goals = local.list_goals().len();
+        if goals > 0 {
+            bail!(
+                "{RADIAL_DIR} already holds {goals} goal(s); linking would hide them.\nMove them to the shared store first, or remove {RADIAL_DIR}."
+            );
+        }
+    }
+
+    fs::create_dir_all(radial_dir).context("Failed to create .radial directory")?;
+    let relinked = redirect_path.is_file();
+    fs::write(&redirect_path, format!("{target}\n")).context("Failed to write redirect file")?;
+
+    let verb = if relinked { "Relinked" } else { "Linked" };
+    println!("{verb} {RADIAL_DIR} to {}", resolved.display());
+    Ok(())
+}
@@ -1,9 +1,8 @@
 use std::collections::{HashMap, HashSet, VecDeque};
 
-use anyhow::Result;
-
 use crate::db::Database;
-use crate::models::{Goal, Metrics, Task};
+use crate::error::Result;
+use crate::models::{Goal, Metrics, Task, TaskSummary};
 
 pub struct GoalWithTasks {
     pub goal: Goal,
@@ -12,27 +11,74 @@ pub struct GoalWithTasks {
 }
 
 pub fn run(db: &Database) -> Result<Vec<GoalWithTasks>> {
-    let results = db
-        .list_goals()
+    let results = db.list_goals().iter().map(|g| goal(g.id(), db)).collect();
+
+    Ok(results)
+}
+
+/// A single goal with its tasks in dependency order. The goal must exist.
+pub fn goal(goal_id: &str, db: &Database) -> GoalWithTasks {
+    GoalWithTasks {
+        goal: db.get_goal(goal_id).unwrap().clone(),
+        tasks: topo_sort(db.list_tasks(goal_id))
+            .into_iter()
+            .cloned()
+            .collect(),
+        metrics: db.compute_goal_metrics(goal_id),
+    }
+}
+
+/// A goal as `rd list` prints it: the goal and a summary row per task,
+/// borrowed from the store rather than copied.
+pub struct GoalRows<'a> {
+    pub goal: &'a Goal,
+    pub tasks: Vec<TaskSummary<'a>>,
+    pub metrics: Metrics,
+}
+
+/// Every goal with its task rows in dependency order, for the table view.
+pub fn rows(db: &Database) -> Vec<GoalRows<'_>> {
+    db.list_goals()
         .into_iter()
-        .map(|goal| {
-            let tasks = topo_sort(db.list_tasks(goal.id()));
-            let metrics = db.compute_goal_metrics(goal.id());
-            GoalWithTasks {
-                goal: goal.clone(),
-                tasks,
-                metrics,
-            }
+        .map(|goal| GoalRows {
+            goal,
+            tasks: topo_sort(db.list_task_summaries(goal.id())),
+            metrics: db.compute_goal_metrics(goal.id()),
         })
-        .collect();
+        .collect()
+}
 
-    Ok(results)
+/// What [`topo_sort`] needs to know of a task.
+trait Node: Copy {
+    fn id(&self) -> &str;
+    fn blocked_by(&self) -> &[String];
+}
+
+impl Node for &Task {
+    fn id(&self) -> &str {
+        Task::id(self)
+    }
+
+    fn blocked_by(&self) -> &[String] {
+        Task::blocked_by(self)
+    }
+}
+
+impl Node for TaskSummary<'_> {
+    fn id(&self) -> &str {
+        self.id
+    }
+
+    fn blocked_by(&self) -> &[String] {
+        self.blocked_by
+    }
 }
 
 /// Topological sort of tasks by `blocked_by` dependencies.
-/// Tasks with no blockers come first. Falls back to creation order for ties.
-fn topo_sort(tasks: Vec<&Task>) -> Vec<Task> {
-    let task_ids: HashSet<&str> = tasks.iter().map(|t| t.id()).collect();
+/// Tasks with no blockers come first. Falls back to the goal's task order
+/// (explicit `order`, then creation time) for ties.
+fn topo_sort<T: Node>(tasks: Vec<T>) -> Vec<T> {
+    let task_ids: HashSet<&str> = tasks.iter().map(Node::id).collect();
 
     // Build adjacency: for each task, count how many in-graph blockers it has
     let mut in_degree: HashMap<&str, usize> = HashMap::new();
@@ -58,10 +104,10 @@ fn topo_sort(tasks: Vec<&Task>) -> Vec<Task> {
     }
 
     // Kahn's algorithm
-    let mut queue: VecDeque<&str> = in_degree
+    let mut queue: VecDeque<&str> = tasks
         .iter()
-        .filter(|(_, deg)| **deg == 0)
-        .map(|(&id, _)| id)
+        .map(Node::id)
+        .filter(|id| in_degree[id] == 0)
         .collect();
 
     let mut ordered_ids: Vec<&str> = Vec::with_capacity(tasks.len());
@@ -80,10 +126,9 @@ fn topo_sort(tasks: Vec<&Task>) -> Vec<Task> {
     }
 
     // Build lookup and return in topo order
-    let task_map: HashMap<&str, &Task> = tasks.iter().map(|t| (t.id(), *t)).collect();
+    let task_map: HashMap<&str, T> = tasks.iter().map(|t| (t.id(), *t)).collect();
     ordered_ids
         .iter()
-        .filter_map(|id| task_map.get(id))
-        .map(|t| (*t).clone())
+        .filter_map(|id| task_map.get(id).copied())
         .collect()
 }
@@ -0,0 +1,259 @@
+use jiff::Timestamp;
+use serde::Serialize;
+
+use crate::db::Database;
+use crate::error::{Error, Result};
+use crate::models::{GoalState, Milestone, Task, TaskState};
+
+/// Progress towards a milestone: its goals and whether the work left fits
+/// in the time left.
+#[derive(Debug, Serialize)]
+pub struct MilestoneStatus {
+    pub name: String,
+    pub due: Timestamp,
+    pub description: Option<String>,
+    /// Time left until `due`; negative once it has passed.
+    pub remaining_ms: i64,
+    pub tasks: usize,
+    pub tasks_completed: usize,
+    /// Work left across all goals: each task's estimate, or else the
+    /// average time a completed task took. `None` while some task has no
+    /// estimate and none has finished with a recorded time.
+    pub estimated_ms: Option<i64>,
+    /// Whether any goal is at risk.
+    pub at_risk: bool,
+    pub goals: Vec<GoalProgress>,
+}
+
+/// One goal attached to a milestone.
+#[derive(Debug, Serialize)]
+pub struct GoalProgress {
+    pub id: String,
+    pub headline: String,
+    pub state: GoalState,
+    pub tasks: usize,
+    pub tasks_completed: usize,
+    pub estimated_ms: Option<i64>,
+    /// Unfinished, and either past the due date or estimated to need more
+    /// time than is left.
+    pub at_risk: bool,
+}
+
+pub fn add(
+    name: &str,
+    due: Timestamp,
+    description: Option<String>,
+    db: &mut Database,
+) -> Result<MilestoneStatus> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(Error::conflict("Milestone name cannot be empty"));
+    }
+    let description = description.filter(|d| !d.trim().is_empty());
+    db.create_milestone(name, Milestone::new(due, description, Timestamp::now()))?;
+    status(Some(name), db).map(|mut all| all.remove(0))
+}
+
+/// Plan `goal_id` for milestone `name`, or take it off its milestone when
+/// `name` is `None`.
+pub fn attach(goal_id: &str, name: Option<&str>, db: &mut Database) -> Result<MilestoneStatus> {
+    db.require_goal_writable(goal_id)?;
+    let previous = db
+        .get_goal(goal_id)
+        .unwrap()
+        .milestone()
+        .map(str::to_string);
+    let Some(target) = name.or(previous.as_deref()).map(str::to_string) else {
+        return Err(Error::conflict(format!(
+            "Goal {goal_id} is not attached to a milestone"
+        )));
+    };
+    db.require_milestone(&target)?;
+
+    let base = db.base_path().to_path_buf();
+    let goal = db.get_goal_mut(goal_id).unwrap();
+    goal.set_milestone(name.map(str::to_string));
+    goal.write_file(&base)?;
+    status(Some(&target), db).map(|mut all| all.remove(0))
+}
+
+/// Status of milestone `name`, or of every milestone by due date.
+pub fn status(name: Option<&str>, db: &Database) -> Result<Vec<MilestoneStatus>> {
+    let mut milestones: Vec<(&str, &Milestone)> = match name {
+        Some(name) => vec![(name, db.require_milestone(name)?)],
+        None => db
+            .milestones()
+            .iter()
+            .map(|(name, m)| (name.as_str(), m))
+            .collect(),
+    };
+    milestones.sort_by_key(|(name, m)| (m.due(), *name));
+
+    let now = Timestamp::now();
+    Ok(milestones
+        .into_iter()
+        .map(|(name, milestone)| milestone_status(name, milestone, now, db))
+        .collect())
+}
+
+fn milestone_status(
+    name: &str,
+    milestone: &Milestone,
+    now: Timestamp,
+    db: &Database,
+) -> MilestoneStatus {
+    let mut goals: Vec<_> = db
+        .list_goals()
+        .into_iter()
+        .filter(|g| g.milestone() == Some(name))
+        .collect();
+    goals.reverse();
+
+    let tasks: Vec<&Task> = goals.iter().flat_map(|g| db.list_tasks(g.id())).collect();
+    let per_task =
+        average_task_ms(tasks.iter().copied()).or_else(|| average_task_ms(db.all_tasks()));
+    let remaining_ms =
+        i64::try_from(milestone.due().duration_since(now).as_millis()).unwrap_or(i64::MAX);
+
+    let goals: Vec<GoalProgress> = goals
+        .into_iter()
+        .map(|goal| {
+            let tasks = db.list_tasks(goal.id());
+            let done = tasks.iter().filter(|t| is_done(t)).count();
+            let estimated_ms = tasks
+                .iter()
+                .filter(|t| !is_done(t))
+                .map(|t| {
+                    t.estimate()
+                        .map(|e| i64::try_from(e.as_millis()).unwrap_or(i64::MAX))
+                        .or(per_task)
+                })
+                .sum::<Option<i64>>();
+            let finished = goal.state() == GoalState::Completed;
+            let at_risk = !finished
+                && (remaining_ms <= 0 || estimated_ms.is_some_and(|ms| ms > remaining_ms));
+            GoalProgress {
+                id: goal.id().to_string(),
+                headline: goal.headline().to_string(),
+                state: goal.state(),
+                tasks: tasks.len(),
+                tasks_completed: done,
+                estimated_ms,
+                at_risk,
+            }
+        })
+        .collect();
+
+    let tasks_completed = tasks.iter().filter(|t| is_done(t)).count();
+    MilestoneStatus {
+        name: name.to_string(),
+        due: milestone.due(),
+        description: milestone.description().map(str::to_string),
+        remaining_ms,
+        tasks: tasks.len(),
+        tasks_completed,
+        estimated_ms: goals.iter().map(|g| g.estimated_ms).sum::<Option<i64>>(),
+        at_risk: goals.iter().any(|g| g.at_risk),
+        goals,
+    }
+}
+
+fn is_done(task: &Task) -> bool {
+    task.state() == TaskState::Completed
+}
+
+/// Mean elapsed time of the completed tasks that recorded one.
+fn average_task_ms<'a>(tasks: impl IntoIterator<Item = &'a Task>) -> Option<i64> {
+    let times: Vec<i64> = tasks
+        .into_iter()
+        .filter(|t| is_done(t))
+        .map(|t| t.metrics().elapsed_ms())
+        .filter(|ms| *ms > 0)
+        .collect();
+    let count = i64::try_from(times.len()).ok().filter(|n| *n > 0)?;
+    Some(times.iter().sum::<i64>() / count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::TaskMetrics;
+    use crate::testing::{GoalBuilder, TaskBuilder, TempProject};
+    use jiff::SignedDuration;
+    use rstest::rstest;
+
+    fn project(due_in: SignedDuration) -> TempProject {
+        let mut project = TempProject::new().unwrap();
+        let due = Timestamp::now().checked_add(due_in).unwrap();
+        add("v1", due, None, project.db_mut()).unwrap();
+        for (goal, tasks_left) in [("g1", 1), ("g2", 3)] {
+            project
+                .add_goal(GoalBuilder::new(goal).id(goal).milestone("v1"))
+                .unwrap();
+            project
+                .add_task(
+                    TaskBuilder::new(goal, "Done")
+                        .state(TaskState::Completed)
+                        .metrics(TaskMetrics::new(0, 3_600_000, 0)),
+                )
+                .unwrap();
+            for _ in 0..tasks_left {
+                project.add_task(TaskBuilder::new(goal, "Left")).unwrap();
+            }
+        }
+        project
+    }
+
+    // With completed tasks taking an hour each, a goal is at risk once its
+    // remaining tasks need more hours than are left before the due date.
+    #[rstest]
+    fn flags_goals_that_will_not_fit() {
+        let project = project(SignedDuration::from_hours(2));
+        let status = status(Some("v1"), project.db()).unwrap().remove(0);
+
+        assert_eq!((status.tasks, status.tasks_completed), (6, 2));
+        assert_eq!(status.estimated_ms, Some(4 * 3_600_000));
+        let risky: Vec<(&str, bool)> = status
+            .goals
+            .iter()
+            .map(|g| (g.id.as_str(), g.at_risk))
+            .collect();
+        assert_eq!(risky, [("g1", false), ("g2", true)]);
+        assert!(status.at_risk);
+    }
+
+    // A task's own estimate is used in place of the average.
+    #[rstest]
+    fn prefers_task_estimates() {
+        let mut project = project(SignedDuration::from_hours(24));
+        project
+            .add_task(TaskBuilder::new("g1", "Quick").estimate(SignedDuration::from_mins(30)))
+            .unwrap();
+        let status = status(Some("v1"), project.db()).unwrap().remove(0);
+        assert_eq!(status.goals[0].estimated_ms, Some(3_600_000 + 1_800_000));
+    }
+
+    // Past the due date, every unfinished goal is at risk.
+    #[rstest]
+    fn overdue_goals_are_at_risk() {
+        let project = project(SignedDuration::from_hours(-1));
+        let status = status(None, project.db()).unwrap().remove(0);
+        assert!(status.remaining_ms < 0);
+        assert!(status.goals.iter().all(|g| g.at_risk));
+    }
+
+    // Attaching needs an existing milestone; detaching clears the goal's link.
+    #[rstest]
+    fn attach_and_detach() {
+        let mut project = project(SignedDuration::from_hours(24));
+        project.add_goal(GoalBuilder::new("g3").id("g3")).unwrap();
+        assert!(attach("g3", Some("v2"), project.db_mut()).is_err());
+
+        let status = attach("g3", Some("v1"), project.db_mut()).unwrap();
+        assert_eq!(status.goals.len(), 3);
+
+        let status = attach("g3", None, project.db_mut()).unwrap();
+        assert_eq!(status.goals.len(), 2);
+        assert!(attach("g3", None, project.db_mut()).is_err());
+    }
+}
@@ -1,12 +1,39 @@
 #![allow(clippy::needless_pass_by_value)]
 
+pub mod all;
+pub mod bundle;
+pub mod capabilities;
 pub mod clean;
+pub mod context;
+pub mod digest;
+pub mod doctor;
 pub mod edit;
+pub mod gc;
 pub mod goal;
+pub mod history;
+pub mod inbox;
+pub mod ingest;
 pub mod init;
+pub mod link;
 pub mod list;
+pub mod milestone;
+pub mod open;
+pub mod plan;
+pub mod postmortems;
 pub mod prep;
+pub mod projects;
+pub mod quota;
 pub mod ready;
+#[cfg(feature = "web")]
+pub mod serve;
 pub mod show;
+pub mod stats;
 pub mod status;
+pub mod suggest;
+pub mod summary;
+pub mod sync;
 pub mod task;
+pub mod toolspec;
+pub mod validate;
+pub mod watch;
+pub mod wip;
@@ -0,0 +1,248 @@
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+
+use crate::db::Database;
+use crate::error::{Error, Result};
+use crate::export::GOALS_EXPORT_DIR;
+use crate::helpers;
+use crate::models::{Outcome, Task};
+
+/// Where a target came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TargetSource {
+    /// One of the task's references.
+    Ref,
+    /// An artifact its result lists.
+    Artifact,
+    /// The goal's markdown export.
+    Export,
+}
+
+/// How a target is opened.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Opener {
+    Browser,
+    Editor,
+}
+
+/// Something a goal or task points at that can be opened.
+#[derive(Debug, Clone, Serialize)]
+pub struct Target {
+    pub source: TargetSource,
+    /// The reference, artifact or file as recorded.
+    pub label: String,
+    /// A URL for the browser, or a file for the editor.
+    pub location: String,
+    pub opener: Opener,
+}
+
+/// What `id` points at: a task's references that have a web page, and its
+/// artifacts that are URLs or files in the project; a goal's markdown
+/// export, once it has been written.
+pub fn targets(id: &str, db: &Database) -> Result<Vec<Target>> {
+    if let Some(task) = db.get_task(id) {
+        return Ok(task_targets(task, db));
+    }
+    if db.get_goal(id).is_some() {
+        let path = db
+            .base_path()
+            .join(GOALS_EXPORT_DIR)
+            .join(format!("{id}.md"));
+        return Ok(path
+            .is_file()
+            .then(|| file_target(TargetSource::Export, &path.to_string_lossy(), path.clone()))
+            .into_iter()
+            .collect());
+    }
+    let mut ids: Vec<&str> = db.list_goals().iter().map(|g| g.id()).collect();
+    ids.extend(db.all_tasks().map(Task::id));
+    Err(Error::not_found("goal or task", id, &ids))
+}
+
+fn task_targets(task: &Task, db: &Database) -> Vec<Target> {
+    let mut targets: Vec<Target> = task
+        .refs()
+        .iter()
+        .filter_map(|r| {
+            r.url().map(|url| Target {
+                source: TargetSource::Ref,
+                label: r.to_string(),
+                location: url,
+                opener: Opener::Browser,
+            })
+        })
+        .collect();
+    let artifacts = task.result().map(Outcome::artifacts).unwrap_or_default();
+    for artifact in artifacts {
+        let artifact = artifact.trim();
+        if artifact.starts_with("https://") || artifact.starts_with("http://") {
+            targets.push(Target {
+                source: TargetSource::Artifact,
+                label: artifact.to_string(),
+                location: artifact.to_string(),
+                opener: Opener::Browser,
+            });
+            continue;
+        }
+        let path = Path::new(artifact);
+        let path = match db.project_dir() {
+            Some(dir) if path.is_relative() => dir.join(path),
+            _ => path.to_path_buf(),
+        };
+        if path.is_file() {
+            targets.push(file_target(TargetSource::Artifact, artifact, path));
+        }
+    }
+    targets
+}
+
+fn file_target(source: TargetSource, label: &str, path: PathBuf) -> Target {
+    Target {
+        source,
+        label: label.to_string(),
+        location: path.to_string_lossy().into_owned(),
+        opener: Opener::Editor,
+    }
+}
+
+/// The target `which` names, by its number in the list (from 1) or by text
+/// in its label; with no `which`, the only target there is.
+pub fn pick(id: &str, mut targets: Vec<Target>, which: Option<&str>) -> Result<Target> {
+    if targets.is_empty() {
+        return Err(Error::conflict(format!(
+            "Nothing to open for {id}: tasks open their references and their URL or file \
+             artifacts, goals their markdown export (`[export] markdown = true`)"
+        )));
+    }
+    let index = match which {
+        None => (targets.len() == 1).then_some(0),
+        Some(which) => {
+            if let Ok(n) = which.parse::<usize>() {
+                n.checked_sub(1).filter(|i| *i < targets.len())
+            } else {
+                let matching: Vec<usize> = (0..targets.len())
+                    .filter(|i| targets[*i].label.contains(which))
+                    .collect();
+                (matching.len() == 1).then(|| matching[0])
+            }
+        }
+    };
+    if let Some(i) = index {
+        return Ok(targets.swap_remove(i));
+    }
+    let listed: Vec<String> = targets
+        .iter()
+        .enumerate()
+        .map(|(i, t)| format!("  {}. {}", i + 1, t.label))
+        .collect();
+    let problem = match which {
+        Some(which) => format!("'{which}' doesn't pick out one of the targets"),
+        None => format!("There are {} targets", targets.len()),
+    };
+    Err(Error::conflict(format!(
+        "{problem} to open for {id}; name one by number or by text it contains:\n{}",
+        listed.join("\n")
+    )))
+}
+
+/// Open `target` in the browser or editor.
+pub fn launch(target: &Target) -> Result<()> {
+    match target.opener {
+        Opener::Browser => helpers::open_in_browser(&target.location)?,
+        Opener::Editor => helpers::open_in_editor(Path::new(&target.location))?,
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{ReasonCode, TaskMetrics, TaskState};
+    use crate::testing::{GoalBuilder, TaskBuilder, TempProject};
+    use rstest::rstest;
+    use std::fs;
+
+    fn project() -> TempProject {
+        let mut project = TempProject::new().unwrap();
+        project.add_goal(GoalBuilder::new("Ship").id("g1")).unwrap();
+        project
+            .add_task(
+                TaskBuilder::new("g1", "Fix login")
+                    .id("t1")
+                    .state(TaskState::InProgress)
+                    .reference("github:acme/web#412".parse().unwrap())
+                    .reference("jira:WEB-7".parse().unwrap()),
+            )
+            .unwrap();
+        let dir = project.radial_dir().parent().unwrap().to_path_buf();
+        fs::write(dir.join("notes.md"), "# Notes").unwrap();
+        let base = project.radial_dir();
+        let task = project.db_mut().get_task_mut("t1").unwrap();
+        task.complete(
+            Outcome::new(
+                "Fixed".to_string(),
+                vec![
+                    "notes.md".to_string(),
+                    "missing.rs".to_string(),
+                    "https://ci.example.com/runs/9".to_string(),
+                ],
+            ),
+            TaskMetrics::default(),
+            ReasonCode::UserRequest,
+        );
+        task.write_file(&base).unwrap();
+        project
+    }
+
+    // References with a page, URL artifacts and artifacts that exist as
+    // files are offered; a ticket key without a host and a missing file
+    // are not.
+    #[rstest]
+    fn lists_task_targets() {
+        let project = project();
+        let all = targets("t1", project.db()).unwrap();
+        let found: Vec<(TargetSource, &str, Opener)> = all
+            .iter()
+            .map(|t| (t.source, t.label.as_str(), t.opener))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                (TargetSource::Ref, "github:acme/web#412", Opener::Browser),
+                (TargetSource::Artifact, "notes.md", Opener::Editor),
+                (
+                    TargetSource::Artifact,
+                    "https://ci.example.com/runs/9",
+                    Opener::Browser
+                ),
+            ]
+        );
+        assert!(targets("g1", project.db()).unwrap().is_empty());
+        assert!(targets("nope", project.db()).is_err());
+    }
+
+    // Several targets need picking, by number or by text; a single one
+    // doesn't.
+    #[rstest]
+    #[case::number(Some("2"), Ok("notes.md"))]
+    #[case::text(Some("ci.example"), Ok("https://ci.example.com/runs/9"))]
+    #[case::ambiguous(None, Err("There are 3 targets"))]
+    #[case::out_of_range(Some("4"), Err("'4' doesn't pick out one"))]
+    #[case::no_match(Some("gitlab"), Err("'gitlab' doesn't pick out one"))]
+    fn picks_a_target(#[case] which: Option<&str>, #[case] expected: Result<&str, &str>) {
+        let project = project();
+        let found = pick("t1", targets("t1", project.db()).unwrap(), which);
+        match expected {
+            Ok(label) => assert_eq!(found.unwrap().label, label),
+            Err(message) => {
+                let err = found.unwrap_err().to_string();
+                assert!(err.contains(message), "{err}");
+            }
+        }
+        let one = targets("t1", project.db()).unwrap().remove(0);
+        assert_eq!(pick("t1", vec![one], None).unwrap().opener, Opener::Browser);
+    }
+}
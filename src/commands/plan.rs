@@ -0,0 +1,240 @@
+use std::io::{BufRead, Write};
+
+use anyhow::Context;
+use jiff::Timestamp;
+
+use crate::commands::list::{self, GoalWithTasks};
+use crate::commands::{goal, task};
+use crate::db::Database;
+use crate::error::{Error, Result};
+use crate::id::generate_id;
+use crate::models::{Task, TaskState};
+
+/// A goal and its tasks gathered before anything is written.
+#[derive(Debug, Default)]
+pub struct Plan {
+    pub goal: String,
+    pub tasks: Vec<PlannedTask>,
+}
+
+/// A task in a plan. `blocked_by` holds zero-based indexes of earlier tasks.
+#[derive(Debug, Default)]
+pub struct PlannedTask {
+    pub description: String,
+    pub title: Option<String>,
+    pub receives: Option<String>,
+    pub produces: Option<String>,
+    pub verify: Option<String>,
+    pub blocked_by: Vec<usize>,
+}
+
+/// Walk the user through describing a goal and its tasks. Earlier tasks are
+/// referenced as blockers by their 1-based number.
+pub fn prompt(input: &mut dyn BufRead, out: &mut dyn Write) -> Result<Plan> {
+    let mut plan = Plan::default();
+
+    loop {
+        match ask(input, out, "Goal description: ")? {
+            Some(goal) if !goal.is_empty() => {
+                plan.goal = goal;
+                break;
+            }
+            Some(_) => writeln!(out, "A goal description is required.")?,
+            None => return Err(Error::conflict("No goal description given")),
+        }
+    }
+
+    writeln!(out)?;
+    writeln!(
+        out,
+        "Add tasks one at a time. Leave the description blank to finish."
+    )?;
+
+    loop {
+        let number = plan.tasks.len() + 1;
+        writeln!(out)?;
+        let description = match ask(input, out, &format!("Task {number} description: "))? {
+            Some(d) if !d.is_empty() => d,
+            _ => break,
+        };
+
+        let receives = ask(input, out, "  Receives: ")?.filter(|s| !s.is_empty());
+        let produces = ask(input, out, "  Produces: ")?.filter(|s| !s.is_empty());
+        let verify = ask(input, out, "  Verify: ")?.filter(|s| !s.is_empty());
+
+        let blocked_by = if plan.tasks.is_empty() {
+            Vec::new()
+        } else {
+            loop {
+                let answer =
+                    ask(input, out, "  Blocked by (task numbers, e.g. 1,2): ")?.unwrap_or_default();
+                match parse_blockers(&answer, plan.tasks.len()) {
+                    Ok(blockers) => break blockers,
+                    Err(message) => writeln!(out, "  {message}")?,
+                }
+            }
+        };
+
+        plan.tasks.push(PlannedTask {
+            description,
+            title: None,
+            receives,
+            produces,
+            verify,
+            blocked_by,
+        });
+    }
+
+    Ok(plan)
+}
+
+/// Write the plan. If any task fails to save, the goal is removed again so a
+/// half-written plan is never left behind.
+///
+/// The tasks are built in memory first, since their blockers can only be
+/// earlier tasks of the same new goal, then written one file each with the
+/// goal written once at the end. Going through [`task::create`] instead
+/// would rescan the goal for every task, which makes plans of thousands of
+/// tasks slow.
+pub fn create(plan: Plan, db: &mut Database) -> Result<GoalWithTasks> {
+    let goal = goal::create(plan.goal, None, None, None, None, None, db)?;
+
+    let alphabet = db.config().ids.alphabet;
+    let now = Timestamp::now();
+    let mut tasks: Vec<Task> = Vec::with_capacity(plan.tasks.len());
+    for (order, planned) in (0..).zip(plan.tasks) {
+        let blocked_by: Vec<String> = planned
+            .blocked_by
+            .iter()
+            .map(|&i| tasks[i].id().to_string())
+            .collect();
+        let state = if blocked_by.is_empty() {
+            TaskState::Pending
+        } else {
+            TaskState::Blocked
+        };
+        let contract = task::contract_from(planned.receives, planned.produces, planned.verify);
+        tasks.push(
+            Task::new(
+                generate_id(alphabet),
+                goal.id().to_string(),
+                planned.description,
+                contract,
+                state,
+                blocked_by,
+                now,
+                now,
+            )
+            .with_title(planned.title.filter(|t| !t.trim().is_empty()))
+            .with_order(order),
+        );
+    }
+
+    let count = tasks.len();
+    let written = tasks
+        .into_iter()
+        .try_for_each(|task| db.create_task(task))
+        .and_then(|()| {
+            let base = db.base_path().to_path_buf();
+            let goal = db.get_goal_mut(goal.id()).unwrap();
+            if count > 0 {
+                goal.mark_in_progress();
+            }
+            goal.write_file(&base)
+        });
+    if let Err(err) = written {
+        db.discard_goal(goal.id())
+            .context("Failed to roll back partially created plan")?;
+        return Err(err.into());
+    }
+
+    Ok(list::goal(goal.id(), db))
+}
+
+/// Print `question` and read one trimmed line. `None` means input ended.
+fn ask(input: &mut dyn BufRead, out: &mut dyn Write, question: &str) -> Result<Option<String>> {
+    write!(out, "{question}")?;
+    out.flush()?;
+
+    let mut line = String::new();
+    if input.read_line(&mut line)? == 0 {
+        return Ok(None);
+    }
+    Ok(Some(line.trim().to_string()))
+}
+
+/// Parse "1, 3" into zero-based indexes, each referring to one of the
+/// `available` tasks entered so far.
+fn parse_blockers(answer: &str, available: usize) -> std::result::Result<Vec<usize>, String> {
+    let mut blockers = Vec::new();
+    for part in answer.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+        let number: usize = part
+            .parse()
+            .map_err(|_| format!("Not a task number: {part}"))?;
+        if number == 0 || number > available {
+            return Err(format!("Choose a task between 1 and {available}"));
+        }
+        if !blockers.contains(&(number - 1)) {
+            blockers.push(number - 1);
+        }
+    }
+    Ok(blockers)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+    use std::io::Cursor;
+
+    // A full session: blank contract fields become None, blockers are
+    // 1-based in the prompt and 0-based in the plan, and a blank task
+    // description ends the loop.
+    #[rstest]
+    fn prompt_collects_goal_and_tasks() {
+        let mut input =
+            Cursor::new("Ship it\nDesign\nreqs\nspec\nreviewed\nBuild\n\n\n\n1\n\n".as_bytes());
+        let mut out = Vec::new();
+
+        let plan = prompt(&mut input, &mut out).unwrap();
+        assert_eq!(plan.goal, "Ship it");
+        assert_eq!(plan.tasks.len(), 2);
+        assert_eq!(plan.tasks[0].produces.as_deref(), Some("spec"));
+        assert!(plan.tasks[0].blocked_by.is_empty());
+        assert_eq!(plan.tasks[1].receives, None);
+        assert_eq!(plan.tasks[1].blocked_by, [0]);
+    }
+
+    // An invalid blocker answer re-prompts instead of failing the session.
+    #[rstest]
+    fn prompt_reprompts_on_bad_blocker() {
+        let mut input = Cursor::new("Goal\nA\n\n\n\nB\n\n\n\n7\n1\n\n".as_bytes());
+        let mut out = Vec::new();
+
+        let plan = prompt(&mut input, &mut out).unwrap();
+        assert_eq!(plan.tasks[1].blocked_by, [0]);
+        assert!(String::from_utf8(out).unwrap().contains("between 1 and 1"));
+    }
+
+    // Input ending before a goal is given is an error, not an empty plan.
+    #[rstest]
+    fn prompt_requires_goal() {
+        let mut input = Cursor::new("\n".as_bytes());
+        assert!(prompt(&mut input, &mut Vec::new()).is_err());
+    }
+
+    #[rstest]
+    #[case::empty("", 2, Ok(vec![]))]
+    #[case::list("2, 1", 2, Ok(vec![1, 0]))]
+    #[case::duplicate("1,1", 2, Ok(vec![0]))]
+    #[case::out_of_range("3", 2, Err(()))]
+    #[case::zero("0", 2, Err(()))]
+    #[case::not_a_number("a", 2, Err(()))]
+    fn parse_blockers_cases(
+        #[case] answer: &str,
+        #[case] available: usize,
+        #[case] expected: std::result::Result<Vec<usize>, ()>,
+    ) {
+        assert_eq!(parse_blockers(answer, available).map_err(|_| ()), expected);
+    }
+}
@@ -0,0 +1,162 @@
+use std::collections::BTreeMap;
+
+use jiff::Timestamp;
+use serde::Serialize;
+
+use crate::db::Database;
+use crate::error::Result;
+use crate::models::{FailureCategory, TaskState};
+
+/// One failed attempt at a task.
+#[derive(Debug, Serialize)]
+pub struct Failure {
+    pub task_id: String,
+    pub goal_id: String,
+    pub description: String,
+    pub failed_at: Timestamp,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub logs: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub suggested_fix: Option<String>,
+}
+
+/// Failures sharing a category, most recent first. `count` covers every
+/// failure in the category even when `failures` is cut short.
+#[derive(Debug, Serialize)]
+pub struct CategoryGroup {
+    /// `None` for failed tasks nobody wrote a postmortem for.
+    pub category: Option<FailureCategory>,
+    pub count: usize,
+    pub failures: Vec<Failure>,
+}
+
+/// Failures across all goals (or just `goal_id`) grouped by category, the
+/// most common category first, keeping the `limit` most recent of each.
+/// Postmortems from attempts that were later retried are included, so the
+/// counts reflect every failure rather than only tasks still failed.
+pub fn run(goal_id: Option<&str>, limit: usize, db: &Database) -> Result<Vec<CategoryGroup>> {
+    if let Some(id) = goal_id {
+        db.require_goal(id)?;
+    }
+
+    let mut by_category: BTreeMap<Option<FailureCategory>, Vec<Failure>> = BTreeMap::new();
+    for goal in db.list_goals() {
+        if goal_id.is_some_and(|id| id != goal.id()) {
+            continue;
+        }
+        for task in db.list_tasks(goal.id()) {
+            let failure = |failed_at, logs, suggested_fix| Failure {
+                task_id: task.id().to_string(),
+                goal_id: task.goal_id().to_string(),
+                description: task.description().to_string(),
+                failed_at,
+                logs,
+                suggested_fix,
+            };
+            for postmortem in task.postmortems() {
+                by_category
+                    .entry(Some(postmortem.category()))
+                    .or_default()
+                    .push(failure(
+                        postmortem.failed_at(),
+                        postmortem.logs().map(ToString::to_string),
+                        postmortem.suggested_fix().map(ToString::to_string),
+                    ));
+            }
+            if task.state() == TaskState::Failed && task.postmortems().is_empty() {
+                by_category
+                    .entry(None)
+                    .or_default()
+                    .push(failure(task.updated_at(), None, None));
+            }
+        }
+    }
+
+    let mut groups: Vec<CategoryGroup> = by_category
+        .into_iter()
+        .map(|(category, mut failures)| {
+            failures.sort_by_key(|f| std::cmp::Reverse(f.failed_at));
+            let count = failures.len();
+            failures.truncate(limit);
+            CategoryGroup {
+                category,
+                count,
+                failures,
+            }
+        })
+        .collect();
+    groups.sort_by_key(|g| std::cmp::Reverse(g.count));
+    Ok(groups)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Goal, GoalState, Metrics, Postmortem, Task};
+    use rstest::rstest;
+    use tempfile::TempDir;
+
+    fn task(id: &str, state: TaskState, categories: &[FailureCategory]) -> Task {
+        let now = Timestamp::now();
+        let mut task = Task::new(
+            id.to_string(),
+            "g1".to_string(),
+            format!("Task {id}"),
+            None,
+            state,
+            Vec::new(),
+            now,
+            now,
+        );
+        for &category in categories {
+            task.add_postmortem(Postmortem::new(category, None, None, now));
+        }
+        task
+    }
+
+    // Every postmortem counts, including ones from retried attempts; failed
+    // tasks without one are grouped as uncategorized.
+    #[rstest]
+    fn groups_by_category() {
+        let dir = TempDir::new().unwrap();
+        let mut db = Database::open(dir.path()).unwrap();
+        let now = Timestamp::now();
+        db.create_goal(Goal::new(
+            "g1".to_string(),
+            None,
+            "Ship".to_string(),
+            GoalState::InProgress,
+            now,
+            now,
+            None,
+            Metrics::default(),
+        ))
+        .unwrap();
+        for t in [
+            task("a", TaskState::Failed, &[FailureCategory::Spec]),
+            task(
+                "b",
+                TaskState::InProgress,
+                &[FailureCategory::Spec, FailureCategory::Timeout],
+            ),
+            task("c", TaskState::Failed, &[]),
+            task("d", TaskState::Pending, &[]),
+        ] {
+            db.create_task(t).unwrap();
+        }
+
+        let groups = run(None, 1, &db).unwrap();
+        let summary: Vec<(Option<FailureCategory>, usize, usize)> = groups
+            .iter()
+            .map(|g| (g.category, g.count, g.failures.len()))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                (Some(FailureCategory::Spec), 2, 1),
+                (None, 1, 1),
+                (Some(FailureCategory::Timeout), 1, 1),
+            ]
+        );
+    }
+}
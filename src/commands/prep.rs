@@ -1,27 +1,72 @@
-/// Returns the preparation guide for LLM agents using radial.
-pub fn run() -> &'static str {
-    r#"## rd preparation
+//! The guide `rd prep` prints, put together from sections so it can be
+//! cut down for small context windows and leave out commands that
+//! `[commands] disabled` turns off.
+
+use strum::{AsRefStr, EnumString};
+
+use crate::config::Config;
+
+/// Which guide to print.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, AsRefStr, EnumString)]
+#[strum(serialize_all = "lowercase")]
+pub enum PrepVariant {
+    /// Every section, with examples.
+    #[default]
+    Full,
+    /// The core loop in a few lines, for models with a small context window.
+    Short,
+}
+
+/// One part of the guide.
+struct Section {
+    full: &'static str,
+    /// What the short guide keeps, or `None` to leave the section out.
+    short: Option<&'static str>,
+}
+
+const INTRO: &str = "## rd preparation
 
 rd is a task orchestration tool for LLM agents. It tracks goals and tasks with dependencies, letting
-agents work on what's ready.
+agents work on what's ready.";
 
-### Setup
+const SETUP: &str = "### Setup
 
 ```bash
 rd init              # Initialize in current project
 rd init --stealth    # Initialize without committing .radial to repo
-```
+rd prep --for short  # This guide in a few lines, for small context windows
+```";
 
-### Goals
+const GOALS: &str = r#"### Goals
 
 Goals are high-level objectives containing tasks.
 
 ```bash
 rd goal create "Implement user authentication"   # Create a goal
+rd goal create "Add OAuth" --parent <goal_id>     # Create a sub-goal
 rd goal list                                      # List all goals
+rd goal list --project api-v2                     # Only goals labelled with a project
+rd goal claim <goal_id> --agent <name> --ttl 2h   # Keep other agents off the goal's tasks
+rd goal release <goal_id> --agent <name>          # Let them back in
+rd goal criterion add <goal_id> "Docs updated"    # Acceptance criterion, ticked by hand
+rd goal criterion add <goal_id> "E2E passes" --command "make e2e"  # ...or checked by a command
+rd goal verify <goal_id>                          # Check criteria; completes the goal when all hold
+rd goal merge <goal_id> <other_goal_id>           # Two goals for one effort: move the other's tasks in
+rd goal reopen <goal_id> --reset-failed           # Failed goal: put every failed task back to pending
+rd export --goal <goal_id> -o plan.json          # Share one goal and its sub-goals; rd import plan.json
+rd milestone add v1 --due 2030-06-01T00:00:00Z    # A named target date goals can be planned for
+rd milestone attach v1 <goal_id>                  # Plan a goal for it
+rd milestone status                               # Progress and goals at risk of missing it
+rd plan new --interactive                         # Prompt for a goal, tasks, and blockers
+rd validate plan.toml                             # Lint a plan file and preview its tasks
+rd plan apply plan.toml                           # Create the goal and tasks from a plan file
+rd --ephemeral --seed plan.toml < session.txt     # Rehearse commands against an in-memory store
 ```
 
-### Tasks
+A claimed goal only accepts changes from its holder. Set `RADIAL_AGENT=<name>` so every command
+you run is made as you."#;
+
+const TASKS: &str = r#"### Tasks
 
 Tasks are units of work under a goal. They can have dependencies and contracts.
 
@@ -29,28 +74,92 @@ Tasks are units of work under a goal. They can have dependencies and contracts.
 # Create a task
 rd task create <goal_id> "Write login handler"
 
+# A short title for list views when the description is long
+rd task create <goal_id> "Add POST /login that checks the hash and sets a session cookie" \
+  --title "Login handler"
+
 # With contracts and dependencies
 rd task create <goal_id> "Parse config" \
   --receives "config.yaml file path" \
   --produces "Config struct" \
   --verify "Unit tests pass" \
-  --blocked-by task_abc,task_def
+  --blocked-by task_abc,task_def \
+  --blocked-by-artifact dist/schema.json \
+  --priority high                 # low, normal (default), high, urgent
+
+# --blocked-by-artifact waits until any completed task in the goal lists that artifact
+
+# receives can name another task's output; rd context task fills it in:
+# {{task:<id>.result}}, {{task:<id>.artifacts}}, {{task:<id>.artifacts[0]}}
+
+# Hold a task back until a time, e.g. after the nightly deploy (timestamp or duration)
+rd task create <goal_id> "Verify deploy" --not-before 2025-06-01T03:00:00Z
+
+# Hold a task back until a shell command succeeds (checked by ready, cached briefly)
+rd task create <goal_id> "Smoke test staging" --gate "curl -sf https://staging/health"
+
+# Link the issue, PR or ticket a task tracks (github:, gitlab:, jira:, linear:, url:)
+rd task create <goal_id> "Fix login redirect" --ref github:acme/web#412
+
+# Say how long a task should take, so agents short on time can skip it
+rd task create <goal_id> "Migrate the sessions table" --estimate 2h
+
+# Creating a task that nearly repeats an open one is refused, naming the
+# existing task; check whether it already covers the work before forcing it
+rd task create <goal_id> "Write login handler again" --allow-duplicate
 
 # List tasks for a goal
 rd task list <goal_id>
-```
 
-### Task Lifecycle
+# Change the order tasks are listed and picked in
+rd task reorder <goal_id> <task_id> <task_id>    # Move to the front, in this order
+rd task reorder <goal_id> <task_id> --after <task_id>
+
+# Review proposed dependencies, then add them
+rd suggest-deps <goal_id>
+rd suggest-deps <goal_id> --apply
+```"#;
+
+const LIFECYCLE: &str = r#"### Task Lifecycle
 
 ```bash
+rd task peek <goal_id>                           # Preview the next task and what it builds on
+rd context task <task_id>                        # What blockers handed over for this task
 rd task start <task_id>                          # Mark as started
+rd task start <task_id> --agent <name>           # Start and record yourself as assignee
+                                                 # ([wip] limits in config warn or refuse past N in progress)
 rd task complete <task_id> --result "Added login endpoint with JWT"
 rd task complete <task_id> --result "Done" --artifacts "src/auth.rs,src/jwt.rs"
+rd task complete <task_id> --result "Done" --prompt-tokens 9000 --completion-tokens 1200  # Or --tokens; fail takes them too
+rd task complete <task_id> --result "Done" --tokens 9000 --model <name> --provider <name>
+                                                 # Or set RADIAL_MODEL / RADIAL_PROVIDER; [pricing] costs it
+rd task complete <task_id> --result "Done" --follow-up "Document the endpoint"  # Repeatable
+rd task complete <task_id> --result "Done" --yes  # Past the warning about releasing tasks whose other
+                                                 # blockers were deleted or finished out of order
+rd task verify <task_id>                         # Run the verify command; output kept in .radial/attachments
+rd task verify <task_id> --sandbox docker:rust   # ...inside a container ([sandbox] in config sets defaults)
+rd task exec <task_id> --on-success complete --on-failure fail -- cargo test  # Run, then finish by exit status
+rd task move <task_id> review                    # Enter a custom state from [states] in config
 rd task fail <task_id>                           # Mark as failed
+rd task fail <task_id> --reason "Tests need a database"  # Kept in the attempt history rd show lists
+rd task fail <task_id> --category spec --fix "..."  # Record why (spec, dependency, environment,
+                                                 # tooling, timeout, other); see rd postmortems
+rd task fail <task_id> --reason-code lease_expired  # Say why for reports (budget_exceeded, verify_failed, ...)
 rd task retry <task_id>                          # Retry a failed task
+rd task retry <task_id> --verify "..."          # Retry with a sharper contract (--edit opens $EDITOR)
+echo '{"verify": "..."}' | rd edit task <task_id> --patch -  # Change any fields at once; prints JSON
 ```
 
-### Comments
+Short forms save tokens on every call:
+
+```bash
+rd grab <goal_id> --agent <name>                 # = rd task claim-next: start the next ready task
+rd grab <goal_id> --max-minutes 30               # Only a task estimated to fit in the time you have left
+rd done <task_id> -r "Added login endpoint"      # = rd task complete --result
+rd drop <task_id> "Tests need a database"        # = rd task fail, keeping the reason as a comment
+```"#;
+
+const COMMENTS: &str = r#"### Comments
 
 Comments allow you to attach notes or progress updates to tasks. They are timestamped and
 preserved in order.
@@ -58,8 +167,18 @@ preserved in order.
 ```bash
 rd task comment <task_id> "Started investigating the auth flow"
 rd task comment <task_id> "Found the issue - missing token validation"
+rd task comment <task_id> "Agreed, but check refresh too" --reply-to <comment_id>
+rd task progress <task_id> 60 --note "handler done, tests remaining"
+rd task ctx set <task_id> pr https://github.com/org/repo/pull/7  # Key-value notes; ctx get <task_id> pr
 ```
 
+Progress updates are comments that carry a percentage. `rd status` shows the latest one next to
+each in-progress task.
+
+Replies are shown indented under the comment they answer, so a review stays with the note it
+is about. Write `#<task_id>` or `#<goal_id>` in a comment or description to reference other
+work; `rd show` on the referenced task or goal lists it under "Referenced by". Comment IDs are printed when a comment is added and in `rd show`.
+
 Comments are shown when viewing full task details:
 
 ```bash
@@ -70,24 +189,194 @@ Use the `--verbose` flag to show comments when listing tasks:
 
 ```bash
 rd task list <goal_id> --verbose
-```
+```"#;
+
+const CHECKLISTS: &str = r#"### Checklists
 
-### Status & Ready
+Break a task into small steps that don't need contracts of their own. Items are numbered
+from 1 and shown in `rd show`.
+
+```bash
+rd task check add <task_id> "Handle expired tokens"
+rd task check tick <task_id> 1          # --undo to reopen
+rd task check list <task_id>
+```"#;
+
+const STATUS: &str = "### Status & Ready
 
 ```bash
 rd status                    # Compact overview of all goals
 rd status --goal <goal_id>   # Compact status of a goal and its tasks
 rd status --task <task_id>   # Compact status of a task
+rd status --stale            # Tasks in progress longer than the stale threshold
+rd status --tree             # Goals nested under their parents with rolled-up progress
+rd inbox --digest            # What needs a human: failures, stale work, failing gates, budgets
 rd show <id>                 # Full details of a goal or task (auto-detects)
+rd show <id> --render plain  # Same, with markdown left as written
+rd open <id> [<which>]       # Open a task's refs or artifacts, or a goal's markdown export
 rd ready <goal_id>           # Show tasks ready to work on (unblocked)
-```
+rd ready <goal_id> --unassigned  # Only ready tasks nobody has been assigned
+rd ready <goal_id> --effective-priority  # Prefer work that unblocks urgent tasks
+rd ready <goal_id> --explain  # Also list what is not ready and why
+rd status --agent <name>     # Tasks assigned to one agent across all goals
+rd status --all-branches     # Include goals scoped to other git branches
+rd summary --for-llm         # Short project digest, --max-tokens to adjust (default 1000)
+rd digest --print            # What got done in the last 24h (--since, --format md|html)
+rd ready <goal_id> --json --fields id,title  # Only the JSON fields you need
+rd task list <goal_id> --template '{{id}} {{state}}'  # One line per task, no jq needed
+rd task list --where 'state=in_progress AND tokens>10000'  # Filter tasks across all goals
+```";
 
-### Typical Workflow
+const WORKFLOW: &str = r#"### Typical Workflow
 
 1. `rd goal create "Build feature X"` -> get goal_id
 2. `rd task create <goal_id> "Task A"` -> create tasks with dependencies
 3. `rd ready <goal_id>` -> see what's unblocked
 4. `rd task start <task_id>` -> claim a task
 5. `rd task complete <task_id> --result "..."` -> finish it
-6. Repeat from step 3"#
+6. Repeat from step 3"#;
+
+const SHORT_INTRO: &str = "## rd preparation
+
+rd tracks goals and tasks with dependencies so agents work on what's ready.";
+
+const SHORT_GOALS: &str = r#"```bash
+rd goal create "Implement auth"    # -> goal_id
+rd goal verify <goal_id>           # Check acceptance criteria
+```"#;
+
+const SHORT_TASKS: &str = r#"```bash
+rd task create <goal_id> "Parse config" --receives "..." --produces "..." --verify "..." \
+  --blocked-by <task_id>
+rd task list <goal_id>
+```"#;
+
+const SHORT_LIFECYCLE: &str = r#"```bash
+rd grab <goal_id> --agent <name>   # Start the next ready task
+rd task verify <task_id>           # Run its verify command
+rd done <task_id> -r "What changed"
+rd drop <task_id> "Why it failed"
+rd task comment <task_id> "Progress note"
+```"#;
+
+const SHORT_STATUS: &str = "```bash
+rd ready <goal_id>                 # What can start now
+rd status --goal <goal_id>
+rd show <id>
+```";
+
+const SECTIONS: &[Section] = &[
+    Section {
+        full: INTRO,
+        short: Some(SHORT_INTRO),
+    },
+    Section {
+        full: SETUP,
+        short: None,
+    },
+    Section {
+        full: GOALS,
+        short: Some(SHORT_GOALS),
+    },
+    Section {
+        full: TASKS,
+        short: Some(SHORT_TASKS),
+    },
+    Section {
+        full: LIFECYCLE,
+        short: Some(SHORT_LIFECYCLE),
+    },
+    Section {
+        full: COMMENTS,
+        short: None,
+    },
+    Section {
+        full: CHECKLISTS,
+        short: None,
+    },
+    Section {
+        full: STATUS,
+        short: Some(SHORT_STATUS),
+    },
+    Section {
+        full: WORKFLOW,
+        short: None,
+    },
+];
+
+/// The preparation guide for LLM agents using radial, in `variant`,
+/// without the commands `config` disables.
+pub fn run(variant: PrepVariant, config: &Config) -> String {
+    let mut guide: Vec<String> = Vec::new();
+    for section in SECTIONS {
+        let text = match variant {
+            PrepVariant::Full => section.full,
+            PrepVariant::Short => match section.short {
+                Some(short) => short,
+                None => continue,
+            },
+        };
+        guide.push(without_disabled(text, config));
+    }
+    guide.join("\n\n")
+}
+
+/// `text` less the lines that use a disabled command, along with the
+/// continuation lines of any that are cut.
+fn without_disabled(text: &str, config: &Config) -> String {
+    let mut kept = Vec::new();
+    let mut continuing = false;
+    for line in text.lines() {
+        let cut = continuing || runs_disabled(line, config);
+        continuing = cut && line.ends_with('\\');
+        if !cut {
+            kept.push(line);
+        }
+    }
+    kept.join("\n")
+}
+
+/// Whether `line` runs, or mentions in backticks, a disabled command.
+fn runs_disabled(line: &str, config: &Config) -> bool {
+    line.trim_start()
+        .strip_prefix("rd ")
+        .into_iter()
+        .chain(line.split("`rd ").skip(1))
+        .any(|rest| config.commands.disables(&command_words(rest)))
+}
+
+/// The command a line of the guide runs, e.g. `["task", "start"]` for
+/// `task start <task_id>`.
+fn command_words(args: &str) -> Vec<&str> {
+    args.split_whitespace()
+        .take_while(|w| {
+            !w.starts_with('-') && w.chars().all(|c| c.is_ascii_lowercase() || c == '-')
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    // Disabled commands drop out of the guide, continuation lines and all,
+    // and shortcuts go with the command they stand for.
+    #[rstest]
+    fn leaves_out_disabled_commands() {
+        let config: Config = toml::from_str(
+            "[commands]\ndisabled = [\"task exec\", \"task create\", \"task complete\"]\n",
+        )
+        .unwrap();
+        let guide = run(PrepVariant::Full, &config);
+        assert!(!guide.contains("rd task exec"));
+        assert!(!guide.contains("rd task create"));
+        assert!(!guide.contains("--produces \"Config struct\""));
+        assert!(!guide.contains("rd done"));
+        assert!(guide.contains("rd task verify"));
+
+        let short = run(PrepVariant::Short, &Config::default());
+        assert!(short.contains("rd grab"));
+        assert!(short.len() < guide.len() / 4);
+    }
 }
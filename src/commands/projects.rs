@@ -0,0 +1,91 @@
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+
+use crate::db::Database;
+use crate::models::GoalState;
+
+/// Goals sharing a project label, with their task metrics added up.
+#[derive(Debug, Default, Serialize)]
+pub struct ProjectSummary {
+    /// `None` for goals without a project.
+    pub project: Option<String>,
+    pub goals: usize,
+    pub goals_completed: usize,
+    pub tasks: i64,
+    pub tasks_completed: i64,
+    pub tasks_failed: i64,
+    pub total_tokens: i64,
+    pub elapsed_ms: i64,
+}
+
+/// One summary per project, by name, then one for unlabelled goals if
+/// there are any.
+pub fn run(db: &Database) -> Vec<ProjectSummary> {
+    let mut projects: BTreeMap<Option<&str>, ProjectSummary> = BTreeMap::new();
+    let mut all_metrics = db.compute_all_goal_metrics();
+    for goal in db.list_goals() {
+        let metrics = all_metrics.remove(goal.id()).unwrap_or_default();
+        let summary = projects
+            .entry(goal.project())
+            .or_insert_with(|| ProjectSummary {
+                project: goal.project().map(str::to_string),
+                ..ProjectSummary::default()
+            });
+        summary.goals += 1;
+        if goal.state() == GoalState::Completed {
+            summary.goals_completed += 1;
+        }
+        summary.tasks += metrics.task_count();
+        summary.tasks_completed += metrics.tasks_completed();
+        summary.tasks_failed += metrics.tasks_failed();
+        summary.total_tokens += metrics.total_tokens();
+        summary.elapsed_ms += metrics.elapsed_ms();
+    }
+
+    // `None` sorts first in the map; show unlabelled goals last instead.
+    let mut summaries: Vec<ProjectSummary> = projects.into_values().collect();
+    if summaries.first().is_some_and(|s| s.project.is_none()) {
+        summaries.rotate_left(1);
+    }
+    summaries
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{TaskMetrics, TaskState};
+    use crate::testing::{GoalBuilder, TaskBuilder, TempProject};
+    use rstest::rstest;
+
+    // Goals are grouped by label with metrics summed, and unlabelled goals
+    // come last.
+    #[rstest]
+    fn sums_goals_per_project() {
+        let mut project = TempProject::new().unwrap();
+        for (id, label) in [("g1", Some("api")), ("g2", Some("api")), ("g3", None)] {
+            let mut goal = GoalBuilder::new("goal").id(id);
+            goal = match label {
+                Some(label) => goal.project(label),
+                None => goal,
+            };
+            project.add_goal(goal).unwrap();
+            project
+                .add_task(
+                    TaskBuilder::new(id, "work")
+                        .state(TaskState::Completed)
+                        .metrics(TaskMetrics::new(100, 10, 0)),
+                )
+                .unwrap();
+        }
+
+        let summaries = run(project.db());
+        assert_eq!(summaries.len(), 2);
+        assert_eq!(summaries[0].project.as_deref(), Some("api"));
+        assert_eq!(summaries[0].goals, 2);
+        assert_eq!(summaries[0].tasks_completed, 2);
+        assert_eq!(summaries[0].total_tokens, 200);
+        assert!(summaries[1].project.is_none());
+        assert_eq!(summaries[1].goals, 1);
+    }
+}
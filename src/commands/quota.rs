@@ -0,0 +1,87 @@
+use std::collections::BTreeMap;
+
+use jiff::Timestamp;
+use jiff::civil::Date;
+use jiff::tz::TimeZone;
+use serde::Serialize;
+
+use crate::config::QuotaAction;
+use crate::db::Database;
+use crate::error::{Error, Result};
+
+/// One agent's token spend for the current UTC day against its budget.
+#[derive(Debug, Serialize)]
+pub struct AgentUsage {
+    pub agent: String,
+    pub tokens_today: i64,
+    pub daily_limit: Option<i64>,
+}
+
+impl AgentUsage {
+    pub fn exceeded(&self) -> bool {
+        self.daily_limit
+            .is_some_and(|limit| self.tokens_today >= limit)
+    }
+}
+
+/// Usage for every agent that has spent tokens today or has a configured budget.
+pub fn run(db: &Database) -> Vec<AgentUsage> {
+    let quotas = &db.config().quotas;
+    let mut spent: BTreeMap<String, i64> = quotas.agents.keys().map(|a| (a.clone(), 0)).collect();
+
+    let today = utc_date(Timestamp::now());
+    for goal in db.list_goals() {
+        for task in db.list_tasks(goal.id()) {
+            let (Some(agent), Some(completed_at)) = (task.assignee(), task.completed_at()) else {
+                continue;
+            };
+            if utc_date(completed_at) == today {
+                *spent.entry(agent.to_string()).or_default() += task.metrics().tokens();
+            }
+        }
+    }
+
+    spent
+        .into_iter()
+        .map(|(agent, tokens_today)| AgentUsage {
+            daily_limit: quotas.limit_for(&agent),
+            agent,
+            tokens_today,
+        })
+        .collect()
+}
+
+/// Check `agent` against its daily budget before it takes on more work.
+/// Returns a warning in `warn` mode and an error in `refuse` mode.
+/// [`crate::commands::task::start`] refuses through this, so `claim-next`
+/// and [`crate::store::RadialStore`] are held to the budget too.
+pub fn check(agent: &str, db: &Database) -> Result<Option<String>> {
+    let quotas = &db.config().quotas;
+    let Some(limit) = quotas.limit_for(agent) else {
+        return Ok(None);
+    };
+
+    let used = usage_today(agent, db);
+    if used < limit {
+        return Ok(None);
+    }
+
+    let message = format!("Agent {agent} has used {used} of its {limit} daily tokens");
+    match quotas.action {
+        QuotaAction::Warn => Ok(Some(message)),
+        QuotaAction::Refuse => Err(Error::conflict(format!(
+            "{message}. Refusing to start more work today."
+        ))),
+    }
+}
+
+fn usage_today(agent: &str, db: &Database) -> i64 {
+    run(db)
+        .into_iter()
+        .find(|u| u.agent == agent)
+        .map_or(0, |u| u.tokens_today)
+}
+
+pub(crate) fn utc_date(ts: Timestamp) -> Date {
+    ts.to_zoned(TimeZone::UTC).date()
+}
@@ -1,16 +1,574 @@
-use anyhow::{Result, anyhow};
+use std::cmp::Reverse;
+use std::collections::{HashMap, HashSet};
 
+use jiff::{SignedDuration, Timestamp};
+use serde::Serialize;
+
+use super::task;
+use crate::config::BlockerPolicy;
 use crate::db::Database;
-use crate::models::{Task, TaskState};
+use crate::error::{Error, Result};
+use crate::gate;
+use crate::models::{Priority, Task, TaskState};
+
+/// A task that can be started now. `effective_priority` is only filled in when
+/// requested, since computing it walks every dependent.
+#[derive(Debug, Serialize)]
+pub struct ReadyTask {
+    #[serde(flatten)]
+    pub task: Task,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub effective_priority: Option<Priority>,
+}
+
+impl ReadyTask {
+    /// The priority used for ordering: inherited if computed, otherwise the task's own.
+    pub fn priority(&self) -> Priority {
+        self.effective_priority.unwrap_or(self.task.priority())
+    }
+}
+
+pub fn run(
+    goal_id: &str,
+    unassigned: bool,
+    effective_priority: bool,
+    branch: Option<&str>,
+    db: &Database,
+) -> Result<Vec<ReadyTask>> {
+    let goal = db.require_goal(goal_id)?;
+    if !goal.visible_on(branch) {
+        return Err(Error::conflict(format!(
+            "Goal {goal_id} belongs to branch '{}'. Switch to it, or pass --all-branches",
+            goal.branch().unwrap_or_default()
+        )));
+    }
+
+    let tasks = db.list_tasks(goal_id);
+    let effective = if effective_priority {
+        effective_priorities(&tasks)
+    } else {
+        HashMap::new()
+    };
+
+    // Under the lenient policy a blocked task counts as pending once what
+    // it waits on is done or gone, without waiting for a completion to
+    // release it.
+    let policy = db.config().ready.blockers;
+    let deps = task::GoalDeps::new(&tasks);
+    let now = Timestamp::now();
+    let candidates: Vec<&Task> = tasks
+        .iter()
+        .copied()
+        .filter(|t| match t.state() {
+            TaskState::Pending => true,
+            TaskState::Blocked => policy == BlockerPolicy::Lenient && deps.met(t, policy),
+            _ => false,
+        })
+        .filter(|t| t.contract().is_some())
+        .filter(|t| t.available(now))
+        .filter(|t| !unassigned || t.assignee().is_none())
+        .collect();
+    let mut ready: Vec<ReadyTask> = gate::passing(candidates, db)
+        .into_iter()
+        .map(|t| ReadyTask {
+            task: t.clone(),
+            effective_priority: effective.get(t.id()).copied(),
+        })
+        .collect();
+
+    // Stable sort keeps creation order among equal priorities.
+    ready.sort_by_key(|r| Reverse(r.priority()));
+    Ok(ready)
+}
+
+/// Why a task that has not started yet cannot be picked up.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "reason", rename_all = "snake_case")]
+pub enum NotReadyReason {
+    MissingContract,
+    /// An unfinished blocker; `state` is `None` if no such task exists, which
+    /// only holds a task back with `[ready] blockers = "strict"`.
+    BlockedBy {
+        task_id: String,
+        state: Option<TaskState>,
+    },
+    /// No completed task in the goal has listed this artifact yet.
+    WaitingOnArtifact {
+        path: String,
+    },
+    /// Excluded by `--unassigned`.
+    AssignedTo {
+        agent: String,
+    },
+    /// Scheduled with `--not-before` for a time still to come.
+    NotBefore {
+        at: Timestamp,
+    },
+    /// A gate command that did not succeed. Gates are only run once nothing
+    /// else holds the task back.
+    GateFailed {
+        command: String,
+    },
+    /// In the blocked state although all its blockers are done, usually from
+    /// a hand edit. Only with `[ready] blockers = "strict"`.
+    MarkedBlocked,
+}
+
+/// A pending or blocked task that `run` left out, with every reason why.
+#[derive(Debug, Serialize)]
+pub struct NotReady {
+    pub task_id: String,
+    pub description: String,
+    pub state: TaskState,
+    pub reasons: Vec<NotReadyReason>,
+}
+
+/// The goal's unstarted tasks that `run` with the same `unassigned` flag
+/// would not return, so an idle agent can say what it is waiting on.
+pub fn explain(goal_id: &str, unassigned: bool, db: &Database) -> Result<Vec<NotReady>> {
+    db.require_goal(goal_id)?;
+
+    let tasks = db.list_tasks(goal_id);
+    let deps = task::GoalDeps::new(&tasks);
+    let lenient = db.config().ready.blockers == BlockerPolicy::Lenient;
+    let now = Timestamp::now();
+    let mut not_ready = Vec::new();
+    for task in &tasks {
+        if !matches!(task.state(), TaskState::Pending | TaskState::Blocked) {
+            continue;
+        }
+
+        let mut reasons = Vec::new();
+        if task.contract().is_none() {
+            reasons.push(NotReadyReason::MissingContract);
+        }
+        let waiting_on: Vec<NotReadyReason> = task
+            .blocked_by()
+            .iter()
+            .filter_map(|id| {
+                let state = db.get_task(id).map(Task::state);
+                if state.is_none() && lenient {
+                    return None;
+                }
+                (state != Some(TaskState::Completed)).then(|| NotReadyReason::BlockedBy {
+                    task_id: id.clone(),
+                    state,
+                })
+            })
+            .chain(
+                task.blocked_by_artifacts()
+                    .iter()
+                    .filter(|a| !deps.produced(a))
+                    .map(|a| NotReadyReason::WaitingOnArtifact { path: a.clone() }),
+            )
+            .collect();
+        if task.state() == TaskState::Blocked && waiting_on.is_empty() && !lenient {
+            reasons.push(NotReadyReason::MarkedBlocked);
+        }
+        reasons.extend(waiting_on);
+        if unassigned && let Some(agent) = task.assignee() {
+            reasons.push(NotReadyReason::AssignedTo {
+                agent: agent.to_string(),
+            });
+        }
+        if let Some(at) = task.not_before().filter(|_| !task.available(now)) {
+            reasons.push(NotReadyReason::NotBefore { at });
+        }
+        if reasons.is_empty() {
+            let results = gate::evaluate(task.gates().iter().map(String::as_str), db);
+            reasons.extend(
+                task.gates()
+                    .iter()
+                    .filter(|g| !results[*g])
+                    .map(|g| NotReadyReason::GateFailed { command: g.clone() }),
+            );
+        }
+
+        if !reasons.is_empty() {
+            not_ready.push(NotReady {
+                task_id: task.id().to_string(),
+                description: task.description().to_string(),
+                state: task.state(),
+                reasons,
+            });
+        }
+    }
+    Ok(not_ready)
+}
+
+/// The task an agent would pick up next: the first ready task, skipping any
+/// assigned to someone other than `agent`. With `within`, only a task
+/// estimated to take no longer is picked; tasks without an estimate are
+/// skipped, since there is no telling whether they fit.
+pub fn next(
+    goal_id: &str,
+    agent: Option<&str>,
+    within: Option<SignedDuration>,
+    db: &Database,
+) -> Result<Option<ReadyTask>> {
+    let next = run(goal_id, false, false, None, db)?.into_iter().find(|r| {
+        let mine = match (r.task.assignee(), agent) {
+            (Some(assignee), Some(agent)) => assignee == agent,
+            (Some(_), None) => false,
+            (None, _) => true,
+        };
+        mine && within.is_none_or(|budget| r.task.estimate().is_some_and(|e| e <= budget))
+    });
+    Ok(next)
+}
+
+/// Each task's priority raised to the highest priority of any unfinished task
+/// that is waiting on it, directly or transitively.
+pub fn effective_priorities(tasks: &[&Task]) -> HashMap<String, Priority> {
+    let mut dependents: HashMap<&str, Vec<&Task>> = HashMap::new();
+    for task in tasks {
+        if task.state() == TaskState::Completed {
+            continue;
+        }
+        for blocker in task.blocked_by() {
+            dependents.entry(blocker.as_str()).or_default().push(task);
+        }
+    }
+
+    let mut memo = HashMap::new();
+    for task in tasks {
+        inherit(task, &dependents, &mut memo, &mut HashSet::new());
+    }
+    memo.into_iter()
+        .map(|(id, priority)| (id.to_string(), priority))
+        .collect()
+}
 
-pub fn run(goal_id: &str, db: &Database) -> Result<Vec<Task>> {
-    db.get_goal(goal_id)
-        .ok_or_else(|| anyhow!("Goal not found: {goal_id}"))?;
+fn inherit<'a>(
+    task: &'a Task,
+    dependents: &HashMap<&str, Vec<&'a Task>>,
+    memo: &mut HashMap<&'a str, Priority>,
+    visiting: &mut HashSet<&'a str>,
+) -> Priority {
+    if let Some(&priority) = memo.get(task.id()) {
+        return priority;
+    }
+    // A cycle can only come from hand-edited files; stop rather than recurse forever.
+    if !visiting.insert(task.id()) {
+        return task.priority();
+    }
 
-    Ok(db
-        .list_tasks(goal_id)
+    let priority = dependents
+        .get(task.id())
         .into_iter()
-        .filter(|t| t.state() == TaskState::Pending && t.contract().is_some())
-        .cloned()
-        .collect())
+        .flatten()
+        .map(|dependent| inherit(dependent, dependents, memo, visiting))
+        .fold(task.priority(), Ord::max);
+
+    visiting.remove(task.id());
+    memo.insert(task.id(), priority);
+    priority
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::ReasonCode;
+    use crate::testing::{GoalBuilder, TaskBuilder, TempProject};
+    use jiff::Timestamp;
+    use rstest::rstest;
+
+    fn task(id: &str, priority: Priority, blocked_by: &[&str]) -> Task {
+        let now = Timestamp::now();
+        Task::new(
+            id.to_string(),
+            "g1".to_string(),
+            "test task".to_string(),
+            None,
+            TaskState::Pending,
+            blocked_by.iter().map(ToString::to_string).collect(),
+            now,
+            now,
+        )
+        .with_priority(priority)
+    }
+
+    // An urgent task two hops downstream lifts every blocker on its path,
+    // while an unrelated task keeps its own priority.
+    #[rstest]
+    fn inherits_through_chain() {
+        let a = task("a", Priority::Low, &[]);
+        let b = task("b", Priority::Normal, &["a"]);
+        let c = task("c", Priority::Urgent, &["b"]);
+        let d = task("d", Priority::Low, &[]);
+
+        let effective = effective_priorities(&[&a, &b, &c, &d]);
+        assert_eq!(effective["a"], Priority::Urgent);
+        assert_eq!(effective["b"], Priority::Urgent);
+        assert_eq!(effective["c"], Priority::Urgent);
+        assert_eq!(effective["d"], Priority::Low);
+    }
+
+    // A task never drops below its own priority, and completed dependents
+    // no longer push anything upstream.
+    #[rstest]
+    fn keeps_own_priority_and_ignores_completed_dependents() {
+        let a = task("a", Priority::High, &[]);
+        let mut b = task("b", Priority::Urgent, &["a"]);
+        b.transition(
+            &TaskState::Pending,
+            TaskState::Completed,
+            ReasonCode::UserRequest,
+        );
+        let c = task("c", Priority::Low, &["a"]);
+
+        let effective = effective_priorities(&[&a, &b, &c]);
+        assert_eq!(effective["a"], Priority::High);
+    }
+
+    // Under the strict policy each left-out task lists every reason: no
+    // contract, unfinished or missing blockers, and a stale blocked state.
+    #[rstest]
+    fn explain_lists_reasons() {
+        let dir = tempfile::TempDir::new().unwrap();
+        std::fs::write(
+            dir.path().join(crate::config::CONFIG_FILE),
+            "[ready]\nblockers = \"strict\"\n",
+        )
+        .unwrap();
+        let mut db = Database::open(dir.path()).unwrap();
+        let now = Timestamp::now();
+        db.create_goal(crate::models::Goal::new(
+            "g1".to_string(),
+            None,
+            "goal".to_string(),
+            crate::models::GoalState::InProgress,
+            now,
+            now,
+            None,
+            crate::models::Metrics::default(),
+        ))
+        .unwrap();
+        let contract =
+            || crate::models::Contract::new("in".to_string(), "out".to_string(), "ok".to_string());
+        let mut a = task("a", Priority::Normal, &[]);
+        a.set_contract(contract());
+        a.transition(
+            &TaskState::Pending,
+            TaskState::InProgress,
+            ReasonCode::UserRequest,
+        );
+        let mut b = task("b", Priority::Normal, &["a", "gone"]);
+        b.transition(
+            &TaskState::Pending,
+            TaskState::Blocked,
+            ReasonCode::UserRequest,
+        );
+        let mut c = task("c", Priority::Normal, &[]);
+        c.set_contract(contract());
+        c.transition(
+            &TaskState::Pending,
+            TaskState::Blocked,
+            ReasonCode::UserRequest,
+        );
+        let mut d = task("d", Priority::Normal, &[]);
+        d.set_contract(contract());
+        for t in [a, b, c, d] {
+            db.create_task(t).unwrap();
+        }
+
+        let not_ready = explain("g1", false, &db).unwrap();
+        let reasons: Vec<(&str, &[NotReadyReason])> = not_ready
+            .iter()
+            .map(|n| (n.task_id.as_str(), n.reasons.as_slice()))
+            .collect();
+        assert_eq!(
+            reasons,
+            [
+                (
+                    "b",
+                    &[
+                        NotReadyReason::MissingContract,
+                        NotReadyReason::BlockedBy {
+                            task_id: "a".to_string(),
+                            state: Some(TaskState::InProgress)
+                        },
+                        NotReadyReason::BlockedBy {
+                            task_id: "gone".to_string(),
+                            state: None
+                        },
+                    ][..]
+                ),
+                ("c", &[NotReadyReason::MarkedBlocked][..]),
+            ]
+        );
+    }
+
+    // By default a blocked task whose blockers are done or deleted is ready,
+    // and starting it releases it; one still waiting stays out.
+    #[rstest]
+    fn lenient_policy_ignores_missing_blockers() {
+        let mut project = TempProject::new().unwrap();
+        project.add_goal(GoalBuilder::new("Ship").id("g1")).unwrap();
+        let task = |id: &str| TaskBuilder::new("g1", id).id(id).contract("-", "-", "true");
+        let blocked = |id: &str| task(id).state(TaskState::Blocked);
+        project
+            .add_task(blocked("gone").blocked_by("deleted"))
+            .unwrap();
+        project.add_task(task("a")).unwrap();
+        project.add_task(blocked("waits").blocked_by("a")).unwrap();
+
+        let ids = |db: &Database| -> Vec<String> {
+            run("g1", false, false, None, db)
+                .unwrap()
+                .into_iter()
+                .map(|r| r.task.id().to_string())
+                .collect()
+        };
+        assert_eq!(ids(project.db()), ["gone", "a"]);
+        assert_eq!(
+            explain("g1", false, project.db()).unwrap()[0].task_id,
+            "waits"
+        );
+
+        task::start("gone", None, project.db_mut()).unwrap();
+        let started = project.db().get_task("gone").unwrap().state();
+        assert_eq!(started, TaskState::InProgress);
+    }
+
+    // With a time budget only tasks estimated to fit are picked, even if
+    // bigger or unestimated ones come first.
+    #[rstest]
+    #[case::none(None, Some("big"))]
+    #[case::hour(Some(60), Some("hour"))]
+    #[case::short(Some(10), None)]
+    fn next_fits_budget(#[case] minutes: Option<i64>, #[case] expected: Option<&str>) {
+        let mut project = TempProject::new().unwrap();
+        project.add_goal(GoalBuilder::new("Ship").id("g1")).unwrap();
+        let task = |id: &str, order| {
+            TaskBuilder::new("g1", id)
+                .id(id)
+                .order(order)
+                .contract("-", "-", "true")
+        };
+        project
+            .add_task(task("big", 0).estimate(SignedDuration::from_hours(3)))
+            .unwrap();
+        project.add_task(task("unknown", 1)).unwrap();
+        project
+            .add_task(task("hour", 2).estimate(SignedDuration::from_mins(60)))
+            .unwrap();
+
+        let within = minutes.map(SignedDuration::from_mins);
+        let next = next("g1", None, within, project.db()).unwrap();
+        assert_eq!(next.as_ref().map(|r| r.task.id()), expected);
+    }
+
+    // Hand-edited cycles must not hang the computation.
+    #[rstest]
+    fn tolerates_cycles() {
+        let a = task("a", Priority::Low, &["b"]);
+        let b = task("b", Priority::High, &["a"]);
+
+        let effective = effective_priorities(&[&a, &b]);
+        assert_eq!(effective["a"], Priority::High);
+    }
+
+    /// An in-memory store of `goals` goals, `g1` onwards, of `per_goal`
+    /// tasks each in chains of ten, each task after the first in a chain
+    /// blocked by the one before.
+    fn big_store(goals: usize, per_goal: usize) -> Database {
+        let mut db = Database::open_in_memory();
+        for g in 1..=goals {
+            let goal_id = format!("g{g}");
+            db.create_goal(GoalBuilder::new("Ship").id(&goal_id).build())
+                .unwrap();
+            let id = |i: usize| {
+                if g == 1 {
+                    format!("t{i}")
+                } else {
+                    format!("{goal_id}t{i}")
+                }
+            };
+            for i in 0..per_goal {
+                let mut task = TaskBuilder::new(&goal_id, format!("Batch {i}"))
+                    .id(id(i))
+                    .contract("-", "-", "true");
+                if i % 10 != 0 {
+                    task = task.blocked_by(id(i - 1)).state(TaskState::Blocked);
+                }
+                db.create_task(task.build()).unwrap();
+            }
+        }
+        db
+    }
+
+    /// Run `f`, failing if it takes `limit_ms` or longer. The limits are
+    /// loose enough for a debug build; they are there to catch a path
+    /// turning quadratic, not to measure it. Run them with
+    /// `cargo test --release -- --ignored bench_`.
+    fn timed<T>(label: &str, limit_ms: u128, f: impl FnOnce() -> T) -> T {
+        let started = std::time::Instant::now();
+        let value = f();
+        let elapsed = started.elapsed();
+        assert!(elapsed.as_millis() < limit_ms, "{label} took {elapsed:?}");
+        value
+    }
+
+    // The queries agents run on every turn stay quick on a 100k-task goal:
+    // finding ready work, claiming it, listing, and completing a task that
+    // releases the next in its chain.
+    #[rstest]
+    #[ignore = "benchmark"]
+    fn bench_hot_paths_on_a_hundred_thousand_tasks() {
+        let mut db = big_store(1, 100_000);
+
+        let ready = timed("ready", 2_000, || {
+            run("g1", false, false, None, &db).unwrap()
+        });
+        assert_eq!(ready.len(), 10_000);
+        timed("explain", 2_000, || explain("g1", false, &db).unwrap());
+        timed("list", 2_000, || crate::commands::list::rows(&db));
+        let claimed = timed("claim-next", 2_000, || {
+            task::claim_next("g1", None, None, &mut db).unwrap()
+        });
+        let claimed = claimed.unwrap().task.id().to_string();
+        let done = timed("complete", 2_000, || {
+            task::complete(
+                &claimed,
+                "done".to_string(),
+                None,
+                task::Usage::default(),
+                Vec::new(),
+                &mut db,
+            )
+            .unwrap()
+        });
+        assert_eq!(done.unblocked_task_ids, ["t1"]);
+        timed("settle", 2_000, || {
+            task::settle_blocked("g1", &mut db).unwrap()
+        });
+    }
+
+    // Spread over many goals, a query about one goal pays for that goal's
+    // tasks, not the whole store.
+    #[rstest]
+    #[ignore = "benchmark"]
+    fn bench_one_goal_among_a_hundred_thousand_tasks() {
+        let mut db = big_store(100, 1_000);
+
+        let ready = timed("ready", 20, || run("g1", false, false, None, &db).unwrap());
+        assert_eq!(ready.len(), 100);
+        let claimed = timed("claim-next", 20, || {
+            task::claim_next("g1", None, None, &mut db).unwrap()
+        });
+        assert_eq!(claimed.unwrap().task.id(), "t0");
+        let done = timed("complete", 20, || {
+            task::complete(
+                "t0",
+                "done".to_string(),
+                None,
+                task::Usage::default(),
+                Vec::new(),
+                &mut db,
+            )
+            .unwrap()
+        });
+        assert_eq!(done.unblocked_task_ids, ["t1"]);
+    }
 }
@@ -0,0 +1,234 @@
+//! `rd serve --web`: a read-only dashboard of goals, task graphs and recent
+//! commands, for people following along without the CLI. Each request
+//! reads the store afresh, so the page shows agents' progress as it
+//! happens; nothing is ever written.
+
+use std::io::{BufRead, BufReader, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::path::Path;
+use std::thread;
+use std::time::Duration;
+
+use anyhow::Context;
+use serde::Serialize;
+
+use crate::commands::history::{self, Numbered};
+use crate::commands::list;
+use crate::db::Database;
+use crate::error::Result;
+use crate::models::{Goal, Metrics, Task};
+
+/// The page, with its script and styles inline so the binary carries
+/// everything it serves.
+const INDEX_HTML: &str = include_str!("dashboard.html");
+
+/// How many logged commands the dashboard lists.
+const RECENT_EVENTS: usize = 20;
+
+/// How long a client may take to send its request or read the reply
+/// before its connection is dropped.
+const CLIENT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Everything the page shows, as served from `/api/dashboard`.
+#[derive(Debug, Serialize)]
+pub struct Dashboard {
+    pub goals: Vec<GoalView>,
+    /// The latest commands run against the store, oldest first.
+    pub events: Vec<Numbered>,
+}
+
+/// A goal with its tasks in dependency order.
+#[derive(Debug, Serialize)]
+pub struct GoalView {
+    #[serde(flatten)]
+    pub goal: Goal,
+    pub tasks: Vec<Task>,
+    pub metrics: Metrics,
+}
+
+pub fn dashboard(db: &Database) -> Result<Dashboard> {
+    let goals = list::run(db)?
+        .into_iter()
+        .map(|g| GoalView {
+            goal: g.goal,
+            tasks: g.tasks,
+            metrics: g.metrics,
+        })
+        .collect();
+    Ok(Dashboard {
+        goals,
+        events: history::list(Some(RECENT_EVENTS), db)?,
+    })
+}
+
+/// A reply to one request.
+#[derive(Debug)]
+pub struct Response {
+    pub status: &'static str,
+    pub content_type: &'static str,
+    pub body: String,
+}
+
+impl Response {
+    fn text(status: &'static str, body: &str) -> Self {
+        Self {
+            status,
+            content_type: "text/plain; charset=utf-8",
+            body: format!("{body}\n"),
+        }
+    }
+}
+
+/// Answer `method target` against the store at `path`.
+pub fn respond(method: &str, target: &str, path: &Path) -> Response {
+    if method != "GET" {
+        return Response::text("405 Method Not Allowed", "The dashboard is read-only");
+    }
+    match target.split('?').next().unwrap_or_default() {
+        "/" | "/index.html" => Response {
+            status: "200 OK",
+            content_type: "text/html; charset=utf-8",
+            body: INDEX_HTML.to_string(),
+        },
+        "/api/dashboard" => match dashboard_json(path) {
+            Ok(body) => Response {
+                status: "200 OK",
+                content_type: "application/json",
+                body,
+            },
+            Err(e) => Response::text("500 Internal Server Error", &e.to_string()),
+        },
+        _ => Response::text("404 Not Found", "Not found"),
+    }
+}
+
+fn dashboard_json(path: &Path) -> Result<String> {
+    let db = Database::open(path)?;
+    let body = serde_json::to_string(&dashboard(&db)?).context("Failed to serialize dashboard")?;
+    Ok(body)
+}
+
+/// Serve the dashboard for the store at `path` on `addr` until interrupted,
+/// each connection on its own thread so a slow client holds up nobody
+/// else. `on_listen` is told the bound address, which differs from `addr`
+/// when it asks for port 0.
+pub fn run(path: &Path, addr: SocketAddr, on_listen: impl FnOnce(SocketAddr)) -> Result<()> {
+    let listener =
+        TcpListener::bind(addr).with_context(|| format!("Failed to listen on {addr}"))?;
+    on_listen(
+        listener
+            .local_addr()
+            .context("Failed to read bound address")?,
+    );
+
+    // A client hanging up mid-request is its own problem; keep serving.
+    for stream in listener.incoming().flatten() {
+        let path = path.to_path_buf();
+        thread::spawn(move || handle(stream, &path));
+    }
+    Ok(())
+}
+
+fn handle(stream: TcpStream, path: &Path) -> std::io::Result<()> {
+    stream.set_read_timeout(Some(CLIENT_TIMEOUT))?;
+    stream.set_write_timeout(Some(CLIENT_TIMEOUT))?;
+    let mut reader = BufReader::new(stream);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    // Headers are not needed; read past them so the client sees a clean close.
+    let mut header = String::new();
+    while reader.read_line(&mut header)? > 2 {
+        header.clear();
+    }
+
+    let mut parts = request_line.split_whitespace();
+    let (method, target) = (
+        parts.next().unwrap_or_default(),
+        parts.next().unwrap_or("/"),
+    );
+    let response = respond(method, target, path);
+
+    let mut stream = reader.into_inner();
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nCache-Control: no-store\r\nConnection: close\r\n\r\n{}",
+        response.status,
+        response.content_type,
+        response.body.len(),
+        response.body
+    )?;
+    stream.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{GoalBuilder, TaskBuilder, TempProject};
+    use rstest::rstest;
+
+    // The page itself and its data are served; anything else is not found.
+    #[rstest]
+    #[case::index("/", "200 OK", "text/html; charset=utf-8")]
+    #[case::api("/api/dashboard?t=1", "200 OK", "application/json")]
+    #[case::unknown("/tasks", "404 Not Found", "text/plain; charset=utf-8")]
+    fn routes_requests(#[case] target: &str, #[case] status: &str, #[case] content_type: &str) {
+        let project = TempProject::new().unwrap();
+        let response = respond("GET", target, &project.radial_dir());
+        assert_eq!(response.status, status);
+        assert_eq!(response.content_type, content_type);
+    }
+
+    // Nothing but GET is answered, so the dashboard can't change the store.
+    #[rstest]
+    fn refuses_writes() {
+        let project = TempProject::new().unwrap();
+        let response = respond("POST", "/api/dashboard", &project.radial_dir());
+        assert_eq!(response.status, "405 Method Not Allowed");
+    }
+
+    // The data holds every goal with its tasks, read fresh from disk.
+    #[rstest]
+    fn serves_goals_with_tasks() {
+        let mut project = TempProject::new().unwrap();
+        project.add_goal(GoalBuilder::new("Ship").id("g1")).unwrap();
+        project
+            .add_task(TaskBuilder::new("g1", "Build").id("t1"))
+            .unwrap();
+
+        let response = respond("GET", "/api/dashboard", &project.radial_dir());
+        let data: serde_json::Value = serde_json::from_str(&response.body).unwrap();
+        assert_eq!(data["goals"][0]["id"], "g1");
+        assert_eq!(data["goals"][0]["tasks"][0]["id"], "t1");
+        assert_eq!(data["events"], serde_json::json!([]));
+    }
+
+    // A client that connects and sends nothing doesn't hold up the next
+    // one.
+    #[rstest]
+    fn serves_past_an_idle_client() {
+        use std::io::Read;
+        use std::sync::mpsc;
+
+        let project = TempProject::new().unwrap();
+        let path = project.radial_dir();
+        let (bound, listening) = mpsc::channel();
+        thread::spawn(move || {
+            run(&path, "127.0.0.1:0".parse().unwrap(), |addr| {
+                bound.send(addr).unwrap();
+            })
+        });
+        let addr = listening.recv().unwrap();
+
+        let _idle = TcpStream::connect(addr).unwrap();
+        let mut client = TcpStream::connect(addr).unwrap();
+        client
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+        client
+            .write_all(b"GET /api/dashboard HTTP/1.1\r\n\r\n")
+            .unwrap();
+        let mut reply = String::new();
+        client.read_to_string(&mut reply).unwrap();
+        assert!(reply.starts_with("HTTP/1.1 200 OK"), "{reply}");
+    }
+}
@@ -0,0 +1,516 @@
+use std::collections::HashMap;
+
+use jiff::Span;
+use jiff::civil::Date;
+use serde::Serialize;
+use strum::{AsRefStr, EnumString};
+
+use super::quota::utc_date;
+use crate::db::Database;
+use crate::error::Result;
+use crate::models::{Task, TaskState};
+
+/// What `stats tokens` groups spend by.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, AsRefStr, EnumString)]
+#[serde(rename_all = "lowercase")]
+#[strum(serialize_all = "lowercase")]
+pub enum GroupBy {
+    #[default]
+    Goal,
+    Agent,
+    /// The UTC day the task completed, or last changed if it hasn't.
+    Day,
+    /// The model each attempt reported with `--model`.
+    Model,
+}
+
+/// Token spend for one goal, agent, day or model.
+#[derive(Debug, Serialize)]
+pub struct TokenGroup {
+    /// Goal ID, agent name (`None` for unassigned work), `YYYY-MM-DD` or
+    /// model name (`None` for attempts that didn't name one).
+    pub key: Option<String>,
+    /// The goal's headline when grouping by goal.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    pub tokens: i64,
+    /// What the priced part of those tokens cost, once `[pricing]` has a
+    /// model in it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cost: Option<f64>,
+    pub tasks: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TokenStats {
+    pub by: GroupBy,
+    /// Spend across every group, including any cut by `top`.
+    pub total_tokens: i64,
+    /// The `[pricing]` currency, when any model is priced.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub currency: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub total_cost: Option<f64>,
+    /// Tokens from attempts whose model has no price, or that predate
+    /// attempts being recorded; left out of every cost.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub unpriced_tokens: Option<i64>,
+    pub groups: Vec<TokenGroup>,
+}
+
+/// Token spend grouped by `by`, biggest first, keeping the `top` largest
+/// groups if given. Tasks that have not reported tokens are left out. One
+/// pass over the tasks, so it stays cheap however long the history gets.
+/// Costs come from each attempt's model and the `[pricing]` config.
+pub fn tokens(by: GroupBy, top: Option<usize>, db: &Database) -> TokenStats {
+    let pricing = &db.config().pricing;
+    let priced = !pricing.models.is_empty();
+    let mut groups: HashMap<Option<String>, TokenGroup> = HashMap::new();
+    let mut total_tokens = 0;
+    let mut total_cost = 0.0;
+    let mut unpriced_tokens = 0;
+
+    for goal in db.list_goals() {
+        for task in db.list_tasks(goal.id()) {
+            let tokens = task.metrics().tokens();
+            if tokens == 0 {
+                continue;
+            }
+            total_tokens += tokens;
+
+            let mut task_cost = 0.0;
+            let mut priced_tokens = 0;
+            for attempt in task.attempts() {
+                if let Some(cost) = pricing.cost(attempt) {
+                    task_cost += cost;
+                    priced_tokens += attempt.tokens();
+                }
+            }
+            total_cost += task_cost;
+            unpriced_tokens += (tokens - priced_tokens).max(0);
+
+            // Each attempt counts for whoever made it, so a task retried by
+            // another agent is split between them.
+            let mut shares: HashMap<Option<String>, (i64, f64)> = HashMap::new();
+            match by {
+                GroupBy::Goal => {
+                    shares.insert(Some(goal.id().to_string()), (tokens, task_cost));
+                }
+                GroupBy::Agent | GroupBy::Model if !task.attempts().is_empty() => {
+                    for attempt in task.attempts() {
+                        let key = if by == GroupBy::Agent {
+                            attempt.agent()
+                        } else {
+                            attempt.model()
+                        };
+                        let share = shares.entry(key.map(str::to_string)).or_default();
+                        share.0 += attempt.tokens();
+                        share.1 += pricing.cost(attempt).unwrap_or_default();
+                    }
+                }
+                GroupBy::Agent => {
+                    shares.insert(task.assignee().map(str::to_string), (tokens, 0.0));
+                }
+                GroupBy::Model => {
+                    shares.insert(None, (tokens, 0.0));
+                }
+                GroupBy::Day => {
+                    let at = task.completed_at().unwrap_or_else(|| task.updated_at());
+                    shares.insert(Some(utc_date(at).to_string()), (tokens, task_cost));
+                }
+            }
+            for (key, (tokens, cost)) in shares {
+                let group = groups.entry(key.clone()).or_insert_with(|| TokenGroup {
+                    key,
+                    label: (by == GroupBy::Goal).then(|| goal.headline().to_string()),
+                    tokens: 0,
+                    cost: priced.then_some(0.0),
+                    tasks: 0,
+                });
+                group.tokens += tokens;
+                if let Some(total) = &mut group.cost {
+                    *total += cost;
+                }
+                group.tasks += 1;
+            }
+        }
+    }
+
+    let mut groups: Vec<TokenGroup> = groups.into_values().collect();
+    groups.sort_by(|a, b| b.tokens.cmp(&a.tokens).then_with(|| a.key.cmp(&b.key)));
+    if let Some(top) = top {
+        groups.truncate(top);
+    }
+
+    TokenStats {
+        by,
+        total_tokens,
+        currency: priced.then(|| pricing.currency.clone()),
+        total_cost: priced.then_some(total_cost),
+        unpriced_tokens: priced.then_some(unpriced_tokens),
+        groups,
+    }
+}
+
+/// A model and who served it.
+type ModelKey<'a> = (Option<&'a str>, Option<&'a str>);
+
+/// How attempts made with one model went.
+#[derive(Debug, Default, Serialize)]
+pub struct ModelStats {
+    /// `None` for attempts that didn't name a model.
+    pub model: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub provider: Option<String>,
+    pub attempts: usize,
+    pub completed: usize,
+    pub failed: usize,
+    /// Share of attempts that completed, from 0 to 1.
+    pub success_rate: f64,
+    /// Attempts that were retries of an earlier one.
+    pub retries: usize,
+    pub tokens: i64,
+    pub avg_tokens: i64,
+    /// Mean time per attempt, over the attempts that reported one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub avg_elapsed_ms: Option<i64>,
+}
+
+/// Every recorded attempt grouped by model and provider, most used first,
+/// limited to goal `goal`'s tasks if given so the models are compared on
+/// similar work.
+pub fn models(goal: Option<&str>, db: &Database) -> Result<Vec<ModelStats>> {
+    let goals = match goal {
+        Some(id) => vec![db.require_goal(id)?],
+        None => db.list_goals(),
+    };
+    let mut groups: HashMap<ModelKey, ModelStats> = HashMap::new();
+    // Attempts that reported a time, and the time they add up to.
+    let mut timed: HashMap<ModelKey, (i64, i64)> = HashMap::new();
+    for goal in goals {
+        for attempt in db
+            .list_tasks(goal.id())
+            .into_iter()
+            .flat_map(Task::attempts)
+        {
+            let key = (attempt.model(), attempt.provider());
+            let group = groups.entry(key).or_insert_with(|| ModelStats {
+                model: key.0.map(str::to_string),
+                provider: key.1.map(str::to_string),
+                ..ModelStats::default()
+            });
+            group.attempts += 1;
+            match attempt.outcome() {
+                TaskState::Completed => group.completed += 1,
+                _ => group.failed += 1,
+            }
+            if attempt.number() > 1 {
+                group.retries += 1;
+            }
+            group.tokens += attempt.tokens();
+            if attempt.elapsed_ms() > 0 {
+                let (count, total) = timed.entry(key).or_default();
+                *count += 1;
+                *total += attempt.elapsed_ms();
+            }
+        }
+    }
+
+    let mut stats: Vec<ModelStats> = groups
+        .into_iter()
+        .map(|(key, mut group)| {
+            let attempts = i64::try_from(group.attempts).unwrap_or(i64::MAX);
+            #[allow(clippy::cast_precision_loss)]
+            let success_rate = group.completed as f64 / group.attempts as f64;
+            group.success_rate = success_rate;
+            group.avg_tokens = group.tokens / attempts;
+            group.avg_elapsed_ms = timed.get(&key).map(|(count, total)| total / count);
+            group
+        })
+        .collect();
+    stats.sort_by(|a, b| {
+        b.attempts
+            .cmp(&a.attempts)
+            .then_with(|| a.model.cmp(&b.model))
+            .then_with(|| a.provider.cmp(&b.provider))
+    });
+    Ok(stats)
+}
+
+/// Tasks completed on one UTC day.
+#[derive(Debug, Serialize)]
+pub struct DayActivity {
+    pub date: Date,
+    pub completed: usize,
+}
+
+/// Task completions per day over whole weeks, Monday to Sunday, ending with
+/// the week containing `today`.
+#[derive(Debug, Serialize)]
+pub struct Activity {
+    pub weeks: usize,
+    /// Every day from the first Monday up to and including today.
+    pub days: Vec<DayActivity>,
+    pub total: usize,
+}
+
+/// Completions per UTC day over the last `weeks` weeks, by `completed_at`.
+pub fn activity(weeks: usize, today: Date, db: &Database) -> Activity {
+    let back = i64::from(today.weekday().to_monday_zero_offset())
+        + 7 * (i64::try_from(weeks).unwrap_or(i64::MAX / 7) - 1);
+    let start = today.saturating_sub(Span::new().days(back));
+
+    let mut counts: HashMap<Date, usize> = HashMap::new();
+    for goal in db.list_goals() {
+        for task in db.list_tasks(goal.id()) {
+            if let Some(at) = task.completed_at() {
+                *counts.entry(utc_date(at)).or_default() += 1;
+            }
+        }
+    }
+
+    let days: Vec<DayActivity> = start
+        .series(Span::new().days(1))
+        .take_while(|date| *date <= today)
+        .map(|date| DayActivity {
+            date,
+            completed: counts.get(&date).copied().unwrap_or_default(),
+        })
+        .collect();
+    Activity {
+        weeks,
+        total: days.iter().map(|d| d.completed).sum(),
+        days,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Attempt, Outcome, ReasonCode, TaskMetrics, TaskState};
+    use crate::testing::{GoalBuilder, TaskBuilder, TempProject};
+    use rstest::rstest;
+
+    fn spend(project: &mut TempProject, goal: &str, agent: Option<&str>, tokens: i64) {
+        let mut task = TaskBuilder::new(goal, "work").metrics(TaskMetrics::new(tokens, 0, 0));
+        if let Some(agent) = agent {
+            task = task.assignee(agent);
+        }
+        project.add_task(task).unwrap();
+    }
+
+    // Groups are ranked by spend, unassigned work is its own group, tasks
+    // without tokens are skipped, and `top` keeps the total intact.
+    #[rstest]
+    #[case::goal(GroupBy::Goal, None, vec![(Some("g2"), 500, 1), (Some("g1"), 300, 3)])]
+    #[case::agent(GroupBy::Agent, None, vec![(Some("ann"), 600, 2), (None, 200, 2)])]
+    #[case::top(GroupBy::Agent, Some(1), vec![(Some("ann"), 600, 2)])]
+    fn groups_spend(
+        #[case] by: GroupBy,
+        #[case] top: Option<usize>,
+        #[case] expected: Vec<(Option<&str>, i64, usize)>,
+    ) {
+        let mut project = TempProject::new().unwrap();
+        project.add_goal(GoalBuilder::new("One").id("g1")).unwrap();
+        project.add_goal(GoalBuilder::new("Two").id("g2")).unwrap();
+        spend(&mut project, "g1", Some("ann"), 100);
+        spend(&mut project, "g1", None, 150);
+        spend(&mut project, "g1", None, 50);
+        spend(&mut project, "g1", Some("bob"), 0);
+        spend(&mut project, "g2", Some("ann"), 500);
+
+        let stats = tokens(by, top, project.db());
+        assert_eq!(stats.total_tokens, 800);
+        let got: Vec<_> = stats
+            .groups
+            .iter()
+            .map(|g| (g.key.as_deref(), g.tokens, g.tasks))
+            .collect();
+        assert_eq!(got, expected);
+    }
+
+    // Attempts are priced by the model they name; unknown models and
+    // tasks without attempts add tokens but no cost.
+    #[rstest]
+    #[allow(clippy::cast_possible_truncation)]
+    fn prices_spend_by_model() {
+        let mut project = TempProject::new().unwrap();
+        std::fs::write(
+            project.radial_dir().join("config.toml"),
+            "[pricing.models.sonnet]\nprompt = 3.0\ncompletion = 15.0\n",
+        )
+        .unwrap();
+        project.reload().unwrap();
+        project.add_goal(GoalBuilder::new("One").id("g1")).unwrap();
+        project.add_goal(GoalBuilder::new("Two").id("g2")).unwrap();
+        let runs = [
+            ("t1", "g1", "sonnet", 1_000_000, 0),
+            ("t1", "g1", "mystery", 500_000, 0),
+            ("t2", "g2", "sonnet", 0, 100_000),
+        ];
+        for (id, goal, model, prompt, completion) in runs {
+            if project.db().get_task(id).is_none() {
+                project
+                    .add_task(TaskBuilder::new(goal, "work").id(id))
+                    .unwrap();
+            }
+            let attempt = Attempt::new(1, None, TaskState::Failed, None, jiff::Timestamp::now())
+                .with_spend(prompt + completion, prompt, completion, 0)
+                .with_model(Some(model.to_string()));
+            project
+                .db_mut()
+                .get_task_mut(id)
+                .unwrap()
+                .add_attempt(attempt);
+        }
+        spend(&mut project, "g2", None, 200);
+
+        let cost = |stats: &TokenStats| -> Vec<(Option<String>, i64)> {
+            stats
+                .groups
+                .iter()
+                .map(|g| (g.key.clone(), (g.cost.unwrap() * 100.0).round() as i64))
+                .collect()
+        };
+        let by_model = tokens(GroupBy::Model, None, project.db());
+        assert_eq!(by_model.currency.as_deref(), Some("USD"));
+        assert_eq!(by_model.unpriced_tokens, Some(500_200));
+        assert_eq!(
+            cost(&by_model),
+            [
+                (Some("sonnet".to_string()), 450),
+                (Some("mystery".to_string()), 0),
+                (None, 0),
+            ]
+        );
+        let by_goal = tokens(GroupBy::Goal, None, project.db());
+        assert_eq!(
+            cost(&by_goal),
+            [(Some("g1".to_string()), 300), (Some("g2".to_string()), 150)]
+        );
+        assert!((by_goal.total_cost.unwrap() - 4.5).abs() < 1e-9);
+    }
+
+    // Attempts are grouped by model and provider with their outcomes,
+    // retries and averages; `goal` narrows them to one goal's tasks.
+    #[rstest]
+    fn compares_models() {
+        let mut project = TempProject::new().unwrap();
+        project.add_goal(GoalBuilder::new("One").id("g1")).unwrap();
+        project.add_goal(GoalBuilder::new("Two").id("g2")).unwrap();
+        let runs = [
+            (
+                "t1",
+                "g1",
+                Some("sonnet"),
+                1,
+                TaskState::Failed,
+                1000,
+                60_000,
+            ),
+            ("t1", "g1", Some("gpt-4o"), 2, TaskState::Completed, 3000, 0),
+            (
+                "t2",
+                "g1",
+                Some("sonnet"),
+                1,
+                TaskState::Completed,
+                2000,
+                120_000,
+            ),
+            ("t3", "g2", Some("sonnet"), 1, TaskState::Completed, 600, 0),
+            ("t4", "g2", None, 1, TaskState::Completed, 100, 0),
+        ];
+        for (id, goal, model, number, outcome, tokens, elapsed) in runs {
+            if project.db().get_task(id).is_none() {
+                project
+                    .add_task(TaskBuilder::new(goal, "work").id(id))
+                    .unwrap();
+            }
+            let provider = model.map(|m| if m == "sonnet" { "anthropic" } else { "openai" });
+            let attempt = Attempt::new(number, None, outcome, None, jiff::Timestamp::now())
+                .with_spend(tokens, 0, 0, elapsed)
+                .with_model(model.map(str::to_string))
+                .with_provider(provider.map(str::to_string));
+            project
+                .db_mut()
+                .get_task_mut(id)
+                .unwrap()
+                .add_attempt(attempt);
+        }
+
+        let all = models(None, project.db()).unwrap();
+        let got: Vec<_> = all
+            .iter()
+            .map(|m| {
+                (
+                    m.model.as_deref(),
+                    m.provider.as_deref(),
+                    m.attempts,
+                    m.completed,
+                    m.retries,
+                    m.avg_tokens,
+                    m.avg_elapsed_ms,
+                )
+            })
+            .collect();
+        assert_eq!(
+            got,
+            [
+                (
+                    Some("sonnet"),
+                    Some("anthropic"),
+                    3,
+                    2,
+                    0,
+                    1200,
+                    Some(90_000)
+                ),
+                (None, None, 1, 1, 0, 100, None),
+                (Some("gpt-4o"), Some("openai"), 1, 1, 1, 3000, None),
+            ]
+        );
+        assert!((all[0].success_rate - 2.0 / 3.0).abs() < 1e-9);
+
+        let g1 = models(Some("g1"), project.db()).unwrap();
+        assert_eq!((g1.len(), g1[0].attempts), (2, 2));
+        assert!(models(Some("nope"), project.db()).is_err());
+    }
+
+    // Days run from the Monday `weeks` weeks back to today, and completions
+    // land on the day they happened; earlier ones fall outside the window.
+    #[rstest]
+    fn counts_completions_per_day() {
+        let mut project = TempProject::new().unwrap();
+        project.add_goal(GoalBuilder::new("One").id("g1")).unwrap();
+        for id in ["t1", "t2"] {
+            project
+                .add_task(
+                    TaskBuilder::new("g1", "work")
+                        .id(id)
+                        .state(TaskState::InProgress),
+                )
+                .unwrap();
+            let task = project.db_mut().get_task_mut(id).unwrap();
+            task.complete(
+                Outcome::new("done".into(), vec![]),
+                TaskMetrics::default(),
+                ReasonCode::UserRequest,
+            );
+        }
+        project.add_task(TaskBuilder::new("g1", "open")).unwrap();
+        let done = utc_date(jiff::Timestamp::now());
+
+        let now = activity(2, done, project.db());
+        assert_eq!(now.days[0].date.weekday(), jiff::civil::Weekday::Monday);
+        assert_eq!(
+            now.days.len(),
+            8 + usize::try_from(done.weekday().to_monday_zero_offset()).unwrap()
+        );
+        assert_eq!(now.days.last().unwrap().completed, 2);
+        assert_eq!(now.total, 2);
+
+        let later = activity(2, done.saturating_add(Span::new().days(21)), project.db());
+        assert_eq!(later.total, 0);
+    }
+}
@@ -1,8 +1,12 @@
-use anyhow::{Result, anyhow};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use jiff::{SignedDuration, Timestamp};
 use serde::Serialize;
 
+use super::wip::{self, Wip};
 use crate::db::Database;
-use crate::models::{Goal, Metrics, Task};
+use crate::error::Result;
+use crate::models::{Goal, Metrics, Task, TaskState, TaskSummary};
 
 #[derive(Debug, Serialize)]
 pub struct GoalStatus {
@@ -10,6 +14,9 @@ pub struct GoalStatus {
     goal: Goal,
     tasks: Vec<Task>,
     metrics: Metrics,
+    /// Seconds in progress for each stale task, keyed by task ID.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    stale: BTreeMap<String, i64>,
 }
 
 impl GoalStatus {
@@ -24,6 +31,54 @@ impl GoalStatus {
     pub fn metrics(&self) -> &Metrics {
         &self.metrics
     }
+
+    pub fn stale_age(&self, task_id: &str) -> Option<SignedDuration> {
+        self.stale
+            .get(task_id)
+            .map(|&secs| SignedDuration::from_secs(secs))
+    }
+
+    pub fn rows(&self) -> GoalRows<'_> {
+        GoalRows {
+            goal: &self.goal,
+            tasks: self.tasks.iter().map(Task::summary).collect(),
+            metrics: self.metrics.clone(),
+            stale: self.stale.clone(),
+        }
+    }
+}
+
+/// A goal as the `rd status <goal>` table shows it: summary rows borrowed
+/// from the store in place of whole tasks.
+pub struct GoalRows<'a> {
+    pub goal: &'a Goal,
+    pub tasks: Vec<TaskSummary<'a>>,
+    pub metrics: Metrics,
+    /// Seconds in progress for each stale task, keyed by task ID.
+    pub stale: BTreeMap<String, i64>,
+}
+
+impl GoalRows<'_> {
+    pub fn stale_age(&self, task_id: &str) -> Option<SignedDuration> {
+        self.stale
+            .get(task_id)
+            .map(|&secs| SignedDuration::from_secs(secs))
+    }
+}
+
+/// The rows of `goal_id`'s table, limited to `agent`'s tasks if given.
+pub fn goal_rows<'a>(goal_id: &str, agent: Option<&str>, db: &'a Database) -> Result<GoalRows<'a>> {
+    let goal = db.require_goal(goal_id)?;
+    let mut tasks = db.list_task_summaries(goal_id);
+    if let Some(agent) = agent {
+        tasks.retain(|t| t.assignee == Some(agent));
+    }
+    Ok(GoalRows {
+        goal,
+        tasks,
+        metrics: db.compute_goal_metrics(goal_id),
+        stale: stale_ages(db.list_tasks(goal_id), db),
+    })
 }
 
 #[derive(Debug, Serialize)]
@@ -31,6 +86,8 @@ pub struct GoalSummary {
     #[serde(flatten)]
     goal: Goal,
     computed_metrics: Metrics,
+    #[serde(skip_serializing_if = "is_zero")]
+    stale_tasks: usize,
 }
 
 impl GoalSummary {
@@ -41,63 +98,457 @@ impl GoalSummary {
     pub fn computed_metrics(&self) -> &Metrics {
         &self.computed_metrics
     }
+
+    pub fn stale_tasks(&self) -> usize {
+        self.stale_tasks
+    }
+}
+
+#[allow(clippy::trivially_copy_pass_by_ref)]
+fn is_zero(n: &usize) -> bool {
+    *n == 0
+}
+
+/// An in-progress task that has been running longer than the stale threshold.
+#[derive(Debug, Serialize)]
+pub struct StaleTask {
+    #[serde(flatten)]
+    task: Task,
+    age_seconds: i64,
+}
+
+impl StaleTask {
+    pub fn task(&self) -> &Task {
+        &self.task
+    }
+
+    pub fn age(&self) -> SignedDuration {
+        SignedDuration::from_secs(self.age_seconds)
+    }
+}
+
+/// Tasks assigned to a single agent across every goal.
+#[derive(Debug, Serialize)]
+pub struct AgentStatus {
+    agent: String,
+    tasks: Vec<Task>,
+}
+
+impl AgentStatus {
+    pub fn agent(&self) -> &str {
+        &self.agent
+    }
+
+    pub fn tasks(&self) -> &[Task] {
+        &self.tasks
+    }
+}
+
+/// Task totals for a goal and every goal nested under it.
+#[derive(Debug, Default, Clone, Copy, Serialize)]
+pub struct Rollup {
+    pub goals: usize,
+    pub tasks: i64,
+    pub completed: i64,
+    pub failed: i64,
+    pub tokens: i64,
+}
+
+impl Rollup {
+    fn add(&mut self, other: Self) {
+        self.goals += other.goals;
+        self.tasks += other.tasks;
+        self.completed += other.completed;
+        self.failed += other.failed;
+        self.tokens += other.tokens;
+    }
+
+    /// Share of tasks completed, rounded down.
+    pub fn percent(&self) -> i64 {
+        if self.tasks == 0 {
+            0
+        } else {
+            self.completed * 100 / self.tasks
+        }
+    }
+}
+
+/// A goal in the hierarchy with its sub-goals and the rollup of the whole subtree.
+#[derive(Debug, Serialize)]
+pub struct GoalNode {
+    #[serde(flatten)]
+    goal: Goal,
+    rollup: Rollup,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    children: Vec<GoalNode>,
+}
+
+impl GoalNode {
+    pub fn goal(&self) -> &Goal {
+        &self.goal
+    }
+
+    pub fn rollup(&self) -> &Rollup {
+        &self.rollup
+    }
+
+    pub fn children(&self) -> &[GoalNode] {
+        &self.children
+    }
+}
+
+/// Every top-level goal as a tree, plus the totals across all of them.
+#[derive(Debug, Serialize)]
+pub struct GoalTree {
+    pub program: Rollup,
+    pub roots: Vec<GoalNode>,
 }
 
-/// Result of a status query - can be a single task, single goal, or all goals.
+/// Result of a status query - can be a single task, single goal, all goals,
+/// one agent's workload, the stale tasks in scope, or the goal hierarchy.
 #[derive(Debug)]
 pub enum StatusResult {
     Task(Task),
     Goal(GoalStatus),
-    AllGoals(Vec<GoalSummary>),
+    /// The goals, with work in progress against the `[wip]` limits.
+    AllGoals(Vec<GoalSummary>, Wip),
+    Agent(AgentStatus),
+    Stale(Vec<StaleTask>),
+    Tree(GoalTree),
 }
 
 pub fn run(
     goal_id: Option<String>,
     task_id: Option<String>,
+    agent: Option<String>,
+    stale: bool,
+    tree: bool,
+    branch: Option<&str>,
     db: &Database,
 ) -> Result<StatusResult> {
     if let Some(tid) = task_id {
         return get_task(&tid, db).map(StatusResult::Task);
     }
 
+    if tree {
+        return get_tree(goal_id.as_deref(), branch, db).map(StatusResult::Tree);
+    }
+
+    if stale {
+        return get_stale(goal_id.as_deref(), agent.as_deref(), branch, db)
+            .map(StatusResult::Stale);
+    }
+
     if let Some(gid) = goal_id {
-        return get_goal(&gid, db).map(StatusResult::Goal);
+        let mut status = get_goal(&gid, db)?;
+        if let Some(agent) = agent {
+            status
+                .tasks
+                .retain(|t| t.assignee() == Some(agent.as_str()));
+        }
+        return Ok(StatusResult::Goal(status));
+    }
+
+    if let Some(agent) = agent {
+        return Ok(StatusResult::Agent(get_agent(agent, branch, db)));
     }
 
-    Ok(StatusResult::AllGoals(get_all_goals(db)))
+    Ok(StatusResult::AllGoals(
+        get_all_goals(branch, db),
+        wip::run(db),
+    ))
 }
 
 fn get_task(task_id: &str, db: &Database) -> Result<Task> {
-    db.get_task(task_id)
-        .cloned()
-        .ok_or_else(|| anyhow!("Task not found: {task_id}"))
+    db.require_task(task_id).cloned()
 }
 
 fn get_goal(goal_id: &str, db: &Database) -> Result<GoalStatus> {
-    let goal = db
-        .get_goal(goal_id)
-        .ok_or_else(|| anyhow!("Goal not found: {goal_id}"))?
-        .clone();
+    let goal = db.require_goal(goal_id)?.clone();
 
     let tasks: Vec<Task> = db.list_tasks(goal_id).into_iter().cloned().collect();
     let metrics = db.compute_goal_metrics(goal_id);
+    let stale = stale_ages(&tasks, db);
 
     Ok(GoalStatus {
         goal,
         tasks,
         metrics,
+        stale,
     })
 }
 
-fn get_all_goals(db: &Database) -> Vec<GoalSummary> {
+/// Seconds in progress for each of `tasks` past the stale threshold.
+fn stale_ages<'a>(
+    tasks: impl IntoIterator<Item = &'a Task>,
+    db: &Database,
+) -> BTreeMap<String, i64> {
+    let threshold = db.config().status.stale_after;
+    let now = Timestamp::now();
+    tasks
+        .into_iter()
+        .filter_map(|t| Some((t.id().to_string(), stale_age(t, threshold, now)?.as_secs())))
+        .collect()
+}
+
+fn get_stale(
+    goal_id: Option<&str>,
+    agent: Option<&str>,
+    branch: Option<&str>,
+    db: &Database,
+) -> Result<Vec<StaleTask>> {
+    let goal_ids: Vec<&str> = match goal_id {
+        Some(id) => vec![db.require_goal(id)?.id()],
+        None => visible_goals(branch, db).iter().map(|g| g.id()).collect(),
+    };
+
+    let threshold = db.config().status.stale_after;
+    let now = Timestamp::now();
+    let mut stale: Vec<StaleTask> = goal_ids
+        .into_iter()
+        .flat_map(|id| db.list_tasks(id))
+        .filter(|t| agent.is_none() || t.assignee() == agent)
+        .filter_map(|t| {
+            Some(StaleTask {
+                task: t.clone(),
+                age_seconds: stale_age(t, threshold, now)?.as_secs(),
+            })
+        })
+        .collect();
+    stale.sort_by_key(|s| std::cmp::Reverse(s.age_seconds));
+    Ok(stale)
+}
+
+/// Build the goal hierarchy, rooted at `goal_id` or at every goal without a
+/// visible parent. Siblings are listed oldest first.
+fn get_tree(goal_id: Option<&str>, branch: Option<&str>, db: &Database) -> Result<GoalTree> {
+    let goals = visible_goals(branch, db);
+    let roots: Vec<&Goal> = if let Some(id) = goal_id {
+        vec![db.require_goal(id)?]
+    } else {
+        let is_visible = |id: &str| goals.iter().any(|g| g.id() == id);
+        goals
+            .iter()
+            .rev()
+            .copied()
+            .filter(|g| g.parent_id().is_none_or(|p| !is_visible(p)))
+            .collect()
+    };
+
+    let metrics = db.compute_all_goal_metrics();
+    let mut visited = BTreeSet::new();
+    let roots: Vec<GoalNode> = roots
+        .into_iter()
+        .map(|root| build_node(root, &goals, &metrics, &mut visited))
+        .collect();
+
+    let mut program = Rollup::default();
+    for root in &roots {
+        program.add(root.rollup);
+    }
+    Ok(GoalTree { program, roots })
+}
+
+fn build_node(
+    goal: &Goal,
+    goals: &[&Goal],
+    metrics: &HashMap<&str, Metrics>,
+    visited: &mut BTreeSet<String>,
+) -> GoalNode {
+    visited.insert(goal.id().to_string());
+
+    let own = metrics.get(goal.id()).cloned().unwrap_or_default();
+    let mut rollup = Rollup {
+        goals: 1,
+        tasks: own.task_count(),
+        completed: own.tasks_completed(),
+        failed: own.tasks_failed(),
+        tokens: own.total_tokens(),
+    };
+
+    // `goals` is newest first; walk it backwards so children read in creation order.
+    let mut children = Vec::new();
+    for child in goals.iter().rev() {
+        // A parent loop written by hand must not recurse forever.
+        if child.parent_id() == Some(goal.id()) && !visited.contains(child.id()) {
+            let node = build_node(child, goals, metrics, visited);
+            rollup.add(node.rollup);
+            children.push(node);
+        }
+    }
+
+    GoalNode {
+        goal: goal.clone(),
+        rollup,
+        children,
+    }
+}
+
+/// How long `task` has been in progress, if that is at least `threshold`.
+/// Tasks started before `started_at` was recorded fall back to `updated_at`.
+pub fn stale_age(task: &Task, threshold: SignedDuration, now: Timestamp) -> Option<SignedDuration> {
+    if task.state() != TaskState::InProgress {
+        return None;
+    }
+    let since = task.started_at().unwrap_or(task.updated_at());
+    let age = now.duration_since(since);
+    (age >= threshold).then_some(age)
+}
+
+fn get_agent(agent: String, branch: Option<&str>, db: &Database) -> AgentStatus {
+    let tasks = visible_goals(branch, db)
+        .into_iter()
+        .flat_map(|goal| db.list_tasks(goal.id()))
+        .filter(|t| t.assignee() == Some(agent.as_str()))
+        .cloned()
+        .collect();
+
+    AgentStatus { agent, tasks }
+}
+
+/// Goals not scoped to a branch other than `branch`.
+fn visible_goals<'a>(branch: Option<&str>, db: &'a Database) -> Vec<&'a Goal> {
     db.list_goals()
         .into_iter()
-        .map(|goal| {
-            let computed_metrics = db.compute_goal_metrics(goal.id());
-            GoalSummary {
-                goal: goal.clone(),
-                computed_metrics,
-            }
+        .filter(|g| g.visible_on(branch))
+        .collect()
+}
+
+fn get_all_goals(branch: Option<&str>, db: &Database) -> Vec<GoalSummary> {
+    let threshold = db.config().status.stale_after;
+    let now = Timestamp::now();
+    let mut metrics = db.compute_all_goal_metrics();
+    let mut stale: HashMap<&str, usize> = HashMap::new();
+    for task in db.all_tasks() {
+        if stale_age(task, threshold, now).is_some() {
+            *stale.entry(task.goal_id()).or_default() += 1;
+        }
+    }
+    visible_goals(branch, db)
+        .into_iter()
+        .map(|goal| GoalSummary {
+            goal: goal.clone(),
+            computed_metrics: metrics.remove(goal.id()).unwrap_or_default(),
+            stale_tasks: stale.get(goal.id()).copied().unwrap_or(0),
         })
         .collect()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::ReasonCode;
+    use rstest::rstest;
+
+    /// A task last touched `mins_ago` minutes ago, with no `started_at`.
+    fn task(state: TaskState, mins_ago: i64) -> Task {
+        let then = Timestamp::now() - SignedDuration::from_mins(mins_ago);
+        Task::new(
+            "t1".to_string(),
+            "g1".to_string(),
+            "test task".to_string(),
+            None,
+            state,
+            Vec::new(),
+            then,
+            then,
+        )
+    }
+
+    // Only in-progress tasks past the threshold are stale. Tasks without
+    // started_at (written by older versions) are aged from updated_at.
+    #[rstest]
+    #[case::fresh(TaskState::InProgress, 10, false)]
+    #[case::old(TaskState::InProgress, 90, true)]
+    #[case::pending(TaskState::Pending, 90, false)]
+    #[case::completed(TaskState::Completed, 90, false)]
+    fn stale_age_respects_state_and_threshold(
+        #[case] state: TaskState,
+        #[case] mins_ago: i64,
+        #[case] expected: bool,
+    ) {
+        let age = stale_age(
+            &task(state, mins_ago),
+            SignedDuration::from_hours(1),
+            Timestamp::now(),
+        );
+        assert_eq!(age.is_some(), expected);
+    }
+
+    // started_at takes precedence over updated_at once the task is started.
+    #[rstest]
+    fn stale_age_uses_started_at() {
+        let mut t = task(TaskState::Pending, 90);
+        t.transition(
+            &TaskState::Pending,
+            TaskState::InProgress,
+            ReasonCode::UserRequest,
+        );
+        assert!(stale_age(&t, SignedDuration::from_hours(1), Timestamp::now()).is_none());
+    }
+
+    fn goal(id: &str, parent: Option<&str>, created_secs: i64) -> Goal {
+        let at = Timestamp::UNIX_EPOCH + SignedDuration::from_secs(created_secs);
+        Goal::new(
+            id.to_string(),
+            parent.map(ToString::to_string),
+            format!("Goal {id}"),
+            crate::models::GoalState::Pending,
+            at,
+            at,
+            None,
+            Metrics::default(),
+        )
+    }
+
+    // Each node rolls up its own tasks plus every descendant's; the program
+    // total covers all roots. Children appear in creation order.
+    #[rstest]
+    fn tree_rolls_up_nested_goals() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let mut db = Database::open(dir.path()).unwrap();
+        db.create_goal(goal("root", None, 1)).unwrap();
+        db.create_goal(goal("a", Some("root"), 2)).unwrap();
+        db.create_goal(goal("b", Some("root"), 3)).unwrap();
+        db.create_goal(goal("leaf", Some("a"), 4)).unwrap();
+        db.create_goal(goal("other", None, 5)).unwrap();
+        for (id, goal_id, state) in [
+            ("t1", "root", TaskState::Completed),
+            ("t2", "a", TaskState::Pending),
+            ("t3", "leaf", TaskState::Completed),
+            ("t4", "other", TaskState::Failed),
+        ] {
+            let now = Timestamp::now();
+            db.create_task(Task::new(
+                id.to_string(),
+                goal_id.to_string(),
+                "task".to_string(),
+                None,
+                state,
+                Vec::new(),
+                now,
+                now,
+            ))
+            .unwrap();
+        }
+
+        let tree = get_tree(None, None, &db).unwrap();
+        let roots: Vec<&str> = tree.roots.iter().map(|n| n.goal().id()).collect();
+        assert_eq!(roots, ["root", "other"]);
+
+        let root = &tree.roots[0];
+        let children: Vec<&str> = root.children().iter().map(|n| n.goal().id()).collect();
+        assert_eq!(children, ["a", "b"]);
+        assert_eq!(root.rollup().goals, 4);
+        assert_eq!(root.rollup().tasks, 3);
+        assert_eq!(root.rollup().completed, 2);
+        assert_eq!(root.children()[0].rollup().tasks, 2);
+
+        assert_eq!(tree.program.goals, 5);
+        assert_eq!(tree.program.tasks, 4);
+        assert_eq!(tree.program.failed, 1);
+
+        let subtree = get_tree(Some("a"), None, &db).unwrap();
+        assert_eq!(subtree.program.goals, 2);
+    }
+}
@@ -0,0 +1,297 @@
+use std::collections::{HashMap, HashSet};
+
+use serde::Serialize;
+
+use crate::db::Database;
+use crate::error::Result;
+use crate::models::{ReasonCode, Task, TaskState};
+
+/// Leading verbs that usually describe work layered on top of an earlier task.
+const FOLLOW_ON_VERBS: &[&str] = &[
+    "test",
+    "tests",
+    "document",
+    "docs",
+    "verify",
+    "validate",
+    "deploy",
+    "review",
+    "integrate",
+    "benchmark",
+    "release",
+    "wire",
+];
+
+/// Words that can come before a follow-on verb, as in "write tests for".
+const LEADING_FILLER: &[&str] = &["write", "add"];
+
+const STOPWORDS: &[&str] = &[
+    "about", "after", "against", "also", "before", "between", "does", "each", "from", "have",
+    "into", "make", "more", "must", "only", "over", "should", "some", "than", "that", "their",
+    "them", "then", "there", "these", "this", "those", "under", "using", "when", "where", "which",
+    "while", "will", "with", "within", "without", "write",
+];
+
+/// A proposed `blocked_by` edge: `task_id` should wait on `blocked_by`.
+#[derive(Debug, Clone, Serialize)]
+pub struct Suggestion {
+    pub task_id: String,
+    pub blocked_by: String,
+    pub reason: String,
+}
+
+pub fn run(goal_id: &str, apply: bool, db: &mut Database) -> Result<Vec<Suggestion>> {
+    if apply {
+        db.require_goal_writable(goal_id)?;
+    } else {
+        db.require_goal(goal_id)?;
+    }
+
+    let suggestions = suggest(&db.list_tasks(goal_id));
+
+    if apply {
+        let base = db.base_path().to_owned();
+        for suggestion in &suggestions {
+            let blocker_done = db
+                .get_task(&suggestion.blocked_by)
+                .is_some_and(|t| t.state() == TaskState::Completed);
+
+            let task = db.get_task_mut(&suggestion.task_id).unwrap();
+            let mut blocked_by = task.blocked_by().to_vec();
+            blocked_by.push(suggestion.blocked_by.clone());
+            task.set_blocked_by(blocked_by);
+            if !blocker_done {
+                task.transition(
+                    &TaskState::Pending,
+                    TaskState::Blocked,
+                    ReasonCode::AgentAuto,
+                );
+            }
+            task.write_file(&base)?;
+        }
+    }
+
+    Ok(suggestions)
+}
+
+/// Propose dependencies between tasks based on contract and description overlap.
+/// Existing edges, edges that would create a cycle, and tasks that have already
+/// started are skipped.
+pub fn suggest(tasks: &[&Task]) -> Vec<Suggestion> {
+    let mut edges: HashMap<&str, Vec<&str>> = tasks
+        .iter()
+        .map(|t| (t.id(), t.blocked_by().iter().map(String::as_str).collect()))
+        .collect();
+    let mut suggestions = Vec::new();
+
+    for task in tasks {
+        if !matches!(task.state(), TaskState::Pending | TaskState::Blocked) {
+            continue;
+        }
+
+        for candidate in tasks {
+            if candidate.id() == task.id()
+                || task.blocked_by().iter().any(|b| b == candidate.id())
+                || depends_on(&edges, candidate.id(), task.id())
+            {
+                continue;
+            }
+
+            if let Some(reason) = reason_for(task, candidate) {
+                edges.entry(task.id()).or_default().push(candidate.id());
+                suggestions.push(Suggestion {
+                    task_id: task.id().to_string(),
+                    blocked_by: candidate.id().to_string(),
+                    reason,
+                });
+            }
+        }
+    }
+
+    suggestions
+}
+
+/// Why `task` looks like it should wait on `candidate`, if it does.
+fn reason_for(task: &Task, candidate: &Task) -> Option<String> {
+    let produced: HashSet<String> = candidate
+        .contract()
+        .map(|c| artifacts(c.produces()))
+        .unwrap_or_default()
+        .into_iter()
+        .chain(
+            candidate
+                .result()
+                .into_iter()
+                .flat_map(|r| r.artifacts().iter().cloned()),
+        )
+        .collect();
+    let needed: HashSet<String> = task
+        .contract()
+        .map(|c| artifacts(c.receives()))
+        .unwrap_or_default()
+        .into_iter()
+        .chain(artifacts(task.description()))
+        .collect();
+
+    let mut shared: Vec<&String> = produced.intersection(&needed).collect();
+    if !shared.is_empty() {
+        shared.sort();
+        return Some(format!(
+            "needs {} produced by {}",
+            shared[0],
+            candidate.id()
+        ));
+    }
+
+    if let (Some(needs), Some(makes)) = (task.contract(), candidate.contract()) {
+        let mut shared: Vec<String> = keywords(needs.receives())
+            .intersection(&keywords(makes.produces()))
+            .cloned()
+            .collect();
+        if shared.len() >= 2 {
+            shared.sort();
+            return Some(format!("receives overlaps produces: {}", shared.join(", ")));
+        }
+    }
+
+    if is_follow_on(task.description()) && !is_follow_on(candidate.description()) {
+        let mut shared: Vec<String> = keywords(task.description())
+            .intersection(&keywords(candidate.description()))
+            .filter(|w| !FOLLOW_ON_VERBS.contains(&w.as_str()))
+            .cloned()
+            .collect();
+        if !shared.is_empty() {
+            shared.sort();
+            return Some(format!("follow-on work for: {}", shared.join(", ")));
+        }
+    }
+
+    None
+}
+
+/// True if `from` already waits on `to`, directly or transitively.
+fn depends_on(edges: &HashMap<&str, Vec<&str>>, from: &str, to: &str) -> bool {
+    let mut stack = vec![from];
+    let mut seen = HashSet::new();
+    while let Some(id) = stack.pop() {
+        if id == to {
+            return true;
+        }
+        if seen.insert(id) {
+            stack.extend(edges.get(id).into_iter().flatten().copied());
+        }
+    }
+    false
+}
+
+/// True if the description opens with a follow-on verb, after any
+/// [`LEADING_FILLER`].
+fn is_follow_on(description: &str) -> bool {
+    description
+        .split_whitespace()
+        .map(str::to_lowercase)
+        .find(|word| !LEADING_FILLER.contains(&word.as_str()))
+        .is_some_and(|word| FOLLOW_ON_VERBS.contains(&word.as_str()))
+}
+
+/// Significant lowercase words, ignoring short words and common filler.
+pub(crate) fn keywords(text: &str) -> HashSet<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .map(str::to_lowercase)
+        .filter(|w| w.len() >= 4 && !STOPWORDS.contains(&w.as_str()))
+        .collect()
+}
+
+/// Tokens that look like file paths or file names, e.g. `src/db.rs`.
+pub(crate) fn artifacts(text: &str) -> HashSet<String> {
+    text.split(|c: char| c.is_whitespace() || matches!(c, ',' | ';' | '(' | ')' | '`' | '"'))
+        .map(|t| t.trim_end_matches(['.', ':']))
+        .filter(|t| {
+            t.contains('/')
+                || t.rsplit_once('.').is_some_and(|(stem, ext)| {
+                    !stem.is_empty() && ext.chars().all(char::is_alphanumeric) && !ext.is_empty()
+                })
+        })
+        .map(str::to_string)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Contract;
+    use jiff::Timestamp;
+    use rstest::rstest;
+
+    fn task(id: &str, description: &str, receives: &str, produces: &str) -> Task {
+        let now = Timestamp::now();
+        Task::new(
+            id.to_string(),
+            "g1".to_string(),
+            description.to_string(),
+            Some(Contract::new(
+                receives.to_string(),
+                produces.to_string(),
+                "check".to_string(),
+            )),
+            TaskState::Pending,
+            Vec::new(),
+            now,
+            now,
+        )
+    }
+
+    // A file named in one task's `produces` and another's `receives` is the
+    // strongest signal and should yield a single edge in the right direction.
+    #[rstest]
+    fn suggests_edge_from_artifact_overlap() {
+        let schema = task("a", "Design schema", "requirements", "dist/schema.json");
+        let client = task("b", "Generate client", "dist/schema.json", "client.rs");
+
+        let suggestions = suggest(&[&schema, &client]);
+        assert_eq!(suggestions.len(), 1);
+        assert_eq!(suggestions[0].task_id, "b");
+        assert_eq!(suggestions[0].blocked_by, "a");
+        assert!(suggestions[0].reason.contains("dist/schema.json"));
+    }
+
+    // "Write tests for X" following "Implement X" should be wired together
+    // even without contract overlap.
+    #[rstest]
+    fn suggests_edge_for_follow_on_description() {
+        let implement = task("a", "Implement login handler", "spec", "code");
+        let tests = task("b", "Write tests for login handler", "nothing", "report");
+
+        let suggestions = suggest(&[&implement, &tests]);
+        assert_eq!(suggestions.len(), 1);
+        assert_eq!(suggestions[0].task_id, "b");
+        assert!(suggestions[0].reason.contains("login"));
+    }
+
+    // Existing edges are not re-suggested, and the reverse edge would form a
+    // cycle so it must not be proposed either.
+    #[rstest]
+    fn skips_existing_and_cyclic_edges() {
+        let schema = task("a", "Design schema", "dist/client.rs", "dist/schema.json");
+        let mut client = task("b", "Generate client", "dist/schema.json", "dist/client.rs");
+        client.set_blocked_by(vec!["a".to_string()]);
+
+        assert!(suggest(&[&schema, &client]).is_empty());
+    }
+
+    // Unrelated tasks produce no suggestions.
+    #[rstest]
+    fn ignores_unrelated_tasks() {
+        let a = task("a", "Set up CI pipeline", "repo", "workflow");
+        let b = task("b", "Draft marketing copy", "brief", "copy");
+        assert!(suggest(&[&a, &b]).is_empty());
+    }
+
+    #[rstest]
+    #[case::path("reads src/db.rs", "src/db.rs")]
+    #[case::file_name("emits schema.json.", "schema.json")]
+    #[case::backticks("uses `config.toml`", "config.toml")]
+    fn artifacts_finds_file_like_tokens(#[case] text: &str, #[case] expected: &str) {
+        assert!(artifacts(text).contains(expected));
+    }
+}
@@ -0,0 +1,326 @@
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::fmt::Write as _;
+
+use jiff::Timestamp;
+
+use crate::db::Database;
+use crate::models::{Contract, GoalState, Priority, Task, TaskState};
+use crate::output::format_age;
+
+/// Rough token count for budgeting: about four characters per token.
+pub fn estimate_tokens(text: &str) -> usize {
+    text.chars().count().div_ceil(4)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Section {
+    Goals,
+    Ready,
+    Blockers,
+    Failures,
+}
+
+impl Section {
+    const ALL: [Self; 4] = [Self::Goals, Self::Ready, Self::Blockers, Self::Failures];
+
+    fn heading(self) -> &'static str {
+        match self {
+            Self::Goals => "Active goals:",
+            Self::Ready => "Ready tasks:",
+            Self::Blockers => "Top blockers:",
+            Self::Failures => "Recent failures:",
+        }
+    }
+}
+
+/// One line of the digest. Lower `rank` survives truncation first.
+struct Line {
+    section: Section,
+    rank: u8,
+    text: String,
+}
+
+/// A plain-text digest of the project for an agent's system prompt, kept
+/// within `max_tokens`. When the budget is tight, active goals go in first,
+/// then urgent ready work and the biggest blockers, then recent failures and
+/// the rest of the ready queue. Omitted lines are counted, not dropped
+/// silently.
+pub fn for_llm(max_tokens: usize, db: &Database) -> String {
+    let lines = collect(db, Timestamp::now());
+    let header = "# Radial project digest\n";
+
+    let mut budget = max_tokens.saturating_sub(estimate_tokens(header));
+    let mut order: Vec<usize> = (0..lines.len()).collect();
+    order.sort_by_key(|&i| (lines[i].rank, lines[i].section));
+
+    let mut kept = vec![false; lines.len()];
+    let mut taken: Vec<(usize, usize)> = Vec::new();
+    let mut opened: Vec<Section> = Vec::new();
+    for i in order {
+        let line = &lines[i];
+        let mut cost = estimate_tokens(&line.text) + 1;
+        if !opened.contains(&line.section) {
+            cost += estimate_tokens(line.section.heading()) + 1;
+        }
+        if cost <= budget {
+            budget -= cost;
+            kept[i] = true;
+            taken.push((i, cost));
+            opened.push(line.section);
+        }
+    }
+
+    // Give back the least important lines until the "(+N more)" notes fit.
+    while note_cost(&lines, &kept) > budget {
+        let Some((i, cost)) = taken.pop() else { break };
+        kept[i] = false;
+        budget += cost;
+    }
+
+    let mut out = String::from(header);
+    for section in Section::ALL {
+        let in_section: Vec<usize> = (0..lines.len())
+            .filter(|&i| lines[i].section == section)
+            .collect();
+        let shown: Vec<&str> = in_section
+            .iter()
+            .filter(|&&i| kept[i])
+            .map(|&i| lines[i].text.as_str())
+            .collect();
+        if shown.is_empty() {
+            continue;
+        }
+
+        let _ = writeln!(out, "\n{}", section.heading());
+        for text in &shown {
+            let _ = writeln!(out, "{text}");
+        }
+        let omitted = in_section.len() - shown.len();
+        if omitted > 0 {
+            let _ = writeln!(out, "(+{omitted} more)");
+        }
+    }
+
+    if lines.is_empty() {
+        out.push_str("\nNo active work.\n");
+    }
+    out
+}
+
+/// Tokens needed for the omission notes of sections that are shown but
+/// incomplete.
+fn note_cost(lines: &[Line], kept: &[bool]) -> usize {
+    let mut cost = 0;
+    for section in Section::ALL {
+        let (shown, total) = lines
+            .iter()
+            .zip(kept)
+            .filter(|(l, _)| l.section == section)
+            .fold((0, 0), |(shown, total), (_, &k)| {
+                (shown + usize::from(k), total + 1)
+            });
+        if shown > 0 && shown < total {
+            cost += estimate_tokens(&format!("(+{} more)", total - shown)) + 1;
+        }
+    }
+    cost
+}
+
+fn collect(db: &Database, now: Timestamp) -> Vec<Line> {
+    let mut lines = Vec::new();
+    let goals: Vec<_> = db
+        .list_goals()
+        .into_iter()
+        .filter(|g| g.state() != GoalState::Completed)
+        .collect();
+
+    let mut ready: Vec<&Task> = Vec::new();
+    let mut failed: Vec<&Task> = Vec::new();
+    let mut dependents: HashMap<&str, usize> = HashMap::new();
+
+    for goal in &goals {
+        let tasks = db.list_tasks(goal.id());
+        let done = tasks
+            .iter()
+            .filter(|t| t.state() == TaskState::Completed)
+            .count();
+        lines.push(Line {
+            section: Section::Goals,
+            rank: 0,
+            text: format!(
+                "- {} [{}, {done}/{} done] {}",
+                goal.id(),
+                goal.state().as_ref(),
+                tasks.len(),
+                first_line(goal.headline())
+            ),
+        });
+
+        for task in tasks {
+            match task.state() {
+                TaskState::Pending if task.contract().is_some() && task.available(now) => {
+                    ready.push(task);
+                }
+                TaskState::Failed => failed.push(task),
+                _ => {}
+            }
+            if task.state() != TaskState::Completed {
+                for blocker in task.blocked_by() {
+                    *dependents.entry(blocker.as_str()).or_default() += 1;
+                }
+            }
+        }
+    }
+
+    ready.sort_by_key(|t| Reverse(t.priority()));
+    for task in ready {
+        let urgent = task.priority() >= Priority::High;
+        let produces = task
+            .contract()
+            .map(Contract::produces)
+            .map(first_line)
+            .unwrap_or_default();
+        lines.push(Line {
+            section: Section::Ready,
+            rank: if urgent { 1 } else { 3 },
+            text: format!(
+                "- {} ({}, {}) {} -> {produces}",
+                task.id(),
+                task.goal_id(),
+                task.priority().as_ref(),
+                first_line(task.headline())
+            ),
+        });
+    }
+
+    let mut blockers: Vec<(&Task, usize)> = dependents
+        .into_iter()
+        .filter_map(|(id, count)| Some((db.get_task(id)?, count)))
+        .filter(|(t, _)| t.state() != TaskState::Completed)
+        .collect();
+    blockers.sort_by_key(|(t, count)| (Reverse(*count), t.id().to_string()));
+    for (task, count) in blockers {
+        lines.push(Line {
+            section: Section::Blockers,
+            rank: 2,
+            text: format!(
+                "- {} [{}] blocks {count}: {}",
+                task.id(),
+                task.state().as_ref(),
+                first_line(task.headline())
+            ),
+        });
+    }
+
+    failed.sort_by_key(|t| Reverse(t.updated_at()));
+    for task in failed {
+        let last_note = task
+            .comments()
+            .last()
+            .map(|c| format!(" Last note: {}", first_line(c.text())))
+            .unwrap_or_default();
+        lines.push(Line {
+            section: Section::Failures,
+            rank: 2,
+            text: format!(
+                "- {} ({} ago) {}.{last_note}",
+                task.id(),
+                format_age(now.duration_since(task.updated_at())),
+                first_line(task.headline())
+            ),
+        });
+    }
+
+    lines
+}
+
+fn first_line(text: &str) -> &str {
+    text.lines().next().unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Goal, Metrics};
+    use rstest::rstest;
+    use tempfile::TempDir;
+
+    fn task(id: &str, state: TaskState, priority: Priority, blocked_by: &[&str]) -> Task {
+        let now = Timestamp::now();
+        Task::new(
+            id.to_string(),
+            "g1".to_string(),
+            format!("Task {id}"),
+            Some(Contract::new(
+                "in".to_string(),
+                "out".to_string(),
+                "check".to_string(),
+            )),
+            state,
+            blocked_by.iter().map(ToString::to_string).collect(),
+            now,
+            now,
+        )
+        .with_priority(priority)
+    }
+
+    fn db() -> (TempDir, Database) {
+        let dir = TempDir::new().unwrap();
+        let mut db = Database::open(dir.path()).unwrap();
+        let now = Timestamp::now();
+        db.create_goal(Goal::new(
+            "g1".to_string(),
+            None,
+            "Ship the API".to_string(),
+            GoalState::InProgress,
+            now,
+            now,
+            None,
+            Metrics::default(),
+        ))
+        .unwrap();
+        for t in [
+            task("a", TaskState::Pending, Priority::Low, &[]),
+            task("b", TaskState::Pending, Priority::Urgent, &[]),
+            task("c", TaskState::Blocked, Priority::Normal, &["b"]),
+            task("d", TaskState::Blocked, Priority::Normal, &["b"]),
+            task("e", TaskState::Failed, Priority::Normal, &[]),
+        ] {
+            db.create_task(t).unwrap();
+        }
+        (dir, db)
+    }
+
+    // With room to spare every section appears, ready work is ordered by
+    // priority and blockers list how much they hold up.
+    #[rstest]
+    fn digest_lists_all_sections() {
+        let (_dir, db) = db();
+        let digest = for_llm(10_000, &db);
+
+        assert!(digest.contains("Active goals:\n- g1 [in_progress, 0/5 done] Ship the API"));
+        let urgent = digest.find("- b (g1, urgent)").unwrap();
+        let low = digest.find("- a (g1, low)").unwrap();
+        assert!(urgent < low);
+        assert!(digest.contains("- b [pending] blocks 2: Task b"));
+        assert!(digest.contains("Recent failures:\n- e ("));
+        assert!(!digest.contains("more)"));
+    }
+
+    // A tight budget keeps the goal and urgent work, drops low-priority
+    // ready tasks first, and stays within the budget.
+    #[rstest]
+    fn digest_truncates_by_priority() {
+        let (_dir, db) = db();
+        let full = for_llm(10_000, &db);
+        let budget = estimate_tokens(&full) - 10;
+        let digest = for_llm(budget, &db);
+
+        assert!(estimate_tokens(&digest) <= budget);
+        assert!(digest.contains("Ship the API"));
+        assert!(digest.contains("- b (g1, urgent)"));
+        assert!(!digest.contains("- a (g1, low)"));
+        assert!(digest.contains("(+1 more)"));
+    }
+}
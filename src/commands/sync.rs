@@ -0,0 +1,482 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use jiff::Timestamp;
+use serde::{Deserialize, Serialize};
+
+use crate::RADIAL_DIR;
+use crate::db::{Database, atomic_write};
+use crate::error::{Error, Result};
+use crate::helpers::expand_redirect_path;
+use crate::models::{Goal, Task};
+use crate::progress::Progress;
+
+/// File under `.radial/` recording when each peer store was last synced.
+pub const SYNC_STATE_FILE: &str = "sync.toml";
+
+/// Which store a change is written to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Side {
+    Local,
+    Remote,
+}
+
+impl Side {
+    fn other(self) -> Self {
+        match self {
+            Self::Local => Self::Remote,
+            Self::Remote => Self::Local,
+        }
+    }
+}
+
+/// A record changed on both sides since the last sync. The newer copy won.
+#[derive(Debug, Serialize)]
+pub struct SyncConflict {
+    pub kind: &'static str,
+    pub id: String,
+    pub winner: Side,
+    pub reason: String,
+}
+
+/// What `sync` changed (or would change with `--dry-run`).
+#[derive(Debug, Serialize)]
+pub struct SyncReport {
+    pub remote: PathBuf,
+    pub dry_run: bool,
+    /// Goals and tasks copied from the remote store into this one.
+    pub pulled: usize,
+    /// Goals and tasks copied from this store into the remote one.
+    pub pushed: usize,
+    /// Goals removed because they were deleted on the other side.
+    pub deleted: Vec<String>,
+    pub conflicts: Vec<SyncConflict>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct SyncState {
+    #[serde(default)]
+    peers: BTreeMap<String, Timestamp>,
+}
+
+impl SyncState {
+    fn load(base: &Path) -> Result<Self> {
+        let path = base.join(SYNC_STATE_FILE);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        Ok(toml::from_str(&content)
+            .with_context(|| format!("Failed to parse {}", path.display()))?)
+    }
+
+    fn save(&self, base: &Path) -> Result<()> {
+        let content = toml::to_string_pretty(self).context("Failed to serialize sync state")?;
+        Ok(atomic_write(
+            &base.join(SYNC_STATE_FILE),
+            content.as_bytes(),
+        )?)
+    }
+}
+
+enum Change {
+    Goal(Side, Box<Goal>),
+    Task(Side, Box<Task>),
+    Bury(Side, String, Timestamp),
+    Unbury(Side, String),
+}
+
+/// Merge this store with the one at `target` in both directions. For every
+/// goal and task the copy with the later `updated_at` wins. A deleted goal
+/// stays deleted unless it, or one of its tasks, changed after the deletion.
+pub fn run(target: &str, dry_run: bool, db: &mut Database) -> Result<SyncReport> {
+    let cwd = std::env::current_dir().context("Failed to read current directory")?;
+    let mut remote_path = expand_redirect_path(target, &cwd);
+    if remote_path.join(RADIAL_DIR).is_dir() {
+        remote_path = remote_path.join(RADIAL_DIR);
+    }
+    if !remote_path.is_dir() {
+        return Err(Error::conflict(format!(
+            "No radial store at {}",
+            remote_path.display()
+        )));
+    }
+
+    let local_key = canonical_key(db.base_path())?;
+    let remote_key = canonical_key(&remote_path)?;
+    if local_key == remote_key {
+        return Err(Error::conflict("Cannot sync a store with itself"));
+    }
+
+    let mut remote = Database::open(&remote_path)?;
+    let mut local_state = SyncState::load(db.base_path())?;
+    let mut remote_state = SyncState::load(remote.base_path())?;
+    let last_sync = local_state.peers.get(&remote_key).copied();
+
+    let (changes, conflicts) = plan(db, &remote, last_sync);
+
+    let mut report = SyncReport {
+        remote: remote_path,
+        dry_run,
+        pulled: 0,
+        pushed: 0,
+        deleted: Vec::new(),
+        conflicts,
+    };
+    for change in &changes {
+        match change {
+            Change::Goal(Side::Local, _) | Change::Task(Side::Local, _) => report.pulled += 1,
+            Change::Goal(Side::Remote, _) | Change::Task(Side::Remote, _) => report.pushed += 1,
+            Change::Bury(side, id, _) => {
+                let present = match side {
+                    Side::Local => db.get_goal(id).is_some(),
+                    Side::Remote => remote.get_goal(id).is_some(),
+                };
+                if present {
+                    report.deleted.push(id.clone());
+                }
+            }
+            Change::Unbury(..) => {}
+        }
+    }
+    report.deleted.sort();
+    report.deleted.dedup();
+
+    if dry_run {
+        return Ok(report);
+    }
+
+    let mut progress = Progress::new("Syncing", changes.len());
+    for change in changes {
+        match change {
+            Change::Goal(side, goal) => store(side, db, &mut remote).put_goal(*goal)?,
+            Change::Task(side, task) => store(side, db, &mut remote).put_task(*task)?,
+            Change::Bury(side, id, at) => store(side, db, &mut remote).bury_goal(&id, at)?,
+            Change::Unbury(side, id) => store(side, db, &mut remote).unbury_goal(&id)?,
+        }
+        progress.inc();
+    }
+
+    let now = Timestamp::now();
+    local_state.peers.insert(remote_key, now);
+    remote_state.peers.insert(local_key, now);
+    local_state.save(db.base_path())?;
+    remote_state.save(remote.base_path())?;
+
+    Ok(report)
+}
+
+fn store<'a>(side: Side, local: &'a mut Database, remote: &'a mut Database) -> &'a mut Database {
+    match side {
+        Side::Local => local,
+        Side::Remote => remote,
+    }
+}
+
+fn canonical_key(path: &Path) -> Result<String> {
+    let canonical = path
+        .canonicalize()
+        .with_context(|| format!("Failed to resolve {}", path.display()))?;
+    Ok(canonical.to_string_lossy().into_owned())
+}
+
+/// Work out every write needed to bring both stores level, without writing.
+fn plan(
+    local: &Database,
+    remote: &Database,
+    last_sync: Option<Timestamp>,
+) -> (Vec<Change>, Vec<SyncConflict>) {
+    let mut changes = Vec::new();
+    let mut conflicts = Vec::new();
+
+    let goal_ids: BTreeSet<&str> = local
+        .list_goals()
+        .into_iter()
+        .chain(remote.list_goals())
+        .map(Goal::id)
+        .chain(local.tombstones().goals.keys().map(String::as_str))
+        .chain(remote.tombstones().goals.keys().map(String::as_str))
+        .collect();
+
+    for id in goal_ids {
+        let local_tomb = local.tombstones().goals.get(id).copied();
+        let remote_tomb = remote.tombstones().goals.get(id).copied();
+
+        if let Some(deleted_at) = local_tomb.max(remote_tomb) {
+            let changed_at = last_change(local, id).max(last_change(remote, id));
+            match changed_at {
+                Some(changed_at) if changed_at > deleted_at => {
+                    let (deleted_on, kept_on) = if local_tomb == Some(deleted_at) {
+                        (Side::Local, Side::Remote)
+                    } else {
+                        (Side::Remote, Side::Local)
+                    };
+                    conflicts.push(SyncConflict {
+                        kind: "goal",
+                        id: id.to_string(),
+                        winner: kept_on,
+                        reason: format!(
+                            "deleted on {} but changed on {} afterwards; kept",
+                            side_name(deleted_on),
+                            side_name(kept_on)
+                        ),
+                    });
+                    for (side, tomb) in [(Side::Local, local_tomb), (Side::Remote, remote_tomb)] {
+                        if tomb.is_some() {
+                            changes.push(Change::Unbury(side, id.to_string()));
+                        }
+                    }
+                }
+                _ => {
+                    for (side, tomb) in [(Side::Local, local_tomb), (Side::Remote, remote_tomb)] {
+                        if tomb != Some(deleted_at) {
+                            changes.push(Change::Bury(side, id.to_string(), deleted_at));
+                        }
+                    }
+                    continue;
+                }
+            }
+        }
+
+        merge_goal(id, local, remote, last_sync, &mut changes, &mut conflicts);
+    }
+
+    (changes, conflicts)
+}
+
+/// Bring one goal and its tasks level on both sides.
+fn merge_goal(
+    id: &str,
+    local: &Database,
+    remote: &Database,
+    last_sync: Option<Timestamp>,
+    changes: &mut Vec<Change>,
+    conflicts: &mut Vec<SyncConflict>,
+) {
+    let local_goal = local.get_goal(id);
+    let remote_goal = remote.get_goal(id);
+    if let Some((side, winner)) = pick(
+        "goal",
+        id,
+        local_goal.map(Goal::updated_at),
+        remote_goal.map(Goal::updated_at),
+        last_sync,
+        conflicts,
+    ) {
+        let goal = match winner {
+            Side::Local => local_goal,
+            Side::Remote => remote_goal,
+        };
+        changes.push(Change::Goal(
+            side,
+            Box::new(goal.cloned().expect("winner exists")),
+        ));
+    }
+
+    let task_ids: BTreeSet<&str> = local
+        .list_tasks(id)
+        .into_iter()
+        .chain(remote.list_tasks(id))
+        .map(Task::id)
+        .collect();
+    for task_id in task_ids {
+        let local_task = local.get_task(task_id);
+        let remote_task = remote.get_task(task_id);
+        if let Some((side, winner)) = pick(
+            "task",
+            task_id,
+            local_task.map(Task::updated_at),
+            remote_task.map(Task::updated_at),
+            last_sync,
+            conflicts,
+        ) {
+            let task = match winner {
+                Side::Local => local_task,
+                Side::Remote => remote_task,
+            };
+            changes.push(Change::Task(
+                side,
+                Box::new(task.cloned().expect("winner exists")),
+            ));
+        }
+    }
+}
+
+/// Decide which copy of a record wins. Returns the side to write and the
+/// side it is copied from, or `None` when both already agree.
+fn pick(
+    kind: &'static str,
+    id: &str,
+    local: Option<Timestamp>,
+    remote: Option<Timestamp>,
+    last_sync: Option<Timestamp>,
+    conflicts: &mut Vec<SyncConflict>,
+) -> Option<(Side, Side)> {
+    let winner = match (local, remote) {
+        (Some(_), None) => Side::Local,
+        (None, Some(_)) => Side::Remote,
+        (Some(l), Some(r)) if l > r => Side::Local,
+        (Some(l), Some(r)) if r > l => Side::Remote,
+        _ => return None,
+    };
+
+    if let (Some(l), Some(r)) = (local, remote)
+        && last_sync.is_none_or(|synced| l > synced && r > synced)
+    {
+        conflicts.push(SyncConflict {
+            kind,
+            id: id.to_string(),
+            winner,
+            reason: format!(
+                "changed on both sides since the last sync; kept the {} copy",
+                side_name(winner)
+            ),
+        });
+    }
+
+    Some((winner.other(), winner))
+}
+
+/// The latest `updated_at` across a goal and its tasks.
+fn last_change(db: &Database, goal_id: &str) -> Option<Timestamp> {
+    db.get_goal(goal_id)
+        .map(Goal::updated_at)
+        .into_iter()
+        .chain(db.list_tasks(goal_id).into_iter().map(Task::updated_at))
+        .max()
+}
+
+fn side_name(side: Side) -> &'static str {
+    match side {
+        Side::Local => "this side",
+        Side::Remote => "the remote",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{GoalState, Metrics, TaskState};
+    use jiff::SignedDuration;
+    use rstest::rstest;
+    use tempfile::TempDir;
+
+    fn at(seconds: i64) -> Timestamp {
+        Timestamp::UNIX_EPOCH + SignedDuration::from_secs(1_000_000 + seconds)
+    }
+
+    fn goal(id: &str, description: &str, updated: i64) -> Goal {
+        Goal::new(
+            id.to_string(),
+            None,
+            description.to_string(),
+            GoalState::Pending,
+            at(0),
+            at(updated),
+            None,
+            Metrics::default(),
+        )
+    }
+
+    fn task(id: &str, goal_id: &str, updated: i64) -> Task {
+        Task::new(
+            id.to_string(),
+            goal_id.to_string(),
+            format!("Task {id}"),
+            None,
+            TaskState::Pending,
+            Vec::new(),
+            at(0),
+            at(updated),
+        )
+    }
+
+    fn store() -> (TempDir, Database) {
+        let dir = TempDir::new().unwrap();
+        let db = Database::open(dir.path()).unwrap();
+        (dir, db)
+    }
+
+    // Records present on one side are copied to the other; the newer copy
+    // of a shared record wins.
+    #[rstest]
+    fn plan_copies_missing_and_newer_records() {
+        let (_l, mut local) = store();
+        let (_r, mut remote) = store();
+        local.put_goal(goal("g1", "old", 1)).unwrap();
+        remote.put_goal(goal("g1", "new", 5)).unwrap();
+        remote.put_task(task("t1", "g1", 2)).unwrap();
+        local.put_goal(goal("g2", "local only", 1)).unwrap();
+
+        let (changes, conflicts) = plan(&local, &remote, Some(at(3)));
+        assert!(conflicts.is_empty());
+
+        let pulled: Vec<&str> = changes
+            .iter()
+            .filter_map(|c| match c {
+                Change::Goal(Side::Local, g) => Some(g.id()),
+                Change::Task(Side::Local, t) => Some(t.id()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(pulled, ["g1", "t1"]);
+        assert!(matches!(&changes[2], Change::Goal(Side::Remote, g) if g.id() == "g2"));
+    }
+
+    // Both copies changed after the last sync: the newer still wins, but the
+    // overwrite is reported.
+    #[rstest]
+    fn plan_reports_concurrent_edits() {
+        let (_l, mut local) = store();
+        let (_r, mut remote) = store();
+        local.put_goal(goal("g1", "mine", 6)).unwrap();
+        remote.put_goal(goal("g1", "theirs", 5)).unwrap();
+
+        let (changes, conflicts) = plan(&local, &remote, Some(at(3)));
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].winner, Side::Local);
+        assert!(matches!(&changes[0], Change::Goal(Side::Remote, _)));
+    }
+
+    // A tombstone newer than every change deletes the goal on the other side.
+    #[rstest]
+    fn plan_propagates_deletions() {
+        let (_l, mut local) = store();
+        let (_r, mut remote) = store();
+        local.put_goal(goal("g1", "gone", 1)).unwrap();
+        local.put_task(task("t1", "g1", 2)).unwrap();
+        remote.bury_goal("g1", at(3)).unwrap();
+
+        let (changes, conflicts) = plan(&local, &remote, None);
+        assert!(conflicts.is_empty());
+        assert_eq!(changes.len(), 1);
+        assert!(
+            matches!(&changes[0], Change::Bury(Side::Local, id, t) if id == "g1" && *t == at(3))
+        );
+    }
+
+    // A task edited after its goal was deleted elsewhere resurrects the goal
+    // and clears the tombstone.
+    #[rstest]
+    fn plan_keeps_goal_changed_after_deletion() {
+        let (_l, mut local) = store();
+        let (_r, mut remote) = store();
+        local.put_goal(goal("g1", "kept", 1)).unwrap();
+        local.put_task(task("t1", "g1", 9)).unwrap();
+        remote.bury_goal("g1", at(3)).unwrap();
+
+        let (changes, conflicts) = plan(&local, &remote, None);
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].winner, Side::Local);
+        assert!(matches!(&changes[0], Change::Unbury(Side::Remote, _)));
+        assert!(
+            changes
+                .iter()
+                .any(|c| matches!(c, Change::Task(Side::Remote, t) if t.id() == "t1"))
+        );
+    }
+}
@@ -1,58 +1,126 @@
-use anyhow::{Result, anyhow};
-use jiff::Timestamp;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
 
+use jiff::{SignedDuration, Timestamp};
+use serde::{Deserialize, Serialize};
+use strum::{AsRefStr, EnumString};
+
+use crate::commands::context::{self, TaskContext};
+use crate::commands::edit::merge_contract;
+use crate::commands::{quota, ready};
+use crate::config::BlockerPolicy;
 use crate::db::Database;
-use crate::helpers::find_similar_id;
+use crate::error::{Error, Result};
+use crate::helpers;
 use crate::id::generate_id;
-use crate::models::{Comment, Contract, GoalState, Outcome, Task, TaskMetrics, TaskState};
+use crate::models::state_machine;
+use crate::models::{
+    Attempt, CheckItem, Comment, Contract, FailureCategory, GoalState, Outcome, Postmortem,
+    Priority, ReasonCode, Reference, Task, TaskState, can_transition,
+};
+use crate::query::Filter;
+use crate::sandbox::{self, Run, Sandbox};
+
+/// Result of starting a task.
+#[derive(Debug, Serialize)]
+pub struct StartResult {
+    pub task: Task,
+    /// The agent the task was assigned to by starting it, if it wasn't
+    /// already theirs.
+    pub assigned_to: Option<String>,
+}
 
 /// Result of completing a task, including any unblocked tasks.
-#[derive(Debug)]
+#[derive(Debug, Serialize)]
 pub struct CompleteResult {
     pub task: Task,
     pub unblocked_task_ids: Vec<String>,
+    /// Tasks created from `--follow-up`, in the order given.
+    pub follow_ups: Vec<Task>,
+    /// The goal's state once the task is done; `completed` when this was its
+    /// last open task.
+    pub goal_state: GoalState,
 }
 
-fn task_not_found_err(task_id: &str, db: &Database) -> anyhow::Error {
-    let all_task_ids: Vec<&str> = db
-        .list_goals()
-        .iter()
-        .flat_map(|goal| db.list_tasks(goal.id()).into_iter().map(Task::id))
-        .collect();
+/// Result of failing a task.
+#[derive(Debug, Serialize)]
+pub struct FailResult {
+    pub task: Task,
+    pub postmortem_recorded: bool,
+    /// Tasks waiting on this one, which stay blocked until it is retried and
+    /// completed.
+    pub waiting_task_ids: Vec<String>,
+}
 
-    if let Some(suggestion) = find_similar_id(task_id, &all_task_ids) {
-        anyhow!("Task not found: {task_id}\nDid you mean: {suggestion}")
-    } else {
-        anyhow!("Task not found: {task_id}")
-    }
+/// Result of running a task's verify command.
+#[derive(Debug, Serialize)]
+pub struct VerifyResult {
+    pub task_id: String,
+    #[serde(flatten)]
+    pub run: Run,
+}
+
+/// What `task exec` does with the task when its command succeeds.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, AsRefStr, EnumString)]
+#[serde(rename_all = "lowercase")]
+#[strum(serialize_all = "lowercase")]
+pub enum OnSuccess {
+    #[default]
+    None,
+    Complete,
+}
+
+/// What `task exec` does with the task when its command fails.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, AsRefStr, EnumString)]
+#[serde(rename_all = "lowercase")]
+#[strum(serialize_all = "lowercase")]
+pub enum OnFailure {
+    #[default]
+    None,
+    Fail,
+}
+
+/// Result of running a command for a task, and the transition it led to.
+#[derive(Debug, Serialize)]
+pub struct ExecResult {
+    pub task_id: String,
+    #[serde(flatten)]
+    pub run: Run,
+    /// Whether the task was started to run the command.
+    pub started: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub completed: Option<CompleteResult>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub failed: Option<FailResult>,
+}
+
+/// Result of retrying a task.
+#[derive(Debug, Serialize)]
+pub struct RetryResult {
+    pub task: Task,
+    /// Whether the description or contract changed, recording a revision.
+    pub revised: bool,
 }
 
 #[allow(clippy::too_many_arguments)]
 pub fn create(
     goal_id: &str,
     description: String,
+    title: Option<String>,
     receives: Option<String>,
     produces: Option<String>,
     verify: Option<String>,
     blocked_by: Option<Vec<String>>,
+    blocked_by_artifacts: Vec<String>,
+    assignee: Option<String>,
+    priority: Priority,
+    not_before: Option<Timestamp>,
+    estimate: Option<SignedDuration>,
+    gates: Vec<String>,
+    refs: Vec<Reference>,
     db: &mut Database,
 ) -> Result<Task> {
-    let goal = db.get_goal(goal_id);
-
-    if goal.is_none() {
-        let all_goals = db.list_goals();
-        let goal_ids: Vec<&str> = all_goals.iter().map(|g| g.id()).collect();
-
-        return if let Some(suggestion) = find_similar_id(goal_id, &goal_ids) {
-            Err(anyhow!(
-                "Goal not found: {goal_id}\nDid you mean: {suggestion}"
-            ))
-        } else {
-            Err(anyhow!("Goal not found: {goal_id}"))
-        };
-    }
-
-    let goal = goal.unwrap();
+    let goal = db.require_goal_writable(goal_id)?;
     let goal_id_owned = goal.id().to_owned();
     let goal_state = goal.state();
 
@@ -63,39 +131,37 @@ pub fn create(
 
         for task_id in task_ids {
             if !existing_task_ids.contains(&task_id.as_str()) {
-                return if let Some(suggestion) = find_similar_id(task_id, &existing_task_ids) {
-                    Err(anyhow!(
-                        "Task not found in blocked-by list: {task_id}\nDid you mean: {suggestion}"
-                    ))
-                } else {
-                    Err(anyhow!(
-                        "Task not found in blocked-by list: {task_id}\nTask must exist in the same goal."
-                    ))
-                };
+                return Err(Error::not_found(
+                    "blocked-by task",
+                    task_id,
+                    &existing_task_ids,
+                ));
             }
         }
     }
 
-    // Build contract if any contract fields are provided
-    let contract = if receives.is_some() || produces.is_some() || verify.is_some() {
-        Some(Contract::new(
-            receives.unwrap_or_default(),
-            produces.unwrap_or_default(),
-            verify.unwrap_or_default(),
-        ))
-    } else {
-        None
-    };
+    let contract = contract_from(receives, produces, verify);
+
+    let order = db
+        .list_tasks(&goal_id_owned)
+        .iter()
+        .map(|t| t.order())
+        .max()
+        .map_or(0, |max| max + 1);
 
     let blocked_by_ids = blocked_by.unwrap_or_default();
-    let state = if blocked_by_ids.is_empty() {
+    let goal_tasks = db.list_tasks(&goal_id_owned);
+    let waiting_on_artifact = blocked_by_artifacts
+        .iter()
+        .any(|a| !goal_tasks.iter().any(|t| t.produced(a)));
+    let state = if blocked_by_ids.is_empty() && !waiting_on_artifact {
         TaskState::Pending
     } else {
         TaskState::Blocked
     };
     let now = Timestamp::now();
     let task = Task::new(
-        generate_id(),
+        generate_id(db.config().ids.alphabet),
         goal_id_owned.clone(),
         description,
         contract,
@@ -103,7 +169,16 @@ pub fn create(
         blocked_by_ids,
         now,
         now,
-    );
+    )
+    .with_title(title.filter(|t| !t.trim().is_empty()))
+    .with_blocked_by_artifacts(blocked_by_artifacts)
+    .with_priority(priority)
+    .with_order(order)
+    .with_assignee(assignee)
+    .with_not_before(not_before)
+    .with_estimate(estimate)
+    .with_gates(gates)
+    .with_refs(refs);
 
     db.create_task(task.clone())?;
 
@@ -120,208 +195,951 @@ pub fn create(
     Ok(task)
 }
 
-pub fn list(goal_id: &str, db: &Database) -> Result<Vec<Task>> {
-    db.get_goal(goal_id)
-        .ok_or_else(|| anyhow!("Goal not found: {goal_id}"))?;
+/// A contract if any of its fields were given, the rest left empty.
+pub(crate) fn contract_from(
+    receives: Option<String>,
+    produces: Option<String>,
+    verify: Option<String>,
+) -> Option<Contract> {
+    if receives.is_none() && produces.is_none() && verify.is_none() {
+        return None;
+    }
+    Some(Contract::new(
+        receives.unwrap_or_default(),
+        produces.unwrap_or_default(),
+        verify.unwrap_or_default(),
+    ))
+}
 
-    Ok(db.list_tasks(goal_id).into_iter().cloned().collect())
+/// How alike two descriptions must be, by the share of words they have in
+/// common, to count as the same task.
+const DUPLICATE_SIMILARITY: f64 = 0.8;
+
+/// A task in the goal, not yet completed, whose description is close enough
+/// to `description` that creating another is probably a mistake, such as a
+/// planner re-creating a task from an earlier session.
+pub fn find_duplicate<'a>(goal_id: &str, description: &str, db: &'a Database) -> Option<&'a Task> {
+    let words = description_words(description);
+    if words.is_empty() {
+        return None;
+    }
+    db.list_tasks(goal_id)
+        .into_iter()
+        .filter(|t| t.state() != TaskState::Completed)
+        .map(|t| (t, similarity(&words, &description_words(t.description()))))
+        .filter(|(_, score)| *score >= DUPLICATE_SIMILARITY)
+        .max_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(t, _)| t)
 }
 
-pub fn start(task_id: &str, db: &mut Database) -> Result<Task> {
-    let task = db.get_task(task_id);
+/// Fail with a conflict naming the existing task if `description` would
+/// duplicate one, per [`find_duplicate`].
+pub fn refuse_duplicate(goal_id: &str, description: &str, db: &Database) -> Result<()> {
+    match find_duplicate(goal_id, description, db) {
+        Some(existing) => Err(Error::conflict(format!(
+            "Task {} ({}) already covers this: {}. Use it, or pass --allow-duplicate to create another",
+            existing.id(),
+            existing.state().as_ref(),
+            existing.description()
+        ))),
+        None => Ok(()),
+    }
+}
+
+fn description_words(text: &str) -> HashSet<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
 
-    if task.is_none() {
-        return Err(task_not_found_err(task_id, db));
+/// Dice coefficient of two word sets: 1.0 when they match, 0.0 when they
+/// share nothing.
+#[allow(clippy::cast_precision_loss)]
+fn similarity(a: &HashSet<String>, b: &HashSet<String>) -> f64 {
+    if a.is_empty() && b.is_empty() {
+        return 1.0;
     }
+    let shared = a.intersection(b).count();
+    (2 * shared) as f64 / (a.len() + b.len()) as f64
+}
+
+/// The tasks of `goal_id`, or of every goal when it is `None`, keeping
+/// those `filter` matches.
+pub fn list(goal_id: Option<&str>, filter: Option<&Filter>, db: &Database) -> Result<Vec<Task>> {
+    let goals = match goal_id {
+        Some(id) => vec![db.require_goal(id)?],
+        None => db.list_goals(),
+    };
+
+    Ok(goals
+        .into_iter()
+        .flat_map(|g| db.list_tasks(g.id()))
+        .filter(|t| filter.is_none_or(|f| f.matches(t)))
+        .cloned()
+        .collect())
+}
+
+/// Preview the next ready task, with its upstream context, without changing
+/// any state.
+pub fn peek(
+    goal_id: &str,
+    agent: Option<&str>,
+    within: Option<SignedDuration>,
+    db: &Database,
+) -> Result<Option<TaskContext>> {
+    let next = ready::next(goal_id, agent, within, db)?;
+    Ok(next.map(|r| context::resolve(&r.task, db)))
+}
 
-    let task = task.unwrap();
+/// Start the task `peek` would show, returning it with its upstream
+/// context, or `None` if nothing in the goal is ready.
+pub fn claim_next(
+    goal_id: &str,
+    agent: Option<String>,
+    within: Option<SignedDuration>,
+    db: &mut Database,
+) -> Result<Option<TaskContext>> {
+    let Some(next) = ready::next(goal_id, agent.as_deref(), within, db)? else {
+        return Ok(None);
+    };
+    let started = start(next.task.id(), agent, db)?;
+    Ok(Some(context::resolve(&started.task, db)))
+}
+
+/// Where `reorder` places the moved tasks relative to the rest of the goal.
+#[derive(Debug, Clone, Copy)]
+pub enum Placement<'a> {
+    Front,
+    Before(&'a str),
+    After(&'a str),
+}
+
+/// Move `task_ids` (in the given order) to `placement`, then renumber every task
+/// in the goal so display and ready ordering follow it. Returns the goal's
+/// tasks in their new order.
+pub fn reorder(
+    goal_id: &str,
+    task_ids: &[String],
+    placement: Placement,
+    db: &mut Database,
+) -> Result<Vec<Task>> {
+    db.require_goal_writable(goal_id)?;
+
+    let current: Vec<String> = db
+        .list_tasks(goal_id)
+        .iter()
+        .map(|t| t.id().to_string())
+        .collect();
+    let current_refs: Vec<&str> = current.iter().map(String::as_str).collect();
+
+    for (i, task_id) in task_ids.iter().enumerate() {
+        if !current.contains(task_id) {
+            return Err(Error::not_found(
+                "task in this goal",
+                task_id,
+                &current_refs,
+            ));
+        }
+        if task_ids[..i].contains(task_id) {
+            return Err(Error::conflict(format!(
+                "Task listed more than once: {task_id}"
+            )));
+        }
+    }
+
+    let mut ordered: Vec<&str> = current_refs
+        .iter()
+        .copied()
+        .filter(|id| !task_ids.iter().any(|moved| moved == id))
+        .collect();
+
+    let index = match placement {
+        Placement::Front => 0,
+        Placement::Before(anchor) | Placement::After(anchor) => {
+            if task_ids.iter().any(|moved| moved == anchor) {
+                return Err(Error::conflict(format!(
+                    "Cannot place tasks relative to one being moved: {anchor}"
+                )));
+            }
+            let position = ordered
+                .iter()
+                .position(|id| *id == anchor)
+                .ok_or_else(|| Error::not_found("task in this goal", anchor, &current_refs))?;
+            if matches!(placement, Placement::After(_)) {
+                position + 1
+            } else {
+                position
+            }
+        }
+    };
+    ordered.splice(index..index, task_ids.iter().map(String::as_str));
+
+    // Renumber from 1 and only rewrite files whose position actually changed.
+    let base = db.base_path().to_owned();
+    let mut tasks = Vec::with_capacity(ordered.len());
+    for (position, task_id) in (1..).zip(&ordered) {
+        let task = db.get_task_mut(task_id).unwrap();
+        if task.order() != position {
+            task.set_order(position);
+            task.write_file(&base)?;
+        }
+        tasks.push(task.clone());
+    }
+
+    Ok(tasks)
+}
+
+pub fn start(task_id: &str, agent: Option<String>, db: &mut Database) -> Result<StartResult> {
+    let task = db.require_task(task_id)?;
+    db.check_claim(task.goal_id(), agent.as_deref().or(db.agent()))?;
 
     if task.contract().is_none() {
-        return Err(anyhow!(
+        return Err(Error::conflict(format!(
             "Task has no contract. Set a contract before starting.\nUse: radial task contract {} --receives \"...\" --produces \"...\" --verify \"...\"",
             task.id()
-        ));
+        )));
     }
 
-    if task.state() == TaskState::Blocked && !task.blocked_by().is_empty() {
-        return Err(anyhow!(
+    // Under the lenient policy a blocked task whose blockers are done, or
+    // gone, is released as it starts.
+    let policy = db.config().ready.blockers;
+    let release = task.state() == TaskState::Blocked
+        && policy == BlockerPolicy::Lenient
+        && GoalDeps::new(&db.list_tasks(task.goal_id())).met(task, policy);
+    if task.state() == TaskState::Blocked
+        && !release
+        && !(task.blocked_by().is_empty() && task.blocked_by_artifacts().is_empty())
+    {
+        let waiting_on: Vec<&str> = task
+            .blocked_by()
+            .iter()
+            .chain(task.blocked_by_artifacts())
+            .map(String::as_str)
+            .collect();
+        return Err(Error::conflict(format!(
             "Task is blocked by: {}\nComplete those tasks first, or use --force to override.",
-            task.blocked_by().join(", ")
-        ));
+            waiting_on.join(", ")
+        )));
     }
 
-    if task.state() != TaskState::Pending {
-        return Err(anyhow!(
-            "Task must be in 'pending' state to start. Current state: {}",
-            task.state().as_ref()
-        ));
+    if task.state() == TaskState::Failed {
+        return Err(Error::conflict(format!(
+            "Task has failed. Retry it with: radial task retry {}",
+            task.id()
+        )));
+    }
+    if !release {
+        state_machine::check_task(task.id(), task.state(), TaskState::InProgress)?;
+    }
+
+    if let (Some(agent), Some(assignee)) = (&agent, task.assignee())
+        && agent != assignee
+    {
+        return Err(Error::conflict(format!(
+            "Task is assigned to {assignee}. Reassign it with: radial edit task {} --assignee {agent}",
+            task.id()
+        )));
+    }
+    if let Some(agent) = agent.as_deref().or(db.agent()) {
+        quota::check(agent, db)?;
     }
 
     let base = db.base_path().to_owned();
+    let reason = db.reason();
     let task = db.get_task_mut(task_id).unwrap();
-    if !task.transition(TaskState::Pending, TaskState::InProgress) {
-        return Err(anyhow!(
-            "Failed to start task: another process may have already started it"
+    if release {
+        task.unblock(reason);
+    }
+    if !task.transition(&TaskState::Pending, TaskState::InProgress, reason) {
+        return Err(Error::conflict(
+            "Failed to start task: another process may have already started it",
         ));
     }
+    let assigned_to = agent.filter(|agent| task.assignee() != Some(agent.as_str()));
+    if assigned_to.is_some() {
+        task.set_assignee(assigned_to.clone());
+    }
     task.write_file(&base)?;
 
-    Ok(task.clone())
+    Ok(StartResult {
+        task: task.clone(),
+        assigned_to,
+    })
 }
 
+/// Complete a task, unblocking its dependents and creating a pending task in
+/// the same goal for each of `follow_ups` before the goal's state is settled,
+/// so a goal with follow-up work is not marked completed.
 pub fn complete(
     task_id: &str,
     result_summary: String,
     artifacts: Option<Vec<String>>,
-    tokens: Option<i64>,
-    elapsed: Option<i64>,
+    usage: Usage,
+    follow_ups: Vec<String>,
     db: &mut Database,
 ) -> Result<CompleteResult> {
-    let task = db.get_task(task_id);
+    let task = db.require_task_writable(task_id)?;
 
-    if task.is_none() {
-        return Err(task_not_found_err(task_id, db));
-    }
-
-    let task = task.unwrap();
-
-    if task.state() != TaskState::InProgress {
-        return Err(anyhow!(
-            "Task must be in 'in_progress' state to complete. Current state: {}",
-            task.state().as_ref()
-        ));
-    }
+    state_machine::check_task(task.id(), task.state(), TaskState::Completed)?;
 
     let goal_id = task.goal_id().to_owned();
-    let retry_count = task.metrics().retry_count();
+    let attempt = usage
+        .attempt(task, db.agent(), TaskState::Completed)
+        .with_result(result_summary.clone());
     let artifacts_list = artifacts.unwrap_or_default();
 
-    let outcome = Outcome::new(result_summary, artifacts_list);
-    let metrics = TaskMetrics::new(tokens.unwrap_or(0), elapsed.unwrap_or(0), retry_count);
+    let commit = db.project_dir().and_then(helpers::current_git_commit);
+    let outcome = Outcome::new(result_summary, artifacts_list).with_commit(commit);
+    // The attempt adds its spend to what earlier attempts used.
+    let metrics = task.metrics().clone();
 
     let base = db.base_path().to_owned();
+    let reason = db.reason();
     let task = db.get_task_mut(task_id).unwrap();
-    if !task.complete(outcome, metrics) {
-        return Err(anyhow!(
-            "Failed to complete task: another process may have changed its state"
+    if !task.complete(outcome, metrics, reason) {
+        return Err(Error::conflict(
+            "Failed to complete task: another process may have changed its state",
         ));
     }
+    task.add_attempt(attempt);
     task.write_file(&base)?;
     let completed_task = task.clone();
 
-    // Snapshot only the fields needed for unblocking
-    let tasks_snapshot: Vec<(String, TaskState, Vec<String>)> = db
-        .list_tasks(&goal_id)
+    // A blocked task is released once every task and artifact it waits on
+    // is done; only those waiting on this task or its artifacts can change.
+    let policy = db.config().ready.blockers;
+    let goal_tasks = db.list_tasks(&goal_id);
+    let deps = GoalDeps::new(&goal_tasks);
+    let unblocked_task_ids: Vec<String> = goal_tasks
         .iter()
         .filter(|t| t.state() == TaskState::Blocked)
-        .map(|t| (t.id().to_owned(), t.state(), t.blocked_by().to_vec()))
+        .filter(|t| {
+            t.blocked_by().iter().any(|id| id == task_id)
+                || t.blocked_by_artifacts()
+                    .iter()
+                    .any(|a| completed_task.produced(a))
+        })
+        .filter(|t| deps.met(t, policy))
+        .map(|t| t.id().to_owned())
         .collect();
-
-    let mut unblocked_task_ids = Vec::new();
-
-    for (dep_id, _, dep_blocked_by) in &tasks_snapshot {
-        if dep_blocked_by.contains(&task_id.to_owned()) {
-            let all_blockers_done = dep_blocked_by.iter().all(|blocker_id| {
-                db.get_task(blocker_id)
-                    .is_some_and(|t| t.state() == TaskState::Completed)
-            });
-
-            if all_blockers_done {
-                let dep_task = db.get_task_mut(dep_id).unwrap();
-                dep_task.unblock();
-                dep_task.write_file(&base)?;
-                unblocked_task_ids.push(dep_id.clone());
-            }
-        }
+    for id in &unblocked_task_ids {
+        let dep_task = db.get_task_mut(id).unwrap();
+        dep_task.unblock(ReasonCode::AgentAuto);
+        dep_task.write_file(&base)?;
     }
 
-    // Check goal completion
+    let follow_ups = follow_ups
+        .into_iter()
+        .map(|description| {
+            create(
+                &goal_id,
+                description,
+                None,
+                None,
+                None,
+                None,
+                None,
+                Vec::new(),
+                None,
+                Priority::default(),
+                None,
+                None,
+                Vec::new(),
+                Vec::new(),
+                db,
+            )
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    // Check goal completion. A goal with acceptance criteria waits for
+    // `rd goal verify` instead.
     let all_tasks = db.list_tasks(&goal_id);
     let all_completed = all_tasks.iter().all(|t| t.state() == TaskState::Completed);
     let any_failed = all_tasks.iter().any(|t| t.state() == TaskState::Failed);
 
     let goal = db
         .get_goal_mut(&goal_id)
-        .ok_or_else(|| anyhow!("Goal not found: {goal_id}"))?;
+        .ok_or_else(|| Error::not_found("goal", &goal_id, &[]))?;
+    let all_completed = all_completed && goal.criteria().is_empty();
 
-    if all_completed {
-        goal.mark_completed();
+    let target = if all_completed {
+        GoalState::Completed
     } else if any_failed {
-        goal.mark_failed();
+        GoalState::Failed
     } else {
+        goal.state()
+    };
+    if !can_transition(&goal.state(), &target) {
         goal.touch();
+    } else if target == GoalState::Completed {
+        goal.mark_completed();
+    } else {
+        goal.mark_failed();
     }
     goal.write_file(&base)?;
 
     Ok(CompleteResult {
         task: completed_task,
         unblocked_task_ids,
+        follow_ups,
+        goal_state: goal.state(),
     })
 }
 
-pub fn fail(task_id: &str, db: &mut Database) -> Result<Task> {
-    let task = db.get_task(task_id);
+/// Warn about what completing `task_id` would set off when a task it
+/// releases has another blocker that never properly finished: one that no
+/// longer exists and only counts as done under the lenient policy, or one
+/// that was completed while tasks it was blocked by still weren't.
+pub fn cascade_check(task_id: &str, db: &Database) -> Result<Option<String>> {
+    let task = db.require_task(task_id)?;
+    let policy = db.config().ready.blockers;
+    let goal_tasks = db.list_tasks(task.goal_id());
+    let deps = GoalDeps::new(&goal_tasks);
 
-    if task.is_none() {
-        return Err(task_not_found_err(task_id, db));
+    let mut concerns = Vec::new();
+    for waiting in &goal_tasks {
+        if waiting.state() != TaskState::Blocked
+            || !waiting.blocked_by().iter().any(|id| id == task_id)
+        {
+            continue;
+        }
+        let mut found = Vec::new();
+        let mut released = true;
+        for blocker_id in waiting.blocked_by().iter().filter(|id| *id != task_id) {
+            match deps.get(blocker_id) {
+                None if policy == BlockerPolicy::Lenient => {
+                    found.push(format!("{blocker_id} no longer exists"));
+                }
+                Some(blocker) if blocker.state() == TaskState::Completed => {
+                    let unfinished: Vec<&str> = blocker
+                        .blocked_by()
+                        .iter()
+                        .filter(|id| {
+                            deps.get(id)
+                                .is_some_and(|t| t.state() != TaskState::Completed)
+                        })
+                        .map(String::as_str)
+                        .collect();
+                    if !unfinished.is_empty() {
+                        found.push(format!(
+                            "{blocker_id} was completed before {} it was blocked by",
+                            unfinished.join(", ")
+                        ));
+                    }
+                }
+                _ => released = false,
+            }
+        }
+        if released && !found.is_empty() {
+            concerns.push(format!(
+                "  {} would be released, though {}",
+                waiting.id(),
+                found.join(" and ")
+            ));
+        }
+    }
+    if concerns.is_empty() {
+        return Ok(None);
     }
+    Ok(Some(format!(
+        "Completing {task_id} releases tasks whose other blockers didn't finish normally:\n{}",
+        concerns.join("\n")
+    )))
+}
 
-    let task = task.unwrap();
+/// Set each waiting task in the goal to blocked or pending from the actual
+/// state of what it waits on, rather than the state it was stored with, so
+/// records brought in from elsewhere are ready as soon as their blockers
+/// are done. Returns the IDs of the tasks that changed.
+pub fn settle_blocked(goal_id: &str, db: &mut Database) -> Result<Vec<String>> {
+    let policy = db.config().ready.blockers;
+    let goal_tasks = db.list_tasks(goal_id);
+    let deps = GoalDeps::new(&goal_tasks);
+    let changed: Vec<(String, bool)> = goal_tasks
+        .iter()
+        .filter_map(|t| {
+            let met = deps.met(t, policy);
+            match t.state() {
+                TaskState::Blocked if met => Some((t.id().to_string(), true)),
+                TaskState::Pending if !met => Some((t.id().to_string(), false)),
+                _ => None,
+            }
+        })
+        .collect();
 
-    if task.state() != TaskState::InProgress && task.state() != TaskState::Verifying {
-        return Err(anyhow!(
-            "Task must be in 'in_progress' or 'verifying' state to fail. Current state: {}",
-            task.state().as_ref()
-        ));
+    let base = db.base_path().to_owned();
+    for (id, met) in &changed {
+        let task = db.get_task_mut(id).unwrap();
+        if *met {
+            task.unblock(ReasonCode::AgentAuto);
+        } else {
+            task.transition(
+                &TaskState::Pending,
+                TaskState::Blocked,
+                ReasonCode::AgentAuto,
+            );
+        }
+        task.write_file(&base)?;
     }
+    Ok(changed.into_iter().map(|(id, _)| id).collect())
+}
+
+/// A goal's tasks keyed by ID, with the artifacts its completed tasks
+/// listed, so checking every task's dependencies takes one pass rather
+/// than a scan of the goal per blocker.
+pub(crate) struct GoalDeps<'a> {
+    tasks: HashMap<&'a str, &'a Task>,
+    produced: HashSet<&'a str>,
+}
+
+impl<'a> GoalDeps<'a> {
+    pub(crate) fn new(goal_tasks: &[&'a Task]) -> Self {
+        let produced = goal_tasks
+            .iter()
+            .filter(|t| t.state() == TaskState::Completed)
+            .filter_map(|t| t.result())
+            .flat_map(Outcome::artifacts)
+            .map(|a| a.trim_start_matches("./"))
+            .collect();
+        Self {
+            tasks: goal_tasks.iter().map(|t| (t.id(), *t)).collect(),
+            produced,
+        }
+    }
+
+    pub(crate) fn get(&self, task_id: &str) -> Option<&'a Task> {
+        self.tasks.get(task_id).copied()
+    }
+
+    /// Whether a completed task in the goal listed `artifact`.
+    pub(crate) fn produced(&self, artifact: &str) -> bool {
+        self.produced.contains(artifact.trim_start_matches("./"))
+    }
+
+    /// Whether every task `task` is blocked by is completed and every
+    /// artifact it waits on was produced. Under the lenient policy,
+    /// blockers missing from the goal don't count.
+    pub(crate) fn met(&self, task: &Task, policy: BlockerPolicy) -> bool {
+        let done = |id: &String| match self.get(id) {
+            Some(t) => t.state() == TaskState::Completed,
+            None => policy == BlockerPolicy::Lenient,
+        };
+        task.blocked_by().iter().all(done)
+            && task.blocked_by_artifacts().iter().all(|a| self.produced(a))
+    }
+}
+
+/// Mark a task failed, attaching `postmortem` if one was given. `code`
+/// says why it was failed, when that wasn't just the caller's decision.
+pub fn fail(
+    task_id: &str,
+    reason: Option<String>,
+    code: Option<ReasonCode>,
+    postmortem: Option<Postmortem>,
+    usage: Usage,
+    db: &mut Database,
+) -> Result<FailResult> {
+    let task = db.require_task_writable(task_id)?;
+
+    state_machine::check_task(task.id(), task.state(), TaskState::Failed)?;
+    let attempt = usage
+        .attempt(task, db.agent(), TaskState::Failed)
+        .with_failure(postmortem.as_ref().map(Postmortem::category), reason);
 
     let base = db.base_path().to_owned();
+    let code = code.unwrap_or_else(|| db.reason());
     let task = db.get_task_mut(task_id).unwrap();
     if !task.transition_from_any(
         &[TaskState::InProgress, TaskState::Verifying],
         TaskState::Failed,
+        code,
     ) {
-        return Err(anyhow!(
-            "Failed to mark task as failed: state may have changed"
+        return Err(Error::conflict(
+            "Failed to mark task as failed: state may have changed",
         ));
     }
+    task.add_attempt(attempt);
+    let postmortem_recorded = postmortem.is_some();
+    if let Some(postmortem) = postmortem {
+        task.add_postmortem(postmortem);
+    }
     task.write_file(&base)?;
+    let task = task.clone();
 
-    Ok(task.clone())
+    let waiting_task_ids = db
+        .list_tasks(task.goal_id())
+        .iter()
+        .filter(|t| t.blocked_by().iter().any(|id| id == task_id))
+        .map(|t| t.id().to_string())
+        .collect();
+    Ok(FailResult {
+        task,
+        postmortem_recorded,
+        waiting_task_ids,
+    })
+}
+
+/// Environment variable naming the model an agent runs on, for attempts
+/// that don't pass `--model`.
+pub const MODEL_ENV: &str = "RADIAL_MODEL";
+
+/// Environment variable naming who serves that model, for attempts that
+/// don't pass `--provider`.
+pub const PROVIDER_ENV: &str = "RADIAL_PROVIDER";
+
+/// What an agent reports an attempt at a task cost, as it completes or
+/// fails it.
+#[derive(Debug, Clone, Default)]
+pub struct Usage {
+    /// All tokens used; the prompt and completion tokens added up if not
+    /// given.
+    pub tokens: Option<i64>,
+    pub prompt_tokens: Option<i64>,
+    pub completion_tokens: Option<i64>,
+    pub elapsed_ms: Option<i64>,
+    pub model: Option<String>,
+    pub provider: Option<String>,
+}
+
+impl Usage {
+    /// The attempt `task` is finishing with `outcome`, made by its
+    /// assignee or else `agent`.
+    fn attempt(self, task: &Task, agent: Option<&str>, outcome: TaskState) -> Attempt {
+        let prompt_tokens = self.prompt_tokens.unwrap_or(0);
+        let completion_tokens = self.completion_tokens.unwrap_or(0);
+        Attempt::new(
+            task.metrics().retry_count() + 1,
+            task.assignee().or(agent).map(str::to_string),
+            outcome,
+            task.started_at(),
+            Timestamp::now(),
+        )
+        .with_spend(
+            self.tokens.unwrap_or(prompt_tokens + completion_tokens),
+            prompt_tokens,
+            completion_tokens,
+            self.elapsed_ms.unwrap_or(0),
+        )
+        .with_model(self.model)
+        .with_provider(self.provider)
+    }
 }
 
-pub fn retry(task_id: &str, db: &mut Database) -> Result<Task> {
-    let task = db.get_task(task_id);
+/// Changes to make to a task's description and contract as it is retried.
+/// Fields left as `None` keep their current value.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Revision {
+    pub description: Option<String>,
+    pub receives: Option<String>,
+    pub produces: Option<String>,
+    pub verify: Option<String>,
+}
+
+impl Revision {
+    /// The task's current description and contract, as a starting point for
+    /// editing.
+    pub fn from_task(task: &Task) -> Self {
+        let contract = task.contract();
+        Self {
+            description: Some(task.description().to_string()),
+            receives: contract.map(|c| c.receives().to_string()),
+            produces: contract.map(|c| c.produces().to_string()),
+            verify: contract.map(|c| c.verify().to_string()),
+        }
+    }
 
-    if task.is_none() {
-        return Err(task_not_found_err(task_id, db));
+    pub fn is_empty(&self) -> bool {
+        self.description.is_none()
+            && self.receives.is_none()
+            && self.produces.is_none()
+            && self.verify.is_none()
+    }
+}
+
+/// Move a task into a custom state from `[states]`, or out of one back to
+/// the core state it refines. Core states are otherwise reached through
+/// their own commands, which check what those moves need.
+pub fn move_to(task_id: &str, state: &str, db: &mut Database) -> Result<Task> {
+    let task = db.require_task_writable(task_id)?;
+    let current = task.label();
+    if current == state {
+        return Err(Error::conflict(format!("Task is already {state}")));
+    }
+    let (custom_state, core) = match db.config().states.get(state) {
+        Some(custom) => {
+            let allowed = if custom.from.is_empty() {
+                task.state() == custom.core || can_transition(&task.state(), &custom.core)
+            } else {
+                custom.from.iter().any(|from| from == current)
+            };
+            if !allowed {
+                return Err(Error::conflict(format!(
+                    "Task is {current}, and {state} can't be entered from there"
+                )));
+            }
+            (Some(state.to_string()), custom.core.clone())
+        }
+        None => match state.parse::<TaskState>() {
+            Ok(core) if core == task.state() => (None, core),
+            Ok(core) if !matches!(core, TaskState::Unknown(_)) => {
+                return Err(Error::conflict(format!(
+                    "Task is {current}. Move only enters and leaves custom states; use start, complete, or fail to reach {state}."
+                )));
+            }
+            _ => {
+                let names: Vec<&str> = db.config().states.keys().map(String::as_str).collect();
+                return Err(Error::not_found("state", state, &names));
+            }
+        },
+    };
+    if core != task.state() {
+        state_machine::check_task(task.id(), task.state(), core.clone())?;
     }
 
-    let task = task.unwrap();
+    let base = db.base_path().to_owned();
+    let reason = db.reason();
+    let task = db.get_task_mut(task_id).unwrap();
+    task.enter_stage(custom_state, core, reason);
+    task.write_file(&base)?;
+    Ok(task.clone())
+}
+
+/// Put a failed task back in progress, first applying `revision`. The
+/// replaced description and contract are kept in the task's revisions.
+pub fn retry(task_id: &str, revision: Revision, db: &mut Database) -> Result<RetryResult> {
+    let task = db.require_task_writable(task_id)?;
 
     if task.state() != TaskState::Failed {
-        return Err(anyhow!(
-            "Task must be in 'failed' state to retry. Current state: {}",
+        return Err(Error::conflict(format!(
+            "Only failed tasks can be retried; task {} is {}",
+            task.id(),
             task.state().as_ref()
-        ));
+        )));
     }
+    state_machine::check_task(task.id(), task.state(), TaskState::InProgress)?;
 
     let base = db.base_path().to_owned();
+    let reason = db.reason();
     let task = db.get_task_mut(task_id).unwrap();
-    if !task.retry() {
-        return Err(anyhow!("Failed to retry task: state may have changed"));
+    let revisions = task.revisions().len();
+    if !revision.is_empty() {
+        let contract = if revision.receives.is_some()
+            || revision.produces.is_some()
+            || revision.verify.is_some()
+        {
+            Some(merge_contract(
+                task.contract(),
+                revision.receives,
+                revision.produces,
+                revision.verify,
+            ))
+        } else {
+            task.contract().cloned()
+        };
+        let description = revision
+            .description
+            .unwrap_or_else(|| task.description().to_string());
+        task.revise(description, contract);
+    }
+    if !task.retry(reason) {
+        return Err(Error::conflict(
+            "Failed to retry task: state may have changed",
+        ));
+    }
+    task.write_file(&base)?;
+
+    Ok(RetryResult {
+        task: task.clone(),
+        revised: task.revisions().len() > revisions,
+    })
+}
+
+/// Run the task's verify command in `dir`, the current directory by
+/// default, inside `sandbox` or else `[sandbox] wrapper`. Its output is kept
+/// under `.radial/attachments/<task-id>/`. The task's state is untouched.
+pub fn verify(
+    task_id: &str,
+    sandbox: Option<Sandbox>,
+    dir: Option<&Path>,
+    db: &Database,
+) -> Result<VerifyResult> {
+    let task = db.require_task(task_id)?;
+    let command = task
+        .contract()
+        .map(Contract::verify)
+        .filter(|v| !v.trim().is_empty())
+        .ok_or_else(|| Error::conflict(format!("Task {task_id} has no verify command")))?;
+
+    let dir = run_dir(dir, db)?;
+
+    let config = &db.config().sandbox;
+    let sandbox = sandbox.unwrap_or_else(|| config.wrapper.clone());
+    let log = sandbox::log_path(task.id(), "verify", db);
+    let run = sandbox::run(command, &sandbox, &dir, &log, config.timeout)?;
+    Ok(VerifyResult {
+        task_id: task.id().to_string(),
+        run,
+    })
+}
+
+/// Run `command`, or the task's verify command if none is given, as
+/// [`verify`] does, filling in `{{task:...}}` placeholders first. With
+/// `on_success` or `on_failure` set, a pending task is started beforehand
+/// and then completed or failed by how the command exits, with the log as
+/// its artifact or postmortem logs.
+pub fn exec(
+    task_id: &str,
+    command: Option<String>,
+    on_success: OnSuccess,
+    on_failure: OnFailure,
+    sandbox: Option<Sandbox>,
+    dir: Option<&Path>,
+    db: &mut Database,
+) -> Result<ExecResult> {
+    let task = db.require_task(task_id)?;
+    let command = command
+        .or_else(|| task.contract().map(|c| c.verify().to_string()))
+        .filter(|c| !c.trim().is_empty())
+        .ok_or_else(|| {
+            Error::conflict(format!(
+                "Task {task_id} has no verify command. Give one after --"
+            ))
+        })?;
+    let substituted = context::substitute(&command, db);
+    if !substituted.unresolved.is_empty() {
+        return Err(Error::conflict(format!(
+            "Can't fill in {} yet",
+            substituted.unresolved.join(", ")
+        )));
+    }
+    let dir = run_dir(dir, db)?;
+
+    let transitions = on_success != OnSuccess::None || on_failure != OnFailure::None;
+    let started = transitions && task.state() == TaskState::Pending;
+    if started {
+        start(task_id, db.agent().map(str::to_string), db)?;
     }
+
+    let config = &db.config().sandbox;
+    let sandbox = sandbox.unwrap_or_else(|| config.wrapper.clone());
+    let log = sandbox::log_path(task_id, "exec", db);
+    let run = sandbox::run(&substituted.text, &sandbox, &dir, &log, config.timeout)?;
+    let log = run.log.display().to_string();
+
+    let (mut completed, mut failed) = (None, None);
+    if run.passed && on_success == OnSuccess::Complete {
+        let usage = Usage {
+            elapsed_ms: Some(run.elapsed_ms),
+            ..Usage::default()
+        };
+        completed = Some(complete(
+            task_id,
+            format!("`{}` succeeded", run.command),
+            Some(vec![log]),
+            usage,
+            Vec::new(),
+            db,
+        )?);
+    } else if !run.passed && on_failure == OnFailure::Fail {
+        let (category, code) = if run.timed_out {
+            (FailureCategory::Timeout, ReasonCode::BudgetExceeded)
+        } else {
+            (FailureCategory::default(), ReasonCode::VerifyFailed)
+        };
+        let postmortem = Postmortem::new(category, Some(log), None, Timestamp::now());
+        let usage = Usage {
+            elapsed_ms: Some(run.elapsed_ms),
+            ..Usage::default()
+        };
+        failed = Some(fail(
+            task_id,
+            None,
+            Some(code),
+            Some(postmortem),
+            usage,
+            db,
+        )?);
+    }
+
+    Ok(ExecResult {
+        task_id: task_id.to_string(),
+        run,
+        started,
+        completed,
+        failed,
+    })
+}
+
+/// `dir`, or the current directory, if `[sandbox] allowed_dirs` permits it.
+pub(crate) fn run_dir(dir: Option<&Path>, db: &Database) -> Result<PathBuf> {
+    let dir = match dir {
+        Some(dir) => dir.to_path_buf(),
+        None => std::env::current_dir().map_err(anyhow::Error::from)?,
+    };
+    sandbox::allowed_dir(&dir, db)?.ok_or_else(|| {
+        Error::conflict(format!(
+            "{} is outside [sandbox] allowed_dirs",
+            dir.display()
+        ))
+    })
+}
+
+/// Add a comment to a task, optionally as a reply to one of its comments.
+pub fn comment(
+    task_id: &str,
+    text: String,
+    reply_to: Option<&str>,
+    db: &mut Database,
+) -> Result<Task> {
+    let task = db.require_task_writable(task_id)?;
+    if let Some(parent) = reply_to
+        && task.comment(parent).is_none()
+    {
+        let ids: Vec<&str> = task.comments().iter().map(Comment::id).collect();
+        return Err(Error::not_found("comment", parent, &ids));
+    }
+
+    let comment = Comment::new(
+        generate_id(db.config().ids.alphabet),
+        text,
+        Timestamp::now(),
+    )
+    .with_reply_to(reply_to.map(str::to_string))
+    .with_author(db.agent().map(str::to_string));
+
+    let base = db.base_path().to_owned();
+    let task = db.get_task_mut(task_id).unwrap();
+    task.add_comment(comment);
     task.write_file(&base)?;
 
     Ok(task.clone())
 }
 
-pub fn comment(task_id: &str, text: String, db: &mut Database) -> Result<Task> {
-    if db.get_task(task_id).is_none() {
-        return Err(task_not_found_err(task_id, db));
+/// Record how far along an in-progress task is, as a comment carrying the
+/// percentage. `note` becomes the comment text.
+pub fn progress(
+    task_id: &str,
+    percent: u8,
+    note: Option<String>,
+    db: &mut Database,
+) -> Result<Task> {
+    let task = db.require_task_writable(task_id)?;
+    if !matches!(task.state(), TaskState::InProgress | TaskState::Verifying) {
+        return Err(Error::conflict(format!(
+            "Only tasks in progress can report progress; task {} is {}",
+            task.id(),
+            task.state().as_ref()
+        )));
     }
 
-    let comment = Comment::new(generate_id(), text, Timestamp::now());
+    let comment = Comment::new(
+        generate_id(db.config().ids.alphabet),
+        note.unwrap_or_default(),
+        Timestamp::now(),
+    )
+    .with_progress(percent)
+    .with_author(db.agent().map(str::to_string));
 
     let base = db.base_path().to_owned();
     let task = db.get_task_mut(task_id).unwrap();
@@ -330,3 +1148,64 @@ pub fn comment(task_id: &str, text: String, db: &mut Database) -> Result<Task> {
 
     Ok(task.clone())
 }
+
+/// Append an item to a task's checklist.
+pub fn check_add(task_id: &str, text: String, db: &mut Database) -> Result<Task> {
+    db.require_task_writable(task_id)?;
+
+    let base = db.base_path().to_owned();
+    let task = db.get_task_mut(task_id).unwrap();
+    task.add_check_item(CheckItem::new(text, Timestamp::now()));
+    task.write_file(&base)?;
+
+    Ok(task.clone())
+}
+
+pub fn ctx_set(task_id: &str, key: String, value: String, db: &mut Database) -> Result<Task> {
+    db.require_task_writable(task_id)?;
+
+    let base = db.base_path().to_owned();
+    let task = db.get_task_mut(task_id).unwrap();
+    task.set_ctx(key, value);
+    task.write_file(&base)?;
+
+    Ok(task.clone())
+}
+
+/// The value stored under `key`, with a suggestion if there is none.
+pub fn ctx_get<'a>(task_id: &str, key: &str, db: &'a Database) -> Result<&'a str> {
+    let task = db.require_task(task_id)?;
+    task.ctx().get(key).map(String::as_str).ok_or_else(|| {
+        let keys: Vec<&str> = task.ctx().keys().map(String::as_str).collect();
+        Error::not_found("context key", key, &keys)
+    })
+}
+
+pub fn ctx_unset(task_id: &str, key: &str, db: &mut Database) -> Result<Task> {
+    db.require_task_writable(task_id)?;
+    ctx_get(task_id, key, db)?;
+
+    let base = db.base_path().to_owned();
+    let task = db.get_task_mut(task_id).unwrap();
+    task.remove_ctx(key);
+    task.write_file(&base)?;
+
+    Ok(task.clone())
+}
+
+/// Mark checklist item `number` (1-based) done, or not done with `undo`.
+pub fn check_tick(task_id: &str, number: usize, undo: bool, db: &mut Database) -> Result<Task> {
+    db.require_task_writable(task_id)?;
+
+    let base = db.base_path().to_owned();
+    let task = db.get_task_mut(task_id).unwrap();
+    if !task.tick_check_item(number, !undo) {
+        return Err(Error::conflict(format!(
+            "Task {task_id} has no checklist item {number} (it has {})",
+            task.checklist().len()
+        )));
+    }
+    task.write_file(&base)?;
+
+    Ok(task.clone())
+}
@@ -0,0 +1,189 @@
+use std::collections::BTreeMap;
+
+use clap::Command;
+use serde::Serialize;
+use serde_json::{Value, json};
+use strum::{AsRefStr, EnumString};
+
+use super::capabilities::{self, ArgSpec, CommandSpec, ValueKind};
+
+/// The tool definition format to emit.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, AsRefStr, EnumString)]
+#[strum(serialize_all = "lowercase")]
+pub enum ToolFormat {
+    /// Chat Completions `tools` entries: `{"type": "function", "function": ...}`.
+    #[default]
+    OpenAi,
+    /// Messages API `tools` entries: `{"name", "description", "input_schema"}`.
+    Anthropic,
+}
+
+/// Commands offered as tools: the ones an agent uses to find, do and report
+/// work. Shorthands such as `done` and store maintenance are left out.
+const CORE: &[&str] = &[
+    "goal create",
+    "goal list",
+    "list",
+    "status",
+    "ready",
+    "show",
+    "task create",
+    "task list",
+    "task peek",
+    "task claim-next",
+    "task start",
+    "task complete",
+    "task fail",
+    "task retry",
+    "task comment",
+    "task progress",
+    "task check add",
+    "task check tick",
+    "task check list",
+    "task ctx set",
+    "task ctx get",
+    "task ctx unset",
+    "context task",
+    "edit task",
+];
+
+/// Flags the integrator decides rather than the model: output format, and
+/// those that open an editor or read a file.
+const HOST_FLAGS: &[&str] = &["json", "interactive", "edit", "patch"];
+
+/// A JSON Schema for a tool's arguments. Property names are the argument
+/// names shown in the tool's usage line.
+#[derive(Debug, Serialize)]
+pub struct Schema {
+    #[serde(rename = "type")]
+    pub kind: &'static str,
+    pub properties: BTreeMap<String, Value>,
+    pub required: Vec<String>,
+}
+
+/// One radial command as a tool.
+#[derive(Debug)]
+pub struct Tool {
+    /// The command with `radial_` in front and spaces and dashes as
+    /// underscores, e.g. `radial_task_claim_next`.
+    pub name: String,
+    /// What the command does, and the `rd` invocation it maps to.
+    pub description: String,
+    pub schema: Schema,
+}
+
+impl Tool {
+    fn new(command: &CommandSpec) -> Self {
+        let args: Vec<&ArgSpec> = command
+            .args
+            .iter()
+            .filter(|a| !HOST_FLAGS.contains(&a.name.as_str()))
+            .collect();
+        let invocation: Vec<String> = std::iter::once(command.name.clone())
+            .chain(args.iter().map(|a| a.usage()))
+            .collect();
+        let about = command.about.as_deref().unwrap_or_default();
+
+        Self {
+            name: format!("radial_{}", command.name.replace([' ', '-'], "_")),
+            description: format!("{about}. Runs `rd {}`.", invocation.join(" ")),
+            schema: Schema {
+                kind: "object",
+                properties: args.iter().map(|a| (a.name.clone(), property(a))).collect(),
+                required: args
+                    .iter()
+                    .filter(|a| a.required)
+                    .map(|a| a.name.clone())
+                    .collect(),
+            },
+        }
+    }
+
+    /// The definition in `format`, ready to pass to the provider's API.
+    pub fn to_json(&self, format: ToolFormat) -> Value {
+        match format {
+            ToolFormat::OpenAi => json!({
+                "type": "function",
+                "function": {
+                    "name": self.name,
+                    "description": self.description,
+                    "parameters": self.schema,
+                },
+            }),
+            ToolFormat::Anthropic => json!({
+                "name": self.name,
+                "description": self.description,
+                "input_schema": self.schema,
+            }),
+        }
+    }
+}
+
+fn property(arg: &ArgSpec) -> Value {
+    let mut property = match arg.kind {
+        ValueKind::Switch => json!({ "type": "boolean" }),
+        ValueKind::Text => json!({ "type": "string" }),
+        ValueKind::Integer => json!({ "type": "integer" }),
+        ValueKind::List => json!({ "type": "array", "items": { "type": "string" } }),
+    };
+    if let Some(help) = &arg.help {
+        property["description"] = json!(help);
+    }
+    if let Some(default) = &arg.default {
+        property["default"] = match arg.kind {
+            ValueKind::Integer => default
+                .parse::<i64>()
+                .map_or_else(|_| json!(default), |n| json!(n)),
+            _ => json!(default),
+        };
+    }
+    property
+}
+
+/// A tool for each core command in `cli`, the root clap command, in the
+/// order listed in [`CORE`].
+pub fn run(cli: &Command) -> Vec<Tool> {
+    let commands = capabilities::run(cli).commands;
+    CORE.iter()
+        .filter_map(|name| commands.iter().find(|c| c.name == *name))
+        .map(Tool::new)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::Cli;
+    use clap::CommandFactory;
+    use rstest::rstest;
+
+    // Every core command exists in the CLI, so none is silently dropped.
+    #[rstest]
+    fn covers_every_core_command() {
+        assert_eq!(run(&Cli::command()).len(), CORE.len());
+    }
+
+    // Arguments become typed properties, positionals and required flags are
+    // required, and host-only flags are left out.
+    #[rstest]
+    #[case::openai(ToolFormat::OpenAi, "/function/parameters")]
+    #[case::anthropic(ToolFormat::Anthropic, "/input_schema")]
+    fn describes_task_create(#[case] format: ToolFormat, #[case] schema_at: &str) {
+        let tools = run(&Cli::command());
+        let tool = tools
+            .iter()
+            .find(|t| t.name == "radial_task_create")
+            .unwrap();
+        let json = tool.to_json(format);
+        let schema = json.pointer(schema_at).unwrap();
+
+        assert_eq!(schema["type"], "object");
+        assert_eq!(schema["required"], json!(["goal_id", "description"]));
+        let props = &schema["properties"];
+        assert_eq!(props["blocked_by"]["type"], "array");
+        assert_eq!(props["priority"]["default"], "normal");
+        assert!(props["goal_id"]["description"].is_string());
+        assert!(props.get("json").is_none());
+        assert!(tool.description.contains("Runs `rd task create <goal_id>"));
+    }
+}
@@ -0,0 +1,333 @@
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+
+use serde::{Deserialize, Serialize};
+
+use crate::commands::plan::{Plan, PlannedTask};
+use crate::helpers::find_cycle;
+
+/// A plan checked into the repository, e.g. `plan.toml`:
+///
+/// ```toml
+/// goal = "Implement user authentication"
+///
+/// [[tasks]]
+/// name = "handler"
+/// title = "Login handler"
+/// description = "Write login handler"
+/// receives = "User model"
+/// produces = "POST /login"
+/// verify = "cargo test login"
+/// blocked_by = ["schema"]
+/// ```
+///
+/// Tasks refer to each other by `name`, which only exists in the file.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PlanFile {
+    pub goal: String,
+    #[serde(default)]
+    pub tasks: Vec<PlanFileTask>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PlanFileTask {
+    pub name: String,
+    pub description: String,
+    pub title: Option<String>,
+    pub receives: Option<String>,
+    pub produces: Option<String>,
+    pub verify: Option<String>,
+    #[serde(default)]
+    pub blocked_by: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Issue {
+    pub severity: Severity,
+    /// The task the issue is about, by name.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub task: Option<String>,
+    pub message: String,
+}
+
+/// One task of the normalized plan, in dependency order.
+#[derive(Debug, Serialize)]
+pub struct PreviewTask {
+    pub name: String,
+    pub description: String,
+    pub blocked_by: Vec<String>,
+    pub has_contract: bool,
+}
+
+/// The result of linting a plan file. `preview` is empty when the file could
+/// not be parsed or its dependencies do not form a DAG.
+#[derive(Debug, Serialize)]
+pub struct Validation {
+    pub goal: Option<String>,
+    pub issues: Vec<Issue>,
+    pub preview: Vec<PreviewTask>,
+    #[serde(skip)]
+    plan: Option<Plan>,
+}
+
+impl Validation {
+    pub fn has_errors(&self) -> bool {
+        self.issues.iter().any(|i| i.severity == Severity::Error)
+    }
+
+    pub fn has_warnings(&self) -> bool {
+        self.issues.iter().any(|i| i.severity == Severity::Warning)
+    }
+
+    /// The plan ready to create, or `None` if there were errors.
+    pub fn into_plan(self) -> Option<Plan> {
+        if self.has_errors() { None } else { self.plan }
+    }
+
+    fn error(&mut self, task: Option<&str>, message: String) {
+        self.push(Severity::Error, task, message);
+    }
+
+    fn push(&mut self, severity: Severity, task: Option<&str>, message: String) {
+        self.issues.push(Issue {
+            severity,
+            task: task.map(ToString::to_string),
+            message,
+        });
+    }
+}
+
+/// Check a plan file's text: TOML schema, empty or duplicate names, unknown
+/// and self references, dependency cycles, and tasks without a complete
+/// contract (a warning, since they can be given one later).
+pub fn run(text: &str) -> Validation {
+    let mut validation = Validation {
+        goal: None,
+        issues: Vec::new(),
+        preview: Vec::new(),
+        plan: None,
+    };
+
+    let file: PlanFile = match toml::from_str(text) {
+        Ok(file) => file,
+        Err(err) => {
+            let line = err
+                .span()
+                .map(|span| format!(" (line {})", text[..span.start].matches('\n').count() + 1))
+                .unwrap_or_default();
+            validation.error(None, format!("Invalid plan file: {}{line}", err.message()));
+            return validation;
+        }
+    };
+
+    validation.goal = Some(file.goal.clone());
+    if file.goal.trim().is_empty() {
+        validation.error(None, "The goal description is empty".to_string());
+    }
+
+    let known: HashSet<&str> = file.tasks.iter().map(|t| t.name.as_str()).collect();
+    let mut names: HashMap<&str, usize> = HashMap::new();
+    for (i, task) in file.tasks.iter().enumerate() {
+        let name = task.name.as_str();
+        if name.trim().is_empty() {
+            validation.error(None, format!("Task {} has no name", i + 1));
+        } else if names.insert(name, i).is_some() {
+            validation.error(Some(name), format!("Duplicate task name: {name}"));
+        }
+        if task.description.trim().is_empty() {
+            validation.error(Some(name), format!("Task {name} has no description"));
+        }
+        if !has_contract(task) {
+            validation.push(
+                Severity::Warning,
+                Some(name),
+                format!(
+                    "Task {name} has no complete contract (receives, produces, verify); it cannot start until it has one"
+                ),
+            );
+        }
+        for blocker in &task.blocked_by {
+            if blocker == name {
+                validation.error(Some(name), format!("Task {name} is blocked by itself"));
+            } else if !known.contains(blocker.as_str()) {
+                validation.error(
+                    Some(name),
+                    format!("Task {name} is blocked by unknown task: {blocker}"),
+                );
+            }
+        }
+    }
+    if validation.has_errors() {
+        return validation;
+    }
+
+    let order = match topological_order(&file.tasks, &names) {
+        Ok(order) => order,
+        Err(stuck) => {
+            let blockers = |name: &str| file.tasks[names[name]].blocked_by.clone();
+            let message = match stuck.iter().find_map(|name| find_cycle(name, blockers)) {
+                Some(cycle) => format!("Dependency cycle: {}", cycle.join(" → ")),
+                None => format!("Dependency cycle between: {}", stuck.join(", ")),
+            };
+            validation.error(None, message);
+            return validation;
+        }
+    };
+
+    let position: HashMap<usize, usize> = order.iter().enumerate().map(|(p, &i)| (i, p)).collect();
+    let mut plan = Plan {
+        goal: file.goal,
+        tasks: Vec::with_capacity(order.len()),
+    };
+    for &i in &order {
+        let task = &file.tasks[i];
+        validation.preview.push(PreviewTask {
+            name: task.name.clone(),
+            description: task.description.clone(),
+            blocked_by: task.blocked_by.clone(),
+            has_contract: has_contract(task),
+        });
+        plan.tasks.push(PlannedTask {
+            description: task.description.clone(),
+            title: task.title.clone(),
+            receives: task.receives.clone(),
+            produces: task.produces.clone(),
+            verify: task.verify.clone(),
+            blocked_by: task
+                .blocked_by
+                .iter()
+                .map(|b| position[&names[b.as_str()]])
+                .collect(),
+        });
+    }
+    validation.plan = Some(plan);
+    validation
+}
+
+fn has_contract(task: &PlanFileTask) -> bool {
+    [&task.receives, &task.produces, &task.verify]
+        .iter()
+        .all(|f| f.as_deref().is_some_and(|s| !s.trim().is_empty()))
+}
+
+/// Task indexes with every task after its blockers, keeping file order where
+/// dependencies allow. On a cycle, the names of the tasks that are on it or
+/// wait on it.
+fn topological_order(
+    tasks: &[PlanFileTask],
+    names: &HashMap<&str, usize>,
+) -> Result<Vec<usize>, Vec<String>> {
+    // Always take the earliest ready task, so the result only departs from
+    // file order where a blocker comes later in the file.
+    let mut waiting: Vec<usize> = tasks.iter().map(|t| t.blocked_by.len()).collect();
+    let mut dependents: Vec<Vec<usize>> = vec![Vec::new(); tasks.len()];
+    for (i, task) in tasks.iter().enumerate() {
+        for blocker in &task.blocked_by {
+            dependents[names[blocker.as_str()]].push(i);
+        }
+    }
+    let mut ready: BinaryHeap<Reverse<usize>> = (0..tasks.len())
+        .filter(|&i| waiting[i] == 0)
+        .map(Reverse)
+        .collect();
+    let mut order = Vec::with_capacity(tasks.len());
+    while let Some(Reverse(next)) = ready.pop() {
+        order.push(next);
+        for &dependent in &dependents[next] {
+            waiting[dependent] -= 1;
+            if waiting[dependent] == 0 {
+                ready.push(Reverse(dependent));
+            }
+        }
+    }
+
+    if order.len() == tasks.len() {
+        Ok(order)
+    } else {
+        Err((0..tasks.len())
+            .filter(|&i| waiting[i] > 0)
+            .map(|i| tasks[i].name.clone())
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    const VALID: &str = r#"
+goal = "Ship auth"
+
+[[tasks]]
+name = "handler"
+description = "Write login handler"
+receives = "schema"
+produces = "POST /login"
+verify = "tests pass"
+blocked_by = ["schema"]
+
+[[tasks]]
+name = "schema"
+description = "Write schema"
+"#;
+
+    // Tasks are reordered after their blockers, blockers become indexes of
+    // earlier tasks, and a missing contract is only a warning.
+    #[rstest]
+    fn valid_plan_is_normalized() {
+        let validation = run(VALID);
+        assert!(!validation.has_errors());
+        assert!(validation.has_warnings());
+        let names: Vec<&str> = validation.preview.iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(names, ["schema", "handler"]);
+
+        let plan = validation.into_plan().unwrap();
+        assert_eq!(plan.goal, "Ship auth");
+        assert_eq!(plan.tasks[1].description, "Write login handler");
+        assert_eq!(plan.tasks[1].blocked_by, [0]);
+    }
+
+    // Each kind of structural problem is reported as an error.
+    #[rstest]
+    #[case::schema("goal = 1", "Invalid plan file")]
+    #[case::unknown_field("goal = \"g\"\nowner = \"me\"", "unknown field")]
+    #[case::duplicate(
+        "goal = \"g\"\n[[tasks]]\nname = \"a\"\ndescription = \"x\"\n[[tasks]]\nname = \"a\"\ndescription = \"y\"",
+        "Duplicate task name: a"
+    )]
+    #[case::unknown_ref(
+        "goal = \"g\"\n[[tasks]]\nname = \"a\"\ndescription = \"x\"\nblocked_by = [\"b\"]",
+        "unknown task: b"
+    )]
+    #[case::self_ref(
+        "goal = \"g\"\n[[tasks]]\nname = \"a\"\ndescription = \"x\"\nblocked_by = [\"a\"]",
+        "blocked by itself"
+    )]
+    #[case::cycle(
+        "goal = \"g\"\n[[tasks]]\nname = \"a\"\ndescription = \"x\"\nblocked_by = [\"b\"]\n[[tasks]]\nname = \"b\"\ndescription = \"y\"\nblocked_by = [\"a\"]",
+        "Dependency cycle: a → b → a"
+    )]
+    fn reports_errors(#[case] text: &str, #[case] expected: &str) {
+        let validation = run(text);
+        assert!(validation.has_errors());
+        assert!(
+            validation
+                .issues
+                .iter()
+                .any(|i| i.severity == Severity::Error && i.message.contains(expected)),
+            "{:?}",
+            validation.issues
+        );
+        assert!(validation.into_plan().is_none());
+    }
+}
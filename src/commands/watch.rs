@@ -0,0 +1,242 @@
+use std::collections::BTreeSet;
+use std::path::Path;
+use std::process::Command;
+use std::thread;
+use std::time::Duration;
+
+use anyhow::Context;
+use jiff::Timestamp;
+use serde::Serialize;
+
+use crate::config::NotifyConfig;
+use crate::db::Database;
+use crate::error::Result;
+use crate::gate;
+use crate::models::{GoalState, Task, TaskState};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EventKind {
+    TaskReady,
+    GoalCompleted,
+}
+
+impl EventKind {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::TaskReady => "task_ready",
+            Self::GoalCompleted => "goal_completed",
+        }
+    }
+}
+
+/// Something a supervising human wants to hear about.
+#[derive(Debug, Serialize)]
+pub struct Event {
+    pub kind: EventKind,
+    pub id: String,
+    pub message: String,
+}
+
+/// The ready tasks and completed goals seen on one poll.
+#[derive(Debug, Default)]
+pub struct Observed {
+    ready: BTreeSet<String>,
+    completed: BTreeSet<String>,
+}
+
+/// Record what is ready and completed now, limited to `goal_id` if given.
+pub fn observe(goal_id: Option<&str>, db: &Database) -> Observed {
+    let now = Timestamp::now();
+    let mut observed = Observed::default();
+    for goal in db.list_goals() {
+        if goal_id.is_some_and(|id| id != goal.id()) {
+            continue;
+        }
+        if goal.state() == GoalState::Completed {
+            observed.completed.insert(goal.id().to_string());
+        }
+        let candidates: Vec<&Task> = db
+            .list_tasks(goal.id())
+            .into_iter()
+            .filter(|t| t.state() == TaskState::Pending && t.contract().is_some())
+            .filter(|t| t.available(now))
+            .collect();
+        observed.ready.extend(
+            gate::passing(candidates, db)
+                .into_iter()
+                .map(|t| t.id().to_string()),
+        );
+    }
+    observed
+}
+
+/// Events for everything that became ready or completed between two polls.
+pub fn events(before: &Observed, after: &Observed, db: &Database) -> Vec<Event> {
+    let ready = after.ready.difference(&before.ready).filter_map(|id| {
+        let task = db.get_task(id)?;
+        Some(Event {
+            kind: EventKind::TaskReady,
+            id: id.clone(),
+            message: format!("Task ready: {}", task.headline()),
+        })
+    });
+    let completed = after
+        .completed
+        .difference(&before.completed)
+        .filter_map(|id| {
+            let goal = db.get_goal(id)?;
+            Some(Event {
+                kind: EventKind::GoalCompleted,
+                id: id.clone(),
+                message: format!("Goal completed: {}", goal.headline()),
+            })
+        });
+    ready.chain(completed).collect()
+}
+
+/// Poll the store at `path` every `interval` until interrupted, handing each
+/// new event to `on_event`. Whatever is already ready when watching starts is
+/// not reported.
+pub fn run(
+    path: &Path,
+    goal_id: Option<&str>,
+    interval: Duration,
+    mut on_event: impl FnMut(&Event, &NotifyConfig) -> Result<()>,
+) -> Result<()> {
+    let db = Database::open(path)?;
+    if let Some(id) = goal_id {
+        db.require_goal(id)?;
+    }
+    let mut seen = observe(goal_id, &db);
+
+    loop {
+        thread::sleep(interval);
+        let db = Database::open(path)?;
+        let now = observe(goal_id, &db);
+        for event in events(&seen, &now, &db) {
+            on_event(&event, &db.config().notify)?;
+        }
+        seen = now;
+    }
+}
+
+/// Ring the bell and run the configured command for `event`. A failing
+/// command is reported but does not stop the watch.
+pub fn notify(config: &NotifyConfig, event: &Event) -> Result<()> {
+    if config.bell {
+        eprint!("\x07");
+    }
+
+    if let Some(command) = &config.command {
+        let (shell, flag) = if cfg!(windows) {
+            ("cmd", "/C")
+        } else {
+            ("sh", "-c")
+        };
+        let status = Command::new(shell)
+            .args([flag, command])
+            .env("RADIAL_EVENT", event.kind.as_str())
+            .env("RADIAL_ID", &event.id)
+            .env("RADIAL_MESSAGE", &event.message)
+            .status()
+            .with_context(|| format!("Failed to run notify command: {command}"))?;
+        if !status.success() {
+            eprintln!("Notify command exited with {status}");
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Contract, Goal, Metrics, ReasonCode, Task};
+    use jiff::Timestamp;
+    use rstest::rstest;
+    use tempfile::TempDir;
+
+    fn task(id: &str, state: TaskState) -> Task {
+        let now = Timestamp::now();
+        Task::new(
+            id.to_string(),
+            "g1".to_string(),
+            format!("Task {id}"),
+            Some(Contract::new(
+                "in".to_string(),
+                "out".to_string(),
+                "check".to_string(),
+            )),
+            state,
+            Vec::new(),
+            now,
+            now,
+        )
+    }
+
+    // A task moving from blocked to pending and a goal completing are each
+    // reported once; nothing is reported when nothing changed.
+    #[rstest]
+    fn events_report_new_ready_tasks_and_completed_goals() {
+        let dir = TempDir::new().unwrap();
+        let mut db = Database::open(dir.path()).unwrap();
+        let now = Timestamp::now();
+        db.create_goal(Goal::new(
+            "g1".to_string(),
+            None,
+            "Ship".to_string(),
+            GoalState::InProgress,
+            now,
+            now,
+            None,
+            Metrics::default(),
+        ))
+        .unwrap();
+        db.create_task(task("a", TaskState::Pending)).unwrap();
+        db.create_task(task("b", TaskState::Blocked)).unwrap();
+
+        let before = observe(None, &db);
+        assert!(events(&before, &observe(None, &db), &db).is_empty());
+
+        db.get_task_mut("b").unwrap().transition(
+            &TaskState::Blocked,
+            TaskState::Pending,
+            ReasonCode::UserRequest,
+        );
+        db.get_goal_mut("g1").unwrap().mark_completed();
+        let found = events(&before, &observe(None, &db), &db);
+
+        let kinds: Vec<(EventKind, &str)> = found.iter().map(|e| (e.kind, e.id.as_str())).collect();
+        assert_eq!(
+            kinds,
+            [
+                (EventKind::TaskReady, "b"),
+                (EventKind::GoalCompleted, "g1")
+            ]
+        );
+        assert_eq!(found[0].message, "Task ready: Task b");
+    }
+
+    // Watching a single goal ignores tasks elsewhere.
+    #[rstest]
+    fn observe_limits_to_goal() {
+        let dir = TempDir::new().unwrap();
+        let mut db = Database::open(dir.path()).unwrap();
+        let now = Timestamp::now();
+        db.create_goal(Goal::new(
+            "g1".to_string(),
+            None,
+            "Ship".to_string(),
+            GoalState::Pending,
+            now,
+            now,
+            None,
+            Metrics::default(),
+        ))
+        .unwrap();
+        db.create_task(task("a", TaskState::Pending)).unwrap();
+
+        assert_eq!(observe(Some("g1"), &db).ready.len(), 1);
+        assert!(observe(Some("other"), &db).ready.is_empty());
+    }
+}
@@ -0,0 +1,155 @@
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+
+use crate::config::QuotaAction;
+use crate::db::Database;
+use crate::error::{Error, Result};
+use crate::models::TaskState;
+
+/// Tasks in progress across the store and per agent, against `[wip]`.
+#[derive(Debug, Default, Serialize)]
+pub struct Wip {
+    pub in_progress: usize,
+    pub limit: Option<usize>,
+    pub agents: Vec<AgentWip>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AgentWip {
+    pub agent: String,
+    pub in_progress: usize,
+    pub limit: Option<usize>,
+}
+
+impl Wip {
+    /// Whether any limit is set, so there is something to report.
+    pub fn limited(&self) -> bool {
+        self.limit.is_some() || self.agents.iter().any(|a| a.limit.is_some())
+    }
+
+    pub fn exceeded(&self) -> bool {
+        self.limit.is_some_and(|l| self.in_progress > l)
+    }
+
+    pub fn agent(&self, agent: &str) -> Option<&AgentWip> {
+        self.agents.iter().find(|a| a.agent == agent)
+    }
+}
+
+impl AgentWip {
+    pub fn exceeded(&self) -> bool {
+        self.limit.is_some_and(|l| self.in_progress > l)
+    }
+}
+
+/// Count in-progress tasks, in total and for each assignee.
+pub fn run(db: &Database) -> Wip {
+    let config = &db.config().wip;
+    let mut per_agent: BTreeMap<&str, usize> = BTreeMap::new();
+    let mut in_progress = 0;
+    for task in db.all_tasks() {
+        if task.state() != TaskState::InProgress {
+            continue;
+        }
+        in_progress += 1;
+        if let Some(agent) = task.assignee() {
+            *per_agent.entry(agent).or_default() += 1;
+        }
+    }
+    Wip {
+        in_progress,
+        limit: config.total,
+        agents: per_agent
+            .into_iter()
+            .map(|(agent, in_progress)| AgentWip {
+                agent: agent.to_string(),
+                in_progress,
+                limit: config.per_agent,
+            })
+            .collect(),
+    }
+}
+
+/// Check whether starting one more task, as `agent` if known, would go past
+/// a `[wip]` limit. Returns a warning in `warn` mode and an error in
+/// `refuse` mode.
+pub fn check(agent: Option<&str>, db: &Database) -> Result<Option<String>> {
+    let config = &db.config().wip;
+    let wip = run(db);
+    let mut over = Vec::new();
+    if let Some(limit) = config.total
+        && wip.in_progress >= limit
+    {
+        over.push(format!(
+            "{} tasks are in progress, the limit is {limit}",
+            wip.in_progress
+        ));
+    }
+    if let (Some(limit), Some(agent)) = (config.per_agent, agent) {
+        let mine = wip.agent(agent).map_or(0, |a| a.in_progress);
+        if mine >= limit {
+            over.push(format!(
+                "{agent} has {mine} tasks in progress, the limit is {limit}"
+            ));
+        }
+    }
+    if over.is_empty() {
+        return Ok(None);
+    }
+
+    let message = over.join("; ");
+    match config.action {
+        QuotaAction::Warn => Ok(Some(message)),
+        QuotaAction::Refuse => Err(Error::conflict(format!(
+            "{message}. Finish something before starting more."
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{GoalBuilder, TaskBuilder, TempProject};
+    use rstest::rstest;
+
+    fn project_with(config: &str) -> TempProject {
+        let mut project = TempProject::new().unwrap();
+        std::fs::write(project.radial_dir().join("config.toml"), config).unwrap();
+        project.reload().unwrap();
+        project.add_goal(GoalBuilder::new("Ship").id("g1")).unwrap();
+        for (id, agent) in [("t1", "bot"), ("t2", "bot"), ("t3", "other")] {
+            project
+                .add_task(
+                    TaskBuilder::new("g1", id)
+                        .id(id)
+                        .state(TaskState::InProgress)
+                        .assignee(agent),
+                )
+                .unwrap();
+        }
+        project
+    }
+
+    // An agent at its limit is warned, one under it isn't, and the total
+    // counts everyone's work.
+    #[rstest]
+    fn warns_at_the_limit() {
+        let project = project_with("[wip]\nper_agent = 2\n");
+        assert!(check(Some("bot"), project.db()).unwrap().is_some());
+        assert!(check(Some("other"), project.db()).unwrap().is_none());
+        assert!(check(None, project.db()).unwrap().is_none());
+
+        let wip = run(project.db());
+        assert_eq!(wip.in_progress, 3);
+        assert_eq!(wip.agent("bot").unwrap().in_progress, 2);
+    }
+
+    // In refuse mode the start is blocked instead.
+    #[rstest]
+    fn refuses_past_the_total() {
+        let project = project_with("[wip]\ntotal = 3\naction = \"refuse\"\n");
+        let err = check(Some("new"), project.db()).unwrap_err();
+        assert_eq!(err.kind(), "conflict");
+    }
+}
@@ -0,0 +1,564 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result, bail};
+use jiff::SignedDuration;
+use serde::{Deserialize, Serialize};
+
+use crate::cli::SHORTCUTS;
+use crate::commands::digest::DigestFormat;
+use crate::id::IdAlphabet;
+use crate::models::{Attempt, TaskState};
+use crate::sandbox::Sandbox;
+
+pub const CONFIG_FILE: &str = "config.toml";
+
+/// Project configuration read from `.radial/config.toml`.
+/// Every section is optional; a missing file yields the defaults.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub commands: CommandsConfig,
+    pub digest: DigestConfig,
+    pub export: ExportConfig,
+    pub gates: GateConfig,
+    pub history: HistoryConfig,
+    pub ids: IdConfig,
+    pub notify: NotifyConfig,
+    pub output: OutputConfig,
+    pub pricing: PricingConfig,
+    pub quotas: QuotaConfig,
+    pub ready: ReadyConfig,
+    pub sandbox: SandboxConfig,
+    /// Project-defined task states, by name.
+    pub states: BTreeMap<String, StateConfig>,
+    pub status: StatusConfig,
+    pub wip: WipConfig,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CommandsConfig {
+    /// Commands to refuse, by their full name as `rd capabilities` lists
+    /// them, e.g. `task exec`. A group such as `task` disables all of its
+    /// subcommands. `rd prep` leaves them out of its guide.
+    pub disabled: Vec<String>,
+}
+
+impl CommandsConfig {
+    /// Whether the command named by `words`, e.g. `["task", "start"]`, is
+    /// disabled. Shortcuts count as the command they stand for.
+    pub fn disables(&self, words: &[&str]) -> bool {
+        let expanded: Vec<&str> = match words.split_first() {
+            Some((first, rest)) => match SHORTCUTS.iter().find(|(short, _)| short == first) {
+                Some((_, full)) => full.split(' ').chain(rest.iter().copied()).collect(),
+                None => words.to_vec(),
+            },
+            None => return false,
+        };
+        self.disabled.iter().any(|name| {
+            let name: Vec<&str> = name.split_whitespace().collect();
+            !name.is_empty() && expanded.starts_with(&name)
+        })
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DigestConfig {
+    /// How far back `rd digest` looks unless `--since` says otherwise.
+    pub since: SignedDuration,
+    pub format: DigestFormat,
+    /// File to write the digest to, relative to the directory holding
+    /// `.radial`; `{date}` becomes the UTC date.
+    pub path: Option<PathBuf>,
+    /// Shell command the digest is piped to, e.g. `mail -s "radial" team@example.com`.
+    pub command: Option<String>,
+}
+
+impl Default for DigestConfig {
+    fn default() -> Self {
+        Self {
+            since: SignedDuration::from_hours(24),
+            format: DigestFormat::default(),
+            path: None,
+            command: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ExportConfig {
+    /// Mirror each goal to `.radial/goals/<id>.md` after every command.
+    pub markdown: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GateConfig {
+    /// How long a gate command may run before it counts as failed.
+    pub timeout: SignedDuration,
+    /// How long a gate's result is reused before it is run again.
+    pub cache_for: SignedDuration,
+}
+
+impl Default for GateConfig {
+    fn default() -> Self {
+        Self {
+            timeout: SignedDuration::from_secs(10),
+            cache_for: SignedDuration::from_secs(30),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HistoryConfig {
+    /// Append every change to `.radial/history/<date>.jsonl`.
+    pub enabled: bool,
+    /// Log each command run to `.radial/history.jsonl` for `rd history`.
+    pub commands: bool,
+    /// How long `rd gc` keeps both logs. Unset keeps them forever.
+    pub keep_for: Option<SignedDuration>,
+}
+
+impl Default for HistoryConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            commands: true,
+            keep_for: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct IdConfig {
+    /// Characters new goal, task and comment IDs are made of.
+    pub alphabet: IdAlphabet,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NotifyConfig {
+    /// Ring the terminal bell when `watch` reports an event.
+    pub bell: bool,
+    /// Shell command run for each event, e.g. `notify-send radial "$RADIAL_MESSAGE"`.
+    /// `RADIAL_EVENT`, `RADIAL_ID` and `RADIAL_MESSAGE` are set in its environment.
+    pub command: Option<String>,
+}
+
+impl Default for NotifyConfig {
+    fn default() -> Self {
+        Self {
+            bell: true,
+            command: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OutputConfig {
+    /// Fixed line width for human output. Defaults to the terminal width.
+    pub width: Option<usize>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PricingConfig {
+    /// What the prices are in, shown next to costs.
+    pub currency: String,
+    /// Prices per million tokens, by the model name attempts report with
+    /// `--model`.
+    pub models: HashMap<String, ModelPrice>,
+}
+
+impl Default for PricingConfig {
+    fn default() -> Self {
+        Self {
+            currency: "USD".to_string(),
+            models: HashMap::new(),
+        }
+    }
+}
+
+impl PricingConfig {
+    /// What `attempt` cost, or `None` if it names no priced model. Tokens
+    /// reported without a prompt/completion split count as prompt tokens.
+    #[allow(clippy::cast_precision_loss)]
+    pub fn cost(&self, attempt: &Attempt) -> Option<f64> {
+        let price = self.models.get(attempt.model()?)?;
+        let split = attempt.prompt_tokens() + attempt.completion_tokens();
+        let prompt = attempt.prompt_tokens() + (attempt.tokens() - split).max(0);
+        Some(
+            (prompt as f64 * price.prompt + attempt.completion_tokens() as f64 * price.completion)
+                / 1_000_000.0,
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ModelPrice {
+    /// Price per million prompt (input) tokens.
+    pub prompt: f64,
+    /// Price per million completion (output) tokens.
+    pub completion: f64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct QuotaConfig {
+    /// Daily token budget for agents without their own entry.
+    pub daily_tokens: Option<i64>,
+    /// What to do when an agent is over budget.
+    pub action: QuotaAction,
+    /// Per-agent daily token budgets.
+    pub agents: HashMap<String, i64>,
+}
+
+impl QuotaConfig {
+    pub fn limit_for(&self, agent: &str) -> Option<i64> {
+        self.agents.get(agent).copied().or(self.daily_tokens)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum QuotaAction {
+    #[default]
+    Warn,
+    Refuse,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ReadyConfig {
+    /// How a task's blockers decide whether it can be picked up.
+    pub blockers: BlockerPolicy,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BlockerPolicy {
+    /// Blockers that no longer exist, e.g. deleted by hand or by a sync,
+    /// are ignored, and a task is ready once the rest are done whatever
+    /// state it was stored in.
+    #[default]
+    Lenient,
+    /// The stored state decides: a task marked blocked, or waiting on a
+    /// blocker that is gone, stays out of `ready` until it is edited.
+    Strict,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SandboxConfig {
+    /// How long `task verify` or `task exec` may run a command before
+    /// killing it.
+    pub timeout: SignedDuration,
+    /// Directories commands may run in, relative to the directory holding
+    /// `.radial`. Empty allows any.
+    pub allowed_dirs: Vec<PathBuf>,
+    /// What commands run inside unless `--sandbox` says otherwise: `none`
+    /// or `docker:<image>`.
+    pub wrapper: Sandbox,
+}
+
+impl Default for SandboxConfig {
+    fn default() -> Self {
+        Self {
+            timeout: SignedDuration::from_mins(10),
+            allowed_dirs: Vec::new(),
+            wrapper: Sandbox::None,
+        }
+    }
+}
+
+/// A project-defined task state such as `review`, entered with
+/// `task move`. It refines one of the core states, which is what every
+/// other command goes by, and is cleared when that state changes.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StateConfig {
+    /// The core state a task in this state is in.
+    pub core: TaskState,
+    #[serde(default)]
+    pub color: StateColor,
+    /// States, core or custom, a task may be moved into this one from.
+    /// Empty allows any the core state machine does.
+    #[serde(default)]
+    pub from: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StateColor {
+    Black,
+    Red,
+    Green,
+    Yellow,
+    Blue,
+    Magenta,
+    Cyan,
+    #[default]
+    White,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StatusConfig {
+    /// How long a task may stay in progress before `status` flags it as
+    /// stale. Accepts friendly (`"90m"`) or ISO 8601 (`"PT1H"`) durations.
+    pub stale_after: SignedDuration,
+}
+
+impl Default for StatusConfig {
+    fn default() -> Self {
+        Self {
+            stale_after: SignedDuration::from_hours(1),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WipConfig {
+    /// Most tasks one agent may have in progress at once, across every goal.
+    pub per_agent: Option<usize>,
+    /// Most tasks in progress at once across the store.
+    pub total: Option<usize>,
+    /// What `start` and `claim-next` do at a limit.
+    pub action: QuotaAction,
+}
+
+impl Config {
+    pub fn load(radial_dir: &Path) -> Result<Self> {
+        let path = radial_dir.join(CONFIG_FILE);
+        if !path.is_file() {
+            return Ok(Self::default());
+        }
+
+        let content = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let config: Self = toml::from_str(&content)
+            .with_context(|| format!("Failed to parse {}", path.display()))?;
+        config
+            .check_states()
+            .with_context(|| format!("Invalid {}", path.display()))?;
+        Ok(config)
+    }
+
+    /// Reject custom states that shadow a core state, refine no known
+    /// state, or can be entered from states that don't exist.
+    fn check_states(&self) -> Result<()> {
+        for (name, state) in &self.states {
+            if name.parse::<TaskState>().is_ok() {
+                bail!("[states.{name}] has the name of a core state");
+            }
+            if matches!(state.core, TaskState::Unknown(_)) {
+                bail!(
+                    "[states.{name}] core must be pending, blocked, in_progress, verifying, completed or failed"
+                );
+            }
+            if let Some(from) = state
+                .from
+                .iter()
+                .find(|from| from.parse::<TaskState>().is_err() && !self.states.contains_key(*from))
+            {
+                bail!("[states.{name}] can be entered from an unknown state: {from}");
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    // A project without a config file should behave exactly as before.
+    #[test]
+    fn load_missing_file_uses_defaults() {
+        let dir = TempDir::new().unwrap();
+        let config = Config::load(dir.path()).unwrap();
+        assert!(!config.export.markdown);
+        assert!(!config.history.enabled);
+        assert!(config.output.width.is_none());
+    }
+
+    // Sections that are present override defaults; absent keys keep them.
+    #[test]
+    fn load_reads_export_section() {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join(CONFIG_FILE), "[export]\nmarkdown = true\n").unwrap();
+        let config = Config::load(dir.path()).unwrap();
+        assert!(config.export.markdown);
+    }
+
+    #[test]
+    fn load_reads_digest_section() {
+        let dir = TempDir::new().unwrap();
+        fs::write(
+            dir.path().join(CONFIG_FILE),
+            "[digest]\nsince = \"7h\"\nformat = \"html\"\npath = \"digests/{date}.html\"\n",
+        )
+        .unwrap();
+        let config = Config::load(dir.path()).unwrap();
+        assert_eq!(config.digest.since, SignedDuration::from_hours(7));
+        assert_eq!(config.digest.format, DigestFormat::Html);
+        assert_eq!(config.digest.command, None);
+    }
+
+    // Unsplit tokens are priced as prompt tokens; unknown models aren't
+    // priced at all.
+    #[test]
+    fn pricing_costs_attempts_by_model() {
+        let dir = TempDir::new().unwrap();
+        fs::write(
+            dir.path().join(CONFIG_FILE),
+            "[pricing]\ncurrency = \"EUR\"\n\n[pricing.models.sonnet]\nprompt = 3.0\ncompletion = 15.0\n",
+        )
+        .unwrap();
+        let pricing = Config::load(dir.path()).unwrap().pricing;
+        assert_eq!(pricing.currency, "EUR");
+        let attempt = |model: Option<&str>| {
+            Attempt::new(1, None, TaskState::Completed, None, jiff::Timestamp::now())
+                .with_spend(1_500_000, 1_000_000, 200_000, 0)
+                .with_model(model.map(str::to_string))
+        };
+        let cost = pricing.cost(&attempt(Some("sonnet"))).unwrap();
+        assert!((cost - 6.9).abs() < 1e-9, "{cost}");
+        assert_eq!(pricing.cost(&attempt(Some("opus"))), None);
+        assert_eq!(pricing.cost(&attempt(None)), None);
+        assert_eq!(Config::default().pricing.currency, "USD");
+    }
+
+    #[test]
+    fn load_reads_output_width() {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join(CONFIG_FILE), "[output]\nwidth = 120\n").unwrap();
+        let config = Config::load(dir.path()).unwrap();
+        assert_eq!(config.output.width, Some(120));
+        assert!(!config.export.markdown);
+    }
+
+    // Per-agent budgets take precedence over the default budget.
+    #[test]
+    fn quota_limit_prefers_agent_entry() {
+        let dir = TempDir::new().unwrap();
+        fs::write(
+            dir.path().join(CONFIG_FILE),
+            "[quotas]\ndaily_tokens = 100\naction = \"refuse\"\n\n[quotas.agents]\nbig = 500\n",
+        )
+        .unwrap();
+        let config = Config::load(dir.path()).unwrap();
+        assert_eq!(config.quotas.action, QuotaAction::Refuse);
+        assert_eq!(config.quotas.limit_for("big"), Some(500));
+        assert_eq!(config.quotas.limit_for("other"), Some(100));
+    }
+
+    // Stale threshold defaults to an hour and accepts friendly durations.
+    #[test]
+    fn load_reads_stale_threshold() {
+        let dir = TempDir::new().unwrap();
+        assert_eq!(
+            Config::load(dir.path()).unwrap().status.stale_after,
+            SignedDuration::from_hours(1)
+        );
+
+        fs::write(
+            dir.path().join(CONFIG_FILE),
+            "[status]\nstale_after = \"90m\"\n",
+        )
+        .unwrap();
+        let config = Config::load(dir.path()).unwrap();
+        assert_eq!(config.status.stale_after, SignedDuration::from_mins(90));
+    }
+
+    // History is kept forever unless a retention is given.
+    #[test]
+    fn load_reads_history_retention() {
+        let dir = TempDir::new().unwrap();
+        assert!(Config::load(dir.path()).unwrap().history.keep_for.is_none());
+
+        fs::write(
+            dir.path().join(CONFIG_FILE),
+            "[history]\nkeep_for = \"720h\"\n",
+        )
+        .unwrap();
+        let config = Config::load(dir.path()).unwrap();
+        assert_eq!(
+            config.history.keep_for,
+            Some(SignedDuration::from_hours(30 * 24))
+        );
+    }
+
+    // The bell is on unless switched off; a command is opt-in.
+    #[test]
+    fn load_reads_notify_section() {
+        let dir = TempDir::new().unwrap();
+        assert!(Config::load(dir.path()).unwrap().notify.bell);
+
+        fs::write(
+            dir.path().join(CONFIG_FILE),
+            "[notify]\nbell = false\ncommand = \"notify-send radial\"\n",
+        )
+        .unwrap();
+        let config = Config::load(dir.path()).unwrap();
+        assert!(!config.notify.bell);
+        assert_eq!(config.notify.command.as_deref(), Some("notify-send radial"));
+    }
+
+    #[test]
+    fn load_reads_id_alphabet() {
+        let dir = TempDir::new().unwrap();
+        assert_eq!(
+            Config::load(dir.path()).unwrap().ids.alphabet,
+            IdAlphabet::Full
+        );
+
+        fs::write(
+            dir.path().join(CONFIG_FILE),
+            "[ids]\nalphabet = \"unambiguous\"\n",
+        )
+        .unwrap();
+        let config = Config::load(dir.path()).unwrap();
+        assert_eq!(config.ids.alphabet, IdAlphabet::Unambiguous);
+    }
+
+    // Custom states must refine a real core state and not shadow one.
+    #[test]
+    fn load_checks_custom_states() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join(CONFIG_FILE);
+        fs::write(
+            &path,
+            "[states.review]\ncore = \"verifying\"\ncolor = \"magenta\"\nfrom = [\"in_progress\"]\n",
+        )
+        .unwrap();
+        let config = Config::load(dir.path()).unwrap();
+        assert_eq!(config.states["review"].core, TaskState::Verifying);
+        assert_eq!(config.states["review"].color, StateColor::Magenta);
+
+        for bad in [
+            "[states.review]\ncore = \"reviewing\"\n",
+            "[states.pending]\ncore = \"blocked\"\n",
+            "[states.review]\ncore = \"verifying\"\nfrom = [\"triage\"]\n",
+        ] {
+            fs::write(&path, bad).unwrap();
+            assert!(Config::load(dir.path()).is_err(), "{bad}");
+        }
+    }
+
+    #[test]
+    fn load_rejects_malformed_file() {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join(CONFIG_FILE), "[export\n").unwrap();
+        assert!(Config::load(dir.path()).is_err());
+    }
+}
@@ -1,15 +1,70 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::fs::{self, File};
 use std::io::Write;
 use std::path::{Path, PathBuf};
+use std::sync::{Mutex, PoisonError};
 
 use anyhow::{Context, Result, bail};
 use fs2::FileExt;
+use jiff::Timestamp;
+use serde::{Deserialize, Serialize};
+
+use crate::clock;
+use crate::config::Config;
+use crate::error::Error;
+use crate::lenient;
+use crate::models::{
+    Attempt, Goal, Metrics, Milestone, ReasonCode, Spend, Task, TaskState, TaskSummary,
+};
+
+/// Base path of a store opened with [`Database::open_in_memory`]. Writes
+/// under it are skipped, so the store only lives as long as the process.
+pub const IN_MEMORY: &str = ":memory:";
+
+/// Environment variable naming the agent running a command. Commands that
+/// change a claimed goal's tasks are refused unless it names the holder.
+pub const AGENT_ENV: &str = "RADIAL_AGENT";
+
+/// Where goal metrics put spend on attempts nobody was assigned.
+const UNASSIGNED: &str = "unassigned";
+
+fn is_in_memory(path: &Path) -> bool {
+    path.starts_with(IN_MEMORY)
+}
 
-use crate::models::{Goal, Metrics, Task, TaskState};
+/// Files that loaded with a value that had to be replaced rather than read
+/// (see [`lenient::take_lossy`]). Writing one back would overwrite the
+/// original, so [`atomic_write`] refuses until it is fixed and reloaded.
+static GUESSED: Mutex<BTreeSet<PathBuf>> = Mutex::new(BTreeSet::new());
+
+/// Record whether the file just parsed from `path` lost a value.
+fn note_guessed(path: &Path) {
+    let mut guessed = GUESSED.lock().unwrap_or_else(PoisonError::into_inner);
+    if lenient::take_lossy() {
+        guessed.insert(path.to_path_buf());
+    } else {
+        guessed.remove(path);
+    }
+}
 
 /// Atomically write content to a file using a temporary file + rename.
+/// Does nothing for paths in an in-memory store, and refuses files that
+/// loaded with a value that couldn't be read.
 pub fn atomic_write(path: &Path, content: &[u8]) -> Result<()> {
+    if is_in_memory(path) {
+        return Ok(());
+    }
+    if GUESSED
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .contains(path)
+    {
+        return Err(Error::conflict(format!(
+            "{} has a value radial couldn't read; fix it by hand before changing this record, or the original would be lost",
+            path.display()
+        ))
+        .into());
+    }
     let temp = path.with_extension("toml.tmp");
     let mut file = File::create(&temp)
         .with_context(|| format!("Failed to create temporary file: {}", temp.display()))?;
@@ -23,10 +78,79 @@ pub fn atomic_write(path: &Path, content: &[u8]) -> Result<()> {
     Ok(())
 }
 
+/// File under `.radial/` recording deleted goals.
+pub const TOMBSTONES_FILE: &str = "tombstones.toml";
+
+/// Goals that were deleted, and when. Kept so `sync` can tell a goal that was
+/// deleted here from one that was never seen.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct Tombstones {
+    #[serde(default)]
+    pub goals: BTreeMap<String, Timestamp>,
+}
+
+impl Tombstones {
+    fn load(base: &Path) -> Result<Self> {
+        let path = base.join(TOMBSTONES_FILE);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        toml::from_str(&content).with_context(|| format!("Failed to parse {}", path.display()))
+    }
+
+    fn save(&self, base: &Path) -> Result<()> {
+        let content = toml::to_string_pretty(self).context("Failed to serialize tombstones")?;
+        atomic_write(&base.join(TOMBSTONES_FILE), content.as_bytes())
+    }
+}
+
+/// File under `.radial/` holding milestones.
+pub const MILESTONES_FILE: &str = "milestones.toml";
+
+/// Named target dates goals can be attached to, by name.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct Milestones {
+    #[serde(default)]
+    pub milestones: BTreeMap<String, Milestone>,
+}
+
+impl Milestones {
+    fn load(base: &Path) -> Result<Self> {
+        let path = base.join(MILESTONES_FILE);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        lenient::take_lossy();
+        let milestones = toml::from_str(&content)
+            .with_context(|| format!("Failed to parse {}", path.display()))?;
+        note_guessed(&path);
+        Ok(milestones)
+    }
+
+    fn save(&self, base: &Path) -> Result<()> {
+        let content = toml::to_string_pretty(self).context("Failed to serialize milestones")?;
+        atomic_write(&base.join(MILESTONES_FILE), content.as_bytes())
+    }
+}
+
 pub struct Database {
     path: PathBuf,
+    config: Config,
     goals: HashMap<String, Goal>,
     tasks: HashMap<String, Task>,
+    /// Task IDs by goal, so a goal's tasks are found without scanning the
+    /// whole store. Kept in step by `insert_task` and `remove_goal`.
+    by_goal: HashMap<String, HashSet<String>>,
+    tombstones: Tombstones,
+    milestones: Milestones,
+    /// Values that had to be guessed at while loading; see [`crate::lenient`].
+    warnings: Vec<String>,
+    /// The agent running this command; see [`AGENT_ENV`].
+    agent: Option<String>,
 }
 
 impl Database {
@@ -38,16 +162,59 @@ impl Database {
             bail!("Database directory does not exist: {}", path.display());
         }
 
+        let config = Config::load(&path)?;
+        let tombstones = Tombstones::load(&path)?;
+        let milestones = Milestones::load(&path)?;
         let mut db = Self {
             path,
+            config,
             goals: HashMap::new(),
             tasks: HashMap::new(),
+            by_goal: HashMap::new(),
+            tombstones,
+            milestones,
+            warnings: Vec::new(),
+            agent: None,
         };
 
         db.load()?;
         Ok(db)
     }
 
+    /// An empty store that is never written to disk, for tests and throwaway
+    /// sessions. Configuration is left at its defaults.
+    pub fn open_in_memory() -> Self {
+        Self {
+            path: PathBuf::from(IN_MEMORY),
+            config: Config::default(),
+            goals: HashMap::new(),
+            tasks: HashMap::new(),
+            by_goal: HashMap::new(),
+            tombstones: Tombstones::default(),
+            milestones: Milestones::default(),
+            warnings: Vec::new(),
+            agent: None,
+        }
+    }
+
+    /// Whether this store was opened with [`Database::open_in_memory`].
+    pub fn is_in_memory(&self) -> bool {
+        is_in_memory(&self.path)
+    }
+
+    /// Problems found while loading, such as unknown states or unreadable
+    /// timestamps, one per value, prefixed with the file they came from.
+    pub fn load_warnings(&self) -> &[String] {
+        &self.warnings
+    }
+
+    fn note_issues(&mut self, path: &Path) {
+        note_guessed(path);
+        for issue in lenient::take_issues() {
+            self.warnings.push(format!("{}: {issue}", path.display()));
+        }
+    }
+
     /// Initialize a new database. The `.radial/` directory must already exist.
     pub fn init_schema(&self) -> Result<()> {
         Ok(())
@@ -58,9 +225,61 @@ impl Database {
         &self.path
     }
 
+    /// The directory holding `.radial/`, where the project's files and git
+    /// checkout live. `None` for an in-memory store.
+    pub fn project_dir(&self) -> Option<&Path> {
+        self.path.parent().filter(|_| !self.is_in_memory())
+    }
+
+    /// Project configuration loaded from `config.toml`.
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    /// The agent running this command, if known.
+    pub fn agent(&self) -> Option<&str> {
+        self.agent.as_deref()
+    }
+
+    /// Why this command moves tasks: on an agent's behalf when one is
+    /// known, otherwise at a person's request.
+    pub fn reason(&self) -> ReasonCode {
+        if self.agent.is_some() {
+            ReasonCode::AgentAuto
+        } else {
+            ReasonCode::UserRequest
+        }
+    }
+
+    pub fn set_agent(&mut self, agent: Option<String>) {
+        self.agent = agent.filter(|a| !a.trim().is_empty());
+    }
+
+    /// Refuse when an agent other than `agent` holds a claim on `goal_id`
+    /// that has not expired. Unnamed callers count as other agents.
+    pub fn check_claim(&self, goal_id: &str, agent: Option<&str>) -> crate::error::Result<()> {
+        let Some(claim) = self
+            .get_goal(goal_id)
+            .and_then(|g| g.claim(Timestamp::now()))
+        else {
+            return Ok(());
+        };
+        if agent == Some(claim.agent()) {
+            return Ok(());
+        }
+        let holder = claim.agent();
+        Err(Error::conflict(format!(
+            "Goal {goal_id} is claimed by {holder} until {}; only {holder} can change its tasks.\n\
+             Run as {holder} with {AGENT_ENV}={holder}, wait for the claim to expire, or release it with: radial goal release {goal_id} --force",
+            claim.expires_at()
+        )))
+    }
+
     /// Load all data from the per-entity TOML files into memory.
     fn load(&mut self) -> Result<()> {
         let dir = fs::read_dir(&self.path).context("Failed to read .radial directory")?;
+        lenient::take_issues();
+        lenient::take_lossy();
 
         for entry in dir {
             let entry = entry.context("Failed to read directory entry")?;
@@ -79,7 +298,9 @@ impl Database {
                 .with_context(|| format!("Failed to read {}", goal_toml_path.display()))?;
             let goal: Goal = toml::from_str(&goal_content)
                 .with_context(|| format!("Failed to parse {}", goal_toml_path.display()))?;
+            self.note_issues(&goal_toml_path);
 
+            clock::observe(goal.seq());
             let goal_id = goal.id().to_owned();
             self.goals.insert(goal_id, goal);
 
@@ -102,8 +323,10 @@ impl Database {
                     .with_context(|| format!("Failed to read {}", task_path.display()))?;
                 let task: Task = toml::from_str(&task_content)
                     .with_context(|| format!("Failed to parse {}", task_path.display()))?;
+                self.note_issues(&task_path);
 
-                self.tasks.insert(task.id().to_owned(), task);
+                clock::observe(task.seq());
+                self.insert_task(task);
             }
         }
 
@@ -114,11 +337,13 @@ impl Database {
 
     pub fn create_goal(&mut self, goal: Goal) -> Result<()> {
         if self.goals.contains_key(goal.id()) {
-            bail!("Goal already exists: {}", goal.id());
+            return Err(Error::conflict(format!("Goal already exists: {}", goal.id())).into());
         }
 
-        let goal_dir = self.path.join(goal.id());
-        fs::create_dir_all(&goal_dir).context("Failed to create goal directory")?;
+        if !self.is_in_memory() {
+            let goal_dir = self.path.join(goal.id());
+            fs::create_dir_all(&goal_dir).context("Failed to create goal directory")?;
+        }
 
         goal.write_file(&self.path)?;
         self.goals.insert(goal.id().to_owned(), goal);
@@ -130,20 +355,126 @@ impl Database {
         self.goals.get(id)
     }
 
+    /// Look up a goal, or fail with a `NotFound` error suggesting a close ID.
+    pub fn require_goal(&self, id: &str) -> crate::error::Result<&Goal> {
+        self.get_goal(id).ok_or_else(|| {
+            let ids: Vec<&str> = self.goals.keys().map(String::as_str).collect();
+            Error::not_found("goal", id, &ids)
+        })
+    }
+
+    /// Look up a goal the current agent may change: like
+    /// [`Database::require_goal`], but refused while another agent claims it.
+    pub fn require_goal_writable(&self, id: &str) -> crate::error::Result<&Goal> {
+        let goal = self.require_goal(id)?;
+        self.check_claim(id, self.agent())?;
+        Ok(goal)
+    }
+
     pub fn get_goal_mut(&mut self, id: &str) -> Option<&mut Goal> {
         self.goals.get_mut(id)
     }
 
     pub fn list_goals(&self) -> Vec<&Goal> {
         let mut goals: Vec<&Goal> = self.goals.values().collect();
-        goals.sort_by_key(|g| std::cmp::Reverse(g.created_at()));
+        goals.sort_by_key(|g| std::cmp::Reverse((g.created_seq(), g.created_at())));
         goals
     }
 
-    /// Delete a goal and all its tasks from disk and memory.
+    /// Write a goal as given, creating or replacing it. Used by `sync` to
+    /// copy goals between stores without touching their timestamps.
+    pub fn put_goal(&mut self, goal: Goal) -> Result<()> {
+        clock::observe(goal.seq());
+        if !self.is_in_memory() {
+            let goal_dir = self.path.join(goal.id());
+            fs::create_dir_all(&goal_dir).context("Failed to create goal directory")?;
+        }
+
+        goal.write_file(&self.path)?;
+        self.goals.insert(goal.id().to_owned(), goal);
+
+        Ok(())
+    }
+
+    /// Delete a goal and all its tasks, leaving a tombstone so the deletion
+    /// can be synced to other stores.
     pub fn delete_goal(&mut self, goal_id: &str) -> Result<()> {
+        self.bury_goal(goal_id, Timestamp::now())
+    }
+
+    /// Remove a goal that never finished being created, and its tasks,
+    /// without a tombstone: no other store has it to delete.
+    pub fn discard_goal(&mut self, goal_id: &str) -> Result<()> {
+        self.remove_goal(goal_id)
+    }
+
+    /// Delete a goal and record it as deleted at `deleted_at`. An existing
+    /// later tombstone is kept.
+    pub fn bury_goal(&mut self, goal_id: &str, deleted_at: Timestamp) -> Result<()> {
+        self.remove_goal(goal_id)?;
+
+        let entry = self
+            .tombstones
+            .goals
+            .entry(goal_id.to_owned())
+            .or_insert(deleted_at);
+        *entry = (*entry).max(deleted_at);
+        self.tombstones.save(&self.path)
+    }
+
+    /// Forget that a goal was deleted, e.g. because it was edited elsewhere
+    /// after the deletion and has been restored.
+    pub fn unbury_goal(&mut self, goal_id: &str) -> Result<()> {
+        if self.tombstones.goals.remove(goal_id).is_some() {
+            self.tombstones.save(&self.path)?;
+        }
+        Ok(())
+    }
+
+    /// Goals deleted from this store.
+    pub fn tombstones(&self) -> &Tombstones {
+        &self.tombstones
+    }
+
+    // Milestone operations
+
+    pub fn create_milestone(&mut self, name: &str, milestone: Milestone) -> Result<()> {
+        if self.milestones.milestones.contains_key(name) {
+            return Err(Error::conflict(format!("Milestone already exists: {name}")).into());
+        }
+        self.milestones
+            .milestones
+            .insert(name.to_owned(), milestone);
+        self.milestones.save(&self.path)
+    }
+
+    pub fn get_milestone(&self, name: &str) -> Option<&Milestone> {
+        self.milestones.milestones.get(name)
+    }
+
+    pub fn require_milestone(&self, name: &str) -> crate::error::Result<&Milestone> {
+        self.get_milestone(name).ok_or_else(|| {
+            let names: Vec<&str> = self
+                .milestones
+                .milestones
+                .keys()
+                .map(String::as_str)
+                .collect();
+            Error::not_found("milestone", name, &names)
+        })
+    }
+
+    /// Milestones by name.
+    pub fn milestones(&self) -> &BTreeMap<String, Milestone> {
+        &self.milestones.milestones
+    }
+
+    /// Remove a goal and all its tasks from disk and memory.
+    fn remove_goal(&mut self, goal_id: &str) -> Result<()> {
         // Remove tasks from memory
-        self.tasks.retain(|_, t| t.goal_id() != goal_id);
+        for task_id in self.by_goal.remove(goal_id).unwrap_or_default() {
+            self.tasks.remove(&task_id);
+        }
 
         // Remove goal from memory
         self.goals.remove(goal_id);
@@ -163,67 +494,209 @@ impl Database {
 
     pub fn create_task(&mut self, task: Task) -> Result<()> {
         if self.tasks.contains_key(task.id()) {
-            bail!("Task already exists: {}", task.id());
+            return Err(Error::conflict(format!("Task already exists: {}", task.id())).into());
         }
+        self.check_goal_exists(&task)?;
 
         task.write_file(&self.path)?;
-        self.tasks.insert(task.id().to_owned(), task);
+        self.insert_task(task);
+
+        Ok(())
+    }
+
+    /// Write a task as given, creating or replacing it. The task's goal
+    /// directory must already exist.
+    pub fn put_task(&mut self, task: Task) -> Result<()> {
+        self.check_goal_exists(&task)?;
+        clock::observe(task.seq());
+        task.write_file(&self.path)?;
+        self.insert_task(task);
 
         Ok(())
     }
 
+    /// Move a task into another goal's directory, placed at `order`. The new
+    /// file is written before the old one is removed, so an interruption
+    /// leaves a stray copy for `rd doctor` to report rather than losing it.
+    pub fn move_task(&mut self, task_id: &str, goal_id: &str, order: u32) -> Result<()> {
+        let mut task = self.require_task(task_id)?.clone();
+        let old_path = task.file_path(&self.path);
+        task.set_goal_id(goal_id.to_owned());
+        task.set_order(order);
+        self.check_goal_exists(&task)?;
+
+        task.write_file(&self.path)?;
+        if !self.is_in_memory() {
+            fs::remove_file(&old_path)
+                .with_context(|| format!("Failed to remove {}", old_path.display()))?;
+        }
+        self.insert_task(task);
+        Ok(())
+    }
+
+    /// Add or replace a task in memory, moving it in `by_goal` if its goal
+    /// changed.
+    fn insert_task(&mut self, task: Task) {
+        if let Some(old) = self.tasks.get(task.id())
+            && old.goal_id() != task.goal_id()
+            && let Some(ids) = self.by_goal.get_mut(old.goal_id())
+        {
+            ids.remove(task.id());
+        }
+        self.by_goal
+            .entry(task.goal_id().to_owned())
+            .or_default()
+            .insert(task.id().to_owned());
+        self.tasks.insert(task.id().to_owned(), task);
+    }
+
+    /// Refuse a task whose goal is not in the store, so no task file is
+    /// written where the loader would never find it.
+    fn check_goal_exists(&self, task: &Task) -> Result<()> {
+        if self.goals.contains_key(task.goal_id()) {
+            return Ok(());
+        }
+        Err(Error::conflict(format!(
+            "Task {} belongs to goal {}, which does not exist",
+            task.id(),
+            task.goal_id()
+        ))
+        .into())
+    }
+
     pub fn get_task(&self, id: &str) -> Option<&Task> {
         self.tasks.get(id)
     }
 
+    /// Look up a task, or fail with a `NotFound` error suggesting a close ID.
+    pub fn require_task(&self, id: &str) -> crate::error::Result<&Task> {
+        self.get_task(id).ok_or_else(|| {
+            let ids: Vec<&str> = self.tasks.keys().map(String::as_str).collect();
+            Error::not_found("task", id, &ids)
+        })
+    }
+
+    /// Look up a task the current agent may change: like
+    /// [`Database::require_task`], but refused while another agent claims
+    /// its goal.
+    pub fn require_task_writable(&self, id: &str) -> crate::error::Result<&Task> {
+        let task = self.require_task(id)?;
+        self.check_claim(task.goal_id(), self.agent())?;
+        Ok(task)
+    }
+
     pub fn get_task_mut(&mut self, id: &str) -> Option<&mut Task> {
         self.tasks.get_mut(id)
     }
 
     pub fn list_tasks(&self, goal_id: &str) -> Vec<&Task> {
-        let mut tasks: Vec<&Task> = self
-            .tasks
-            .values()
-            .filter(|t| t.goal_id() == goal_id)
-            .collect();
-        tasks.sort_by_key(|t| t.created_at());
+        let mut tasks: Vec<&Task> = self.goal_tasks(goal_id).collect();
+        tasks.sort_by_key(|t| (t.order(), t.created_seq(), t.created_at()));
         tasks
     }
 
-    #[allow(clippy::missing_panics_doc)]
+    /// The goal's tasks in the order of [`Database::list_tasks`], as just
+    /// the fields a table needs.
+    pub fn list_task_summaries(&self, goal_id: &str) -> Vec<TaskSummary<'_>> {
+        self.list_tasks(goal_id)
+            .into_iter()
+            .map(Task::summary)
+            .collect()
+    }
+
+    /// Every task in the store, in no particular order.
+    pub fn all_tasks(&self) -> impl Iterator<Item = &Task> {
+        self.tasks.values()
+    }
+
+    /// The goal's tasks, in no particular order.
+    fn goal_tasks(&self, goal_id: &str) -> impl Iterator<Item = &Task> {
+        self.by_goal
+            .get(goal_id)
+            .into_iter()
+            .flatten()
+            .filter_map(|id| self.tasks.get(id))
+    }
+
     pub fn compute_goal_metrics(&self, goal_id: &str) -> Metrics {
-        let tasks = self.list_tasks(goal_id);
-
-        let total_tokens: i64 = tasks.iter().map(|t| t.metrics().tokens()).sum();
-        let elapsed_ms: i64 = tasks.iter().map(|t| t.metrics().elapsed_ms()).sum();
-        let task_count = i64::try_from(tasks.len()).unwrap_or(0);
-        let tasks_completed = i64::try_from(
-            tasks
-                .iter()
-                .filter(|t| t.state() == TaskState::Completed)
-                .count(),
-        )
-        .unwrap_or(0);
-        let tasks_failed = i64::try_from(
-            tasks
-                .iter()
-                .filter(|t| t.state() == TaskState::Failed)
-                .count(),
-        )
-        .unwrap_or(0);
-
-        Metrics::new(
-            total_tokens,
-            0,
-            0,
-            elapsed_ms,
-            task_count,
-            tasks_completed,
-            tasks_failed,
-        )
+        tally(self.goal_tasks(goal_id))
+    }
+
+    /// Metrics for every goal, keyed by ID, from one pass over the tasks
+    /// rather than one per goal. Goals without tasks get zeroed metrics.
+    pub fn compute_all_goal_metrics(&self) -> HashMap<&str, Metrics> {
+        let mut by_goal: HashMap<&str, Vec<&Task>> = self
+            .goals
+            .keys()
+            .map(|id| (id.as_str(), Vec::new()))
+            .collect();
+        for task in self.tasks.values() {
+            by_goal.entry(task.goal_id()).or_default().push(task);
+        }
+        by_goal
+            .into_iter()
+            .map(|(id, tasks)| (id, tally(tasks)))
+            .collect()
     }
 }
 
+/// Tokens and elapsed time summed over `tasks`, with how many there are and
+/// how many completed or failed, broken down by agent and attempt number.
+/// Spend recorded before attempts were kept counts as a first attempt by
+/// the task's assignee.
+fn tally<'a>(tasks: impl IntoIterator<Item = &'a Task>) -> Metrics {
+    let (mut total_tokens, mut elapsed_ms) = (0, 0);
+    let (mut task_count, mut tasks_completed, mut tasks_failed) = (0, 0, 0);
+    let mut by_agent: BTreeMap<String, Spend> = BTreeMap::new();
+    let mut by_attempt: BTreeMap<i64, Spend> = BTreeMap::new();
+    for task in tasks {
+        total_tokens += task.metrics().tokens();
+        elapsed_ms += task.metrics().elapsed_ms();
+        task_count += 1;
+
+        let legacy = (task.attempts().is_empty()
+            && (task.metrics().tokens() > 0 || task.metrics().elapsed_ms() > 0))
+            .then(|| {
+                Attempt::new(
+                    1,
+                    task.assignee().map(str::to_string),
+                    task.state(),
+                    task.started_at(),
+                    task.completed_at().unwrap_or_else(|| task.updated_at()),
+                )
+                .with_spend(
+                    task.metrics().tokens(),
+                    0,
+                    0,
+                    task.metrics().elapsed_ms(),
+                )
+            });
+        for attempt in task.attempts().iter().chain(&legacy) {
+            let agent = attempt.agent().unwrap_or(UNASSIGNED);
+            by_agent.entry(agent.to_string()).or_default().add(attempt);
+            by_attempt.entry(attempt.number()).or_default().add(attempt);
+        }
+        match task.state() {
+            TaskState::Completed => tasks_completed += 1,
+            TaskState::Failed => tasks_failed += 1,
+            _ => {}
+        }
+    }
+
+    let prompt_tokens = by_attempt.values().map(|s| s.prompt_tokens).sum();
+    let completion_tokens = by_attempt.values().map(|s| s.completion_tokens).sum();
+    Metrics::new(
+        total_tokens,
+        prompt_tokens,
+        completion_tokens,
+        elapsed_ms,
+        task_count,
+        tasks_completed,
+        tasks_failed,
+    )
+    .with_breakdown(by_agent, by_attempt)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -266,8 +739,14 @@ mod tests {
         let dir = TempDir::new().unwrap();
         let db = Database {
             path: dir.path().to_path_buf(),
+            config: Config::default(),
             goals: HashMap::new(),
             tasks: HashMap::new(),
+            by_goal: HashMap::new(),
+            tombstones: Tombstones::default(),
+            milestones: Milestones::default(),
+            warnings: Vec::new(),
+            agent: None,
         };
         (dir, db)
     }
@@ -278,8 +757,14 @@ mod tests {
         let dir = TempDir::new().unwrap();
         let mut db = Database {
             path: dir.path().to_path_buf(),
+            config: Config::default(),
             goals: HashMap::new(),
             tasks: HashMap::new(),
+            by_goal: HashMap::new(),
+            tombstones: Tombstones::default(),
+            milestones: Milestones::default(),
+            warnings: Vec::new(),
+            agent: None,
         };
         db.create_goal(make_goal("g1")).unwrap();
         db.create_task(make_task("t1", "g1", TaskState::Pending))
@@ -370,7 +855,7 @@ mod tests {
 
     // -- list_goals --
 
-    // Goals should be returned newest-first (descending created_at).
+    // Goals should be returned newest-first (latest created first).
     #[rstest]
     fn list_goals_sorted_by_created_at_desc(db: (TempDir, Database)) {
         let (_dir, mut db) = db;
@@ -421,6 +906,22 @@ mod tests {
         assert_eq!(loaded.goal_id(), "g1");
     }
 
+    // A task can only be written under a goal the store holds, whether it
+    // is new or copied in by sync.
+    #[rstest]
+    fn tasks_need_an_existing_goal(db_with_goal_and_task: (TempDir, Database)) {
+        let (dir, mut db) = db_with_goal_and_task;
+        let err = db
+            .create_task(make_task("t2", "g9", TaskState::Pending))
+            .unwrap_err();
+        assert!(err.to_string().contains("goal g9, which does not exist"));
+        assert!(
+            db.put_task(make_task("t3", "g9", TaskState::Pending))
+                .is_err()
+        );
+        assert!(!dir.path().join("g9").exists());
+    }
+
     // Duplicate task IDs within the same database should be rejected.
     #[rstest]
     fn create_task_duplicate_fails(db_with_goal_and_task: (TempDir, Database)) {
@@ -445,16 +946,47 @@ mod tests {
     #[rstest]
     fn get_task_mut_allows_mutation(db_with_goal_and_task: (TempDir, Database)) {
         let (_dir, mut db) = db_with_goal_and_task;
-        db.get_task_mut("t1")
-            .unwrap()
-            .transition(TaskState::Pending, TaskState::InProgress);
+        db.get_task_mut("t1").unwrap().transition(
+            &TaskState::Pending,
+            TaskState::InProgress,
+            ReasonCode::UserRequest,
+        );
         assert_eq!(db.get_task("t1").unwrap().state(), TaskState::InProgress);
     }
 
+    // Deleting a goal leaves a tombstone on disk that survives a reload;
+    // unburying it forgets the deletion.
+    #[rstest]
+    fn delete_goal_records_tombstone(db_with_goal_and_task: (TempDir, Database)) {
+        let (dir, mut db) = db_with_goal_and_task;
+        db.delete_goal("g1").unwrap();
+        assert!(db.get_task("t1").is_none());
+
+        let mut reloaded = Database::open(dir.path()).unwrap();
+        assert!(reloaded.get_goal("g1").is_none());
+        assert!(reloaded.tombstones().goals.contains_key("g1"));
+
+        reloaded.unbury_goal("g1").unwrap();
+        let reloaded = Database::open(dir.path()).unwrap();
+        assert!(reloaded.tombstones().goals.is_empty());
+    }
+
+    // Discarding a goal removes it from disk without a tombstone.
+    #[rstest]
+    fn discard_goal_leaves_no_tombstone(db_with_goal_and_task: (TempDir, Database)) {
+        let (dir, mut db) = db_with_goal_and_task;
+        db.discard_goal("g1").unwrap();
+        assert!(db.get_task("t1").is_none());
+
+        let reloaded = Database::open(dir.path()).unwrap();
+        assert!(reloaded.get_goal("g1").is_none());
+        assert!(reloaded.tombstones().goals.is_empty());
+    }
+
     // -- list_tasks --
 
-    // list_tasks filters by goal_id and sorts by created_at ascending
-    // (oldest first). Tasks from other goals should not appear, and
+    // list_tasks filters by goal_id and sorts in creation order by the
+    // logical clock, not created_at. Tasks from other goals should not appear, and
     // querying a nonexistent goal returns an empty vec.
     #[rstest]
     fn list_tasks_filters_by_goal_and_sorts(db: (TempDir, Database)) {
@@ -490,15 +1022,76 @@ mod tests {
         db.create_task(make_task("t3", "g2", TaskState::Pending))
             .unwrap();
 
+        // t2 was created second on a machine whose clock is behind, so its
+        // timestamp is earlier, but it still lists after t1.
         let g1_tasks = db.list_tasks("g1");
         assert_eq!(g1_tasks.len(), 2);
-        assert_eq!(g1_tasks[0].id(), "t2");
-        assert_eq!(g1_tasks[1].id(), "t1");
+        assert_eq!(g1_tasks[0].id(), "t1");
+        assert_eq!(g1_tasks[1].id(), "t2");
 
         assert_eq!(db.list_tasks("g2").len(), 1);
         assert!(db.list_tasks("nonexistent").is_empty());
     }
 
+    // An explicit order wins over creation time; unordered (legacy) tasks
+    // share order 0 and still sort by creation among themselves.
+    #[rstest]
+    fn list_tasks_prefers_explicit_order(db: (TempDir, Database)) {
+        let (_dir, mut db) = db;
+        db.create_goal(make_goal("g1")).unwrap();
+
+        db.create_task(make_task("t1", "g1", TaskState::Pending).with_order(2))
+            .unwrap();
+        db.create_task(make_task("t2", "g1", TaskState::Pending).with_order(1))
+            .unwrap();
+        db.create_task(make_task("t3", "g1", TaskState::Pending))
+            .unwrap();
+
+        let ids: Vec<&str> = db.list_tasks("g1").iter().map(|t| t.id()).collect();
+        assert_eq!(ids, ["t3", "t2", "t1"]);
+    }
+
+    // A task written again under another goal, as sync may do, lists under
+    // that goal only, and a removed goal takes its tasks with it.
+    #[rstest]
+    fn list_tasks_follows_goal_changes(db_with_goal_and_task: (TempDir, Database)) {
+        let (_dir, mut db) = db_with_goal_and_task;
+        db.create_goal(make_goal("g2")).unwrap();
+        db.put_task(make_task("t1", "g2", TaskState::Pending))
+            .unwrap();
+        assert!(db.list_tasks("g1").is_empty());
+        assert_eq!(db.list_tasks("g2")[0].id(), "t1");
+        assert_eq!(db.compute_goal_metrics("g2").task_count(), 1);
+
+        db.delete_goal("g2").unwrap();
+        assert!(db.get_task("t1").is_none());
+        assert!(db.list_tasks("g2").is_empty());
+    }
+
+    // Summary rows come in the same order and carry what a table prints.
+    #[rstest]
+    fn list_task_summaries_follow_list_order(db: (TempDir, Database)) {
+        let (_dir, mut db) = db;
+        db.create_goal(make_goal("g1")).unwrap();
+        db.create_task(make_task("t1", "g1", TaskState::Pending).with_order(2))
+            .unwrap();
+        db.create_task(make_task("t2", "g1", TaskState::InProgress).with_order(1))
+            .unwrap();
+
+        let rows: Vec<(&str, String, &str)> = db
+            .list_task_summaries("g1")
+            .iter()
+            .map(|t| (t.id, t.label().to_string(), t.headline))
+            .collect();
+        assert_eq!(
+            rows,
+            [
+                ("t2", "in_progress".to_string(), "test task"),
+                ("t1", "pending".to_string(), "test task")
+            ]
+        );
+    }
+
     // -- compute_goal_metrics --
 
     // Metrics should aggregate tokens and elapsed_ms across all tasks,
@@ -527,6 +1120,36 @@ mod tests {
         assert_eq!(metrics.elapsed_ms(), 800);
     }
 
+    // Spend is split by the agent and attempt number that used it, and a
+    // task from before attempts were kept counts as a first attempt.
+    #[rstest]
+    fn compute_goal_metrics_breaks_down_attempts(db: (TempDir, Database)) {
+        let (_dir, mut db) = db;
+        db.create_goal(make_goal("g1")).unwrap();
+
+        let mut retried = make_task("t1", "g1", TaskState::Completed);
+        let now = Timestamp::now();
+        let attempt = |number, agent: &str, outcome| {
+            Attempt::new(number, Some(agent.into()), outcome, None, now)
+        };
+        retried.add_attempt(attempt(1, "a", TaskState::Failed).with_spend(100, 80, 20, 10));
+        retried.add_attempt(attempt(2, "b", TaskState::Completed).with_spend(50, 30, 20, 5));
+        db.create_task(retried).unwrap();
+        db.create_task(
+            make_task("t2", "g1", TaskState::Completed).with_metrics(TaskMetrics::new(7, 0, 0)),
+        )
+        .unwrap();
+
+        let metrics = db.compute_goal_metrics("g1");
+        assert_eq!(metrics.total_tokens(), 157);
+        assert_eq!(metrics.prompt_tokens(), 110);
+        assert_eq!(metrics.completion_tokens(), 40);
+        assert_eq!(metrics.by_agent()["a"].tokens, 100);
+        assert_eq!(metrics.by_agent()["unassigned"].tokens, 7);
+        assert_eq!(metrics.by_attempt()[&1].attempts, 2);
+        assert_eq!(metrics.by_attempt()[&2].tokens, 50);
+    }
+
     // A nonexistent goal should produce zeroed metrics, not an error.
     #[rstest]
     fn compute_goal_metrics_empty(db: (TempDir, Database)) {
@@ -536,6 +1159,38 @@ mod tests {
         assert_eq!(metrics.total_tokens(), 0);
     }
 
+    // Computing every goal at once matches computing each on its own, and
+    // covers goals without tasks.
+    #[rstest]
+    fn compute_all_goal_metrics_matches_per_goal(db: (TempDir, Database)) {
+        let (_dir, mut db) = db;
+        for goal in ["g1", "g2", "g3"] {
+            db.create_goal(make_goal(goal)).unwrap();
+        }
+        db.create_task(
+            make_task("t1", "g1", TaskState::Completed).with_metrics(TaskMetrics::new(100, 5, 0)),
+        )
+        .unwrap();
+        db.create_task(make_task("t2", "g1", TaskState::Failed))
+            .unwrap();
+        db.create_task(
+            make_task("t3", "g2", TaskState::Completed).with_metrics(TaskMetrics::new(40, 0, 0)),
+        )
+        .unwrap();
+
+        let all = db.compute_all_goal_metrics();
+        assert_eq!(all.len(), 3);
+        for goal in ["g1", "g2", "g3"] {
+            let one = db.compute_goal_metrics(goal);
+            let from_all = &all[goal];
+            assert_eq!(from_all.task_count(), one.task_count());
+            assert_eq!(from_all.tasks_completed(), one.tasks_completed());
+            assert_eq!(from_all.tasks_failed(), one.tasks_failed());
+            assert_eq!(from_all.total_tokens(), one.total_tokens());
+        }
+        assert_eq!(all["g3"].task_count(), 0);
+    }
+
     // -- open / reload --
 
     // Dropping a Database and reopening from the same directory should
@@ -555,4 +1210,74 @@ mod tests {
     fn open_nonexistent_dir_fails() {
         assert!(Database::open("/tmp/definitely_does_not_exist_radial").is_err());
     }
+
+    // An in-memory store keeps records without touching the filesystem,
+    // not even a directory named after the sentinel path.
+    #[rstest]
+    fn in_memory_writes_nothing() {
+        let mut db = Database::open_in_memory();
+        assert!(db.is_in_memory());
+        db.create_goal(make_goal("g1")).unwrap();
+        db.create_task(make_task("t1", "g1", TaskState::Pending))
+            .unwrap();
+        assert_eq!(db.list_tasks("g1").len(), 1);
+        assert!(!Path::new(IN_MEMORY).exists());
+    }
+
+    // Files from older versions or other tools load with a warning instead
+    // of failing: an unknown state is kept as written and an epoch-millis
+    // timestamp is converted.
+    #[rstest]
+    fn open_tolerates_foreign_values(db_with_goal_and_task: (TempDir, Database)) {
+        let (dir, _) = db_with_goal_and_task;
+        let path = dir.path().join("g1").join("t1.toml");
+        let content = fs::read_to_string(&path).unwrap();
+        let content = content
+            .lines()
+            .map(|line| match line.split_once(" = ") {
+                Some(("state", _)) => "state = \"Archived\"".to_string(),
+                Some(("created_at", _)) => "created_at = 1714572180000".to_string(),
+                _ => line.to_string(),
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        fs::write(&path, content).unwrap();
+
+        let reloaded = Database::open(dir.path()).unwrap();
+        let task = reloaded.get_task("t1").unwrap();
+        assert_eq!(task.state(), TaskState::Unknown("Archived".to_string()));
+        assert_eq!(task.created_at().as_millisecond(), 1_714_572_180_000);
+        assert_eq!(reloaded.load_warnings().len(), 1);
+        assert!(reloaded.load_warnings()[0].contains("unknown state 'Archived'"));
+    }
+
+    // A timestamp that can't be read loads as the epoch, so the file is
+    // left alone rather than rewritten with it, until it is fixed by hand.
+    #[rstest]
+    fn unreadable_timestamp_blocks_rewrite(db_with_goal_and_task: (TempDir, Database)) {
+        let (dir, _) = db_with_goal_and_task;
+        let path = dir.path().join("g1").join("t1.toml");
+        let original = fs::read_to_string(&path).unwrap();
+        let damaged = original
+            .lines()
+            .map(|line| match line.split_once(" = ") {
+                Some(("created_at", _)) => "created_at = \"last tuesday\"".to_string(),
+                _ => line.to_string(),
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        fs::write(&path, &damaged).unwrap();
+
+        let mut reloaded = Database::open(dir.path()).unwrap();
+        assert!(reloaded.load_warnings()[0].contains("last tuesday"));
+        let task = reloaded.get_task("t1").unwrap().clone();
+        let err = reloaded.put_task(task).unwrap_err();
+        assert!(err.to_string().contains("couldn't read"), "{err}");
+        assert_eq!(fs::read_to_string(&path).unwrap(), damaged);
+
+        fs::write(&path, &original).unwrap();
+        let mut fixed = Database::open(dir.path()).unwrap();
+        let task = fixed.get_task("t1").unwrap().clone();
+        fixed.put_task(task).unwrap();
+    }
 }
@@ -0,0 +1,187 @@
+use std::fmt;
+
+use serde::Serialize;
+use serde::ser::SerializeStruct;
+
+use crate::helpers::find_similar_id;
+use crate::models::TaskState;
+use crate::models::state_machine;
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// Errors returned by the command layer. The CLI wraps these in `anyhow`, so
+/// callers that need the kind can `downcast_ref::<radial::Error>()`.
+#[derive(Debug)]
+pub enum Error {
+    /// No goal or task with this ID. `kind` is a lowercase noun such as "goal".
+    NotFound {
+        kind: &'static str,
+        id: String,
+        suggestion: Option<String>,
+    },
+    /// The task is not in a state that allows the requested move.
+    InvalidTransition {
+        id: String,
+        from: TaskState,
+        to: TaskState,
+    },
+    /// The request is well-formed but conflicts with current state, e.g. a
+    /// missing contract or another agent's assignment.
+    Conflict(String),
+    /// Reading or writing the `.radial` directory failed.
+    Storage(anyhow::Error),
+}
+
+impl Error {
+    /// A `NotFound` error with a "did you mean" hint drawn from `candidates`.
+    pub fn not_found(kind: &'static str, id: &str, candidates: &[&str]) -> Self {
+        Self::NotFound {
+            kind,
+            id: id.to_string(),
+            suggestion: find_similar_id(id, candidates).map(str::to_string),
+        }
+    }
+
+    pub fn conflict(message: impl Into<String>) -> Self {
+        Self::Conflict(message.into())
+    }
+
+    /// Stable `snake_case` name for the variant, used in JSON error output.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::NotFound { .. } => "not_found",
+            Self::InvalidTransition { .. } => "invalid_transition",
+            Self::Conflict(_) => "conflict",
+            Self::Storage(_) => "storage",
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotFound {
+                kind,
+                id,
+                suggestion,
+            } => {
+                let mut chars = kind.chars();
+                let first = chars.next().map(|c| c.to_ascii_uppercase());
+                write!(
+                    f,
+                    "{}{} not found: {id}",
+                    first.unwrap_or_default(),
+                    chars.as_str()
+                )?;
+                if let Some(suggestion) = suggestion {
+                    write!(f, "\nDid you mean: {suggestion}")?;
+                }
+                Ok(())
+            }
+            Self::InvalidTransition { id, from, to } => write!(
+                f,
+                "Cannot move task {id} from '{}' to '{}'\nFrom '{}' a task can move to: {}",
+                from.as_ref(),
+                to.as_ref(),
+                from.as_ref(),
+                state_machine::describe_next(from)
+            ),
+            Self::Conflict(message) => f.write_str(message),
+            Self::Storage(err) => write!(f, "{err:#}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Storage(err) => Some(err.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+/// Lower-level code still reports through `anyhow`; recover a wrapped
+/// `Error` if there is one, otherwise treat it as a storage failure.
+impl From<anyhow::Error> for Error {
+    fn from(err: anyhow::Error) -> Self {
+        err.downcast().unwrap_or_else(Self::Storage)
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(err: std::io::Error) -> Self {
+        Self::Storage(err.into())
+    }
+}
+
+impl Serialize for Error {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("Error", 4)?;
+        state.serialize_field("kind", self.kind())?;
+        state.serialize_field("message", &self.to_string())?;
+        match self {
+            Self::NotFound { id, suggestion, .. } => {
+                state.serialize_field("id", id)?;
+                state.serialize_field("suggestion", suggestion)?;
+            }
+            Self::InvalidTransition { id, from, to } => {
+                state.serialize_field("id", id)?;
+                state.serialize_field("transition", &(from, to))?;
+            }
+            Self::Conflict(_) | Self::Storage(_) => {}
+        }
+        state.end()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    // The suggestion is only attached when a candidate is close enough.
+    #[rstest]
+    #[case::close("abcdefgh", Some("abcdefgx"))]
+    #[case::far("zzzzzzzz", None)]
+    fn not_found_suggests_similar_id(#[case] id: &str, #[case] expected: Option<&str>) {
+        let err = Error::not_found("task", id, &["abcdefgx", "12345678"]);
+        match err {
+            Error::NotFound { suggestion, .. } => assert_eq!(suggestion.as_deref(), expected),
+            other => panic!("unexpected error: {other:?}"),
+        }
+    }
+
+    // Display keeps the "Did you mean" hint on its own line.
+    #[rstest]
+    fn not_found_display_includes_hint() {
+        let err = Error::not_found("goal", "abcdefgh", &["abcdefgx"]);
+        assert_eq!(
+            err.to_string(),
+            "Goal not found: abcdefgh\nDid you mean: abcdefgx"
+        );
+    }
+
+    // A domain error that passed through anyhow comes back out with its
+    // original kind rather than being flattened into Storage.
+    #[rstest]
+    fn from_anyhow_recovers_domain_error() {
+        let wrapped = anyhow::Error::from(Error::conflict("busy"));
+        assert_eq!(Error::from(wrapped).kind(), "conflict");
+        assert_eq!(Error::from(anyhow::anyhow!("disk full")).kind(), "storage");
+    }
+
+    // JSON output carries the kind and the variant's structured fields.
+    #[rstest]
+    fn serializes_kind_and_fields() {
+        let err = Error::InvalidTransition {
+            id: "t1".to_string(),
+            from: TaskState::Completed,
+            to: TaskState::InProgress,
+        };
+        let json = serde_json::to_value(&err).unwrap();
+        assert_eq!(json["kind"], "invalid_transition");
+        assert_eq!(json["transition"][0], "completed");
+        assert_eq!(json["transition"][1], "inprogress");
+    }
+}
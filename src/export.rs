@@ -0,0 +1,187 @@
+use std::fmt::Write as _;
+use std::fs;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+
+use crate::db::{Database, atomic_write};
+use crate::models::{Goal, Task, TaskState};
+
+pub const GOALS_EXPORT_DIR: &str = "goals";
+
+/// Regenerate `.radial/goals/<id>.md` for every goal and drop files for goals
+/// that no longer exist. Files are only rewritten when their content changes so
+/// read-only commands leave the working tree untouched.
+pub fn write_markdown(db: &Database) -> Result<()> {
+    let export_dir = db.base_path().join(GOALS_EXPORT_DIR);
+    fs::create_dir_all(&export_dir).context("Failed to create goals export directory")?;
+
+    for goal in db.list_goals() {
+        let path = export_dir.join(format!("{}.md", goal.id()));
+        let content = render_goal(goal, &db.list_tasks(goal.id()));
+        write_if_changed(&path, &content)?;
+    }
+
+    for entry in fs::read_dir(&export_dir).context("Failed to read goals export directory")? {
+        let path = entry.context("Failed to read export entry")?.path();
+        let is_stale = path.extension().is_some_and(|ext| ext == "md")
+            && path
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .is_some_and(|id| db.get_goal(id).is_none());
+        if is_stale {
+            fs::remove_file(&path)
+                .with_context(|| format!("Failed to remove {}", path.display()))?;
+        }
+    }
+
+    Ok(())
+}
+
+fn write_if_changed(path: &Path, content: &str) -> Result<()> {
+    if fs::read_to_string(path).is_ok_and(|existing| existing == content) {
+        return Ok(());
+    }
+    atomic_write(path, content.as_bytes())
+}
+
+/// Render a goal and its tasks as markdown. Volatile fields such as
+/// `updated_at` are left out so diffs only show changes to the plan itself.
+pub fn render_goal(goal: &Goal, tasks: &[&Task]) -> String {
+    let mut out = String::new();
+    let title = goal.headline().lines().next().unwrap_or_default();
+
+    let _ = writeln!(out, "# {title}");
+    let _ = writeln!(out);
+    let _ = writeln!(out, "- ID: `{}`", goal.id());
+    let _ = writeln!(out, "- State: {}", goal.state().as_ref());
+    let _ = writeln!(out, "- Created: {}", goal.created_at());
+    if let Some(completed_at) = goal.completed_at() {
+        let _ = writeln!(out, "- Completed: {completed_at}");
+    }
+
+    if goal.title().is_some() || goal.description().lines().count() > 1 {
+        let _ = writeln!(out);
+        let _ = writeln!(out, "{}", goal.description().trim_end());
+    }
+
+    let _ = writeln!(out);
+    let _ = writeln!(out, "## Tasks");
+
+    if tasks.is_empty() {
+        let _ = writeln!(out);
+        let _ = writeln!(out, "No tasks.");
+    }
+
+    for task in tasks {
+        let mark = if task.state() == TaskState::Completed {
+            "x"
+        } else {
+            " "
+        };
+        let summary = task.headline().lines().next().unwrap_or_default();
+
+        let _ = writeln!(out);
+        let _ = writeln!(out, "### [{mark}] `{}` {summary}", task.id());
+        let _ = writeln!(out);
+        let _ = writeln!(out, "- State: {}", task.state().as_ref());
+        if task.title().is_some() {
+            let _ = writeln!(out, "- Description: {}", task.description().trim_end());
+        }
+        if !task.blocked_by().is_empty() {
+            let _ = writeln!(out, "- Blocked by: {}", task.blocked_by().join(", "));
+        }
+        if let Some(contract) = task.contract() {
+            let _ = writeln!(out, "- Receives: {}", contract.receives());
+            let _ = writeln!(out, "- Produces: {}", contract.produces());
+            let _ = writeln!(out, "- Verify: {}", contract.verify());
+        }
+        if let Some(result) = task.result() {
+            let _ = writeln!(out, "- Result: {}", result.summary());
+            if !result.artifacts().is_empty() {
+                let _ = writeln!(out, "- Artifacts: {}", result.artifacts().join(", "));
+            }
+        }
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Contract, GoalState, Metrics, Outcome, ReasonCode, TaskMetrics};
+    use jiff::Timestamp;
+
+    fn goal() -> Goal {
+        let now = Timestamp::from_millisecond(1_000_000).unwrap();
+        Goal::new(
+            "g1".to_string(),
+            None,
+            "Ship the API\nWith auth and pagination.".to_string(),
+            GoalState::InProgress,
+            now,
+            now,
+            None,
+            Metrics::default(),
+        )
+    }
+
+    fn task(id: &str, state: TaskState) -> Task {
+        let now = Timestamp::from_millisecond(1_000_000).unwrap();
+        Task::new(
+            id.to_string(),
+            "g1".to_string(),
+            format!("Task {id}"),
+            Some(Contract::new(
+                "input".to_string(),
+                "output".to_string(),
+                "check".to_string(),
+            )),
+            state,
+            Vec::new(),
+            now,
+            now,
+        )
+    }
+
+    // The heading uses the first description line; the full description
+    // follows the metadata so multi-line goals are not lost.
+    #[test]
+    fn render_goal_includes_title_and_body() {
+        let out = render_goal(&goal(), &[]);
+        assert!(out.starts_with("# Ship the API\n"));
+        assert!(out.contains("With auth and pagination."));
+        assert!(out.contains("No tasks."));
+    }
+
+    // Completed tasks get a ticked checkbox and their result; contracts are
+    // listed for every task that has one.
+    #[test]
+    fn render_goal_lists_tasks_with_contracts_and_results() {
+        let mut done = task("t1", TaskState::InProgress);
+        done.complete(
+            Outcome::new("shipped".to_string(), vec!["api.rs".to_string()]),
+            TaskMetrics::default(),
+            ReasonCode::UserRequest,
+        );
+        let pending = task("t2", TaskState::Pending);
+
+        let out = render_goal(&goal(), &[&done, &pending]);
+        assert!(out.contains("### [x] `t1` Task t1"));
+        assert!(out.contains("### [ ] `t2` Task t2"));
+        assert!(out.contains("- Result: shipped"));
+        assert!(out.contains("- Artifacts: api.rs"));
+        assert!(out.contains("- Verify: check"));
+    }
+
+    // updated_at changes on every touch; leaving it out keeps the mirror
+    // stable across commands that don't change the plan.
+    #[test]
+    fn render_goal_is_stable_across_touches() {
+        let mut g = goal();
+        let before = render_goal(&g, &[]);
+        g.touch();
+        assert_eq!(before, render_goal(&g, &[]));
+    }
+}
@@ -0,0 +1,140 @@
+//! Gates: shell commands a task waits on besides its blockers, such as a
+//! health check on a staging server. A gate passes when its command exits
+//! successfully within the configured timeout. Results are kept in
+//! `.radial/gate-cache.toml` for a while, so agents polling `ready` don't
+//! run every check each time.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use jiff::{SignedDuration, Timestamp};
+use serde::{Deserialize, Serialize};
+
+use crate::db::{Database, atomic_write};
+use crate::models::Task;
+
+pub const CACHE_FILE: &str = "gate-cache.toml";
+
+/// How often a running gate is checked for having exited.
+const POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+struct Checked {
+    passed: bool,
+    at: Timestamp,
+}
+
+/// Whether each of `commands` passes, running those without a fresh cached
+/// result. Results are cached for `[gates] cache_for`; a command that fails
+/// to start or outlives `[gates] timeout` fails.
+pub fn evaluate<'a>(
+    commands: impl IntoIterator<Item = &'a str>,
+    db: &Database,
+) -> HashMap<String, bool> {
+    let config = &db.config().gates;
+    let path = db.base_path().join(CACHE_FILE);
+    let mut cache: BTreeMap<String, Checked> = fs::read_to_string(&path)
+        .ok()
+        .and_then(|text| toml::from_str(&text).ok())
+        .unwrap_or_default();
+
+    let now = Timestamp::now();
+    let mut results = HashMap::new();
+    let mut ran = false;
+    for command in commands {
+        if results.contains_key(command) {
+            continue;
+        }
+        let fresh = cache
+            .get(command)
+            .filter(|c| now.duration_since(c.at) < config.cache_for);
+        let passed = if let Some(checked) = fresh {
+            checked.passed
+        } else {
+            let passed = run(command, config.timeout);
+            cache.insert(command.to_string(), Checked { passed, at: now });
+            ran = true;
+            passed
+        };
+        results.insert(command.to_string(), passed);
+    }
+
+    if ran {
+        // Results older than the cache window are of no further use.
+        cache.retain(|_, c| now.duration_since(c.at) < config.cache_for);
+        if let Ok(content) = toml::to_string(&cache) {
+            // A cache that can't be written only means checking again.
+            let _ = atomic_write(&path, content.as_bytes());
+        }
+    }
+    results
+}
+
+/// Those of `tasks` whose gates all pass, running each distinct gate once.
+pub fn passing<'t>(tasks: Vec<&'t Task>, db: &Database) -> Vec<&'t Task> {
+    let results = evaluate(tasks.iter().flat_map(|t| t.gates()).map(String::as_str), db);
+    tasks
+        .into_iter()
+        .filter(|t| t.gates().iter().all(|g| results[g]))
+        .collect()
+}
+
+/// Run `command` in the shell, killing it if it outlives `timeout`.
+fn run(command: &str, timeout: SignedDuration) -> bool {
+    let (shell, flag) = if cfg!(windows) {
+        ("cmd", "/C")
+    } else {
+        ("sh", "-c")
+    };
+    let Ok(mut child) = Command::new(shell)
+        .args([flag, command])
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+    else {
+        return false;
+    };
+
+    let deadline = Instant::now() + timeout.unsigned_abs();
+    loop {
+        match child.try_wait() {
+            Ok(Some(status)) => return status.success(),
+            Ok(None) if Instant::now() < deadline => thread::sleep(POLL_INTERVAL),
+            _ => {
+                let _ = child.kill();
+                let _ = child.wait();
+                return false;
+            }
+        }
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::testing::TempProject;
+    use rstest::rstest;
+
+    // Exit status decides, a slow command times out, and a cached result is
+    // reused without running the command again.
+    #[rstest]
+    fn runs_and_caches_gates() {
+        let project = TempProject::new().unwrap();
+        let marker = project.path().join("ran");
+        let counted = format!("echo x >> {}", marker.display());
+
+        let results = evaluate(["true", "false", counted.as_str()], project.db());
+        assert!(results["true"]);
+        assert!(!results["false"]);
+        assert!(results[&counted]);
+
+        evaluate([counted.as_str()], project.db());
+        assert_eq!(fs::read_to_string(&marker).unwrap().lines().count(), 1);
+
+        assert!(!run("sleep 5", SignedDuration::from_millis(100)));
+    }
+}
@@ -0,0 +1,504 @@
+//! Just enough gzip (RFC 1951 and 1952) to keep archives small without a
+//! compression dependency. Writing uses LZ77 with the fixed Huffman codes,
+//! which is well suited to JSON's repetition; reading handles any gzip
+//! stream, so an archive recompressed with `gzip -9` still restores.
+
+use anyhow::{Result, bail};
+
+const MAGIC: [u8; 2] = [0x1f, 0x8b];
+const DEFLATE: u8 = 8;
+
+/// How far back a match may reach, and the longest one deflate allows.
+const WINDOW: usize = 32 * 1024;
+const MAX_MATCH: usize = 258;
+const MIN_MATCH: usize = 3;
+/// How many earlier positions to try per match; enough for JSON keys.
+const MAX_CHAIN: usize = 64;
+const HASH_BITS: u32 = 15;
+
+const LEN_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+    163, 195, 227, 258,
+];
+const LEN_EXTRA: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+const DIST_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+    2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+const DIST_EXTRA: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13,
+    13,
+];
+/// The order code length code lengths are sent in.
+const CODE_LENGTH_ORDER: [usize; 19] = [
+    16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15,
+];
+
+/// Whether `data` starts like a gzip stream.
+pub fn is_gzip(data: &[u8]) -> bool {
+    data.starts_with(&MAGIC)
+}
+
+/// `data` as a gzip stream.
+pub fn compress(data: &[u8]) -> Vec<u8> {
+    let mut out = BitWriter::default();
+    // Final block, fixed Huffman codes.
+    out.bits(1, 1);
+    out.bits(1, 2);
+
+    let mut head = vec![usize::MAX; 1 << HASH_BITS];
+    let mut prev = vec![usize::MAX; data.len()];
+    let mut at = 0;
+    while at < data.len() {
+        let (length, distance) = longest_match(data, at, &head, &prev);
+        if length >= MIN_MATCH {
+            out.length(length);
+            out.distance(distance);
+            for covered in at..at + length {
+                insert(data, covered, &mut head, &mut prev);
+            }
+            at += length;
+        } else {
+            out.literal(u16::from(data[at]));
+            insert(data, at, &mut head, &mut prev);
+            at += 1;
+        }
+    }
+    out.literal(256);
+
+    let mut gz = vec![MAGIC[0], MAGIC[1], DEFLATE, 0, 0, 0, 0, 0, 0, 255];
+    gz.extend(out.finish());
+    gz.extend(crc32(data).to_le_bytes());
+    // ISIZE is the length modulo 2^32.
+    #[allow(clippy::cast_possible_truncation)]
+    gz.extend((data.len() as u32).to_le_bytes());
+    gz
+}
+
+/// The data in gzip stream `gz`, checked against its CRC.
+pub fn decompress(gz: &[u8]) -> Result<Vec<u8>> {
+    if gz.len() < 18 || !is_gzip(gz) || gz[2] != DEFLATE {
+        bail!("Not a gzip stream");
+    }
+    let flags = gz[3];
+    let mut at = 10;
+    if flags & 0x04 != 0 {
+        let extra = usize::from(u16::from_le_bytes([gz[at], gz[at + 1]]));
+        at += 2 + extra;
+    }
+    for flag in [0x08, 0x10] {
+        if flags & flag != 0 {
+            let Some(end) = gz
+                .get(at..)
+                .and_then(|rest| rest.iter().position(|b| *b == 0))
+            else {
+                bail!("Truncated gzip header");
+            };
+            at += end + 1;
+        }
+    }
+    if flags & 0x02 != 0 {
+        at += 2;
+    }
+    let Some(body) = gz.get(at..gz.len() - 8) else {
+        bail!("Truncated gzip header");
+    };
+
+    let data = inflate(body)?;
+    let trailer = &gz[gz.len() - 8..];
+    let crc = u32::from_le_bytes([trailer[0], trailer[1], trailer[2], trailer[3]]);
+    if crc != crc32(&data) {
+        bail!("Gzip checksum mismatch; the file is damaged");
+    }
+    Ok(data)
+}
+
+fn hash(bytes: &[u8]) -> usize {
+    let key = u32::from(bytes[0]) << 16 | u32::from(bytes[1]) << 8 | u32::from(bytes[2]);
+    (key.wrapping_mul(2_654_435_761) >> (32 - HASH_BITS)) as usize
+}
+
+/// Remember position `at` as a match candidate for later bytes.
+fn insert(data: &[u8], at: usize, head: &mut [usize], prev: &mut [usize]) {
+    if at + MIN_MATCH <= data.len() {
+        let h = hash(&data[at..]);
+        prev[at] = head[h];
+        head[h] = at;
+    }
+}
+
+/// The longest earlier match for the bytes at `at`, as (length, distance).
+fn longest_match(data: &[u8], at: usize, head: &[usize], prev: &[usize]) -> (usize, usize) {
+    if at + MIN_MATCH > data.len() {
+        return (0, 0);
+    }
+    let limit = MAX_MATCH.min(data.len() - at);
+    let mut best = (0, 0);
+    let mut candidate = head[hash(&data[at..])];
+    for _ in 0..MAX_CHAIN {
+        if candidate == usize::MAX || at - candidate > WINDOW {
+            break;
+        }
+        let length = data[candidate..]
+            .iter()
+            .zip(&data[at..at + limit])
+            .take_while(|(a, b)| a == b)
+            .count();
+        if length > best.0 {
+            best = (length, at - candidate);
+            if length == limit {
+                break;
+            }
+        }
+        candidate = prev[candidate];
+    }
+    best
+}
+
+#[derive(Default)]
+struct BitWriter {
+    out: Vec<u8>,
+    acc: u64,
+    count: u32,
+}
+
+impl BitWriter {
+    /// Append the low `count` bits of `value`, least significant first.
+    fn bits(&mut self, value: u32, count: u32) {
+        self.acc |= u64::from(value) << self.count;
+        self.count += count;
+        while self.count >= 8 {
+            #[allow(clippy::cast_possible_truncation)]
+            self.out.push(self.acc as u8);
+            self.acc >>= 8;
+            self.count -= 8;
+        }
+    }
+
+    /// Append a Huffman code, which is sent most significant bit first.
+    fn code(&mut self, code: u32, length: u32) {
+        self.bits(code.reverse_bits() >> (32 - length), length);
+    }
+
+    fn literal(&mut self, symbol: u16) {
+        let symbol = u32::from(symbol);
+        match symbol {
+            0..=143 => self.code(0x30 + symbol, 8),
+            144..=255 => self.code(0x190 + symbol - 144, 9),
+            256..=279 => self.code(symbol - 256, 7),
+            _ => self.code(0xc0 + symbol - 280, 8),
+        }
+    }
+
+    fn length(&mut self, length: usize) {
+        let index = LEN_BASE
+            .iter()
+            .rposition(|b| usize::from(*b) <= length)
+            .unwrap();
+        #[allow(clippy::cast_possible_truncation)]
+        self.literal(257 + index as u16);
+        self.extra(length - usize::from(LEN_BASE[index]), LEN_EXTRA[index]);
+    }
+
+    fn distance(&mut self, distance: usize) {
+        let index = DIST_BASE
+            .iter()
+            .rposition(|b| usize::from(*b) <= distance)
+            .unwrap();
+        #[allow(clippy::cast_possible_truncation)]
+        self.code(index as u32, 5);
+        self.extra(distance - usize::from(DIST_BASE[index]), DIST_EXTRA[index]);
+    }
+
+    fn extra(&mut self, value: usize, count: u8) {
+        #[allow(clippy::cast_possible_truncation)]
+        self.bits(value as u32, u32::from(count));
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.count > 0 {
+            self.bits(0, 8 - self.count);
+        }
+        self.out
+    }
+}
+
+struct BitReader<'a> {
+    data: &'a [u8],
+    at: usize,
+    bit: u32,
+}
+
+impl BitReader<'_> {
+    fn bit(&mut self) -> Result<u32> {
+        let Some(byte) = self.data.get(self.at) else {
+            bail!("Gzip stream ends early");
+        };
+        let bit = u32::from(byte >> self.bit) & 1;
+        self.bit += 1;
+        if self.bit == 8 {
+            self.bit = 0;
+            self.at += 1;
+        }
+        Ok(bit)
+    }
+
+    /// `count` bits, least significant first.
+    fn bits(&mut self, count: u32) -> Result<u32> {
+        let mut value = 0;
+        for i in 0..count {
+            value |= self.bit()? << i;
+        }
+        Ok(value)
+    }
+
+    fn align(&mut self) {
+        if self.bit > 0 {
+            self.bit = 0;
+            self.at += 1;
+        }
+    }
+}
+
+/// A canonical Huffman code: how many codes each length has, and the
+/// symbols in code order.
+struct Huffman {
+    counts: [u16; 16],
+    symbols: Vec<u16>,
+}
+
+impl Huffman {
+    fn new(lengths: &[u8]) -> Self {
+        let mut counts = [0u16; 16];
+        for length in lengths {
+            counts[usize::from(*length)] += 1;
+        }
+        counts[0] = 0;
+        let mut offsets = [0u16; 16];
+        for length in 1..16 {
+            offsets[length] = offsets[length - 1] + counts[length - 1];
+        }
+        let mut symbols = vec![0; lengths.len()];
+        for (symbol, length) in (0u16..).zip(lengths) {
+            if *length > 0 {
+                let slot = &mut offsets[usize::from(*length)];
+                symbols[usize::from(*slot)] = symbol;
+                *slot += 1;
+            }
+        }
+        Self { counts, symbols }
+    }
+
+    fn decode(&self, input: &mut BitReader) -> Result<u16> {
+        let (mut code, mut first, mut index) = (0i32, 0i32, 0i32);
+        for count in &self.counts[1..] {
+            code |= i32::try_from(input.bit()?)?;
+            let count = i32::from(*count);
+            if code - first < count {
+                return Ok(self.symbols[usize::try_from(index + code - first)?]);
+            }
+            index += count;
+            first = (first + count) << 1;
+            code <<= 1;
+        }
+        bail!("Invalid Huffman code in gzip stream")
+    }
+}
+
+fn inflate(body: &[u8]) -> Result<Vec<u8>> {
+    let mut input = BitReader {
+        data: body,
+        at: 0,
+        bit: 0,
+    };
+    let mut out = Vec::new();
+    loop {
+        let last = input.bit()? == 1;
+        match input.bits(2)? {
+            0 => {
+                input.align();
+                let Some(header) = body.get(input.at..input.at + 4) else {
+                    bail!("Gzip stream ends early");
+                };
+                let length = usize::from(u16::from_le_bytes([header[0], header[1]]));
+                let start = input.at + 4;
+                let Some(stored) = body.get(start..start + length) else {
+                    bail!("Gzip stream ends early");
+                };
+                out.extend_from_slice(stored);
+                input.at = start + length;
+            }
+            1 => {
+                let mut lengths = [0u8; 288];
+                lengths[..144].fill(8);
+                lengths[144..256].fill(9);
+                lengths[256..280].fill(7);
+                lengths[280..].fill(8);
+                let literals = Huffman::new(&lengths);
+                let distances = Huffman::new(&[5; 30]);
+                inflate_block(&mut input, &literals, &distances, &mut out)?;
+            }
+            2 => {
+                let (literals, distances) = dynamic_codes(&mut input)?;
+                inflate_block(&mut input, &literals, &distances, &mut out)?;
+            }
+            _ => bail!("Invalid block type in gzip stream"),
+        }
+        if last {
+            return Ok(out);
+        }
+    }
+}
+
+/// Read the code tables a dynamic block starts with.
+fn dynamic_codes(input: &mut BitReader) -> Result<(Huffman, Huffman)> {
+    let literal_count = input.bits(5)? as usize + 257;
+    let distance_count = input.bits(5)? as usize + 1;
+    let code_length_count = input.bits(4)? as usize + 4;
+
+    let mut code_lengths = [0u8; 19];
+    for slot in &CODE_LENGTH_ORDER[..code_length_count] {
+        #[allow(clippy::cast_possible_truncation)]
+        let length = input.bits(3)? as u8;
+        code_lengths[*slot] = length;
+    }
+    let code_lengths = Huffman::new(&code_lengths);
+
+    let mut lengths = Vec::with_capacity(literal_count + distance_count);
+    while lengths.len() < literal_count + distance_count {
+        let (value, repeat) = match code_lengths.decode(input)? {
+            #[allow(clippy::cast_possible_truncation)]
+            symbol @ 0..=15 => (symbol as u8, 1),
+            16 => {
+                let Some(previous) = lengths.last() else {
+                    bail!("Invalid code lengths in gzip stream");
+                };
+                (*previous, 3 + input.bits(2)?)
+            }
+            17 => (0, 3 + input.bits(3)?),
+            18 => (0, 11 + input.bits(7)?),
+            _ => bail!("Invalid code lengths in gzip stream"),
+        };
+        lengths.extend(std::iter::repeat_n(value, repeat as usize));
+    }
+    if lengths.len() > literal_count + distance_count {
+        bail!("Invalid code lengths in gzip stream");
+    }
+    let (literals, distances) = lengths.split_at(literal_count);
+    Ok((Huffman::new(literals), Huffman::new(distances)))
+}
+
+fn inflate_block(
+    input: &mut BitReader,
+    literals: &Huffman,
+    distances: &Huffman,
+    out: &mut Vec<u8>,
+) -> Result<()> {
+    loop {
+        let symbol = literals.decode(input)?;
+        match symbol {
+            #[allow(clippy::cast_possible_truncation)]
+            0..=255 => out.push(symbol as u8),
+            256 => return Ok(()),
+            257..=285 => {
+                let index = usize::from(symbol - 257);
+                let length = usize::from(LEN_BASE[index])
+                    + input.bits(u32::from(LEN_EXTRA[index]))? as usize;
+                let index = usize::from(distances.decode(input)?);
+                if index >= DIST_BASE.len() {
+                    bail!("Invalid distance in gzip stream");
+                }
+                let distance = usize::from(DIST_BASE[index])
+                    + input.bits(u32::from(DIST_EXTRA[index]))? as usize;
+                if distance > out.len() {
+                    bail!("Invalid distance in gzip stream");
+                }
+                let start = out.len() - distance;
+                for i in 0..length {
+                    out.push(out[start + i]);
+                }
+            }
+            _ => bail!("Invalid length in gzip stream"),
+        }
+    }
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for byte in data {
+        crc ^= u32::from(*byte);
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0xedb8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    // What goes in comes back out, however repetitive or short, and
+    // repetitive JSON shrinks.
+    #[rstest]
+    #[case::empty(b"".to_vec())]
+    #[case::short(b"ab".to_vec())]
+    #[case::json(
+        (0..500)
+            .map(|i| format!(r#"{{"id":"t{i}","state":"completed","description":"Task {i}"}},"#))
+            .collect::<Vec<_>>()
+            .concat()
+            .into_bytes()
+    )]
+    #[case::bytes((0..70_000u32).map(|i| (i * 7919 % 251) as u8).collect())]
+    fn round_trips(#[case] data: Vec<u8>) {
+        let gz = compress(&data);
+        assert!(is_gzip(&gz));
+        assert_eq!(decompress(&gz).unwrap(), data);
+        if data.len() > 1000 {
+            assert!(gz.len() < data.len() / 2, "{} of {}", gz.len(), data.len());
+        }
+    }
+
+    // Streams from other tools use dynamic Huffman codes and header fields
+    // radial doesn't write; this one is Python's `gzip` at level 9, with a
+    // file name.
+    #[rstest]
+    fn reads_other_tools_output() {
+        let gz = [
+            0x1f, 0x8b, 0x08, 0x08, 0x00, 0x00, 0x00, 0x00, 0x02, 0xff, 0x61, 0x2e, 0x74, 0x78,
+            0x74, 0x00, 0x25, 0x8d, 0xd1, 0x0d, 0x83, 0x30, 0x0c, 0x44, 0x57, 0xb9, 0x01, 0x10,
+            0x3b, 0xf0, 0xd1, 0x05, 0xba, 0x81, 0xe5, 0x18, 0x12, 0x41, 0xe2, 0xca, 0x36, 0xad,
+            0xd8, 0xbe, 0x21, 0xfc, 0xf9, 0x9e, 0xfd, 0xce, 0x6f, 0x4a, 0x85, 0x0e, 0x84, 0x11,
+            0xef, 0x8e, 0x4d, 0xe9, 0x70, 0x50, 0x4b, 0x08, 0xf2, 0x9e, 0x57, 0x35, 0xd0, 0x26,
+            0x2d, 0x7c, 0xc6, 0x8b, 0x38, 0x0f, 0x8c, 0x4c, 0xfd, 0x06, 0x49, 0x9c, 0xad, 0x7c,
+            0xa2, 0x68, 0x9b, 0x7a, 0xf4, 0xa0, 0x90, 0x69, 0xb8, 0x84, 0xaf, 0x58, 0x59, 0x0b,
+            0xd3, 0xbd, 0x04, 0x6b, 0xad, 0x1d, 0xcf, 0x58, 0x46, 0x13, 0xf8, 0xa0, 0x52, 0x61,
+            0x42, 0xe9, 0x7a, 0xde, 0x4c, 0xb0, 0xb3, 0x21, 0xb2, 0xd4, 0xc7, 0x37, 0x61, 0xb5,
+            0x74, 0x03, 0xe8, 0x19, 0x5d, 0x17, 0xfc, 0x4a, 0xe4, 0x01, 0x42, 0x77, 0x69, 0x7e,
+            0x8f, 0x17, 0x4e, 0x97, 0xde, 0xfa, 0x07, 0x8b, 0x53, 0x8d, 0xf4, 0xc2, 0x00, 0x00,
+            0x00,
+        ];
+        let text = String::from_utf8(decompress(&gz).unwrap()).unwrap();
+        assert!(text.starts_with("Radial tracks goals and tasks for agents. "));
+        assert!(text.ends_with("with the tokens they used. "));
+        assert_eq!(text.len(), 194);
+    }
+
+    // A damaged stream is refused rather than restored wrongly.
+    #[rstest]
+    fn rejects_damage() {
+        let mut gz = compress(b"hello hello hello");
+        let last = gz.len() - 5;
+        gz[last] ^= 0xff;
+        assert!(decompress(&gz).is_err());
+        assert!(decompress(b"{\"goal\":1}").is_err());
+    }
+}
@@ -1,5 +1,12 @@
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use anyhow::{Context, Result, bail};
+use jiff::{SignedDuration, Timestamp};
 use strsim::levenshtein;
 
+use crate::lenient::parse_timestamp;
+
 /// Find the most similar ID from a list of candidates
 pub fn find_similar_id<'a>(target: &str, candidates: &[&'a str]) -> Option<&'a str> {
     candidates
@@ -10,9 +17,483 @@ pub fn find_similar_id<'a>(target: &str, candidates: &[&'a str]) -> Option<&'a s
         .map(|(id, _)| id)
 }
 
+/// Resolve a redirect target relative to `base`, expanding `~`, `$VAR`,
+/// `${VAR}` and `%VAR%` from the process environment.
+pub fn expand_redirect_path(target: &str, base: &Path) -> PathBuf {
+    expand_path_with(target, base, |name| std::env::var(name).ok())
+}
+
+fn expand_path_with(target: &str, base: &Path, env: impl Fn(&str) -> Option<String>) -> PathBuf {
+    let expanded = expand_vars(target.trim(), &env);
+
+    let expanded = match expanded.strip_prefix('~') {
+        Some(rest) if rest.is_empty() || rest.starts_with(['/', '\\']) => {
+            match env("HOME").or_else(|| env("USERPROFILE")) {
+                Some(home) => format!("{home}{rest}"),
+                None => expanded,
+            }
+        }
+        _ => expanded,
+    };
+
+    if is_absolute_anywhere(&expanded) {
+        PathBuf::from(expanded)
+    } else {
+        base.join(expanded)
+    }
+}
+
+/// Replace `$VAR`, `${VAR}` and `%VAR%`. Unset variables are left verbatim
+/// so the resulting path fails visibly instead of silently pointing elsewhere.
+fn expand_vars(input: &str, env: &impl Fn(&str) -> Option<String>) -> String {
+    let mut out = String::with_capacity(input.len());
+    let mut rest = input;
+
+    while let Some(pos) = rest.find(['$', '%']) {
+        out.push_str(&rest[..pos]);
+        let sigil = &rest[pos..];
+
+        let (name, token_len) = if let Some(braced) = sigil.strip_prefix("${") {
+            match braced.find('}') {
+                Some(end) => (&braced[..end], end + 3),
+                None => ("", 0),
+            }
+        } else if let Some(percent) = sigil.strip_prefix('%') {
+            match percent.find('%') {
+                Some(end) if is_var_name(&percent[..end]) => (&percent[..end], end + 2),
+                _ => ("", 0),
+            }
+        } else {
+            let body = &sigil[1..];
+            let end = body
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                .unwrap_or(body.len());
+            (&body[..end], end + 1)
+        };
+
+        if let Some(value) = env(name).filter(|_| is_var_name(name)) {
+            out.push_str(&value);
+            rest = &sigil[token_len..];
+        } else {
+            let keep = token_len.max(1);
+            out.push_str(&sigil[..keep]);
+            rest = &sigil[keep..];
+        }
+    }
+
+    out.push_str(rest);
+    out
+}
+
+fn is_var_name(name: &str) -> bool {
+    !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Absolute on this platform, or a Windows drive-absolute (`C:\x`, `C:/x`),
+/// UNC (`\\server\share`) or verbatim (`\\?\C:\x`) path. Checking the
+/// Windows forms everywhere keeps a shared redirect file from being joined
+/// onto the checkout path when read from another OS.
+fn is_absolute_anywhere(path: &str) -> bool {
+    let bytes = path.as_bytes();
+    Path::new(path).is_absolute()
+        || path.starts_with("\\\\")
+        || (bytes.len() >= 3
+            && bytes[0].is_ascii_alphabetic()
+            && bytes[1] == b':'
+            && matches!(bytes[2], b'\\' | b'/'))
+}
+
+/// The branch checked out in the git repository containing `dir`, read from
+/// `HEAD` so no `git` binary is needed. Worktrees (where `.git` is a file
+/// pointing at the real git directory) are followed. `None` outside a
+/// repository or on a detached HEAD.
+pub fn current_git_branch(dir: &Path) -> Option<String> {
+    let head = std::fs::read_to_string(git_dir(dir)?.join("HEAD")).ok()?;
+    head.trim()
+        .strip_prefix("ref: refs/heads/")
+        .map(ToString::to_string)
+}
+
+/// The commit checked out in the git repository containing `dir`, read
+/// from `HEAD` and the refs it points at, loose or packed. `None` outside a
+/// repository or before the first commit.
+pub fn current_git_commit(dir: &Path) -> Option<String> {
+    let git_dir = git_dir(dir)?;
+    let head = std::fs::read_to_string(git_dir.join("HEAD")).ok()?;
+    let Some(reference) = head.trim().strip_prefix("ref:").map(str::trim) else {
+        return Some(head.trim().to_string()).filter(|sha| is_sha(sha));
+    };
+    let common = common_dir(&git_dir);
+    if let Ok(sha) = std::fs::read_to_string(common.join(reference)) {
+        return Some(sha.trim().to_string()).filter(|sha| is_sha(sha));
+    }
+    std::fs::read_to_string(common.join("packed-refs"))
+        .ok()?
+        .lines()
+        .find_map(|line| {
+            let (sha, name) = line.split_once(' ')?;
+            (name == reference && is_sha(sha)).then(|| sha.to_string())
+        })
+}
+
+/// The URL of the `origin` remote, or else the first remote, from the
+/// repository's config.
+pub fn git_remote_url(dir: &Path) -> Option<String> {
+    let config = std::fs::read_to_string(common_dir(&git_dir(dir)?).join("config")).ok()?;
+    let mut remotes: Vec<(String, String)> = Vec::new();
+    let mut section = None;
+    for line in config.lines().map(str::trim) {
+        if line.starts_with('[') {
+            section = line
+                .strip_prefix("[remote \"")
+                .and_then(|rest| rest.strip_suffix("\"]"))
+                .map(str::to_string);
+        } else if let (Some(remote), Some((key, value))) = (&section, line.split_once('='))
+            && key.trim() == "url"
+        {
+            remotes.push((remote.clone(), value.trim().to_string()));
+        }
+    }
+    let origin = remotes.iter().position(|(name, _)| name == "origin");
+    remotes
+        .into_iter()
+        .nth(origin.unwrap_or_default())
+        .map(|(_, url)| url)
+}
+
+/// The git directory of the repository containing `dir`. Worktrees (where
+/// `.git` is a file pointing at the real git directory) are followed.
+fn git_dir(dir: &Path) -> Option<PathBuf> {
+    let dot_git = dir
+        .ancestors()
+        .map(|d| d.join(".git"))
+        .find(|p| p.exists())?;
+    if dot_git.is_file() {
+        let pointer = std::fs::read_to_string(&dot_git).ok()?;
+        let target = pointer.trim().strip_prefix("gitdir:")?.trim();
+        Some(dot_git.parent()?.join(target))
+    } else {
+        Some(dot_git)
+    }
+}
+
+/// Where refs and config live: a linked worktree's git directory names the
+/// main one in `commondir`.
+fn common_dir(git_dir: &Path) -> PathBuf {
+    std::fs::read_to_string(git_dir.join("commondir")).map_or_else(
+        |_| git_dir.to_path_buf(),
+        |common| git_dir.join(common.trim()),
+    )
+}
+
+fn is_sha(text: &str) -> bool {
+    text.len() >= 40 && text.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+/// Open `initial` in the user's editor (`$VISUAL`, then `$EDITOR`, then
+/// `vi`) and return what they saved. `extension` names the temp file's type
+/// so editors pick the right syntax highlighting.
+pub fn edit_in_editor(initial: &str, extension: &str) -> Result<String> {
+    let editor = editor();
+    let path = std::env::temp_dir().join(format!("radial-{}.{extension}", std::process::id()));
+    std::fs::write(&path, initial)
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+
+    let status = shell_with(&editor, &path.to_string_lossy())
+        .status()
+        .with_context(|| format!("Failed to run editor: {editor}"))?;
+    let edited = std::fs::read_to_string(&path);
+    let _ = std::fs::remove_file(&path);
+
+    if !status.success() {
+        bail!("Editor exited with {status}; nothing was changed");
+    }
+    edited.with_context(|| format!("Failed to read {}", path.display()))
+}
+
+/// Open `path` in the user's editor and wait for it to close.
+pub fn open_in_editor(path: &Path) -> Result<()> {
+    let editor = editor();
+    let status = shell_with(&editor, &path.to_string_lossy())
+        .status()
+        .with_context(|| format!("Failed to run editor: {editor}"))?;
+    if !status.success() {
+        bail!("Editor exited with {status}");
+    }
+    Ok(())
+}
+
+/// Open `url` in `$BROWSER`, or else the system's default browser.
+pub fn open_in_browser(url: &str) -> Result<()> {
+    let mut command = if let Ok(browser) = std::env::var("BROWSER") {
+        shell_with(&browser, url)
+    } else {
+        let mut command = if cfg!(target_os = "macos") {
+            Command::new("open")
+        } else if cfg!(windows) {
+            let mut start = Command::new("cmd");
+            start.args(["/C", "start", ""]);
+            start
+        } else {
+            Command::new("xdg-open")
+        };
+        command.arg(url);
+        command
+    };
+    let status = command
+        .status()
+        .with_context(|| format!("Failed to open a browser for {url}"))?;
+    if !status.success() {
+        bail!("Browser exited with {status}");
+    }
+    Ok(())
+}
+
+/// `$VISUAL`, then `$EDITOR`, then `vi`.
+fn editor() -> String {
+    std::env::var("VISUAL")
+        .or_else(|_| std::env::var("EDITOR"))
+        .unwrap_or_else(|_| "vi".to_string())
+}
+
+/// `program` run on `arg` through the shell, so programs configured with
+/// arguments of their own work. On Unix `arg` is passed as a positional
+/// parameter, so it is never read as shell syntax.
+fn shell_with(program: &str, arg: &str) -> Command {
+    let mut command;
+    if cfg!(windows) {
+        command = Command::new("cmd");
+        command.arg("/C").arg(format!("{program} \"{arg}\""));
+    } else {
+        command = Command::new("sh");
+        command
+            .arg("-c")
+            .arg(format!("{program} \"$1\""))
+            .arg("sh")
+            .arg(arg);
+    }
+    command
+}
+
+/// Split a command line into words the way a POSIX shell would for plain
+/// arguments: whitespace separates words, single quotes keep text as is, and
+/// double quotes and backslashes work as usual. No expansion is done.
+pub fn split_words(line: &str) -> Result<Vec<String>> {
+    let mut words = Vec::new();
+    let mut word: Option<String> = None;
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => words.extend(word.take()),
+            '\'' => {
+                let word = word.get_or_insert_default();
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some(c) => word.push(c),
+                        None => bail!("Unterminated single quote"),
+                    }
+                }
+            }
+            '"' => {
+                let word = word.get_or_insert_default();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some(c @ ('"' | '\\' | '$' | '`')) => word.push(c),
+                            Some(c) => {
+                                word.push('\\');
+                                word.push(c);
+                            }
+                            None => bail!("Unterminated double quote"),
+                        },
+                        Some(c) => word.push(c),
+                        None => bail!("Unterminated double quote"),
+                    }
+                }
+            }
+            '\\' => {
+                if let Some(c) = chars.next() {
+                    word.get_or_insert_default().push(c);
+                }
+            }
+            c => word.get_or_insert_default().push(c),
+        }
+    }
+    words.extend(word);
+    Ok(words)
+}
+
+/// `text` as one shell word, quoted only if it needs to be.
+pub fn shell_quote(text: &str) -> String {
+    let plain = !text.is_empty()
+        && text
+            .chars()
+            .all(|c| c.is_alphanumeric() || "-_./:=@,".contains(c));
+    if plain {
+        text.to_string()
+    } else {
+        format!("'{}'", text.replace('\'', "'\\''"))
+    }
+}
+
+/// IDs written as `#<id>` in `text`, in order of first mention. A `#`
+/// inside a word, URL or HTML entity (`C#`, `page#top`, `&#39;`) is not a
+/// mention, nor is a markdown heading's `# `.
+pub fn mentions(text: &str) -> Vec<&str> {
+    let mut found: Vec<&str> = Vec::new();
+    for (at, _) in text.match_indices('#') {
+        let after_word = text[..at]
+            .chars()
+            .next_back()
+            .is_some_and(|c| c.is_alphanumeric() || "_&/#".contains(c));
+        if after_word {
+            continue;
+        }
+        let rest = &text[at + 1..];
+        let end = rest
+            .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_' || c == '-'))
+            .unwrap_or(rest.len());
+        let id = rest[..end].trim_end_matches('-');
+        if !id.is_empty() && !found.contains(&id) {
+            found.push(id);
+        }
+    }
+    found
+}
+
+/// A path from `start` back to itself following `next`, which gives the
+/// nodes a node points at (for tasks, its blockers), e.g. `[a, b, c, a]`.
+/// `None` if `start` is not on a cycle.
+pub fn find_cycle(start: &str, next: impl Fn(&str) -> Vec<String>) -> Option<Vec<String>> {
+    let mut path = vec![start.to_string()];
+    let mut visited = std::collections::HashSet::new();
+    if walk_to(start, &next, &mut path, &mut visited) {
+        Some(path)
+    } else {
+        None
+    }
+}
+
+fn walk_to(
+    target: &str,
+    next: &impl Fn(&str) -> Vec<String>,
+    path: &mut Vec<String>,
+    visited: &mut std::collections::HashSet<String>,
+) -> bool {
+    let current = path.last().unwrap().clone();
+    for node in next(&current) {
+        if node == target {
+            path.push(node);
+            return true;
+        }
+        if visited.insert(node.clone()) {
+            path.push(node);
+            if walk_to(target, next, path, visited) {
+                return true;
+            }
+            path.pop();
+        }
+    }
+    false
+}
+
+/// A point in time written as a timestamp (anything
+/// [`crate::lenient::parse_timestamp`] reads) or as a duration from now,
+/// such as `2h` or `1d 6h`.
+pub fn parse_when(text: &str) -> Result<Timestamp, String> {
+    if let Some(at) = parse_timestamp(text) {
+        return Ok(at);
+    }
+    text.trim()
+        .parse::<SignedDuration>()
+        .ok()
+        .and_then(|offset| Timestamp::now().checked_add(offset).ok())
+        .ok_or_else(|| format!("'{text}' is neither a timestamp nor a duration such as 2h"))
+}
+
+/// A positive length of time such as `45m`, `2h`, `1h 30m` or `5m30s`.
+pub fn parse_duration(text: &str) -> Result<SignedDuration, String> {
+    text.trim()
+        .parse::<SignedDuration>()
+        .ok()
+        .filter(SignedDuration::is_positive)
+        .ok_or_else(|| format!("'{text}' is not a duration such as 45m or 1h 30m"))
+}
+
+/// A duration for a flag that took a bare number before it took durations:
+/// a whole number counts in `unit`, anything else must be a duration.
+fn duration_or_count(
+    text: &str,
+    unit: fn(i64) -> SignedDuration,
+) -> Result<SignedDuration, String> {
+    match text.trim().parse::<i64>() {
+        Ok(count) if count >= 0 => Ok(unit(count)),
+        Ok(_) => Err(format!("'{text}' is negative")),
+        Err(_) => parse_duration(text),
+    }
+}
+
+/// `--elapsed`: a duration such as `5m30s`, or a bare number of
+/// milliseconds. Returns milliseconds, as the metrics store them.
+pub fn parse_elapsed(text: &str) -> Result<i64, String> {
+    let elapsed = duration_or_count(text, SignedDuration::from_millis)?;
+    Ok(i64::try_from(elapsed.as_millis()).unwrap_or(i64::MAX))
+}
+
+/// A duration such as `90s`, or a bare number of seconds.
+pub fn parse_seconds(text: &str) -> Result<SignedDuration, String> {
+    duration_or_count(text, SignedDuration::from_secs)
+}
+
+/// A duration such as `1h 30m`, or a bare number of minutes.
+pub fn parse_minutes(text: &str) -> Result<SignedDuration, String> {
+    duration_or_count(text, SignedDuration::from_mins)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use rstest::rstest;
+
+    // Durations read in jiff's friendly format, with or without spaces;
+    // zero, negative and unitless values are refused.
+    #[rstest]
+    #[case::compact("5m30s", Some(330))]
+    #[case::spaced("1h 30m", Some(5400))]
+    #[case::long("2 hours", Some(7200))]
+    #[case::zero("0s", None)]
+    #[case::negative("-5m", None)]
+    #[case::bare("90", None)]
+    fn parses_durations(#[case] text: &str, #[case] secs: Option<i64>) {
+        assert_eq!(parse_duration(text).ok().map(|d| d.as_secs()), secs);
+    }
+
+    // Flags that took bare numbers still do, in their old unit.
+    #[rstest]
+    fn bare_numbers_keep_their_unit() {
+        assert_eq!(parse_elapsed("1500"), Ok(1500));
+        assert_eq!(parse_elapsed("5m30s"), Ok(330_000));
+        assert_eq!(parse_elapsed("0"), Ok(0));
+        assert_eq!(parse_seconds("2"), Ok(SignedDuration::from_secs(2)));
+        assert_eq!(parse_minutes("90"), Ok(SignedDuration::from_mins(90)));
+        assert_eq!(parse_minutes("1h"), Ok(SignedDuration::from_mins(60)));
+        assert!(parse_minutes("-5").is_err());
+    }
+
+    // Only a `#` that starts a word is a mention; each ID is listed once.
+    #[rstest]
+    #[case::plain("see #t1 and #g_2.", &["t1", "g_2"])]
+    #[case::repeated("#t1, then #t1 again", &["t1"])]
+    #[case::line_start("#t1 first\n(#t2)", &["t1", "t2"])]
+    #[case::in_word("C# and page#top", &[])]
+    #[case::url("https://x.io/#frag", &[])]
+    #[case::entity("it&#39;s", &[])]
+    #[case::heading("# Heading\n## Sub", &[])]
+    fn finds_mentions(#[case] text: &str, #[case] expected: &[&str]) {
+        assert_eq!(mentions(text), expected);
+    }
 
     #[test]
     fn test_find_similar_id() {
@@ -25,4 +506,142 @@ mod tests {
         // Very different ID should return None
         assert_eq!(find_similar_id("zzzzz", &candidates), None);
     }
+
+    // Timestamps are taken as written; durations count from now.
+    #[rstest]
+    fn parses_times_and_offsets() {
+        let at: Timestamp = "2030-01-01T00:00:00Z".parse().unwrap();
+        assert_eq!(parse_when("2030-01-01T00:00:00Z"), Ok(at));
+        let later = parse_when("2h").unwrap().duration_since(Timestamp::now());
+        assert!(later > SignedDuration::from_mins(119) && later <= SignedDuration::from_hours(2));
+        assert!(parse_when("tomorrowish").is_err());
+    }
+
+    fn env(name: &str) -> Option<String> {
+        match name {
+            "HOME" => Some("/home/me".to_string()),
+            "WORK" => Some("/srv/work".to_string()),
+            "DRIVE" => Some("D:".to_string()),
+            _ => None,
+        }
+    }
+
+    // Redirect targets are expanded and only joined onto the checkout when
+    // they are genuinely relative, whichever OS wrote them.
+    #[rstest]
+    #[case::relative("../shared/.radial", "/repo/../shared/.radial")]
+    #[case::absolute("/srv/.radial", "/srv/.radial")]
+    #[case::tilde("~/work/.radial", "/home/me/work/.radial")]
+    #[case::tilde_alone("~", "/home/me")]
+    #[case::tilde_user_untouched("~bob/.radial", "/repo/~bob/.radial")]
+    #[case::dollar("$HOME/work/.radial", "/home/me/work/.radial")]
+    #[case::braced("${WORK}/.radial", "/srv/work/.radial")]
+    #[case::percent("%DRIVE%\\work\\.radial", "D:\\work\\.radial")]
+    #[case::unset_left_verbatim("$NOPE/.radial", "/repo/$NOPE/.radial")]
+    #[case::lone_percent("50%/x", "/repo/50%/x")]
+    #[case::drive_backslash("C:\\Users\\me\\.radial", "C:\\Users\\me\\.radial")]
+    #[case::drive_slash("C:/Users/me/.radial", "C:/Users/me/.radial")]
+    #[case::unc("\\\\server\\share\\.radial", "\\\\server\\share\\.radial")]
+    #[case::verbatim("\\\\?\\C:\\very\\long\\.radial", "\\\\?\\C:\\very\\long\\.radial")]
+    #[case::whitespace("  /srv/.radial\n", "/srv/.radial")]
+    fn expand_redirect_targets(#[case] target: &str, #[case] expected: &str) {
+        let resolved = expand_path_with(target, Path::new("/repo"), env);
+        assert_eq!(resolved, PathBuf::from(expected));
+    }
+
+    // Quotes group words and are removed; an empty quoted word is kept.
+    #[rstest]
+    #[case::plain("task start  t1", &["task", "start", "t1"])]
+    #[case::double(r#"goal create "Ship it""#, &["goal", "create", "Ship it"])]
+    #[case::single("comment t1 'say \"hi\"'", &["comment", "t1", "say \"hi\""])]
+    #[case::escaped(r#"a\ b "c\"d""#, &["a b", "c\"d"])]
+    #[case::joined(r#"--title="A B"x"#, &["--title=A Bx"])]
+    #[case::empty(r#"edit --title """#, &["edit", "--title", ""])]
+    fn split_words_follows_shell_quoting(#[case] line: &str, #[case] expected: &[&str]) {
+        assert_eq!(split_words(line).unwrap(), expected);
+    }
+
+    // An unterminated quote is an error rather than a silent guess.
+    #[rstest]
+    fn split_words_rejects_open_quote() {
+        assert!(split_words("goal create \"Ship").is_err());
+    }
+
+    // The branch comes from HEAD, including through a worktree's `.git`
+    // file; a detached HEAD has no branch.
+    #[rstest]
+    #[case::branch("ref: refs/heads/feature/login\n", Some("feature/login"))]
+    #[case::detached("3f2c1a9e0b\n", None)]
+    fn current_git_branch_reads_head(#[case] head: &str, #[case] expected: Option<&str>) {
+        let dir = tempfile::TempDir::new().unwrap();
+        let repo = dir.path().join("repo");
+        std::fs::create_dir_all(repo.join(".git")).unwrap();
+        std::fs::write(repo.join(".git/HEAD"), head).unwrap();
+        let nested = repo.join("src");
+        std::fs::create_dir_all(&nested).unwrap();
+        assert_eq!(current_git_branch(&nested).as_deref(), expected);
+
+        let worktree = dir.path().join("worktree");
+        std::fs::create_dir_all(&worktree).unwrap();
+        std::fs::write(worktree.join(".git"), "gitdir: ../repo/.git\n").unwrap();
+        assert_eq!(current_git_branch(&worktree).as_deref(), expected);
+    }
+
+    // The commit comes from the ref HEAD names, loose or packed, or from a
+    // detached HEAD directly; the remote prefers origin.
+    #[rstest]
+    fn reads_commit_and_remote() {
+        let sha = "3f2c1a9e0b3f2c1a9e0b3f2c1a9e0b3f2c1a9e0b";
+        let dir = tempfile::TempDir::new().unwrap();
+        let git = dir.path().join(".git");
+        std::fs::create_dir_all(git.join("refs/heads")).unwrap();
+        std::fs::write(git.join("HEAD"), "ref: refs/heads/main\n").unwrap();
+        assert_eq!(current_git_commit(dir.path()), None);
+
+        std::fs::write(
+            git.join("packed-refs"),
+            format!("# pack\n{sha} refs/heads/main\n"),
+        )
+        .unwrap();
+        assert_eq!(current_git_commit(dir.path()).as_deref(), Some(sha));
+        let loose = sha.replace('3', "4");
+        std::fs::write(git.join("refs/heads/main"), format!("{loose}\n")).unwrap();
+        assert_eq!(current_git_commit(dir.path()), Some(loose));
+        std::fs::write(git.join("HEAD"), format!("{sha}\n")).unwrap();
+        assert_eq!(current_git_commit(dir.path()).as_deref(), Some(sha));
+
+        std::fs::write(
+            git.join("config"),
+            "[core]\n\tbare = false\n[remote \"fork\"]\n\turl = git@github.com:me/repo.git\n\
+             [remote \"origin\"]\n\turl = https://gitlab.com/org/repo.git\n",
+        )
+        .unwrap();
+        assert_eq!(
+            git_remote_url(dir.path()).as_deref(),
+            Some("https://gitlab.com/org/repo.git")
+        );
+    }
+
+    // The path runs from the start back to it; nodes merely downstream of a
+    // cycle, or with no way back, have none.
+    #[rstest]
+    #[case::loop_of_three("a", Some(vec!["a", "b", "c", "a"]))]
+    #[case::self_loop("d", Some(vec!["d", "d"]))]
+    #[case::feeds_a_cycle("e", None)]
+    #[case::acyclic("c2", None)]
+    fn find_cycle_returns_path(#[case] start: &str, #[case] expected: Option<Vec<&str>>) {
+        let edges = |node: &str| -> Vec<String> {
+            let to: &[&str] = match node {
+                "a" => &["x", "b"],
+                "b" => &["c"],
+                "c" | "e" => &["a"],
+                "d" => &["d"],
+                "c2" => &["x"],
+                _ => &[],
+            };
+            to.iter().map(ToString::to_string).collect()
+        };
+        let expected = expected.map(|p| p.into_iter().map(String::from).collect());
+        assert_eq!(find_cycle(start, edges), expected);
+    }
 }
@@ -0,0 +1,326 @@
+use std::collections::HashMap;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+
+use anyhow::{Context, Result};
+use fs2::FileExt;
+use jiff::Timestamp;
+use jiff::tz::TimeZone;
+use serde::Serialize;
+
+use crate::clock;
+use crate::db::{Database, atomic_write};
+use crate::models::{Goal, ReasonCode, Task, TaskState};
+
+pub const HISTORY_DIR: &str = "history";
+
+/// Git merges each day's log by keeping the lines from both sides, so
+/// branches that each appended entries merge without conflicts.
+const GITATTRIBUTES: &str = "*.jsonl merge=union\n";
+
+/// The `updated_at` of every goal and task before a command ran, and each
+/// task's state.
+pub struct Snapshot {
+    goals: HashMap<String, Timestamp>,
+    tasks: HashMap<String, (Timestamp, TaskState)>,
+}
+
+impl Snapshot {
+    pub fn take(db: &Database) -> Self {
+        let goals = db.list_goals();
+        Self {
+            tasks: goals
+                .iter()
+                .flat_map(|g| db.list_tasks(g.id()))
+                .map(|t| (t.id().to_owned(), (t.updated_at(), t.state())))
+                .collect(),
+            goals: goals
+                .into_iter()
+                .map(|g| (g.id().to_owned(), g.updated_at()))
+                .collect(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Op {
+    Created,
+    Updated,
+    Deleted,
+}
+
+/// One line of `.radial/history/<date>.jsonl`. Created and updated entries
+/// carry the full record so the log can be replayed on its own.
+#[derive(Debug, Serialize)]
+pub struct Entry<'a> {
+    /// Logical clock value of the change; orders entries even when `at`
+    /// came from machines whose clocks disagree.
+    pub seq: u64,
+    pub at: Timestamp,
+    pub op: Op,
+    pub kind: &'static str,
+    pub id: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub goal: Option<&'a Goal>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub task: Option<&'a Task>,
+    /// Set when an existing task changed state.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub transition: Option<Transition>,
+}
+
+/// A task's move from one state to another, and why it was made.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Transition {
+    pub from: TaskState,
+    pub to: TaskState,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<ReasonCode>,
+}
+
+/// Everything that changed between `before` and the current state of `db`.
+/// Tasks are only removed along with their goal, so only the goal's deletion
+/// is listed.
+pub fn changes<'a>(before: &'a Snapshot, db: &'a Database) -> Vec<Entry<'a>> {
+    let now = Timestamp::now();
+    let mut entries = Vec::new();
+
+    for goal in db.list_goals() {
+        if let Some(op) = op_for(before.goals.get(goal.id()), goal.updated_at()) {
+            entries.push(Entry {
+                seq: goal.seq(),
+                at: goal.updated_at(),
+                op,
+                kind: "goal",
+                id: goal.id(),
+                goal: Some(goal),
+                task: None,
+                transition: None,
+            });
+        }
+        for task in db.list_tasks(goal.id()) {
+            let previous = before.tasks.get(task.id());
+            if let Some(op) = op_for(previous.map(|(at, _)| at), task.updated_at()) {
+                let transition =
+                    previous
+                        .filter(|(_, from)| *from != task.state())
+                        .map(|(_, from)| Transition {
+                            from: from.clone(),
+                            to: task.state(),
+                            reason: task.state_reason(),
+                        });
+                entries.push(Entry {
+                    seq: task.seq(),
+                    at: task.updated_at(),
+                    op,
+                    kind: "task",
+                    id: task.id(),
+                    goal: None,
+                    task: Some(task),
+                    transition,
+                });
+            }
+        }
+    }
+
+    for id in before.goals.keys() {
+        if db.get_goal(id).is_none() {
+            entries.push(Entry {
+                seq: clock::tick(),
+                at: db.tombstones().goals.get(id).copied().unwrap_or(now),
+                op: Op::Deleted,
+                kind: "goal",
+                id,
+                goal: None,
+                task: None,
+                transition: None,
+            });
+        }
+    }
+
+    entries.sort_by_key(|e| e.seq);
+    entries
+}
+
+fn op_for(before: Option<&Timestamp>, updated_at: Timestamp) -> Option<Op> {
+    match before {
+        None => Some(Op::Created),
+        Some(&previous) if previous != updated_at => Some(Op::Updated),
+        Some(_) => None,
+    }
+}
+
+/// Append whatever changed since `before` to today's history log (UTC).
+pub fn record(before: &Snapshot, db: &Database) -> Result<()> {
+    let entries = changes(before, db);
+    if entries.is_empty() {
+        return Ok(());
+    }
+
+    let dir = db.base_path().join(HISTORY_DIR);
+    fs::create_dir_all(&dir).context("Failed to create history directory")?;
+    let attributes = dir.join(".gitattributes");
+    if !attributes.exists() {
+        atomic_write(&attributes, GITATTRIBUTES.as_bytes())?;
+    }
+
+    let mut lines = String::new();
+    for entry in &entries {
+        lines.push_str(&serde_json::to_string(entry).context("Failed to serialize history")?);
+        lines.push('\n');
+    }
+
+    let date = Timestamp::now().to_zoned(TimeZone::UTC).date();
+    let path = dir.join(format!("{date}.jsonl"));
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .with_context(|| format!("Failed to open {}", path.display()))?;
+    file.lock_exclusive()
+        .context("Failed to acquire file lock")?;
+    file.write_all(lines.as_bytes())
+        .with_context(|| format!("Failed to append to {}", path.display()))?;
+    file.unlock().context("Failed to unlock file")?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{GoalState, Metrics};
+    use rstest::rstest;
+    use tempfile::TempDir;
+
+    fn goal(id: &str) -> Goal {
+        let now = Timestamp::now();
+        Goal::new(
+            id.to_string(),
+            None,
+            "goal".to_string(),
+            GoalState::Pending,
+            now,
+            now,
+            None,
+            Metrics::default(),
+        )
+    }
+
+    fn task(id: &str, goal_id: &str) -> Task {
+        let now = Timestamp::now();
+        Task::new(
+            id.to_string(),
+            goal_id.to_string(),
+            "task".to_string(),
+            None,
+            TaskState::Pending,
+            Vec::new(),
+            now,
+            now,
+        )
+    }
+
+    // New records are "created", records with a new updated_at are
+    // "updated", untouched ones are left out and removed goals are "deleted".
+    #[rstest]
+    fn changes_classifies_mutations() {
+        let dir = TempDir::new().unwrap();
+        let mut db = Database::open(dir.path()).unwrap();
+        db.create_goal(goal("g1")).unwrap();
+        db.create_goal(goal("g2")).unwrap();
+        db.create_task(task("t1", "g1")).unwrap();
+        let before = Snapshot::take(&db);
+
+        db.get_task_mut("t1").unwrap().transition(
+            &TaskState::Pending,
+            TaskState::InProgress,
+            ReasonCode::UserRequest,
+        );
+        db.create_task(task("t2", "g1")).unwrap();
+        db.delete_goal("g2").unwrap();
+
+        let entries = changes(&before, &db);
+        let summary: Vec<(&str, Op)> = entries.iter().map(|e| (e.id, e.op)).collect();
+        assert_eq!(summary.len(), 3);
+        assert!(summary.contains(&("t1", Op::Updated)));
+        assert!(summary.contains(&("t2", Op::Created)));
+        assert!(summary.contains(&("g2", Op::Deleted)));
+    }
+
+    // A state change is logged with where the task came from and why; other
+    // updates carry no transition.
+    #[rstest]
+    fn changes_records_transitions() {
+        let dir = TempDir::new().unwrap();
+        let mut db = Database::open(dir.path()).unwrap();
+        db.create_goal(goal("g1")).unwrap();
+        db.create_task(task("t1", "g1")).unwrap();
+        db.create_task(task("t2", "g1")).unwrap();
+        let before = Snapshot::take(&db);
+
+        db.get_task_mut("t1").unwrap().transition(
+            &TaskState::Pending,
+            TaskState::Blocked,
+            ReasonCode::AgentAuto,
+        );
+        db.get_task_mut("t2")
+            .unwrap()
+            .set_title(Some("Renamed".to_string()));
+
+        let entries = changes(&before, &db);
+        let transitions: Vec<(&str, Option<Transition>)> = entries
+            .iter()
+            .map(|e| (e.id, e.transition.clone()))
+            .collect();
+        assert_eq!(
+            transitions,
+            [
+                (
+                    "t1",
+                    Some(Transition {
+                        from: TaskState::Pending,
+                        to: TaskState::Blocked,
+                        reason: Some(ReasonCode::AgentAuto),
+                    })
+                ),
+                ("t2", None),
+            ]
+        );
+        let line = serde_json::to_value(&entries[0]).unwrap();
+        assert_eq!(line["transition"]["reason"], "agent_auto");
+    }
+
+    // Recording appends JSON lines and sets up the union merge driver.
+    #[rstest]
+    fn record_appends_lines() {
+        let dir = TempDir::new().unwrap();
+        let mut db = Database::open(dir.path()).unwrap();
+        let before = Snapshot::take(&db);
+        db.create_goal(goal("g1")).unwrap();
+        record(&before, &db).unwrap();
+
+        let before = Snapshot::take(&db);
+        db.create_task(task("t1", "g1")).unwrap();
+        record(&before, &db).unwrap();
+
+        let history = dir.path().join(HISTORY_DIR);
+        let attributes = fs::read_to_string(history.join(".gitattributes")).unwrap();
+        assert!(attributes.contains("merge=union"));
+
+        let log = fs::read_dir(&history)
+            .unwrap()
+            .map(|e| e.unwrap().path())
+            .find(|p| p.extension().is_some_and(|ext| ext == "jsonl"))
+            .unwrap();
+        let lines: Vec<serde_json::Value> = fs::read_to_string(log)
+            .unwrap()
+            .lines()
+            .map(|l| serde_json::from_str(l).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["kind"], "goal");
+        assert_eq!(lines[1]["op"], "created");
+        assert_eq!(lines[1]["task"]["id"], "t1");
+    }
+}
@@ -1,25 +1,105 @@
+use std::sync::OnceLock;
+
+use serde::{Deserialize, Serialize};
+
+use crate::clock;
+
+/// Makes generated IDs follow from this seed instead of being random, so
+/// tests can assert on them. **Never set it for a real store**: every store
+/// using the same seed hands out the same IDs, so records collide when
+/// stores are synced or shared, and IDs become guessable.
+pub const ID_SEED_ENV: &str = "RADIAL_ID_SEED";
+
+/// Which characters generated IDs are drawn from. IDs from either alphabet
+/// can live in the same store; lookups always match the exact string.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum IdAlphabet {
+    /// Digits and upper- and lowercase letters.
+    #[default]
+    Full,
+    /// Like `Full`, minus look-alikes that get mistyped when IDs are copied
+    /// by hand: `0`, `O`, `o`, `1`, `l` and `I`.
+    Unambiguous,
+}
+
+const FULL: [char; 62] = [
+    '0', '1', '2', '3', '4', '5', '6', '7', '8', '9', 'A', 'B', 'C', 'D', 'E', 'F', 'G', 'H', 'I',
+    'J', 'K', 'L', 'M', 'N', 'O', 'P', 'Q', 'R', 'S', 'T', 'U', 'V', 'W', 'X', 'Y', 'Z', 'a', 'b',
+    'c', 'd', 'e', 'f', 'g', 'h', 'i', 'j', 'k', 'l', 'm', 'n', 'o', 'p', 'q', 'r', 's', 't', 'u',
+    'v', 'w', 'x', 'y', 'z',
+];
+
+const UNAMBIGUOUS: [char; 56] = [
+    '2', '3', '4', '5', '6', '7', '8', '9', 'A', 'B', 'C', 'D', 'E', 'F', 'G', 'H', 'J', 'K', 'L',
+    'M', 'N', 'P', 'Q', 'R', 'S', 'T', 'U', 'V', 'W', 'X', 'Y', 'Z', 'a', 'b', 'c', 'd', 'e', 'f',
+    'g', 'h', 'i', 'j', 'k', 'm', 'n', 'p', 'q', 'r', 's', 't', 'u', 'v', 'w', 'x', 'y', 'z',
+];
+
 /// Generate a safe 8-character ID
 /// Uses alphanumeric characters only (no dashes or underscores)
 /// to avoid conflicts with CLI flag parsing
-pub fn generate_id() -> String {
-    const ALPHABET: [char; 62] = [
-        '0', '1', '2', '3', '4', '5', '6', '7', '8', '9', 'A', 'B', 'C', 'D', 'E', 'F', 'G', 'H',
-        'I', 'J', 'K', 'L', 'M', 'N', 'O', 'P', 'Q', 'R', 'S', 'T', 'U', 'V', 'W', 'X', 'Y', 'Z',
-        'a', 'b', 'c', 'd', 'e', 'f', 'g', 'h', 'i', 'j', 'k', 'l', 'm', 'n', 'o', 'p', 'q', 'r',
-        's', 't', 'u', 'v', 'w', 'x', 'y', 'z',
-    ];
-
-    nanoid::nanoid!(8, &ALPHABET)
+pub fn generate_id(alphabet: IdAlphabet) -> String {
+    let chars: &[char] = match alphabet {
+        IdAlphabet::Full => &FULL,
+        IdAlphabet::Unambiguous => &UNAMBIGUOUS,
+    };
+    match seed() {
+        Some(seed) => seeded_id(seed, clock::tick(), chars),
+        None => nanoid::nanoid!(8, chars),
+    }
+}
+
+/// The `RADIAL_ID_SEED` in effect, hashed, read once per process.
+pub fn seed() -> Option<u64> {
+    static SEED: OnceLock<Option<u64>> = OnceLock::new();
+    *SEED.get_or_init(|| {
+        std::env::var(ID_SEED_ENV)
+            .ok()
+            .filter(|s| !s.is_empty())
+            .map(|s| fnv1a(s.as_bytes()))
+    })
+}
+
+/// The ID for logical clock value `seq` under `seed`. The clock starts past
+/// every record loaded, so the same commands run against the same store
+/// give the same IDs in every process, and no two records share one.
+fn seeded_id(seed: u64, seq: u64, chars: &[char]) -> String {
+    let mut state = seed ^ seq;
+    (0..8)
+        .map(|_| {
+            let n = splitmix64(&mut state);
+            chars[usize::try_from(n % chars.len() as u64).unwrap_or_default()]
+        })
+        .collect()
+}
+
+/// A stable hash, unlike `std`'s, which may change between releases.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &b| {
+        (hash ^ u64::from(b)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+fn splitmix64(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use rstest::rstest;
 
-    #[test]
-    fn test_generate_id() {
+    #[rstest]
+    #[case::full(IdAlphabet::Full)]
+    #[case::unambiguous(IdAlphabet::Unambiguous)]
+    fn test_generate_id(#[case] alphabet: IdAlphabet) {
         for _ in 0..100 {
-            let id = generate_id();
+            let id = generate_id(alphabet);
             assert_eq!(id.len(), 8);
             assert!(!id.starts_with('-'));
             assert!(!id.starts_with('_'));
@@ -27,4 +107,25 @@ mod tests {
             assert!(!id.contains('_'));
         }
     }
+
+    // A seed gives the same ID for the same clock value, and different IDs
+    // for different values or seeds.
+    #[rstest]
+    fn seeded_ids_are_reproducible() {
+        let seed = fnv1a(b"golden");
+        let id = seeded_id(seed, 7, &FULL);
+        assert_eq!(id.len(), 8);
+        assert_eq!(id, seeded_id(seed, 7, &FULL));
+        assert_ne!(id, seeded_id(seed, 8, &FULL));
+        assert_ne!(id, seeded_id(fnv1a(b"other"), 7, &FULL));
+    }
+
+    // The unambiguous alphabet never produces look-alike characters.
+    #[rstest]
+    fn unambiguous_ids_skip_look_alikes() {
+        for _ in 0..200 {
+            let id = generate_id(IdAlphabet::Unambiguous);
+            assert!(!id.contains(['0', 'O', 'o', '1', 'l', 'I']), "{id}");
+        }
+    }
 }
@@ -0,0 +1,185 @@
+//! Forgiving decoders for fields that older versions and other tools write
+//! differently. Rather than failing the whole load, an odd value is read as
+//! best it can be and the problem is noted here; the store collects the
+//! notes per file (see [`crate::db::Database::load_warnings`]).
+
+use std::cell::{Cell, RefCell};
+use std::fmt;
+
+use jiff::Timestamp;
+use serde::de::{self, MapAccess, Visitor};
+use serde::{Deserialize, Deserializer};
+
+use crate::models::state_machine::State;
+
+thread_local! {
+    static ISSUES: RefCell<Vec<String>> = const { RefCell::new(Vec::new()) };
+    static LOSSY: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Note a value that had to be guessed at.
+pub(crate) fn warn(issue: String) {
+    ISSUES.with(|issues| issues.borrow_mut().push(issue));
+}
+
+/// Everything noted since the last call.
+pub(crate) fn take_issues() -> Vec<String> {
+    ISSUES.with(|issues| issues.borrow_mut().drain(..).collect())
+}
+
+/// Whether any value since the last call was replaced outright rather
+/// than read, so writing the record back would lose what was there.
+pub(crate) fn take_lossy() -> bool {
+    LOSSY.with(Cell::take)
+}
+
+/// Integers below this are taken as epoch seconds, at or above as epoch
+/// milliseconds. Seconds reach it in the year 5138; milliseconds passed it
+/// in 1973.
+const MILLIS_THRESHOLD: i64 = 100_000_000_000;
+
+/// Parse a timestamp written as RFC 3339 (what radial writes), RFC 2822,
+/// or epoch seconds or milliseconds.
+pub fn parse_timestamp(text: &str) -> Option<Timestamp> {
+    let text = text.trim();
+    if let Ok(number) = text.parse::<i64>() {
+        return from_epoch(number);
+    }
+    text.parse::<Timestamp>()
+        .ok()
+        .or_else(|| jiff::fmt::rfc2822::parse(text).ok().map(|z| z.timestamp()))
+}
+
+fn from_epoch(number: i64) -> Option<Timestamp> {
+    if number.abs() < MILLIS_THRESHOLD {
+        Timestamp::from_second(number).ok()
+    } else {
+        Timestamp::from_millisecond(number).ok()
+    }
+}
+
+/// `deserialize_with` for a timestamp. One that can't be read at all
+/// becomes the Unix epoch, with a warning, and marks the load lossy (see
+/// [`take_lossy`]).
+pub fn timestamp<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Timestamp, D::Error> {
+    deserializer.deserialize_any(TimestampVisitor)
+}
+
+/// `deserialize_with` for an optional timestamp; pair it with
+/// `#[serde(default)]`.
+pub fn option_timestamp<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<Timestamp>, D::Error> {
+    timestamp(deserializer).map(Some)
+}
+
+/// Decode a state by name, ignoring case, `_` and `-`, so `in_progress`,
+/// `inprogress` and `IN-PROGRESS` all match. Names radial doesn't know
+/// are handed to `unknown`, with a warning.
+pub(crate) fn state<'de, D: Deserializer<'de>, S: State>(
+    deserializer: D,
+    unknown: impl FnOnce(String) -> S,
+) -> Result<S, D::Error> {
+    let raw = String::deserialize(deserializer)?;
+    let key = normalize(&raw);
+    if let Some(state) = S::ALL.iter().find(|s| normalize(s.as_ref()) == key) {
+        return Ok(state.clone());
+    }
+    let state = unknown(raw.clone());
+    if key != normalize(state.as_ref()) {
+        warn(format!("unknown state '{raw}'"));
+    }
+    Ok(state)
+}
+
+fn normalize(name: &str) -> String {
+    name.chars()
+        .filter(|c| !matches!(c, '_' | '-' | ' '))
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+struct TimestampVisitor;
+
+impl TimestampVisitor {
+    fn fallback(raw: &dyn fmt::Display) -> Timestamp {
+        warn(format!(
+            "unreadable timestamp '{raw}', using the Unix epoch; the file won't be rewritten until it's fixed"
+        ));
+        LOSSY.with(|lossy| lossy.set(true));
+        Timestamp::UNIX_EPOCH
+    }
+}
+
+impl<'de> Visitor<'de> for TimestampVisitor {
+    type Value = Timestamp;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a timestamp")
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Timestamp, E> {
+        Ok(parse_timestamp(v).unwrap_or_else(|| Self::fallback(&v)))
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> Result<Timestamp, E> {
+        Ok(from_epoch(v).unwrap_or_else(|| Self::fallback(&v)))
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> Result<Timestamp, E> {
+        i64::try_from(v).map_or_else(|_| Ok(Self::fallback(&v)), |v| self.visit_i64(v))
+    }
+
+    #[allow(clippy::cast_possible_truncation)]
+    fn visit_f64<E: de::Error>(self, v: f64) -> Result<Timestamp, E> {
+        self.visit_i64(v as i64)
+    }
+
+    /// TOML's own datetime type arrives as a one-entry map holding its text.
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Timestamp, A::Error> {
+        let Some((_, text)) = map.next_entry::<String, String>()? else {
+            return Ok(Self::fallback(&"{}"));
+        };
+        self.visit_str(&text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+    use serde::Deserialize;
+
+    #[derive(Deserialize)]
+    struct Row {
+        #[serde(deserialize_with = "timestamp")]
+        at: Timestamp,
+    }
+
+    // Every accepted spelling of the same instant decodes to it.
+    #[rstest]
+    #[case::rfc3339("at = \"2024-05-01T14:03:00Z\"")]
+    #[case::offset("at = \"2024-05-01T16:03:00+02:00\"")]
+    #[case::rfc2822("at = \"Wed, 01 May 2024 14:03:00 +0000\"")]
+    #[case::millis("at = 1714572180000")]
+    #[case::seconds("at = 1714572180")]
+    #[case::toml_datetime("at = 2024-05-01T14:03:00Z")]
+    fn reads_timestamp_formats(#[case] input: &str) {
+        let row: Row = toml::from_str(input).unwrap();
+        assert_eq!(row.at, "2024-05-01T14:03:00Z".parse::<Timestamp>().unwrap());
+        assert!(take_issues().is_empty());
+        assert!(!take_lossy());
+    }
+
+    // Garbage falls back to the epoch and is reported, not fatal.
+    #[rstest]
+    fn unreadable_timestamp_warns() {
+        let row: Row = toml::from_str("at = \"last tuesday\"").unwrap();
+        assert_eq!(row.at, Timestamp::UNIX_EPOCH);
+        let issues = take_issues();
+        assert_eq!(issues.len(), 1);
+        assert!(issues[0].contains("last tuesday"));
+        assert!(take_lossy());
+        assert!(!take_lossy());
+    }
+}
@@ -3,18 +3,48 @@
 #![allow(clippy::must_use_candidate)]
 
 pub mod cli;
+pub mod clock;
 pub mod commands;
+pub mod config;
 pub mod db;
+pub mod error;
+pub mod export;
+pub mod gate;
+pub mod gzip;
 pub mod helpers;
+pub mod history;
 pub mod id;
+pub mod lenient;
+pub mod markdown;
 pub mod models;
 pub mod output;
+pub mod progress;
+pub mod query;
+pub mod registry;
+pub mod sandbox;
+pub mod store;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
 
-use anyhow::{Context, Result, anyhow};
-use std::path::PathBuf;
+pub use error::Error;
+pub use store::RadialStore;
 
-use cli::{Cli, Commands, EditCommands, GoalCommands, TaskCommands};
-use db::Database;
+use anyhow::{Context, Result, anyhow, bail};
+use clap::{CommandFactory, Parser};
+use jiff::{SignedDuration, Timestamp};
+use std::io::{self, BufRead, IsTerminal};
+use std::path::{Path, PathBuf};
+
+use cli::{
+    AllCommands, CheckCommands, ClaimArgs, CleanArgs, Cli, Commands, CompleteArgs, ContextCommands,
+    CriterionCommands, CtxCommands, EditCommands, GoalCommands, MilestoneCommands, PlanCommands,
+    PostmortemArgs, RevisionArgs, StatsCommands, TaskCommands, TaskEdit, UsageArgs,
+};
+use commands::edit::TaskDraft;
+use commands::task::{MODEL_ENV, PROVIDER_ENV, Placement, Revision, Usage};
+use db::{AGENT_ENV, Database};
+use id::ID_SEED_ENV;
+use models::{Postmortem, ReasonCode};
 
 pub const RADIAL_DIR: &str = ".radial";
 pub const REDIRECT_FILE: &str = "redirect";
@@ -37,26 +67,26 @@ pub fn find_radial_dir() -> Option<PathBuf> {
 
 /// Resolves the final radial directory, following any redirect file.
 /// A redirect file contains a path (absolute or relative) to another `.radial/` directory.
+/// `~` and environment variables are expanded; see [`helpers::expand_redirect_path`].
 pub fn resolve_radial_dir() -> Option<PathBuf> {
-    let radial_dir = find_radial_dir()?;
-    let redirect_path = radial_dir.join(REDIRECT_FILE);
+    find_radial_dir().map(|dir| follow_redirect(&dir))
+}
 
-    if redirect_path.is_file() {
-        let target = std::fs::read_to_string(&redirect_path).ok()?;
-        let target = target.trim();
+/// The store `radial_dir` redirects to, or `radial_dir` itself if it has no
+/// redirect file or the target is missing.
+pub fn follow_redirect(radial_dir: &Path) -> PathBuf {
+    let redirect_path = radial_dir.join(REDIRECT_FILE);
+    let target = std::fs::read_to_string(&redirect_path).ok();
 
-        let target_path = if PathBuf::from(target).is_absolute() {
-            PathBuf::from(target)
-        } else {
-            radial_dir.parent()?.join(target)
-        };
+    if let (Some(target), Some(parent)) = (target, radial_dir.parent()) {
+        let target_path = helpers::expand_redirect_path(&target, parent);
 
         if target_path.is_dir() {
-            return Some(target_path);
+            return target_path;
         }
     }
 
-    Some(radial_dir)
+    radial_dir.to_path_buf()
 }
 
 fn get_radial_path() -> Option<PathBuf> {
@@ -67,157 +97,1304 @@ fn ensure_initialized() -> Result<Database> {
     let radial_dir = get_radial_path()
         .ok_or_else(|| anyhow!("Radial not initialized. Run 'radial init' first."))?;
 
-    Database::open(&radial_dir).context("Failed to open database")
+    let mut db = Database::open(&radial_dir).context("Failed to open database")?;
+    for warning in db.load_warnings() {
+        output::warning(warning)?;
+    }
+    db.set_agent(std::env::var(AGENT_ENV).ok());
+    Ok(db)
+}
+
+fn current_branch() -> Option<String> {
+    helpers::current_git_branch(&std::env::current_dir().ok()?)
+}
+
+/// The branch to scope `status` and `ready` to, or `None` to show every goal.
+fn branch_scope(all_branches: bool) -> Option<String> {
+    if all_branches { None } else { current_branch() }
 }
 
 fn run_goal(goal_cmd: GoalCommands, db: &mut Database) -> Result<()> {
     match goal_cmd {
-        GoalCommands::Create { description, json } => {
-            let goal = commands::goal::create(description, db)?;
+        GoalCommands::Create {
+            description,
+            title,
+            parent,
+            branch,
+            project,
+            milestone,
+            json,
+        } => {
+            let branch = match branch {
+                Some(Some(name)) => Some(name),
+                Some(None) => Some(
+                    current_branch()
+                        .context("Not on a git branch; pass one explicitly with --branch <name>")?,
+                ),
+                None => None,
+            };
+            let goal =
+                commands::goal::create(description, title, parent, branch, project, milestone, db)?;
             output::goal_created(&goal, json)
         }
-        GoalCommands::List { json } => {
-            let goals = commands::goal::list(db);
+        GoalCommands::List { project, json } => {
+            let goals = commands::goal::list(project.as_deref(), db);
             output::goal_list(&goals, json)
         }
+        GoalCommands::Archive { goal_id, json } => {
+            let report = commands::goal::archive(&goal_id, db)?;
+            output::goal_archived(&report, json)
+        }
+        GoalCommands::Reopen {
+            goal_id,
+            reset_failed,
+            json,
+        } => {
+            let report = commands::goal::reopen(&goal_id, reset_failed, db)?;
+            output::goal_reopened(&report, json)
+        }
+        GoalCommands::Merge { into, from, json } => {
+            let report = commands::goal::merge(&into, &from, db)?;
+            output::goal_merged(&report, json)
+        }
+        GoalCommands::Restore { path, json } => {
+            let archived = commands::goal::restore(&path, db)?;
+            output::goal_restored(&archived, json)
+        }
+        GoalCommands::Claim {
+            goal_id,
+            agent,
+            ttl,
+            json,
+        } => {
+            let goal = commands::goal::claim(&goal_id, agent, ttl, db)?;
+            output::goal_claim(&goal, json)
+        }
+        GoalCommands::Release {
+            goal_id,
+            agent,
+            force,
+            json,
+        } => {
+            let goal = commands::goal::release(&goal_id, agent.as_deref(), force, db)?;
+            output::goal_claim(&goal, json)
+        }
+        GoalCommands::Criterion(criterion_cmd) => run_criterion(criterion_cmd, db),
+        GoalCommands::Verify { goal_id, dir, json } => {
+            let verified = commands::goal::verify(&goal_id, dir.as_deref(), db)?;
+            output::emit(&verified, json)?;
+            if !verified.passed {
+                return Err(anyhow!("Goal {goal_id} is not done yet"));
+            }
+            Ok(())
+        }
+    }
+}
+
+fn run_criterion(criterion_cmd: CriterionCommands, db: &mut Database) -> Result<()> {
+    match criterion_cmd {
+        CriterionCommands::Add {
+            goal_id,
+            text,
+            command,
+            json,
+        } => {
+            let goal = commands::goal::criterion_add(&goal_id, text, command, db)?;
+            output::criteria(&goal, json)
+        }
+        CriterionCommands::Tick {
+            goal_id,
+            number,
+            undo,
+            json,
+        } => {
+            let goal = commands::goal::criterion_tick(&goal_id, number, undo, db)?;
+            output::criteria(&goal, json)
+        }
+        CriterionCommands::List { goal_id, json } => {
+            let goal = db.require_goal(&goal_id)?;
+            output::criteria(goal, json)
+        }
+    }
+}
+
+fn run_task_create(create: TaskCommands, db: &mut Database) -> Result<()> {
+    let TaskCommands::Create {
+        goal_id,
+        description,
+        title,
+        receives,
+        produces,
+        verify,
+        blocked_by,
+        blocked_by_artifacts,
+        assignee,
+        priority,
+        not_before,
+        estimate,
+        gates,
+        refs,
+        allow_duplicate,
+        json,
+    } = create
+    else {
+        unreachable!("not a create command")
+    };
+    if !allow_duplicate {
+        commands::task::refuse_duplicate(&goal_id, &description, db)?;
+    }
+    let task = commands::task::create(
+        &goal_id,
+        description,
+        title,
+        receives,
+        produces,
+        verify,
+        blocked_by,
+        blocked_by_artifacts,
+        assignee,
+        priority,
+        not_before,
+        estimate,
+        gates,
+        refs,
+        db,
+    )?;
+    output::task_created(&task, json)
+}
+
+fn run_task_exec(exec: TaskCommands, db: &mut Database) -> Result<()> {
+    let TaskCommands::Exec {
+        task_id,
+        on_success,
+        on_failure,
+        sandbox,
+        dir,
+        json,
+        command,
+    } = exec
+    else {
+        unreachable!("not an exec command")
+    };
+    let command = (!command.is_empty()).then(|| {
+        let words: Vec<String> = command.iter().map(|w| helpers::shell_quote(w)).collect();
+        words.join(" ")
+    });
+    let ran = commands::task::exec(
+        &task_id,
+        command,
+        on_success,
+        on_failure,
+        sandbox,
+        dir.as_deref(),
+        db,
+    )?;
+    output::emit(&ran, json)?;
+    if !ran.run.passed {
+        return Err(anyhow!("Command failed for {task_id}"));
+    }
+    Ok(())
+}
+
+fn run_task_note(note: TaskCommands, db: &mut Database) -> Result<()> {
+    match note {
+        TaskCommands::Comment {
+            task_id,
+            text,
+            reply_to,
+        } => {
+            let task = commands::task::comment(&task_id, text, reply_to.as_deref(), db)?;
+            output::task_commented(&task, false)
+        }
+        TaskCommands::Progress {
+            task_id,
+            percent,
+            note,
+            json,
+        } => {
+            let task = commands::task::progress(&task_id, percent, note, db)?;
+            output::task_progress(&task, json)
+        }
+        _ => unreachable!("not a comment or progress command"),
     }
 }
 
 fn run_task(task_cmd: TaskCommands, db: &mut Database) -> Result<()> {
     match task_cmd {
-        TaskCommands::Create {
+        create @ TaskCommands::Create { .. } => run_task_create(create, db),
+        TaskCommands::List {
             goal_id,
-            description,
-            receives,
-            produces,
-            verify,
-            blocked_by,
+            filter,
             json,
+            verbose,
         } => {
-            let task = commands::task::create(
-                &goal_id,
+            let tasks = commands::task::list(goal_id.as_deref(), filter.as_ref(), db)?;
+            let goal = goal_id.map(|id| db.require_goal(&id)).transpose()?;
+            output::task_list(&tasks, goal, verbose, json)
+        }
+        TaskCommands::Reorder {
+            goal_id,
+            task_ids,
+            before,
+            after,
+            json,
+        } => {
+            let placement = match (&before, &after) {
+                (Some(anchor), _) => Placement::Before(anchor),
+                (_, Some(anchor)) => Placement::After(anchor),
+                (None, None) => Placement::Front,
+            };
+            let tasks = commands::task::reorder(&goal_id, &task_ids, placement, db)?;
+            let goal = db.require_goal(&goal_id)?;
+            output::task_list(&tasks, Some(goal), false, json)
+        }
+        TaskCommands::Peek {
+            goal_id,
+            agent,
+            max_minutes,
+            json,
+        } => {
+            let within = max_minutes;
+            let peek = commands::task::peek(&goal_id, agent.as_deref(), within, db)?;
+            output::peek(peek.as_ref(), &goal_id, json)
+        }
+        TaskCommands::ClaimNext(args) => run_claim(args, db),
+        TaskCommands::Start {
+            task_id,
+            agent,
+            json,
+        } => {
+            check_wip(agent.as_deref(), db)?;
+            let quota_agent = agent.clone();
+            let started = commands::task::start(&task_id, agent, db)?;
+            warn_quota(quota_agent.as_deref(), db)?;
+            output::emit(&started, json)
+        }
+        TaskCommands::Complete(args) => run_complete(args, db),
+        TaskCommands::Move {
+            task_id,
+            state,
+            json,
+        } => output::task_moved(&commands::task::move_to(&task_id, &state, db)?, json),
+        TaskCommands::Fail {
+            task_id,
+            reason,
+            reason_code,
+            postmortem,
+            usage,
+            json,
+        } => {
+            let failed = run_fail(&task_id, reason, reason_code, postmortem, usage, db)?;
+            output::emit(&failed, json)
+        }
+        TaskCommands::Verify {
+            task_id,
+            sandbox,
+            dir,
+            json,
+        } => {
+            let verified = commands::task::verify(&task_id, sandbox, dir.as_deref(), db)?;
+            output::emit(&verified, json)?;
+            if !verified.run.passed {
+                return Err(anyhow!("Verify failed for {task_id}"));
+            }
+            Ok(())
+        }
+        exec @ TaskCommands::Exec { .. } => run_task_exec(exec, db),
+        TaskCommands::Retry {
+            task_id,
+            edit,
+            revision,
+            json,
+        } => run_retry(&task_id, edit, revision, json, db),
+        note @ (TaskCommands::Comment { .. } | TaskCommands::Progress { .. }) => {
+            run_task_note(note, db)
+        }
+        TaskCommands::Check(check_cmd) => run_check(check_cmd, db),
+        TaskCommands::Ctx(ctx_cmd) => run_ctx(ctx_cmd, db),
+    }
+}
+
+/// What the attempt cost, with the model and provider falling back to the
+/// environment when not given.
+fn usage_from(args: UsageArgs) -> Usage {
+    Usage {
+        tokens: args.tokens,
+        prompt_tokens: args.prompt_tokens,
+        completion_tokens: args.completion_tokens,
+        elapsed_ms: args.elapsed,
+        model: args.model.or_else(|| std::env::var(MODEL_ENV).ok()),
+        provider: args.provider.or_else(|| std::env::var(PROVIDER_ENV).ok()),
+    }
+}
+
+/// A postmortem if any of its flags were given; the category defaults to
+/// `other` when only logs or a fix were supplied.
+fn postmortem_from(args: PostmortemArgs) -> Option<Postmortem> {
+    if args.category.is_none() && args.logs.is_none() && args.fix.is_none() {
+        return None;
+    }
+    Some(Postmortem::new(
+        args.category.unwrap_or_default(),
+        args.logs,
+        args.fix,
+        Timestamp::now(),
+    ))
+}
+
+fn run_retry(
+    task_id: &str,
+    edit: bool,
+    args: RevisionArgs,
+    json: bool,
+    db: &mut Database,
+) -> Result<()> {
+    let mut revision = Revision {
+        description: args.description,
+        receives: args.receives,
+        produces: args.produces,
+        verify: args.verify,
+    };
+    if edit {
+        revision = edit_revision(task_id, revision, db)?;
+    }
+    let retried = commands::task::retry(task_id, revision, db)?;
+    output::emit(&retried, json)
+}
+
+/// Let the user revise a task in `$EDITOR`, starting from its current
+/// description and contract with any flags already applied.
+fn edit_revision(task_id: &str, flags: Revision, db: &Database) -> Result<Revision> {
+    let current = Revision::from_task(db.require_task(task_id)?);
+    let draft = Revision {
+        description: flags.description.or(current.description),
+        receives: flags.receives.or(current.receives),
+        produces: flags.produces.or(current.produces),
+        verify: flags.verify.or(current.verify),
+    };
+    let text = format!(
+        "# Revise task {task_id} before retrying. Save and close to continue;\n\
+         # delete a line to keep its current value.\n{}",
+        toml::to_string(&draft).context("Failed to serialize task")?
+    );
+    let edited = helpers::edit_in_editor(&text, "toml")?;
+    toml::from_str(&edited).context("Edited task is not valid TOML")
+}
+
+/// Let the user edit every field of a task in `$EDITOR`, starting from its
+/// current values with any flags already applied. Nothing is saved unless the
+/// whole draft is valid.
+fn edit_draft(task_id: &str, flags: TaskDraft, db: &Database) -> Result<TaskDraft> {
+    let draft = TaskDraft::from_task(db.require_task(task_id)?).overlay(flags);
+    let text = format!(
+        "# Edit task {task_id}. Save and close to apply; delete a line to keep\n\
+         # its current value.\n{}",
+        toml::to_string(&draft).context("Failed to serialize task")?
+    );
+    let edited = helpers::edit_in_editor(&text, "toml")?;
+    toml::from_str(&edited).context("Edited task is not valid TOML; nothing was changed")
+}
+
+/// Read a JSON merge patch (RFC 7386) of task fields from `source`, or
+/// stdin for `-`. A `null` clears the field; the description and priority
+/// can only be replaced.
+fn read_patch(source: &Path) -> Result<TaskDraft> {
+    let text = if source == Path::new("-") {
+        io::read_to_string(io::stdin()).context("Failed to read patch from stdin")?
+    } else {
+        std::fs::read_to_string(source)
+            .with_context(|| format!("Failed to read {}", source.display()))?
+    };
+    let serde_json::Value::Object(mut fields) =
+        serde_json::from_str(&text).context("Patch is not valid JSON")?
+    else {
+        bail!("Patch must be a JSON object of task fields");
+    };
+    for (name, value) in &mut fields {
+        if value.is_null() {
+            *value = match name.as_str() {
+                "blocked_by" | "gates" | "refs" => serde_json::json!([]),
+                "description" | "priority" => bail!("Patch cannot remove the {name}"),
+                _ => serde_json::json!(""),
+            };
+        }
+    }
+    serde_json::from_value(serde_json::Value::Object(fields)).context("Invalid patch")
+}
+
+fn run_check(check_cmd: CheckCommands, db: &mut Database) -> Result<()> {
+    match check_cmd {
+        CheckCommands::Add {
+            task_id,
+            text,
+            json,
+        } => {
+            let task = commands::task::check_add(&task_id, text, db)?;
+            output::checklist(&task, json)
+        }
+        CheckCommands::Tick {
+            task_id,
+            item,
+            undo,
+            json,
+        } => {
+            let task = commands::task::check_tick(&task_id, item, undo, db)?;
+            output::checklist(&task, json)
+        }
+        CheckCommands::List { task_id, json } => {
+            let task = db.require_task(&task_id)?;
+            output::checklist(task, json)
+        }
+    }
+}
+
+fn run_ctx(ctx_cmd: CtxCommands, db: &mut Database) -> Result<()> {
+    match ctx_cmd {
+        CtxCommands::Set {
+            task_id,
+            key,
+            value,
+            json,
+        } => {
+            let task = commands::task::ctx_set(&task_id, key, value, db)?;
+            output::ctx(&task, json)
+        }
+        CtxCommands::Get {
+            task_id,
+            key: Some(key),
+            json,
+        } => {
+            let value = commands::task::ctx_get(&task_id, &key, db)?;
+            output::ctx_value(value, json)
+        }
+        CtxCommands::Get {
+            task_id,
+            key: None,
+            json,
+        } => output::ctx(db.require_task(&task_id)?, json),
+        CtxCommands::Unset { task_id, key, json } => {
+            let task = commands::task::ctx_unset(&task_id, &key, db)?;
+            output::ctx(&task, json)
+        }
+    }
+}
+
+fn run_edit(edit_cmd: EditCommands, db: &mut Database) -> Result<()> {
+    match edit_cmd {
+        EditCommands::Goal {
+            goal_id,
+            description,
+            title,
+            project,
+        } => {
+            let goal = commands::edit::goal(&goal_id, description, title, project, db)?;
+            output::goal_edited(&goal)
+        }
+        EditCommands::Task(edit) => {
+            let TaskEdit {
+                task_id,
+                description,
+                title,
+                receives,
+                produces,
+                verify,
+                blocked_by,
+                assignee,
+                priority,
+                not_before,
+                estimate,
+                gates,
+                refs,
+                interactive,
+                patch,
+                json,
+            } = *edit;
+            let mut draft = TaskDraft {
+                title,
                 description,
                 receives,
                 produces,
                 verify,
                 blocked_by,
+                assignee,
+                priority,
+                not_before,
+                estimate,
+                gates,
+                refs,
+            };
+            if interactive {
+                draft = edit_draft(&task_id, draft, db)?;
+            }
+            if let Some(source) = &patch {
+                draft = draft.overlay(read_patch(source)?);
+            }
+            let task = commands::edit::task(
+                &task_id,
+                draft.description,
+                draft.title,
+                draft.receives,
+                draft.produces,
+                draft.verify,
+                draft.blocked_by,
+                draft.assignee,
+                draft.priority,
+                draft.not_before,
+                draft.estimate,
+                draft.gates,
+                draft.refs,
                 db,
             )?;
-            output::task_created(&task, json)
+            output::task_edited(&task, json || patch.is_some())
         }
-        TaskCommands::List {
+    }
+}
+
+pub fn run(cli: Cli) -> Result<()> {
+    let Cli {
+        command,
+        ephemeral,
+        seed,
+        full,
+        utc,
+        no_progress,
+        fields,
+        template,
+        ..
+    } = cli;
+
+    if id::seed().is_some() {
+        output::warning(&format!(
+            "{ID_SEED_ENV} is set, so new IDs are predictable. Use it only for tests."
+        ))?;
+    }
+    if ephemeral {
+        output::configure(output::OutputOptions {
+            full,
+            width: None,
+            utc,
+            no_progress,
+            fields,
+            template,
+            state_colors: Vec::new(),
+        });
+        return run_ephemeral(command, seed.as_deref());
+    }
+    let command = command.ok_or_else(|| anyhow!("No command given. See: rd --help"))?;
+
+    let Some(command) = run_storeless(command)? else {
+        return Ok(());
+    };
+    let mut db = ensure_initialized()?;
+    check_enabled(&std::env::args().skip(1).collect::<Vec<_>>(), &db)?;
+    output::configure(output::OutputOptions {
+        full,
+        width: db.config().output.width,
+        utc,
+        no_progress,
+        fields,
+        template,
+        state_colors: db
+            .config()
+            .states
+            .iter()
+            .map(|(name, state)| (name.clone(), state.color))
+            .collect(),
+    });
+    let before = db
+        .config()
+        .history
+        .enabled
+        .then(|| history::Snapshot::take(&db));
+    let log_command =
+        db.config().history.commands && !matches!(command, Commands::History { replay: None, .. });
+    let result = run_command(command, &mut db);
+    let logged = if log_command {
+        let argv: Vec<String> = std::env::args().skip(1).collect();
+        let error = result.as_ref().err().map(|e| format!("{e:#}"));
+        commands::history::record(&argv, error, &db)
+    } else {
+        Ok(())
+    };
+    result?;
+    logged.context("Failed to log command")?;
+
+    if let Some(before) = before {
+        history::record(&before, &db).context("Failed to record history")?;
+    }
+    if db.config().export.markdown {
+        export::write_markdown(&db).context("Failed to export goal markdown")?;
+    }
+    Ok(())
+}
+
+/// Run `command` if it does not need a store, otherwise hand it back.
+fn run_storeless(command: Commands) -> Result<Option<Commands>> {
+    match command {
+        Commands::Init {
+            bare: Some(path), ..
+        } => commands::init::bare(&path)?,
+        Commands::Init { stealth, .. } => commands::init::run(stealth)?,
+        Commands::Link { path } => commands::link::run(&path)?,
+        Commands::Prep { variant } => {
+            let config = match get_radial_path() {
+                Some(radial_dir) => config::Config::load(&radial_dir)?,
+                None => config::Config::default(),
+            };
+            output::prep(&commands::prep::run(variant, &config))?;
+        }
+        Commands::Capabilities { json } => {
+            let capabilities = commands::capabilities::run(&Cli::command());
+            output::capabilities(&capabilities, json)?;
+        }
+        Commands::Toolspec { format } => {
+            let tools = commands::toolspec::run(&Cli::command());
+            output::toolspec(&tools, format)?;
+        }
+        Commands::All(all) => run_all(all)?,
+        Commands::Validate { path, strict, json } => {
+            let validation = validate_plan_file(&path)?;
+            output::plan_validation(&validation, json)?;
+            if validation.has_errors() || (strict && validation.has_warnings()) {
+                return Err(anyhow!("{} did not pass validation", path.display()));
+            }
+        }
+        command => return Ok(Some(command)),
+    }
+    Ok(None)
+}
+
+fn run_all(command: AllCommands) -> Result<()> {
+    match command {
+        AllCommands::Add { path, name } => {
+            let project = commands::all::add(path.as_deref(), name)?;
+            output::project_registered(&project, true)
+        }
+        AllCommands::Remove { name } => {
+            let project = commands::all::remove(&name)?;
+            output::project_registered(&project, false)
+        }
+        AllCommands::List { json } => {
+            let projects = commands::all::list()?;
+            output::registered_projects(&projects, json)
+        }
+        AllCommands::Status { json } => {
+            let statuses = commands::all::status()?;
+            output::all_status(&statuses, json)
+        }
+        AllCommands::Ready { json } => {
+            let tasks = commands::all::ready()?;
+            output::all_ready(&tasks, json)
+        }
+    }
+}
+
+/// Run against an in-memory store, optionally seeded from a plan file.
+/// Without a command, each line of stdin is run as one; a failing line is
+/// reported and the session carries on.
+fn run_ephemeral(command: Option<Commands>, seed: Option<&Path>) -> Result<()> {
+    let mut db = Database::open_in_memory();
+    db.set_agent(std::env::var(AGENT_ENV).ok());
+    if let Some(path) = seed {
+        let validation = validate_plan_file(path)?;
+        if validation.has_errors() {
+            output::plan_validation(&validation, false)?;
+            return Err(anyhow!("{} has errors; nothing was seeded", path.display()));
+        }
+        commands::plan::create(validation.into_plan().unwrap(), &mut db)?;
+    }
+
+    if let Some(command) = command {
+        return run_in_memory(command, &mut db);
+    }
+
+    let mut failed = 0;
+    for line in io::stdin().lock().lines() {
+        let line = line.context("Failed to read stdin")?;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let json = line.split_whitespace().any(|word| word == "--json");
+        let result = helpers::split_words(line)
+            .and_then(|words| {
+                Ok(Cli::try_parse_from(
+                    std::iter::once("rd".to_string()).chain(words),
+                )?)
+            })
+            .and_then(|cli| {
+                let command = cli
+                    .command
+                    .ok_or_else(|| anyhow!("No command given on this line"))?;
+                run_in_memory(command, &mut db)
+            });
+        if let Err(err) = result {
+            output::error(&err, json);
+            failed += 1;
+        }
+    }
+    if failed > 0 {
+        return Err(anyhow!("{failed} command(s) in the session failed"));
+    }
+    Ok(())
+}
+
+fn run_in_memory(command: Commands, db: &mut Database) -> Result<()> {
+    if matches!(command, Commands::Init { .. } | Commands::Link { .. }) {
+        return Err(anyhow!("init and link are not available with --ephemeral"));
+    }
+    match run_storeless(command)? {
+        Some(command) => run_command(command, db),
+        None => Ok(()),
+    }
+}
+
+/// Warn when the agent that just started work is over its daily token
+/// budget. `task::start` has already refused it if the quota config says to.
+fn warn_quota(agent: Option<&str>, db: &Database) -> Result<()> {
+    if let Some(agent) = agent.or(db.agent())
+        && let Some(warning) = commands::quota::check(agent, db)?
+    {
+        output::warning(&warning)?;
+    }
+    Ok(())
+}
+
+/// Warn, or refuse per `[wip]`, when starting another task would go past a
+/// work-in-progress limit.
+fn check_wip(agent: Option<&str>, db: &Database) -> Result<()> {
+    if let Some(warning) = commands::wip::check(agent.or(db.agent()), db)? {
+        output::warning(&warning)?;
+    }
+    Ok(())
+}
+
+fn run_claim(args: ClaimArgs, db: &mut Database) -> Result<()> {
+    let ClaimArgs {
+        goal_id,
+        agent,
+        max_minutes,
+        json,
+    } = args;
+    check_wip(agent.as_deref(), db)?;
+    let quota_agent = agent.clone();
+    let claimed = commands::task::claim_next(&goal_id, agent, max_minutes, db)?;
+    if claimed.is_some() {
+        warn_quota(quota_agent.as_deref(), db)?;
+    }
+    output::claimed(claimed.as_ref(), &goal_id, json)
+}
+
+fn run_complete(args: CompleteArgs, db: &mut Database) -> Result<()> {
+    let CompleteArgs {
+        task_id,
+        result,
+        artifacts,
+        usage,
+        follow_ups,
+        yes,
+        json,
+    } = args;
+    if let Some(warning) = commands::task::cascade_check(&task_id, db)? {
+        output::warning(&warning)?;
+        if !yes && io::stdin().is_terminal() {
+            return Err(Error::conflict(format!(
+                "{task_id} was not completed; pass --yes to complete it anyway"
+            ))
+            .into());
+        }
+    }
+    let usage = usage_from(usage);
+    let complete_result =
+        commands::task::complete(&task_id, result, artifacts, usage, follow_ups, db)?;
+    output::emit(&complete_result, json)
+}
+
+fn run_clean(args: CleanArgs, db: &mut Database) -> Result<()> {
+    let CleanArgs {
+        all,
+        force,
+        dry_run,
+        goal,
+        unlink,
+        json,
+    } = args;
+    let options = commands::clean::CleanOptions {
+        all,
+        force,
+        dry_run,
+        unlink,
+        goal,
+    };
+    let cleaned = commands::clean::run(&options, db)?;
+    output::emit(&cleaned, json)
+}
+
+/// Refuse `argv`, the arguments after `rd`, if `[commands] disabled` turns
+/// off the command it runs.
+fn check_enabled(argv: &[String], db: &Database) -> Result<()> {
+    let argv = std::iter::once("rd").chain(argv.iter().map(String::as_str));
+    let Ok(matches) = Cli::command().try_get_matches_from(argv) else {
+        return Ok(());
+    };
+    let mut words = Vec::new();
+    let mut matches = &matches;
+    while let Some((name, sub)) = matches.subcommand() {
+        words.push(name);
+        matches = sub;
+    }
+    if db.config().commands.disables(&words) {
+        return Err(Error::conflict(format!(
+            "rd {} is disabled by [commands] in config.toml",
+            words.join(" ")
+        ))
+        .into());
+    }
+    Ok(())
+}
+
+/// Run history entry `number` again, as it was typed.
+fn run_replay(number: usize, db: &mut Database) -> Result<()> {
+    let argv = commands::history::entry(number, db)?.argv;
+    let cli = Cli::try_parse_from(std::iter::once("rd").chain(argv.iter().map(String::as_str)))
+        .with_context(|| format!("Command {number} no longer parses"))?;
+    let command = cli
+        .command
+        .ok_or_else(|| anyhow!("Command {number} has no subcommand"))?;
+    if matches!(command, Commands::History { .. }) {
+        bail!("Command {number} is itself a history command");
+    }
+    check_enabled(&argv, db)?;
+    output::replaying(number, &argv)?;
+    match run_storeless(command)? {
+        Some(command) => run_command(command, db),
+        None => Ok(()),
+    }
+}
+
+/// Propose the radial operations a transcript mentions, running them with
+/// `apply`. Proposals that don't parse as commands are dropped.
+fn run_ingest(path: &Path, apply: bool, json: bool, db: &mut Database) -> Result<()> {
+    let text = if path == Path::new("-") {
+        io::read_to_string(io::stdin()).context("Failed to read transcript from stdin")?
+    } else {
+        std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?
+    };
+    let proposals: Vec<_> = commands::ingest::scan(&text, db)
+        .into_iter()
+        .filter_map(|proposal| {
+            let cli = Cli::try_parse_from(
+                std::iter::once("rd").chain(proposal.args.iter().map(String::as_str)),
+            )
+            .ok()?;
+            Some((proposal, cli.command?))
+        })
+        .collect();
+    let listed: Vec<_> = proposals.iter().map(|(p, _)| p.clone()).collect();
+    output::transcript_proposals(&listed, apply, json)?;
+    if !apply {
+        return Ok(());
+    }
+
+    let mut failed = 0;
+    for (_, command) in proposals {
+        if let Err(err) = run_command(command, db) {
+            output::error(&err, false);
+            failed += 1;
+        }
+    }
+    if failed > 0 {
+        return Err(anyhow!("{failed} proposed operation(s) failed"));
+    }
+    Ok(())
+}
+
+fn run_fail(
+    task_id: &str,
+    reason: Option<String>,
+    code: Option<ReasonCode>,
+    postmortem: PostmortemArgs,
+    usage: UsageArgs,
+    db: &mut Database,
+) -> Result<commands::task::FailResult> {
+    let postmortem = postmortem_from(postmortem);
+    let usage = usage_from(usage);
+    let failed = commands::task::fail(task_id, reason, code, postmortem, usage, db)?;
+    Ok(failed)
+}
+
+/// Fail a task, keeping `reason` as a comment.
+fn run_drop(
+    task_id: &str,
+    reason: Option<String>,
+    code: Option<ReasonCode>,
+    postmortem: PostmortemArgs,
+    usage: UsageArgs,
+    json: bool,
+    db: &mut Database,
+) -> Result<()> {
+    let mut failed = run_fail(task_id, reason.clone(), code, postmortem, usage, db)?;
+    if let Some(reason) = reason {
+        failed.task = commands::task::comment(task_id, reason, None, db)?;
+    }
+    output::emit(&failed, json)
+}
+
+fn run_watch(
+    goal: Option<&str>,
+    interval: SignedDuration,
+    json: bool,
+    db: &Database,
+) -> Result<()> {
+    commands::watch::run(
+        db.base_path(),
+        goal,
+        interval.unsigned_abs(),
+        |event, notify| {
+            output::watch_event(event, json)?;
+            commands::watch::notify(notify, event)
+        },
+    )?;
+    Ok(())
+}
+
+#[cfg(feature = "web")]
+fn run_serve(addr: std::net::SocketAddr, db: &Database) -> Result<()> {
+    commands::serve::run(db.base_path(), addr, output::serving)?;
+    Ok(())
+}
+
+fn run_plan(plan_cmd: PlanCommands, db: &mut Database) -> Result<()> {
+    let (plan, json) = match plan_cmd {
+        PlanCommands::New { interactive, json } => {
+            debug_assert!(interactive);
+            let plan = commands::plan::prompt(&mut io::stdin().lock(), &mut io::stderr())?;
+            (plan, json)
+        }
+        PlanCommands::Apply { path, json } => {
+            let validation = validate_plan_file(&path)?;
+            if validation.has_errors() {
+                output::plan_validation(&validation, false)?;
+                return Err(anyhow!(
+                    "{} has errors; nothing was created",
+                    path.display()
+                ));
+            }
+            (validation.into_plan().unwrap(), json)
+        }
+    };
+    let result = commands::plan::create(plan, db)?;
+    output::plan_created(&result, json)
+}
+
+fn validate_plan_file(path: &Path) -> Result<commands::validate::Validation> {
+    let text = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    Ok(commands::validate::run(&text))
+}
+
+/// Commands that look after the store itself rather than its goals.
+fn run_upkeep(command: &Commands, db: &mut Database) -> Result<()> {
+    match *command {
+        Commands::Doctor { fix, json } => {
+            let report = commands::doctor::run(fix, db)?;
+            output::doctor(&report, json)
+        }
+        Commands::Gc { dry_run, json } => {
+            let report = commands::gc::run(dry_run, db)?;
+            output::gc(&report, json)
+        }
+        _ => unreachable!("not an upkeep command"),
+    }
+}
+
+/// Status and readiness views, which only read the store.
+fn run_query(command: Commands, db: &Database) -> Result<()> {
+    match command {
+        status @ Commands::Status { .. } => run_status(status, db),
+        Commands::Ready {
             goal_id,
+            unassigned,
+            effective_priority,
+            explain,
+            all_branches,
             json,
-            verbose,
         } => {
-            let tasks = commands::task::list(&goal_id, db)?;
-            let goal = db
-                .get_goal(&goal_id)
-                .ok_or_else(|| anyhow!("Goal not found: {goal_id}"))?;
-            output::task_list(&tasks, goal, verbose, json)
+            let branch = branch_scope(all_branches);
+            let tasks = commands::ready::run(
+                &goal_id,
+                unassigned,
+                effective_priority,
+                branch.as_deref(),
+                db,
+            )?;
+            let not_ready = explain
+                .then(|| commands::ready::explain(&goal_id, unassigned, db))
+                .transpose()?;
+            let goal = db.require_goal(&goal_id)?;
+            output::ready_tasks(&tasks, not_ready.as_deref(), goal, json)
         }
-        TaskCommands::Start { task_id } => {
-            let task = commands::task::start(&task_id, db)?;
-            output::task_started(&task)
+        _ => unreachable!("not a query command"),
+    }
+}
+
+fn run_milestone(milestone_cmd: MilestoneCommands, db: &mut Database) -> Result<()> {
+    match milestone_cmd {
+        MilestoneCommands::Add {
+            name,
+            due,
+            description,
+            json,
+        } => {
+            let status = commands::milestone::add(&name, due, description, db)?;
+            output::milestone_added(&status, json)
         }
-        TaskCommands::Complete {
-            task_id,
-            result,
-            artifacts,
-            tokens,
-            elapsed,
+        MilestoneCommands::Attach {
+            name,
+            goal_id,
+            json,
         } => {
-            let complete_result =
-                commands::task::complete(&task_id, result, artifacts, tokens, elapsed, db)?;
-            output::task_completed(&complete_result)
+            let status = commands::milestone::attach(&goal_id, Some(&name), db)?;
+            output::milestone_status(&[status], json)
         }
-        TaskCommands::Fail { task_id } => {
-            let task = commands::task::fail(&task_id, db)?;
-            output::task_failed(&task)
+        MilestoneCommands::Detach { goal_id, json } => {
+            let status = commands::milestone::attach(&goal_id, None, db)?;
+            output::milestone_status(&[status], json)
         }
-        TaskCommands::Retry { task_id } => {
-            let task = commands::task::retry(&task_id, db)?;
-            output::task_retry(&task)
+        MilestoneCommands::Status { name, json } => {
+            let statuses = commands::milestone::status(name.as_deref(), db)?;
+            output::milestone_status(&statuses, json)
         }
-        TaskCommands::Comment { task_id, text } => {
-            let task = commands::task::comment(&task_id, text, db)?;
-            output::task_commented(&task, false)
+    }
+}
+
+fn run_stats(stats_cmd: &StatsCommands, db: &Database) -> Result<()> {
+    match *stats_cmd {
+        StatsCommands::Tokens { by, top, json } => {
+            let stats = commands::stats::tokens(by, top, db);
+            output::token_stats(&stats, json)
+        }
+        StatsCommands::Models { ref goal, json } => {
+            let models = commands::stats::models(goal.as_deref(), db)?;
+            output::model_stats(&models, json)
+        }
+        StatsCommands::Activity { weeks, json } => {
+            let today = commands::quota::utc_date(Timestamp::now());
+            let activity = commands::stats::activity(usize::from(weeks), today, db);
+            output::activity(&activity, json)
         }
     }
 }
 
-pub fn run(cli: Cli) -> Result<()> {
-    match cli.command {
-        Commands::Init { stealth } => commands::init::run(stealth),
-        Commands::Goal(goal_cmd) => {
-            let mut db = ensure_initialized()?;
-            run_goal(goal_cmd, &mut db)
-        }
-        Commands::List { json } => {
-            let db = ensure_initialized()?;
-            let results = commands::list::run(&db)?;
-            output::list(&results, json)
-        }
-        Commands::Task(task_cmd) => {
-            let mut db = ensure_initialized()?;
-            run_task(task_cmd, &mut db)
-        }
-        Commands::Edit(edit_cmd) => {
-            let mut db = ensure_initialized()?;
-            match edit_cmd {
-                EditCommands::Goal {
-                    goal_id,
-                    description,
-                } => {
-                    let goal = commands::edit::goal(&goal_id, description, &mut db)?;
-                    output::goal_edited(&goal)
-                }
-                EditCommands::Task {
-                    task_id,
-                    description,
-                    receives,
-                    produces,
-                    verify,
-                    blocked_by,
-                } => {
-                    let task = commands::edit::task(
-                        &task_id,
-                        description,
-                        receives,
-                        produces,
-                        verify,
-                        blocked_by,
-                        &mut db,
-                    )?;
-                    output::task_edited(&task)
+fn run_inbox(digest: bool, json: bool, db: &Database) -> Result<()> {
+    let items = commands::inbox::run(db);
+    if digest {
+        output::inbox_digest(&commands::inbox::digest(items), json)
+    } else {
+        output::inbox(&items, json)
+    }
+}
+
+/// The table is printed from summary rows; JSON and templates get whole
+/// tasks.
+fn run_list(json: bool, db: &Database) -> Result<()> {
+    if output::structured(json) {
+        let results = commands::list::run(db)?;
+        return output::list(&results, json);
+    }
+    output::list_rows(&commands::list::rows(db))
+}
+
+/// A goal's table is printed from summary rows; JSON, templates and the
+/// other views still get whole tasks.
+fn run_status(status: Commands, db: &Database) -> Result<()> {
+    let Commands::Status {
+        goal,
+        task,
+        agent,
+        stale,
+        tree,
+        all_branches,
+        json,
+    } = status
+    else {
+        unreachable!("not a status command")
+    };
+    if let Some(goal_id) = &goal
+        && task.is_none()
+        && !stale
+        && !tree
+        && !output::structured(json)
+    {
+        let rows = commands::status::goal_rows(goal_id, agent.as_deref(), db)?;
+        return output::status_goal_rows(&rows);
+    }
+    let branch = branch_scope(all_branches);
+    let result = commands::status::run(goal, task, agent, stale, tree, branch.as_deref(), db)?;
+    output::status(&result, json)
+}
+
+fn run_open(open: Commands, db: &Database) -> Result<()> {
+    let Commands::Open {
+        id,
+        which,
+        list,
+        json,
+    } = open
+    else {
+        unreachable!("not an open command")
+    };
+    let targets = commands::open::targets(&id, db)?;
+    if list {
+        return output::open_targets(&targets, json);
+    }
+    let target = commands::open::pick(&id, targets, which.as_deref())?;
+    commands::open::launch(&target)?;
+    output::opened(&target)
+}
+
+fn run_bundle(command: Commands, db: &mut Database) -> Result<()> {
+    match command {
+        Commands::Export { goal, output, json } => {
+            let bundle = commands::bundle::export(&goal, db)?;
+            match output {
+                Some(path) => {
+                    output::bundle_written(&commands::bundle::write(&bundle, &path)?, json)
                 }
+                None => output::bundle(&bundle),
             }
         }
-        Commands::Status { goal, task, json } => {
-            let db = ensure_initialized()?;
-            let result = commands::status::run(goal, task, &db)?;
-            output::status(&result, json)
+        Commands::Import { path, json } => {
+            let bundle = commands::bundle::read(&path)?;
+            output::bundle_imported(&commands::bundle::import(bundle, db)?, json)
         }
-        Commands::Show { id, json } => {
-            let db = ensure_initialized()?;
-            let result = commands::show::run(&id, &db)?;
-            output::show(&result, json)
+        _ => unreachable!("not an export or import command"),
+    }
+}
+
+/// The digest goes where `[digest]` says, unless it is printed: with
+/// `--print` or `--json`, or when nowhere is configured.
+fn run_digest(digest: &Commands, db: &Database) -> Result<()> {
+    let &Commands::Digest {
+        since,
+        format,
+        print,
+        json,
+    } = digest
+    else {
+        unreachable!("not a digest command")
+    };
+    let config = &db.config().digest;
+    let now = jiff::Timestamp::now();
+    let digest = commands::digest::build(since.unwrap_or(config.since), now, db);
+    let text = commands::digest::render(&digest, format.unwrap_or(config.format));
+    if print || json || (config.path.is_none() && config.command.is_none()) {
+        return output::digest(&digest, &text, json);
+    }
+    let delivery = commands::digest::deliver(&text, config, now, db)?;
+    output::digest_delivered(&delivery)
+}
+
+fn run_command(command: Commands, db: &mut Database) -> Result<()> {
+    match command {
+        Commands::Init { .. }
+        | Commands::Link { .. }
+        | Commands::Prep { .. }
+        | Commands::Capabilities { .. }
+        | Commands::Toolspec { .. }
+        | Commands::All(_)
+        | Commands::Validate { .. } => {
+            unreachable!("handled before opening the database")
+        }
+        Commands::Goal(goal_cmd) => run_goal(goal_cmd, db),
+        Commands::List { json } => run_list(json, db),
+        Commands::Task(task_cmd) => run_task(task_cmd, db),
+        Commands::Context(ContextCommands::Task { task_id, json }) => {
+            let context = commands::context::task(&task_id, db)?;
+            output::task_context(&context, json)
+        }
+        Commands::Grab(args) => run_claim(args, db),
+        Commands::Done(args) => run_complete(args, db),
+        Commands::Drop {
+            task_id,
+            reason,
+            reason_code,
+            postmortem,
+            usage,
+            json,
+        } => run_drop(&task_id, reason, reason_code, postmortem, usage, json, db),
+        Commands::Plan(plan_cmd) => run_plan(plan_cmd, db),
+        Commands::Edit(edit_cmd) => run_edit(edit_cmd, db),
+        command @ (Commands::Status { .. } | Commands::Ready { .. }) => run_query(command, db),
+        Commands::Show {
+            id,
+            render,
+            links,
+            json,
+        } => {
+            let result = commands::show::run(&id, links, db)?;
+            output::show(&result, render, json)
+        }
+        command @ Commands::Open { .. } => run_open(command, db),
+        command @ Commands::Digest { .. } => run_digest(&command, db),
+        Commands::Clean(args) => run_clean(args, db),
+        Commands::Summary {
+            for_llm,
+            max_tokens,
+        } => {
+            debug_assert!(for_llm);
+            let digest = commands::summary::for_llm(max_tokens, db);
+            output::summary(&digest)
         }
-        Commands::Clean { all, force } => {
-            let mut db = ensure_initialized()?;
-            commands::clean::run(all, force, &mut db)
+        Commands::Postmortems { goal, limit, json } => {
+            let groups = commands::postmortems::run(goal.as_deref(), limit, db)?;
+            output::postmortems(&groups, json)
         }
-        Commands::Ready { goal_id, json } => {
-            let db = ensure_initialized()?;
-            let tasks = commands::ready::run(&goal_id, &db)?;
-            let goal = db
-                .get_goal(&goal_id)
-                .ok_or_else(|| anyhow!("Goal not found: {goal_id}"))?;
-            output::ready_tasks(&tasks, goal, json)
+        Commands::Stats(stats_cmd) => run_stats(&stats_cmd, db),
+        Commands::Projects { json } => {
+            let projects = commands::projects::run(db);
+            output::projects(&projects, json)
         }
-        Commands::Prep => {
-            let text = commands::prep::run();
-            output::prep(text)
+        Commands::Inbox { digest, json } => run_inbox(digest, json, db),
+        Commands::Milestone(milestone_cmd) => run_milestone(milestone_cmd, db),
+        Commands::Quota { json } => {
+            let usage = commands::quota::run(db);
+            output::quota(&usage, json)
         }
+        Commands::SuggestDeps {
+            goal_id,
+            apply,
+            json,
+        } => {
+            let suggestions = commands::suggest::run(&goal_id, apply, db)?;
+            output::suggestions(&suggestions, apply, json)
+        }
+        Commands::IngestTranscript { path, apply, json } => run_ingest(&path, apply, json, db),
+        Commands::History {
+            replay: Some(number),
+            ..
+        } => run_replay(number, db),
+        Commands::History { limit, json, .. } => {
+            let invocations = commands::history::list(Some(limit), db)?;
+            output::command_history(&invocations, json)
+        }
+        command @ (Commands::Doctor { .. } | Commands::Gc { .. }) => run_upkeep(&command, db),
+        command @ (Commands::Export { .. } | Commands::Import { .. }) => run_bundle(command, db),
+        Commands::Sync {
+            path,
+            dry_run,
+            json,
+        } => {
+            let report = commands::sync::run(&path, dry_run, db)?;
+            output::sync(&report, json)
+        }
+        Commands::Watch {
+            goal,
+            interval,
+            json,
+        } => run_watch(goal.as_deref(), interval, json, db),
+        #[cfg(feature = "web")]
+        Commands::Serve { addr, .. } => run_serve(addr, db),
     }
 }
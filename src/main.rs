@@ -1,8 +1,17 @@
-use anyhow::Result;
+use std::process::ExitCode;
+
 use clap::Parser;
 use radial::cli::Cli;
+use radial::output;
 
-fn main() -> Result<()> {
+fn main() -> ExitCode {
+    let json = std::env::args().any(|arg| arg == "--json");
     let cli = Cli::parse();
-    radial::run(cli)
+    match radial::run(cli) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            output::error(&err, json);
+            ExitCode::FAILURE
+        }
+    }
 }
@@ -0,0 +1,107 @@
+//! Just enough markdown for terminal output: headings, bold, italics, code
+//! spans, fenced code blocks and lists. Agents write their summaries and
+//! comments in markdown; this shows them without the raw syntax. Anything
+//! it doesn't recognize, including an unclosed marker, is left as written.
+
+use console::style;
+
+/// Render `text` line by line for the terminal.
+pub fn render(text: &str) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut in_fence = false;
+
+    for line in text.lines() {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") {
+            in_fence = !in_fence;
+            continue;
+        }
+        if in_fence {
+            lines.push(style(line).cyan().to_string());
+            continue;
+        }
+
+        let indent = &line[..line.len() - trimmed.len()];
+        let rendered = if let Some(heading) = heading(trimmed) {
+            style(inline(heading)).bold().to_string()
+        } else if let Some(item) = ["- ", "* ", "+ "]
+            .iter()
+            .find_map(|bullet| trimmed.strip_prefix(bullet))
+        {
+            format!("• {}", inline(item))
+        } else {
+            inline(trimmed)
+        };
+        lines.push(format!("{indent}{rendered}"));
+    }
+    lines
+}
+
+/// The text of an ATX heading such as `## Notes`.
+fn heading(line: &str) -> Option<&str> {
+    let text = line.trim_start_matches('#');
+    let level = line.len() - text.len();
+    ((1..=6).contains(&level) && text.starts_with(' ')).then(|| text.trim())
+}
+
+/// Style code spans, `**bold**` and `*italic*` within one line.
+fn inline(text: &str) -> String {
+    let mut out = String::new();
+    let mut rest = text;
+
+    while let Some(start) = rest.find(['`', '*']) {
+        out.push_str(&rest[..start]);
+        let after = &rest[start..];
+        let (marker, styled): (&str, fn(&str) -> String) = if after.starts_with('`') {
+            ("`", |s: &str| style(s).cyan().to_string())
+        } else if after.starts_with("**") {
+            ("**", |s: &str| style(s).bold().to_string())
+        } else {
+            ("*", |s: &str| style(s).italic().to_string())
+        };
+
+        let body = &after[marker.len()..];
+        match body.find(marker) {
+            Some(end) if end > 0 && !body.starts_with(' ') => {
+                out.push_str(&styled(&body[..end]));
+                rest = &body[end + marker.len()..];
+            }
+            _ => {
+                out.push_str(marker);
+                rest = body;
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    // With colors off (as under `cargo test`), only the markup is removed,
+    // which shows exactly what each construct turns into.
+    #[rstest]
+    #[case::bold("Made **all** tests pass", "Made all tests pass")]
+    #[case::code("Run `cargo test`", "Run cargo test")]
+    #[case::italic("*mostly* done", "mostly done")]
+    #[case::heading("## Summary", "Summary")]
+    #[case::bullet("  - item one", "  • item one")]
+    #[case::numbered("1. first", "1. first")]
+    #[case::unclosed("2 * 3 and **oops", "2 * 3 and **oops")]
+    #[case::not_heading("#hashtag", "#hashtag")]
+    fn renders_line(#[case] input: &str, #[case] expected: &str) {
+        console::set_colors_enabled(false);
+        assert_eq!(render(input), vec![expected.to_string()]);
+    }
+
+    // Fences are dropped and their lines kept verbatim, markers included.
+    #[rstest]
+    fn keeps_code_blocks_verbatim() {
+        console::set_colors_enabled(false);
+        let text = "Before\n```rust\nlet x = a * b * c;\n```\nAfter";
+        assert_eq!(render(text), vec!["Before", "let x = a * b * c;", "After"]);
+    }
+}
@@ -0,0 +1,168 @@
+use jiff::Timestamp;
+use serde::{Deserialize, Serialize};
+
+use super::{FailureCategory, TaskState};
+
+/// One run of a task, from when it was started to when it completed or
+/// failed, with what it cost. Kept for every run, so a retried task keeps
+/// the history of its earlier failures and their spend.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Attempt {
+    /// 1 for the first run, 2 for the first retry, and so on.
+    number: i64,
+    /// Who made the attempt: the task's assignee, or else the acting agent.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    agent: Option<String>,
+    /// The model that did the work, as priced under `[pricing.models]`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    model: Option<String>,
+    /// Who serves the model, such as `openai`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    provider: Option<String>,
+    /// `completed` or `failed`.
+    outcome: TaskState,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    started_at: Option<Timestamp>,
+    ended_at: Timestamp,
+    tokens: i64,
+    #[serde(default)]
+    prompt_tokens: i64,
+    #[serde(default)]
+    completion_tokens: i64,
+    elapsed_ms: i64,
+    /// The result summary of a completed attempt.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    result: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    category: Option<FailureCategory>,
+    /// Why a failed attempt failed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    reason: Option<String>,
+}
+
+impl Attempt {
+    pub fn new(
+        number: i64,
+        agent: Option<String>,
+        outcome: TaskState,
+        started_at: Option<Timestamp>,
+        ended_at: Timestamp,
+    ) -> Self {
+        Self {
+            number,
+            agent,
+            model: None,
+            provider: None,
+            outcome,
+            started_at,
+            ended_at,
+            tokens: 0,
+            prompt_tokens: 0,
+            completion_tokens: 0,
+            elapsed_ms: 0,
+            result: None,
+            category: None,
+            reason: None,
+        }
+    }
+
+    #[must_use]
+    pub fn with_spend(
+        mut self,
+        tokens: i64,
+        prompt_tokens: i64,
+        completion_tokens: i64,
+        elapsed_ms: i64,
+    ) -> Self {
+        self.tokens = tokens;
+        self.prompt_tokens = prompt_tokens;
+        self.completion_tokens = completion_tokens;
+        self.elapsed_ms = elapsed_ms;
+        self
+    }
+
+    #[must_use]
+    pub fn with_model(mut self, model: Option<String>) -> Self {
+        self.model = model;
+        self
+    }
+
+    #[must_use]
+    pub fn with_provider(mut self, provider: Option<String>) -> Self {
+        self.provider = provider;
+        self
+    }
+
+    #[must_use]
+    pub fn with_result(mut self, result: String) -> Self {
+        self.result = Some(result);
+        self
+    }
+
+    #[must_use]
+    pub fn with_failure(
+        mut self,
+        category: Option<FailureCategory>,
+        reason: Option<String>,
+    ) -> Self {
+        self.category = category;
+        self.reason = reason;
+        self
+    }
+
+    pub fn number(&self) -> i64 {
+        self.number
+    }
+
+    pub fn agent(&self) -> Option<&str> {
+        self.agent.as_deref()
+    }
+
+    pub fn model(&self) -> Option<&str> {
+        self.model.as_deref()
+    }
+
+    pub fn provider(&self) -> Option<&str> {
+        self.provider.as_deref()
+    }
+
+    pub fn outcome(&self) -> TaskState {
+        self.outcome.clone()
+    }
+
+    pub fn started_at(&self) -> Option<Timestamp> {
+        self.started_at
+    }
+
+    pub fn ended_at(&self) -> Timestamp {
+        self.ended_at
+    }
+
+    pub fn tokens(&self) -> i64 {
+        self.tokens
+    }
+
+    pub fn prompt_tokens(&self) -> i64 {
+        self.prompt_tokens
+    }
+
+    pub fn completion_tokens(&self) -> i64 {
+        self.completion_tokens
+    }
+
+    pub fn elapsed_ms(&self) -> i64 {
+        self.elapsed_ms
+    }
+
+    pub fn result(&self) -> Option<&str> {
+        self.result.as_deref()
+    }
+
+    pub fn category(&self) -> Option<FailureCategory> {
+        self.category
+    }
+
+    pub fn reason(&self) -> Option<&str> {
+        self.reason.as_deref()
+    }
+}
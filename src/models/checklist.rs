@@ -0,0 +1,45 @@
+use jiff::Timestamp;
+use serde::{Deserialize, Serialize};
+
+/// A small step inside a task, tracked without a contract of its own.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CheckItem {
+    text: String,
+    #[serde(default)]
+    done: bool,
+    created_at: Timestamp,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    done_at: Option<Timestamp>,
+}
+
+impl CheckItem {
+    pub fn new(text: String, created_at: Timestamp) -> Self {
+        Self {
+            text,
+            done: false,
+            created_at,
+            done_at: None,
+        }
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn done(&self) -> bool {
+        self.done
+    }
+
+    pub fn created_at(&self) -> Timestamp {
+        self.created_at
+    }
+
+    pub fn done_at(&self) -> Option<Timestamp> {
+        self.done_at
+    }
+
+    pub fn set_done(&mut self, done: bool) {
+        self.done = done;
+        self.done_at = done.then(Timestamp::now);
+    }
+}
@@ -1,11 +1,23 @@
 use jiff::Timestamp;
 use serde::{Deserialize, Serialize};
 
+use crate::lenient;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Comment {
     id: String,
     text: String,
+    #[serde(deserialize_with = "lenient::timestamp")]
     created_at: Timestamp,
+    /// Percent complete, set on progress updates.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    progress: Option<u8>,
+    /// The comment this one replies to.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    reply_to: Option<String>,
+    /// The agent that wrote it, when known.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    author: Option<String>,
 }
 
 impl Comment {
@@ -14,9 +26,30 @@ impl Comment {
             id,
             text,
             created_at,
+            progress: None,
+            reply_to: None,
+            author: None,
         }
     }
 
+    #[must_use]
+    pub fn with_progress(mut self, percent: u8) -> Self {
+        self.progress = Some(percent);
+        self
+    }
+
+    #[must_use]
+    pub fn with_reply_to(mut self, comment_id: Option<String>) -> Self {
+        self.reply_to = comment_id;
+        self
+    }
+
+    #[must_use]
+    pub fn with_author(mut self, author: Option<String>) -> Self {
+        self.author = author;
+        self
+    }
+
     pub fn id(&self) -> &str {
         &self.id
     }
@@ -28,4 +61,16 @@ impl Comment {
     pub fn created_at(&self) -> Timestamp {
         self.created_at
     }
+
+    pub fn progress(&self) -> Option<u8> {
+        self.progress
+    }
+
+    pub fn reply_to(&self) -> Option<&str> {
+        self.reply_to.as_deref()
+    }
+
+    pub fn author(&self) -> Option<&str> {
+        self.author.as_deref()
+    }
 }
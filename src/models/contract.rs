@@ -1,6 +1,7 @@
+use jiff::Timestamp;
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Contract {
     receives: String,
     produces: String,
@@ -28,3 +29,35 @@ impl Contract {
         &self.verify
     }
 }
+
+/// A description and contract that were replaced, kept so a retried task
+/// still shows what the failed attempt was working from.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContractRevision {
+    description: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    contract: Option<Contract>,
+    replaced_at: Timestamp,
+}
+
+impl ContractRevision {
+    pub fn new(description: String, contract: Option<Contract>, replaced_at: Timestamp) -> Self {
+        Self {
+            description,
+            contract,
+            replaced_at,
+        }
+    }
+
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+
+    pub fn contract(&self) -> Option<&Contract> {
+        self.contract.as_ref()
+    }
+
+    pub fn replaced_at(&self) -> Timestamp {
+        self.replaced_at
+    }
+}
@@ -0,0 +1,50 @@
+use jiff::Timestamp;
+use serde::{Deserialize, Serialize};
+
+/// Something that must hold for a goal to be done, beyond its tasks being
+/// complete. One with a command is met when the command succeeds under
+/// `rd goal verify`; one without is ticked by hand.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Criterion {
+    text: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    command: Option<String>,
+    #[serde(default)]
+    met: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    met_at: Option<Timestamp>,
+}
+
+impl Criterion {
+    pub fn new(text: String, command: Option<String>) -> Self {
+        Self {
+            text,
+            command,
+            met: false,
+            met_at: None,
+        }
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn command(&self) -> Option<&str> {
+        self.command.as_deref()
+    }
+
+    pub fn met(&self) -> bool {
+        self.met
+    }
+
+    pub fn met_at(&self) -> Option<Timestamp> {
+        self.met_at
+    }
+
+    pub fn set_met(&mut self, met: bool) {
+        if met != self.met {
+            self.met_at = met.then(Timestamp::now);
+        }
+        self.met = met;
+    }
+}
@@ -1,16 +1,20 @@
+use std::collections::BTreeMap;
 use std::io::Write;
 use std::path::{Path, PathBuf};
 
 use anyhow::{Context, Result};
 use console::style;
 use jiff::Timestamp;
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
 use strum::{AsRefStr, EnumString};
 
+use super::{Attempt, Criterion};
+use crate::clock;
 use crate::db::atomic_write;
+use crate::lenient;
 use crate::output::Render;
 
-#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, AsRefStr, EnumString)]
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, AsRefStr, EnumString)]
 #[serde(rename_all = "lowercase")]
 #[strum(serialize_all = "snake_case")]
 pub enum GoalState {
@@ -18,6 +22,15 @@ pub enum GoalState {
     InProgress,
     Completed,
     Failed,
+    /// Read from a file with a state this version doesn't know. Nothing
+    /// moves into or out of it; fix the file to recover the record.
+    Unknown,
+}
+
+impl<'de> Deserialize<'de> for GoalState {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        lenient::state(deserializer, |_| Self::Unknown)
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -29,6 +42,33 @@ pub struct Metrics {
     task_count: i64,
     tasks_completed: i64,
     tasks_failed: i64,
+    /// Spend per agent, keyed by name; `unassigned` for work nobody owned.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    by_agent: BTreeMap<String, Spend>,
+    /// Spend per attempt number: first runs under `1`, first retries under
+    /// `2`, and so on.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    by_attempt: BTreeMap<i64, Spend>,
+}
+
+/// Tokens and time spent by a group of attempts.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Spend {
+    pub attempts: i64,
+    pub tokens: i64,
+    pub prompt_tokens: i64,
+    pub completion_tokens: i64,
+    pub elapsed_ms: i64,
+}
+
+impl Spend {
+    pub fn add(&mut self, attempt: &Attempt) {
+        self.attempts += 1;
+        self.tokens += attempt.tokens();
+        self.prompt_tokens += attempt.prompt_tokens();
+        self.completion_tokens += attempt.completion_tokens();
+        self.elapsed_ms += attempt.elapsed_ms();
+    }
 }
 
 impl Metrics {
@@ -49,9 +89,22 @@ impl Metrics {
             task_count,
             tasks_completed,
             tasks_failed,
+            by_agent: BTreeMap::new(),
+            by_attempt: BTreeMap::new(),
         }
     }
 
+    #[must_use]
+    pub fn with_breakdown(
+        mut self,
+        by_agent: BTreeMap<String, Spend>,
+        by_attempt: BTreeMap<i64, Spend>,
+    ) -> Self {
+        self.by_agent = by_agent;
+        self.by_attempt = by_attempt;
+        self
+    }
+
     pub fn total_tokens(&self) -> i64 {
         self.total_tokens
     }
@@ -79,6 +132,14 @@ impl Metrics {
     pub fn tasks_failed(&self) -> i64 {
         self.tasks_failed
     }
+
+    pub fn by_agent(&self) -> &BTreeMap<String, Spend> {
+        &self.by_agent
+    }
+
+    pub fn by_attempt(&self) -> &BTreeMap<i64, Spend> {
+        &self.by_attempt
+    }
 }
 
 impl Render for Metrics {
@@ -89,23 +150,103 @@ impl Render for Metrics {
             self.task_count, self.tasks_completed, self.tasks_failed
         )?;
         writeln!(w, "  Tokens: {}", self.total_tokens)?;
+        if self.prompt_tokens + self.completion_tokens > 0 {
+            writeln!(
+                w,
+                "    {} prompt, {} completion",
+                self.prompt_tokens, self.completion_tokens
+            )?;
+        }
         writeln!(w, "  Elapsed: {}ms", self.elapsed_ms)?;
+        for (agent, spend) in &self.by_agent {
+            writeln!(
+                w,
+                "  {agent}: {} tokens over {} attempt(s), {}ms",
+                spend.tokens, spend.attempts, spend.elapsed_ms
+            )?;
+        }
+        for (number, spend) in self.by_attempt.iter().filter(|(n, _)| **n > 1) {
+            writeln!(
+                w,
+                "  Attempt {number}: {} tokens over {} task(s)",
+                spend.tokens, spend.attempts
+            )?;
+        }
         Ok(())
     }
 }
 
+/// One agent's exclusive hold on a goal's tasks, until it is released or
+/// `expires_at` passes. See `rd goal claim`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Claim {
+    agent: String,
+    #[serde(deserialize_with = "lenient::timestamp")]
+    expires_at: Timestamp,
+}
+
+impl Claim {
+    pub fn new(agent: String, expires_at: Timestamp) -> Self {
+        Self { agent, expires_at }
+    }
+
+    pub fn agent(&self) -> &str {
+        &self.agent
+    }
+
+    pub fn expires_at(&self) -> Timestamp {
+        self.expires_at
+    }
+
+    pub fn is_active(&self, now: Timestamp) -> bool {
+        now < self.expires_at
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Goal {
     id: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     parent_id: Option<String>,
+    /// Short name for list views; `description` can run to several lines.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    title: Option<String>,
     description: String,
     state: GoalState,
+    #[serde(deserialize_with = "lenient::timestamp")]
     created_at: Timestamp,
+    #[serde(deserialize_with = "lenient::timestamp")]
     updated_at: Timestamp,
-    #[serde(skip_serializing_if = "Option::is_none")]
+    /// Logical clock values (see [`crate::clock`]) for when the record was
+    /// created and last changed. Unlike the timestamps, they never run
+    /// backwards when records come from machines with different clocks.
+    #[serde(default)]
+    created_seq: u64,
+    #[serde(default)]
+    seq: u64,
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "lenient::option_timestamp"
+    )]
     completed_at: Option<Timestamp>,
     metrics: Metrics,
+    /// Git branch the goal belongs to. Scoped goals are hidden from
+    /// `status` and `ready` on other branches.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    branch: Option<String>,
+    /// Label grouping related goals, e.g. an epic, without nesting them.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    project: Option<String>,
+    /// Name of the milestone the goal is planned for; see `rd milestone`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    milestone: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    claim: Option<Claim>,
+    /// What must hold besides the tasks being done. A goal with criteria is
+    /// only completed by `rd goal verify`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    criteria: Vec<Criterion>,
 }
 
 impl Goal {
@@ -120,18 +261,59 @@ impl Goal {
         completed_at: Option<Timestamp>,
         metrics: Metrics,
     ) -> Self {
+        let seq = clock::tick();
         Self {
             id,
             parent_id,
+            title: None,
             description,
             state,
             created_at,
             updated_at,
+            created_seq: seq,
+            seq,
             completed_at,
             metrics,
+            branch: None,
+            project: None,
+            milestone: None,
+            claim: None,
+            criteria: Vec::new(),
         }
     }
 
+    #[must_use]
+    pub fn with_title(mut self, title: Option<String>) -> Self {
+        self.title = title;
+        self
+    }
+
+    /// The same goal under another ID, for copies imported from a bundle.
+    #[must_use]
+    pub(crate) fn with_id(mut self, id: String, parent_id: Option<String>) -> Self {
+        self.id = id;
+        self.parent_id = parent_id;
+        self
+    }
+
+    #[must_use]
+    pub fn with_branch(mut self, branch: Option<String>) -> Self {
+        self.branch = branch;
+        self
+    }
+
+    #[must_use]
+    pub fn with_project(mut self, project: Option<String>) -> Self {
+        self.project = project;
+        self
+    }
+
+    #[must_use]
+    pub fn with_milestone(mut self, milestone: Option<String>) -> Self {
+        self.milestone = milestone;
+        self
+    }
+
     pub fn id(&self) -> &str {
         &self.id
     }
@@ -140,10 +322,19 @@ impl Goal {
         self.parent_id.as_deref()
     }
 
+    pub fn title(&self) -> Option<&str> {
+        self.title.as_deref()
+    }
+
     pub fn description(&self) -> &str {
         &self.description
     }
 
+    /// The title, or the description when there is none.
+    pub fn headline(&self) -> &str {
+        self.title.as_deref().unwrap_or(&self.description)
+    }
+
     pub fn state(&self) -> GoalState {
         self.state
     }
@@ -156,6 +347,14 @@ impl Goal {
         self.updated_at
     }
 
+    pub fn created_seq(&self) -> u64 {
+        self.created_seq
+    }
+
+    pub fn seq(&self) -> u64 {
+        self.seq
+    }
+
     pub fn completed_at(&self) -> Option<Timestamp> {
         self.completed_at
     }
@@ -164,30 +363,108 @@ impl Goal {
         &self.metrics
     }
 
+    pub fn branch(&self) -> Option<&str> {
+        self.branch.as_deref()
+    }
+
+    pub fn project(&self) -> Option<&str> {
+        self.project.as_deref()
+    }
+
+    pub fn milestone(&self) -> Option<&str> {
+        self.milestone.as_deref()
+    }
+
+    /// The claim on the goal, if one is in force at `now`.
+    pub fn claim(&self, now: Timestamp) -> Option<&Claim> {
+        self.claim.as_ref().filter(|c| c.is_active(now))
+    }
+
+    pub fn criteria(&self) -> &[Criterion] {
+        &self.criteria
+    }
+
+    /// Whether every acceptance criterion has been met.
+    pub fn criteria_met(&self) -> bool {
+        self.criteria.iter().all(Criterion::met)
+    }
+
+    /// Whether the goal should be shown when working on `branch`. Unscoped
+    /// goals are always visible, as is everything when `branch` is unknown.
+    pub fn visible_on(&self, branch: Option<&str>) -> bool {
+        match (self.branch(), branch) {
+            (Some(scope), Some(current)) => scope == current,
+            _ => true,
+        }
+    }
+
     pub fn set_description(&mut self, description: String) {
         self.description = description;
-        self.updated_at = Timestamp::now();
+        self.touch();
+    }
+
+    pub fn set_title(&mut self, title: Option<String>) {
+        self.title = title;
+        self.touch();
+    }
+
+    pub fn set_project(&mut self, project: Option<String>) {
+        self.project = project;
+        self.touch();
+    }
+
+    pub fn set_parent_id(&mut self, parent_id: Option<String>) {
+        self.parent_id = parent_id;
+        self.touch();
+    }
+
+    pub fn set_milestone(&mut self, milestone: Option<String>) {
+        self.milestone = milestone;
+        self.touch();
     }
 
-    pub fn touch(&mut self) {
-        self.updated_at = Timestamp::now();
+    pub fn set_claim(&mut self, claim: Option<Claim>) {
+        self.claim = claim;
+        self.touch();
+    }
+
+    pub fn add_criterion(&mut self, criterion: Criterion) {
+        self.criteria.push(criterion);
+        self.touch();
+    }
+
+    /// Criterion `number` (1-based), if there is one.
+    pub fn criterion_mut(&mut self, number: usize) -> Option<&mut Criterion> {
+        number.checked_sub(1).and_then(|i| self.criteria.get_mut(i))
+    }
+
+    /// Record a change, returning the new `updated_at`.
+    pub fn touch(&mut self) -> Timestamp {
+        self.updated_at = clock::after(self.updated_at);
+        self.seq = clock::tick();
+        self.updated_at
     }
 
     pub fn mark_in_progress(&mut self) {
         self.state = GoalState::InProgress;
-        self.updated_at = Timestamp::now();
+        self.touch();
+    }
+
+    /// Back in progress after completing or failing, no longer completed.
+    pub fn reopen(&mut self) {
+        self.state = GoalState::InProgress;
+        self.completed_at = None;
+        self.touch();
     }
 
     pub fn mark_completed(&mut self) {
         self.state = GoalState::Completed;
-        let now = Timestamp::now();
-        self.updated_at = now;
-        self.completed_at = Some(now);
+        self.completed_at = Some(self.touch());
     }
 
     pub fn mark_failed(&mut self) {
         self.state = GoalState::Failed;
-        self.updated_at = Timestamp::now();
+        self.touch();
     }
 
     pub fn file_path(&self, base: &Path) -> PathBuf {
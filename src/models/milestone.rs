@@ -0,0 +1,39 @@
+use jiff::Timestamp;
+use serde::{Deserialize, Serialize};
+
+use crate::lenient;
+
+/// A named target date that goals can be attached to, for planning above
+/// the level of single goals. Milestones live in `.radial/milestones.toml`,
+/// keyed by name.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Milestone {
+    #[serde(deserialize_with = "lenient::timestamp")]
+    due: Timestamp,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    description: Option<String>,
+    #[serde(deserialize_with = "lenient::timestamp")]
+    created_at: Timestamp,
+}
+
+impl Milestone {
+    pub fn new(due: Timestamp, description: Option<String>, created_at: Timestamp) -> Self {
+        Self {
+            due,
+            description,
+            created_at,
+        }
+    }
+
+    pub fn due(&self) -> Timestamp {
+        self.due
+    }
+
+    pub fn description(&self) -> Option<&str> {
+        self.description.as_deref()
+    }
+
+    pub fn created_at(&self) -> Timestamp {
+        self.created_at
+    }
+}
@@ -1,11 +1,25 @@
+mod attempt;
+mod checklist;
 mod comment;
 mod contract;
+mod criterion;
 mod goal;
+mod milestone;
 mod outcome;
+mod postmortem;
+mod reference;
+pub mod state_machine;
 mod task;
 
+pub use attempt::Attempt;
+pub use checklist::CheckItem;
 pub use comment::Comment;
-pub use contract::Contract;
-pub use goal::{Goal, GoalState, Metrics};
+pub use contract::{Contract, ContractRevision};
+pub use criterion::Criterion;
+pub use goal::{Claim, Goal, GoalState, Metrics, Spend};
+pub use milestone::Milestone;
 pub use outcome::Outcome;
-pub use task::{Task, TaskMetrics, TaskState};
+pub use postmortem::{FailureCategory, Postmortem};
+pub use reference::{RefKind, Reference};
+pub use state_machine::{ReasonCode, can_transition};
+pub use task::{Priority, Task, TaskMetrics, TaskState, TaskSummary};
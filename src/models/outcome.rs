@@ -4,11 +4,25 @@ use serde::{Deserialize, Serialize};
 pub struct Outcome {
     summary: String,
     artifacts: Vec<String>,
+    /// The git commit checked out when the task was completed, so artifact
+    /// paths can be linked as they were then.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    commit: Option<String>,
 }
 
 impl Outcome {
     pub fn new(summary: String, artifacts: Vec<String>) -> Self {
-        Self { summary, artifacts }
+        Self {
+            summary,
+            artifacts,
+            commit: None,
+        }
+    }
+
+    #[must_use]
+    pub fn with_commit(mut self, commit: Option<String>) -> Self {
+        self.commit = commit;
+        self
     }
 
     pub fn summary(&self) -> &str {
@@ -18,4 +32,8 @@ impl Outcome {
     pub fn artifacts(&self) -> &[String] {
         &self.artifacts
     }
+
+    pub fn commit(&self) -> Option<&str> {
+        self.commit.as_deref()
+    }
 }
@@ -0,0 +1,79 @@
+use jiff::Timestamp;
+use serde::{Deserialize, Serialize};
+use strum::{AsRefStr, EnumString};
+
+/// Why an attempt failed, coarse enough to spot trends across goals.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    Default,
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    AsRefStr,
+    EnumString,
+)]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+pub enum FailureCategory {
+    /// The description or contract was wrong or unclear.
+    Spec,
+    /// Something the task relied on was missing or broken.
+    Dependency,
+    /// Setup, credentials, network or other machine problems.
+    Environment,
+    /// The agent's tools or the model misbehaved.
+    Tooling,
+    /// Ran out of time or tokens.
+    Timeout,
+    #[default]
+    Other,
+}
+
+/// What went wrong with one failed attempt at a task.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Postmortem {
+    category: FailureCategory,
+    /// Path to logs from the attempt.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    logs: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    suggested_fix: Option<String>,
+    failed_at: Timestamp,
+}
+
+impl Postmortem {
+    pub fn new(
+        category: FailureCategory,
+        logs: Option<String>,
+        suggested_fix: Option<String>,
+        failed_at: Timestamp,
+    ) -> Self {
+        Self {
+            category,
+            logs,
+            suggested_fix,
+            failed_at,
+        }
+    }
+
+    pub fn category(&self) -> FailureCategory {
+        self.category
+    }
+
+    pub fn logs(&self) -> Option<&str> {
+        self.logs.as_deref()
+    }
+
+    pub fn suggested_fix(&self) -> Option<&str> {
+        self.suggested_fix.as_deref()
+    }
+
+    pub fn failed_at(&self) -> Timestamp {
+        self.failed_at
+    }
+}
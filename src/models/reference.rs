@@ -0,0 +1,178 @@
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+use strum::{AsRefStr, EnumString, VariantNames};
+
+/// The system a [`Reference`] points into.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, AsRefStr, EnumString, VariantNames)]
+#[strum(serialize_all = "snake_case")]
+pub enum RefKind {
+    /// An issue or pull request, as `owner/repo#123`.
+    Github,
+    /// An issue, as `group/project#123`; groups may nest.
+    Gitlab,
+    /// A ticket key such as `PROJ-123`.
+    Jira,
+    /// An issue identifier such as `ENG-123`.
+    Linear,
+    /// Any other web page.
+    Url,
+}
+
+/// A link from a task to an issue, pull request or ticket elsewhere.
+/// Written and stored as `kind:target`, such as `github:owner/repo#123`,
+/// so the same text identifies the item when matching against a tracker.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Reference {
+    kind: RefKind,
+    /// The whole reference, `kind:target`.
+    text: String,
+}
+
+impl Reference {
+    pub fn kind(&self) -> RefKind {
+        self.kind
+    }
+
+    pub fn target(&self) -> &str {
+        &self.text[self.kind.as_ref().len() + 1..]
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.text
+    }
+
+    /// Where the item can be opened, for the kinds whose host is known.
+    pub fn url(&self) -> Option<String> {
+        match self.kind {
+            RefKind::Github => {
+                let (repo, number) = self.target().split_once('#')?;
+                Some(format!("https://github.com/{repo}/issues/{number}"))
+            }
+            RefKind::Gitlab => {
+                let (project, number) = self.target().split_once('#')?;
+                Some(format!("https://gitlab.com/{project}/-/issues/{number}"))
+            }
+            RefKind::Url => Some(self.target().to_string()),
+            RefKind::Jira | RefKind::Linear => None,
+        }
+    }
+}
+
+impl FromStr for Reference {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, String> {
+        let text = text.trim();
+        let (kind, target) = text.split_once(':').ok_or_else(|| {
+            format!("'{text}' is not a reference; write it as kind:target, such as github:owner/repo#123")
+        })?;
+        let kind: RefKind = kind.trim().to_ascii_lowercase().parse().map_err(|_| {
+            format!(
+                "Unknown reference kind '{kind}'; expected one of: {}",
+                RefKind::VARIANTS.join(", ")
+            )
+        })?;
+        let target = target.trim();
+        let valid = match kind {
+            RefKind::Github | RefKind::Gitlab => target.split_once('#').is_some_and(|(repo, n)| {
+                is_number(n) && repo.split('/').filter(|p| !p.is_empty()).count() >= 2
+            }),
+            RefKind::Jira | RefKind::Linear => target.split_once('-').is_some_and(|(key, n)| {
+                is_number(n)
+                    && key.starts_with(|c: char| c.is_ascii_uppercase())
+                    && key
+                        .chars()
+                        .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit())
+            }),
+            RefKind::Url => target.starts_with("https://") || target.starts_with("http://"),
+        };
+        if !valid {
+            let example = match kind {
+                RefKind::Github => "github:owner/repo#123",
+                RefKind::Gitlab => "gitlab:group/project#123",
+                RefKind::Jira => "jira:PROJ-123",
+                RefKind::Linear => "linear:ENG-123",
+                RefKind::Url => "url:https://example.com/tickets/123",
+            };
+            return Err(format!(
+                "'{text}' is not a valid {} reference; expected e.g. {example}",
+                kind.as_ref()
+            ));
+        }
+        Ok(Self {
+            kind,
+            text: format!("{}:{target}", kind.as_ref()),
+        })
+    }
+}
+
+fn is_number(text: &str) -> bool {
+    !text.is_empty() && text.chars().all(|c| c.is_ascii_digit())
+}
+
+impl fmt::Display for Reference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.text)
+    }
+}
+
+impl TryFrom<String> for Reference {
+    type Error = String;
+
+    fn try_from(text: String) -> Result<Self, String> {
+        text.parse()
+    }
+}
+
+impl From<Reference> for String {
+    fn from(reference: Reference) -> Self {
+        reference.text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    // Each kind keeps its target as written and links where its host is known.
+    #[rstest]
+    #[case::github(
+        "github:owner/repo#123",
+        Some("https://github.com/owner/repo/issues/123")
+    )]
+    #[case::gitlab(
+        "gitlab:group/sub/app#7",
+        Some("https://gitlab.com/group/sub/app/-/issues/7")
+    )]
+    #[case::jira("jira:PROJ-42", None)]
+    #[case::url("url:https://example.com/t/1", Some("https://example.com/t/1"))]
+    fn parses_references(#[case] text: &str, #[case] url: Option<&str>) {
+        let reference: Reference = text.parse().unwrap();
+        assert_eq!(reference.to_string(), text);
+        assert_eq!(reference.url().as_deref(), url);
+    }
+
+    // The kind is read ignoring case and surrounding space.
+    #[rstest]
+    fn normalizes_the_kind() {
+        let reference: Reference = " GitHub:owner/repo#1 ".parse().unwrap();
+        assert_eq!(reference.to_string(), "github:owner/repo#1");
+    }
+
+    // Malformed targets are refused with an example of the right form.
+    #[rstest]
+    #[case::no_kind("owner/repo#123", "write it as kind:target")]
+    #[case::unknown_kind("trello:abc", "Unknown reference kind 'trello'")]
+    #[case::no_number("github:owner/repo", "github:owner/repo#123")]
+    #[case::no_owner("github:repo#1", "github:owner/repo#123")]
+    #[case::lowercase_key("jira:proj-1", "jira:PROJ-123")]
+    #[case::not_http("url:ftp://host/file", "url:https://")]
+    fn rejects_bad_references(#[case] text: &str, #[case] message: &str) {
+        let err = text.parse::<Reference>().unwrap_err();
+        assert!(err.contains(message), "{err}");
+    }
+}
@@ -0,0 +1,157 @@
+//! The state changes tasks and goals allow. Commands check moves against
+//! these tables rather than comparing states inline, so adding a state means
+//! editing one match arm here.
+
+use serde::{Deserialize, Serialize};
+use strum::{AsRefStr, EnumString};
+
+use super::{GoalState, TaskState};
+use crate::error::{Error, Result};
+
+/// Why a task changed state, kept with the task and in the history log so
+/// reports can tell people's interventions from automated moves.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, AsRefStr, EnumString)]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+pub enum ReasonCode {
+    /// Someone ran the command without `RADIAL_AGENT` set.
+    UserRequest,
+    /// An agent ran the command, or radial moved the task on its own, such
+    /// as releasing it once its blockers finished.
+    AgentAuto,
+    /// Whoever was working on it let its claim run out.
+    LeaseExpired,
+    /// Its verify command failed.
+    VerifyFailed,
+    /// It ran out of tokens or time.
+    BudgetExceeded,
+}
+
+/// A lifecycle state with a fixed set of next states.
+pub trait State: Clone + PartialEq + AsRef<str> + 'static {
+    /// Every state, in lifecycle order. `Unknown`, which only comes from
+    /// reading a state this version doesn't know, is left out.
+    const ALL: &'static [Self];
+
+    /// States reachable from `self` in one step. Staying put is not a move.
+    fn next_states(&self) -> &'static [Self];
+}
+
+impl State for TaskState {
+    const ALL: &'static [Self] = &[
+        Self::Pending,
+        Self::Blocked,
+        Self::InProgress,
+        Self::Verifying,
+        Self::Completed,
+        Self::Failed,
+    ];
+
+    fn next_states(&self) -> &'static [Self] {
+        match self {
+            Self::Pending => &[Self::InProgress, Self::Blocked],
+            Self::Blocked => &[Self::Pending],
+            Self::InProgress => &[Self::Verifying, Self::Completed, Self::Failed],
+            Self::Verifying => &[Self::InProgress, Self::Completed, Self::Failed],
+            // Retrying puts the task straight back into progress; reopening
+            // its goal with `--reset-failed` returns it to pending.
+            Self::Failed => &[Self::InProgress, Self::Pending],
+            Self::Completed | Self::Unknown(_) => &[],
+        }
+    }
+}
+
+/// Goal states follow from their tasks, so any derived state is reachable;
+/// only a pending goal cannot be returned to.
+impl State for GoalState {
+    const ALL: &'static [Self] = &[
+        Self::Pending,
+        Self::InProgress,
+        Self::Completed,
+        Self::Failed,
+    ];
+
+    fn next_states(&self) -> &'static [Self] {
+        match self {
+            Self::Pending => &[Self::InProgress, Self::Completed, Self::Failed],
+            Self::InProgress => &[Self::Completed, Self::Failed],
+            Self::Completed => &[Self::InProgress, Self::Failed],
+            Self::Failed => &[Self::InProgress, Self::Completed],
+            Self::Unknown => &[],
+        }
+    }
+}
+
+pub fn can_transition<S: State>(from: &S, to: &S) -> bool {
+    from.next_states().contains(to)
+}
+
+/// `Ok` if task `id` may move from `from` to `to`, otherwise an
+/// `InvalidTransition` error that lists where it can go instead.
+pub fn check_task(id: &str, from: TaskState, to: TaskState) -> Result<()> {
+    if can_transition(&from, &to) {
+        Ok(())
+    } else {
+        Err(Error::InvalidTransition {
+            id: id.to_string(),
+            from,
+            to,
+        })
+    }
+}
+
+/// The next states of `from` as a list, e.g. `in_progress, blocked`, for
+/// error messages.
+pub fn describe_next<S: State>(from: &S) -> String {
+    let next = from.next_states();
+    if next.is_empty() {
+        return "nothing (final state)".to_string();
+    }
+    next.iter()
+        .map(AsRef::as_ref)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    // The task table allows the lifecycle the commands implement and
+    // nothing else.
+    #[rstest]
+    #[case::start(TaskState::Pending, TaskState::InProgress, true)]
+    #[case::block(TaskState::Pending, TaskState::Blocked, true)]
+    #[case::unblock(TaskState::Blocked, TaskState::Pending, true)]
+    #[case::complete(TaskState::InProgress, TaskState::Completed, true)]
+    #[case::fail_verifying(TaskState::Verifying, TaskState::Failed, true)]
+    #[case::retry(TaskState::Failed, TaskState::InProgress, true)]
+    #[case::skip_start(TaskState::Pending, TaskState::Completed, false)]
+    #[case::start_blocked(TaskState::Blocked, TaskState::InProgress, false)]
+    #[case::reopen(TaskState::Completed, TaskState::Pending, false)]
+    #[case::stay(TaskState::Pending, TaskState::Pending, false)]
+    fn task_transitions(#[case] from: TaskState, #[case] to: TaskState, #[case] allowed: bool) {
+        assert_eq!(can_transition(&from, &to), allowed);
+        assert_eq!(check_task("t1", from, to).is_ok(), allowed);
+    }
+
+    // A goal never goes back to pending.
+    #[rstest]
+    #[case(GoalState::InProgress)]
+    #[case(GoalState::Completed)]
+    #[case(GoalState::Failed)]
+    fn goals_never_return_to_pending(#[case] from: GoalState) {
+        assert!(!can_transition(&from, &GoalState::Pending));
+    }
+
+    // The description names the next states, or says there are none.
+    #[rstest]
+    fn describe_next_lists_targets() {
+        assert_eq!(describe_next(&TaskState::Pending), "in_progress, blocked");
+        assert_eq!(
+            describe_next(&TaskState::Completed),
+            "nothing (final state)"
+        );
+    }
+}
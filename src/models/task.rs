@@ -1,18 +1,23 @@
+use std::collections::BTreeMap;
 use std::io::Write;
 use std::path::{Path, PathBuf};
 
 use anyhow::{Context, Result};
 use console::style;
-use jiff::Timestamp;
-use serde::{Deserialize, Serialize};
+use jiff::{SignedDuration, Timestamp};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use strum::{AsRefStr, EnumString};
 
-use super::{Comment, Contract, Outcome};
+use super::{
+    Attempt, CheckItem, Comment, Contract, ContractRevision, Outcome, Postmortem, ReasonCode,
+    Reference,
+};
+use crate::clock;
 use crate::db::atomic_write;
+use crate::lenient;
 use crate::output::Render;
 
-#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, AsRefStr, EnumString)]
-#[serde(rename_all = "lowercase")]
+#[derive(Debug, Clone, PartialEq, Eq, EnumString)]
 #[strum(serialize_all = "snake_case")]
 pub enum TaskState {
     Pending,
@@ -21,6 +26,64 @@ pub enum TaskState {
     Verifying,
     Completed,
     Failed,
+    /// Read from a file with a state this version doesn't know, kept as
+    /// written so saving the task doesn't lose it. Nothing moves into or
+    /// out of it; fix the file to recover the record.
+    #[strum(disabled)]
+    Unknown(String),
+}
+
+impl AsRef<str> for TaskState {
+    fn as_ref(&self) -> &str {
+        match self {
+            Self::Pending => "pending",
+            Self::Blocked => "blocked",
+            Self::InProgress => "in_progress",
+            Self::Verifying => "verifying",
+            Self::Completed => "completed",
+            Self::Failed => "failed",
+            Self::Unknown(_) => "unknown",
+        }
+    }
+}
+
+impl Serialize for TaskState {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Self::Unknown(raw) => serializer.serialize_str(raw),
+            known => serializer.serialize_str(&known.as_ref().replace('_', "")),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for TaskState {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        lenient::state(deserializer, Self::Unknown)
+    }
+}
+
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    Default,
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    AsRefStr,
+    EnumString,
+)]
+#[serde(rename_all = "lowercase")]
+#[strum(serialize_all = "lowercase")]
+pub enum Priority {
+    Low,
+    #[default]
+    Normal,
+    High,
+    Urgent,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -52,25 +115,124 @@ impl TaskMetrics {
     }
 }
 
+/// The parts of a task that table views print, borrowed from the task so
+/// listing thousands of them copies nothing else.
+#[derive(Debug, Clone, Copy)]
+pub struct TaskSummary<'a> {
+    pub id: &'a str,
+    pub state: &'a TaskState,
+    pub stage: Option<&'a str>,
+    pub priority: Priority,
+    pub assignee: Option<&'a str>,
+    /// The title, or the description when there is none.
+    pub headline: &'a str,
+    /// The latest progress update of the current attempt.
+    pub progress: Option<u8>,
+    /// Kept so views can put tasks in dependency order.
+    pub blocked_by: &'a [String],
+}
+
+impl TaskSummary<'_> {
+    /// The custom state if the task is in one, otherwise the core state.
+    pub fn label(&self) -> &str {
+        self.stage.unwrap_or(self.state.as_ref())
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Task {
     id: String,
     goal_id: String,
+    /// Short name for list views; `description` can run to several lines.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    title: Option<String>,
     description: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     contract: Option<Contract>,
     state: TaskState,
+    /// A custom state from `[states]` refining `state`, cleared whenever
+    /// `state` changes.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    stage: Option<String>,
+    /// Why `state` was last entered; unset on tasks that haven't moved
+    /// since they were created, or last moved before reasons were kept.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    state_reason: Option<ReasonCode>,
+    #[serde(default)]
+    priority: Priority,
+    /// Position within the goal; ties fall back to `created_at`.
+    #[serde(default)]
+    order: u32,
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     blocked_by: Vec<String>,
+    /// Artifact paths that some completed task in the goal must have listed.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    blocked_by_artifacts: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    assignee: Option<String>,
+    /// Until this time the task is left out of `ready` and `claim-next`,
+    /// even once its blockers are done.
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "lenient::option_timestamp"
+    )]
+    not_before: Option<Timestamp>,
+    /// How long the task is expected to take, so `claim-next --max-minutes`
+    /// can skip work that won't fit.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    estimate_ms: Option<i64>,
+    /// Shell commands that must succeed before the task is ready; see
+    /// [`crate::gate`].
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    gates: Vec<String>,
+    /// Issues, pull requests and tickets in other systems the task tracks.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    refs: Vec<Reference>,
     #[serde(skip_serializing_if = "Option::is_none")]
     result: Option<Outcome>,
+    #[serde(deserialize_with = "lenient::timestamp")]
     created_at: Timestamp,
+    #[serde(deserialize_with = "lenient::timestamp")]
     updated_at: Timestamp,
-    #[serde(skip_serializing_if = "Option::is_none")]
+    /// Logical clock values (see [`crate::clock`]) for when the record was
+    /// created and last changed. Unlike the timestamps, they never run
+    /// backwards when records come from machines with different clocks.
+    #[serde(default)]
+    created_seq: u64,
+    #[serde(default)]
+    seq: u64,
+    /// When the task last entered `in_progress`.
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "lenient::option_timestamp"
+    )]
+    started_at: Option<Timestamp>,
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "lenient::option_timestamp"
+    )]
     completed_at: Option<Timestamp>,
     metrics: TaskMetrics,
     #[serde(default)]
     comments: Vec<Comment>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    checklist: Vec<CheckItem>,
+    /// Earlier descriptions and contracts, oldest first.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    revisions: Vec<ContractRevision>,
+    /// One entry per failed attempt that was given a postmortem.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    postmortems: Vec<Postmortem>,
+    /// The spend of each finished attempt, oldest first.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    attempts: Vec<Attempt>,
+    /// Key-value notes agents keep with the task, such as a branch name or
+    /// PR URL.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    ctx: BTreeMap<String, String>,
 }
 
 impl Task {
@@ -85,28 +247,111 @@ impl Task {
         created_at: Timestamp,
         updated_at: Timestamp,
     ) -> Self {
+        let seq = clock::tick();
         Self {
             id,
             goal_id,
+            title: None,
             description,
             contract,
             state,
+            stage: None,
+            state_reason: None,
+            priority: Priority::default(),
+            order: 0,
             blocked_by,
+            blocked_by_artifacts: Vec::new(),
+            assignee: None,
+            not_before: None,
+            estimate_ms: None,
+            gates: Vec::new(),
+            refs: Vec::new(),
             result: None,
             created_at,
             updated_at,
+            created_seq: seq,
+            seq,
+            started_at: None,
             completed_at: None,
             metrics: TaskMetrics::default(),
             comments: Vec::new(),
+            checklist: Vec::new(),
+            revisions: Vec::new(),
+            postmortems: Vec::new(),
+            attempts: Vec::new(),
+            ctx: BTreeMap::new(),
         }
     }
 
+    /// The same task under another ID and goal, for copies imported from
+    /// a bundle.
+    #[must_use]
+    pub(crate) fn with_id(mut self, id: String, goal_id: String) -> Self {
+        self.id = id;
+        self.goal_id = goal_id;
+        self
+    }
+
+    #[must_use]
+    pub fn with_title(mut self, title: Option<String>) -> Self {
+        self.title = title;
+        self
+    }
+
+    #[must_use]
+    pub fn with_blocked_by_artifacts(mut self, artifacts: Vec<String>) -> Self {
+        self.blocked_by_artifacts = artifacts;
+        self
+    }
+
     #[must_use]
     pub fn with_metrics(mut self, metrics: TaskMetrics) -> Self {
         self.metrics = metrics;
         self
     }
 
+    #[must_use]
+    pub fn with_priority(mut self, priority: Priority) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    #[must_use]
+    pub fn with_order(mut self, order: u32) -> Self {
+        self.order = order;
+        self
+    }
+
+    #[must_use]
+    pub fn with_assignee(mut self, assignee: Option<String>) -> Self {
+        self.assignee = assignee;
+        self
+    }
+
+    #[must_use]
+    pub fn with_not_before(mut self, not_before: Option<Timestamp>) -> Self {
+        self.not_before = not_before;
+        self
+    }
+
+    #[must_use]
+    pub fn with_estimate(mut self, estimate: Option<SignedDuration>) -> Self {
+        self.estimate_ms = estimate.map(duration_ms);
+        self
+    }
+
+    #[must_use]
+    pub fn with_gates(mut self, gates: Vec<String>) -> Self {
+        self.gates = gates;
+        self
+    }
+
+    #[must_use]
+    pub fn with_refs(mut self, refs: Vec<Reference>) -> Self {
+        self.refs = distinct(refs);
+        self
+    }
+
     pub fn id(&self) -> &str {
         &self.id
     }
@@ -115,22 +360,108 @@ impl Task {
         &self.goal_id
     }
 
+    pub fn title(&self) -> Option<&str> {
+        self.title.as_deref()
+    }
+
     pub fn description(&self) -> &str {
         &self.description
     }
 
+    /// The title, or the description when there is none.
+    pub fn headline(&self) -> &str {
+        self.title.as_deref().unwrap_or(&self.description)
+    }
+
+    pub fn summary(&self) -> TaskSummary<'_> {
+        TaskSummary {
+            id: &self.id,
+            state: &self.state,
+            stage: self.stage.as_deref(),
+            priority: self.priority,
+            assignee: self.assignee.as_deref(),
+            headline: self.headline(),
+            progress: self.progress(),
+            blocked_by: &self.blocked_by,
+        }
+    }
+
     pub fn contract(&self) -> Option<&Contract> {
         self.contract.as_ref()
     }
 
     pub fn state(&self) -> TaskState {
-        self.state
+        self.state.clone()
+    }
+
+    /// The custom state from `[states]` the task is in, if any.
+    pub fn stage(&self) -> Option<&str> {
+        self.stage.as_deref()
+    }
+
+    pub fn state_reason(&self) -> Option<ReasonCode> {
+        self.state_reason
+    }
+
+    /// The state to show: the custom state if the task is in one,
+    /// otherwise the core state.
+    pub fn label(&self) -> &str {
+        self.stage.as_deref().unwrap_or(self.state.as_ref())
+    }
+
+    pub fn priority(&self) -> Priority {
+        self.priority
+    }
+
+    pub fn order(&self) -> u32 {
+        self.order
     }
 
     pub fn blocked_by(&self) -> &[String] {
         &self.blocked_by
     }
 
+    pub fn blocked_by_artifacts(&self) -> &[String] {
+        &self.blocked_by_artifacts
+    }
+
+    /// Whether this task is completed and listed `artifact` in its result.
+    /// A leading `./` is ignored on both sides.
+    pub fn produced(&self, artifact: &str) -> bool {
+        let wanted = artifact.trim_start_matches("./");
+        self.state == TaskState::Completed
+            && self.result.as_ref().is_some_and(|r| {
+                r.artifacts()
+                    .iter()
+                    .any(|a| a.trim_start_matches("./") == wanted)
+            })
+    }
+
+    pub fn assignee(&self) -> Option<&str> {
+        self.assignee.as_deref()
+    }
+
+    pub fn not_before(&self) -> Option<Timestamp> {
+        self.not_before
+    }
+
+    /// Whether any `not_before` time has passed by `now`.
+    pub fn available(&self, now: Timestamp) -> bool {
+        self.not_before.is_none_or(|at| at <= now)
+    }
+
+    pub fn estimate(&self) -> Option<SignedDuration> {
+        self.estimate_ms.map(SignedDuration::from_millis)
+    }
+
+    pub fn gates(&self) -> &[String] {
+        &self.gates
+    }
+
+    pub fn refs(&self) -> &[Reference] {
+        &self.refs
+    }
+
     pub fn result(&self) -> Option<&Outcome> {
         self.result.as_ref()
     }
@@ -143,6 +474,18 @@ impl Task {
         self.updated_at
     }
 
+    pub fn created_seq(&self) -> u64 {
+        self.created_seq
+    }
+
+    pub fn seq(&self) -> u64 {
+        self.seq
+    }
+
+    pub fn started_at(&self) -> Option<Timestamp> {
+        self.started_at
+    }
+
     pub fn completed_at(&self) -> Option<Timestamp> {
         self.completed_at
     }
@@ -155,19 +498,126 @@ impl Task {
         &self.comments
     }
 
+    pub fn comment(&self, comment_id: &str) -> Option<&Comment> {
+        self.comments.iter().find(|c| c.id() == comment_id)
+    }
+
+    /// Comments in thread order, each with its depth: a comment is followed
+    /// by its replies, oldest first. Replies to a comment that is gone, or
+    /// caught in a loop by a hand edit, are treated as starting a thread.
+    pub fn comment_threads(&self) -> Vec<(usize, &Comment)> {
+        fn push<'a>(
+            out: &mut Vec<(usize, &'a Comment)>,
+            all: &'a [Comment],
+            comment: &'a Comment,
+            depth: usize,
+        ) {
+            out.push((depth, comment));
+            for reply in all.iter().filter(|c| c.reply_to() == Some(comment.id())) {
+                if !out.iter().any(|(_, seen)| seen.id() == reply.id()) {
+                    push(out, all, reply, depth + 1);
+                }
+            }
+        }
+
+        let mut out = Vec::with_capacity(self.comments.len());
+        for comment in &self.comments {
+            let starts_thread = comment
+                .reply_to()
+                .is_none_or(|parent| self.comment(parent).is_none());
+            if starts_thread {
+                push(&mut out, &self.comments, comment, 0);
+            }
+        }
+        for comment in &self.comments {
+            if !out.iter().any(|(_, seen)| seen.id() == comment.id()) {
+                push(&mut out, &self.comments, comment, 0);
+            }
+        }
+        out
+    }
+
+    pub fn checklist(&self) -> &[CheckItem] {
+        &self.checklist
+    }
+
+    pub fn ctx(&self) -> &BTreeMap<String, String> {
+        &self.ctx
+    }
+
+    pub fn revisions(&self) -> &[ContractRevision] {
+        &self.revisions
+    }
+
+    pub fn postmortems(&self) -> &[Postmortem] {
+        &self.postmortems
+    }
+
+    pub fn attempts(&self) -> &[Attempt] {
+        &self.attempts
+    }
+
     pub fn set_description(&mut self, description: String) {
         self.description = description;
-        self.updated_at = Timestamp::now();
+        self.touch();
+    }
+
+    pub fn set_title(&mut self, title: Option<String>) {
+        self.title = title;
+        self.touch();
     }
 
     pub fn set_contract(&mut self, contract: Contract) {
         self.contract = Some(contract);
-        self.updated_at = Timestamp::now();
+        self.touch();
     }
 
     pub fn set_blocked_by(&mut self, blocked_by: Vec<String>) {
         self.blocked_by = blocked_by;
-        self.updated_at = Timestamp::now();
+        self.touch();
+    }
+
+    pub fn set_priority(&mut self, priority: Priority) {
+        self.priority = priority;
+        self.touch();
+    }
+
+    /// Move the task to another goal. Only for
+    /// [`crate::db::Database::move_task`], which also moves its file and its
+    /// place in the store's index of tasks by goal.
+    pub(crate) fn set_goal_id(&mut self, goal_id: String) {
+        self.goal_id = goal_id;
+        self.touch();
+    }
+
+    pub fn set_order(&mut self, order: u32) {
+        self.order = order;
+        self.touch();
+    }
+
+    pub fn set_assignee(&mut self, assignee: Option<String>) {
+        self.assignee = assignee;
+        self.touch();
+    }
+
+    pub fn set_not_before(&mut self, not_before: Option<Timestamp>) {
+        self.not_before = not_before;
+        self.touch();
+    }
+
+    pub fn set_estimate(&mut self, estimate: Option<SignedDuration>) {
+        self.estimate_ms = estimate.map(duration_ms);
+        self.touch();
+    }
+
+    pub fn set_gates(&mut self, gates: Vec<String>) {
+        self.gates = gates;
+        self.touch();
+    }
+
+    pub fn set_refs(&mut self, refs: Vec<Reference>) {
+        self.refs = distinct(refs);
+        self.touch();
     }
 
     pub fn file_path(&self, base: &Path) -> PathBuf {
@@ -180,55 +630,171 @@ impl Task {
         atomic_write(&path, content.as_bytes())
     }
 
-    pub fn transition(&mut self, from: TaskState, to: TaskState) -> bool {
-        if self.state != from {
+    pub fn transition(&mut self, from: &TaskState, to: TaskState, reason: ReasonCode) -> bool {
+        if self.state != *from {
             return false;
         }
-        self.state = to;
-        self.updated_at = Timestamp::now();
+        self.set_state(to, reason);
         true
     }
 
-    pub fn transition_from_any(&mut self, from: &[TaskState], to: TaskState) -> bool {
+    pub fn transition_from_any(
+        &mut self,
+        from: &[TaskState],
+        to: TaskState,
+        reason: ReasonCode,
+    ) -> bool {
         if !from.contains(&self.state) {
             return false;
         }
-        self.state = to;
-        self.updated_at = Timestamp::now();
+        self.set_state(to, reason);
         true
     }
 
-    pub fn complete(&mut self, outcome: Outcome, metrics: TaskMetrics) -> bool {
+    /// Record a change, returning the new `updated_at`.
+    fn touch(&mut self) -> Timestamp {
+        self.updated_at = clock::after(self.updated_at);
+        self.seq = clock::tick();
+        self.updated_at
+    }
+
+    /// Move to `to` for `reason`, recording when work (re)started.
+    fn set_state(&mut self, to: TaskState, reason: ReasonCode) {
+        let now = self.touch();
+        if to == TaskState::InProgress {
+            self.started_at = Some(now);
+        }
+        self.state = to;
+        self.stage = None;
+        self.state_reason = Some(reason);
+    }
+
+    /// Enter custom state `stage`, or leave one with `None`, moving to the
+    /// core state `core` it refines if the task isn't already there.
+    pub fn enter_stage(&mut self, stage: Option<String>, core: TaskState, reason: ReasonCode) {
+        if self.state == core {
+            self.touch();
+        } else {
+            self.set_state(core, reason);
+        }
+        self.stage = stage;
+    }
+
+    pub fn complete(&mut self, outcome: Outcome, metrics: TaskMetrics, reason: ReasonCode) -> bool {
         if self.state != TaskState::InProgress {
             return false;
         }
         self.state = TaskState::Completed;
+        self.stage = None;
+        self.state_reason = Some(reason);
         self.result = Some(outcome);
         self.metrics = metrics;
-        let now = Timestamp::now();
-        self.updated_at = now;
-        self.completed_at = Some(now);
+        self.completed_at = Some(self.touch());
         true
     }
 
-    pub fn retry(&mut self) -> bool {
+    pub fn retry(&mut self, reason: ReasonCode) -> bool {
         if self.state != TaskState::Failed {
             return false;
         }
         self.state = TaskState::InProgress;
+        self.stage = None;
+        self.state_reason = Some(reason);
         self.metrics.retry_count += 1;
-        self.updated_at = Timestamp::now();
+        self.started_at = Some(self.touch());
         true
     }
 
-    pub fn unblock(&mut self) {
+    /// Back to pending from failed, keeping its attempts and metrics, for
+    /// when its goal is reopened. The caller re-checks its blockers.
+    pub fn reset(&mut self, reason: ReasonCode) -> bool {
+        if self.state != TaskState::Failed {
+            return false;
+        }
+        self.set_state(TaskState::Pending, reason);
+        true
+    }
+
+    pub fn unblock(&mut self, reason: ReasonCode) {
         self.state = TaskState::Pending;
-        self.updated_at = Timestamp::now();
+        self.stage = None;
+        self.state_reason = Some(reason);
+        self.touch();
+    }
+
+    /// The latest progress update since the task was last started, so a
+    /// retried task does not report progress from the failed attempt.
+    pub fn progress(&self) -> Option<u8> {
+        self.comments
+            .iter()
+            .rev()
+            .take_while(|c| self.started_at.is_none_or(|at| c.created_at() >= at))
+            .find_map(Comment::progress)
     }
 
     pub fn add_comment(&mut self, comment: Comment) {
         self.comments.push(comment);
-        self.updated_at = Timestamp::now();
+        self.touch();
+    }
+
+    /// Replace the description and contract, keeping the old ones in
+    /// `revisions`. Nothing is recorded when neither actually changes.
+    pub fn revise(&mut self, description: String, contract: Option<Contract>) {
+        if description == self.description && contract == self.contract {
+            return;
+        }
+        let now = self.touch();
+        self.revisions.push(ContractRevision::new(
+            std::mem::replace(&mut self.description, description),
+            std::mem::replace(&mut self.contract, contract),
+            now,
+        ));
+    }
+
+    /// Record a finished attempt, adding its spend to the task's metrics.
+    pub fn add_attempt(&mut self, attempt: Attempt) {
+        self.metrics.tokens += attempt.tokens();
+        self.metrics.elapsed_ms += attempt.elapsed_ms();
+        self.attempts.push(attempt);
+        self.touch();
+    }
+
+    pub fn add_postmortem(&mut self, postmortem: Postmortem) {
+        self.postmortems.push(postmortem);
+        self.touch();
+    }
+
+    pub fn set_ctx(&mut self, key: String, value: String) {
+        self.ctx.insert(key, value);
+        self.touch();
+    }
+
+    /// Remove a context entry. Returns false if there was none.
+    pub fn remove_ctx(&mut self, key: &str) -> bool {
+        let removed = self.ctx.remove(key).is_some();
+        if removed {
+            self.touch();
+        }
+        removed
+    }
+
+    pub fn add_check_item(&mut self, item: CheckItem) {
+        self.checklist.push(item);
+        self.touch();
+    }
+
+    /// Mark checklist item `number` (1-based) done or not done. Returns
+    /// false if there is no such item.
+    pub fn tick_check_item(&mut self, number: usize, done: bool) -> bool {
+        let Some(item) = number
+            .checked_sub(1)
+            .and_then(|i| self.checklist.get_mut(i))
+        else {
+            return false;
+        };
+        item.set_done(done);
+        self.touch();
+        true
     }
 }
 
@@ -238,8 +804,11 @@ impl Render for Task {
             w,
             "{} [{}]",
             style(&self.id).cyan().bold(),
-            style(self.state.as_ref()).yellow()
+            style(self.label()).yellow()
         )?;
+        if let Some(title) = &self.title {
+            writeln!(w, "  {}", style(title).bold())?;
+        }
         writeln!(w, "  {}", &self.description)?;
 
         match self.contract {
@@ -258,6 +827,10 @@ impl Render for Task {
             writeln!(w, "  Blocked by: {}", self.blocked_by.join(", "))?;
         }
 
+        if let Some(assignee) = &self.assignee {
+            writeln!(w, "  Assignee: {assignee}")?;
+        }
+
         if let Some(result) = &self.result {
             writeln!(w, "  Result: {}", result.summary())?;
             if !result.artifacts().is_empty() {
@@ -268,6 +841,21 @@ impl Render for Task {
     }
 }
 
+fn duration_ms(duration: SignedDuration) -> i64 {
+    i64::try_from(duration.as_millis()).unwrap_or(i64::MAX)
+}
+
+/// `refs` with repeats dropped, keeping the first of each.
+fn distinct(refs: Vec<Reference>) -> Vec<Reference> {
+    let mut kept: Vec<Reference> = Vec::with_capacity(refs.len());
+    for reference in refs {
+        if !kept.contains(&reference) {
+            kept.push(reference);
+        }
+    }
+    kept
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -280,16 +868,35 @@ mod tests {
         Task {
             id: "t_abc123".to_string(),
             goal_id: "g_xyz789".to_string(),
+            title: None,
             description: "test task".to_string(),
             contract: None,
             state: TaskState::Pending,
+            stage: None,
+            state_reason: None,
+            priority: Priority::Normal,
+            order: 0,
             blocked_by: Vec::new(),
+            blocked_by_artifacts: Vec::new(),
+            assignee: None,
+            not_before: None,
+            estimate_ms: None,
+            gates: Vec::new(),
+            refs: Vec::new(),
             result: None,
             created_at: now,
             updated_at: now,
+            created_seq: 0,
+            seq: 0,
+            started_at: None,
             completed_at: None,
             metrics: TaskMetrics::default(),
             comments: Vec::new(),
+            checklist: Vec::new(),
+            revisions: Vec::new(),
+            postmortems: Vec::new(),
+            attempts: Vec::new(),
+            ctx: BTreeMap::new(),
         }
     }
 
@@ -326,9 +933,9 @@ mod tests {
         #[case] to: TaskState,
         #[case] expected: bool,
     ) {
-        task.state = initial;
+        task.state = initial.clone();
         let before = task.updated_at;
-        let result = task.transition(from, to);
+        let result = task.transition(&from, to.clone(), ReasonCode::UserRequest);
         assert_eq!(result, expected);
         if expected {
             assert_eq!(task.state, to);
@@ -339,6 +946,46 @@ mod tests {
         }
     }
 
+    // Entering in_progress stamps started_at; other moves leave it alone.
+    #[rstest]
+    fn transition_records_started_at(mut task: Task) {
+        assert!(task.started_at().is_none());
+        task.transition(
+            &TaskState::Pending,
+            TaskState::InProgress,
+            ReasonCode::UserRequest,
+        );
+        let started = task.started_at().expect("started_at should be set");
+
+        task.transition(
+            &TaskState::InProgress,
+            TaskState::Verifying,
+            ReasonCode::UserRequest,
+        );
+        assert_eq!(task.started_at(), Some(started));
+    }
+
+    // Each move keeps the reason it was made for; a refused move keeps the
+    // previous one.
+    #[rstest]
+    fn transition_records_reason(mut task: Task) {
+        assert_eq!(task.state_reason(), None);
+        task.transition(
+            &TaskState::Pending,
+            TaskState::InProgress,
+            ReasonCode::AgentAuto,
+        );
+        assert_eq!(task.state_reason(), Some(ReasonCode::AgentAuto));
+
+        assert!(!task.retry(ReasonCode::UserRequest));
+        task.transition(
+            &TaskState::InProgress,
+            TaskState::Failed,
+            ReasonCode::BudgetExceeded,
+        );
+        assert_eq!(task.state_reason(), Some(ReasonCode::BudgetExceeded));
+    }
+
     // -- transition_from_any --
 
     // transition_from_any() accepts a list of valid source states.
@@ -353,10 +1000,11 @@ mod tests {
         #[case] current: TaskState,
         #[case] expected: bool,
     ) {
-        task.state = current;
+        task.state = current.clone();
         let result = task.transition_from_any(
             &[TaskState::InProgress, TaskState::Verifying],
             TaskState::Failed,
+            ReasonCode::UserRequest,
         );
         assert_eq!(result, expected);
         if expected {
@@ -376,7 +1024,7 @@ mod tests {
         let outcome = Outcome::new("done".to_string(), vec!["file.txt".to_string()]);
         let metrics = TaskMetrics::new(100, 5000, 1);
 
-        assert!(task.complete(outcome, metrics));
+        assert!(task.complete(outcome, metrics, ReasonCode::UserRequest));
         assert_eq!(task.state, TaskState::Completed);
         assert!(task.completed_at.is_some());
         assert_eq!(task.result.as_ref().unwrap().summary(), "done");
@@ -392,9 +1040,9 @@ mod tests {
     #[case::from_completed(TaskState::Completed)]
     #[case::from_failed(TaskState::Failed)]
     fn complete_rejects_non_in_progress(mut task: Task, #[case] state: TaskState) {
-        task.state = state;
+        task.state = state.clone();
         let outcome = Outcome::new("done".to_string(), Vec::new());
-        assert!(!task.complete(outcome, TaskMetrics::default()));
+        assert!(!task.complete(outcome, TaskMetrics::default(), ReasonCode::UserRequest));
         assert_eq!(task.state, state);
         assert!(task.completed_at.is_none());
     }
@@ -407,7 +1055,7 @@ mod tests {
     fn retry_increments_and_transitions(mut task: Task) {
         task.state = TaskState::Failed;
         task.metrics.retry_count = 2;
-        assert!(task.retry());
+        assert!(task.retry(ReasonCode::UserRequest));
         assert_eq!(task.state, TaskState::InProgress);
         assert_eq!(task.metrics.retry_count, 3);
     }
@@ -419,8 +1067,8 @@ mod tests {
     #[case::from_completed(TaskState::Completed)]
     #[case::from_blocked(TaskState::Blocked)]
     fn retry_rejects_non_failed(mut task: Task, #[case] state: TaskState) {
-        task.state = state;
-        assert!(!task.retry());
+        task.state = state.clone();
+        assert!(!task.retry(ReasonCode::UserRequest));
         assert_eq!(task.state, state);
     }
 
@@ -431,11 +1079,64 @@ mod tests {
     fn unblock_sets_pending(mut task: Task) {
         task.state = TaskState::Blocked;
         let before = task.updated_at;
-        task.unblock();
+        task.unblock(ReasonCode::UserRequest);
         assert_eq!(task.state, TaskState::Pending);
         assert!(task.updated_at >= before);
     }
 
+    // -- artifacts --
+
+    // Only a completed task's listed artifacts count, ignoring a leading ./
+    #[rstest]
+    fn produced_matches_completed_artifacts(mut task: Task) {
+        task.state = TaskState::InProgress;
+        let outcome = Outcome::new("done".to_string(), vec!["./dist/a.json".to_string()]);
+        assert!(!task.produced("dist/a.json"));
+        task.complete(outcome, TaskMetrics::default(), ReasonCode::UserRequest);
+
+        assert!(task.produced("dist/a.json"));
+        assert!(task.produced("./dist/a.json"));
+        assert!(!task.produced("dist/b.json"));
+    }
+
+    // -- revisions --
+
+    // Revising keeps the old description and contract; an identical
+    // revision is not recorded.
+    #[rstest]
+    fn revise_records_previous_contract(mut task: Task) {
+        let contract = Contract::new("a".to_string(), "b".to_string(), "c".to_string());
+        task.revise("sharper task".to_string(), Some(contract.clone()));
+        task.revise("sharper task".to_string(), Some(contract));
+
+        assert_eq!(task.description(), "sharper task");
+        assert_eq!(task.contract().unwrap().verify(), "c");
+        assert_eq!(task.revisions().len(), 1);
+        assert_eq!(task.revisions()[0].description(), "test task");
+        assert!(task.revisions()[0].contract().is_none());
+    }
+
+    // -- checklist --
+
+    // Items are numbered from 1; ticking records when, unticking clears it,
+    // and numbers outside the list are refused.
+    #[rstest]
+    fn tick_check_item_by_number(mut task: Task) {
+        task.add_check_item(CheckItem::new("parse".to_string(), Timestamp::now()));
+        task.add_check_item(CheckItem::new("test".to_string(), Timestamp::now()));
+
+        assert!(task.tick_check_item(2, true));
+        assert!(task.checklist()[1].done());
+        assert!(task.checklist()[1].done_at().is_some());
+        assert!(!task.checklist()[0].done());
+
+        assert!(task.tick_check_item(2, false));
+        assert!(task.checklist()[1].done_at().is_none());
+
+        assert!(!task.tick_check_item(0, true));
+        assert!(!task.tick_check_item(3, true));
+    }
+
     // -- add_comment --
 
     // Adding a comment should append to the list and bump updated_at.
@@ -450,6 +1151,32 @@ mod tests {
         assert!(task.updated_at >= before);
     }
 
+    // Replies follow the comment they answer, nested one level deeper;
+    // a reply whose parent is missing starts its own thread.
+    #[rstest]
+    fn comment_threads_nest_replies(mut task: Task) {
+        let at = Timestamp::now();
+        let comment = |id: &str, reply_to: Option<&str>| {
+            Comment::new(id.to_string(), String::new(), at)
+                .with_reply_to(reply_to.map(str::to_string))
+        };
+        task.add_comment(comment("c_1", None));
+        task.add_comment(comment("c_2", None));
+        task.add_comment(comment("c_3", Some("c_1")));
+        task.add_comment(comment("c_4", Some("c_3")));
+        task.add_comment(comment("c_5", Some("c_9")));
+
+        let threads: Vec<(usize, &str)> = task
+            .comment_threads()
+            .into_iter()
+            .map(|(depth, c)| (depth, c.id()))
+            .collect();
+        assert_eq!(
+            threads,
+            [(0, "c_1"), (1, "c_3"), (2, "c_4"), (0, "c_2"), (0, "c_5")]
+        );
+    }
+
     // -- file_path --
 
     // Task files live at {base}/{goal_id}/{task_id}.toml.
@@ -459,6 +1186,34 @@ mod tests {
         assert_eq!(path, PathBuf::from("/tmp/.radial/g_xyz789/t_abc123.toml"));
     }
 
+    // Progress is the latest update, but only from the current attempt.
+    #[rstest]
+    fn progress_ignores_earlier_attempts(mut task: Task) {
+        let earlier = Timestamp::from_second(1_000).unwrap();
+        let later = Timestamp::from_second(2_000).unwrap();
+        let update = |id: &str, at, percent| {
+            Comment::new(id.to_string(), String::new(), at).with_progress(percent)
+        };
+        task.add_comment(update("c_1", earlier, 40));
+        task.add_comment(Comment::new("c_2".to_string(), "note".to_string(), earlier));
+        assert_eq!(task.progress(), Some(40));
+
+        task.started_at = Some(later);
+        assert_eq!(task.progress(), None);
+        task.add_comment(update("c_3", later, 10));
+        assert_eq!(task.progress(), Some(10));
+    }
+
+    // List views use the title when there is one and fall back to the
+    // description otherwise.
+    #[rstest]
+    fn headline_prefers_title(task: Task) {
+        assert_eq!(task.headline(), "test task");
+        let task = task.with_title(Some("Short".to_string()));
+        assert_eq!(task.headline(), "Short");
+        assert_eq!(task.description(), "test task");
+    }
+
     // -- render --
 
     // The summary render should show the description and indicate
@@ -493,6 +1248,14 @@ mod tests {
         assert!(output.contains("Blocked by: t_other"));
     }
 
+    // Assigned tasks name their agent; unassigned tasks omit the line.
+    #[rstest]
+    fn render_includes_assignee(mut task: Task) {
+        assert!(!render_to_string(&task).contains("Assignee"));
+        task.assignee = Some("claude-1".to_string());
+        assert!(render_to_string(&task).contains("Assignee: claude-1"));
+    }
+
     // Completed tasks should show the result summary and artifact list.
     #[rstest]
     fn render_includes_result(mut task: Task) {
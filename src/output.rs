@@ -1,20 +1,69 @@
 use std::io::{self, Write};
+use std::sync::OnceLock;
 
 use anyhow::Result;
-use console::style;
+use console::{Term, style};
+use jiff::tz::TimeZone;
+use jiff::{SignedDuration, Timestamp};
 use serde::Serialize;
+use serde_json::Value;
+use strum::{AsRefStr, EnumString};
 
-use crate::commands::list::GoalWithTasks;
-use crate::commands::show::ShowResult;
-use crate::commands::status::{GoalSummary, StatusResult};
-use crate::commands::task::CompleteResult;
-use crate::models::{Goal, Task};
+use crate::commands::all::{ProjectStatus, ProjectTask};
+use crate::commands::bundle::{Bundle, ExportReport, ImportReport};
+use crate::commands::capabilities::{ArgSpec, Capabilities};
+use crate::commands::clean::CleanResult;
+use crate::commands::context::TaskContext;
+use crate::commands::digest::{Delivery, Digest};
+use crate::commands::doctor::DoctorReport;
+use crate::commands::gc::GcReport;
+use crate::commands::goal::{
+    ArchiveReport, ArchivedGoal, GoalVerification, MergeReport, ReopenReport,
+};
+use crate::commands::history::{LOG_FILE, Numbered};
+use crate::commands::inbox::{Group, Item, ItemKind};
+use crate::commands::ingest::{Proposal, Source};
+use crate::commands::list::{GoalRows, GoalWithTasks};
+use crate::commands::milestone::MilestoneStatus;
+use crate::commands::open::{Opener, Target};
+use crate::commands::postmortems::CategoryGroup;
+use crate::commands::projects::ProjectSummary;
+use crate::commands::quota::AgentUsage;
+use crate::commands::ready::{NotReady, NotReadyReason, ReadyTask};
+use crate::commands::show::{ArtifactLink, Backlink, LinkSource, ShowResult};
+use crate::commands::stats::{Activity, GroupBy, ModelStats, TokenStats};
+use crate::commands::status::{
+    AgentStatus, GoalNode, GoalRows as GoalStatusRows, GoalSummary, GoalTree, StaleTask,
+    StatusResult,
+};
+use crate::commands::suggest::Suggestion;
+use crate::commands::sync::SyncReport;
+use crate::commands::task::{
+    CompleteResult, ExecResult, FailResult, RetryResult, StartResult, VerifyResult,
+};
+use crate::commands::toolspec::{Tool, ToolFormat};
+use crate::commands::validate::{Severity, Validation};
+use crate::commands::watch::{Event, EventKind};
+use crate::commands::wip::Wip;
+use crate::config::StateColor;
+use crate::error::Error;
+use crate::helpers::shell_quote;
+use crate::markdown;
+use crate::models::state_machine::describe_next;
+use crate::models::{Criterion, Goal, GoalState, RefKind, Task, TaskState, TaskSummary};
+use crate::registry::Project;
+use crate::sandbox::{Run, Sandbox};
 
 /// Trait for types that can render themselves as human-readable CLI output.
 pub trait Render {
     fn render(&self, w: &mut dyn Write) -> Result<()>;
 }
 
+/// Print a command's result as JSON if `json` is true, otherwise as text.
+pub fn emit<T: Serialize + Render>(value: &T, json: bool) -> Result<()> {
+    json_or(value, json, |w| value.render(w))
+}
+
 /// Print as JSON if `json` is true, otherwise call `human` with a writer.
 fn json_or<T: Serialize + ?Sized>(
     value: &T,
@@ -22,8 +71,10 @@ fn json_or<T: Serialize + ?Sized>(
     human: impl FnOnce(&mut dyn Write) -> Result<()>,
 ) -> Result<()> {
     let mut stdout = io::stdout().lock();
-    if json {
-        serde_json::to_writer_pretty(&mut stdout, value)?;
+    if let Some(template) = &options().template {
+        fill_template(&mut stdout, template, &selected(value)?)?;
+    } else if json {
+        serde_json::to_writer_pretty(&mut stdout, &selected(value)?)?;
         writeln!(stdout)?;
     } else {
         human(&mut stdout)?;
@@ -31,13 +82,171 @@ fn json_or<T: Serialize + ?Sized>(
     Ok(())
 }
 
-/// Truncate a string to the first line, capping at `max` characters.
+/// `value` as JSON, cut down to the `--fields` asked for.
+fn selected<T: Serialize + ?Sized>(value: &T) -> Result<Value> {
+    let value = serde_json::to_value(value)?;
+    let fields = &options().fields;
+    if fields.is_empty() {
+        return Ok(value);
+    }
+    let paths: Vec<Vec<&str>> = fields
+        .iter()
+        .map(|f| f.trim().split('.').collect())
+        .collect();
+    Ok(project(&value, &paths))
+}
+
+/// Write each item of `value` (or `value` itself, if it isn't a list) on its
+/// own line, with every `{{path}}` in `template` replaced by that field.
+/// Strings are written bare, missing fields as nothing, and anything else
+/// as JSON. `\t` and `\n` in the template stand for a tab and a newline.
+fn fill_template(w: &mut dyn Write, template: &str, value: &Value) -> Result<()> {
+    let template = template.replace("\\t", "\t").replace("\\n", "\n");
+    let items = match value {
+        Value::Array(items) => items.as_slice(),
+        other => std::slice::from_ref(other),
+    };
+    for item in items {
+        let mut line = String::new();
+        let mut rest = template.as_str();
+        while let Some(start) = rest.find("{{") {
+            let Some(len) = rest[start..].find("}}") else {
+                break;
+            };
+            line.push_str(&rest[..start]);
+            let path = rest[start + 2..start + len].trim();
+            match path.split('.').try_fold(item, |v, key| match v {
+                Value::Array(items) => items.get(key.parse::<usize>().ok()?),
+                _ => v.get(key),
+            }) {
+                None | Some(Value::Null) => {}
+                Some(Value::String(s)) => line.push_str(s),
+                Some(other) => line.push_str(&other.to_string()),
+            }
+            rest = &rest[start + len + 2..];
+        }
+        line.push_str(rest);
+        writeln!(w, "{line}")?;
+    }
+    Ok(())
+}
+
+/// Keep only `paths` of `value`. Arrays are projected element by element,
+/// at the top or anywhere along a path, so `--fields id` works on a list of
+/// tasks and `tasks.id` on a goal holding them. Fields a value doesn't have
+/// are left out.
+fn project(value: &Value, paths: &[Vec<&str>]) -> Value {
+    match value {
+        Value::Array(items) => Value::Array(items.iter().map(|v| project(v, paths)).collect()),
+        Value::Object(map) => {
+            let mut out = serde_json::Map::new();
+            for key in paths.iter().filter_map(|p| p.first()) {
+                let Some(child) = map.get(*key) else {
+                    continue;
+                };
+                if out.contains_key(*key) {
+                    continue;
+                }
+                let rests: Vec<Vec<&str>> = paths
+                    .iter()
+                    .filter(|p| p.first() == Some(key))
+                    .map(|p| p[1..].to_vec())
+                    .collect();
+                let projected = if rests.iter().any(Vec::is_empty) {
+                    child.clone()
+                } else {
+                    project(child, &rests)
+                };
+                out.insert((*key).to_string(), projected);
+            }
+            Value::Object(out)
+        }
+        other => other.clone(),
+    }
+}
+
+/// Line width used when stdout is not a terminal and no width is configured.
+const DEFAULT_LINE_WIDTH: usize = 100;
+
+/// Never squeeze a truncated column below this many columns.
+const MIN_COLUMN_WIDTH: usize = 20;
+
+/// Display settings for the current invocation.
+#[derive(Debug, Clone, Default)]
+pub struct OutputOptions {
+    /// Disable truncation entirely.
+    pub full: bool,
+    /// Fixed line width, overriding terminal detection.
+    pub width: Option<usize>,
+    /// Show times in UTC rather than the local time zone.
+    pub utc: bool,
+    /// Don't draw progress bars, even on a terminal.
+    pub no_progress: bool,
+    /// Field paths to keep in JSON output; all of them when empty.
+    pub fields: Vec<String>,
+    /// Format string each result is printed through instead.
+    pub template: Option<String>,
+    /// Colors of the custom states in `[states]`.
+    pub state_colors: Vec<(String, StateColor)>,
+}
+
+static OPTIONS: OnceLock<OutputOptions> = OnceLock::new();
+
+/// Whether a command run with `json` prints whole records, as JSON or
+/// through `--template`, rather than a table.
+pub fn structured(json: bool) -> bool {
+    json || options().template.is_some()
+}
+
+/// Set the display options for this process. Only the first call takes effect.
+pub fn configure(options: OutputOptions) {
+    let _ = OPTIONS.set(options);
+}
+
+fn options() -> &'static OutputOptions {
+    static DEFAULT: OutputOptions = OutputOptions {
+        full: false,
+        width: None,
+        utc: false,
+        no_progress: false,
+        fields: Vec::new(),
+        template: None,
+        state_colors: Vec::new(),
+    };
+    OPTIONS.get().unwrap_or(&DEFAULT)
+}
+
+pub(crate) fn no_progress() -> bool {
+    options().no_progress
+}
+
+/// Total line width: configured, else the terminal's, else a fixed default.
+fn line_width() -> usize {
+    options().width.unwrap_or_else(|| {
+        Term::stdout()
+            .size_checked()
+            .map_or(DEFAULT_LINE_WIDTH, |(_, cols)| usize::from(cols))
+    })
+}
+
+/// Fit text into whatever remains of the line after `used` columns.
+/// With `--full`, every line is kept and joined instead.
+pub(crate) fn fit(s: &str, used: usize) -> String {
+    if options().full {
+        return s.lines().collect::<Vec<_>>().join(" ");
+    }
+    let max = line_width().saturating_sub(used).max(MIN_COLUMN_WIDTH);
+    truncate(s, max)
+}
+
+/// Truncate a string to the first line, capping at `max` display columns.
+/// Measures by display width so multibyte text is never split mid-character.
 fn truncate(s: &str, max: usize) -> String {
     let first_line = s.lines().next().unwrap_or(s);
-    if first_line.len() <= max {
+    if console::measure_text_width(first_line) <= max {
         first_line.to_string()
     } else {
-        format!("{}…", &first_line[..max - 1])
+        console::truncate_str(first_line, max, "…").into_owned()
     }
 }
 
@@ -51,7 +260,183 @@ pub fn goal_created(goal: &Goal, json: bool) -> Result<()> {
             style("Created goal:").green(),
             style(goal.id()).cyan().bold()
         )?;
-        writeln!(w, "  {}", truncate(goal.description(), 80))?;
+        writeln!(w, "  {}", fit(goal.description(), 2))?;
+        if let Some(branch) = goal.branch() {
+            writeln!(w, "  {} {branch}", style("Branch:").dim())?;
+        }
+        if let Some(project) = goal.project() {
+            writeln!(w, "  {} {project}", style("Project:").dim())?;
+        }
+        if let Some(milestone) = goal.milestone() {
+            writeln!(w, "  {} {milestone}", style("Milestone:").dim())?;
+        }
+        Ok(())
+    })
+}
+
+pub fn goal_archived(report: &ArchiveReport, json: bool) -> Result<()> {
+    json_or(report, json, |w| {
+        writeln!(
+            w,
+            "{} {}",
+            style("Archived goal:").green(),
+            style(report.goal.id()).cyan().bold()
+        )?;
+        writeln!(w, "  {}", fit(report.goal.headline(), 2))?;
+        writeln!(
+            w,
+            "  {} task(s) written to {}",
+            report.task_count,
+            report.path.display()
+        )?;
+        Ok(())
+    })
+}
+
+pub fn goal_reopened(report: &ReopenReport, json: bool) -> Result<()> {
+    json_or(report, json, |w| {
+        writeln!(
+            w,
+            "{} {}",
+            style("Reopened goal:").green(),
+            style(report.goal.id()).cyan().bold()
+        )?;
+        writeln!(w, "  {}", fit(report.goal.headline(), 2))?;
+        if !report.reset_task_ids.is_empty() {
+            writeln!(
+                w,
+                "  Reset to pending: {}",
+                report.reset_task_ids.join(", ")
+            )?;
+        }
+        if !report.blocked_task_ids.is_empty() {
+            writeln!(w, "  Still blocked: {}", report.blocked_task_ids.join(", "))?;
+        }
+        if report.ready_task_ids.is_empty() {
+            writeln!(w, "  No tasks ready")?;
+        } else {
+            writeln!(w, "  Ready: {}", report.ready_task_ids.join(", "))?;
+        }
+        Ok(())
+    })
+}
+
+pub fn goal_merged(report: &MergeReport, json: bool) -> Result<()> {
+    json_or(report, json, |w| {
+        writeln!(
+            w,
+            "{} {} {} {}",
+            style("Merged goal").green(),
+            style(&report.merged_goal_id).cyan().bold(),
+            style("into").green(),
+            style(report.goal.id()).cyan().bold()
+        )?;
+        writeln!(w, "  {}", fit(report.goal.headline(), 2))?;
+        writeln!(w, "  {} task(s) moved", report.moved_task_ids.len())?;
+        if !report.reparented_goal_ids.is_empty() {
+            writeln!(
+                w,
+                "  Sub-goals moved: {}",
+                report.reparented_goal_ids.join(", ")
+            )?;
+        }
+        writeln!(
+            w,
+            "  {} archived to {}",
+            report.merged_goal_id,
+            report.archive_path.display()
+        )?;
+        Ok(())
+    })
+}
+
+pub fn goal_restored(archived: &ArchivedGoal, json: bool) -> Result<()> {
+    json_or(archived, json, |w| {
+        writeln!(
+            w,
+            "{} {}",
+            style("Restored goal:").green(),
+            style(archived.goal.id()).cyan().bold()
+        )?;
+        writeln!(w, "  {}", fit(archived.goal.headline(), 2))?;
+        writeln!(w, "  {} task(s) restored", archived.tasks.len())?;
+        Ok(())
+    })
+}
+
+/// A bundle from `export --goal`, printed as JSON.
+pub fn bundle(bundle: &Bundle) -> Result<()> {
+    json_or(bundle, true, |_| Ok(()))
+}
+
+pub fn bundle_written(report: &ExportReport, json: bool) -> Result<()> {
+    json_or(report, json, |w| {
+        writeln!(
+            w,
+            "{} {}",
+            style("Exported goal:").green(),
+            style(report.goal.id()).cyan().bold()
+        )?;
+        writeln!(w, "  {}", fit(report.goal.headline(), 2))?;
+        writeln!(
+            w,
+            "  {} goal(s), {} task(s), {} attachment(s) written to {}",
+            report.goal_count,
+            report.task_count,
+            report.attachment_count,
+            report.path.display()
+        )?;
+        Ok(())
+    })
+}
+
+pub fn bundle_imported(report: &ImportReport, json: bool) -> Result<()> {
+    json_or(report, json, |w| {
+        writeln!(
+            w,
+            "{} {}",
+            style("Imported goal:").green(),
+            style(report.goal.id()).cyan().bold()
+        )?;
+        writeln!(w, "  {}", fit(report.goal.headline(), 2))?;
+        writeln!(
+            w,
+            "  {} goal(s), {} task(s), {} attachment(s) added under new IDs",
+            report.goal_count, report.task_count, report.attachment_count
+        )?;
+        for (old, new) in &report.ids {
+            writeln!(
+                w,
+                "  {} {} {}",
+                style(old).dim(),
+                style("->").dim(),
+                style(new).cyan()
+            )?;
+        }
+        Ok(())
+    })
+}
+
+/// A goal after `goal claim` or `goal release`.
+pub fn goal_claim(goal: &Goal, json: bool) -> Result<()> {
+    json_or(goal, json, |w| {
+        match goal.claim(Timestamp::now()) {
+            Some(claim) => writeln!(
+                w,
+                "{} {} by {} until {}",
+                style("Claimed goal:").green(),
+                style(goal.id()).cyan().bold(),
+                style(claim.agent()).bold(),
+                format_time(claim.expires_at())
+            )?,
+            None => writeln!(
+                w,
+                "{} {}",
+                style("Released goal:").green(),
+                style(goal.id()).cyan().bold()
+            )?,
+        }
+        writeln!(w, "  {}", fit(goal.headline(), 2))?;
         Ok(())
     })
 }
@@ -77,7 +462,7 @@ pub fn goal_list(goals: &[Goal], json: bool) -> Result<()> {
                 "{:<10} {:<13} {}",
                 style(goal.id()).cyan(),
                 state_styled(goal.state().as_ref()),
-                truncate(goal.description(), 80),
+                fit(goal.headline(), 25),
             )?;
         }
         Ok(())
@@ -94,24 +479,38 @@ pub fn goal_edited(goal: &Goal) -> Result<()> {
         style("Updated goal:").green(),
         style(goal.id()).cyan().bold()
     )?;
-    writeln!(w, "  {}", truncate(goal.description(), 80))?;
+    writeln!(w, "  {}", fit(goal.description(), 2))?;
     Ok(())
 }
 
-pub fn task_edited(task: &Task) -> Result<()> {
-    let mut w = io::stdout().lock();
-    writeln!(
-        w,
-        "{} {}",
-        style("Updated task:").green(),
-        style(task.id()).cyan().bold()
-    )?;
-    writeln!(w, "  {}", truncate(task.description(), 80))?;
-    Ok(())
+pub fn task_edited(task: &Task, json: bool) -> Result<()> {
+    json_or(task, json, |w| {
+        writeln!(
+            w,
+            "{} {}",
+            style("Updated task:").green(),
+            style(task.id()).cyan().bold()
+        )?;
+        writeln!(w, "  {}", fit(task.description(), 2))?;
+        Ok(())
+    })
 }
 
 // -- Task outputs --
 
+pub fn task_moved(task: &Task, json: bool) -> Result<()> {
+    json_or(task, json, |w| {
+        writeln!(
+            w,
+            "{} {} [{}]",
+            style("Moved task:").green(),
+            style(task.id()).cyan().bold(),
+            task_state(task)
+        )?;
+        Ok(())
+    })
+}
+
 pub fn task_created(task: &Task, json: bool) -> Result<()> {
     json_or(task, json, |w| {
         writeln!(
@@ -120,8 +519,8 @@ pub fn task_created(task: &Task, json: bool) -> Result<()> {
             style("Created task:").green(),
             style(task.id()).cyan().bold()
         )?;
-        writeln!(w, "  {}", truncate(task.description(), 80))?;
-        writeln!(w, "  State: {}", state_styled(task.state().as_ref()))?;
+        writeln!(w, "  {}", fit(task.description(), 2))?;
+        writeln!(w, "  State: {}", task_state(task))?;
         if task.contract().is_none() {
             writeln!(
                 w,
@@ -133,44 +532,60 @@ pub fn task_created(task: &Task, json: bool) -> Result<()> {
     })
 }
 
-pub fn task_list(tasks: &[Task], goal: &Goal, verbose: bool, json: bool) -> Result<()> {
+/// Tasks of one goal, or with `goal` unset, matches from across goals,
+/// each shown with the goal it belongs to.
+pub fn task_list(tasks: &[Task], goal: Option<&Goal>, verbose: bool, json: bool) -> Result<()> {
     json_or(tasks, json, |w| {
-        writeln!(
-            w,
-            "Tasks for {} [{}]",
-            style(goal.id()).cyan().bold(),
-            state_styled(goal.state().as_ref()),
-        )?;
-        writeln!(w, "  {}", truncate(goal.description(), 80))?;
-        writeln!(w)?;
+        if let Some(goal) = goal {
+            writeln!(
+                w,
+                "Tasks for {} [{}]",
+                style(goal.id()).cyan().bold(),
+                state_styled(goal.state().as_ref()),
+            )?;
+            writeln!(w, "  {}", fit(goal.description(), 2))?;
+            writeln!(w)?;
+        }
 
         if tasks.is_empty() {
             writeln!(w, "No tasks found.")?;
             return Ok(());
         }
 
+        let goal_column = if goal.is_none() { 11 } else { 0 };
+        write!(w, "{:<10} ", style("ID").bold().underlined())?;
+        if goal.is_none() {
+            write!(w, "{:<10} ", style("GOAL").bold().underlined())?;
+        }
         writeln!(
             w,
-            "{:<10} {:<13} {}",
-            style("ID").bold().underlined(),
+            "{:<13} {}",
             style("STATE").bold().underlined(),
             style("DESCRIPTION").bold().underlined(),
         )?;
         for task in tasks {
+            write!(w, "{:<10} ", style(task.id()).cyan())?;
+            if goal.is_none() {
+                write!(w, "{:<10} ", style(task.goal_id()).dim())?;
+            }
             writeln!(
                 w,
-                "{:<10} {:<13} {}",
-                style(task.id()).cyan(),
-                state_styled(task.state().as_ref()),
-                truncate(task.description(), 80),
+                "{:<13} {}",
+                task_state(task),
+                fit(task.headline(), 25 + goal_column),
             )?;
             if verbose && !task.comments().is_empty() {
                 for comment in task.comments() {
+                    let created_at = format_time(comment.created_at());
+                    let text = match comment.progress() {
+                        Some(percent) => format!("{percent}% {}", comment.text()),
+                        None => comment.text().to_string(),
+                    };
                     writeln!(
                         w,
                         "           {}  {}",
-                        style(comment.created_at()).dim(),
-                        truncate(comment.text(), 60),
+                        style(&created_at).dim(),
+                        fit(&text, 13 + created_at.chars().count()),
                     )?;
                 }
             }
@@ -179,143 +594,518 @@ pub fn task_list(tasks: &[Task], goal: &Goal, verbose: bool, json: bool) -> Resu
     })
 }
 
-pub fn task_started(task: &Task) -> Result<()> {
-    let mut w = io::stdout().lock();
-    writeln!(
-        w,
-        "{} {}",
-        style("Started task:").green(),
-        style(task.id()).cyan().bold()
-    )?;
-    writeln!(w, "  {}", truncate(task.description(), 80))?;
-    Ok(())
-}
-
-pub fn task_completed(result: &CompleteResult) -> Result<()> {
-    let mut w = io::stdout().lock();
-    writeln!(
-        w,
-        "{} {}",
-        style("Completed task:").green(),
-        style(result.task.id()).cyan().bold()
-    )?;
-    if let Some(res) = result.task.result() {
-        writeln!(w, "  {}", truncate(res.summary(), 80))?;
-    }
-
-    if !result.unblocked_task_ids.is_empty() {
-        writeln!(w)?;
-        writeln!(w, "{}", style("Unblocked tasks:").yellow())?;
-        for id in &result.unblocked_task_ids {
-            writeln!(w, "  - {}", style(id).cyan())?;
+impl Render for StartResult {
+    fn render(&self, w: &mut dyn Write) -> Result<()> {
+        writeln!(
+            w,
+            "{} {}",
+            style("Started task:").green(),
+            style(self.task.id()).cyan().bold()
+        )?;
+        writeln!(w, "  {}", fit(self.task.description(), 2))?;
+        if let Some(agent) = &self.assigned_to {
+            writeln!(w, "  {} {agent}", style("Assigned to:").dim())?;
         }
+        Ok(())
     }
-    Ok(())
-}
-
-pub fn task_failed(task: &Task) -> Result<()> {
-    let mut w = io::stdout().lock();
-    writeln!(
-        w,
-        "{} {}",
-        style("Failed task:").red(),
-        style(task.id()).cyan().bold()
-    )?;
-    writeln!(w, "  {}", truncate(task.description(), 80))?;
-    Ok(())
-}
-
-pub fn task_retry(task: &Task) -> Result<()> {
-    let mut w = io::stdout().lock();
-    writeln!(
-        w,
-        "{} {}",
-        style("Retrying task:").yellow(),
-        style(task.id()).cyan().bold()
-    )?;
-    writeln!(w, "  {}", truncate(task.description(), 80))?;
-    writeln!(w, "  Retry count: {}", task.metrics().retry_count())?;
-    Ok(())
 }
 
-pub fn task_commented(task: &Task, json: bool) -> Result<()> {
-    json_or(task, json, |w| {
+impl Render for CompleteResult {
+    fn render(&self, w: &mut dyn Write) -> Result<()> {
         writeln!(
             w,
             "{} {}",
-            style("Added comment to task:").green(),
-            style(task.id()).cyan().bold()
+            style("Completed task:").green(),
+            style(self.task.id()).cyan().bold()
         )?;
-        if let Some(comment) = task.comments().last() {
-            writeln!(w, "  {}", truncate(comment.text(), 80))?;
+        if let Some(res) = self.task.result() {
+            writeln!(w, "  {}", fit(res.summary(), 2))?;
         }
-        writeln!(w, "  Total comments: {}", task.comments().len())?;
-        Ok(())
-    })
-}
 
-// -- Status outputs (compact) --
+        if !self.unblocked_task_ids.is_empty() {
+            writeln!(w)?;
+            writeln!(w, "{}", style("Unblocked tasks:").yellow())?;
+            for id in &self.unblocked_task_ids {
+                writeln!(w, "  - {}", style(id).cyan())?;
+            }
+        }
 
-pub fn status(result: &StatusResult, json: bool) -> Result<()> {
-    match result {
-        StatusResult::Task(task) => status_task(task, json),
-        StatusResult::Goal(goal_status) => status_goal(goal_status, json),
-        StatusResult::AllGoals(summaries) => status_all_goals(summaries, json),
+        if !self.follow_ups.is_empty() {
+            writeln!(w)?;
+            writeln!(w, "{}", style("Follow-up tasks:").yellow())?;
+            for task in &self.follow_ups {
+                writeln!(
+                    w,
+                    "  - {} {}",
+                    style(task.id()).cyan(),
+                    fit(task.headline(), task.id().len() + 5)
+                )?;
+            }
+            writeln!(
+                w,
+                "  Give them a contract with: rd edit task <task_id> --receives ... --produces ... --verify ..."
+            )?;
+        }
+
+        if self.goal_state == GoalState::Completed {
+            writeln!(w)?;
+            writeln!(
+                w,
+                "{} {}",
+                style("Goal completed:").green(),
+                style(self.task.goal_id()).cyan()
+            )?;
+        }
+        Ok(())
     }
 }
 
-fn status_task(task: &Task, json: bool) -> Result<()> {
-    json_or(task, json, |w| {
+impl Render for FailResult {
+    fn render(&self, w: &mut dyn Write) -> Result<()> {
         writeln!(
             w,
-            "{:<10} {:<13} {}",
-            style(task.id()).cyan(),
-            state_styled(task.state().as_ref()),
-            truncate(task.description(), 80),
+            "{} {}",
+            style("Failed task:").red(),
+            style(self.task.id()).cyan().bold()
         )?;
+        writeln!(w, "  {}", fit(self.task.description(), 2))?;
+        if !self.waiting_task_ids.is_empty() {
+            writeln!(
+                w,
+                "  {} {}",
+                style("Still waiting on it:").dim(),
+                self.waiting_task_ids.join(", ")
+            )?;
+        }
         Ok(())
-    })
+    }
 }
 
-fn status_goal(goal_status: &crate::commands::status::GoalStatus, json: bool) -> Result<()> {
-    json_or(goal_status, json, |w| {
-        let goal = goal_status.goal();
-        let metrics = goal_status.metrics();
+/// Lines of a failed command's log shown after the summary.
+const LOG_TAIL: usize = 10;
 
-        writeln!(
-            w,
-            "Goal: {}  {}  ({}/{} tasks)",
-            style(goal.id()).cyan().bold(),
-            state_styled(goal.state().as_ref()),
-            metrics.tasks_completed(),
-            metrics.task_count(),
+impl Render for VerifyResult {
+    fn render(&self, w: &mut dyn Write) -> Result<()> {
+        command_run(w, "Verify", &self.task_id, &self.run)
+    }
+}
+
+impl Render for ExecResult {
+    fn render(&self, w: &mut dyn Write) -> Result<()> {
+        if self.started {
+            writeln!(
+                w,
+                "{} {}",
+                style("Started task:").green(),
+                style(&self.task_id).cyan().bold()
+            )?;
+        }
+        command_run(w, "Command", &self.task_id, &self.run)?;
+        if let Some(completed) = &self.completed {
+            writeln!(w)?;
+            completed.render(w)?;
+        }
+        if let Some(failed) = &self.failed {
+            writeln!(w)?;
+            failed.render(w)?;
+        }
+        Ok(())
+    }
+}
+
+/// How a command run for a task went, with the end of its log if it failed.
+fn command_run(w: &mut dyn Write, label: &str, task_id: &str, run: &Run) -> Result<()> {
+    let (mark, outcome) = if run.passed {
+        (style("✓").green(), "passed".to_string())
+    } else if run.timed_out {
+        (style("✗").red(), "timed out".to_string())
+    } else {
+        let code = run
+            .exit_code
+            .map_or_else(|| "killed".to_string(), |c| format!("exit {c}"));
+        (style("✗").red(), format!("failed ({code})"))
+    };
+    writeln!(
+        w,
+        "{mark} {label} {outcome}: {}",
+        style(task_id).cyan().bold()
+    )?;
+    field(w, "Command", &run.command)?;
+    if run.sandbox != Sandbox::None {
+        field(w, "Sandbox", &run.sandbox.to_string())?;
+    }
+    field(w, "Log", &run.log.display().to_string())?;
+
+    if !run.passed {
+        let log = std::fs::read_to_string(&run.log).unwrap_or_default();
+        let lines: Vec<&str> = log.lines().collect();
+        if !lines.is_empty() {
+            writeln!(w)?;
+        }
+        for line in &lines[lines.len().saturating_sub(LOG_TAIL)..] {
+            writeln!(w, "  {}", style(line).dim())?;
+        }
+    }
+    Ok(())
+}
+
+impl Render for RetryResult {
+    fn render(&self, w: &mut dyn Write) -> Result<()> {
+        let task = &self.task;
+        writeln!(
+            w,
+            "{} {}",
+            style("Retrying task:").yellow(),
+            style(task.id()).cyan().bold()
         )?;
-        writeln!(w, "  {}", truncate(goal.description(), 80))?;
-        writeln!(w)?;
+        writeln!(w, "  {}", fit(task.description(), 2))?;
+        writeln!(w, "  Retry count: {}", task.metrics().retry_count())?;
+        if !task.revisions().is_empty() {
+            writeln!(
+                w,
+                "  Earlier revisions: {} (see rd show {})",
+                task.revisions().len(),
+                task.id()
+            )?;
+        }
+        Ok(())
+    }
+}
+
+pub fn task_commented(task: &Task, json: bool) -> Result<()> {
+    json_or(task, json, |w| {
+        writeln!(
+            w,
+            "{} {}",
+            style("Added comment to task:").green(),
+            style(task.id()).cyan().bold()
+        )?;
+        if let Some(comment) = task.comments().last() {
+            writeln!(w, "  {}", fit(comment.text(), 2))?;
+            field(w, "  Comment", comment.id())?;
+            if let Some(parent) = comment.reply_to() {
+                field(w, "  Reply to", parent)?;
+            }
+        }
+        writeln!(w, "  Total comments: {}", task.comments().len())?;
+        Ok(())
+    })
+}
+
+pub fn task_progress(task: &Task, json: bool) -> Result<()> {
+    json_or(task, json, |w| {
+        writeln!(
+            w,
+            "{} {} {}%",
+            style("Progress on task:").green(),
+            style(task.id()).cyan().bold(),
+            task.progress().unwrap_or_default()
+        )?;
+        if let Some(comment) = task.comments().last()
+            && !comment.text().is_empty()
+        {
+            writeln!(w, "  {}", fit(comment.text(), 2))?;
+        }
+        Ok(())
+    })
+}
+
+/// ` (60%)` for an in-progress task that has reported progress.
+fn progress_note(task: &Task) -> String {
+    summary_progress(&task.summary())
+}
 
-        if !goal_status.tasks().is_empty() {
+fn summary_progress(task: &TaskSummary) -> String {
+    match task.progress {
+        Some(percent) if *task.state == TaskState::InProgress => format!(" ({percent}%)"),
+        _ => String::new(),
+    }
+}
+
+pub fn checklist(task: &Task, json: bool) -> Result<()> {
+    json_or(task.checklist(), json, |w| {
+        let done = task.checklist().iter().filter(|i| i.done()).count();
+        writeln!(
+            w,
+            "Checklist for {} ({done}/{})",
+            style(task.id()).cyan().bold(),
+            task.checklist().len()
+        )?;
+        if task.checklist().is_empty() {
             writeln!(
                 w,
-                "{:<10} {:<13} {}",
-                style("ID").bold().underlined(),
-                style("STATE").bold().underlined(),
-                style("DESCRIPTION").bold().underlined(),
+                "  No items. Add one with: rd task check add {} <text>",
+                task.id()
             )?;
-            for task in goal_status.tasks() {
-                writeln!(
-                    w,
-                    "{:<10} {:<13} {}",
-                    style(task.id()).cyan(),
-                    state_styled(task.state().as_ref()),
-                    truncate(task.description(), 80),
-                )?;
+            return Ok(());
+        }
+        checklist_items(w, task)
+    })
+}
+
+/// A task's context entries.
+pub fn ctx(task: &Task, json: bool) -> Result<()> {
+    json_or(task.ctx(), json, |w| {
+        writeln!(w, "Context for {}", style(task.id()).cyan().bold())?;
+        if task.ctx().is_empty() {
+            writeln!(
+                w,
+                "  No entries. Add one with: rd task ctx set {} <key> <value>",
+                task.id()
+            )?;
+            return Ok(());
+        }
+        ctx_entries(w, task)
+    })
+}
+
+/// One context value, printed bare so scripts can capture it.
+pub fn ctx_value(value: &str, json: bool) -> Result<()> {
+    json_or(value, json, |w| {
+        writeln!(w, "{value}")?;
+        Ok(())
+    })
+}
+
+fn ctx_entries(w: &mut dyn Write, task: &Task) -> Result<()> {
+    for (key, value) in task.ctx() {
+        field(w, &format!("  {key}"), value)?;
+    }
+    Ok(())
+}
+
+/// Numbered `[x]`/`[ ]` lines, numbered as `task check tick` expects.
+/// A goal's acceptance criteria.
+pub fn criteria(goal: &Goal, json: bool) -> Result<()> {
+    json_or(goal.criteria(), json, |w| {
+        let met = goal.criteria().iter().filter(|c| c.met()).count();
+        writeln!(
+            w,
+            "Acceptance criteria for {} ({met}/{})",
+            style(goal.id()).cyan().bold(),
+            goal.criteria().len()
+        )?;
+        if goal.criteria().is_empty() {
+            writeln!(
+                w,
+                "  None. Add one with: rd goal criterion add {} <text> [--command <cmd>]",
+                goal.id()
+            )?;
+            return Ok(());
+        }
+        criteria_items(w, goal.criteria().iter())
+    })
+}
+
+fn criteria_items<'a>(
+    w: &mut dyn Write,
+    criteria: impl Iterator<Item = &'a Criterion>,
+) -> Result<()> {
+    for (i, criterion) in criteria.enumerate() {
+        let number = format!("{:>3}.", i + 1);
+        let mark = if criterion.met() {
+            style("[x]").green()
+        } else {
+            style("[ ]").dim()
+        };
+        writeln!(
+            w,
+            "{number} {mark} {}",
+            fit(criterion.text(), number.len() + 5)
+        )?;
+        if let Some(command) = criterion.command() {
+            writeln!(w, "         {}", style(fit(command, 9)).dim())?;
+        }
+    }
+    Ok(())
+}
+
+impl Render for GoalVerification {
+    fn render(&self, w: &mut dyn Write) -> Result<()> {
+        let (mark, outcome) = if self.passed {
+            (style("✓").green(), "accepted")
+        } else {
+            (style("✗").red(), "not done")
+        };
+        writeln!(
+            w,
+            "{mark} Goal {outcome}: {}  [{}]",
+            style(&self.goal_id).cyan().bold(),
+            state_styled(self.goal_state.as_ref()),
+        )?;
+        if !self.criteria.is_empty() {
+            writeln!(w)?;
+            criteria_items(w, self.criteria.iter().map(|c| &c.criterion))?;
+        }
+        for check in &self.criteria {
+            if let Some(run) = check.run.as_ref().filter(|r| !r.passed) {
+                writeln!(w)?;
+                command_run(w, "Criterion", &check.number.to_string(), run)?;
             }
         }
+        if !self.open_task_ids.is_empty() {
+            writeln!(w)?;
+            field(w, "Open tasks", &self.open_task_ids.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+fn checklist_items(w: &mut dyn Write, task: &Task) -> Result<()> {
+    for (i, item) in task.checklist().iter().enumerate() {
+        let number = format!("{:>3}.", i + 1);
+        let mark = if item.done() {
+            style("[x]").green()
+        } else {
+            style("[ ]").dim()
+        };
+        writeln!(w, "{number} {mark} {}", fit(item.text(), number.len() + 5))?;
+    }
+    Ok(())
+}
+
+// -- Status outputs (compact) --
+
+pub fn status(result: &StatusResult, json: bool) -> Result<()> {
+    match result {
+        StatusResult::Task(task) => status_task(task, json),
+        StatusResult::Goal(goal_status) => status_goal(goal_status, json),
+        StatusResult::AllGoals(summaries, wip) => status_all_goals(summaries, wip, json),
+        StatusResult::Agent(agent_status) => status_agent(agent_status, json),
+        StatusResult::Stale(tasks) => status_stale(tasks, json),
+        StatusResult::Tree(tree) => status_tree(tree, json),
+    }
+}
+
+fn status_tree(tree: &GoalTree, json: bool) -> Result<()> {
+    json_or(tree, json, |w| {
+        if tree.roots.is_empty() {
+            writeln!(w, "No goals found.")?;
+            return Ok(());
+        }
+
+        let program = &tree.program;
+        writeln!(
+            w,
+            "{} {} goal(s), {}/{} tasks ({}%), {} failed",
+            style("Program:").bold(),
+            program.goals,
+            program.completed,
+            program.tasks,
+            program.percent(),
+            program.failed,
+        )?;
+        writeln!(w)?;
+
+        for root in &tree.roots {
+            goal_node(w, root, "", "")?;
+        }
+        Ok(())
+    })
+}
+
+/// One goal line, then its children indented with box-drawing guides.
+fn goal_node(w: &mut dyn Write, node: &GoalNode, lead: &str, rest: &str) -> Result<()> {
+    let goal = node.goal();
+    let rollup = node.rollup();
+    let progress = format!("{}/{}", rollup.completed, rollup.tasks);
+    writeln!(
+        w,
+        "{lead}{} {:<13} {:<7} {}",
+        style(goal.id()).cyan(),
+        state_styled(goal.state().as_ref()),
+        progress,
+        fit(
+            goal.headline(),
+            console::measure_text_width(lead) + goal.id().len() + 23
+        ),
+    )?;
+
+    let count = node.children().len();
+    for (i, child) in node.children().iter().enumerate() {
+        let (branch, guide) = if i + 1 == count {
+            ("└── ", "    ")
+        } else {
+            ("├── ", "│   ")
+        };
+        goal_node(
+            w,
+            child,
+            &format!("{rest}{branch}"),
+            &format!("{rest}{guide}"),
+        )?;
+    }
+    Ok(())
+}
+
+fn status_task(task: &Task, json: bool) -> Result<()> {
+    json_or(task, json, |w| {
+        let progress = progress_note(task);
+        writeln!(
+            w,
+            "{:<10} {:<13} {}{}",
+            style(task.id()).cyan(),
+            task_state(task),
+            fit(task.headline(), 25 + progress.len()),
+            style(progress).cyan(),
+        )?;
         Ok(())
     })
 }
 
-fn status_all_goals(summaries: &[GoalSummary], json: bool) -> Result<()> {
+fn status_goal(goal_status: &crate::commands::status::GoalStatus, json: bool) -> Result<()> {
+    json_or(goal_status, json, |w| goal_table(w, &goal_status.rows()))
+}
+
+/// `rd status <goal>` as a table, from summary rows alone.
+pub fn status_goal_rows(rows: &GoalStatusRows) -> Result<()> {
+    goal_table(&mut io::stdout().lock(), rows)
+}
+
+fn goal_table(w: &mut dyn Write, rows: &GoalStatusRows) -> Result<()> {
+    let goal = rows.goal;
+    let metrics = &rows.metrics;
+
+    writeln!(
+        w,
+        "Goal: {}  {}  ({}/{} tasks)",
+        style(goal.id()).cyan().bold(),
+        state_styled(goal.state().as_ref()),
+        metrics.tasks_completed(),
+        metrics.task_count(),
+    )?;
+    writeln!(w, "  {}", fit(goal.description(), 2))?;
+    writeln!(w)?;
+
+    if !rows.tasks.is_empty() {
+        writeln!(
+            w,
+            "{:<10} {:<13} {}",
+            style("ID").bold().underlined(),
+            style("STATE").bold().underlined(),
+            style("DESCRIPTION").bold().underlined(),
+        )?;
+        for task in &rows.tasks {
+            let progress = summary_progress(task);
+            let note = rows
+                .stale_age(task.id)
+                .map(|age| format!(" (stale {})", format_age(age)))
+                .unwrap_or_default();
+            writeln!(
+                w,
+                "{:<10} {:<13} {}{}{}",
+                style(task.id).cyan(),
+                stage_styled(task.stage, task.label()),
+                fit(task.headline, 25 + progress.len() + note.len()),
+                style(progress).cyan(),
+                style(note).red(),
+            )?;
+        }
+    }
+    Ok(())
+}
+
+fn status_all_goals(summaries: &[GoalSummary], wip: &Wip, json: bool) -> Result<()> {
     json_or(summaries, json, |w| {
         if summaries.is_empty() {
             writeln!(w, "No goals found.")?;
@@ -339,48 +1129,414 @@ fn status_all_goals(summaries: &[GoalSummary], json: bool) -> Result<()> {
                 style(goal.id()).cyan(),
                 state_styled(goal.state().as_ref()),
                 format!("{}/{}", metrics.tasks_completed(), metrics.task_count()),
-                truncate(goal.description(), 80),
+                fit(goal.headline(), 33),
+            )?;
+        }
+
+        let stale: usize = summaries.iter().map(GoalSummary::stale_tasks).sum();
+        if stale > 0 {
+            writeln!(w)?;
+            writeln!(
+                w,
+                "{} {stale} task(s) in progress past the stale threshold. See: rd status --stale",
+                style("!").red().bold(),
             )?;
         }
+        if wip.limited() {
+            writeln!(w)?;
+            wip_usage(w, wip)?;
+        }
         Ok(())
     })
 }
 
-// -- Show outputs (full detail) --
-
-pub fn show(result: &ShowResult, json: bool) -> Result<()> {
-    match result {
-        ShowResult::Task(task) => show_task(task, json),
-        ShowResult::Goal {
-            goal,
-            tasks,
-            metrics,
-        } => show_goal(goal, tasks, metrics, json),
+/// Tasks in progress against the `[wip]` limits, flagging any over them.
+fn wip_usage(w: &mut dyn Write, wip: &Wip) -> Result<()> {
+    let limit = wip
+        .limit
+        .map_or_else(|| "no limit".to_string(), |l| format!("limit {l}"));
+    let mark = if wip.exceeded() {
+        style("!").red().bold()
+    } else {
+        style(" ")
+    };
+    writeln!(w, "{mark} WIP: {} in progress ({limit})", wip.in_progress)?;
+    for agent in wip.agents.iter().filter(|a| a.exceeded()) {
+        writeln!(
+            w,
+            "{} {} has {} in progress (limit {})",
+            style("!").red().bold(),
+            style(&agent.agent).cyan(),
+            agent.in_progress,
+            agent.limit.unwrap_or_default(),
+        )?;
     }
+    Ok(())
 }
 
-fn show_task(task: &Task, json: bool) -> Result<()> {
-    json_or(task, json, |w| {
+fn status_agent(agent_status: &AgentStatus, json: bool) -> Result<()> {
+    json_or(agent_status, json, |w| {
         writeln!(
             w,
-            "Task {}  [{}]",
-            style(task.id()).cyan().bold(),
-            state_styled(task.state().as_ref()),
+            "Agent: {}  ({} tasks)",
+            style(agent_status.agent()).cyan().bold(),
+            agent_status.tasks().len(),
         )?;
         writeln!(w)?;
 
-        writeln!(w, "{}", style("Description").bold())?;
-        for line in task.description().lines() {
-            writeln!(w, "  {line}")?;
+        if agent_status.tasks().is_empty() {
+            writeln!(w, "No tasks assigned.")?;
+            return Ok(());
         }
 
-        writeln!(w)?;
-        field(w, "Goal", task.goal_id())?;
-        field(w, "Created", &task.created_at().to_string())?;
-        field(w, "Updated", &task.updated_at().to_string())?;
+        writeln!(
+            w,
+            "{:<10} {:<10} {:<13} {}",
+            style("ID").bold().underlined(),
+            style("GOAL").bold().underlined(),
+            style("STATE").bold().underlined(),
+            style("DESCRIPTION").bold().underlined(),
+        )?;
+        for task in agent_status.tasks() {
+            let progress = progress_note(task);
+            writeln!(
+                w,
+                "{:<10} {:<10} {:<13} {}{}",
+                style(task.id()).cyan(),
+                style(task.goal_id()).dim(),
+                task_state(task),
+                fit(task.headline(), 36 + progress.len()),
+                style(progress).cyan(),
+            )?;
+        }
+        Ok(())
+    })
+}
 
-        // Contract
-        writeln!(w)?;
+fn status_stale(tasks: &[StaleTask], json: bool) -> Result<()> {
+    json_or(tasks, json, |w| {
+        if tasks.is_empty() {
+            writeln!(w, "No stale tasks.")?;
+            return Ok(());
+        }
+
+        writeln!(
+            w,
+            "{:<10} {:<10} {:<8} {:<12} {}",
+            style("ID").bold().underlined(),
+            style("GOAL").bold().underlined(),
+            style("AGE").bold().underlined(),
+            style("ASSIGNEE").bold().underlined(),
+            style("DESCRIPTION").bold().underlined(),
+        )?;
+        for stale in tasks {
+            let task = stale.task();
+            let progress = progress_note(task);
+            writeln!(
+                w,
+                "{:<10} {:<10} {:<8} {:<12} {}{}",
+                style(task.id()).cyan(),
+                style(task.goal_id()).dim(),
+                style(format_age(stale.age())).red(),
+                task.assignee().unwrap_or("-"),
+                fit(task.headline(), 44 + progress.len()),
+                style(progress).cyan(),
+            )?;
+        }
+        Ok(())
+    })
+}
+
+// -- Show outputs (full detail) --
+
+/// How `show` prints free text such as descriptions and comments.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, AsRefStr, EnumString)]
+#[strum(serialize_all = "lowercase")]
+pub enum TextFormat {
+    /// Render markdown; see [`crate::markdown`].
+    #[default]
+    Md,
+    /// Print the text as written.
+    Plain,
+}
+
+pub fn show(result: &ShowResult, format: TextFormat, json: bool) -> Result<()> {
+    match result {
+        ShowResult::Task {
+            task,
+            referenced_by,
+            artifact_links,
+        } => show_task(task, referenced_by, artifact_links, format, json),
+        ShowResult::Goal {
+            goal,
+            tasks,
+            metrics,
+            referenced_by,
+        } => show_goal(goal, tasks, metrics, referenced_by, format, json),
+    }
+}
+
+/// What `rd open` can open, numbered for picking one.
+pub fn open_targets(targets: &[Target], json: bool) -> Result<()> {
+    json_or(targets, json, |w| {
+        if targets.is_empty() {
+            writeln!(w, "{}", style("Nothing to open").dim())?;
+        }
+        for (i, target) in targets.iter().enumerate() {
+            let opener = match target.opener {
+                Opener::Browser => "browser",
+                Opener::Editor => "editor",
+            };
+            writeln!(w, "{:>3}. {}  {}", i + 1, target.label, style(opener).dim())?;
+            if target.location != target.label {
+                writeln!(w, "     {}", style(&target.location).underlined())?;
+            }
+        }
+        Ok(())
+    })
+}
+
+pub fn opened(target: &Target) -> Result<()> {
+    let mut w = io::stdout().lock();
+    writeln!(w, "{} {}", style("Opened").green(), target.label)?;
+    Ok(())
+}
+
+/// The goals and tasks that mention the one shown, and where.
+fn referenced_by(w: &mut dyn Write, links: &[Backlink]) -> Result<()> {
+    if links.is_empty() {
+        return Ok(());
+    }
+    writeln!(w)?;
+    writeln!(w, "{}", style("Referenced by").bold())?;
+    for link in links {
+        let place = match (link.kind, &link.comment) {
+            (_, Some(comment)) => format!("comment {comment}"),
+            (LinkSource::Goal, None) => "goal".to_string(),
+            (LinkSource::Task, None) => "task".to_string(),
+        };
+        let used = 2 + link.id.len() + 2 + place.len() + 2;
+        writeln!(
+            w,
+            "  {}  {}  {}",
+            style(&link.id).cyan(),
+            style(&place).dim(),
+            fit(&link.headline, used)
+        )?;
+    }
+    Ok(())
+}
+
+/// Write free text indented under a heading.
+fn text_block(w: &mut dyn Write, text: &str, format: TextFormat) -> Result<()> {
+    indented_block(w, text, format, "")
+}
+
+/// `text_block`, pushed further right by `indent`.
+fn indented_block(w: &mut dyn Write, text: &str, format: TextFormat, indent: &str) -> Result<()> {
+    let lines = match format {
+        TextFormat::Md => markdown::render(text),
+        TextFormat::Plain => text.lines().map(str::to_string).collect(),
+    };
+    for line in lines {
+        writeln!(w, "  {indent}{line}")?;
+    }
+    Ok(())
+}
+
+/// A task's references, each with a link where its kind has one.
+fn task_refs(w: &mut dyn Write, task: &Task) -> Result<()> {
+    for reference in task.refs() {
+        match reference.url().filter(|_| reference.kind() != RefKind::Url) {
+            Some(url) => field(
+                w,
+                "Ref",
+                &format!("{reference}  {}", style(url).underlined()),
+            )?,
+            None => field(w, "Ref", reference.as_str())?,
+        }
+    }
+    Ok(())
+}
+
+/// The tasks, artifacts and gates a task waits on, if any.
+fn task_waits_on(w: &mut dyn Write, task: &Task) -> Result<()> {
+    if task.blocked_by().is_empty()
+        && task.blocked_by_artifacts().is_empty()
+        && task.gates().is_empty()
+    {
+        return Ok(());
+    }
+    writeln!(w)?;
+    if !task.blocked_by().is_empty() {
+        field(w, "Blocked by", &task.blocked_by().join(", "))?;
+    }
+    if !task.blocked_by_artifacts().is_empty() {
+        field(w, "Needs", &task.blocked_by_artifacts().join(", "))?;
+    }
+    for gate in task.gates() {
+        field(w, "Gate", gate)?;
+    }
+    Ok(())
+}
+
+/// The descriptions and contracts a task had before it was revised, if any.
+fn task_revisions(w: &mut dyn Write, task: &Task) -> Result<()> {
+    if task.revisions().is_empty() {
+        return Ok(());
+    }
+    writeln!(w)?;
+    writeln!(
+        w,
+        "{} ({})",
+        style("Earlier revisions").bold(),
+        task.revisions().len()
+    )?;
+    for revision in task.revisions() {
+        writeln!(
+            w,
+            "  {} {}",
+            style(format!("[{}]", format_time(revision.replaced_at()))).dim(),
+            fit(revision.description().lines().next().unwrap_or_default(), 2)
+        )?;
+        if let Some(contract) = revision.contract() {
+            field(w, "    Receives", contract.receives())?;
+            field(w, "    Produces", contract.produces())?;
+            field(w, "    Verify", contract.verify())?;
+        }
+    }
+    Ok(())
+}
+
+/// One entry per failed attempt that was given a postmortem.
+fn task_postmortems(w: &mut dyn Write, task: &Task) -> Result<()> {
+    if task.postmortems().is_empty() {
+        return Ok(());
+    }
+    writeln!(w)?;
+    writeln!(
+        w,
+        "{} ({})",
+        style("Postmortems").bold(),
+        task.postmortems().len()
+    )?;
+    for postmortem in task.postmortems() {
+        writeln!(
+            w,
+            "  {} {}",
+            style(format!("[{}]", format_time(postmortem.failed_at()))).dim(),
+            postmortem.category().as_ref()
+        )?;
+        if let Some(fix) = postmortem.suggested_fix() {
+            field(w, "    Fix", fix)?;
+        }
+        if let Some(logs) = postmortem.logs() {
+            field(w, "    Logs", logs)?;
+        }
+    }
+    Ok(())
+}
+
+/// Each run of a task, oldest first, with what it cost and how it ended.
+fn task_attempts(w: &mut dyn Write, task: &Task) -> Result<()> {
+    if task.attempts().is_empty() {
+        return Ok(());
+    }
+    writeln!(w)?;
+    writeln!(
+        w,
+        "{} ({})",
+        style("Attempts").bold(),
+        task.attempts().len()
+    )?;
+    for attempt in task.attempts() {
+        let category = attempt
+            .category()
+            .map(|category| format!(" ({})", category.as_ref()))
+            .unwrap_or_default();
+        writeln!(
+            w,
+            "  #{} {}{category} {}{}",
+            attempt.number(),
+            state_styled(attempt.outcome().as_ref()),
+            style(format!("[{}]", format_time(attempt.ended_at()))).dim(),
+            attempt
+                .agent()
+                .map(|agent| format!(" by {agent}"))
+                .unwrap_or_default()
+        )?;
+        if attempt.tokens() > 0 || attempt.elapsed_ms() > 0 {
+            writeln!(
+                w,
+                "     {} tokens, {}",
+                attempt.tokens(),
+                format_duration(SignedDuration::from_millis(attempt.elapsed_ms()))
+            )?;
+        }
+        if let Some(result) = attempt.result() {
+            field(w, "     Result", &fit(result, 15))?;
+        }
+        if let Some(reason) = attempt.reason() {
+            field(w, "     Reason", &fit(reason, 15))?;
+        }
+    }
+    Ok(())
+}
+
+fn show_task(
+    task: &Task,
+    links: &[Backlink],
+    artifact_links: &[ArtifactLink],
+    format: TextFormat,
+    json: bool,
+) -> Result<()> {
+    #[derive(Serialize)]
+    struct TaskDetail<'a> {
+        #[serde(flatten)]
+        task: &'a Task,
+        referenced_by: &'a [Backlink],
+        #[serde(skip_serializing_if = "<[ArtifactLink]>::is_empty")]
+        artifact_links: &'a [ArtifactLink],
+    }
+    let detail = TaskDetail {
+        task,
+        referenced_by: links,
+        artifact_links,
+    };
+
+    json_or(&detail, json, |w| {
+        writeln!(
+            w,
+            "Task {}  [{}]",
+            style(task.id()).cyan().bold(),
+            task_state(task),
+        )?;
+        if let Some(title) = task.title() {
+            writeln!(w, "{}", style(title).bold())?;
+        }
+        writeln!(w)?;
+
+        writeln!(w, "{}", style("Description").bold())?;
+        text_block(w, task.description(), format)?;
+
+        writeln!(w)?;
+        field(w, "Goal", task.goal_id())?;
+        field(w, "Priority", task.priority().as_ref())?;
+        if let Some(assignee) = task.assignee() {
+            field(w, "Assignee", assignee)?;
+        }
+        if let Some(at) = task.not_before() {
+            field(w, "Not before", &format_time(at))?;
+        }
+        if let Some(estimate) = task.estimate() {
+            field(w, "Estimate", &format_duration(estimate))?;
+        }
+        task_refs(w, task)?;
+        field(w, "Created", &format_time(task.created_at()))?;
+        field(w, "Updated", &format_time(task.updated_at()))?;
+
+        // Contract
+        writeln!(w)?;
         match task.contract() {
             Some(contract) => {
                 writeln!(w, "{}", style("Contract").bold())?;
@@ -398,158 +1554,1274 @@ fn show_task(task: &Task, json: bool) -> Result<()> {
             }
         }
 
-        if !task.blocked_by().is_empty() {
-            writeln!(w)?;
-            field(w, "Blocked by", &task.blocked_by().join(", "))?;
-        }
+        task_waits_on(w, task)?;
 
         if let Some(result) = task.result() {
             writeln!(w)?;
             writeln!(w, "{}", style("Result").bold())?;
-            for line in result.summary().lines() {
-                writeln!(w, "  {line}")?;
-            }
+            text_block(w, result.summary(), format)?;
             if !result.artifacts().is_empty() {
                 field(w, "  Artifacts", &result.artifacts().join(", "))?;
             }
+            for link in artifact_links {
+                writeln!(w, "{:<15}{}", "", style(&link.url).underlined())?;
+            }
         }
 
-        if !task.comments().is_empty() {
+        if !task.checklist().is_empty() {
             writeln!(w)?;
+            let done = task.checklist().iter().filter(|i| i.done()).count();
             writeln!(
                 w,
-                "{} ({})",
-                style("Comments").bold(),
-                task.comments().len()
+                "{} ({done}/{})",
+                style("Checklist").bold(),
+                task.checklist().len()
+            )?;
+            checklist_items(w, task)?;
+        }
+
+        if !task.ctx().is_empty() {
+            writeln!(w)?;
+            writeln!(w, "{}", style("Context").bold())?;
+            ctx_entries(w, task)?;
+        }
+
+        task_revisions(w, task)?;
+        task_attempts(w, task)?;
+        task_postmortems(w, task)?;
+        task_comments(w, task, format)?;
+        referenced_by(w, links)
+    })
+}
+
+/// Comments as threads, each reply indented under the comment it answers.
+fn task_comments(w: &mut dyn Write, task: &Task, format: TextFormat) -> Result<()> {
+    if task.comments().is_empty() {
+        return Ok(());
+    }
+    writeln!(w)?;
+    writeln!(
+        w,
+        "{} ({})",
+        style("Comments").bold(),
+        task.comments().len()
+    )?;
+    for (depth, comment) in task.comment_threads() {
+        let indent = "    ".repeat(depth);
+        writeln!(
+            w,
+            "  {indent}{} {}{}",
+            style(format!("[{}]", format_time(comment.created_at()))).dim(),
+            style(comment.id()).dim(),
+            comment
+                .author()
+                .map(|author| format!(" by {author}"))
+                .unwrap_or_default()
+        )?;
+        if let Some(percent) = comment.progress() {
+            writeln!(w, "  {indent}Progress: {percent}%")?;
+        }
+        indented_block(w, comment.text(), format, &indent)?;
+        writeln!(w)?;
+    }
+    Ok(())
+}
+
+fn show_goal(
+    goal: &Goal,
+    tasks: &[Task],
+    metrics: &crate::models::Metrics,
+    links: &[Backlink],
+    format: TextFormat,
+    json: bool,
+) -> Result<()> {
+    // Wrap in a struct for JSON serialization
+    #[derive(Serialize)]
+    struct GoalDetail<'a> {
+        #[serde(flatten)]
+        goal: &'a Goal,
+        tasks: &'a [Task],
+        metrics: &'a crate::models::Metrics,
+        referenced_by: &'a [Backlink],
+    }
+    let detail = GoalDetail {
+        goal,
+        tasks,
+        metrics,
+        referenced_by: links,
+    };
+
+    json_or(&detail, json, |w| {
+        writeln!(
+            w,
+            "Goal {}  [{}]",
+            style(goal.id()).cyan().bold(),
+            state_styled(goal.state().as_ref()),
+        )?;
+        if let Some(title) = goal.title() {
+            writeln!(w, "{}", style(title).bold())?;
+        }
+        writeln!(w)?;
+
+        writeln!(w, "{}", style("Description").bold())?;
+        text_block(w, goal.description(), format)?;
+
+        writeln!(w)?;
+        if let Some(project) = goal.project() {
+            field(w, "Project", project)?;
+        }
+        if let Some(milestone) = goal.milestone() {
+            field(w, "Milestone", milestone)?;
+        }
+        if let Some(claim) = goal.claim(Timestamp::now()) {
+            let until = format_time(claim.expires_at());
+            field(w, "Claimed", &format!("by {} until {until}", claim.agent()))?;
+        }
+        field(w, "Created", &format_time(goal.created_at()))?;
+        field(w, "Updated", &format_time(goal.updated_at()))?;
+        if let Some(completed_at) = goal.completed_at() {
+            field(w, "Completed", &format_time(completed_at))?;
+        }
+
+        if !goal.criteria().is_empty() {
+            writeln!(w)?;
+            writeln!(w, "{}", style("Acceptance").bold())?;
+            criteria_items(w, goal.criteria().iter())?;
+        }
+
+        writeln!(w)?;
+        writeln!(w, "{}", style("Metrics").bold())?;
+        metrics.render(w)?;
+
+        if !tasks.is_empty() {
+            writeln!(w)?;
+            writeln!(
+                w,
+                "{:<10} {:<13} {}",
+                style("ID").bold().underlined(),
+                style("STATE").bold().underlined(),
+                style("DESCRIPTION").bold().underlined(),
             )?;
-            for comment in task.comments() {
+            for task in tasks {
                 writeln!(
                     w,
-                    "  {}",
-                    style(format!("[{}]", comment.created_at())).dim()
+                    "{:<10} {:<13} {}",
+                    style(task.id()).cyan(),
+                    task_state(task),
+                    fit(task.headline(), 25),
                 )?;
-                for line in comment.text().lines() {
-                    writeln!(w, "  {line}")?;
-                }
-                writeln!(w)?;
             }
         }
-
+        referenced_by(w, links)
+    })
+}
+
+// -- Ready --
+
+pub fn ready_tasks(
+    tasks: &[ReadyTask],
+    not_ready: Option<&[NotReady]>,
+    goal: &Goal,
+    json: bool,
+) -> Result<()> {
+    let human = |w: &mut dyn Write| {
+        writeln!(
+            w,
+            "Ready tasks for {} [{}]",
+            style(goal.id()).cyan().bold(),
+            state_styled(goal.state().as_ref()),
+        )?;
+        writeln!(w)?;
+        ready_table(w, tasks)?;
+        if let Some(not_ready) = not_ready {
+            not_ready_list(w, not_ready)?;
+        }
+        Ok(())
+    };
+
+    match not_ready {
+        Some(not_ready) => {
+            #[derive(Serialize)]
+            struct Explained<'a> {
+                ready: &'a [ReadyTask],
+                not_ready: &'a [NotReady],
+            }
+            json_or(
+                &Explained {
+                    ready: tasks,
+                    not_ready,
+                },
+                json,
+                human,
+            )
+        }
+        None => json_or(tasks, json, human),
+    }
+}
+
+fn ready_table(w: &mut dyn Write, tasks: &[ReadyTask]) -> Result<()> {
+    if tasks.is_empty() {
+        writeln!(w, "No tasks ready to start.")?;
+        return Ok(());
+    }
+
+    writeln!(
+        w,
+        "{:<10} {:<9} {}",
+        style("ID").bold().underlined(),
+        style("PRIORITY").bold().underlined(),
+        style("DESCRIPTION").bold().underlined(),
+    )?;
+    let mut any_inherited = false;
+    for ready in tasks {
+        let inherited = ready.priority() > ready.task.priority();
+        any_inherited |= inherited;
+        let priority = format!(
+            "{}{}",
+            ready.priority().as_ref(),
+            if inherited { "*" } else { "" }
+        );
+        writeln!(
+            w,
+            "{:<10} {:<9} {}",
+            style(ready.task.id()).cyan(),
+            priority,
+            fit(ready.task.headline(), 21),
+        )?;
+    }
+
+    if any_inherited {
+        writeln!(w)?;
+        writeln!(
+            w,
+            "{}",
+            style("* inherited from a blocked task waiting on it").dim()
+        )?;
+    }
+    Ok(())
+}
+
+/// Each task that is not ready, with its reasons on the following lines.
+fn not_ready_list(w: &mut dyn Write, not_ready: &[NotReady]) -> Result<()> {
+    writeln!(w)?;
+    writeln!(w, "{} ({})", style("Not ready").bold(), not_ready.len())?;
+    for task in not_ready {
+        writeln!(
+            w,
+            "  {} {}",
+            style(&task.task_id).cyan(),
+            fit(&task.description, task.task_id.len() + 3)
+        )?;
+
+        let blockers: Vec<String> = task
+            .reasons
+            .iter()
+            .filter_map(|r| match r {
+                NotReadyReason::BlockedBy { task_id, state } => Some(format!(
+                    "{task_id} ({})",
+                    state.as_ref().map_or("missing", AsRef::as_ref)
+                )),
+                _ => None,
+            })
+            .collect();
+        if !blockers.is_empty() {
+            writeln!(w, "    - blocked by {}", blockers.join(", "))?;
+        }
+        for reason in &task.reasons {
+            match reason {
+                NotReadyReason::MissingContract => writeln!(
+                    w,
+                    "    - no contract; set one with: rd edit task {} --receives ... --produces ... --verify ...",
+                    task.task_id
+                )?,
+                NotReadyReason::WaitingOnArtifact { path } => {
+                    writeln!(w, "    - waiting for a completed task to produce {path}")?;
+                }
+                NotReadyReason::AssignedTo { agent } => {
+                    writeln!(w, "    - assigned to {agent}")?;
+                }
+                NotReadyReason::NotBefore { at } => {
+                    writeln!(w, "    - scheduled for {}", format_time(*at))?;
+                }
+                NotReadyReason::GateFailed { command } => {
+                    writeln!(w, "    - gate did not pass: {command}")?;
+                }
+                NotReadyReason::MarkedBlocked => {
+                    writeln!(
+                        w,
+                        "    - marked blocked, but nothing it waits on is unfinished"
+                    )?;
+                }
+                NotReadyReason::BlockedBy { .. } => {}
+            }
+        }
+    }
+    Ok(())
+}
+
+// -- Peek / Context --
+
+pub fn peek(peek: Option<&TaskContext>, goal_id: &str, json: bool) -> Result<()> {
+    next_task(peek, goal_id, "Next task", json)
+}
+
+pub fn claimed(claimed: Option<&TaskContext>, goal_id: &str, json: bool) -> Result<()> {
+    next_task(claimed, goal_id, "Started task", json)
+}
+
+/// A task picked from a goal's ready list, with what it builds on.
+fn next_task(peek: Option<&TaskContext>, goal_id: &str, heading: &str, json: bool) -> Result<()> {
+    json_or(&peek, json, |w| {
+        let Some(peek) = peek else {
+            writeln!(w, "No ready tasks in goal {goal_id}.")?;
+            return Ok(());
+        };
+        let task = &peek.task;
+
+        writeln!(
+            w,
+            "{heading} {}  [{}]",
+            style(task.id()).cyan().bold(),
+            task.priority().as_ref(),
+        )?;
+        writeln!(w)?;
+        for line in task.description().lines() {
+            writeln!(w, "  {line}")?;
+        }
+
+        if let Some(contract) = task.contract() {
+            writeln!(w)?;
+            writeln!(w, "{}", style("Contract").bold())?;
+            field(w, "  Receives", contract.receives())?;
+            field(w, "  Produces", contract.produces())?;
+            field(w, "  Verify", contract.verify())?;
+        }
+
+        upstream(w, peek)
+    })
+}
+
+pub fn task_context(context: &TaskContext, json: bool) -> Result<()> {
+    json_or(context, json, |w| {
+        let task = &context.task;
+        writeln!(
+            w,
+            "Context for {}  [{}]",
+            style(task.id()).cyan().bold(),
+            task_state(task),
+        )?;
+        writeln!(w, "  {}", fit(task.description(), 2))?;
+        writeln!(w)?;
+        match task.contract() {
+            Some(contract) => field(w, "Receives", contract.receives())?,
+            None => field(w, "Receives", &style("(no contract)").dim().to_string())?,
+        }
+        if let Some(inputs) = &context.inputs {
+            field(w, "Inputs", inputs)?;
+        }
+
+        if context.upstream.is_empty() {
+            writeln!(w)?;
+            writeln!(w, "No upstream tasks.")?;
+        }
+        upstream(w, context)
+    })
+}
+
+/// The "Builds on" section: each blocker's result and how it maps onto `receives`.
+fn upstream(w: &mut dyn Write, context: &TaskContext) -> Result<()> {
+    if !context.upstream.is_empty() {
+        writeln!(w)?;
+        writeln!(w, "{}", style("Builds on").bold())?;
+    }
+    for up in &context.upstream {
+        writeln!(
+            w,
+            "  {:<10} {:<13} {}",
+            style(&up.id).cyan(),
+            state_styled(up.state.as_ref()),
+            fit(&up.description, 27),
+        )?;
+        if let Some(result) = &up.result {
+            field(w, "    Result", &fit(result.summary(), 20))?;
+            if !result.artifacts().is_empty() {
+                field(w, "    Artifacts", &result.artifacts().join(", "))?;
+            }
+        }
+        if !up.matched_artifacts.is_empty() {
+            field(w, "    Provides", &up.matched_artifacts.join(", "))?;
+        } else if !up.shared_terms.is_empty() {
+            field(w, "    Related", &up.shared_terms.join(", "))?;
+        }
+    }
+
+    if !context.unresolved.is_empty() {
+        writeln!(w)?;
+        field(
+            w,
+            "Unresolved",
+            &style(context.unresolved.join(", ")).yellow().to_string(),
+        )?;
+    }
+    Ok(())
+}
+
+// -- Gc --
+
+impl Render for CleanResult {
+    fn render(&self, w: &mut dyn Write) -> Result<()> {
+        if self.removed.is_empty() && self.kept.is_empty() {
+            let msg = if self.force {
+                "No goals found."
+            } else {
+                "No completed goals to clean."
+            };
+            writeln!(w, "{msg}")?;
+            return Ok(());
+        }
+
+        let verb = if self.dry_run {
+            "Would remove"
+        } else {
+            "Removed"
+        };
+        for goal in &self.removed {
+            writeln!(
+                w,
+                "  {} {} — {}",
+                style(verb).red(),
+                style(&goal.id).cyan(),
+                fit(&goal.headline, verb.len() + goal.id.len() + 6),
+            )?;
+            writeln!(w, "    {}", style(goal.contents()).dim())?;
+        }
+        if !self.unlinked.is_empty() {
+            let heading = if self.dry_run {
+                "Other goals depend on these tasks (pass --unlink to drop the dependencies):"
+            } else {
+                "Dropped dependencies from other goals:"
+            };
+            writeln!(w, "\n{}", style(heading).yellow())?;
+            for r in &self.unlinked {
+                writeln!(
+                    w,
+                    "  {} (goal {}) blocked by {}",
+                    style(&r.task_id).cyan(),
+                    r.goal_id,
+                    r.blocked_by
+                )?;
+            }
+        }
+        if self.dry_run {
+            writeln!(
+                w,
+                "\nWould clean {} goal(s). Nothing was removed.",
+                style(self.removed.len()).bold()
+            )?;
+        } else {
+            writeln!(w, "\nCleaned {} goal(s).", style(self.removed.len()).bold())?;
+        }
+        Ok(())
+    }
+}
+
+pub fn gc(report: &GcReport, json: bool) -> Result<()> {
+    json_or(report, json, |w| {
+        if report.removed.is_empty() && report.pruned_commands == 0 {
+            writeln!(w, "Nothing to clean up.")?;
+            return Ok(());
+        }
+
+        let verb = if report.dry_run {
+            "Would remove"
+        } else {
+            "Removed"
+        };
+        for path in &report.removed {
+            writeln!(w, "  {} {}", style(verb).red(), path.display())?;
+        }
+        if report.pruned_commands > 0 {
+            writeln!(
+                w,
+                "  {} {} command(s) from {}",
+                style(verb).red(),
+                report.pruned_commands,
+                LOG_FILE
+            )?;
+        }
+        writeln!(w)?;
+        writeln!(
+            w,
+            "{verb} {} item(s), {} bytes reclaimed.",
+            style(report.removed.len()).bold(),
+            style(report.bytes_reclaimed).bold(),
+        )?;
+        Ok(())
+    })
+}
+
+// -- Doctor --
+
+pub fn doctor(report: &DoctorReport, json: bool) -> Result<()> {
+    json_or(report, json, |w| {
+        if report.issues.is_empty() {
+            writeln!(w, "{}", style("No problems found.").green())?;
+            return Ok(());
+        }
+
+        for issue in &report.issues {
+            let label = if issue.fixed {
+                style("fixed").green()
+            } else {
+                style("problem").yellow()
+            };
+            writeln!(w, "  {label} {}", issue.message)?;
+        }
+        let left = report.issues.iter().filter(|i| !i.fixed).count();
+        writeln!(w)?;
+        writeln!(
+            w,
+            "{} problem(s), {} fixed.",
+            style(report.issues.len()).bold(),
+            style(report.issues.len() - left).bold()
+        )?;
+        if left > 0 && report.issues.iter().all(|i| !i.fixed) {
+            writeln!(w, "Run rd doctor --fix to repair what can be repaired.")?;
+        }
+        Ok(())
+    })
+}
+
+// -- Sync --
+
+pub fn sync(report: &SyncReport, json: bool) -> Result<()> {
+    json_or(report, json, |w| {
+        let prefix = if report.dry_run {
+            "Would sync"
+        } else {
+            "Synced"
+        };
+        writeln!(
+            w,
+            "{prefix} with {}: {} pulled, {} pushed.",
+            report.remote.display(),
+            style(report.pulled).bold(),
+            style(report.pushed).bold(),
+        )?;
+
+        for id in &report.deleted {
+            writeln!(w, "  {} goal {id}", style("Deleted").red())?;
+        }
+
+        if !report.conflicts.is_empty() {
+            writeln!(w)?;
+            writeln!(w, "{}", style("Conflicts").bold().yellow())?;
+            for conflict in &report.conflicts {
+                writeln!(
+                    w,
+                    "  {} {}: {}",
+                    conflict.kind,
+                    style(&conflict.id).cyan(),
+                    conflict.reason
+                )?;
+            }
+        }
+        Ok(())
+    })
+}
+
+// -- Watch --
+
+/// Print one event as it happens. JSON events are one object per line so the
+/// stream can be piped into other tools.
+pub fn watch_event(event: &Event, json: bool) -> Result<()> {
+    let mut stdout = io::stdout().lock();
+    if let Some(template) = &options().template {
+        fill_template(&mut stdout, template, &selected(event)?)?;
+    } else if json {
+        serde_json::to_writer(&mut stdout, &selected(event)?)?;
+        writeln!(stdout)?;
+    } else {
+        let time = Timestamp::now().to_zoned(display_tz()).strftime("%H:%M:%S");
+        let marker = match event.kind {
+            EventKind::TaskReady => style("ready").green(),
+            EventKind::GoalCompleted => style("done").cyan(),
+        };
+        writeln!(
+            stdout,
+            "{} {:<6} {} {}",
+            style(time).dim(),
+            marker,
+            style(&event.id).cyan(),
+            fit(&event.message, 27 + event.id.len()),
+        )?;
+    }
+    stdout.flush()?;
+    Ok(())
+}
+
+// -- Quota --
+
+pub fn postmortems(groups: &[CategoryGroup], json: bool) -> Result<()> {
+    json_or(groups, json, |w| {
+        if groups.is_empty() {
+            writeln!(w, "No failures recorded.")?;
+            return Ok(());
+        }
+
+        for (i, group) in groups.iter().enumerate() {
+            if i > 0 {
+                writeln!(w)?;
+            }
+            let category = group
+                .category
+                .as_ref()
+                .map_or("uncategorized", AsRef::as_ref);
+            writeln!(w, "{} ({})", style(category).bold(), group.count)?;
+            for failure in &group.failures {
+                let prefix = format!("  {}  {}  ", failure.task_id, failure.goal_id);
+                writeln!(
+                    w,
+                    "  {}  {}  {}",
+                    style(&failure.task_id).cyan(),
+                    style(&failure.goal_id).dim(),
+                    fit(&failure.description, prefix.len())
+                )?;
+                writeln!(w, "    {}", style(format_time(failure.failed_at)).dim())?;
+                if let Some(fix) = &failure.suggested_fix {
+                    writeln!(w, "    Fix: {}", fit(fix, 9))?;
+                }
+                if let Some(logs) = &failure.logs {
+                    writeln!(w, "    Logs: {logs}")?;
+                }
+            }
+            let hidden = group.count - group.failures.len();
+            if hidden > 0 {
+                writeln!(w, "  {}", style(format!("... and {hidden} more")).dim())?;
+            }
+        }
+        Ok(())
+    })
+}
+
+// -- Inbox --
+
+pub fn inbox(items: &[Item], json: bool) -> Result<()> {
+    json_or(items, json, |w| {
+        if items.is_empty() {
+            writeln!(w, "Nothing needs attention.")?;
+            return Ok(());
+        }
+        for item in items {
+            inbox_item(w, item, true)?;
+        }
+        Ok(())
+    })
+}
+
+pub fn inbox_digest(groups: &[Group], json: bool) -> Result<()> {
+    json_or(groups, json, |w| {
+        if groups.is_empty() {
+            writeln!(w, "Nothing needs attention.")?;
+            return Ok(());
+        }
+        for (i, group) in groups.iter().enumerate() {
+            if i > 0 {
+                writeln!(w)?;
+            }
+            writeln!(
+                w,
+                "{} ({})",
+                style(inbox_label(group.kind)).bold(),
+                group.count
+            )?;
+            for item in &group.items {
+                inbox_item(w, item, false)?;
+            }
+            let more = group.count - group.items.len();
+            if more > 0 {
+                writeln!(w, "  {}", style(format!("... and {more} more")).dim())?;
+            }
+        }
+        Ok(())
+    })
+}
+
+fn inbox_label(kind: ItemKind) -> &'static str {
+    match kind {
+        ItemKind::Failed => "Failed",
+        ItemKind::OverBudget => "Over budget",
+        ItemKind::AwaitingVerification => "Awaiting verification",
+        ItemKind::Stale => "Stale",
+        ItemKind::WaitingOnGate => "Waiting on a gate",
+    }
+}
+
+fn inbox_item(w: &mut dyn Write, item: &Item, with_kind: bool) -> Result<()> {
+    let id = format!("{:<10}", item.id);
+    let id = if item.kind == ItemKind::OverBudget {
+        style(id).yellow()
+    } else {
+        style(id).cyan()
+    };
+    if with_kind {
+        let label = format!("{:<22}", inbox_label(item.kind));
+        let label = match item.kind {
+            ItemKind::Failed | ItemKind::OverBudget => style(label).red(),
+            _ => style(label).yellow(),
+        };
+        writeln!(w, "{label} {id} {}", fit(&item.message, 34))?;
+    } else {
+        writeln!(w, "  {id} {}", fit(&item.message, 13))?;
+    }
+    Ok(())
+}
+
+pub fn quota(usage: &[AgentUsage], json: bool) -> Result<()> {
+    json_or(usage, json, |w| {
+        if usage.is_empty() {
+            writeln!(w, "No agent usage recorded today.")?;
+            return Ok(());
+        }
+
+        writeln!(
+            w,
+            "{:<20} {:>12} {:>12}",
+            style("AGENT").bold().underlined(),
+            style("TODAY").bold().underlined(),
+            style("LIMIT").bold().underlined(),
+        )?;
+        for u in usage {
+            let limit = u
+                .daily_limit
+                .map_or_else(|| "-".to_string(), |l| l.to_string());
+            let today = if u.exceeded() {
+                style(u.tokens_today.to_string()).red()
+            } else {
+                style(u.tokens_today.to_string())
+            };
+            writeln!(
+                w,
+                "{:<20} {:>12} {:>12}",
+                style(&u.agent).cyan(),
+                today,
+                limit
+            )?;
+        }
+        Ok(())
+    })
+}
+
+// -- Milestones --
+
+pub fn milestone_added(status: &MilestoneStatus, json: bool) -> Result<()> {
+    json_or(status, json, |w| {
+        writeln!(
+            w,
+            "{} {}",
+            style("Added milestone:").green(),
+            style(&status.name).cyan().bold()
+        )?;
+        writeln!(w, "  {} {}", style("Due:").dim(), format_time(status.due))?;
+        Ok(())
+    })
+}
+
+pub fn milestone_status(statuses: &[MilestoneStatus], json: bool) -> Result<()> {
+    json_or(statuses, json, |w| {
+        if statuses.is_empty() {
+            writeln!(
+                w,
+                "No milestones. Add one with: rd milestone add <name> --due <date>"
+            )?;
+            return Ok(());
+        }
+
+        for (i, status) in statuses.iter().enumerate() {
+            if i > 0 {
+                writeln!(w)?;
+            }
+            let flag = if status.at_risk {
+                style("at risk").red()
+            } else {
+                style("on track").green()
+            };
+            writeln!(
+                w,
+                "{} due {} [{flag}]",
+                style(&status.name).cyan().bold(),
+                format_time(status.due)
+            )?;
+            if let Some(description) = &status.description {
+                writeln!(w, "  {}", fit(description, 2))?;
+            }
+            let estimate = status.estimated_ms.map_or_else(
+                || "no estimate yet".to_string(),
+                |ms| {
+                    format!(
+                        "~{} of work left",
+                        format_duration(SignedDuration::from_millis(ms))
+                    )
+                },
+            );
+            writeln!(
+                w,
+                "  {}/{} tasks done, {estimate}",
+                status.tasks_completed, status.tasks
+            )?;
+            for goal in &status.goals {
+                write!(
+                    w,
+                    "  {:<10} {:<13} {:>7} {}",
+                    style(&goal.id).cyan(),
+                    state_styled(goal.state.as_ref()),
+                    format!("{}/{}", goal.tasks_completed, goal.tasks),
+                    fit(&goal.headline, 44),
+                )?;
+                if goal.at_risk {
+                    write!(w, " {}", style("at risk").red())?;
+                }
+                writeln!(w)?;
+            }
+        }
+        Ok(())
+    })
+}
+
+// -- Dashboard --
+
+#[cfg(feature = "web")]
+pub fn serving(addr: std::net::SocketAddr) {
+    println!("{} http://{addr}/", style("Serving dashboard on").green());
+    let _ = io::stdout().flush();
+}
+
+// -- Projects --
+
+pub fn projects(projects: &[ProjectSummary], json: bool) -> Result<()> {
+    json_or(projects, json, |w| {
+        if projects.is_empty() {
+            writeln!(w, "No goals found.")?;
+            return Ok(());
+        }
+
+        writeln!(
+            w,
+            "{:<20} {:>7} {:>11} {:>7} {:>12}",
+            style("PROJECT").bold().underlined(),
+            style("GOALS").bold().underlined(),
+            style("TASKS").bold().underlined(),
+            style("FAILED").bold().underlined(),
+            style("TOKENS").bold().underlined(),
+        )?;
+        for p in projects {
+            let name = match &p.project {
+                Some(name) => style(fit(name, 60)).cyan(),
+                None => style("(none)".to_string()).dim(),
+            };
+            writeln!(
+                w,
+                "{:<20} {:>7} {:>11} {:>7} {:>12}",
+                name,
+                format!("{}/{}", p.goals_completed, p.goals),
+                format!("{}/{}", p.tasks_completed, p.tasks),
+                p.tasks_failed,
+                p.total_tokens,
+            )?;
+        }
+        Ok(())
+    })
+}
+
+// -- All projects --
+
+pub fn project_registered(project: &Project, added: bool) -> Result<()> {
+    let verb = if added { "Registered" } else { "Unregistered" };
+    let mut w = io::stdout().lock();
+    writeln!(
+        w,
+        "{} {} ({})",
+        style(format!("{verb}:")).green(),
+        style(&project.name).cyan().bold(),
+        project.path.display()
+    )?;
+    Ok(())
+}
+
+pub fn registered_projects(projects: &[Project], json: bool) -> Result<()> {
+    json_or(projects, json, |w| {
+        if projects.is_empty() {
+            writeln!(w, "No projects registered. Add one with: rd all add")?;
+            return Ok(());
+        }
+        for project in projects {
+            writeln!(
+                w,
+                "{:<20} {}",
+                style(&project.name).cyan(),
+                project.path.display()
+            )?;
+        }
+        Ok(())
+    })
+}
+
+pub fn all_status(statuses: &[ProjectStatus], json: bool) -> Result<()> {
+    json_or(statuses, json, |w| {
+        if statuses.is_empty() {
+            writeln!(w, "No projects registered. Add one with: rd all add")?;
+            return Ok(());
+        }
+
+        writeln!(
+            w,
+            "{:<20} {:>6} {:>6} {:>7} {:>6} {:>8} {:>7} {:>6}",
+            style("PROJECT").bold().underlined(),
+            style("GOALS").bold().underlined(),
+            style("READY").bold().underlined(),
+            style("ACTIVE").bold().underlined(),
+            style("STALE").bold().underlined(),
+            style("BLOCKED").bold().underlined(),
+            style("FAILED").bold().underlined(),
+            style("DONE").bold().underlined(),
+        )?;
+        for s in statuses {
+            let name = style(fit(&s.name, 60)).cyan();
+            if let Some(error) = &s.error {
+                writeln!(w, "{name:<20} {}", style(fit(error, 21)).red())?;
+                continue;
+            }
+            writeln!(
+                w,
+                "{name:<20} {:>6} {:>6} {:>7} {:>6} {:>8} {:>7} {:>6}",
+                s.goals,
+                s.ready,
+                s.in_progress,
+                if s.stale > 0 {
+                    style(s.stale.to_string()).yellow()
+                } else {
+                    style(s.stale.to_string())
+                },
+                s.blocked,
+                if s.failed > 0 {
+                    style(s.failed.to_string()).red()
+                } else {
+                    style(s.failed.to_string())
+                },
+                s.completed,
+            )?;
+        }
+        Ok(())
+    })
+}
+
+pub fn all_ready(tasks: &[ProjectTask], json: bool) -> Result<()> {
+    json_or(tasks, json, |w| {
+        if tasks.is_empty() {
+            writeln!(w, "No tasks ready to start.")?;
+            return Ok(());
+        }
+
+        writeln!(
+            w,
+            "{:<16} {:<10} {:<9} {}",
+            style("PROJECT").bold().underlined(),
+            style("ID").bold().underlined(),
+            style("PRIORITY").bold().underlined(),
+            style("DESCRIPTION").bold().underlined(),
+        )?;
+        for t in tasks {
+            writeln!(
+                w,
+                "{:<16} {:<10} {:<9} {}",
+                style(fit(&t.project, 64)).cyan(),
+                t.task.task.id(),
+                t.task.priority().as_ref(),
+                fit(t.task.task.headline(), 38),
+            )?;
+        }
+        Ok(())
+    })
+}
+
+// -- Stats --
+
+pub fn token_stats(stats: &TokenStats, json: bool) -> Result<()> {
+    json_or(stats, json, |w| {
+        if stats.groups.is_empty() {
+            writeln!(w, "No token usage recorded.")?;
+            return Ok(());
+        }
+
+        write!(
+            w,
+            "{:<12} {:>12} {:>6} {:>6}",
+            style(stats.by.as_ref().to_uppercase()).bold().underlined(),
+            style("TOKENS").bold().underlined(),
+            style("SHARE").bold().underlined(),
+            style("TASKS").bold().underlined(),
+        )?;
+        if let Some(currency) = &stats.currency {
+            write!(w, " {:>10}", style(currency).bold().underlined())?;
+        }
+        if stats.by == GroupBy::Goal {
+            write!(w, "  {}", style("DESCRIPTION").bold().underlined())?;
+        }
+        writeln!(w)?;
+        for group in &stats.groups {
+            let unknown = if stats.by == GroupBy::Model {
+                "(unknown)"
+            } else {
+                "(unassigned)"
+            };
+            let key = group.key.as_deref().unwrap_or(unknown);
+            #[allow(clippy::cast_precision_loss)]
+            let share = group.tokens as f64 * 100.0 / stats.total_tokens as f64;
+            write!(
+                w,
+                "{:<12} {:>12} {:>5.0}% {:>6}",
+                style(key).cyan(),
+                group.tokens,
+                share,
+                group.tasks,
+            )?;
+            let mut used = 41;
+            if let Some(cost) = group.cost {
+                write!(w, " {cost:>10.2}")?;
+                used += 11;
+            }
+            if let Some(label) = &group.label {
+                write!(w, "  {}", fit(label, used))?;
+            }
+            writeln!(w)?;
+        }
+        write!(w, "{:<12} {:>12}", style("total").dim(), stats.total_tokens)?;
+        if let Some(cost) = stats.total_cost {
+            write!(w, " {:>13} {cost:>10.2}", "")?;
+        }
+        writeln!(w)?;
+        if let Some(unpriced) = stats.unpriced_tokens.filter(|t| *t > 0) {
+            writeln!(
+                w,
+                "{}",
+                style(format!(
+                    "{unpriced} tokens have no price: their attempts name no model, or one missing from [pricing.models]"
+                ))
+                .dim()
+            )?;
+        }
+        Ok(())
+    })
+}
+
+/// Heat map shades from a quiet day to the busiest one.
+const SHADES: [&str; 4] = ["░", "▒", "▓", "█"];
+
+pub fn model_stats(models: &[ModelStats], json: bool) -> Result<()> {
+    json_or(models, json, |w| {
+        if models.is_empty() {
+            writeln!(w, "No attempts recorded.")?;
+            return Ok(());
+        }
+
+        writeln!(
+            w,
+            "{:<20} {:<12} {:>8} {:>8} {:>7} {:>10} {:>8}",
+            style("MODEL").bold().underlined(),
+            style("PROVIDER").bold().underlined(),
+            style("ATTEMPTS").bold().underlined(),
+            style("SUCCESS").bold().underlined(),
+            style("RETRIES").bold().underlined(),
+            style("AVG TOKENS").bold().underlined(),
+            style("AVG TIME").bold().underlined(),
+        )?;
+        for model in models {
+            let time = model.avg_elapsed_ms.map_or_else(
+                || "-".to_string(),
+                |ms| format_duration(SignedDuration::from_millis(ms)),
+            );
+            writeln!(
+                w,
+                "{:<20} {:<12} {:>8} {:>7.0}% {:>7} {:>10} {:>8}",
+                style(model.model.as_deref().unwrap_or("(unknown)")).cyan(),
+                model.provider.as_deref().unwrap_or("-"),
+                model.attempts,
+                model.success_rate * 100.0,
+                model.retries,
+                model.avg_tokens,
+                time,
+            )?;
+        }
         Ok(())
     })
 }
 
-fn show_goal(
-    goal: &Goal,
-    tasks: &[Task],
-    metrics: &crate::models::Metrics,
-    json: bool,
-) -> Result<()> {
-    // Wrap in a struct for JSON serialization
-    #[derive(Serialize)]
-    struct GoalDetail<'a> {
-        #[serde(flatten)]
-        goal: &'a Goal,
-        tasks: &'a [Task],
-        metrics: &'a crate::models::Metrics,
-    }
-    let detail = GoalDetail {
-        goal,
-        tasks,
-        metrics,
-    };
-
-    json_or(&detail, json, |w| {
-        writeln!(
-            w,
-            "Goal {}  [{}]",
-            style(goal.id()).cyan().bold(),
-            state_styled(goal.state().as_ref()),
-        )?;
-        writeln!(w)?;
+pub fn activity(activity: &Activity, json: bool) -> Result<()> {
+    json_or(activity, json, |w| {
+        let busiest = activity.days.iter().max_by_key(|d| d.completed);
+        let max = busiest.map_or(0, |d| d.completed);
 
-        writeln!(w, "{}", style("Description").bold())?;
-        for line in goal.description().lines() {
-            writeln!(w, "  {line}")?;
+        // Month names over the first week starting in them that has room.
+        let mut months = String::new();
+        let mut labelled = None;
+        for (column, week) in activity.days.chunks(7).enumerate() {
+            let month = week[0].date.month();
+            if labelled != Some(month) && months.len() <= 2 * column {
+                months.push_str(&" ".repeat(2 * column - months.len()));
+                months.push_str(&week[0].date.strftime("%b ").to_string());
+                labelled = Some(month);
+            }
         }
+        writeln!(w, "     {}", style(months.trim_end()).dim())?;
 
-        writeln!(w)?;
-        field(w, "Created", &goal.created_at().to_string())?;
-        field(w, "Updated", &goal.updated_at().to_string())?;
-        if let Some(completed_at) = goal.completed_at() {
-            field(w, "Completed", &completed_at.to_string())?;
+        for (row, label) in ["Mon", "", "Wed", "", "Fri", "", "Sun"].iter().enumerate() {
+            write!(w, "{label:<4}")?;
+            for week in activity.days.chunks(7) {
+                match week.get(row) {
+                    Some(day) if day.completed == 0 => write!(w, " {}", style("·").dim())?,
+                    Some(day) => {
+                        let shade = (day.completed * SHADES.len()).div_ceil(max) - 1;
+                        write!(w, " {}", style(SHADES[shade]).green())?;
+                    }
+                    None => {}
+                }
+            }
+            writeln!(w)?;
         }
 
         writeln!(w)?;
-        writeln!(w, "{}", style("Metrics").bold())?;
-        writeln!(
+        write!(
             w,
-            "  Tasks: {} total, {} completed, {} failed",
-            metrics.task_count(),
-            metrics.tasks_completed(),
-            metrics.tasks_failed()
+            "{} tasks completed in the last {} weeks",
+            activity.total, activity.weeks
         )?;
-        writeln!(w, "  Tokens: {}", metrics.total_tokens())?;
-        writeln!(w, "  Elapsed: {}ms", metrics.elapsed_ms())?;
+        match busiest {
+            Some(day) if day.completed > 0 => {
+                writeln!(w, "; busiest day {} ({})", day.date, day.completed)?;
+            }
+            _ => writeln!(w)?,
+        }
+        Ok(())
+    })
+}
 
-        if !tasks.is_empty() {
-            writeln!(w)?;
+// -- Suggest deps --
+
+pub fn suggestions(suggestions: &[Suggestion], applied: bool, json: bool) -> Result<()> {
+    json_or(suggestions, json, |w| {
+        if suggestions.is_empty() {
+            writeln!(w, "No dependency suggestions.")?;
+            return Ok(());
+        }
+
+        let heading = if applied {
+            "Added dependencies:"
+        } else {
+            "Suggested dependencies:"
+        };
+        writeln!(w, "{}", style(heading).bold())?;
+        for s in suggestions {
             writeln!(
                 w,
-                "{:<10} {:<13} {}",
-                style("ID").bold().underlined(),
-                style("STATE").bold().underlined(),
-                style("DESCRIPTION").bold().underlined(),
+                "  {} blocked by {}  {}",
+                style(&s.task_id).cyan(),
+                style(&s.blocked_by).cyan(),
+                style(format!("({})", s.reason)).dim(),
             )?;
-            for task in tasks {
-                writeln!(
-                    w,
-                    "{:<10} {:<13} {}",
-                    style(task.id()).cyan(),
-                    state_styled(task.state().as_ref()),
-                    truncate(task.description(), 80),
-                )?;
-            }
+        }
+
+        if !applied {
+            writeln!(w)?;
+            writeln!(w, "Run again with --apply to add them.")?;
         }
         Ok(())
     })
 }
 
-// -- Ready --
-
-pub fn ready_tasks(tasks: &[Task], goal: &Goal, json: bool) -> Result<()> {
-    json_or(tasks, json, |w| {
-        writeln!(
-            w,
-            "Ready tasks for {} [{}]",
-            style(goal.id()).cyan().bold(),
-            state_styled(goal.state().as_ref()),
-        )?;
-        writeln!(w)?;
-
-        if tasks.is_empty() {
-            writeln!(w, "No tasks ready to start.")?;
+pub fn transcript_proposals(proposals: &[Proposal], applying: bool, json: bool) -> Result<()> {
+    json_or(proposals, json, |w| {
+        if proposals.is_empty() {
+            writeln!(w, "No radial operations found in the transcript.")?;
             return Ok(());
         }
 
-        writeln!(
-            w,
-            "{:<10} {}",
-            style("ID").bold().underlined(),
-            style("DESCRIPTION").bold().underlined(),
-        )?;
-        for task in tasks {
+        let heading = if applying {
+            "Applying:"
+        } else {
+            "Proposed operations:"
+        };
+        writeln!(w, "{}", style(heading).bold())?;
+        for (i, proposal) in proposals.iter().enumerate() {
+            let number = format!("{:>3}.", i + 1);
+            let words: Vec<String> = proposal.args.iter().map(|a| shell_quote(a)).collect();
+            let source = match proposal.source {
+                Source::Command => "command",
+                Source::Outcome => "reported done",
+            };
             writeln!(
                 w,
-                "{:<10} {}",
-                style(task.id()).cyan(),
-                truncate(task.description(), 80),
+                "{number} rd {}  {}",
+                words.join(" "),
+                style(format!("(line {}, {source})", proposal.line)).dim(),
+            )?;
+        }
+
+        if !applying {
+            writeln!(w)?;
+            writeln!(w, "Run again with --apply to run them.")?;
+        }
+        Ok(())
+    })
+}
+
+// -- Command history --
+
+pub fn command_history(invocations: &[Numbered], json: bool) -> Result<()> {
+    json_or(invocations, json, |w| {
+        if invocations.is_empty() {
+            writeln!(w, "No commands recorded.")?;
+            return Ok(());
+        }
+        for entry in invocations {
+            let invocation = &entry.invocation;
+            let words: Vec<String> = invocation.argv.iter().map(|a| shell_quote(a)).collect();
+            let mark = if invocation.ok {
+                style("✓").green()
+            } else {
+                style("✗").red()
+            };
+            write!(
+                w,
+                "{:>4} {} {mark} rd {}",
+                entry.number,
+                style(format_time(invocation.at)).dim(),
+                words.join(" ")
             )?;
+            if let Some(agent) = &invocation.agent {
+                write!(w, "  {}", style(format!("({agent})")).dim())?;
+            }
+            writeln!(w)?;
+            if let Some(error) = &invocation.error {
+                writeln!(w, "       {}", style(fit(error, 7)).red())?;
+            }
         }
         Ok(())
     })
 }
 
+/// Announce a replayed command on stderr, so its own output stays clean.
+pub fn replaying(number: usize, argv: &[String]) -> Result<()> {
+    let words: Vec<String> = argv.iter().map(|a| shell_quote(a)).collect();
+    let mut w = io::stderr().lock();
+    writeln!(
+        w,
+        "{} rd {}",
+        style(format!("Replaying {number}:")).dim(),
+        words.join(" ")
+    )?;
+    Ok(())
+}
+
 // -- List --
 
 pub fn list(results: &[GoalWithTasks], json: bool) -> Result<()> {
@@ -572,59 +2844,351 @@ pub fn list(results: &[GoalWithTasks], json: bool) -> Result<()> {
         .collect();
 
     json_or(&entries, json, |w| {
-        if results.is_empty() {
-            writeln!(w, "No goals found.")?;
-            return Ok(());
+        let rows: Vec<GoalRows> = results
+            .iter()
+            .map(|r| GoalRows {
+                goal: &r.goal,
+                tasks: r.tasks.iter().map(Task::summary).collect(),
+                metrics: r.metrics.clone(),
+            })
+            .collect();
+        list_table(w, &rows)
+    })
+}
+
+/// `rd list` as a table, from summary rows alone.
+pub fn list_rows(rows: &[GoalRows]) -> Result<()> {
+    list_table(&mut io::stdout().lock(), rows)
+}
+
+fn list_table(w: &mut dyn Write, rows: &[GoalRows]) -> Result<()> {
+    if rows.is_empty() {
+        writeln!(w, "No goals found.")?;
+        return Ok(());
+    }
+
+    for r in rows {
+        let goal = r.goal;
+        let metrics = &r.metrics;
+
+        writeln!(
+            w,
+            "{}  {}  ({}/{})",
+            style(goal.id()).cyan().bold(),
+            state_styled(goal.state().as_ref()),
+            metrics.tasks_completed(),
+            metrics.task_count(),
+        )?;
+        writeln!(w, "  {}", fit(goal.description(), 2))?;
+
+        if !r.tasks.is_empty() {
+            writeln!(w)?;
+            for task in &r.tasks {
+                writeln!(
+                    w,
+                    "  {:<10} {:<13} {}",
+                    style(task.id).cyan(),
+                    stage_styled(task.stage, task.label()),
+                    fit(task.headline, 27),
+                )?;
+            }
         }
+        writeln!(w)?;
+    }
+    Ok(())
+}
 
-        for r in results {
-            let goal = &r.goal;
-            let metrics = &r.metrics;
+// -- Plan --
 
-            writeln!(
-                w,
-                "{}  {}  ({}/{})",
-                style(goal.id()).cyan().bold(),
-                state_styled(goal.state().as_ref()),
-                metrics.tasks_completed(),
-                metrics.task_count(),
-            )?;
-            writeln!(w, "  {}", truncate(goal.description(), 80))?;
+pub fn plan_validation(validation: &Validation, json: bool) -> Result<()> {
+    json_or(validation, json, |w| {
+        for issue in &validation.issues {
+            let label = match issue.severity {
+                Severity::Error => style("error:").red().bold(),
+                Severity::Warning => style("warning:").yellow().bold(),
+            };
+            writeln!(w, "{label} {}", issue.message)?;
+        }
 
-            if !r.tasks.is_empty() {
+        if let Some(goal) = &validation.goal
+            && !validation.preview.is_empty()
+        {
+            if !validation.issues.is_empty() {
                 writeln!(w)?;
-                for task in &r.tasks {
+            }
+            writeln!(w, "Goal: {}", fit(goal, 6))?;
+            let width = validation
+                .preview
+                .iter()
+                .map(|t| t.name.len())
+                .max()
+                .unwrap_or_default();
+            for (i, task) in validation.preview.iter().enumerate() {
+                let number = format!("{:>3}.", i + 1);
+                writeln!(
+                    w,
+                    "{number} {:<width$}  {}",
+                    style(&task.name).cyan(),
+                    fit(&task.description, number.len() + width + 3),
+                )?;
+                if !task.blocked_by.is_empty() {
                     writeln!(
                         w,
-                        "  {:<10} {:<13} {}",
-                        style(task.id()).cyan(),
-                        state_styled(task.state().as_ref()),
-                        truncate(task.description(), 60),
+                        "{:<pad$}{} {}",
+                        "",
+                        style("└─ after").dim(),
+                        task.blocked_by.join(", "),
+                        pad = number.len() + 1,
                     )?;
                 }
             }
+        }
+
+        let errors = validation
+            .issues
+            .iter()
+            .filter(|i| i.severity == Severity::Error)
+            .count();
+        let warnings = validation.issues.len() - errors;
+        writeln!(w)?;
+        writeln!(w, "{errors} error(s), {warnings} warning(s)")?;
+        Ok(())
+    })
+}
+
+pub fn plan_created(result: &GoalWithTasks, json: bool) -> Result<()> {
+    #[derive(Serialize)]
+    struct PlanEntry<'a> {
+        #[serde(flatten)]
+        goal: &'a Goal,
+        tasks: &'a [Task],
+    }
+
+    let entry = PlanEntry {
+        goal: &result.goal,
+        tasks: &result.tasks,
+    };
+
+    json_or(&entry, json, |w| {
+        writeln!(w)?;
+        writeln!(w, "Created goal: {}", style(result.goal.id()).cyan().bold())?;
+        writeln!(w, "  {}", fit(result.goal.description(), 2))?;
+
+        if !result.tasks.is_empty() {
             writeln!(w)?;
         }
+        for task in &result.tasks {
+            writeln!(
+                w,
+                "  {:<10} {}",
+                style(task.id()).cyan(),
+                fit(task.headline(), 13),
+            )?;
+            if !task.blocked_by().is_empty() {
+                writeln!(
+                    w,
+                    "  {:<10} {} {}",
+                    "",
+                    style("└─ after").dim(),
+                    task.blocked_by().join(", ")
+                )?;
+            }
+        }
         Ok(())
     })
 }
 
 // -- Prep --
 
+pub fn digest(digest: &Digest, text: &str, json: bool) -> Result<()> {
+    json_or(digest, json, |w| {
+        write!(w, "{text}")?;
+        Ok(())
+    })
+}
+
+pub fn digest_delivered(delivery: &Delivery) -> Result<()> {
+    let mut w = io::stdout().lock();
+    if let Some(path) = &delivery.path {
+        writeln!(w, "{} {}", style("Wrote digest to").green(), path.display())?;
+    }
+    if let Some(command) = &delivery.command {
+        writeln!(w, "{} {command}", style("Sent digest to").green())?;
+    }
+    Ok(())
+}
+
+pub fn summary(digest: &str) -> Result<()> {
+    let mut w = io::stdout().lock();
+    write!(w, "{digest}")?;
+    Ok(())
+}
+
 pub fn prep(text: &str) -> Result<()> {
     let mut w = io::stdout().lock();
     writeln!(w, "{text}")?;
     Ok(())
 }
 
+pub fn capabilities(capabilities: &Capabilities, json: bool) -> Result<()> {
+    json_or(capabilities, json, |w| {
+        writeln!(w, "{}", style("Commands:").bold())?;
+        for command in &capabilities.commands {
+            let usage: Vec<String> = command.args.iter().map(ArgSpec::usage).collect();
+            writeln!(
+                w,
+                "  {} {}",
+                style(&command.name).cyan(),
+                fit(&usage.join(" "), command.name.len() + 3)
+            )?;
+            if let Some(about) = &command.about {
+                writeln!(w, "    {}", style(fit(about, 4)).dim())?;
+            }
+        }
+
+        writeln!(w)?;
+        writeln!(w, "{}", style("Task states:").bold())?;
+        for spec in &capabilities.task_states {
+            writeln!(
+                w,
+                "  {} -> {}",
+                spec.state.as_ref(),
+                describe_next(&spec.state)
+            )?;
+        }
+        writeln!(w)?;
+        writeln!(w, "{}", style("Goal states:").bold())?;
+        for spec in &capabilities.goal_states {
+            writeln!(
+                w,
+                "  {} -> {}",
+                spec.state.as_ref(),
+                describe_next(&spec.state)
+            )?;
+        }
+        Ok(())
+    })
+}
+
+/// Tool definitions are only useful as JSON, so there is no human form.
+pub fn toolspec(tools: &[Tool], format: ToolFormat) -> Result<()> {
+    let tools: Vec<serde_json::Value> = tools.iter().map(|t| t.to_json(format)).collect();
+    json_or(&tools, true, |_| Ok(()))
+}
+
 // -- Helpers --
 
+/// Print a non-fatal warning to stderr so it never pollutes JSON on stdout.
+pub fn warning(message: &str) -> Result<()> {
+    let mut w = io::stderr().lock();
+    writeln!(w, "{} {message}", style("warning:").yellow().bold())?;
+    Ok(())
+}
+
+/// Print a fatal error to stderr. With `--json` it is written as
+/// `{"error": {"kind": ..., "message": ...}}` so callers can branch on the kind.
+pub fn error(err: &anyhow::Error, json: bool) {
+    let mut w = io::stderr().lock();
+    if !json {
+        let _ = writeln!(w, "Error: {err:?}");
+        return;
+    }
+
+    let body = match err.downcast_ref::<Error>() {
+        Some(radial_err) => serde_json::to_value(radial_err).unwrap_or_default(),
+        None => serde_json::json!({ "kind": "other", "message": format!("{err:#}") }),
+    };
+    let _ = writeln!(w, "{}", serde_json::json!({ "error": body }));
+}
+
+/// A length of time such as `850ms`, `5m 30s` or `1h 12m`, down to the
+/// second under an hour. JSON keeps milliseconds.
+pub(crate) fn format_duration(duration: SignedDuration) -> String {
+    let ms = duration.as_millis().max(0);
+    let secs = ms / 1000;
+    match secs {
+        0 => format!("{ms}ms"),
+        1..60 => format!("{secs}s"),
+        60..3600 if secs % 60 == 0 => format!("{}m", secs / 60),
+        60..3600 => format!("{}m {}s", secs / 60, secs % 60),
+        _ => format_age(duration),
+    }
+}
+
+/// Compact age such as `45m`, `3h 12m`, or `2d 4h`.
+pub(crate) fn format_age(age: SignedDuration) -> String {
+    let mins = age.as_secs().max(0) / 60;
+    let (days, hours, mins) = (mins / 1440, mins / 60 % 24, mins % 60);
+    match (days, hours) {
+        (0, 0) => format!("{mins}m"),
+        (0, _) => format!("{hours}h {mins}m"),
+        _ => format!("{days}d {hours}h"),
+    }
+}
+
+/// The time zone human output is shown in.
+fn display_tz() -> TimeZone {
+    if options().utc {
+        TimeZone::UTC
+    } else {
+        TimeZone::system()
+    }
+}
+
+/// A timestamp for human output: local (or UTC) wall time and how long ago
+/// it was, e.g. `2024-05-01 14:03 (2h 5m ago)`. JSON keeps RFC 3339.
+pub(crate) fn format_time(ts: Timestamp) -> String {
+    format_time_in(ts, Timestamp::now(), &display_tz())
+}
+
+fn format_time_in(ts: Timestamp, now: Timestamp, tz: &TimeZone) -> String {
+    let zoned = ts.to_zoned(tz.clone());
+    let suffix = if *tz == TimeZone::UTC { " UTC" } else { "" };
+    let age = now.duration_since(ts);
+    let relative = if age.abs() < SignedDuration::from_mins(1) {
+        "just now".to_string()
+    } else if age.is_negative() {
+        format!("in {}", format_age(-age))
+    } else {
+        format!("{} ago", format_age(age))
+    };
+    format!("{}{suffix} ({relative})", zoned.strftime("%Y-%m-%d %H:%M"))
+}
+
 /// Write a labeled field: `{label}  {value}` with consistent alignment.
 fn field(w: &mut dyn Write, label: &str, value: &str) -> Result<()> {
     writeln!(w, "{:<14} {}", style(label).dim(), value)?;
     Ok(())
 }
 
+/// A task's state, showing the custom state it is in, if any, in the color
+/// `[states]` gives it.
+fn task_state(task: &Task) -> console::StyledObject<&str> {
+    stage_styled(task.stage(), task.label())
+}
+
+/// A task's `label`, in its custom state's color if it is in one.
+fn stage_styled<'a>(stage: Option<&'a str>, label: &'a str) -> console::StyledObject<&'a str> {
+    let Some(stage) = stage else {
+        return state_styled(label);
+    };
+    let color = options()
+        .state_colors
+        .iter()
+        .find(|(name, _)| name == stage)
+        .map_or(StateColor::White, |(_, color)| *color);
+    style(stage).fg(match color {
+        StateColor::Black => console::Color::Black,
+        StateColor::Red => console::Color::Red,
+        StateColor::Green => console::Color::Green,
+        StateColor::Yellow => console::Color::Yellow,
+        StateColor::Blue => console::Color::Blue,
+        StateColor::Magenta => console::Color::Magenta,
+        StateColor::Cyan => console::Color::Cyan,
+        StateColor::White => console::Color::White,
+    })
+}
+
 /// Apply color to a state string based on its value.
 fn state_styled(state: &str) -> console::StyledObject<&str> {
     match state {
@@ -635,3 +3199,113 @@ fn state_styled(state: &str) -> console::StyledObject<&str> {
         _ => style(state).white(),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    // Each list item gets a line; strings are bare, missing fields empty,
+    // and escapes and unclosed braces are handled.
+    #[rstest]
+    #[case::list(
+        "{{id}}\\t{{state}}",
+        serde_json::json!([{"id": "t1", "state": "pending"}, {"id": "t2"}]),
+        "t1\tpending\nt2\t\n"
+    )]
+    #[case::nested(
+        "{{ id }}: {{metrics.tokens}} {{tags.0}}",
+        serde_json::json!({"id": "g1", "metrics": {"tokens": 5}, "tags": ["a"]}),
+        "g1: 5 a\n"
+    )]
+    #[case::unclosed("{{id} {{", serde_json::json!({"id": "x"}), "{{id} {{\n")]
+    fn fills_templates(#[case] template: &str, #[case] value: Value, #[case] expected: &str) {
+        let mut out = Vec::new();
+        fill_template(&mut out, template, &value).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), expected);
+    }
+
+    // Field paths reach into nested objects and through arrays, and fields
+    // a value lacks are simply left out.
+    #[rstest]
+    #[case::top(&["id"], serde_json::json!([{"id": "t1"}, {"id": "t2"}]))]
+    #[case::nested(
+        &["id", "metrics.tokens"],
+        serde_json::json!([{"id": "t1", "metrics": {"tokens": 5}}, {"id": "t2", "metrics": {}}])
+    )]
+    #[case::whole_and_nested(
+        &["metrics", "metrics.tokens"],
+        serde_json::json!([{"metrics": {"tokens": 5, "ms": 9}}, {"metrics": {}}])
+    )]
+    #[case::missing(&["nope"], serde_json::json!([{}, {}]))]
+    fn projects_field_paths(#[case] fields: &[&str], #[case] expected: Value) {
+        let value = serde_json::json!([
+            {"id": "t1", "state": "pending", "metrics": {"tokens": 5, "ms": 9}},
+            {"id": "t2", "state": "failed", "metrics": {}},
+        ]);
+        let paths: Vec<Vec<&str>> = fields.iter().map(|f| f.split('.').collect()).collect();
+        assert_eq!(project(&value, &paths), expected);
+    }
+
+    // Truncation keeps only the first line and caps it at `max` display
+    // columns, including the ellipsis.
+    #[rstest]
+    #[case::short("hello", 10, "hello")]
+    #[case::exact("hello", 5, "hello")]
+    #[case::long("hello world", 8, "hello w…")]
+    #[case::first_line_only("one\ntwo", 10, "one")]
+    fn truncate_caps_first_line(#[case] input: &str, #[case] max: usize, #[case] expected: &str) {
+        assert_eq!(truncate(input, max), expected);
+    }
+
+    // Byte slicing used to panic when the cut landed inside a multibyte
+    // character; truncation must respect character boundaries.
+    #[rstest]
+    #[case::accents("ééééééééé", 5)]
+    #[case::cjk("日本語のテキストです", 7)]
+    #[case::emoji_dash("build — deploy — verify", 9)]
+    fn truncate_is_char_safe(#[case] input: &str, #[case] max: usize) {
+        let out = truncate(input, max);
+        assert!(console::measure_text_width(&out) <= max);
+        assert!(out.ends_with('…'));
+    }
+
+    #[rstest]
+    #[case::minutes(45 * 60, "45m")]
+    #[case::hours(3 * 3600 + 12 * 60, "3h 12m")]
+    #[case::days(2 * 86400 + 4 * 3600 + 59, "2d 4h")]
+    fn format_age_is_compact(#[case] secs: i64, #[case] expected: &str) {
+        assert_eq!(format_age(SignedDuration::from_secs(secs)), expected);
+    }
+
+    // Durations keep seconds under an hour and milliseconds under a second.
+    #[rstest]
+    #[case::millis(850, "850ms")]
+    #[case::seconds(42_000, "42s")]
+    #[case::minutes(300_000, "5m")]
+    #[case::minutes_and_seconds(330_500, "5m 30s")]
+    #[case::hours(4_320_000, "1h 12m")]
+    fn format_duration_is_humane(#[case] ms: i64, #[case] expected: &str) {
+        assert_eq!(format_duration(SignedDuration::from_millis(ms)), expected);
+    }
+
+    // Human timestamps show wall time in the chosen zone and a relative
+    // age; times within a minute read "just now".
+    #[rstest]
+    #[case::ago(-(2 * 3600 + 5 * 60), "2024-05-01 12:00 UTC (2h 5m ago)")]
+    #[case::recent(-30, "2024-05-01 12:00 UTC (just now)")]
+    #[case::future(45 * 60, "2024-05-01 12:00 UTC (in 45m)")]
+    fn format_time_is_relative(#[case] offset_secs: i64, #[case] expected: &str) {
+        let ts: Timestamp = "2024-05-01T12:00:00Z".parse().unwrap();
+        let now = ts - SignedDuration::from_secs(offset_secs);
+        assert_eq!(format_time_in(ts, now, &TimeZone::UTC), expected);
+    }
+
+    // Non-UTC zones convert the wall time and drop the UTC marker.
+    #[rstest]
+    fn format_time_converts_zone() {
+        let ts: Timestamp = "2024-05-01T12:00:00Z".parse().unwrap();
+        let tz = TimeZone::fixed(jiff::tz::offset(-5));
+        assert_eq!(format_time_in(ts, ts, &tz), "2024-05-01 07:00 (just now)");
+    }
+}
@@ -0,0 +1,98 @@
+//! A progress line on stderr for commands that work through many records,
+//! so a large sync or clean doesn't look hung. It only draws when stderr is
+//! a terminal and `--no-progress` wasn't given, and clears itself when done.
+
+use console::{Term, style};
+
+use crate::output;
+
+/// Width of the bar in columns.
+const BAR_WIDTH: usize = 30;
+
+/// Fewer items than this finish too quickly to be worth a bar.
+const MIN_ITEMS: usize = 50;
+
+pub struct Progress {
+    term: Option<Term>,
+    label: &'static str,
+    total: usize,
+    done: usize,
+    drawn: usize,
+}
+
+impl Progress {
+    pub fn new(label: &'static str, total: usize) -> Self {
+        let term = Term::stderr();
+        let show = total >= MIN_ITEMS && term.is_term() && !output::no_progress();
+        let progress = Self {
+            term: show.then_some(term),
+            label,
+            total,
+            done: 0,
+            drawn: 0,
+        };
+        progress.draw();
+        progress
+    }
+
+    /// Count one more item done, redrawing when the bar moves.
+    pub fn inc(&mut self) {
+        self.done = (self.done + 1).min(self.total);
+        let filled = self.filled();
+        if filled != self.drawn || self.done == self.total {
+            self.drawn = filled;
+            self.draw();
+        }
+    }
+
+    fn filled(&self) -> usize {
+        (self.done * BAR_WIDTH)
+            .checked_div(self.total)
+            .unwrap_or(BAR_WIDTH)
+    }
+
+    fn draw(&self) {
+        let Some(term) = &self.term else {
+            return;
+        };
+        let filled = self.filled();
+        let bar = format!("{}{}", "=".repeat(filled), " ".repeat(BAR_WIDTH - filled));
+        // Drawing is best effort; a broken stderr must not fail the command.
+        let _ = term.clear_line();
+        let _ = term.write_str(&format!(
+            "{} [{}] {}/{}",
+            self.label,
+            style(bar).cyan(),
+            self.done,
+            self.total
+        ));
+    }
+}
+
+impl Drop for Progress {
+    fn drop(&mut self) {
+        if let Some(term) = &self.term {
+            let _ = term.clear_line();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    // The bar fills in proportion and never runs past the total.
+    #[rstest]
+    #[case::start(0, 0)]
+    #[case::half(50, 15)]
+    #[case::done(100, 30)]
+    #[case::overrun(120, 30)]
+    fn fills_in_proportion(#[case] steps: usize, #[case] filled: usize) {
+        let mut progress = Progress::new("Testing", 100);
+        for _ in 0..steps {
+            progress.inc();
+        }
+        assert_eq!(progress.filled(), filled);
+    }
+}
@@ -0,0 +1,306 @@
+//! `--where` filters: a small expression language over task fields, such as
+//! `state=in_progress AND tokens>10000`. Conditions are joined with `AND`
+//! and `OR`, with `AND` binding tighter; there are no parentheses.
+//!
+//! Each condition is `field op value`, where `op` is one of `=`, `!=`, `>`,
+//! `>=`, `<`, `<=` or `~` (contains, ignoring case). Numbers and priorities
+//! compare by size, times (a timestamp or an offset from now such as `-2h`)
+//! by order, and text only by `=`, `!=` and `~`. An empty value stands for
+//! an unset field, so `assignee=` finds unassigned tasks.
+
+use jiff::Timestamp;
+use strum::{AsRefStr, EnumString, VariantNames};
+
+use crate::helpers::{parse_when, split_words};
+use crate::models::{Priority, Task};
+
+/// Fields a condition can test.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, AsRefStr, EnumString, VariantNames)]
+#[strum(serialize_all = "snake_case")]
+enum Field {
+    Id,
+    Goal,
+    /// The core state or the custom state the task is in.
+    State,
+    Priority,
+    Assignee,
+    Title,
+    Description,
+    /// Any of the task's references, as `kind:target`.
+    Ref,
+    Tokens,
+    /// Milliseconds, summed over attempts.
+    Elapsed,
+    Retries,
+    Attempts,
+    Created,
+    Updated,
+    Completed,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Op {
+    Eq,
+    Ne,
+    Gt,
+    Ge,
+    Lt,
+    Le,
+    Contains,
+}
+
+impl Op {
+    /// The operator at the start of `text`, and its length.
+    fn at(text: &str) -> Option<(Self, usize)> {
+        [
+            ("!=", Self::Ne),
+            (">=", Self::Ge),
+            ("<=", Self::Le),
+            ("=", Self::Eq),
+            (">", Self::Gt),
+            ("<", Self::Lt),
+            ("~", Self::Contains),
+        ]
+        .into_iter()
+        .find(|(symbol, _)| text.starts_with(symbol))
+        .map(|(symbol, op)| (op, symbol.len()))
+    }
+
+    fn orders(self) -> bool {
+        matches!(self, Self::Gt | Self::Ge | Self::Lt | Self::Le)
+    }
+
+    fn compare<T: Ord + Copy>(self, left: T, right: T) -> bool {
+        match self {
+            Self::Eq => left == right,
+            Self::Ne => left != right,
+            Self::Gt => left > right,
+            Self::Ge => left >= right,
+            Self::Lt => left < right,
+            Self::Le => left <= right,
+            Self::Contains => false,
+        }
+    }
+}
+
+/// A condition's value, read according to its field.
+#[derive(Debug, Clone)]
+enum Value {
+    Text(String),
+    Number(i64),
+    Priority(Priority),
+    Time(Option<Timestamp>),
+}
+
+#[derive(Debug, Clone)]
+struct Condition {
+    field: Field,
+    op: Op,
+    value: Value,
+}
+
+/// A parsed `--where` expression: tasks match when every condition of at
+/// least one `OR` branch holds.
+#[derive(Debug, Clone)]
+pub struct Filter {
+    branches: Vec<Vec<Condition>>,
+}
+
+/// Parse a `--where` expression, for use as a clap value parser.
+pub fn parse(text: &str) -> Result<Filter, String> {
+    let words = split_words(text).map_err(|e| e.to_string())?;
+    let mut branches = vec![Vec::new()];
+    let mut clause: Vec<String> = Vec::new();
+    let mut last_keyword = None;
+    for word in words {
+        let keyword = word.to_ascii_uppercase();
+        if keyword != "AND" && keyword != "OR" {
+            clause.push(word);
+            continue;
+        }
+        if clause.is_empty() {
+            return Err(format!("Expected a condition before {keyword}"));
+        }
+        branches
+            .last_mut()
+            .unwrap()
+            .push(condition(&clause.join(" "))?);
+        clause.clear();
+        if keyword == "OR" {
+            branches.push(Vec::new());
+        }
+        last_keyword = Some(keyword);
+    }
+    if clause.is_empty() {
+        return Err(match last_keyword {
+            Some(keyword) => format!("Expected a condition after {keyword}"),
+            None => "Expected at least one condition, e.g. state=pending".to_string(),
+        });
+    }
+    branches
+        .last_mut()
+        .unwrap()
+        .push(condition(&clause.join(" "))?);
+    Ok(Filter { branches })
+}
+
+fn condition(text: &str) -> Result<Condition, String> {
+    let (at, (op, len)) = text
+        .char_indices()
+        .find_map(|(i, _)| Op::at(&text[i..]).map(|found| (i, found)))
+        .ok_or_else(|| format!("'{text}' has no operator; use one of = != > >= < <= ~"))?;
+    let name = text[..at].trim();
+    let raw = text[at + len..].trim();
+    let field: Field = name.parse().map_err(|_| {
+        format!(
+            "Unknown field '{name}'; expected one of: {}",
+            Field::VARIANTS.join(", ")
+        )
+    })?;
+
+    let value = match field {
+        Field::Tokens | Field::Elapsed | Field::Retries | Field::Attempts => raw
+            .parse()
+            .map(Value::Number)
+            .map_err(|_| format!("{name} takes a number, not '{raw}'"))?,
+        Field::Priority => raw.parse().map(Value::Priority).map_err(|_| {
+            format!("Unknown priority '{raw}'; expected low, normal, high or urgent")
+        })?,
+        Field::Created | Field::Updated | Field::Completed if raw.is_empty() => Value::Time(None),
+        Field::Created | Field::Updated | Field::Completed => Value::Time(Some(parse_when(raw)?)),
+        _ if op.orders() => {
+            return Err(format!("{name} is text; compare it with =, != or ~"));
+        }
+        _ => Value::Text(raw.to_string()),
+    };
+    if op == Op::Contains && !matches!(value, Value::Text(_)) {
+        return Err(format!("~ only applies to text fields, not {name}"));
+    }
+    Ok(Condition { field, op, value })
+}
+
+impl Filter {
+    pub fn matches(&self, task: &Task) -> bool {
+        self.branches
+            .iter()
+            .any(|branch| branch.iter().all(|c| c.holds(task)))
+    }
+}
+
+impl Condition {
+    fn holds(&self, task: &Task) -> bool {
+        match &self.value {
+            Value::Number(n) => self.op.compare(self.number(task), *n),
+            Value::Priority(p) => self.op.compare(task.priority(), *p),
+            Value::Time(at) => self.op.compare(self.time(task), *at),
+            Value::Text(text) => {
+                let state = task.state();
+                let mut values = self.text(task);
+                if self.field == Field::State {
+                    values.push(Some(state.as_ref()));
+                }
+                match self.op {
+                    Op::Contains => {
+                        let needle = text.to_lowercase();
+                        values
+                            .iter()
+                            .any(|v| v.is_some_and(|v| v.to_lowercase().contains(&needle)))
+                    }
+                    Op::Ne => !values.iter().any(|v| v.unwrap_or_default() == text),
+                    _ => values.iter().any(|v| v.unwrap_or_default() == text),
+                }
+            }
+        }
+    }
+
+    fn number(&self, task: &Task) -> i64 {
+        let metrics = task.metrics();
+        match self.field {
+            Field::Tokens => metrics.tokens(),
+            Field::Elapsed => metrics.elapsed_ms(),
+            Field::Retries => metrics.retry_count(),
+            _ => i64::try_from(task.attempts().len()).unwrap_or(i64::MAX),
+        }
+    }
+
+    fn time(&self, task: &Task) -> Option<Timestamp> {
+        match self.field {
+            Field::Created => Some(task.created_at()),
+            Field::Updated => Some(task.updated_at()),
+            _ => task.completed_at(),
+        }
+    }
+
+    /// The text a condition is tested against. For `state` this is only the
+    /// custom state, if any; `holds` adds the core one.
+    fn text<'a>(&self, task: &'a Task) -> Vec<Option<&'a str>> {
+        match self.field {
+            Field::Id => vec![Some(task.id())],
+            Field::Goal => vec![Some(task.goal_id())],
+            Field::State => task.stage().map(Some).into_iter().collect(),
+            Field::Assignee => vec![task.assignee()],
+            Field::Title => vec![task.title()],
+            Field::Ref if task.refs().is_empty() => vec![None],
+            Field::Ref => task.refs().iter().map(|r| Some(r.as_str())).collect(),
+            _ => vec![Some(task.description())],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{TaskMetrics, TaskState};
+    use crate::testing::TaskBuilder;
+    use rstest::rstest;
+
+    fn task() -> Task {
+        TaskBuilder::new("g1", "Add the login form")
+            .id("t1")
+            .state(TaskState::InProgress)
+            .priority(Priority::High)
+            .assignee("alice")
+            .metrics(TaskMetrics::new(12_000, 500, 1))
+            .reference("github:acme/web#12".parse().unwrap())
+            .reference("jira:WEB-7".parse().unwrap())
+            .build()
+    }
+
+    // Conditions read each field in its own terms: numbers and priorities
+    // by size, text by equality or substring, and empty for unset.
+    #[rstest]
+    #[case::state("state=in_progress", true)]
+    #[case::state_ne("state!=in_progress", false)]
+    #[case::tokens("tokens>10000", true)]
+    #[case::tokens_le("tokens <= 10000", false)]
+    #[case::priority("priority>=high", true)]
+    #[case::priority_lt("priority<high", false)]
+    #[case::contains("description~LOGIN", true)]
+    #[case::quoted("description~'login form'", true)]
+    #[case::unset("assignee=", false)]
+    #[case::and("state=in_progress AND assignee=bob", false)]
+    #[case::or("assignee=bob OR retries=1", true)]
+    #[case::and_binds_tighter("assignee=bob AND retries=1 OR goal=g1", true)]
+    #[case::created("created<1h", true)]
+    #[case::completed("completed=", true)]
+    #[case::reference("ref=jira:WEB-7", true)]
+    #[case::reference_contains("ref~acme/web", true)]
+    #[case::reference_ne("ref!=github:acme/web#12", false)]
+    fn matches_tasks(#[case] expr: &str, #[case] expected: bool) {
+        assert_eq!(parse(expr).unwrap().matches(&task()), expected);
+    }
+
+    // Mistakes are reported when the expression is parsed, naming the fix.
+    #[rstest]
+    #[case::field("tag=backend", "Unknown field 'tag'")]
+    #[case::number("tokens>lots", "tokens takes a number")]
+    #[case::order("state>pending", "compare it with =, != or ~")]
+    #[case::operator("state", "has no operator")]
+    #[case::leading("OR state=pending", "Expected a condition before OR")]
+    #[case::dangling("state=pending AND", "Expected a condition after AND")]
+    #[case::empty("", "Expected at least one condition")]
+    fn rejects_bad_expressions(#[case] expr: &str, #[case] message: &str) {
+        let err = parse(expr).unwrap_err();
+        assert!(err.contains(message), "{err}");
+    }
+}
@@ -0,0 +1,129 @@
+//! The global list of projects that `rd all` reports on. It lives in
+//! `$RADIAL_REGISTRY` if set, otherwise in `radial/projects.toml` under
+//! `$XDG_CONFIG_HOME` or `~/.config`.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result, anyhow, bail};
+use serde::{Deserialize, Serialize};
+
+use crate::db::atomic_write;
+
+pub const REGISTRY_ENV: &str = "RADIAL_REGISTRY";
+
+/// A registered project: a directory holding a `.radial/`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Project {
+    pub name: String,
+    pub path: PathBuf,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Registry {
+    #[serde(default, rename = "project")]
+    projects: Vec<Project>,
+}
+
+impl Registry {
+    /// Where the registry is kept, or `None` without a home directory.
+    pub fn path() -> Option<PathBuf> {
+        if let Some(path) = std::env::var_os(REGISTRY_ENV) {
+            return Some(PathBuf::from(path));
+        }
+        let config = std::env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .or_else(|| {
+                std::env::var_os("HOME")
+                    .or_else(|| std::env::var_os("USERPROFILE"))
+                    .map(|home| Path::new(&home).join(".config"))
+            })?;
+        Some(config.join("radial").join("projects.toml"))
+    }
+
+    /// The registry, empty if nothing has been registered yet.
+    pub fn load() -> Result<Self> {
+        let path = Self::path().ok_or_else(|| anyhow!("No home directory for the registry"))?;
+        match fs::read_to_string(&path) {
+            Ok(text) => {
+                toml::from_str(&text).with_context(|| format!("Failed to parse {}", path.display()))
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e).with_context(|| format!("Failed to read {}", path.display())),
+        }
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let path = Self::path().ok_or_else(|| anyhow!("No home directory for the registry"))?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)
+                .with_context(|| format!("Failed to create {}", dir.display()))?;
+        }
+        atomic_write(&path, toml::to_string(self)?.as_bytes())
+    }
+
+    pub fn projects(&self) -> &[Project] {
+        &self.projects
+    }
+
+    /// Register `project`, replacing any entry for the same directory. A
+    /// name already taken by another directory is refused.
+    pub fn add(&mut self, project: Project) -> Result<()> {
+        if let Some(other) = self
+            .projects
+            .iter()
+            .find(|p| p.name == project.name && p.path != project.path)
+        {
+            bail!(
+                "'{}' already names {}. Pick another with --name",
+                other.name,
+                other.path.display()
+            );
+        }
+        self.projects.retain(|p| p.path != project.path);
+        self.projects.push(project);
+        Ok(())
+    }
+
+    /// Drop the project called `name`, returning it.
+    pub fn remove(&mut self, name: &str) -> Option<Project> {
+        let index = self.projects.iter().position(|p| p.name == name)?;
+        Some(self.projects.remove(index))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    fn project(name: &str, path: &str) -> Project {
+        Project {
+            name: name.to_string(),
+            path: PathBuf::from(path),
+        }
+    }
+
+    // Re-adding a directory renames it, a name can't point at two
+    // directories, and the registry survives a round trip through TOML.
+    #[rstest]
+    fn adds_and_removes_projects() {
+        let mut registry = Registry::default();
+        registry.add(project("api", "/src/api")).unwrap();
+        registry.add(project("web", "/src/web")).unwrap();
+        registry.add(project("backend", "/src/api")).unwrap();
+        assert!(registry.add(project("web", "/src/other")).is_err());
+
+        let text = toml::to_string(&registry).unwrap();
+        let mut registry: Registry = toml::from_str(&text).unwrap();
+        let names: Vec<&str> = registry
+            .projects()
+            .iter()
+            .map(|p| p.name.as_str())
+            .collect();
+        assert_eq!(names, ["web", "backend"]);
+
+        assert_eq!(registry.remove("web").unwrap().path, Path::new("/src/web"));
+        assert!(registry.remove("web").is_none());
+    }
+}
@@ -0,0 +1,252 @@
+//! Running a task's contract commands under the limits in `[sandbox]`: a
+//! timeout, the directories they may run in, and optionally a container.
+//! Output is captured to a log under `.radial/attachments/<task-id>/`
+//! rather than shown as it runs.
+
+use std::fmt;
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::str::FromStr;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result};
+use jiff::{SignedDuration, Timestamp};
+use serde::{Deserialize, Serialize};
+
+use crate::db::Database;
+
+pub const ATTACHMENTS_DIR: &str = "attachments";
+
+/// How often a running command is checked for having exited.
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// What a command is run inside.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum Sandbox {
+    /// Directly in the shell.
+    #[default]
+    None,
+    /// In a throwaway container of this image, with the working directory
+    /// mounted at the same path.
+    Docker(String),
+}
+
+impl FromStr for Sandbox {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "" | "none" => Ok(Self::None),
+            spec => match spec.strip_prefix("docker:") {
+                Some(image) if !image.is_empty() => Ok(Self::Docker(image.to_string())),
+                _ => Err(format!(
+                    "Unknown sandbox '{spec}': expected none or docker:<image>"
+                )),
+            },
+        }
+    }
+}
+
+impl fmt::Display for Sandbox {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::None => write!(f, "none"),
+            Self::Docker(image) => write!(f, "docker:{image}"),
+        }
+    }
+}
+
+impl TryFrom<String> for Sandbox {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<Sandbox> for String {
+    fn from(sandbox: Sandbox) -> Self {
+        sandbox.to_string()
+    }
+}
+
+/// A finished (or killed) command.
+#[derive(Debug, Clone, Serialize)]
+pub struct Run {
+    pub command: String,
+    pub sandbox: Sandbox,
+    pub dir: PathBuf,
+    pub passed: bool,
+    /// `None` when the command was killed or died from a signal.
+    pub exit_code: Option<i32>,
+    pub timed_out: bool,
+    pub elapsed_ms: i64,
+    /// Everything the command wrote to stdout and stderr.
+    pub log: PathBuf,
+}
+
+/// `dir` made absolute, if `[sandbox] allowed_dirs` lets commands run there.
+/// Relative entries are taken from the directory holding the store; with
+/// none listed, any directory is allowed.
+pub fn allowed_dir(dir: &Path, db: &Database) -> Result<Option<PathBuf>> {
+    let dir = dir
+        .canonicalize()
+        .with_context(|| format!("No such directory: {}", dir.display()))?;
+    let allowed = &db.config().sandbox.allowed_dirs;
+    if allowed.is_empty() {
+        return Ok(Some(dir));
+    }
+    let root = db.base_path().parent().unwrap_or(db.base_path());
+    let permitted = allowed
+        .iter()
+        .filter_map(|a| root.join(a).canonicalize().ok())
+        .any(|a| dir.starts_with(a));
+    Ok(permitted.then_some(dir))
+}
+
+/// Where to keep the output of a `kind` run (such as `verify`) for `task_id`.
+/// An in-memory store has no directory of its own, so its logs go under the
+/// system temp directory instead.
+pub fn log_path(task_id: &str, kind: &str, db: &Database) -> PathBuf {
+    let base = if db.is_in_memory() {
+        std::env::temp_dir().join("radial-ephemeral")
+    } else {
+        db.base_path().to_path_buf()
+    };
+    base.join(ATTACHMENTS_DIR)
+        .join(task_id)
+        .join(format!("{kind}-{}.log", Timestamp::now().as_millisecond()))
+}
+
+/// Run `command` in `dir` inside `sandbox`, writing its output to `log` and
+/// killing it if it outlives `timeout`.
+pub fn run(
+    command: &str,
+    sandbox: &Sandbox,
+    dir: &Path,
+    log: &Path,
+    timeout: SignedDuration,
+) -> Result<Run> {
+    if let Some(parent) = log.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create {}", parent.display()))?;
+    }
+    let out = File::create(log).with_context(|| format!("Failed to create {}", log.display()))?;
+    let err = out.try_clone().context("Failed to share the log file")?;
+
+    // Named so a container outliving its timeout can be stopped.
+    let container = format!(
+        "radial-{}",
+        log.file_stem().unwrap_or_default().to_string_lossy()
+    );
+    let mut process = match sandbox {
+        Sandbox::None => {
+            let (shell, flag) = if cfg!(windows) {
+                ("cmd", "/C")
+            } else {
+                ("sh", "-c")
+            };
+            let mut process = Command::new(shell);
+            process.args([flag, command]).current_dir(dir);
+            process
+        }
+        Sandbox::Docker(image) => {
+            let mount = format!("{0}:{0}", dir.display());
+            let mut process = Command::new("docker");
+            process
+                .args(["run", "--rm", "--name", &container, "-v", &mount, "-w"])
+                .arg(dir)
+                .args([image.as_str(), "sh", "-c", command]);
+            process
+        }
+    };
+    let started = Instant::now();
+    let mut child = process
+        .stdin(Stdio::null())
+        .stdout(out)
+        .stderr(err)
+        .spawn()
+        .with_context(|| format!("Failed to run {command}"))?;
+
+    let deadline = started + timeout.unsigned_abs();
+    let (status, timed_out) = loop {
+        match child.try_wait().context("Failed to wait for command")? {
+            Some(status) => break (Some(status), false),
+            None if Instant::now() < deadline => thread::sleep(POLL_INTERVAL),
+            None => {
+                if matches!(sandbox, Sandbox::Docker(_)) {
+                    let _ = Command::new("docker")
+                        .args(["kill", &container])
+                        .stdout(Stdio::null())
+                        .stderr(Stdio::null())
+                        .status();
+                }
+                let _ = child.kill();
+                let _ = child.wait();
+                break (None, true);
+            }
+        }
+    };
+
+    Ok(Run {
+        command: command.to_string(),
+        sandbox: sandbox.clone(),
+        dir: dir.to_path_buf(),
+        passed: status.is_some_and(|s| s.success()),
+        exit_code: status.and_then(|s| s.code()),
+        timed_out,
+        elapsed_ms: i64::try_from(started.elapsed().as_millis()).unwrap_or(i64::MAX),
+        log: log.to_path_buf(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case::none("none", Ok(Sandbox::None))]
+    #[case::empty("", Ok(Sandbox::None))]
+    #[case::docker("docker:rust:1.85", Ok(Sandbox::Docker("rust:1.85".to_string())))]
+    #[case::no_image("docker:", Err(()))]
+    #[case::unknown("podman:rust", Err(()))]
+    fn parses_sandbox_specs(#[case] spec: &str, #[case] expected: Result<Sandbox, ()>) {
+        assert_eq!(spec.parse::<Sandbox>().map_err(|_| ()), expected);
+    }
+
+    // Output lands in the log, the exit status decides, and a command that
+    // outlives its timeout is killed.
+    #[cfg(unix)]
+    #[rstest]
+    fn runs_commands_and_captures_output() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let log = dir.path().join("logs/verify.log");
+        let timeout = SignedDuration::from_secs(10);
+
+        let failed = run(
+            "echo out; echo err >&2; exit 3",
+            &Sandbox::None,
+            dir.path(),
+            &log,
+            timeout,
+        )
+        .unwrap();
+        assert!(!failed.passed);
+        assert_eq!(failed.exit_code, Some(3));
+        assert_eq!(fs::read_to_string(&log).unwrap(), "out\nerr\n");
+
+        let slow = run(
+            "sleep 5",
+            &Sandbox::None,
+            dir.path(),
+            &log,
+            SignedDuration::from_millis(100),
+        )
+        .unwrap();
+        assert!(slow.timed_out && !slow.passed);
+    }
+}
@@ -0,0 +1,384 @@
+//! [`RadialStore`], for driving radial from another Rust program instead of
+//! running `rd`.
+//!
+//! ```no_run
+//! use radial::store::{Completion, NewTask, RadialStore};
+//!
+//! let store = RadialStore::open("/work/api/.radial")?.with_agent("builder-1");
+//! let goal = store.create_goal("Ship login", None)?;
+//! store.create_task(
+//!     goal.id(),
+//!     NewTask::new("Write the handler").contract("Schema", "POST /login", "cargo test"),
+//! )?;
+//! if let Some(claimed) = store.claim_next(goal.id())? {
+//!     store.complete(claimed.task.id(), Completion::new("Handler merged"))?;
+//! }
+//! # Ok::<(), radial::Error>(())
+//! ```
+
+use std::fs::{File, OpenOptions};
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use fs2::FileExt;
+use jiff::SignedDuration;
+
+use crate::commands::context::TaskContext;
+use crate::commands::ready::{self, ReadyTask};
+use crate::commands::task::{CompleteResult, FailResult, Usage};
+use crate::commands::{goal, task};
+use crate::db::Database;
+use crate::error::{Error, Result};
+use crate::models::{Goal, Postmortem, Priority, Reference, Task};
+use crate::{RADIAL_DIR, export, history};
+
+/// Held for the whole of each change, so two embedders sharing a store
+/// never interleave their reads and writes.
+pub const LOCK_FILE: &str = "store.lock";
+
+/// A radial store on disk. Nothing here prints, or reads the environment or
+/// the current directory: the store's path and the acting agent are given
+/// explicitly.
+///
+/// Each call is its own transaction. It reads the store fresh, so it sees
+/// changes made by `rd` or other processes since the last call, then writes
+/// its changes back along with history and the markdown mirror, as `rd`
+/// would if they are enabled.
+#[derive(Debug, Clone)]
+pub struct RadialStore {
+    path: PathBuf,
+    agent: Option<String>,
+}
+
+/// A task to add with [`RadialStore::create_task`].
+#[derive(Debug, Clone, Default)]
+pub struct NewTask {
+    pub description: String,
+    pub title: Option<String>,
+    pub receives: Option<String>,
+    pub produces: Option<String>,
+    pub verify: Option<String>,
+    pub blocked_by: Vec<String>,
+    pub assignee: Option<String>,
+    pub priority: Priority,
+    pub estimate: Option<SignedDuration>,
+    pub refs: Vec<Reference>,
+}
+
+impl NewTask {
+    pub fn new(description: impl Into<String>) -> Self {
+        Self {
+            description: description.into(),
+            ..Self::default()
+        }
+    }
+
+    #[must_use]
+    pub fn title(mut self, title: impl Into<String>) -> Self {
+        self.title = Some(title.into());
+        self
+    }
+
+    /// What the task receives, produces, and how it is verified. A task
+    /// without a contract is never ready.
+    #[must_use]
+    pub fn contract(
+        mut self,
+        receives: impl Into<String>,
+        produces: impl Into<String>,
+        verify: impl Into<String>,
+    ) -> Self {
+        self.receives = Some(receives.into());
+        self.produces = Some(produces.into());
+        self.verify = Some(verify.into());
+        self
+    }
+
+    #[must_use]
+    pub fn blocked_by(mut self, task_id: impl Into<String>) -> Self {
+        self.blocked_by.push(task_id.into());
+        self
+    }
+
+    #[must_use]
+    pub fn priority(mut self, priority: Priority) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    /// How long the task should take; see [`RadialStore::claim_next_within`].
+    #[must_use]
+    pub fn estimate(mut self, estimate: SignedDuration) -> Self {
+        self.estimate = Some(estimate);
+        self
+    }
+
+    /// An issue, pull request or ticket the task tracks.
+    #[must_use]
+    pub fn reference(mut self, reference: Reference) -> Self {
+        self.refs.push(reference);
+        self
+    }
+}
+
+/// How a task went, for [`RadialStore::complete`].
+#[derive(Debug, Clone, Default)]
+pub struct Completion {
+    pub result: String,
+    pub artifacts: Vec<String>,
+    pub tokens: Option<i64>,
+    pub elapsed_ms: Option<i64>,
+}
+
+impl Completion {
+    pub fn new(result: impl Into<String>) -> Self {
+        Self {
+            result: result.into(),
+            ..Self::default()
+        }
+    }
+}
+
+impl RadialStore {
+    /// Open the store at `path`: a `.radial` directory, a bare store, or a
+    /// directory with a `.radial` directory in it. Redirect files are not
+    /// followed, since they may name environment variables; open the
+    /// shared store's own path instead.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let nested = path.join(RADIAL_DIR);
+        let path = if nested.is_dir() {
+            nested
+        } else {
+            path.to_path_buf()
+        };
+        // Fail now rather than on first use if it isn't a store.
+        Database::open(&path)?;
+        Ok(Self { path, agent: None })
+    }
+
+    /// Act as `agent`: tasks claimed are assigned to it, and goals claimed
+    /// by another agent are refused, as with `RADIAL_AGENT` for `rd`.
+    #[must_use]
+    pub fn with_agent(mut self, agent: impl Into<String>) -> Self {
+        self.agent = Some(agent.into());
+        self
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn goals(&self) -> Result<Vec<Goal>> {
+        let db = self.read()?;
+        Ok(db.list_goals().into_iter().cloned().collect())
+    }
+
+    pub fn tasks(&self, goal_id: &str) -> Result<Vec<Task>> {
+        let db = self.read()?;
+        db.require_goal(goal_id)?;
+        Ok(db.list_tasks(goal_id).into_iter().cloned().collect())
+    }
+
+    pub fn task(&self, task_id: &str) -> Result<Task> {
+        self.read()?.require_task(task_id).cloned()
+    }
+
+    /// Tasks in the goal that can be started now, highest priority first.
+    pub fn ready(&self, goal_id: &str) -> Result<Vec<ReadyTask>> {
+        ready::run(goal_id, false, false, None, &self.read()?)
+    }
+
+    pub fn create_goal(
+        &self,
+        description: impl Into<String>,
+        title: Option<String>,
+    ) -> Result<Goal> {
+        self.transact(|db| goal::create(description.into(), title, None, None, None, None, db))
+    }
+
+    pub fn create_task(&self, goal_id: &str, new: NewTask) -> Result<Task> {
+        self.transact(|db| {
+            task::create(
+                goal_id,
+                new.description,
+                new.title,
+                new.receives,
+                new.produces,
+                new.verify,
+                (!new.blocked_by.is_empty()).then_some(new.blocked_by),
+                Vec::new(),
+                new.assignee,
+                new.priority,
+                None,
+                new.estimate,
+                Vec::new(),
+                new.refs,
+                db,
+            )
+        })
+    }
+
+    /// Start the goal's next ready task as this store's agent, with the
+    /// context needed to work on it. `None` if nothing is ready.
+    pub fn claim_next(&self, goal_id: &str) -> Result<Option<TaskContext>> {
+        let agent = self.agent.clone();
+        self.transact(|db| task::claim_next(goal_id, agent, None, db))
+    }
+
+    /// [`Self::claim_next`], taking only a task estimated to fit in
+    /// `budget`, such as the time left in a session.
+    pub fn claim_next_within(
+        &self,
+        goal_id: &str,
+        budget: SignedDuration,
+    ) -> Result<Option<TaskContext>> {
+        let agent = self.agent.clone();
+        self.transact(|db| task::claim_next(goal_id, agent, Some(budget), db))
+    }
+
+    pub fn complete(&self, task_id: &str, completion: Completion) -> Result<CompleteResult> {
+        self.transact(|db| {
+            task::complete(
+                task_id,
+                completion.result,
+                Some(completion.artifacts),
+                Usage {
+                    tokens: completion.tokens,
+                    elapsed_ms: completion.elapsed_ms,
+                    ..Usage::default()
+                },
+                Vec::new(),
+                db,
+            )
+        })
+    }
+
+    pub fn fail(&self, task_id: &str, postmortem: Option<Postmortem>) -> Result<FailResult> {
+        self.transact(|db| task::fail(task_id, None, None, postmortem, Usage::default(), db))
+    }
+
+    fn read(&self) -> Result<Database> {
+        let mut db = Database::open(&self.path)?;
+        db.set_agent(self.agent.clone());
+        Ok(db)
+    }
+
+    /// Run `change` against a fresh copy of the store under the store lock,
+    /// then record it as `rd` does.
+    fn transact<T>(&self, change: impl FnOnce(&mut Database) -> Result<T>) -> Result<T> {
+        let _lock = self.lock()?;
+        let mut db = self.read()?;
+        let before = db
+            .config()
+            .history
+            .enabled
+            .then(|| history::Snapshot::take(&db));
+
+        let value = change(&mut db)?;
+
+        if let Some(before) = before {
+            history::record(&before, &db).context("Failed to record history")?;
+        }
+        if db.config().export.markdown {
+            export::write_markdown(&db).context("Failed to export goal markdown")?;
+        }
+        Ok(value)
+    }
+
+    /// The store lock, released when the returned file is dropped.
+    fn lock(&self) -> Result<File> {
+        let path = self.path.join(LOCK_FILE);
+        let file = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(&path)
+            .with_context(|| format!("Failed to open {}", path.display()))?;
+        file.lock_exclusive()
+            .with_context(|| format!("Failed to lock {}", path.display()))
+            .map_err(Error::from)?;
+        Ok(file)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::TaskState;
+    use crate::testing::TempProject;
+    use rstest::rstest;
+
+    // A goal is worked end to end through the facade, and each call sees
+    // what the previous one wrote to disk.
+    #[rstest]
+    fn works_a_goal_through_the_facade() {
+        let project = TempProject::new().unwrap();
+        let store = RadialStore::open(project.path()).unwrap().with_agent("bot");
+
+        let goal = store.create_goal("Ship login", None).unwrap();
+        let first = store
+            .create_task(
+                goal.id(),
+                NewTask::new("Schema").contract("-", "SQL", "psql"),
+            )
+            .unwrap();
+        let second = store
+            .create_task(
+                goal.id(),
+                NewTask::new("Handler")
+                    .contract("SQL", "POST /login", "cargo test")
+                    .blocked_by(first.id()),
+            )
+            .unwrap();
+
+        let claimed = store.claim_next(goal.id()).unwrap().unwrap();
+        assert_eq!(claimed.task.id(), first.id());
+        assert_eq!(claimed.task.assignee(), Some("bot"));
+        assert!(store.claim_next(goal.id()).unwrap().is_none());
+
+        let done = store
+            .complete(first.id(), Completion::new("Migrated"))
+            .unwrap();
+        assert_eq!(done.unblocked_task_ids, [second.id()]);
+        assert_eq!(store.task(second.id()).unwrap().state(), TaskState::Pending);
+        assert_eq!(store.ready(goal.id()).unwrap().len(), 1);
+    }
+
+    // A refusing quota holds for library callers, not just the CLI: once the
+    // agent has spent its budget it can't claim more work.
+    #[rstest]
+    fn claim_respects_refusing_quota() {
+        let project = TempProject::new().unwrap();
+        std::fs::write(
+            project.radial_dir().join("config.toml"),
+            "[quotas]\ndaily_tokens = 100\naction = \"refuse\"\n",
+        )
+        .unwrap();
+        let store = RadialStore::open(project.path()).unwrap().with_agent("bot");
+        let goal = store.create_goal("Ship", None).unwrap();
+        for name in ["First", "Second"] {
+            store
+                .create_task(goal.id(), NewTask::new(name).contract("-", "-", "true"))
+                .unwrap();
+        }
+
+        let first = store.claim_next(goal.id()).unwrap().unwrap();
+        let spent = Completion {
+            tokens: Some(500),
+            ..Completion::new("Done")
+        };
+        store.complete(first.task.id(), spent).unwrap();
+
+        let err = store.claim_next(goal.id()).unwrap_err();
+        assert_eq!(err.kind(), "conflict");
+        assert!(err.to_string().contains("500 of its 100"), "{err}");
+    }
+
+    // Opening something that isn't a store fails straight away.
+    #[rstest]
+    fn refuses_a_missing_store() {
+        let err = RadialStore::open("/nonexistent/radial").unwrap_err();
+        assert_eq!(err.kind(), "storage");
+    }
+}
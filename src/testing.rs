@@ -0,0 +1,349 @@
+//! Fixtures for tests in crates that embed radial. Enabled by the `testing`
+//! feature:
+//!
+//! ```toml
+//! [dev-dependencies]
+//! radial = { version = "0.1", features = ["testing"] }
+//! ```
+//!
+//! [`TempProject`] is an initialized project in a temporary directory, and
+//! [`GoalBuilder`] and [`TaskBuilder`] put records into it without going
+//! through the command layer, so a test can start from any state.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use jiff::{SignedDuration, Timestamp};
+use tempfile::TempDir;
+
+use crate::RADIAL_DIR;
+use crate::db::Database;
+use crate::id::{IdAlphabet, generate_id};
+use crate::models::{
+    Contract, Goal, GoalState, Metrics, Priority, Reference, Task, TaskMetrics, TaskState,
+};
+
+/// A project directory holding an initialized `.radial/` store, removed
+/// when dropped.
+pub struct TempProject {
+    dir: TempDir,
+    db: Database,
+}
+
+impl TempProject {
+    pub fn new() -> Result<Self> {
+        let dir = TempDir::new().context("Failed to create temp directory")?;
+        let radial_dir = dir.path().join(RADIAL_DIR);
+        std::fs::create_dir(&radial_dir).context("Failed to create .radial directory")?;
+        let db = Database::open(&radial_dir)?;
+        db.init_schema()?;
+        Ok(Self { dir, db })
+    }
+
+    /// The project root, e.g. the working directory for running `rd`.
+    pub fn path(&self) -> &Path {
+        self.dir.path()
+    }
+
+    pub fn radial_dir(&self) -> PathBuf {
+        self.dir.path().join(RADIAL_DIR)
+    }
+
+    pub fn db(&self) -> &Database {
+        &self.db
+    }
+
+    pub fn db_mut(&mut self) -> &mut Database {
+        &mut self.db
+    }
+
+    /// Reload the store from disk, picking up changes made by other
+    /// processes such as an `rd` binary run in [`TempProject::path`].
+    pub fn reload(&mut self) -> Result<()> {
+        self.db = Database::open(self.radial_dir())?;
+        Ok(())
+    }
+
+    /// Write a goal to the store and return it.
+    pub fn add_goal(&mut self, goal: GoalBuilder) -> Result<Goal> {
+        let goal = goal.build();
+        self.db.create_goal(goal.clone())?;
+        Ok(goal)
+    }
+
+    /// Write a task to the store and return it. Its goal must already exist.
+    pub fn add_task(&mut self, task: TaskBuilder) -> Result<Task> {
+        let task = task.build();
+        self.db.require_goal(task.goal_id())?;
+        self.db.create_task(task.clone())?;
+        Ok(task)
+    }
+}
+
+/// Builds a [`Goal`]. Anything not set takes the value `rd goal create`
+/// would give it, with a random ID.
+#[derive(Debug, Clone)]
+pub struct GoalBuilder {
+    id: Option<String>,
+    description: String,
+    title: Option<String>,
+    parent_id: Option<String>,
+    state: GoalState,
+    branch: Option<String>,
+    project: Option<String>,
+    milestone: Option<String>,
+}
+
+impl GoalBuilder {
+    pub fn new(description: impl Into<String>) -> Self {
+        Self {
+            id: None,
+            description: description.into(),
+            title: None,
+            parent_id: None,
+            state: GoalState::Pending,
+            branch: None,
+            project: None,
+            milestone: None,
+        }
+    }
+
+    #[must_use]
+    pub fn id(mut self, id: impl Into<String>) -> Self {
+        self.id = Some(id.into());
+        self
+    }
+
+    #[must_use]
+    pub fn title(mut self, title: impl Into<String>) -> Self {
+        self.title = Some(title.into());
+        self
+    }
+
+    #[must_use]
+    pub fn parent(mut self, parent_id: impl Into<String>) -> Self {
+        self.parent_id = Some(parent_id.into());
+        self
+    }
+
+    #[must_use]
+    pub fn state(mut self, state: GoalState) -> Self {
+        self.state = state;
+        self
+    }
+
+    #[must_use]
+    pub fn branch(mut self, branch: impl Into<String>) -> Self {
+        self.branch = Some(branch.into());
+        self
+    }
+
+    #[must_use]
+    pub fn project(mut self, project: impl Into<String>) -> Self {
+        self.project = Some(project.into());
+        self
+    }
+
+    #[must_use]
+    pub fn milestone(mut self, milestone: impl Into<String>) -> Self {
+        self.milestone = Some(milestone.into());
+        self
+    }
+
+    pub fn build(self) -> Goal {
+        let now = Timestamp::now();
+        let completed_at = (self.state == GoalState::Completed).then_some(now);
+        Goal::new(
+            self.id.unwrap_or_else(|| generate_id(IdAlphabet::Full)),
+            self.parent_id,
+            self.description,
+            self.state,
+            now,
+            now,
+            completed_at,
+            Metrics::default(),
+        )
+        .with_title(self.title)
+        .with_branch(self.branch)
+        .with_project(self.project)
+        .with_milestone(self.milestone)
+    }
+}
+
+/// Builds a [`Task`] under a goal. Anything not set takes the value
+/// `rd task create` would give it, with a random ID and no contract.
+#[derive(Debug, Clone)]
+pub struct TaskBuilder {
+    id: Option<String>,
+    goal_id: String,
+    description: String,
+    title: Option<String>,
+    contract: Option<Contract>,
+    state: TaskState,
+    priority: Priority,
+    order: u32,
+    blocked_by: Vec<String>,
+    assignee: Option<String>,
+    metrics: TaskMetrics,
+    estimate: Option<SignedDuration>,
+    refs: Vec<Reference>,
+}
+
+impl TaskBuilder {
+    pub fn new(goal_id: impl Into<String>, description: impl Into<String>) -> Self {
+        Self {
+            id: None,
+            goal_id: goal_id.into(),
+            description: description.into(),
+            title: None,
+            contract: None,
+            state: TaskState::Pending,
+            priority: Priority::default(),
+            order: 0,
+            blocked_by: Vec::new(),
+            assignee: None,
+            metrics: TaskMetrics::default(),
+            estimate: None,
+            refs: Vec::new(),
+        }
+    }
+
+    #[must_use]
+    pub fn id(mut self, id: impl Into<String>) -> Self {
+        self.id = Some(id.into());
+        self
+    }
+
+    #[must_use]
+    pub fn title(mut self, title: impl Into<String>) -> Self {
+        self.title = Some(title.into());
+        self
+    }
+
+    #[must_use]
+    pub fn contract(
+        mut self,
+        receives: impl Into<String>,
+        produces: impl Into<String>,
+        verify: impl Into<String>,
+    ) -> Self {
+        self.contract = Some(Contract::new(
+            receives.into(),
+            produces.into(),
+            verify.into(),
+        ));
+        self
+    }
+
+    /// Sets the state as is; blockers are not checked.
+    #[must_use]
+    pub fn state(mut self, state: TaskState) -> Self {
+        self.state = state;
+        self
+    }
+
+    #[must_use]
+    pub fn priority(mut self, priority: Priority) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    #[must_use]
+    pub fn order(mut self, order: u32) -> Self {
+        self.order = order;
+        self
+    }
+
+    #[must_use]
+    pub fn blocked_by(mut self, task_id: impl Into<String>) -> Self {
+        self.blocked_by.push(task_id.into());
+        self
+    }
+
+    #[must_use]
+    pub fn assignee(mut self, agent: impl Into<String>) -> Self {
+        self.assignee = Some(agent.into());
+        self
+    }
+
+    #[must_use]
+    pub fn metrics(mut self, metrics: TaskMetrics) -> Self {
+        self.metrics = metrics;
+        self
+    }
+
+    #[must_use]
+    pub fn estimate(mut self, estimate: SignedDuration) -> Self {
+        self.estimate = Some(estimate);
+        self
+    }
+
+    #[must_use]
+    pub fn reference(mut self, reference: Reference) -> Self {
+        self.refs.push(reference);
+        self
+    }
+
+    pub fn build(self) -> Task {
+        let now = Timestamp::now();
+        Task::new(
+            self.id.unwrap_or_else(|| generate_id(IdAlphabet::Full)),
+            self.goal_id,
+            self.description,
+            self.contract,
+            self.state,
+            self.blocked_by,
+            now,
+            now,
+        )
+        .with_title(self.title)
+        .with_priority(self.priority)
+        .with_order(self.order)
+        .with_assignee(self.assignee)
+        .with_metrics(self.metrics)
+        .with_estimate(self.estimate)
+        .with_refs(self.refs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    // Records added to a temp project land on disk, so a reload (or an `rd`
+    // process pointed at the project) sees them.
+    #[rstest]
+    fn temp_project_persists_records() {
+        let mut project = TempProject::new().unwrap();
+        let goal = project
+            .add_goal(GoalBuilder::new("Ship auth").id("g1").title("Auth"))
+            .unwrap();
+        let schema = project
+            .add_task(TaskBuilder::new("g1", "Write schema").state(TaskState::Completed))
+            .unwrap();
+        project
+            .add_task(
+                TaskBuilder::new("g1", "Write handler")
+                    .contract("Schema", "POST /login", "cargo test")
+                    .blocked_by(schema.id()),
+            )
+            .unwrap();
+
+        project.reload().unwrap();
+        let db = project.db();
+        assert_eq!(db.get_goal("g1").unwrap().headline(), goal.headline());
+        let tasks = db.list_tasks("g1");
+        assert_eq!(tasks.len(), 2);
+        assert_eq!(tasks[1].blocked_by(), [schema.id().to_string()]);
+        assert!(tasks[1].contract().is_some());
+        assert!(project.radial_dir().is_dir());
+    }
+
+    // A task can't be added under a goal that doesn't exist.
+    #[rstest]
+    fn add_task_requires_goal() {
+        let mut project = TempProject::new().unwrap();
+        assert!(project.add_task(TaskBuilder::new("nope", "x")).is_err());
+    }
+}
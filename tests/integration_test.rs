@@ -43,6 +43,77 @@ impl TestEnv {
         }
     }
 
+    /// Run a radial command with `input` piped to stdin
+    fn run_with_input(&self, args: &[&str], input: &str) -> Result<String, String> {
+        use std::io::Write;
+        use std::process::Stdio;
+
+        let mut child = Command::new(&self.binary_path)
+            .args(args)
+            .current_dir(&self.work_dir)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .expect("Failed to execute radial command");
+        child
+            .stdin
+            .take()
+            .unwrap()
+            .write_all(input.as_bytes())
+            .unwrap();
+        let output = child.wait_with_output().unwrap();
+
+        if output.status.success() {
+            Ok(String::from_utf8_lossy(&output.stdout).to_string())
+        } else {
+            Err(String::from_utf8_lossy(&output.stderr).to_string())
+        }
+    }
+
+    /// Run a radial command with `editor` as `$VISUAL`
+    fn run_with_editor(&self, args: &[&str], editor: &str) -> Result<String, String> {
+        let output = Command::new(&self.binary_path)
+            .args(args)
+            .current_dir(&self.work_dir)
+            .env("VISUAL", editor)
+            .output()
+            .expect("Failed to execute radial command");
+
+        if output.status.success() {
+            Ok(String::from_utf8_lossy(&output.stdout).to_string())
+        } else {
+            Err(String::from_utf8_lossy(&output.stderr).to_string())
+        }
+    }
+
+    /// Create a goal and return its ID
+    fn create_goal(&self, description: &str) -> String {
+        let output = self
+            .run(&["goal", "create", description])
+            .expect("Create goal failed");
+        extract_id(&output, "Created goal:")
+    }
+
+    /// Create a task with a full contract and return its ID
+    fn create_task(&self, goal_id: &str, description: &str, extra: &[&str]) -> String {
+        let mut args = vec![
+            "task",
+            "create",
+            goal_id,
+            description,
+            "--receives",
+            "Input",
+            "--produces",
+            "Output",
+            "--verify",
+            "Check",
+        ];
+        args.extend_from_slice(extra);
+        let output = self.run(&args).expect("Create task failed");
+        extract_id(&output, "Created task:")
+    }
+
     /// Check if .radial directory exists
     fn radial_dir_exists(&self) -> bool {
         self.work_dir.join(".radial").exists()
@@ -54,6 +125,16 @@ impl TestEnv {
     }
 }
 
+/// Extract the ID following `marker` in command output
+fn extract_id(output: &str, marker: &str) -> String {
+    output
+        .lines()
+        .find(|line| line.contains(marker))
+        .and_then(|line| line.split_whitespace().nth(2))
+        .expect("Could not extract ID")
+        .to_string()
+}
+
 #[test]
 fn test_init_creates_radial_directory() {
     let env = TestEnv::new();
@@ -649,6 +730,38 @@ fn test_redirect_file() {
     );
 }
 
+#[test]
+fn test_redirect_file_expands_env_vars() {
+    let shared = TempDir::new().expect("Failed to create shared dir");
+    let env = TestEnv::new();
+
+    let output = Command::new(&env.binary_path)
+        .args(["init"])
+        .current_dir(shared.path())
+        .output()
+        .expect("Failed to init shared");
+    assert!(output.status.success());
+
+    let radial = env.work_dir.join(".radial");
+    std::fs::create_dir_all(&radial).unwrap();
+    std::fs::write(radial.join("redirect"), "${RADIAL_SHARED}/.radial\n").unwrap();
+
+    let output = Command::new(&env.binary_path)
+        .args(["goal", "create", "Goal via env redirect"])
+        .current_dir(&env.work_dir)
+        .env("RADIAL_SHARED", shared.path())
+        .output()
+        .expect("Failed to create goal");
+    assert!(output.status.success());
+
+    let output = Command::new(&env.binary_path)
+        .args(["goal", "list"])
+        .current_dir(shared.path())
+        .output()
+        .expect("Failed to list goals");
+    assert!(String::from_utf8_lossy(&output.stdout).contains("Goal via env redirect"));
+}
+
 #[test]
 fn test_json_output_goal_list() {
     let env = TestEnv::new();
@@ -994,3 +1107,2914 @@ fn test_task_comments_json_output() {
     let comments = parsed["comments"].as_array().unwrap();
     assert_eq!(comments.len(), 2, "Show JSON should include comments");
 }
+
+#[test]
+fn test_markdown_export_on_change() {
+    let env = TestEnv::new();
+    env.run(&["init"]).expect("Init failed");
+
+    let goals_dir = env.work_dir.join(".radial").join("goals");
+
+    // Export is opt-in
+    env.run(&["goal", "create", "Not exported"])
+        .expect("Create goal failed");
+    assert!(!goals_dir.exists());
+
+    std::fs::write(
+        env.work_dir.join(".radial").join("config.toml"),
+        "[export]\nmarkdown = true\n",
+    )
+    .expect("Failed to write config");
+
+    let output = env
+        .run(&["goal", "create", "Exported goal"])
+        .expect("Create goal failed");
+    let goal_id = output
+        .lines()
+        .find(|line| line.contains("Created goal:"))
+        .and_then(|line| line.split_whitespace().nth(2))
+        .unwrap();
+
+    env.run(&[
+        "task",
+        "create",
+        goal_id,
+        "Exported task",
+        "--receives",
+        "Input",
+        "--produces",
+        "Output",
+        "--verify",
+        "Check",
+    ])
+    .expect("Create task failed");
+
+    let markdown = std::fs::read_to_string(goals_dir.join(format!("{goal_id}.md")))
+        .expect("Goal markdown should exist");
+    assert!(markdown.starts_with("# Exported goal"));
+    assert!(markdown.contains("Exported task"));
+    assert!(markdown.contains("- Verify: Check"));
+
+    // Removing a goal removes its mirror
+    env.run(&["clean", "--force"]).expect("Clean failed");
+    assert!(!goals_dir.join(format!("{goal_id}.md")).exists());
+
+    // The export directory must not be mistaken for a goal
+    let output = env.run(&["goal", "list"]).expect("List failed");
+    assert!(output.contains("No goals found"));
+}
+
+#[test]
+fn test_truncation_width_and_full_flag() {
+    let env = TestEnv::new();
+    env.run(&["init"]).expect("Init failed");
+    std::fs::write(
+        env.work_dir.join(".radial").join("config.toml"),
+        "[output]\nwidth = 60\n",
+    )
+    .expect("Failed to write config");
+
+    // Multibyte characters straddling the cut point used to panic
+    let description = "Déployer l’API — vérifier les en-têtes, les délais et les journaux";
+    env.run(&["goal", "create", description])
+        .expect("Create goal failed");
+
+    let output = env.run(&["goal", "list"]).expect("List failed");
+    assert!(output.contains('…'));
+    assert!(!output.contains("journaux"));
+
+    let output = env.run(&["goal", "list", "--full"]).expect("List failed");
+    assert!(output.contains(description));
+
+    let output = env.run(&["goal", "list", "--wide"]).expect("List failed");
+    assert!(output.contains(description));
+}
+
+#[test]
+fn test_assignee_filters() {
+    let env = TestEnv::new();
+    env.run(&["init"]).expect("Init failed");
+
+    let goal_id = env.create_goal("Assignee test");
+    let mine = env.create_task(&goal_id, "Assigned task", &["--assignee", "claude-1"]);
+    let free = env.create_task(&goal_id, "Unassigned task", &[]);
+
+    // ready --unassigned hides pre-assigned work
+    let output = env
+        .run(&["ready", &goal_id, "--unassigned"])
+        .expect("Ready failed");
+    assert!(output.contains(&free));
+    assert!(!output.contains(&mine));
+
+    // Another agent cannot start a task assigned to someone else
+    let err = env
+        .run(&["task", "start", &mine, "--agent", "claude-2"])
+        .unwrap_err();
+    assert!(err.contains("assigned to claude-1"));
+
+    // Starting with --agent records the assignee
+    env.run(&["task", "start", &free, "--agent", "claude-2"])
+        .expect("Start failed");
+
+    let output = env
+        .run(&["status", "--agent", "claude-2"])
+        .expect("Status failed");
+    assert!(output.contains(&free));
+    assert!(!output.contains(&mine));
+
+    let output = env
+        .run(&[
+            "status", "--goal", &goal_id, "--agent", "claude-1", "--json",
+        ])
+        .expect("Status failed");
+    let parsed: Value = serde_json::from_str(&output).expect("Should be valid JSON");
+    let tasks = parsed["tasks"].as_array().unwrap();
+    assert_eq!(tasks.len(), 1);
+    assert_eq!(tasks[0]["assignee"], "claude-1");
+}
+
+#[test]
+fn test_suggest_deps() {
+    let env = TestEnv::new();
+    env.run(&["init"]).expect("Init failed");
+
+    let goal_id = env.create_goal("Suggest test");
+    let implement = env.create_task(&goal_id, "Implement login handler", &[]);
+    let tests = env.create_task(&goal_id, "Test login handler", &[]);
+
+    let output = env
+        .run(&["suggest-deps", &goal_id])
+        .expect("Suggest failed");
+    assert!(output.contains(&format!("{tests} blocked by {implement}")));
+    assert!(output.contains("--apply"));
+
+    // Suggesting alone must not change anything
+    let output = env.run(&["status", "--task", &tests]).unwrap();
+    assert!(output.contains("pending"));
+
+    env.run(&["suggest-deps", &goal_id, "--apply"])
+        .expect("Apply failed");
+    let output = env.run(&["status", "--task", &tests]).unwrap();
+    assert!(output.contains("blocked"));
+
+    // Once applied, nothing is left to suggest
+    let output = env
+        .run(&["suggest-deps", &goal_id, "--json"])
+        .expect("Suggest failed");
+    let parsed: Value = serde_json::from_str(&output).expect("Should be valid JSON");
+    assert!(parsed.as_array().unwrap().is_empty());
+}
+
+#[test]
+fn test_ready_effective_priority() {
+    let env = TestEnv::new();
+    env.run(&["init"]).expect("Init failed");
+
+    let goal_id = env.create_goal("Priority test");
+    let blocker = env.create_task(&goal_id, "Low blocker", &["--priority", "low"]);
+    let normal = env.create_task(&goal_id, "Normal task", &[]);
+    env.create_task(
+        &goal_id,
+        "Urgent follow-up",
+        &["--priority", "urgent", "--blocked-by", &blocker],
+    );
+
+    // Own priority only: the normal task outranks the low blocker
+    let output = env.run(&["ready", &goal_id]).expect("Ready failed");
+    assert!(output.find(&normal).unwrap() < output.find(&blocker).unwrap());
+
+    // Effective priority: the blocker inherits urgency from its dependent
+    let output = env
+        .run(&["ready", &goal_id, "--effective-priority"])
+        .expect("Ready failed");
+    assert!(output.find(&blocker).unwrap() < output.find(&normal).unwrap());
+    assert!(output.contains("urgent*"));
+
+    let output = env
+        .run(&["ready", &goal_id, "--effective-priority", "--json"])
+        .expect("Ready failed");
+    let parsed: Value = serde_json::from_str(&output).expect("Should be valid JSON");
+    let tasks = parsed.as_array().unwrap();
+    assert_eq!(tasks[0]["id"], blocker.as_str());
+    assert_eq!(tasks[0]["priority"], "low");
+    assert_eq!(tasks[0]["effective_priority"], "urgent");
+
+    let err = env
+        .run(&["task", "create", &goal_id, "Bad", "--priority", "asap"])
+        .unwrap_err();
+    assert!(err.contains("asap"));
+}
+
+#[test]
+fn test_agent_daily_quota() {
+    let env = TestEnv::new();
+    env.run(&["init"]).expect("Init failed");
+    std::fs::write(
+        env.work_dir.join(".radial").join("config.toml"),
+        "[quotas]\ndaily_tokens = 100\naction = \"refuse\"\n",
+    )
+    .expect("Failed to write config");
+
+    let goal_id = env.create_goal("Quota test");
+    let first = env.create_task(&goal_id, "First", &[]);
+    let second = env.create_task(&goal_id, "Second", &[]);
+
+    env.run(&["task", "start", &first, "--agent", "spender"])
+        .expect("Start failed");
+    env.run(&[
+        "task", "complete", &first, "--result", "done", "--tokens", "150",
+    ])
+    .expect("Complete failed");
+
+    let output = env.run(&["quota", "--json"]).expect("Quota failed");
+    let parsed: Value = serde_json::from_str(&output).expect("Should be valid JSON");
+    assert_eq!(parsed[0]["agent"], "spender");
+    assert_eq!(parsed[0]["tokens_today"], 150);
+    assert_eq!(parsed[0]["daily_limit"], 100);
+
+    let err = env
+        .run(&["task", "start", &second, "--agent", "spender"])
+        .unwrap_err();
+    assert!(err.contains("150 of its 100 daily tokens"));
+
+    // Other agents still have budget
+    env.run(&["task", "start", &second, "--agent", "frugal"])
+        .expect("Start failed");
+}
+
+#[test]
+fn test_gc_removes_leftovers() {
+    let env = TestEnv::new();
+    env.run(&["init"]).expect("Init failed");
+
+    let goal_id = env.create_goal("Gc test");
+    let radial = env.work_dir.join(".radial");
+    let stray_tmp = radial.join(&goal_id).join("dead.toml.tmp");
+    std::fs::write(&stray_tmp, "half written").unwrap();
+    std::fs::File::options()
+        .write(true)
+        .open(&stray_tmp)
+        .unwrap()
+        .set_modified(std::time::SystemTime::now() - std::time::Duration::from_mins(10))
+        .unwrap();
+    // A temp file this fresh may belong to a write in progress
+    let live_tmp = radial.join(&goal_id).join("live.toml.tmp");
+    std::fs::write(&live_tmp, "being written").unwrap();
+    let empty_dir = radial.join("abandoned");
+    std::fs::create_dir(&empty_dir).unwrap();
+
+    let output = env.run(&["gc", "--dry-run"]).expect("Gc failed");
+    assert!(output.contains("Would remove"));
+    assert!(stray_tmp.exists());
+
+    let output = env.run(&["gc", "--json"]).expect("Gc failed");
+    let parsed: Value = serde_json::from_str(&output).expect("Should be valid JSON");
+    assert_eq!(parsed["removed"].as_array().unwrap().len(), 2);
+    assert_eq!(parsed["bytes_reclaimed"], 12);
+    assert!(!stray_tmp.exists());
+    assert!(live_tmp.exists());
+    assert!(!empty_dir.exists());
+
+    // Live data is untouched
+    let output = env.run(&["goal", "list"]).expect("List failed");
+    assert!(output.contains(&goal_id));
+
+    let output = env.run(&["gc"]).expect("Gc failed");
+    assert!(output.contains("Nothing to clean up"));
+}
+
+#[test]
+fn test_gc_prunes_old_history() {
+    let env = TestEnv::new();
+    env.run(&["init"]).expect("Init failed");
+    let radial = env.work_dir.join(".radial");
+    std::fs::write(
+        radial.join("config.toml"),
+        "[history]\nenabled = true\nkeep_for = \"720h\"\n",
+    )
+    .unwrap();
+    env.create_goal("Recent");
+
+    let old_day = radial.join("history").join("2020-01-01.jsonl");
+    std::fs::write(&old_day, "{}\n").unwrap();
+    let log = radial.join("history.jsonl");
+    let recent = std::fs::read_to_string(&log).unwrap();
+    let old = "{\"at\":\"2020-01-01T00:00:00Z\",\"argv\":[\"status\"],\"ok\":true}\n";
+    std::fs::write(&log, format!("{old}{recent}")).unwrap();
+
+    let output = env.run(&["gc", "--dry-run"]).expect("Gc failed");
+    assert!(output.contains("1 command(s)"), "{output}");
+    assert!(old_day.exists());
+
+    let parsed: Value =
+        serde_json::from_str(&env.run(&["gc", "--json"]).expect("Gc failed")).unwrap();
+    assert_eq!(parsed["pruned_commands"], 1);
+    assert!(!old_day.exists());
+    let log_text = std::fs::read_to_string(&log).unwrap();
+    assert!(log_text.starts_with(&recent), "{log_text}");
+    assert!(!log_text.contains("2020-01-01"));
+    // Today's changelog is kept
+    assert_eq!(
+        std::fs::read_dir(radial.join("history")).unwrap().count(),
+        2
+    );
+}
+
+#[test]
+fn test_structured_json_errors() {
+    let env = TestEnv::new();
+    env.run(&["init"]).expect("Init failed");
+
+    let goal_id = env.create_goal("Errors");
+    let task_id = env.create_task(&goal_id, "Task", &[]);
+
+    // Plain errors keep the human-readable message
+    let err = env.run(&["show", "missing1"]).unwrap_err();
+    assert!(err.contains("Goal or task not found: missing1"));
+
+    let err = env
+        .run(&["status", "--task", "nosuchid", "--json"])
+        .unwrap_err();
+    let parsed: Value = serde_json::from_str(&err).expect("Should be valid JSON");
+    assert_eq!(parsed["error"]["kind"], "not_found");
+    assert_eq!(parsed["error"]["id"], "nosuchid");
+
+    let last = if goal_id.ends_with('x') { 'y' } else { 'x' };
+    let err = env
+        .run(&[
+            "task",
+            "list",
+            &format!("{}{last}", &goal_id[..goal_id.len() - 1]),
+            "--json",
+        ])
+        .unwrap_err();
+    let parsed: Value = serde_json::from_str(&err).expect("Should be valid JSON");
+    assert_eq!(parsed["error"]["suggestion"], goal_id.as_str());
+
+    env.run(&["task", "start", &task_id]).expect("Start failed");
+    env.run(&["task", "complete", &task_id, "--result", "done"])
+        .expect("Complete failed");
+    let err = env.run(&["task", "start", &task_id]).unwrap_err();
+    assert!(err.contains("from 'completed' to 'in_progress'"));
+}
+
+#[test]
+fn test_task_reorder() {
+    let env = TestEnv::new();
+    env.run(&["init"]).expect("Init failed");
+
+    let goal_id = env.create_goal("Ordering");
+    let a = env.create_task(&goal_id, "Task A", &[]);
+    let b = env.create_task(&goal_id, "Task B", &[]);
+    let c = env.create_task(&goal_id, "Task C", &[]);
+
+    let ids = |output: &str| -> Vec<String> {
+        let parsed: Value = serde_json::from_str(output).expect("Should be valid JSON");
+        parsed["tasks"]
+            .as_array()
+            .or_else(|| parsed.as_array())
+            .unwrap()
+            .iter()
+            .map(|t| t["id"].as_str().unwrap().to_string())
+            .collect()
+    };
+
+    let output = env
+        .run(&["task", "reorder", &goal_id, &c, "--json"])
+        .expect("Reorder failed");
+    assert_eq!(ids(&output), [c.clone(), a.clone(), b.clone()]);
+
+    let output = env
+        .run(&["task", "reorder", &goal_id, &c, "--after", &b, "--json"])
+        .expect("Reorder failed");
+    assert_eq!(ids(&output), [a.clone(), b.clone(), c.clone()]);
+
+    env.run(&["task", "reorder", &goal_id, &a, "--before", &c])
+        .expect("Reorder failed");
+
+    // Ready follows the explicit order for equal priorities
+    let output = env
+        .run(&["ready", &goal_id, "--json"])
+        .expect("Ready failed");
+    assert_eq!(ids(&output), [b.clone(), a.clone(), c.clone()]);
+
+    // A task created later goes to the end
+    let d = env.create_task(&goal_id, "Task D", &[]);
+    let output = env
+        .run(&["task", "list", &goal_id, "--json"])
+        .expect("List failed");
+    assert_eq!(ids(&output), [b.clone(), a.clone(), c.clone(), d]);
+
+    let err = env
+        .run(&["task", "reorder", &goal_id, &a, "--after", &a])
+        .unwrap_err();
+    assert!(err.contains("relative to one being moved"));
+}
+
+#[test]
+fn test_plan_new_interactive() {
+    let env = TestEnv::new();
+    env.run(&["init"]).expect("Init failed");
+
+    let input = "Launch docs site\n\
+        Write content\noutline\npages\nreviewed\n\
+        Build site\npages\nhtml\nbuild passes\n1\n\
+        Deploy\nhtml\nlive site\nURL loads\n2\n\
+        \n";
+    let output = env
+        .run_with_input(&["plan", "new", "--interactive", "--json"], input)
+        .expect("Plan failed");
+    let parsed: Value = serde_json::from_str(&output).expect("Should be valid JSON");
+    let tasks = parsed["tasks"].as_array().unwrap();
+    assert_eq!(parsed["description"], "Launch docs site");
+    assert_eq!(tasks.len(), 3);
+    assert_eq!(tasks[1]["blocked_by"][0], tasks[0]["id"]);
+    assert_eq!(tasks[2]["blocked_by"][0], tasks[1]["id"]);
+    assert_eq!(tasks[1]["state"], "blocked");
+
+    let goal_id = parsed["id"].as_str().unwrap();
+    let output = env.run(&["ready", goal_id]).expect("Ready failed");
+    assert!(output.contains("Write content"));
+    assert!(!output.contains("Deploy"));
+
+    // Giving up before a goal is entered writes nothing
+    let err = env
+        .run_with_input(&["plan", "new", "--interactive"], "")
+        .unwrap_err();
+    assert!(err.contains("No goal description"));
+    let output = env.run(&["goal", "list"]).expect("List failed");
+    assert_eq!(output.matches("Launch docs site").count(), 1);
+}
+
+#[test]
+fn test_task_peek() {
+    let env = TestEnv::new();
+    env.run(&["init"]).expect("Init failed");
+
+    let goal_id = env.create_goal("Peek");
+    let first = env.create_task(&goal_id, "Produce schema", &[]);
+    let second = env.create_task(&goal_id, "Use schema", &["--blocked-by", &first]);
+
+    let output = env
+        .run(&["task", "peek", &goal_id, "--json"])
+        .expect("Peek failed");
+    let parsed: Value = serde_json::from_str(&output).expect("Should be valid JSON");
+    assert_eq!(parsed["task"]["id"], first.as_str());
+
+    // Peeking does not claim the task
+    let output = env
+        .run(&["status", "--task", &first, "--json"])
+        .expect("Status failed");
+    let parsed: Value = serde_json::from_str(&output).expect("Should be valid JSON");
+    assert_eq!(parsed["state"], "pending");
+
+    env.run(&["task", "start", &first]).expect("Start failed");
+    env.run(&[
+        "task",
+        "complete",
+        &first,
+        "--result",
+        "Wrote schema",
+        "--artifacts",
+        "schema.json",
+    ])
+    .expect("Complete failed");
+
+    let output = env.run(&["task", "peek", &goal_id]).expect("Peek failed");
+    assert!(output.contains(&second));
+    assert!(output.contains("Builds on"));
+    assert!(output.contains("Wrote schema"));
+    assert!(output.contains("schema.json"));
+
+    // Tasks assigned to someone else are skipped
+    env.run(&["edit", "task", &second, "--assignee", "bob"])
+        .expect("Edit failed");
+    let output = env
+        .run(&["task", "peek", &goal_id, "--agent", "alice"])
+        .expect("Peek failed");
+    assert!(output.contains("No ready tasks"));
+    let output = env
+        .run(&["task", "peek", &goal_id, "--agent", "bob", "--json"])
+        .expect("Peek failed");
+    let parsed: Value = serde_json::from_str(&output).expect("Should be valid JSON");
+    assert_eq!(parsed["task"]["id"], second.as_str());
+}
+
+#[test]
+fn test_short_aliases() {
+    let env = TestEnv::new();
+    env.run(&["init"]).expect("Init failed");
+
+    let goal_id = env.create_goal("Aliases");
+    let first = env.create_task(&goal_id, "First", &[]);
+    let second = env.create_task(&goal_id, "Second", &[]);
+
+    let output = env
+        .run(&["grab", &goal_id, "--agent", "alice", "--json"])
+        .expect("Grab failed");
+    let parsed: Value = serde_json::from_str(&output).expect("Should be valid JSON");
+    assert_eq!(parsed["task"]["id"], first.as_str());
+    assert_eq!(parsed["task"]["state"], "inprogress");
+    assert_eq!(parsed["task"]["assignee"], "alice");
+
+    env.run(&["done", &first, "-r", "Did the first"])
+        .expect("Done failed");
+    let output = env
+        .run(&["task", "claim-next", &goal_id])
+        .expect("Claim failed");
+    assert!(output.contains(&second));
+
+    env.run(&[
+        "drop",
+        &second,
+        "Flaky network",
+        "--category",
+        "environment",
+    ])
+    .expect("Drop failed");
+    let output = env.run(&["show", &second, "--json"]).expect("Show failed");
+    let parsed: Value = serde_json::from_str(&output).expect("Should be valid JSON");
+    assert_eq!(parsed["state"], "failed");
+    assert_eq!(parsed["comments"][0]["text"], "Flaky network");
+
+    let output = env.run(&["grab", &goal_id]).expect("Grab failed");
+    assert!(output.contains("No ready tasks"));
+}
+
+#[test]
+fn test_context_task() {
+    let env = TestEnv::new();
+    env.run(&["init"]).expect("Init failed");
+
+    let goal_id = env.create_goal("Context");
+    let schema = env.create_task(&goal_id, "Design schema", &[]);
+    let output = env
+        .run(&[
+            "task",
+            "create",
+            &goal_id,
+            "Generate client",
+            "--receives",
+            "dist/schema.json and auth.toml",
+            "--produces",
+            "client",
+            "--verify",
+            "builds",
+            "--blocked-by",
+            &schema,
+        ])
+        .expect("Create task failed");
+    let client = extract_id(&output, "Created task:");
+
+    env.run(&["task", "start", &schema]).expect("Start failed");
+    env.run(&[
+        "task",
+        "complete",
+        &schema,
+        "--result",
+        "Schema covers users and orders",
+        "--artifacts",
+        "dist/schema.json",
+    ])
+    .expect("Complete failed");
+
+    let output = env
+        .run(&["context", "task", &client])
+        .expect("Context failed");
+    assert!(output.contains("Builds on"));
+    assert!(output.contains("Schema covers users and orders"));
+    assert!(output.contains("Provides"));
+    assert!(output.contains("Unresolved"));
+    assert!(output.contains("auth.toml"));
+
+    let output = env
+        .run(&["context", "task", &client, "--json"])
+        .expect("Context failed");
+    let parsed: Value = serde_json::from_str(&output).expect("Should be valid JSON");
+    assert_eq!(parsed["upstream"][0]["id"], schema.as_str());
+    assert_eq!(
+        parsed["upstream"][0]["matched_artifacts"][0],
+        "dist/schema.json"
+    );
+    assert_eq!(parsed["unresolved"][0], "auth.toml");
+
+    let output = env
+        .run(&["context", "task", &schema])
+        .expect("Context failed");
+    assert!(output.contains("No upstream tasks"));
+}
+
+#[test]
+fn test_status_stale() {
+    let env = TestEnv::new();
+    env.run(&["init"]).expect("Init failed");
+
+    let goal_id = env.create_goal("Stale");
+    let running = env.create_task(&goal_id, "Long running", &["--assignee", "alice"]);
+    env.create_task(&goal_id, "Not started", &[]);
+    env.run(&["task", "start", &running]).expect("Start failed");
+
+    // Default threshold is an hour, so nothing is stale yet
+    let output = env.run(&["status", "--stale"]).expect("Status failed");
+    assert!(output.contains("No stale tasks"));
+
+    std::fs::write(
+        env.work_dir.join(".radial/config.toml"),
+        "[status]\nstale_after = \"0s\"\n",
+    )
+    .unwrap();
+
+    let output = env
+        .run(&["status", "--stale", "--json"])
+        .expect("Status failed");
+    let parsed: Value = serde_json::from_str(&output).expect("Should be valid JSON");
+    let stale = parsed.as_array().unwrap();
+    assert_eq!(stale.len(), 1);
+    assert_eq!(stale[0]["id"], running.as_str());
+    assert!(stale[0]["started_at"].is_string());
+
+    let output = env
+        .run(&["status", "--goal", &goal_id])
+        .expect("Status failed");
+    assert!(output.contains("(stale 0m)"));
+
+    let output = env.run(&["status"]).expect("Status failed");
+    assert!(output.contains("rd status --stale"));
+
+    let output = env
+        .run(&["status", "--stale", "--agent", "bob"])
+        .expect("Status failed");
+    assert!(output.contains("No stale tasks"));
+}
+
+#[test]
+fn test_init_bare_and_link() {
+    let shared = TempDir::new().expect("Failed to create shared dir");
+    let store = shared.path().join("team-ledger");
+    let store_arg = store.to_string_lossy().to_string();
+    let repo_a = TestEnv::new();
+    let repo_b = TestEnv::new();
+
+    let output = repo_a
+        .run(&["init", "--bare", &store_arg])
+        .expect("Bare init failed");
+    assert!(output.contains("Initialized bare radial store"));
+    assert!(store.is_dir());
+
+    // Linking to a missing store fails with a hint
+    let err = repo_a
+        .run(&["link", &format!("{store_arg}-missing")])
+        .unwrap_err();
+    assert!(err.contains("init --bare"));
+
+    repo_a.run(&["link", &store_arg]).expect("Link A failed");
+    repo_b.run(&["link", &store_arg]).expect("Link B failed");
+
+    repo_a.create_goal("Goal from repo A");
+    let output = repo_b.run(&["goal", "list"]).expect("List failed");
+    assert!(output.contains("Goal from repo A"));
+
+    // A repo with its own goals refuses to be linked
+    let local = TestEnv::new();
+    local.run(&["init"]).expect("Init failed");
+    local.create_goal("Local goal");
+    let err = local.run(&["link", &store_arg]).unwrap_err();
+    assert!(err.contains("linking would hide them"));
+}
+
+#[test]
+fn test_sync_merges_and_propagates_deletions() {
+    let laptop = TestEnv::new();
+    let runner = TestEnv::new();
+    laptop.run(&["init"]).expect("Init failed");
+    runner.run(&["init"]).expect("Init failed");
+    let runner_arg = runner.work_dir.to_string_lossy().to_string();
+
+    let kept = laptop.create_goal("Goal from laptop");
+    let doomed = runner.create_goal("Goal from runner");
+    runner.create_task(&doomed, "Runner task", &[]);
+
+    let output = laptop
+        .run(&["sync", &runner_arg, "--dry-run", "--json"])
+        .expect("Dry run failed");
+    let json: Value = serde_json::from_str(&output).expect("Invalid JSON");
+    assert_eq!(json["pulled"], 2);
+    assert_eq!(json["pushed"], 1);
+    assert!(
+        !laptop
+            .run(&["goal", "list"])
+            .unwrap()
+            .contains("Goal from runner")
+    );
+
+    let output = laptop.run(&["sync", &runner_arg]).expect("Sync failed");
+    assert!(output.contains("2 pulled, 1 pushed"));
+    assert!(
+        laptop
+            .run(&["goal", "list"])
+            .unwrap()
+            .contains("Goal from runner")
+    );
+    assert!(
+        runner
+            .run(&["goal", "list"])
+            .unwrap()
+            .contains("Goal from laptop")
+    );
+
+    // A deletion on one side removes the goal from the other on the next sync
+    laptop.run(&["clean", "--force"]).expect("Clean failed");
+    let output = runner
+        .run(&["sync", &laptop.work_dir.to_string_lossy()])
+        .expect("Second sync failed");
+    assert!(output.contains(&format!("Deleted goal {kept}")));
+    assert!(runner.run(&["goal", "list"]).unwrap().contains("No goals"));
+}
+
+#[test]
+fn test_history_log_records_changes() {
+    let env = TestEnv::new();
+    env.run(&["init"]).expect("Init failed");
+    let radial = env.work_dir.join(".radial");
+    std::fs::write(radial.join("config.toml"), "[history]\nenabled = true\n").unwrap();
+
+    let goal_id = env.create_goal("Tracked goal");
+    let task_id = env.create_task(&goal_id, "Tracked task", &[]);
+    env.run(&["task", "start", &task_id]).expect("Start failed");
+    env.run(&["status"]).expect("Status failed");
+
+    let history = radial.join("history");
+    assert!(history.join(".gitattributes").exists());
+    let log = std::fs::read_dir(&history)
+        .unwrap()
+        .map(|e| e.unwrap().path())
+        .find(|p| p.extension().is_some_and(|ext| ext == "jsonl"))
+        .expect("No history log written");
+    let entries: Vec<Value> = std::fs::read_to_string(log)
+        .unwrap()
+        .lines()
+        .map(|l| serde_json::from_str(l).unwrap())
+        .collect();
+
+    let ops: Vec<(&str, &str)> = entries
+        .iter()
+        .map(|e| (e["id"].as_str().unwrap(), e["op"].as_str().unwrap()))
+        .collect();
+    assert!(ops.contains(&(goal_id.as_str(), "created")));
+    assert!(ops.contains(&(task_id.as_str(), "created")));
+    assert!(ops.contains(&(task_id.as_str(), "updated")));
+}
+
+#[test]
+fn test_goals_scoped_to_branch() {
+    let env = TestEnv::new();
+    env.run(&["init"]).expect("Init failed");
+    let git_dir = env.work_dir.join(".git");
+    std::fs::create_dir_all(&git_dir).unwrap();
+    std::fs::write(git_dir.join("HEAD"), "ref: refs/heads/feature-a\n").unwrap();
+
+    let shared = env.create_goal("Shared goal");
+    let output = env
+        .run(&["goal", "create", "Feature A goal", "--branch"])
+        .expect("Create failed");
+    assert!(output.contains("Branch: feature-a"));
+    let feature_a = extract_id(&output, "Created goal:");
+    env.run(&["goal", "create", "Feature B goal", "--branch", "feature-b"])
+        .expect("Create failed");
+    let feature_b = env
+        .run(&["goal", "list", "--json"])
+        .map(|out| {
+            let goals: Value = serde_json::from_str(&out).unwrap();
+            goals
+                .as_array()
+                .unwrap()
+                .iter()
+                .find(|g| g["branch"] == "feature-b")
+                .unwrap()["id"]
+                .as_str()
+                .unwrap()
+                .to_string()
+        })
+        .unwrap();
+
+    let status = env.run(&["status"]).expect("Status failed");
+    assert!(status.contains(&shared));
+    assert!(status.contains(&feature_a));
+    assert!(!status.contains(&feature_b));
+
+    let err = env.run(&["ready", &feature_b]).unwrap_err();
+    assert!(err.contains("belongs to branch 'feature-b'"));
+    env.run(&["ready", &feature_b, "--all-branches"])
+        .expect("Ready with --all-branches failed");
+
+    let status = env
+        .run(&["status", "--all-branches"])
+        .expect("Status failed");
+    assert!(status.contains(&feature_b));
+}
+
+#[test]
+fn test_status_tree_rolls_up_sub_goals() {
+    let env = TestEnv::new();
+    env.run(&["init"]).expect("Init failed");
+
+    let program = env.create_goal("Platform rewrite");
+    let output = env
+        .run(&["goal", "create", "Auth service", "--parent", &program])
+        .expect("Create sub-goal failed");
+    let auth = extract_id(&output, "Created goal:");
+    let task_id = env.create_task(&auth, "Login endpoint", &[]);
+    env.create_task(&program, "Write plan", &[]);
+    env.run(&["task", "start", &task_id]).expect("Start failed");
+    env.run(&["task", "complete", &task_id, "--result", "done"])
+        .expect("Complete failed");
+
+    let err = env
+        .run(&["goal", "create", "Orphan", "--parent", "missing"])
+        .unwrap_err();
+    assert!(err.contains("Goal not found"));
+
+    let output = env.run(&["status", "--tree"]).expect("Tree failed");
+    assert!(output.contains("2 goal(s), 1/2 tasks (50%)"));
+    assert!(output.contains(&format!("└── {auth}")));
+
+    let output = env
+        .run(&["status", "--tree", "--json"])
+        .expect("Tree JSON failed");
+    let json: Value = serde_json::from_str(&output).expect("Invalid JSON");
+    assert_eq!(json["roots"][0]["id"], program.as_str());
+    assert_eq!(json["roots"][0]["rollup"]["goals"], 2);
+    assert_eq!(
+        json["roots"][0]["children"][0]["parent_id"],
+        program.as_str()
+    );
+}
+
+#[test]
+fn test_show_times_are_relative() {
+    let env = TestEnv::new();
+    env.run(&["init"]).expect("Init failed");
+    let goal_id = env.create_goal("Timed goal");
+
+    let output = env.run(&["show", &goal_id, "--utc"]).expect("Show failed");
+    assert!(output.contains(" UTC (just now)"));
+
+    let output = env.run(&["show", &goal_id, "--json"]).expect("Show failed");
+    let json: Value = serde_json::from_str(&output).expect("Invalid JSON");
+    assert!(json["created_at"].as_str().unwrap().ends_with('Z'));
+
+    assert!(env.run(&["show", &goal_id, "--utc", "--local"]).is_err());
+}
+
+#[test]
+fn test_task_checklist() {
+    let env = TestEnv::new();
+    env.run(&["init"]).expect("Init failed");
+    let goal_id = env.create_goal("Checklist goal");
+    let task_id = env.create_task(&goal_id, "Big task", &[]);
+
+    env.run(&["task", "check", "add", &task_id, "Write parser"])
+        .expect("Add failed");
+    env.run(&["task", "check", "add", &task_id, "Write tests"])
+        .expect("Add failed");
+    let output = env
+        .run(&["task", "check", "tick", &task_id, "1"])
+        .expect("Tick failed");
+    assert!(output.contains("(1/2)"));
+
+    let err = env
+        .run(&["task", "check", "tick", &task_id, "3"])
+        .unwrap_err();
+    assert!(err.contains("no checklist item 3"));
+
+    let output = env
+        .run(&["task", "check", "list", &task_id, "--json"])
+        .expect("List failed");
+    let items: Value = serde_json::from_str(&output).expect("Invalid JSON");
+    assert_eq!(items[0]["done"], true);
+    assert_eq!(items[1]["done"], false);
+
+    let output = env.run(&["show", &task_id]).expect("Show failed");
+    assert!(output.contains("Checklist (1/2)"));
+    assert!(output.contains("[x] Write parser"));
+}
+
+#[test]
+fn test_summary_for_llm() {
+    let env = TestEnv::new();
+    env.run(&["init"]).expect("Init failed");
+
+    let goal_id = env.create_goal("Ship the API");
+    env.create_task(&goal_id, "Write handler", &["--priority", "urgent"]);
+
+    let output = env.run(&["summary", "--for-llm"]).expect("Summary failed");
+    assert!(output.contains("Active goals:"));
+    assert!(output.contains("Ship the API"));
+    assert!(output.contains("Write handler"));
+
+    let short = env
+        .run(&["summary", "--for-llm", "--max-tokens", "20"])
+        .expect("Short summary failed");
+    assert!(short.len() < output.len());
+}
+
+#[test]
+fn test_retry_with_revised_contract() {
+    let env = TestEnv::new();
+    env.run(&["init"]).expect("Init failed");
+
+    let goal_id = env.create_goal("Ship the API");
+    let task_id = env.create_task(&goal_id, "Write handler", &[]);
+    env.run(&["task", "start", &task_id]).expect("Start failed");
+    env.run(&["task", "fail", &task_id]).expect("Fail failed");
+
+    let output = env
+        .run(&[
+            "task",
+            "retry",
+            &task_id,
+            "--produces",
+            "Handler with tests",
+            "--description",
+            "Write handler and tests",
+        ])
+        .expect("Retry failed");
+    assert!(output.contains("Earlier revisions: 1"));
+
+    let output = env.run(&["show", &task_id, "--json"]).expect("Show failed");
+    let json: Value = serde_json::from_str(&output).expect("Invalid JSON");
+    assert_eq!(json["state"], "inprogress");
+    assert_eq!(json["description"], "Write handler and tests");
+    assert_eq!(json["contract"]["produces"], "Handler with tests");
+    assert_eq!(json["contract"]["receives"], "Input");
+    assert_eq!(json["revisions"][0]["description"], "Write handler");
+    assert_eq!(json["revisions"][0]["contract"]["produces"], "Output");
+}
+
+#[test]
+fn test_fail_postmortems() {
+    let env = TestEnv::new();
+    env.run(&["init"]).expect("Init failed");
+
+    let goal_id = env.create_goal("Ship the API");
+    let first = env.create_task(&goal_id, "Write handler", &[]);
+    let second = env.create_task(&goal_id, "Write client", &[]);
+    for task_id in [&first, &second] {
+        env.run(&["task", "start", task_id]).expect("Start failed");
+    }
+    env.run(&[
+        "task",
+        "fail",
+        &first,
+        "--category",
+        "spec",
+        "--fix",
+        "Say which status codes to return",
+        "--logs",
+        "logs/run1.txt",
+    ])
+    .expect("Fail failed");
+    env.run(&["task", "fail", &second]).expect("Fail failed");
+
+    let err = env
+        .run(&["task", "fail", &first, "--category", "bogus"])
+        .unwrap_err();
+    assert!(err.contains("bogus"));
+
+    let output = env.run(&["postmortems"]).expect("Postmortems failed");
+    assert!(output.contains("spec (1)"));
+    assert!(output.contains("uncategorized (1)"));
+    assert!(output.contains("Fix: Say which status codes to return"));
+
+    let output = env
+        .run(&["postmortems", "--json"])
+        .expect("Postmortems JSON failed");
+    let json: Value = serde_json::from_str(&output).expect("Invalid JSON");
+    let spec = json
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|g| g["category"] == "spec")
+        .expect("spec group");
+    assert_eq!(spec["failures"][0]["task_id"], first.as_str());
+    assert_eq!(spec["failures"][0]["logs"], "logs/run1.txt");
+}
+
+#[test]
+fn test_ready_explain() {
+    let env = TestEnv::new();
+    env.run(&["init"]).expect("Init failed");
+
+    let goal_id = env.create_goal("Ship the API");
+    let first = env.create_task(&goal_id, "Write handler", &[]);
+    let second = env.create_task(&goal_id, "Write client", &["--blocked-by", &first]);
+    let output = env
+        .run(&["task", "create", &goal_id, "Write docs"])
+        .expect("Create failed");
+    let third = extract_id(&output, "Created task:");
+    env.run(&["task", "start", &first]).expect("Start failed");
+
+    let output = env
+        .run(&["ready", &goal_id, "--explain"])
+        .expect("Ready failed");
+    assert!(output.contains("No tasks ready to start."));
+    assert!(output.contains("Not ready (2)"));
+    assert!(output.contains(&format!("blocked by {first} (in_progress)")));
+    assert!(output.contains("no contract"));
+
+    let output = env
+        .run(&["ready", &goal_id, "--explain", "--json"])
+        .expect("Ready JSON failed");
+    let json: Value = serde_json::from_str(&output).expect("Invalid JSON");
+    assert_eq!(json["ready"].as_array().unwrap().len(), 0);
+    assert_eq!(json["not_ready"][0]["task_id"], second.as_str());
+    assert_eq!(json["not_ready"][0]["reasons"][0]["reason"], "blocked_by");
+    assert_eq!(json["not_ready"][1]["task_id"], third.as_str());
+    assert_eq!(
+        json["not_ready"][1]["reasons"][0]["reason"],
+        "missing_contract"
+    );
+}
+
+#[test]
+fn test_not_before_hides_task_from_ready() {
+    let env = TestEnv::new();
+    env.run(&["init"]).expect("Init failed");
+    let goal_id = env.create_goal("Nightly");
+    let task_id = env.create_task(&goal_id, "Verify deploy", &["--not-before", "2h"]);
+
+    let output = env
+        .run(&["ready", &goal_id, "--explain", "--json"])
+        .expect("Ready failed");
+    let json: Value = serde_json::from_str(&output).expect("Invalid JSON");
+    assert_eq!(json["ready"].as_array().unwrap().len(), 0);
+    assert_eq!(json["not_ready"][0]["reasons"][0]["reason"], "not_before");
+    let output = env
+        .run(&["task", "claim-next", &goal_id])
+        .expect("Claim failed");
+    assert!(!output.contains(&task_id));
+
+    env.run(&["edit", "task", &task_id, "--not-before", ""])
+        .expect("Edit failed");
+    let output = env.run(&["ready", &goal_id]).expect("Ready failed");
+    assert!(output.contains(&task_id));
+
+    env.run(&["edit", "task", &task_id, "--not-before", "soon"])
+        .expect_err("Unreadable times should be refused");
+}
+
+#[cfg(unix)]
+#[test]
+fn test_gates_hold_back_ready() {
+    let env = TestEnv::new();
+    env.run(&["init"]).expect("Init failed");
+    let goal_id = env.create_goal("Gated");
+    let task_id = env.create_task(&goal_id, "Verify staging", &["--gate", "false"]);
+
+    let output = env
+        .run(&["ready", &goal_id, "--explain", "--json"])
+        .expect("Ready failed");
+    let json: Value = serde_json::from_str(&output).expect("Invalid JSON");
+    assert_eq!(json["ready"].as_array().unwrap().len(), 0);
+    assert_eq!(json["not_ready"][0]["reasons"][0]["reason"], "gate_failed");
+    assert_eq!(json["not_ready"][0]["reasons"][0]["command"], "false");
+
+    env.run(&["edit", "task", &task_id, "--gate", "true"])
+        .expect("Edit failed");
+    let output = env.run(&["ready", &goal_id]).expect("Ready failed");
+    assert!(output.contains(&task_id));
+}
+
+#[test]
+fn test_complete_with_follow_ups() {
+    let env = TestEnv::new();
+    env.run(&["init"]).expect("Init failed");
+
+    let goal_id = env.create_goal("Ship the API");
+    let task_id = env.create_task(&goal_id, "Write handler", &[]);
+    env.run(&["task", "start", &task_id]).expect("Start failed");
+
+    let output = env
+        .run(&[
+            "task",
+            "complete",
+            &task_id,
+            "--result",
+            "Handler done",
+            "--follow-up",
+            "Write docs for the endpoint",
+            "--follow-up",
+            "Add rate limiting",
+        ])
+        .expect("Complete failed");
+    assert!(output.contains("Follow-up tasks:"));
+    assert!(output.contains("Write docs for the endpoint"));
+
+    let output = env
+        .run(&["task", "list", &goal_id, "--json"])
+        .expect("List failed");
+    let json: Value = serde_json::from_str(&output).expect("Invalid JSON");
+    let tasks = json.as_array().unwrap();
+    assert_eq!(tasks.len(), 3);
+    assert_eq!(tasks[1]["description"], "Write docs for the endpoint");
+    assert_eq!(tasks[1]["state"], "pending");
+    assert_eq!(tasks[2]["description"], "Add rate limiting");
+
+    let output = env.run(&["show", &goal_id, "--json"]).expect("Show failed");
+    let json: Value = serde_json::from_str(&output).expect("Invalid JSON");
+    assert_eq!(json["state"], "inprogress");
+}
+
+#[test]
+fn test_blocked_by_artifact() {
+    let env = TestEnv::new();
+    env.run(&["init"]).expect("Init failed");
+
+    let goal_id = env.create_goal("Ship the API");
+    let producer = env.create_task(&goal_id, "Write schema", &[]);
+    let consumer = env.create_task(
+        &goal_id,
+        "Generate client",
+        &["--blocked-by-artifact", "dist/schema.json"],
+    );
+
+    let output = env
+        .run(&["show", &consumer, "--json"])
+        .expect("Show failed");
+    let json: Value = serde_json::from_str(&output).expect("Invalid JSON");
+    assert_eq!(json["state"], "blocked");
+    assert_eq!(json["blocked_by_artifacts"][0], "dist/schema.json");
+
+    let err = env.run(&["task", "start", &consumer]).unwrap_err();
+    assert!(err.contains("dist/schema.json"));
+
+    env.run(&["task", "start", &producer])
+        .expect("Start failed");
+    let output = env
+        .run(&[
+            "task",
+            "complete",
+            &producer,
+            "--result",
+            "Schema written",
+            "--artifacts",
+            "./dist/schema.json",
+        ])
+        .expect("Complete failed");
+    assert!(output.contains(&consumer));
+
+    // Once produced, later tasks needing the same artifact start unblocked.
+    let late = env.create_task(
+        &goal_id,
+        "Generate docs",
+        &["--blocked-by-artifact", "dist/schema.json"],
+    );
+    let output = env
+        .run(&["ready", &goal_id, "--json"])
+        .expect("Ready failed");
+    let json: Value = serde_json::from_str(&output).expect("Invalid JSON");
+    let ready: Vec<&str> = json
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|t| t["id"].as_str().unwrap())
+        .collect();
+    assert_eq!(ready, [consumer.as_str(), late.as_str()]);
+}
+
+#[test]
+fn test_validate_and_apply_plan_file() {
+    let env = TestEnv::new();
+
+    let plan = r#"
+goal = "Ship auth"
+
+[[tasks]]
+name = "handler"
+description = "Write login handler"
+receives = "Schema"
+produces = "POST /login"
+verify = "cargo test login"
+blocked_by = ["schema"]
+
+[[tasks]]
+name = "schema"
+description = "Write schema"
+"#;
+    std::fs::write(env.work_dir.join("plan.toml"), plan).unwrap();
+    std::fs::write(
+        env.work_dir.join("broken.toml"),
+        "goal = \"g\"\n[[tasks]]\nname = \"a\"\ndescription = \"x\"\nblocked_by = [\"b\"]\n",
+    )
+    .unwrap();
+
+    // Validation needs no .radial directory, so CI can run it anywhere.
+    let output = env
+        .run(&["validate", "plan.toml"])
+        .expect("Validate failed");
+    assert!(output.contains("warning: Task schema has no complete contract"));
+    assert!(output.find("schema").unwrap() < output.find("handler").unwrap());
+    assert!(output.contains("0 error(s), 1 warning(s)"));
+
+    let err = env.run(&["validate", "plan.toml", "--strict"]).unwrap_err();
+    assert!(err.contains("did not pass validation"));
+    let err = env.run(&["validate", "broken.toml"]).unwrap_err();
+    assert!(err.contains("did not pass validation"));
+
+    env.run(&["init"]).expect("Init failed");
+    let err = env.run(&["plan", "apply", "broken.toml"]).unwrap_err();
+    assert!(err.contains("nothing was created"));
+
+    let output = env
+        .run(&["plan", "apply", "plan.toml", "--json"])
+        .expect("Apply failed");
+    let json: Value = serde_json::from_str(&output).expect("Invalid JSON");
+    assert_eq!(json["description"], "Ship auth");
+    assert_eq!(json["tasks"][0]["description"], "Write schema");
+    assert_eq!(
+        json["tasks"][1]["blocked_by"][0],
+        json["tasks"][0]["id"].as_str().unwrap()
+    );
+}
+
+#[test]
+fn test_capabilities_json() {
+    let env = TestEnv::new();
+
+    // Works before `init`, so tool schemas can be built up front.
+    let output = env
+        .run(&["capabilities", "--json"])
+        .expect("Capabilities failed");
+    let parsed: Value = serde_json::from_str(&output).expect("Should be valid JSON");
+
+    let commands = parsed["commands"].as_array().unwrap();
+    let start = commands
+        .iter()
+        .find(|c| c["name"] == "task start")
+        .expect("task start should be listed");
+    assert!(
+        start["args"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .any(|a| a["long"] == "agent")
+    );
+
+    let pending = &parsed["task_states"][0];
+    assert_eq!(pending["state"], "pending");
+    assert!(
+        pending["next"]
+            .as_array()
+            .unwrap()
+            .contains(&Value::from("inprogress"))
+    );
+}
+
+#[test]
+fn test_task_title() {
+    let env = TestEnv::new();
+    env.run(&["init"]).expect("Init failed");
+
+    let goal_id = env.create_goal("Ship the API");
+    let task_id = env.create_task(
+        &goal_id,
+        "Add a POST /login handler that checks the password hash\nand issues a session cookie",
+        &["--title", "Login handler"],
+    );
+
+    let list = env.run(&["task", "list", &goal_id]).expect("List failed");
+    assert!(list.contains("Login handler"));
+    assert!(!list.contains("POST /login"));
+
+    let show = env.run(&["show", &task_id]).expect("Show failed");
+    assert!(show.contains("Login handler"));
+    assert!(show.contains("issues a session cookie"));
+
+    env.run(&["edit", "task", &task_id, "--title", ""])
+        .expect("Edit failed");
+    let list = env.run(&["task", "list", &goal_id]).expect("List failed");
+    assert!(list.contains("POST /login"));
+}
+
+#[test]
+fn test_task_progress() {
+    let env = TestEnv::new();
+    env.run(&["init"]).expect("Init failed");
+
+    let goal_id = env.create_goal("Ship the API");
+    let task_id = env.create_task(&goal_id, "Write handler", &[]);
+
+    let result = env.run(&["task", "progress", &task_id, "60"]);
+    assert!(result.is_err(), "Pending tasks cannot report progress");
+
+    env.run(&["task", "start", &task_id]).expect("Start failed");
+    env.run(&[
+        "task",
+        "progress",
+        &task_id,
+        "60",
+        "--note",
+        "handler done, tests remaining",
+    ])
+    .expect("Progress failed");
+
+    let status = env
+        .run(&["status", "--goal", &goal_id])
+        .expect("Status failed");
+    assert!(status.contains("(60%)"));
+
+    let show = env.run(&["show", &task_id]).expect("Show failed");
+    assert!(show.contains("Progress: 60%"));
+    assert!(show.contains("tests remaining"));
+
+    let result = env.run(&["task", "progress", &task_id, "101"]);
+    assert!(result.is_err(), "Percent is capped at 100");
+}
+
+#[test]
+fn test_ephemeral_session() {
+    let env = TestEnv::new();
+
+    let session = "# Lines run in order against one store\n\
+                   goal create \"Try things out\" --title Scratch\n\
+                   task frobnicate\n\
+                   list\n";
+    let result = env.run_with_input(&["--ephemeral"], session);
+    let stderr = result.expect_err("A bad line fails the session");
+    assert!(stderr.contains("frobnicate"));
+    assert!(stderr.contains("1 command(s) in the session failed"));
+
+    let output = env
+        .run_with_input(&["--ephemeral"], "goal create \"Try things out\"\nlist\n")
+        .expect("Session failed");
+    assert!(output.contains("Try things out"));
+    assert!(!env.work_dir.join(".radial").exists());
+
+    std::fs::write(
+        env.work_dir.join("plan.toml"),
+        "goal = \"Seeded\"\n[[tasks]]\nname = \"a\"\ndescription = \"First step\"\n",
+    )
+    .unwrap();
+    let output = env
+        .run(&["--ephemeral", "--seed", "plan.toml", "list"])
+        .expect("Seeded run failed");
+    assert!(output.contains("Seeded"));
+    assert!(!env.work_dir.join(".radial").exists());
+
+    assert!(env.run(&["--ephemeral", "init"]).is_err());
+    assert!(
+        env.run(&["list"]).is_err(),
+        "Normal runs still need a store"
+    );
+}
+
+#[test]
+fn test_ephemeral_verify_writes_nothing_here() {
+    let env = TestEnv::new();
+    std::fs::write(
+        env.work_dir.join("plan.toml"),
+        "goal = \"Seeded\"\n[[tasks]]\nname = \"a\"\ndescription = \"First step\"\n\
+         receives = \"-\"\nproduces = \"-\"\nverify = \"echo boom; false\"\n",
+    )
+    .unwrap();
+    let rd = |args: &[&str]| {
+        Command::new(&env.binary_path)
+            .args(["--ephemeral", "--seed", "plan.toml"])
+            .args(args)
+            .current_dir(&env.work_dir)
+            .env("RADIAL_ID_SEED", "ephemeral-verify")
+            .output()
+            .expect("Failed to run rd")
+    };
+    let listed: Value = serde_json::from_slice(&rd(&["list", "--json"]).stdout).unwrap();
+    let task = listed[0]["tasks"][0]["id"].as_str().unwrap().to_string();
+
+    let output = rd(&["task", "verify", &task]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("boom"), "{output:?}");
+    let entries: Vec<_> = std::fs::read_dir(&env.work_dir)
+        .unwrap()
+        .map(|e| e.unwrap().file_name())
+        .collect();
+    assert_eq!(entries, ["plan.toml"]);
+}
+
+#[test]
+fn test_show_renders_markdown() {
+    let env = TestEnv::new();
+    env.run(&["init"]).expect("Init failed");
+    let goal_id = env.create_goal("Render goal");
+    let task_id = env.create_task(&goal_id, "Render task", &[]);
+    env.run(&[
+        "task",
+        "comment",
+        &task_id,
+        "Done:\n- **fixed** the `auth` bug",
+    ])
+    .expect("Comment failed");
+
+    let output = env.run(&["show", &task_id]).expect("Show failed");
+    assert!(output.contains("• fixed the auth bug"));
+
+    let output = env
+        .run(&["show", &task_id, "--render", "plain"])
+        .expect("Show failed");
+    assert!(output.contains("- **fixed** the `auth` bug"));
+}
+
+#[test]
+fn test_edit_rejects_dependency_cycle() {
+    let env = TestEnv::new();
+    env.run(&["init"]).expect("Init failed");
+    let goal_id = env.create_goal("Cycle goal");
+    let first = env.create_task(&goal_id, "Write schema", &[]);
+    let second = env.create_task(&goal_id, "Write client", &["--blocked-by", &first]);
+    let third = env.create_task(&goal_id, "Write docs", &["--blocked-by", &second]);
+
+    let err = env
+        .run(&["edit", "task", &first, "--blocked-by", &third])
+        .expect_err("Cycle should be rejected");
+    assert!(err.contains(&format!(
+        "{first} \"Write schema\" → {third} \"Write docs\" → {second} \"Write client\" → {first} \"Write schema\""
+    )));
+    assert!(err.contains(&format!("The newest link, {first} → {third}")));
+
+    let output = env
+        .run(&["status", "--task", &first, "--json"])
+        .expect("Status failed");
+    let parsed: Value = serde_json::from_str(&output).expect("Should be valid JSON");
+    assert!(
+        parsed["blocked_by"].is_null(),
+        "The rejected edit is not saved"
+    );
+}
+
+#[test]
+fn test_edit_task_interactive() {
+    let env = TestEnv::new();
+    env.run(&["init"]).expect("Init failed");
+    let goal_id = env.create_goal("Interactive goal");
+    let task_id = env.create_task(&goal_id, "Old description", &[]);
+
+    // The "editor" rewrites the draft in place, as a user would.
+    env.run_with_editor(
+        &["edit", "task", &task_id, "--interactive", "--priority", "high"],
+        "sed -i -e 's/Old description/Line one\\\\nLine two/' -e 's/^verify = .*/verify = \"cargo test\"/'",
+    )
+    .expect("Interactive edit failed");
+
+    let output = env
+        .run(&["status", "--task", &task_id, "--json"])
+        .expect("Status failed");
+    let parsed: Value = serde_json::from_str(&output).expect("Should be valid JSON");
+    assert_eq!(parsed["description"], "Line one\nLine two");
+    assert_eq!(parsed["contract"]["verify"], "cargo test");
+    assert_eq!(parsed["priority"], "high");
+
+    // An invalid draft changes nothing
+    let err = env
+        .run_with_editor(
+            &["edit", "task", &task_id, "-i"],
+            "sed -i 's/^blocked_by = .*/blocked_by = [\"nope\"]/'",
+        )
+        .expect_err("Unknown blocker should be rejected");
+    assert!(err.contains("nope"));
+    let err = env
+        .run_with_editor(
+            &["edit", "task", &task_id, "-i"],
+            "sed -i 's/^verify/tags/'",
+        )
+        .expect_err("Unknown field should be rejected");
+    assert!(err.contains("not valid TOML"));
+    let output = env
+        .run(&["status", "--task", &task_id, "--json"])
+        .expect("Status failed");
+    let parsed: Value = serde_json::from_str(&output).expect("Should be valid JSON");
+    assert_eq!(parsed["contract"]["verify"], "cargo test");
+}
+
+#[test]
+fn test_edit_task_patch() {
+    let env = TestEnv::new();
+    env.run(&["init"]).expect("Init failed");
+    let goal_id = env.create_goal("Patch goal");
+    let blocker = env.create_task(&goal_id, "Blocker", &[]);
+    let task_id = env.create_task(&goal_id, "Patched", &["--assignee", "ann"]);
+
+    let patch = format!(
+        r#"{{"verify": "cargo test\nand clippy", "blocked_by": ["{blocker}"], "assignee": null, "priority": "urgent"}}"#
+    );
+    let output = env
+        .run_with_input(&["edit", "task", &task_id, "--patch", "-"], &patch)
+        .expect("Patch failed");
+    let parsed: Value = serde_json::from_str(&output).expect("Should be valid JSON");
+    assert_eq!(parsed["contract"]["verify"], "cargo test\nand clippy");
+    assert_eq!(parsed["contract"]["receives"], "Input");
+    assert_eq!(parsed["blocked_by"][0], blocker.as_str());
+    assert!(parsed["assignee"].is_null());
+    assert_eq!(parsed["priority"], "urgent");
+
+    for bad in [r#"{"tags": ["x"]}"#, r#"{"description": null}"#, "[1]"] {
+        assert!(
+            env.run_with_input(&["edit", "task", &task_id, "--patch", "-"], bad)
+                .is_err(),
+            "{bad} should be rejected"
+        );
+    }
+}
+
+#[test]
+fn test_lifecycle_json_results() {
+    let env = TestEnv::new();
+    env.run(&["init"]).expect("Init failed");
+    let goal_id = env.create_goal("Results goal");
+    let first = env.create_task(&goal_id, "First", &[]);
+    let second = env.create_task(&goal_id, "Second", &["--blocked-by", &first]);
+
+    let parse = |output: String| -> Value { serde_json::from_str(&output).expect("Valid JSON") };
+
+    let started = parse(
+        env.run(&["task", "start", &first, "--agent", "ann", "--json"])
+            .expect("Start failed"),
+    );
+    assert_eq!(started["task"]["state"], "inprogress");
+    assert_eq!(started["assigned_to"], "ann");
+
+    let failed = parse(
+        env.run(&["task", "fail", &first, "--category", "spec", "--json"])
+            .expect("Fail failed"),
+    );
+    assert_eq!(failed["postmortem_recorded"], true);
+    assert_eq!(failed["waiting_task_ids"][0], second.as_str());
+
+    let retried = parse(
+        env.run(&["task", "retry", &first, "--verify", "Sharper", "--json"])
+            .expect("Retry failed"),
+    );
+    assert_eq!(retried["revised"], true);
+
+    let completed = parse(
+        env.run(&["task", "complete", &first, "-r", "Done", "--json"])
+            .expect("Complete failed"),
+    );
+    assert_eq!(completed["unblocked_task_ids"][0], second.as_str());
+    assert_eq!(completed["goal_state"], "inprogress");
+
+    env.run(&["task", "start", &second]).expect("Start failed");
+    env.run(&["done", &second, "-r", "Done"])
+        .expect("Done failed");
+    let cleaned = parse(
+        env.run(&["clean", "--all", "--json"])
+            .expect("Clean failed"),
+    );
+    assert_eq!(cleaned["removed"][0]["id"], goal_id.as_str());
+    assert_eq!(cleaned["removed"][0]["task_count"], 2);
+}
+
+#[test]
+fn test_task_ctx() {
+    let env = TestEnv::new();
+    env.run(&["init"]).expect("Init failed");
+    let goal_id = env.create_goal("Ctx goal");
+    let task_id = env.create_task(&goal_id, "Ctx task", &[]);
+
+    env.run(&["task", "ctx", "set", &task_id, "branch", "feat/login"])
+        .expect("Set failed");
+    env.run(&[
+        "task",
+        "ctx",
+        "set",
+        &task_id,
+        "pr",
+        "https://example.com/pr/7",
+    ])
+    .expect("Set failed");
+
+    let output = env
+        .run(&["task", "ctx", "get", &task_id, "branch"])
+        .expect("Get failed");
+    assert_eq!(output, "feat/login\n");
+
+    let output = env.run(&["show", &task_id, "--json"]).expect("Show failed");
+    let parsed: Value = serde_json::from_str(&output).expect("Should be valid JSON");
+    assert_eq!(parsed["ctx"]["pr"], "https://example.com/pr/7");
+
+    env.run(&["task", "ctx", "unset", &task_id, "branch"])
+        .expect("Unset failed");
+    let err = env
+        .run(&["task", "ctx", "get", &task_id, "branch"])
+        .expect_err("Removed key should be missing");
+    assert!(err.contains("not found: branch"));
+}
+
+#[test]
+fn test_goal_claim() {
+    let env = TestEnv::new();
+    env.run(&["init"]).expect("Init failed");
+    let goal_id = env.create_goal("Claimed goal");
+    let task_id = env.create_task(&goal_id, "Claimed task", &[]);
+
+    let output = env
+        .run(&["goal", "claim", &goal_id, "--agent", "alice", "--ttl", "2h"])
+        .expect("Claim failed");
+    assert!(output.contains("by alice"));
+
+    let err = env
+        .run(&["task", "start", &task_id, "--agent", "bob"])
+        .expect_err("Other agents should be refused");
+    assert!(err.contains("claimed by alice"));
+    env.run(&["goal", "release", &goal_id, "--agent", "bob"])
+        .expect_err("Only the holder can release without --force");
+
+    env.run(&["task", "start", &task_id, "--agent", "alice"])
+        .expect("Holder should be able to start");
+    env.run(&["goal", "release", &goal_id, "--agent", "alice"])
+        .expect("Release failed");
+    env.run(&["task", "comment", &task_id, "released"])
+        .expect("Anyone may write after release");
+}
+
+#[test]
+fn test_ingest_transcript() {
+    let env = TestEnv::new();
+    env.run(&["init"]).expect("Init failed");
+    let goal_id = env.create_goal("Transcript goal");
+    let task_id = env.create_task(&goal_id, "Write parser", &[]);
+    env.run(&["task", "start", &task_id]).expect("Start failed");
+
+    let transcript = format!(
+        "Working on it.\n\n```bash\nrd task comment {task_id} \"parser half done\"\n```\n\nFinished {task_id}: the parser handles JSONL.\n"
+    );
+    std::fs::write(env.work_dir.join("session.md"), &transcript).unwrap();
+
+    let output = env
+        .run(&["ingest-transcript", "session.md", "--json"])
+        .expect("Ingest failed");
+    let parsed: Value = serde_json::from_str(&output).expect("Should be valid JSON");
+    assert_eq!(parsed.as_array().unwrap().len(), 2);
+    assert_eq!(parsed[1]["source"], "outcome");
+
+    env.run(&["ingest-transcript", "session.md", "--apply"])
+        .expect("Apply failed");
+    let output = env
+        .run(&["status", "--task", &task_id, "--json"])
+        .expect("Status failed");
+    let parsed: Value = serde_json::from_str(&output).expect("Should be valid JSON");
+    assert_eq!(parsed["state"], "completed");
+    assert_eq!(parsed["comments"][0]["text"], "parser half done");
+}
+
+#[test]
+fn test_all_status_across_registered_projects() {
+    let api = TestEnv::new();
+    let web = TestEnv::new();
+    let registry = api.work_dir.join("projects.toml");
+    let all = |env: &TestEnv, args: &[&str]| {
+        let output = Command::new(&env.binary_path)
+            .arg("all")
+            .args(args)
+            .current_dir(&env.work_dir)
+            .env("RADIAL_REGISTRY", &registry)
+            .output()
+            .expect("Failed to run rd all");
+        assert!(
+            output.status.success(),
+            "{}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        String::from_utf8_lossy(&output.stdout).to_string()
+    };
+
+    for (env, name) in [(&api, "api"), (&web, "web")] {
+        env.run(&["init"]).expect("Init failed");
+        let goal = env.create_goal("Ship");
+        env.create_task(&goal, "First", &[]);
+        all(env, &["add", "--name", name]);
+    }
+    let goal = web.create_goal("Polish");
+    let task = web.create_task(&goal, "Second", &[]);
+    web.run(&["task", "start", &task]).expect("Start failed");
+
+    let status: Value = serde_json::from_str(&all(&api, &["status", "--json"])).unwrap();
+    assert_eq!(status[0]["name"], "api");
+    assert_eq!(status[0]["ready"], 1);
+    assert_eq!(status[1]["name"], "web");
+    assert_eq!(status[1]["goals"], 2);
+    assert_eq!(status[1]["in_progress"], 1);
+
+    let ready: Value = serde_json::from_str(&all(&web, &["ready", "--json"])).unwrap();
+    let projects: Vec<&str> = ready
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|t| t["project"].as_str().unwrap())
+        .collect();
+    assert_eq!(projects, ["api", "web"]);
+
+    all(&api, &["remove", "web"]);
+    assert!(!all(&api, &["list"]).contains("web"));
+}
+
+#[test]
+fn test_history_replays_a_command() {
+    let env = TestEnv::new();
+    env.run(&["init"]).expect("Init failed");
+    env.create_goal("Ship it");
+    let _ = env.run(&["task", "start", "missing"]);
+
+    let history: Value = serde_json::from_str(&env.run(&["history", "--json"]).unwrap()).unwrap();
+    assert_eq!(
+        history[0]["argv"],
+        serde_json::json!(["goal", "create", "Ship it"])
+    );
+    assert_eq!(history[1]["ok"], false);
+    assert_eq!(history.as_array().unwrap().len(), 2);
+
+    env.run(&["history", "--replay", "1"])
+        .expect("Replay failed");
+    let goals: Value =
+        serde_json::from_str(&env.run(&["goal", "list", "--json"]).unwrap()).unwrap();
+    assert_eq!(goals.as_array().unwrap().len(), 2);
+}
+
+#[test]
+fn test_task_create_refuses_near_duplicate() {
+    let env = TestEnv::new();
+    env.run(&["init"]).expect("Init failed");
+    let goal_id = env.create_goal("Ship login");
+    let task_id = env.create_task(&goal_id, "Add the login endpoint", &[]);
+
+    let err = env
+        .run(&["task", "create", &goal_id, "add login endpoint"])
+        .unwrap_err();
+    assert!(
+        err.contains(&task_id),
+        "should name the existing task: {err}"
+    );
+    assert!(err.contains("--allow-duplicate"));
+
+    env.create_task(&goal_id, "add login endpoint", &["--allow-duplicate"]);
+    env.create_task(&goal_id, "Add the logout endpoint", &[]);
+}
+
+#[test]
+fn test_task_verify_runs_contract_command() {
+    let env = TestEnv::new();
+    env.run(&["init"]).expect("Init failed");
+    let goal_id = env.create_goal("Ship it");
+    let create = |description: &str, verify: &str| {
+        let output = env
+            .run(&["task", "create", &goal_id, description, "--verify", verify])
+            .expect("Create task failed");
+        extract_id(&output, "Created task:")
+    };
+    let passing = create("Build", "echo built");
+    let failing = create("Test", "echo broken; exit 2");
+
+    let verified: Value =
+        serde_json::from_str(&env.run(&["task", "verify", &passing, "--json"]).unwrap()).unwrap();
+    assert_eq!(verified["passed"], true);
+    let log = verified["log"].as_str().unwrap();
+    assert_eq!(std::fs::read_to_string(log).unwrap(), "built\n");
+
+    let err = env.run(&["task", "verify", &failing]).unwrap_err();
+    assert!(err.contains("Verify failed"));
+
+    std::fs::create_dir(env.work_dir.join("src")).unwrap();
+    std::fs::write(
+        env.work_dir.join(".radial").join("config.toml"),
+        "[sandbox]\nallowed_dirs = [\"src\"]\n",
+    )
+    .expect("Failed to write config");
+    let err = env.run(&["task", "verify", &passing]).unwrap_err();
+    assert!(err.contains("allowed_dirs"));
+}
+
+#[test]
+fn test_metrics_keep_every_attempt() {
+    let env = TestEnv::new();
+    env.run(&["init"]).expect("Init failed");
+    let goal_id = env.create_goal("Ship");
+    let task_id = env.create_task(&goal_id, "Build", &[]);
+
+    env.run(&["task", "start", &task_id, "--agent", "first"])
+        .expect("Start failed");
+    env.run(&["task", "fail", &task_id, "--tokens", "300"])
+        .expect("Fail failed");
+    env.run(&["task", "retry", &task_id]).expect("Retry failed");
+    env.run(&[
+        "task",
+        "complete",
+        &task_id,
+        "--result",
+        "Built",
+        "--prompt-tokens",
+        "80",
+        "--completion-tokens",
+        "20",
+    ])
+    .expect("Complete failed");
+
+    let output = env.run(&["show", &task_id, "--json"]).expect("Show failed");
+    let task: Value = serde_json::from_str(&output).expect("Should be valid JSON");
+    assert_eq!(task["metrics"]["tokens"], 400);
+    assert_eq!(task["attempts"].as_array().unwrap().len(), 2);
+    assert_eq!(task["attempts"][1]["number"], 2);
+
+    let output = env.run(&["show", &goal_id, "--json"]).expect("Show failed");
+    let goal: Value = serde_json::from_str(&output).expect("Should be valid JSON");
+    let metrics = &goal["metrics"];
+    assert_eq!(metrics["total_tokens"], 400);
+    assert_eq!(metrics["prompt_tokens"], 80);
+    assert_eq!(metrics["by_agent"]["first"]["attempts"], 2);
+    assert_eq!(metrics["by_attempt"]["2"]["tokens"], 100);
+}
+
+#[test]
+fn test_show_lists_attempts() {
+    let env = TestEnv::new();
+    env.run(&["init"]).expect("Init failed");
+    let goal_id = env.create_goal("Ship");
+    let task_id = env.create_task(&goal_id, "Build", &[]);
+
+    env.run(&["task", "start", &task_id]).expect("Start failed");
+    env.run(&[
+        "task",
+        "fail",
+        &task_id,
+        "--reason",
+        "Linker ran out of memory",
+        "--category",
+        "environment",
+    ])
+    .expect("Fail failed");
+    env.run(&["task", "retry", &task_id]).expect("Retry failed");
+    env.run(&["done", &task_id, "-r", "Built on a bigger box"])
+        .expect("Complete failed");
+
+    let output = env.run(&["show", &task_id, "--json"]).expect("Show failed");
+    let task: Value = serde_json::from_str(&output).expect("Should be valid JSON");
+    let attempts = task["attempts"].as_array().unwrap();
+    assert_eq!(attempts[0]["outcome"], "failed");
+    assert_eq!(attempts[0]["category"], "environment");
+    assert_eq!(attempts[0]["reason"], "Linker ran out of memory");
+    assert_eq!(attempts[1]["outcome"], "completed");
+    assert_eq!(attempts[1]["result"], "Built on a bigger box");
+    assert_ne!(attempts[0]["started_at"], attempts[1]["started_at"]);
+
+    let output = env.run(&["show", &task_id]).expect("Show failed");
+    assert!(output.contains("Attempts (2)"));
+    assert!(output.contains("Linker ran out of memory"));
+}
+
+#[test]
+fn test_task_move_through_custom_states() {
+    let env = TestEnv::new();
+    env.run(&["init"]).expect("Init failed");
+    std::fs::write(
+        env.work_dir.join(".radial/config.toml"),
+        "[states.review]\ncore = \"verifying\"\ncolor = \"magenta\"\nfrom = [\"in_progress\"]\n\n\
+         [states.deploying]\ncore = \"in_progress\"\nfrom = [\"review\"]\n",
+    )
+    .unwrap();
+    let goal_id = env.create_goal("Ship");
+    let task_id = env.create_task(&goal_id, "Build", &[]);
+
+    let err = env
+        .run(&["task", "move", &task_id, "review"])
+        .expect_err("Entered review from pending");
+    assert!(err.contains("can't be entered"));
+
+    env.run(&["task", "start", &task_id]).expect("Start failed");
+    let output = env
+        .run(&["task", "move", &task_id, "review", "--json"])
+        .expect("Move failed");
+    let task: Value = serde_json::from_str(&output).expect("Should be valid JSON");
+    assert_eq!(task["state"], "verifying");
+    assert_eq!(task["stage"], "review");
+    let shown = env.run(&["show", &task_id]).expect("Show failed");
+    assert!(shown.contains("review"));
+
+    env.run(&["task", "move", &task_id, "deploying"])
+        .expect("Move failed");
+    let err = env
+        .run(&["task", "move", &task_id, "verifying"])
+        .expect_err("Moved to another core state");
+    assert!(err.contains("use start, complete, or fail"));
+
+    env.run(&["task", "complete", &task_id, "--result", "Shipped"])
+        .expect("Complete failed");
+    let output = env.run(&["show", &task_id, "--json"]).expect("Show failed");
+    let task: Value = serde_json::from_str(&output).expect("Should be valid JSON");
+    assert!(task.get("stage").is_none());
+}
+
+#[test]
+fn test_prep_leaves_out_disabled_commands() {
+    let env = TestEnv::new();
+    env.run(&["init"]).expect("Init failed");
+    let full = env.run(&["prep"]).expect("Prep failed");
+    assert!(full.contains("rd task exec"));
+    let short = env.run(&["prep", "--for", "short"]).expect("Prep failed");
+    assert!(short.contains("rd grab") && short.len() < full.len());
+
+    std::fs::write(
+        env.work_dir.join(".radial/config.toml"),
+        "[commands]\ndisabled = [\"task exec\", \"task complete\"]\n",
+    )
+    .unwrap();
+    let guide = env.run(&["prep"]).expect("Prep failed");
+    assert!(!guide.contains("rd task exec") && !guide.contains("rd done"));
+    assert!(guide.contains("rd task verify"));
+
+    let goal_id = env.create_goal("Ship");
+    let task_id = env.create_task(&goal_id, "Build", &[]);
+    let err = env
+        .run(&["done", &task_id, "-r", "Built"])
+        .expect_err("Disabled command ran");
+    assert!(err.contains("rd done is disabled"));
+}
+
+#[test]
+fn test_id_seed_makes_ids_reproducible() {
+    let seeded = |env: &TestEnv, args: &[&str]| {
+        let output = Command::new(&env.binary_path)
+            .args(args)
+            .current_dir(&env.work_dir)
+            .env("RADIAL_ID_SEED", "golden")
+            .output()
+            .expect("Failed to run rd");
+        assert!(output.status.success());
+        assert!(String::from_utf8_lossy(&output.stderr).contains("RADIAL_ID_SEED"));
+        String::from_utf8_lossy(&output.stdout).to_string()
+    };
+    let ids = |env: &TestEnv| {
+        seeded(env, &["init"]);
+        let goal = extract_id(&seeded(env, &["goal", "create", "Ship"]), "Created goal:");
+        let task = extract_id(
+            &seeded(env, &["task", "create", &goal, "Build"]),
+            "Created task:",
+        );
+        (goal, task)
+    };
+
+    let first = ids(&TestEnv::new());
+    assert_eq!(first, ids(&TestEnv::new()));
+    assert_ne!(first.0, first.1);
+}
+
+/// Not run by default; `cargo test --release -- --ignored` times a plan the
+/// size of a large migration.
+#[test]
+#[ignore = "benchmark"]
+fn bench_plan_apply_ten_thousand_tasks() {
+    use std::fmt::Write as _;
+
+    let env = TestEnv::new();
+    env.run(&["init"]).expect("Init failed");
+    let mut plan = String::from("goal = \"Migrate records\"\n");
+    for i in 0..10_000 {
+        write!(
+            plan,
+            "[[tasks]]\nname = \"t{i}\"\ndescription = \"Migrate batch {i}\"\nreceives = \"-\"\nproduces = \"-\"\nverify = \"true\"\n"
+        )
+        .unwrap();
+        if i % 10 != 0 {
+            writeln!(plan, "blocked_by = [\"t{}\"]", i - 1).unwrap();
+        }
+    }
+    let path = env.work_dir.join("plan.toml");
+    std::fs::write(&path, plan).expect("Failed to write plan");
+
+    let started = std::time::Instant::now();
+    env.run(&["plan", "apply", path.to_str().unwrap(), "--json"])
+        .expect("Apply failed");
+    let elapsed = started.elapsed();
+    assert!(elapsed.as_secs() < 30, "took {elapsed:?}");
+}
+
+#[test]
+fn test_json_fields_selects_paths() {
+    let env = TestEnv::new();
+    env.run(&["init"]).expect("Init failed");
+    let goal_id = env.create_goal("Ship it");
+
+    let goals: Value = serde_json::from_str(
+        &env.run(&[
+            "goal",
+            "list",
+            "--json",
+            "--fields",
+            "id,metrics.task_count",
+        ])
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        goals,
+        serde_json::json!([{"id": goal_id, "metrics": {"task_count": 0}}])
+    );
+}
+
+#[test]
+fn test_template_prints_one_line_per_item() {
+    let env = TestEnv::new();
+    env.run(&["init"]).expect("Init failed");
+    let goal_id = env.create_goal("Ship it");
+    let first = env.create_task(&goal_id, "Build", &[]);
+    let second = env.create_task(&goal_id, "Deploy", &["--blocked-by", &first]);
+
+    let output = env
+        .run(&["task", "list", &goal_id, "--template", "{{id}}\\t{{state}}"])
+        .unwrap();
+    let mut lines: Vec<&str> = output.lines().collect();
+    lines.sort_unstable();
+    let mut expected = [format!("{first}\tpending"), format!("{second}\tblocked")];
+    expected.sort_unstable();
+    assert_eq!(lines, expected);
+}
+
+#[test]
+fn test_goal_verify_checks_acceptance_criteria() {
+    let env = TestEnv::new();
+    env.run(&["init"]).expect("Init failed");
+    let goal_id = env.create_goal("Ship it");
+    env.run(&["goal", "criterion", "add", &goal_id, "Docs reviewed"])
+        .expect("Add criterion failed");
+    env.run(&[
+        "goal",
+        "criterion",
+        "add",
+        &goal_id,
+        "Builds",
+        "--command",
+        "echo ok",
+    ])
+    .expect("Add criterion failed");
+
+    let err = env.run(&["goal", "verify", &goal_id]).unwrap_err();
+    assert!(err.contains("not done"));
+
+    env.run(&["goal", "criterion", "tick", &goal_id, "1"])
+        .expect("Tick failed");
+    let verified: Value =
+        serde_json::from_str(&env.run(&["goal", "verify", &goal_id, "--json"]).unwrap()).unwrap();
+    assert_eq!(verified["passed"], true);
+    assert_eq!(verified["goal_state"], "completed");
+    assert_eq!(verified["criteria"][1]["run"]["passed"], true);
+}
+
+#[test]
+fn test_task_exec_completes_or_fails_by_exit_status() {
+    let env = TestEnv::new();
+    env.run(&["init"]).expect("Init failed");
+    let goal_id = env.create_goal("Ship it");
+    let build = env.create_task(&goal_id, "Build", &[]);
+    let deploy = env.create_task(&goal_id, "Deploy", &["--blocked-by", &build]);
+
+    let ran: Value = serde_json::from_str(
+        &env.run(&[
+            "task",
+            "exec",
+            &build,
+            "--on-success",
+            "complete",
+            "--json",
+            "--",
+            "echo",
+            "built it",
+        ])
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(ran["started"], true);
+    assert_eq!(ran["completed"]["task"]["state"], "completed");
+    assert_eq!(ran["completed"]["unblocked_task_ids"][0], deploy.as_str());
+    let log = ran["completed"]["task"]["result"]["artifacts"][0]
+        .as_str()
+        .unwrap();
+    assert_eq!(std::fs::read_to_string(log).unwrap(), "built it\n");
+
+    let err = env
+        .run(&[
+            "task",
+            "exec",
+            &deploy,
+            "--on-failure",
+            "fail",
+            "--",
+            "false",
+        ])
+        .unwrap_err();
+    assert!(err.contains("Command failed"));
+    let task: Value =
+        serde_json::from_str(&env.run(&["show", &deploy, "--json"]).unwrap()).unwrap();
+    assert_eq!(task["state"], "failed");
+}
+
+#[test]
+fn test_milestone_status_tracks_attached_goals() {
+    let env = TestEnv::new();
+    env.run(&["init"]).unwrap();
+    env.run(&["milestone", "add", "v1", "--due", "336h"])
+        .unwrap();
+    assert!(
+        env.run(&["goal", "create", "Docs", "--milestone", "v2"])
+            .unwrap_err()
+            .contains("not found")
+    );
+
+    let goal = env.create_goal("Ship v1");
+    let task = env.create_task(&goal, "Write code", &[]);
+    let attached: Value = serde_json::from_str(
+        &env.run(&["milestone", "attach", "v1", &goal, "--json"])
+            .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(attached[0]["goals"][0]["id"], goal.as_str());
+    assert_eq!(attached[0]["at_risk"], false);
+
+    env.run(&["task", "start", &task]).unwrap();
+    env.run(&["task", "complete", &task, "--result", "Done"])
+        .unwrap();
+    let status = env.run(&["milestone", "status", "v1"]).unwrap();
+    assert!(status.contains("1/1 tasks done"));
+    assert!(status.contains("on track"));
+
+    env.run(&["milestone", "detach", &goal]).unwrap();
+    let status: Value =
+        serde_json::from_str(&env.run(&["milestone", "status", "--json"]).unwrap()).unwrap();
+    assert_eq!(status[0]["goals"].as_array().unwrap().len(), 0);
+}
+
+#[cfg(feature = "web")]
+#[test]
+fn test_serve_web_dashboard() {
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpStream;
+    use std::process::Stdio;
+
+    let env = TestEnv::new();
+    env.run(&["init"]).unwrap();
+    let goal = env.create_goal("Ship the dashboard");
+
+    let mut server = Command::new(&env.binary_path)
+        .args(["serve", "--web", "--addr", "127.0.0.1:0"])
+        .current_dir(&env.work_dir)
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let mut banner = String::new();
+    BufReader::new(server.stdout.take().unwrap())
+        .read_line(&mut banner)
+        .unwrap();
+    let addr = banner
+        .split("http://")
+        .nth(1)
+        .unwrap()
+        .trim()
+        .trim_end_matches('/')
+        .to_string();
+
+    let get = |target: &str| {
+        let mut stream = TcpStream::connect(&addr).unwrap();
+        write!(stream, "GET {target} HTTP/1.1\r\nHost: {addr}\r\n\r\n").unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        response
+    };
+    let page = get("/");
+    let data = get("/api/dashboard");
+    server.kill().unwrap();
+    server.wait().unwrap();
+
+    assert!(page.starts_with("HTTP/1.1 200 OK"));
+    assert!(page.contains("<title>radial</title>"));
+    let body: Value = serde_json::from_str(data.split("\r\n\r\n").nth(1).unwrap()).unwrap();
+    assert_eq!(body["goals"][0]["id"], goal.as_str());
+}
+
+#[test]
+fn test_task_list_where_filters_across_goals() {
+    let env = TestEnv::new();
+    env.run(&["init"]).unwrap();
+    let api = env.create_goal("API");
+    let ui = env.create_goal("UI");
+    let login = env.create_task(&api, "Login endpoint", &["--priority", "high"]);
+    env.create_task(&api, "Logout endpoint", &[]);
+    let form = env.create_task(&ui, "Login form", &["--priority", "urgent"]);
+    env.run(&["task", "start", &form]).unwrap();
+
+    let ids = |args: &[&str]| -> Vec<String> {
+        let out: Value = serde_json::from_str(&env.run(args).unwrap()).unwrap();
+        out.as_array()
+            .unwrap()
+            .iter()
+            .map(|t| t["id"].as_str().unwrap().to_string())
+            .collect()
+    };
+    let mut high = ids(&["task", "list", "--where", "priority>=high", "--json"]);
+    high.sort();
+    let mut expected = vec![login.clone(), form.clone()];
+    expected.sort();
+    assert_eq!(high, expected);
+    assert_eq!(
+        ids(&[
+            "task",
+            "list",
+            &api,
+            "--where",
+            "description~login OR state=in_progress",
+            "--json"
+        ]),
+        [login]
+    );
+
+    let err = env
+        .run(&["task", "list", "--where", "tag=backend"])
+        .unwrap_err();
+    assert!(err.contains("Unknown field 'tag'"));
+    assert!(env.run(&["task", "list"]).is_err());
+}
+
+#[test]
+fn test_doctor_repairs_dangling_references() {
+    let env = TestEnv::new();
+    env.run(&["init"]).unwrap();
+    let goal = env.create_goal("Ship");
+    let task = env.create_task(&goal, "Build", &[]);
+    assert!(env.run(&["doctor"]).unwrap().contains("No problems found"));
+
+    // Simulate a blocker deleted by hand, outside radial.
+    let path = env
+        .work_dir
+        .join(".radial")
+        .join(&goal)
+        .join(format!("{task}.toml"));
+    let text = std::fs::read_to_string(&path).unwrap();
+    std::fs::write(&path, format!("blocked_by = [\"gone1234\"]\n{text}")).unwrap();
+
+    let report: Value = serde_json::from_str(&env.run(&["doctor", "--json"]).unwrap()).unwrap();
+    assert_eq!(report["issues"][0]["kind"], "missing_blocker");
+    assert_eq!(report["issues"][0]["fixed"], false);
+
+    let fixed = env.run(&["doctor", "--fix"]).unwrap();
+    assert!(fixed.contains("1 fixed"));
+    assert!(env.run(&["doctor"]).unwrap().contains("No problems found"));
+}
+
+#[test]
+fn test_comment_replies_form_threads() {
+    let env = TestEnv::new();
+    env.run(&["init"]).unwrap();
+    let goal = env.create_goal("Ship");
+    let task = env.create_task(&goal, "Build", &[]);
+
+    let comment_as = |agent: &str, args: &[&str]| {
+        let output = Command::new(&env.binary_path)
+            .args(["task", "comment", &task])
+            .args(args)
+            .current_dir(&env.work_dir)
+            .env("RADIAL_AGENT", agent)
+            .output()
+            .expect("Failed to run rd");
+        assert!(output.status.success(), "{output:?}");
+        let stdout = String::from_utf8_lossy(&output.stdout).to_string();
+        stdout
+            .lines()
+            .find_map(|line| line.trim_start().strip_prefix("Comment "))
+            .map(|id| id.trim().to_string())
+            .expect("Comment ID not printed")
+    };
+    let note = comment_as("builder", &["Used a hash map here"]);
+    env.run(&["task", "comment", &task, "Unrelated note"])
+        .unwrap();
+    comment_as("reviewer", &["Why not a BTreeMap?", "--reply-to", &note]);
+
+    let err = env
+        .run(&["task", "comment", &task, "Lost", "--reply-to", "nope"])
+        .unwrap_err();
+    assert!(err.contains("nope"), "{err}");
+
+    let show = env.run(&["show", &task, "--render", "plain"]).unwrap();
+    let order: Vec<&str> = ["Used a hash map", "Why not a BTreeMap", "Unrelated note"]
+        .iter()
+        .map(|text| show.lines().find(|l| l.contains(text)).unwrap())
+        .collect();
+    assert!(order[1].starts_with("      Why not"), "{show}");
+    assert!(show.find(order[1]) < show.find(order[2]));
+    assert!(show.contains("by reviewer"));
+
+    let json: Value = serde_json::from_str(&env.run(&["show", &task, "--json"]).unwrap()).unwrap();
+    let reply = &json["comments"][2];
+    assert_eq!(reply["reply_to"], note.as_str());
+    assert_eq!(reply["author"], "reviewer");
+}
+
+#[test]
+fn test_show_lists_mentions_as_backlinks() {
+    let env = TestEnv::new();
+    env.run(&["init"]).unwrap();
+    let goal = env.create_goal("Ship");
+    let schema = env.create_task(&goal, "Write schema", &[]);
+    let client = env.create_task(&goal, &format!("Client for the #{schema} tables"), &[]);
+    env.run(&[
+        "task",
+        "comment",
+        &client,
+        &format!("Depends on #{schema}, see #{goal}"),
+    ])
+    .unwrap();
+
+    let show = env.run(&["show", &schema]).unwrap();
+    let section = show.split("Referenced by").nth(1).expect("no backlinks");
+    assert_eq!(section.matches(client.as_str()).count(), 2, "{show}");
+    assert!(section.contains("comment "));
+
+    let json: Value = serde_json::from_str(&env.run(&["show", &goal, "--json"]).unwrap()).unwrap();
+    assert_eq!(json["referenced_by"][0]["id"], client.as_str());
+    assert_eq!(json["referenced_by"][0]["kind"], "task");
+}
+
+#[test]
+fn test_show_links_artifacts_at_completion_commit() {
+    let env = TestEnv::new();
+    env.run(&["init"]).unwrap();
+    let sha = "0123456789abcdef0123456789abcdef01234567";
+    let git = env.work_dir.join(".git");
+    std::fs::create_dir_all(git.join("refs/heads")).unwrap();
+    std::fs::write(git.join("HEAD"), "ref: refs/heads/main\n").unwrap();
+    std::fs::write(git.join("refs/heads/main"), format!("{sha}\n")).unwrap();
+    std::fs::write(
+        git.join("config"),
+        "[remote \"origin\"]\n\turl = git@github.com:org/app.git\n",
+    )
+    .unwrap();
+
+    let goal = env.create_goal("Ship");
+    let task = env.create_task(&goal, "Build", &[]);
+    env.run(&["task", "start", &task]).unwrap();
+    env.run(&[
+        "task",
+        "complete",
+        &task,
+        "--result",
+        "Built",
+        "--artifacts",
+        "./src/app.rs,PR 12",
+    ])
+    .unwrap();
+
+    let url = format!("https://github.com/org/app/blob/{sha}/src/app.rs");
+    assert!(!env.run(&["show", &task]).unwrap().contains(&url));
+    assert!(env.run(&["show", &task, "--links"]).unwrap().contains(&url));
+
+    let json: Value =
+        serde_json::from_str(&env.run(&["show", &task, "--links", "--json"]).unwrap()).unwrap();
+    assert_eq!(json["result"]["commit"], sha);
+    assert_eq!(json["artifact_links"][0]["artifact"], "./src/app.rs");
+    assert_eq!(json["artifact_links"][0]["url"], url.as_str());
+    assert_eq!(json["artifact_links"].as_array().unwrap().len(), 1);
+}
+
+#[test]
+fn test_claim_next_within_max_minutes() {
+    let env = TestEnv::new();
+    env.run(&["init"]).unwrap();
+    let goal = env.create_goal("Ship");
+    let migrate = env.create_task(&goal, "Migrate", &["--estimate", "3h"]);
+    let unknown = env.create_task(&goal, "Unknown", &[]);
+    let docs = env.create_task(&goal, "Docs", &["--estimate", "20m"]);
+
+    let err = env
+        .run(&["task", "create", &goal, "Bad", "--estimate", "soon"])
+        .unwrap_err();
+    assert!(err.contains("not a duration"), "{err}");
+    assert!(env.run(&["show", &migrate]).unwrap().contains("3h"));
+
+    let peek = env
+        .run(&["task", "peek", &goal, "--max-minutes", "30", "--json"])
+        .unwrap();
+    let peek: Value = serde_json::from_str(&peek).unwrap();
+    assert_eq!(peek["task"]["id"], docs.as_str());
+
+    let claimed = env.run(&["grab", &goal, "--max-minutes", "30"]).unwrap();
+    assert!(claimed.contains(&docs));
+    let claimed = env.run(&["grab", &goal, "--max-minutes", "30"]).unwrap();
+    assert!(claimed.contains("No ready tasks"), "{claimed}");
+
+    env.run(&["edit", "task", &unknown, "--estimate", "15m"])
+        .unwrap();
+    let claimed = env.run(&["grab", &goal, "--max-minutes", "30"]).unwrap();
+    assert!(claimed.contains(&unknown));
+    env.run(&["edit", "task", &migrate, "--estimate", ""])
+        .unwrap();
+    let json: Value =
+        serde_json::from_str(&env.run(&["show", &migrate, "--json"]).unwrap()).unwrap();
+    assert!(json.get("estimate_ms").is_none());
+}
+
+#[test]
+fn test_goal_merge() {
+    let env = TestEnv::new();
+    env.run(&["init"]).unwrap();
+    let auth = env.create_goal("Auth");
+    let login = env.create_goal("Login");
+    let schema = env.create_task(&auth, "Schema", &[]);
+    let form = env.create_task(&login, "Form", &[]);
+
+    let output = env.run(&["goal", "merge", &auth, &login]).unwrap();
+    assert!(output.contains("1 task(s) moved"), "{output}");
+
+    let show: Value = serde_json::from_str(&env.run(&["show", &auth, "--json"]).unwrap()).unwrap();
+    let ids: Vec<&str> = show["tasks"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|t| t["id"].as_str().unwrap())
+        .collect();
+    assert_eq!(ids, [schema.as_str(), form.as_str()]);
+    assert!(env.run(&["show", &login]).is_err());
+    assert!(
+        env.work_dir
+            .join(format!(".radial/archive/{login}.json.gz"))
+            .exists()
+    );
+    assert!(env.run(&["doctor"]).unwrap().contains("No problems found"));
+}
+
+#[test]
+fn test_elapsed_takes_durations() {
+    let env = TestEnv::new();
+    env.run(&["init"]).unwrap();
+    let goal = env.create_goal("Ship");
+    let task = env.create_task(&goal, "Build", &[]);
+    env.run(&["task", "start", &task]).unwrap();
+    env.run(&[
+        "task",
+        "complete",
+        &task,
+        "--result",
+        "done",
+        "--elapsed",
+        "5m30s",
+    ])
+    .unwrap();
+
+    let json: Value = serde_json::from_str(&env.run(&["show", &task, "--json"]).unwrap()).unwrap();
+    assert_eq!(json["metrics"]["elapsed_ms"], 330_000);
+    assert!(env.run(&["show", &task]).unwrap().contains("5m 30s"));
+
+    let err = env
+        .run(&["task", "fail", &task, "--elapsed", "soon"])
+        .unwrap_err();
+    assert!(err.contains("not a duration"), "{err}");
+}
+
+#[test]
+fn test_goal_reopen_resets_failed_tasks() {
+    let env = TestEnv::new();
+    env.run(&["init"]).unwrap();
+    let goal = env.create_goal("Ship");
+    let first = env.create_task(&goal, "Build", &[]);
+    let second = env.create_task(&goal, "Test", &[]);
+    for task in [&first, &second] {
+        env.run(&["task", "start", task]).unwrap();
+        env.run(&["task", "fail", task, "--reason", "flaky"])
+            .unwrap();
+    }
+
+    let output = env
+        .run(&["goal", "reopen", &goal, "--reset-failed", "--json"])
+        .unwrap();
+    let report: Value = serde_json::from_str(&output).unwrap();
+    assert_eq!(report["goal"]["state"], "inprogress");
+    assert_eq!(report["reset_task_ids"].as_array().unwrap().len(), 2);
+    assert_eq!(report["ready_task_ids"].as_array().unwrap().len(), 2);
+
+    let show: Value = serde_json::from_str(&env.run(&["show", &first, "--json"]).unwrap()).unwrap();
+    assert_eq!(show["state"], "pending");
+    assert_eq!(show["attempts"].as_array().unwrap().len(), 1);
+}
+
+#[test]
+fn test_task_refs() {
+    let env = TestEnv::new();
+    env.run(&["init"]).unwrap();
+    let goal = env.create_goal("Ship");
+    let task = env.create_task(
+        &goal,
+        "Fix login redirect",
+        &["--ref", "github:acme/web#412", "--ref", "jira:WEB-7"],
+    );
+
+    let json: Value = serde_json::from_str(&env.run(&["show", &task, "--json"]).unwrap()).unwrap();
+    assert_eq!(
+        json["refs"],
+        serde_json::json!(["github:acme/web#412", "jira:WEB-7"])
+    );
+    let shown = env.run(&["show", &task]).unwrap();
+    assert!(
+        shown.contains("https://github.com/acme/web/issues/412"),
+        "{shown}"
+    );
+
+    let found = env
+        .run(&["task", "list", "--where", "ref=jira:WEB-7", "--json"])
+        .unwrap();
+    assert!(found.contains(&task), "{found}");
+
+    env.run(&["edit", "task", &task, "--ref", ""]).unwrap();
+    let json: Value = serde_json::from_str(&env.run(&["show", &task, "--json"]).unwrap()).unwrap();
+    assert_eq!(json["refs"], Value::Null);
+
+    let err = env
+        .run(&["task", "create", &goal, "Other", "--ref", "github:web#1"])
+        .unwrap_err();
+    assert!(err.contains("github:owner/repo#123"), "{err}");
+}
+
+#[test]
+fn test_open_targets() {
+    let env = TestEnv::new();
+    env.run(&["init"]).unwrap();
+    let goal = env.create_goal("Ship");
+    let task = env.create_task(&goal, "Fix login", &["--ref", "github:acme/web#412"]);
+    std::fs::write(env.work_dir.join("notes.md"), "# Notes").unwrap();
+    env.run(&["task", "start", &task]).unwrap();
+    env.run(&[
+        "task",
+        "complete",
+        &task,
+        "--result",
+        "done",
+        "--artifacts",
+        "notes.md",
+    ])
+    .unwrap();
+
+    let listed: Value =
+        serde_json::from_str(&env.run(&["open", &task, "--list", "--json"]).unwrap()).unwrap();
+    assert_eq!(
+        listed[0]["location"],
+        "https://github.com/acme/web/issues/412"
+    );
+    assert_eq!(listed[1]["opener"], "editor");
+
+    let err = env.run(&["open", &task]).unwrap_err();
+    assert!(err.contains("There are 2 targets"), "{err}");
+    let opened = env
+        .run_with_editor(&["open", &task, "notes"], "true")
+        .unwrap();
+    assert!(opened.contains("Opened notes.md"), "{opened}");
+
+    let err = env.run(&["open", &goal]).unwrap_err();
+    assert!(err.contains("Nothing to open"), "{err}");
+}
+
+#[test]
+fn test_digest() {
+    let env = TestEnv::new();
+    env.run(&["init"]).unwrap();
+    let goal = env.create_goal("Ship");
+    let task = env.create_task(&goal, "Build", &[]);
+    env.run(&["task", "start", &task]).unwrap();
+    env.run(&["task", "complete", &task, "--result", "Built it"])
+        .unwrap();
+
+    let printed = env.run(&["digest", "--format", "html"]).unwrap();
+    assert!(printed.contains("<h1>Radial digest</h1>"), "{printed}");
+    assert!(printed.contains("Build: Built it"), "{printed}");
+
+    std::fs::write(
+        env.work_dir.join(".radial/config.toml"),
+        "[digest]\npath = \"reports/{date}.md\"\n",
+    )
+    .unwrap();
+    let output = env.run(&["digest", "--since", "1h"]).unwrap();
+    assert!(output.contains("Wrote digest to"), "{output}");
+    let reports: Vec<_> = std::fs::read_dir(env.work_dir.join("reports"))
+        .unwrap()
+        .collect();
+    assert_eq!(reports.len(), 1);
+
+    let json: Value = serde_json::from_str(&env.run(&["digest", "--json"]).unwrap()).unwrap();
+    assert_eq!(json["tasks_completed"], 1);
+}
+
+#[test]
+fn test_transition_reason_codes() {
+    let env = TestEnv::new();
+    env.run(&["init"]).unwrap();
+    let radial = env.work_dir.join(".radial");
+    std::fs::write(radial.join("config.toml"), "[history]\nenabled = true\n").unwrap();
+    let goal = env.create_goal("Ship");
+    let task = env.create_task(&goal, "Build", &[]);
+
+    let output = Command::new(&env.binary_path)
+        .args(["task", "start", &task])
+        .current_dir(&env.work_dir)
+        .env("RADIAL_AGENT", "builder")
+        .output()
+        .expect("Failed to run rd");
+    assert!(output.status.success(), "{output:?}");
+
+    let failed: Value = serde_json::from_str(
+        &env.run(&[
+            "task",
+            "fail",
+            &task,
+            "--reason-code",
+            "lease_expired",
+            "--json",
+        ])
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(failed["task"]["state_reason"], "lease_expired");
+    let retried: Value =
+        serde_json::from_str(&env.run(&["task", "retry", &task, "--json"]).unwrap()).unwrap();
+    assert_eq!(retried["task"]["state_reason"], "user_request");
+
+    let log = std::fs::read_dir(radial.join("history"))
+        .unwrap()
+        .map(|e| e.unwrap().path())
+        .find(|p| p.extension().is_some_and(|ext| ext == "jsonl"))
+        .expect("No history log written");
+    let reasons: Vec<String> = std::fs::read_to_string(log)
+        .unwrap()
+        .lines()
+        .map(|l| serde_json::from_str::<Value>(l).unwrap())
+        .filter_map(|e| e.get("transition").cloned())
+        .map(|t| format!("{} -> {}: {}", t["from"], t["to"], t["reason"]))
+        .collect();
+    assert_eq!(
+        reasons,
+        [
+            r#""pending" -> "inprogress": "agent_auto""#,
+            r#""inprogress" -> "failed": "lease_expired""#,
+            r#""failed" -> "inprogress": "user_request""#,
+        ]
+    );
+
+    let err = env
+        .run(&["task", "fail", &task, "--reason-code", "bored"])
+        .unwrap_err();
+    assert!(err.contains("bored"), "{err}");
+}
+
+#[test]
+fn test_complete_warns_about_cascade() {
+    let env = TestEnv::new();
+    env.run(&["init"]).unwrap();
+    let goal = env.create_goal("Ship");
+    let schema = env.create_task(&goal, "Design schema", &[]);
+    let migrate = env.create_task(&goal, "Write migration", &[]);
+    let docs = env.create_task(&goal, "Write docs", &[]);
+    let deploy = env.create_task(
+        &goal,
+        "Deploy",
+        &["--blocked-by", &format!("{migrate},{docs}")],
+    );
+
+    env.run(&["task", "start", &migrate]).unwrap();
+    env.run(&["task", "complete", &migrate, "--result", "Done"])
+        .unwrap();
+    env.run(&["edit", "task", &migrate, "--blocked-by", &schema])
+        .unwrap();
+    env.run(&["task", "start", &docs]).unwrap();
+
+    let output = Command::new(&env.binary_path)
+        .args(["task", "complete", &docs, "--result", "Written", "--json"])
+        .current_dir(&env.work_dir)
+        .output()
+        .expect("Failed to run rd");
+    assert!(output.status.success(), "{output:?}");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains(&format!(
+            "{deploy} would be released, though {migrate} was completed before {schema}"
+        )),
+        "{stderr}"
+    );
+    let completed: Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(completed["unblocked_task_ids"][0], deploy.as_str());
+
+    env.run(&["task", "start", &schema]).unwrap();
+    let output = Command::new(&env.binary_path)
+        .args(["task", "complete", &schema, "--result", "Done"])
+        .current_dir(&env.work_dir)
+        .output()
+        .expect("Failed to run rd");
+    assert!(output.status.success(), "{output:?}");
+    assert!(!String::from_utf8_lossy(&output.stderr).contains("warning"));
+}
+
+#[test]
+fn test_export_and_import_goal_bundle() {
+    let env = TestEnv::new();
+    env.run(&["init"]).unwrap();
+    let goal = env.create_goal("Ship auth");
+    let child = extract_id(
+        &env.run(&["goal", "create", "Write docs", "--parent", &goal])
+            .unwrap(),
+        "Created goal:",
+    );
+    let schema = env.create_task(&goal, "Design schema", &[]);
+    env.create_task(&goal, "Migrate", &["--blocked-by", &schema]);
+    env.create_task(&child, "Guide", &[]);
+    env.create_goal("Unrelated");
+
+    let bundle = env.work_dir.join("auth.json");
+    let written: Value = serde_json::from_str(
+        &env.run(&[
+            "export",
+            "--goal",
+            &goal,
+            "--output",
+            bundle.to_str().unwrap(),
+            "--json",
+        ])
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(written["goal_count"], 2);
+    assert_eq!(written["task_count"], 3);
+
+    let other = TestEnv::new();
+    other.run(&["init"]).unwrap();
+    let imported: Value = serde_json::from_str(
+        &other
+            .run(&["import", bundle.to_str().unwrap(), "--json"])
+            .unwrap(),
+    )
+    .unwrap();
+    let ids: std::collections::HashMap<&str, &str> = imported["ids"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|pair| (pair[0].as_str().unwrap(), pair[1].as_str().unwrap()))
+        .collect();
+    assert_eq!(ids.len(), 5);
+    let new_goal = imported["goal"]["id"].as_str().unwrap();
+    assert_eq!(ids[goal.as_str()], new_goal);
+
+    let listed: Value =
+        serde_json::from_str(&other.run(&["task", "list", new_goal, "--json"]).unwrap()).unwrap();
+    let tasks = listed.as_array().unwrap();
+    assert_eq!(tasks.len(), 2);
+    assert_eq!(tasks[1]["blocked_by"][0], ids[schema.as_str()]);
+    assert_eq!(tasks[1]["state"], "blocked");
+
+    let printed: Value =
+        serde_json::from_str(&env.run(&["export", "--goal", &child]).unwrap()).unwrap();
+    assert_eq!(printed["goals"].as_array().unwrap().len(), 1);
+    assert!(other.run(&["import", "missing.json"]).is_err());
+}
+
+#[test]
+fn test_stats_prices_tokens_by_model() {
+    let env = TestEnv::new();
+    env.run(&["init"]).unwrap();
+    std::fs::write(
+        env.work_dir.join(".radial/config.toml"),
+        "[pricing]\ncurrency = \"EUR\"\n\n[pricing.models.small]\nprompt = 1.0\ncompletion = 4.0\n",
+    )
+    .unwrap();
+    let goal = env.create_goal("Ship");
+    let task = env.create_task(&goal, "Build", &[]);
+    env.run(&["task", "start", &task]).unwrap();
+    env.run(&[
+        "task",
+        "complete",
+        &task,
+        "--result",
+        "Built",
+        "--prompt-tokens",
+        "2000000",
+        "--completion-tokens",
+        "500000",
+        "--model",
+        "small",
+    ])
+    .unwrap();
+
+    let stats: Value = serde_json::from_str(
+        &env.run(&["stats", "tokens", "--by", "model", "--json"])
+            .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(stats["currency"], "EUR");
+    assert_eq!(stats["total_cost"], 4.0);
+    assert_eq!(stats["groups"][0]["key"], "small");
+    assert_eq!(stats["groups"][0]["cost"], 4.0);
+
+    let table = env.run(&["stats", "tokens"]).unwrap();
+    assert!(table.contains("EUR"), "{table}");
+    assert!(table.contains("4.00"), "{table}");
+}
+
+#[test]
+fn test_stats_compares_models() {
+    let env = TestEnv::new();
+    env.run(&["init"]).unwrap();
+    let goal = env.create_goal("Ship");
+    let first = env.create_task(&goal, "Build", &[]);
+    let second = env.create_task(&goal, "Test", &[]);
+
+    env.run(&["task", "start", &first]).unwrap();
+    env.run(&[
+        "task",
+        "complete",
+        &first,
+        "--result",
+        "Built",
+        "--tokens",
+        "4000",
+        "--elapsed",
+        "2m",
+        "--model",
+        "gpt-4o",
+        "--provider",
+        "openai",
+    ])
+    .unwrap();
+
+    // The model and provider fall back to the environment.
+    env.run(&["task", "start", &second]).unwrap();
+    let output = Command::new(&env.binary_path)
+        .args(["task", "fail", &second, "--tokens", "1000"])
+        .current_dir(&env.work_dir)
+        .env("RADIAL_MODEL", "gpt-4o")
+        .env("RADIAL_PROVIDER", "openai")
+        .output()
+        .expect("Failed to run rd");
+    assert!(output.status.success(), "{output:?}");
+
+    let shown: Value =
+        serde_json::from_str(&env.run(&["show", &second, "--json"]).unwrap()).unwrap();
+    assert_eq!(shown["attempts"][0]["provider"], "openai");
+
+    let models: Value =
+        serde_json::from_str(&env.run(&["stats", "models", "--json"]).unwrap()).unwrap();
+    assert_eq!(models.as_array().unwrap().len(), 1);
+    assert_eq!(models[0]["model"], "gpt-4o");
+    assert_eq!(models[0]["attempts"], 2);
+    assert_eq!(models[0]["success_rate"], 0.5);
+    assert_eq!(models[0]["avg_tokens"], 2500);
+    assert_eq!(models[0]["avg_elapsed_ms"], 120_000);
+}
+
+#[test]
+fn test_unknown_state_survives_rewrites() {
+    let env = TestEnv::new();
+    env.run(&["init"]).unwrap();
+    let goal = env.create_goal("Ship");
+    let first = env.create_task(&goal, "First", &[]);
+    let second = env.create_task(&goal, "Second", &[]);
+
+    // A state written by a newer version or another tool
+    let path = env
+        .work_dir
+        .join(".radial")
+        .join(&goal)
+        .join(format!("{first}.toml"));
+    let content = std::fs::read_to_string(&path).unwrap();
+    let content = content
+        .lines()
+        .map(|line| {
+            if line.starts_with("state = ") {
+                "state = \"Archived\""
+            } else {
+                line
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    std::fs::write(&path, content).unwrap();
+
+    // Reordering renumbers and rewrites every task in the goal
+    env.run(&["task", "reorder", &goal, &second, &first])
+        .unwrap();
+    let rewritten = std::fs::read_to_string(&path).unwrap();
+    assert!(rewritten.contains("state = \"Archived\""), "{rewritten}");
+    assert!(rewritten.contains("order = 2"), "{rewritten}");
+}
+
+#[test]
+fn test_goal_archive_writes_gzip() {
+    let env = TestEnv::new();
+    env.run(&["init"]).unwrap();
+    let goal = env.create_goal("Ship");
+    let task = env.create_task(&goal, "Build", &[]);
+
+    env.run(&["goal", "archive", &goal]).unwrap();
+    let path = env.work_dir.join(format!(".radial/archive/{goal}.json.gz"));
+    let bytes = std::fs::read(&path).unwrap();
+    assert_eq!(bytes[..2], [0x1f, 0x8b]);
+    assert!(env.run(&["show", &task]).is_err());
+
+    env.run(&["goal", "restore", path.to_str().unwrap()])
+        .unwrap();
+    assert!(env.run(&["show", &task]).unwrap().contains("Build"));
+}